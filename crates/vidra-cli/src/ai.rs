@@ -533,6 +533,8 @@ fn apply_caption_segments(
                 font_family: font_family.to_string(),
                 font_size,
                 color,
+                style: Default::default(),
+                animators: Vec::new(),
             },
         );
 
@@ -53,18 +53,27 @@ pub fn run_benchmark(file: PathBuf, update_baseline: bool) -> Result<()> {
 
         let _target_frames = proj.total_frames();
 
-        // Render
+        // Render on a fresh pipeline so cache stats cover this profile only
         let render_start = Instant::now();
-        let render_result = vidra_render::RenderPipeline::render(&proj);
+        let render_result = vidra_render::RenderPipeline::new().and_then(|mut pipeline| {
+            pipeline.expand_media(&mut proj)?;
+            pipeline.load_assets(&proj)?;
+            let res = pipeline.render_project(&proj)?;
+            Ok((res, pipeline.cache_stats()))
+        });
         let render_time = render_start.elapsed();
 
-        if let Ok(res) = render_result {
+        if let Ok((res, cache)) = render_result {
             let fps = res.frame_count as f64 / render_time.as_secs_f64();
             println!(
                 "{:.1}ms ({:.0} fps)",
                 render_time.as_secs_f64() * 1000.0,
                 fps
             );
+            println!(
+                "      layer cache: {} hits / {} misses, frame cache: {} hits / {} misses",
+                cache.layers.hits, cache.layers.misses, cache.frames.hits, cache.frames.misses
+            );
 
             results.push(BenchResult {
                 profile: name.to_string(),
@@ -73,6 +82,10 @@ pub fn run_benchmark(file: PathBuf, update_baseline: bool) -> Result<()> {
                 frames: res.frame_count,
                 duration_ms: render_time.as_secs_f64() * 1000.0,
                 fps,
+                layer_cache_hits: cache.layers.hits,
+                layer_cache_misses: cache.layers.misses,
+                frame_cache_hits: cache.frames.hits,
+                frame_cache_misses: cache.frames.misses,
             });
         } else {
             println!("ERROR");
@@ -81,12 +94,12 @@ pub fn run_benchmark(file: PathBuf, update_baseline: bool) -> Result<()> {
 
     println!("\n📊 Benchmark Report:");
     println!(
-        "{:<10} | {:<10} | {:<10} | {:<10} | {}",
-        "Profile", "Resolution", "Render (ms)", "FPS", "Regression"
+        "{:<10} | {:<10} | {:<10} | {:<10} | {:<14} | {:<10}",
+        "Profile", "Resolution", "Render (ms)", "FPS", "Cache hit L/F", "Regression"
     );
     println!(
-        "{:-<10}-+-{:-<10}-+-{:-<10}-+-{:-<10}-+-{:-<10}",
-        "", "", "", "", ""
+        "{:-<10}-+-{:-<10}-+-{:-<10}-+-{:-<10}-+-{:-<14}-+-{:-<10}",
+        "", "", "", "", "", ""
     );
 
     let baseline_path = std::path::Path::new("tests/snapshots/benchmarks.json");
@@ -123,9 +136,15 @@ pub fn run_benchmark(file: PathBuf, update_baseline: bool) -> Result<()> {
             }
         }
 
+        let cache_str = format!(
+            "{:.0}% / {:.0}%",
+            hit_percent(res.layer_cache_hits, res.layer_cache_misses),
+            hit_percent(res.frame_cache_hits, res.frame_cache_misses)
+        );
+
         println!(
-            "{:<10} | {:>4}x{:<4}  | {:>10.1} | {:>10.0} | {}",
-            res.profile, res.width, res.height, res.duration_ms, res.fps, reg_str
+            "{:<10} | {:>4}x{:<4}  | {:>10.1} | {:>10.0} | {:>14} | {}",
+            res.profile, res.width, res.height, res.duration_ms, res.fps, cache_str, reg_str
         );
     }
 
@@ -155,4 +174,21 @@ struct BenchResult {
     frames: u64,
     duration_ms: f64,
     fps: f64,
+    #[serde(default)]
+    layer_cache_hits: u64,
+    #[serde(default)]
+    layer_cache_misses: u64,
+    #[serde(default)]
+    frame_cache_hits: u64,
+    #[serde(default)]
+    frame_cache_misses: u64,
+}
+
+fn hit_percent(hits: u64, misses: u64) -> f64 {
+    let stats = vidra_render::CacheStats {
+        hits,
+        misses,
+        ..Default::default()
+    };
+    stats.hit_rate() * 100.0
 }
@@ -44,7 +44,7 @@ pub async fn run_dev_server(file: PathBuf) -> Result<()> {
         error: None,
     };
 
-    match compile_and_load(&file) {
+    match compile_and_load(&file, &initial_state.pipeline) {
         Ok(proj) => {
             if let Err(e) = initial_state.pipeline.load_assets(&proj) {
                 println!("   ✗ Asset load failed: {}", e);
@@ -77,7 +77,7 @@ pub async fn run_dev_server(file: PathBuf) -> Result<()> {
                 if matches!(event.kind, EventKind::Modify(_)) {
                     println!("🔄 File changed, recompiling...");
                     let mut lock = watch_state.dev_state.write();
-                    match compile_and_load(&watch_file) {
+                    match compile_and_load(&watch_file, &lock.pipeline) {
                         Ok(proj) => {
                             if let Err(e) = lock.pipeline.load_assets(&proj) {
                                 println!("   ✗ Asset load failed: {}", e);
@@ -120,7 +120,7 @@ pub async fn run_dev_server(file: PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn compile_and_load(file: &PathBuf) -> Result<Project> {
+fn compile_and_load(file: &PathBuf, pipeline: &RenderPipeline) -> Result<Project> {
     let file_name = file.file_name().unwrap_or_default().to_string_lossy();
     let ast = crate::parse_and_resolve_imports(file)?;
 
@@ -144,6 +144,11 @@ fn compile_and_load(file: &PathBuf) -> Result<Project> {
         anyhow::anyhow!("Validation errors:\n  {}", msgs.join("\n  "))
     })?;
 
+    // SVG groups and Lottie animations become ordinary layers.
+    pipeline
+        .expand_media(&mut project)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
     Ok(project)
 }
 
@@ -62,7 +62,7 @@ pub async fn run_editor_server(file: PathBuf, port: u16, open: bool) -> Result<(
     let source = std::fs::read_to_string(&file).unwrap_or_default();
     initial_state.source_text = Some(source);
 
-    match compile_and_load(&file) {
+    match compile_and_load(&file, &initial_state.pipeline) {
         Ok(proj) => {
             if let Err(e) = initial_state.pipeline.load_assets(&proj) {
                 println!("   ✗ Asset load failed: {}", e);
@@ -99,7 +99,7 @@ pub async fn run_editor_server(file: PathBuf, port: u16, open: bool) -> Result<(
                     if let Ok(src) = std::fs::read_to_string(&watch_file) {
                         lock.source_text = Some(src);
                     }
-                    match compile_and_load(&watch_file) {
+                    match compile_and_load(&watch_file, &lock.pipeline) {
                         Ok(proj) => {
                             if let Err(e) = lock.pipeline.load_assets(&proj) {
                                 println!("   ✗ Asset load failed: {}", e);
@@ -177,7 +177,7 @@ pub async fn run_editor_server(file: PathBuf, port: u16, open: bool) -> Result<(
 
 // ── Compilation helper (reused from dev_server pattern) ─────────────
 
-fn compile_and_load(file: &PathBuf) -> Result<Project> {
+fn compile_and_load(file: &PathBuf, pipeline: &RenderPipeline) -> Result<Project> {
     let file_name = file.file_name().unwrap_or_default().to_string_lossy();
     let ast = crate::parse_and_resolve_imports(file)?;
 
@@ -198,6 +198,11 @@ fn compile_and_load(file: &PathBuf) -> Result<Project> {
         anyhow::anyhow!("Validation errors:\n  {}", msgs.join("\n  "))
     })?;
 
+    // SVG groups and Lottie animations become ordinary layers.
+    pipeline
+        .expand_media(&mut project)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+
     Ok(project)
 }
 
@@ -345,7 +350,7 @@ async fn put_project(
         }
     };
 
-    let project: Project = match serde_json::from_str(&ir_json) {
+    let mut project: Project = match serde_json::from_str(&ir_json) {
         Ok(p) => p,
         Err(e) => {
             return (
@@ -357,7 +362,11 @@ async fn put_project(
 
     {
         let mut lock = state.editor_state.write();
-        if let Err(e) = lock.pipeline.load_assets(&project) {
+        let loaded = lock
+            .pipeline
+            .expand_media(&mut project)
+            .and_then(|_| lock.pipeline.load_assets(&project));
+        if let Err(e) = loaded {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({ "error": e.to_string() })),
@@ -403,7 +412,7 @@ async fn put_project_source(
     {
         let mut lock = state.editor_state.write();
         lock.source_text = Some(source);
-        match compile_and_load(&state.file_path) {
+        match compile_and_load(&state.file_path, &lock.pipeline) {
             Ok(proj) => {
                 if let Err(e) = lock.pipeline.load_assets(&proj) {
                     lock.error = Some(e.to_string());
@@ -453,7 +462,7 @@ async fn patch_project(
             if let Ok(src) = std::fs::read_to_string(&file) {
                 lock.source_text = Some(src);
             }
-            match compile_and_load(&file) {
+            match compile_and_load(&file, &lock.pipeline) {
                 Ok(proj) => {
                     let _ = lock.pipeline.load_assets(&proj);
                     lock.project = Some(proj);
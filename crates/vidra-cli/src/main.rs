@@ -73,6 +73,10 @@ enum Commands {
         /// Web capture backend: auto, platform, playwright (default: auto)
         #[arg(long, default_value = "auto")]
         web_backend: String,
+
+        /// Compositing backend: auto, gpu, cpu (default: auto — CPU when no GPU adapter is found)
+        #[arg(long, default_value = "auto")]
+        backend: String,
     },
 
     /// Check a VidraScript file for errors (parse + type check)
@@ -82,6 +86,12 @@ enum Commands {
         file: PathBuf,
     },
 
+    /// Import a file from another tool as a Vidra IR project
+    Import {
+        #[command(subcommand)]
+        command: ImportCommands,
+    },
+
     /// Display version and engine info
     Info,
 
@@ -296,6 +306,20 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum ImportCommands {
+    /// Import a Lottie (Bodymovin JSON) animation
+    Lottie {
+        /// Path to the Lottie .json file
+        #[arg()]
+        file: PathBuf,
+
+        /// Output IR project path (default: <name>.vidra.json)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Subcommand)]
 enum AuthCommands {
     /// Login and obtain a Vidra License Token (VLT)
@@ -375,6 +399,15 @@ enum PluginCommands {
     Install {
         #[arg()]
         name: String,
+        /// Install the plugin library (.so/.dylib/.dll) or WebAssembly module (.wasm) at this path
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Fuel a WebAssembly plugin may burn per call (about one unit per instruction)
+        #[arg(long)]
+        fuel: Option<u64>,
+        /// Memory a WebAssembly plugin may use, in MiB
+        #[arg(long)]
+        max_memory: Option<usize>,
     },
     /// Remove a plugin
     Remove {
@@ -455,8 +488,12 @@ fn main() -> Result<()> {
             cloud,
             data,
             web_backend,
+            backend,
         } => {
             std::env::set_var("VIDRA_WEB_BACKEND", &web_backend);
+            let backend: vidra_render::RenderBackend =
+                backend.parse().map_err(|e| anyhow::anyhow!("{}", e))?;
+            std::env::set_var(vidra_render::RenderBackend::ENV_VAR, backend.to_string());
 
             // If using platform webview on macOS, run the render on a
             // background thread while the main thread pumps the RunLoop.
@@ -493,6 +530,9 @@ fn main() -> Result<()> {
         Commands::Test { file, update } => test_runner::run_test(file, update),
         Commands::Bench { file, update } => bench_runner::run_benchmark(file, update),
         Commands::Add { template } => template_manager::execute_add(&template),
+        Commands::Import { command } => match command {
+            ImportCommands::Lottie { file, output } => cmd_import_lottie(file, output),
+        },
         Commands::Info => cmd_info(),
         Commands::Init { name, kit } => cmd_init(&name, kit),
         Commands::Dev { file } => run_async(dev_server::run_dev_server(file)),
@@ -562,7 +602,12 @@ fn main() -> Result<()> {
         Commands::Publish { path } => cmd_publish(&path),
         Commands::Plugins { command } => match command {
             PluginCommands::List => cmd_plugin_list(),
-            PluginCommands::Install { name } => cmd_plugin_install(&name),
+            PluginCommands::Install {
+                name,
+                path,
+                fuel,
+                max_memory,
+            } => cmd_plugin_install(&name, path.as_deref(), fuel, max_memory),
             PluginCommands::Remove { name } => cmd_plugin_remove(&name),
             PluginCommands::Info { name } => cmd_plugin_info(&name),
         },
@@ -942,6 +987,15 @@ fn cmd_render(
     println!("🎬 Vidra Render Engine v{}", env!("CARGO_PKG_VERSION"));
     println!("   Source: {}", file.display());
 
+    let mut plugins = vidra_core::plugin::PluginRegistry::new();
+    let loaded = plugin_tools::load_installed_plugins(&mut plugins)?;
+    if !loaded.is_empty() {
+        let names: Vec<&str> = loaded.iter().map(|p| p.name.as_str()).collect();
+        println!("   ✓ Loaded plugins: {}", names.join(", "));
+    }
+    let plugin_signatures = plugins.signatures();
+    let plugins = std::sync::Arc::new(plugins);
+
     let is_json = file.extension().map_or(false, |e| e == "json");
 
     let mut parse_time_secs = 0.0;
@@ -963,7 +1017,8 @@ fn cmd_render(
 
         // Phase 2: Type Check & Lint
         let type_start = Instant::now();
-        let checker = vidra_lang::TypeChecker::new(file_name.clone());
+        let checker =
+            vidra_lang::TypeChecker::new(file_name.clone()).with_plugins(plugin_signatures);
         let diagnostics = match checker.check(&ast) {
             Ok(diags) => diags,
             Err(diags) => {
@@ -1126,6 +1181,28 @@ fn cmd_render(
                 media_report.waveforms_materialized
             );
         }
+        // Audio envelopes for audio-reactive expressions are cached alongside.
+        let media_cache_dir = media::media_cache_dir(&config)?;
+
+        let mut pipeline =
+            vidra_render::RenderPipeline::new().map_err(|e| anyhow::anyhow!("{}", e))?;
+        pipeline.set_plugins(plugins.clone());
+        pipeline.set_media_cache_dir(Some(media_cache_dir));
+        let expanded = pipeline
+            .expand_media(&mut project)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if expanded.svg_layers_expanded > 0 {
+            println!(
+                "   ✓ SVG groups expanded: {} layer(s)",
+                expanded.svg_layers_expanded
+            );
+        }
+        if expanded.lottie_layers_imported > 0 {
+            println!(
+                "   ✓ Lottie animations imported: {}",
+                expanded.lottie_layers_imported
+            );
+        }
 
         // Phase 3.5: AI materialization (TTS/captions/etc) — gated by config.ai.enabled
         let ai_report = ai::prepare_project_ai(&mut project, &config)?;
@@ -1158,20 +1235,7 @@ fn cmd_render(
             format!("sha256:{}", hex)
         };
 
-        // Phase 4: Render
-        let render_start = Instant::now();
-        let result =
-            vidra_render::RenderPipeline::render(&project).map_err(|e| anyhow::anyhow!("{}", e))?;
-        let render_time = render_start.elapsed();
-        let render_fps = result.frame_count as f64 / render_time.as_secs_f64();
-        println!(
-            "   ✓ Rendered {} frames in {:.1}ms ({:.0} fps)",
-            result.frame_count,
-            render_time.as_secs_f64() * 1000.0,
-            render_fps
-        );
-
-        // Phase 5: Encode — detect output format
+        // Detect output format
         // Determine the output format: explicit --format flag > file extension > default mp4
         let out_ext = format.as_deref().unwrap_or_else(|| {
             output
@@ -1221,75 +1285,76 @@ fn cmd_render(
             }
         }
 
-        let encode_start = Instant::now();
+        // Phase 4+5: Render and encode — frames stream straight into the encoder,
+        // so memory stays flat regardless of video length.
         let audio_tracks = extract_audio_tracks(&project);
+        let (width, height, fps) = (
+            project.settings.width,
+            project.settings.height,
+            project.settings.fps,
+        );
+        let mut sink: Box<dyn vidra_encode::FrameSink> = match out_ext {
+            "webm" => Box::new(vidra_encode::WebmEncoder::open(
+                &audio_tracks,
+                width,
+                height,
+                fps,
+                &output_path,
+                None,
+            )?),
+            "gif" => Box::new(vidra_encode::GifEncoder::open(
+                width,
+                height,
+                fps,
+                &output_path,
+                None,
+            )?),
+            "apng" => Box::new(vidra_encode::ApngEncoder::open(
+                width,
+                height,
+                fps,
+                &output_path,
+                None,
+                project.total_frames() as u32,
+            )?),
+            _ => Box::new(vidra_encode::FfmpegEncoder::open(
+                &audio_tracks,
+                width,
+                height,
+                fps,
+                &output_path,
+            )?),
+        };
 
-        match out_ext {
-            "webm" => {
-                vidra_encode::WebmEncoder::encode(
-                    &result.frames,
-                    &audio_tracks,
-                    result.width,
-                    result.height,
-                    result.fps,
-                    &output_path,
-                    None,
-                )
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            }
-            "gif" => {
-                vidra_encode::GifEncoder::encode(
-                    &result.frames,
-                    result.width,
-                    result.height,
-                    result.fps,
-                    &output_path,
-                    None,
-                )
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            }
-            "apng" => {
-                vidra_encode::ApngEncoder::encode(
-                    &result.frames,
-                    result.width,
-                    result.height,
-                    result.fps,
-                    &output_path,
-                    None,
-                )
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            }
-            _ => {
-                vidra_encode::FfmpegEncoder::encode(
-                    &result.frames,
-                    &audio_tracks,
-                    result.width,
-                    result.height,
-                    result.fps,
-                    &output_path,
-                )
-                .map_err(|e| anyhow::anyhow!("{}", e))?;
-            }
-        }
-
-        let encode_time = encode_start.elapsed();
+        let render_start = Instant::now();
+        let frame_count = pipeline
+            .load_assets(&project)
+            .and_then(|()| {
+                let written = pipeline.stream_frames(&project, sink.as_mut())?;
+                sink.finish()?;
+                Ok(written)
+            })
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let render_time = render_start.elapsed();
+        let render_fps = frame_count as f64 / render_time.as_secs_f64();
         println!(
-            "   ✓ Encoded to {} ({}) in {:.1}ms",
+            "   ✓ Rendered and encoded {} frames to {} ({}) in {:.1}ms ({:.0} fps)",
+            frame_count,
             output_path.display(),
             out_ext.to_uppercase(),
-            encode_time.as_secs_f64() * 1000.0
+            render_time.as_secs_f64() * 1000.0,
+            render_fps
         );
 
         let total_time = start.elapsed();
         println!();
         println!(
-            "   ⚡ Total: {:.2}s (parse: {:.0}ms → typeck: {:.0}ms → compile: {:.0}ms → render: {:.0}ms → encode: {:.0}ms)",
+            "   ⚡ Total: {:.2}s (parse: {:.0}ms → typeck: {:.0}ms → compile: {:.0}ms → render+encode: {:.0}ms)",
             total_time.as_secs_f64(),
             parse_time_secs * 1000.0,
             type_time_secs * 1000.0,
             compile_time_secs * 1000.0,
             render_time.as_secs_f64() * 1000.0,
-            encode_time.as_secs_f64() * 1000.0,
         );
         println!("   📦 Output: {}", output_path.display());
 
@@ -1318,7 +1383,7 @@ fn cmd_render(
                     output_hash,
                     output_format,
                     render_time.as_millis() as u64,
-                    result.frame_count as u64,
+                    frame_count,
                     crate::receipt::HardwareInfo::basic(),
                     vlt_id,
                     &signing_key,
@@ -1479,6 +1544,44 @@ fn cmd_fmt(file: PathBuf, check: bool) -> Result<()> {
     Ok(())
 }
 
+fn cmd_import_lottie(file: PathBuf, output: Option<PathBuf>) -> Result<()> {
+    println!("📥 Importing {}", file.display());
+
+    let (output, issues) = import_lottie(&file, output)?;
+    for issue in &issues {
+        println!("   ⚠️ {}", issue);
+    }
+    println!("   ✅ Wrote {}", output.display());
+
+    Ok(())
+}
+
+/// Convert a Lottie file to an IR project JSON (default: `<name>.vidra.json` next to it).
+/// Embedded images are extracted to `<name>_images/` beside the output.
+pub(crate) fn import_lottie(
+    file: &std::path::Path,
+    output: Option<PathBuf>,
+) -> Result<(PathBuf, Vec<vidra_ir::lottie::ImportIssue>)> {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let output = output.unwrap_or_else(|| file.with_file_name(format!("{}.vidra.json", stem)));
+    let images_dir = output.with_file_name(format!("{}_images", stem));
+
+    let mut import = media::read_lottie(file)?;
+    media::localize_lottie_assets(
+        &mut import,
+        file.parent().unwrap_or(std::path::Path::new("")),
+        &images_dir,
+    )?;
+    let issues = std::mem::take(&mut import.issues);
+
+    let project = import.into_project();
+    let json = serde_json::to_string_pretty(&project).context("failed to serialize project")?;
+    std::fs::write(&output, json)
+        .with_context(|| format!("failed to write project: {}", output.display()))?;
+
+    Ok((output, issues))
+}
+
 fn cmd_info() -> Result<()> {
     println!("🎬 Vidra Video Engine");
     println!("   Version:   {}", env!("CARGO_PKG_VERSION"));
@@ -1713,6 +1816,15 @@ fn print_layer(
     let content_type = match &layer.content {
         vidra_ir::layer::LayerContent::Text { text, .. } => format!("Text (\"{}\")", text),
         vidra_ir::layer::LayerContent::Image { asset_id } => format!("Image (asset: {})", asset_id),
+        vidra_ir::layer::LayerContent::Svg {
+            asset_id, element, ..
+        } => match element {
+            Some(element) => format!("SVG (asset: {}, element: {})", asset_id, element),
+            None => format!("SVG (asset: {})", asset_id),
+        },
+        vidra_ir::layer::LayerContent::Lottie { asset_id } => {
+            format!("Lottie (asset: {})", asset_id)
+        }
         vidra_ir::layer::LayerContent::Video { asset_id, .. } => {
             format!("Video (asset: {})", asset_id)
         }
@@ -1751,6 +1863,7 @@ fn print_layer(
             format!("Shader (asset: {})", asset_id)
         }
         vidra_ir::layer::LayerContent::Web { source, .. } => format!("Web (source: {})", source),
+        vidra_ir::layer::LayerContent::Plugin { name, .. } => format!("Plugin ({})", name),
         vidra_ir::layer::LayerContent::Empty => "Component/Group".to_string(),
     };
 
@@ -1763,7 +1876,18 @@ fn print_layer(
         let mut opacity = layer.transform.opacity;
 
         for anim in &layer.animations {
-            if let Some(val) = anim.evaluate(t) {
+            if anim.property.is_vector() {
+                let v = anim.evaluate_vector(t).unwrap_or_default();
+                if let [vx, vy, ..] = v[..] {
+                    match anim.property {
+                        vidra_ir::animation::AnimatableProperty::Position => (x, y) = (vx, vy),
+                        vidra_ir::animation::AnimatableProperty::Scale => {
+                            (scale_x, scale_y) = (vx, vy)
+                        }
+                        _ => {}
+                    }
+                }
+            } else if let Some(val) = anim.evaluate(t) {
                 match anim.property {
                     vidra_ir::animation::AnimatableProperty::PositionX => x = val,
                     vidra_ir::animation::AnimatableProperty::PositionY => y = val,
@@ -1797,10 +1921,20 @@ fn print_layer(
         };
         let mut keyframes_str = String::new();
         for (k, keyframe) in anim.keyframes.iter().enumerate() {
+            let value = if anim.property.is_vector() {
+                let parts: Vec<_> = keyframe
+                    .vector
+                    .iter()
+                    .map(|v| format!("{:.1}", v))
+                    .collect();
+                format!("({})", parts.join(", "))
+            } else {
+                format!("{:.1}", keyframe.value)
+            };
             keyframes_str.push_str(&format!(
-                "[{:.2}s -> {:.1}]",
+                "[{:.2}s -> {}]",
                 keyframe.time.as_seconds(),
-                keyframe.value
+                value
             ));
             if k < anim.keyframes.len() - 1 {
                 keyframes_str.push_str(" ");
@@ -1808,7 +1942,13 @@ fn print_layer(
         }
 
         let eval_str = if let Some(t) = eval_time {
-            if let Some(val) = anim.evaluate(t) {
+            if let Some(v) = anim
+                .evaluate_vector(t)
+                .filter(|_| anim.property.is_vector())
+            {
+                let parts: Vec<_> = v.iter().map(|v| format!("{:.2}", v)).collect();
+                format!(" => ({})", parts.join(", "))
+            } else if let Some(val) = anim.evaluate(t) {
                 format!(" => {:.2}", val)
             } else {
                 " => (inactive)".to_string()
@@ -2510,8 +2650,20 @@ fn cmd_plugin_list() -> Result<()> {
     Ok(())
 }
 
-fn cmd_plugin_install(name: &str) -> Result<()> {
-    let path = plugin_tools::install_plugin(name, None)?;
+fn cmd_plugin_install(
+    name: &str,
+    library: Option<&std::path::Path>,
+    fuel: Option<u64>,
+    max_memory: Option<usize>,
+) -> Result<()> {
+    let limits = (fuel.is_some() || max_memory.is_some()).then(|| {
+        let default = vidra_core::plugin::wasm::WasmLimits::default();
+        vidra_core::plugin::wasm::WasmLimits {
+            fuel: fuel.unwrap_or(default.fuel),
+            memory: max_memory.map(|mib| mib << 20).unwrap_or(default.memory),
+        }
+    });
+    let path = plugin_tools::install_plugin(name, None, library, limits)?;
     println!("🔌 Installed plugin '{}'", name);
     println!("   Manifest: {}", path.display());
     Ok(())
@@ -2528,6 +2680,17 @@ fn cmd_plugin_info(name: &str) -> Result<()> {
     println!("🔌 Plugin: {}", m.name);
     println!("   Version:     {}", m.version);
     println!("   Installed:   {}", m.installed_at.to_rfc3339());
+    if let Some(library) = &m.library {
+        println!("   Library:     {}", library);
+        println!("   Plugin ABI:  v{}", m.abi_version.unwrap_or(0));
+    }
+    if let Some(limits) = &m.limits {
+        println!(
+            "   Sandbox:     WebAssembly, {} fuel per call, {} MiB memory",
+            limits.fuel,
+            limits.memory >> 20
+        );
+    }
     Ok(())
 }
 
@@ -216,6 +216,18 @@ async fn handle_request(req: &JsonRpcRequest) -> Option<JsonRpcResponse> {
                         "required": ["id", "path", "type"]
                     }
                 }),
+                serde_json::json!({
+                    "name": "vidra-import_lottie",
+                    "description": "Convert a Lottie (Bodymovin JSON) animation to a Vidra IR project JSON. To use it inside a .vidra file instead, add a lottie(\"file.json\") layer.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "file": { "type": "string", "description": "Path to the Lottie .json file" },
+                            "output": { "type": "string", "description": "Output project path (default: <name>.vidra.json)" }
+                        },
+                        "required": ["file"]
+                    }
+                }),
                 serde_json::json!({
                     "name": "vidra-list_templates",
                     "description": "List available video templates",
@@ -527,6 +539,32 @@ async fn execute_tool(name: &str, args: Value) -> String {
                 (Err(e), _) => format!("❌ Failed to register asset '{}': {:#}", id, e),
             }
         }
+        "vidra-import_lottie" => {
+            let file = args.get("file").and_then(|v| v.as_str()).unwrap_or("");
+            if file.is_empty() {
+                return "❌ Missing required `file`".to_string();
+            }
+            let output = args
+                .get("output")
+                .and_then(|v| v.as_str())
+                .map(std::path::PathBuf::from);
+            match crate::import_lottie(std::path::Path::new(file), output) {
+                Ok((out, issues)) if issues.is_empty() => {
+                    format!("✅ Imported '{}' to {}", file, out.display())
+                }
+                Ok((out, issues)) => {
+                    let lines: Vec<String> = issues.iter().map(|i| format!("- {}", i)).collect();
+                    format!(
+                        "✅ Imported '{}' to {} with {} issue(s):\n{}",
+                        file,
+                        out.display(),
+                        issues.len(),
+                        lines.join("\n")
+                    )
+                }
+                Err(e) => format!("❌ Failed to import '{}': {:#}", file, e),
+            }
+        }
         "vidra-list_templates" => {
             let names: Vec<&str> = crate::template_manager::available_templates()
                 .into_iter()
@@ -1,13 +1,16 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use sha2::{Digest, Sha256};
 
 use vidra_core::VidraConfig;
 use vidra_encode::FfmpegEncoder;
 use vidra_ir::asset::{Asset, AssetId, AssetRegistry, AssetType};
 use vidra_ir::layer::{Layer, LayerContent};
+use vidra_ir::lottie::LottieImport;
 use vidra_ir::Project;
 
 pub struct MediaPrepareReport {
@@ -74,6 +77,55 @@ fn materialize_layer_media(
     Ok(())
 }
 
+/// Read and import a Lottie JSON file.
+pub fn read_lottie(path: &Path) -> Result<LottieImport> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read Lottie file: {}", path.display()))?;
+    vidra_ir::lottie::import(&json)
+        .with_context(|| format!("failed to import Lottie file: {}", path.display()))
+}
+
+/// Point a Lottie import's images at files on disk: relative paths are resolved against
+/// `base_dir` (the animation's directory) and embedded `data:` images are written to `out_dir`.
+pub fn localize_lottie_assets(
+    import: &mut LottieImport,
+    base_dir: &Path,
+    out_dir: &Path,
+) -> Result<()> {
+    import.resolve_image_paths(base_dir);
+    for asset in &mut import.assets {
+        let path = asset.path.to_string_lossy().into_owned();
+        if let Some(uri) = path.strip_prefix("data:") {
+            let (header, payload) = uri
+                .split_once(',')
+                .ok_or_else(|| anyhow!("lottie: malformed data URI for image '{}'", asset.id))?;
+            let Some(mime) = header.strip_suffix(";base64") else {
+                bail!("lottie: image '{}' is not base64 encoded", asset.id);
+            };
+            let bytes = BASE64_STANDARD
+                .decode(payload.trim())
+                .with_context(|| format!("lottie: failed to decode image '{}'", asset.id))?;
+            let ext = match mime {
+                "image/jpeg" | "image/jpg" => "jpg",
+                "image/svg+xml" => "svg",
+                "image/webp" => "webp",
+                _ => "png",
+            };
+            std::fs::create_dir_all(out_dir).with_context(|| {
+                format!("failed to create Lottie image dir: {}", out_dir.display())
+            })?;
+            let out_path = out_dir.join(format!("{}.{}", sha256_hex_bytes(&bytes), ext));
+            if !out_path.exists() {
+                std::fs::write(&out_path, &bytes).with_context(|| {
+                    format!("failed to write Lottie image: {}", out_path.display())
+                })?;
+            }
+            asset.path = out_path;
+        }
+    }
+    Ok(())
+}
+
 fn resolve_asset_path(assets: &AssetRegistry, asset_id: &AssetId) -> Option<PathBuf> {
     assets
         .get(asset_id)
@@ -146,6 +198,11 @@ fn materialize_waveform_png(
     Ok(asset_id)
 }
 
+/// Directory for cached media derived from project files (waveforms, audio envelopes).
+pub fn media_cache_dir(config: &VidraConfig) -> Result<PathBuf> {
+    Ok(resolve_cache_root(config)?.join("media"))
+}
+
 fn resolve_cache_root(config: &VidraConfig) -> Result<PathBuf> {
     let raw = &config.resources.cache_dir;
     expand_tilde(raw)
@@ -191,4 +248,26 @@ mod tests {
         let d = sha256_hex_bytes(b"x");
         assert_eq!(c, d);
     }
+
+    #[test]
+    fn lottie_images_are_localized() {
+        let json = r#"{"fr":30,"ip":0,"op":30,"w":100,"h":100,
+            "assets":[
+                {"id":"a","w":1,"h":1,"u":"images/","p":"a.png","e":0},
+                {"id":"b","w":1,"h":1,"u":"","p":"data:image/png;base64,aGVsbG8=","e":1}],
+            "layers":[
+                {"ty":2,"refId":"a","ind":1,"ip":0,"op":30,"st":0,"ks":{}},
+                {"ty":2,"refId":"b","ind":2,"ip":0,"op":30,"st":0,"ks":{}}]}"#;
+        let mut import = vidra_ir::lottie::import(json).unwrap();
+        let out_dir = std::env::temp_dir().join(format!("vidra-lottie-{}", std::process::id()));
+        localize_lottie_assets(&mut import, Path::new("anim"), &out_dir).unwrap();
+
+        let path = |id: &str| &import.assets.iter().find(|a| a.id.0 == id).unwrap().path;
+        assert_eq!(path("a"), Path::new("anim/images/a.png"));
+        let embedded = path("b");
+        assert!(embedded.starts_with(&out_dir));
+        assert_eq!(embedded.extension().unwrap(), "png");
+        assert_eq!(std::fs::read(embedded).unwrap(), b"hello");
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
 }
@@ -1,11 +1,53 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use vidra_core::plugin::wasm::{self, WasmLimits};
+use vidra_core::plugin::{abi, PluginRegistry, PluginSignature};
+
+/// How an installed plugin's library is run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginRuntime {
+    /// A dynamic library, run with the same access as Vidra itself.
+    #[default]
+    Native,
+    /// A WebAssembly module, run sandboxed within its limits.
+    Wasm,
+}
+
+impl PluginRuntime {
+    /// The runtime for the library at `path`, by its extension.
+    pub fn for_library(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("wasm") => PluginRuntime::Wasm,
+            _ => PluginRuntime::Native,
+        }
+    }
+
+    /// The plugin ABI version this Vidra supports for the runtime.
+    pub fn abi_version(self) -> u32 {
+        match self {
+            PluginRuntime::Native => abi::ABI_VERSION,
+            PluginRuntime::Wasm => wasm::ABI_VERSION,
+        }
+    }
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PluginManifest {
     pub name: String,
     pub version: String,
     pub installed_at: chrono::DateTime<chrono::Utc>,
+    /// File name of the plugin's library in its directory, if it has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+    /// Plugin ABI version the library was built against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abi_version: Option<u32>,
+    #[serde(default)]
+    pub runtime: PluginRuntime,
+    /// Limits a WebAssembly plugin runs within.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<WasmLimits>,
 }
 
 fn resolve_home_dir() -> Result<PathBuf> {
@@ -33,15 +75,52 @@ pub fn plugin_manifest_path(name: &str) -> Result<PathBuf> {
     Ok(plugin_dir(name)?.join("plugin.json"))
 }
 
-pub fn install_plugin(name: &str, version: Option<&str>) -> Result<PathBuf> {
+/// Install plugin `name`. With `library`, the library is checked to load and copied
+/// into the plugin's directory, to be loaded by [`load_installed_plugins`]. A `.wasm`
+/// library is a WebAssembly plugin, run within `limits` (or the default limits).
+pub fn install_plugin(
+    name: &str,
+    version: Option<&str>,
+    library: Option<&Path>,
+    limits: Option<WasmLimits>,
+) -> Result<PathBuf> {
+    let runtime = library.map(PluginRuntime::for_library).unwrap_or_default();
+    let limits = match runtime {
+        PluginRuntime::Wasm => Some(limits.unwrap_or_default()),
+        PluginRuntime::Native if limits.is_some() => {
+            anyhow::bail!("limits only apply to WebAssembly (.wasm) plugins")
+        }
+        PluginRuntime::Native => None,
+    };
+
     let dir = plugin_dir(name)?;
     std::fs::create_dir_all(&dir)
         .with_context(|| format!("failed to create plugin dir: {}", dir.display()))?;
 
+    let library = match library {
+        Some(source) => {
+            load_library(&mut PluginRegistry::new(), source, runtime, limits)?;
+            let file_name = source
+                .file_name()
+                .context("plugin library path has no file name")?
+                .to_string_lossy()
+                .to_string();
+            let dest = dir.join(&file_name);
+            std::fs::copy(source, &dest)
+                .with_context(|| format!("failed to copy plugin library to {}", dest.display()))?;
+            Some(file_name)
+        }
+        None => None,
+    };
+
     let manifest = PluginManifest {
         name: name.to_string(),
         version: version.unwrap_or("0.1.0").to_string(),
         installed_at: chrono::Utc::now(),
+        abi_version: library.as_ref().map(|_| runtime.abi_version()),
+        library,
+        runtime,
+        limits,
     };
     let path = plugin_manifest_path(name)?;
     let json =
@@ -51,6 +130,50 @@ pub fn install_plugin(name: &str, version: Option<&str>) -> Result<PathBuf> {
     Ok(path)
 }
 
+/// Load the libraries of the installed plugins into `registry`. Plugins installed for
+/// another ABI version, or whose library fails to load, are skipped with a warning.
+pub fn load_installed_plugins(registry: &mut PluginRegistry) -> Result<Vec<PluginSignature>> {
+    let mut loaded = Vec::new();
+    for manifest in list_plugins()? {
+        let Some(library) = &manifest.library else {
+            continue;
+        };
+        let abi_version = manifest.runtime.abi_version();
+        if manifest.abi_version != Some(abi_version) {
+            tracing::warn!(
+                "Skipping plugin '{}': installed for plugin ABI v{}, but this Vidra uses v{}; reinstall it",
+                manifest.name,
+                manifest.abi_version.unwrap_or(0),
+                abi_version
+            );
+            continue;
+        }
+        let path = plugin_dir(&manifest.name)?.join(library);
+        match load_library(registry, &path, manifest.runtime, manifest.limits) {
+            Ok(signature) => loaded.push(signature),
+            Err(e) => tracing::warn!("Skipping plugin '{}': {}", manifest.name, e),
+        }
+    }
+    Ok(loaded)
+}
+
+fn load_library(
+    registry: &mut PluginRegistry,
+    path: &Path,
+    runtime: PluginRuntime,
+    limits: Option<WasmLimits>,
+) -> Result<PluginSignature> {
+    let signature = match runtime {
+        PluginRuntime::Native => registry.load_from_path(path),
+        PluginRuntime::Wasm => {
+            let module = std::fs::read(path)
+                .with_context(|| format!("failed to read plugin module: {}", path.display()))?;
+            registry.load_wasm(&module, limits.unwrap_or_default())
+        }
+    };
+    signature.map_err(|e| anyhow::anyhow!("{}", e))
+}
+
 pub fn remove_plugin(name: &str) -> Result<()> {
     let dir = plugin_dir(name)?;
     if !dir.exists() {
@@ -123,7 +246,7 @@ mod tests {
         std::fs::create_dir_all(&tmp).unwrap();
         std::env::set_var("VIDRA_HOME_DIR", &tmp);
 
-        install_plugin("vidra-color-grade", Some("1.1.0")).unwrap();
+        install_plugin("vidra-color-grade", Some("1.1.0"), None, None).unwrap();
         let list = list_plugins().unwrap();
         assert_eq!(list.len(), 1);
         assert_eq!(list[0].name, "vidra-color-grade");
@@ -105,11 +105,12 @@ fn prepare_registry_remote_assets(
 
 fn asset_type_dir(asset_type: &AssetType) -> &'static str {
     match asset_type {
-        AssetType::Image => "images",
+        AssetType::Image | AssetType::Svg => "images",
         AssetType::Video => "video",
         AssetType::Audio => "audio",
         AssetType::Font => "fonts",
         AssetType::Shader => "shaders",
+        AssetType::Lottie => "lottie",
         AssetType::Lut => "luts",
     }
 }
@@ -117,6 +118,8 @@ fn asset_type_dir(asset_type: &AssetType) -> &'static str {
 fn default_extension_for_type(asset_type: &AssetType) -> Option<&'static str> {
     match asset_type {
         AssetType::Image => Some("png"),
+        AssetType::Svg => Some("svg"),
+        AssetType::Lottie => Some("json"),
         AssetType::Video => Some("mp4"),
         AssetType::Audio => Some("mp3"),
         AssetType::Font => Some("ttf"),
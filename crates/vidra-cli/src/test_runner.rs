@@ -31,7 +31,7 @@ pub fn run_test(file: PathBuf, update: bool) -> Result<()> {
     }
 
     // 2. Compile to IR
-    let project =
+    let mut project =
         vidra_lang::Compiler::compile(&ast).map_err(|e| anyhow::anyhow!("Compile error: {}", e))?;
 
     vidra_ir::validate::validate_project(&project).map_err(|errors| {
@@ -41,6 +41,9 @@ pub fn run_test(file: PathBuf, update: bool) -> Result<()> {
 
     // 3. Setup Render Pipeline
     let mut pipeline = vidra_render::RenderPipeline::new().map_err(|e| anyhow::anyhow!("{}", e))?;
+    pipeline
+        .expand_media(&mut project)
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
     pipeline
         .load_assets(&project)
         .map_err(|e| anyhow::anyhow!("{}", e))?;
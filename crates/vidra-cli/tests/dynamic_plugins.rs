@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Build the sample checkerboard plugin and return the path of its library.
+fn build_checkerboard() -> PathBuf {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let output = Command::new(env!("CARGO"))
+        .current_dir(&root)
+        .args([
+            "build",
+            "-p",
+            "vidra-plugin-checkerboard",
+            "--message-format=json-render-diagnostics",
+        ])
+        .output()
+        .expect("failed to run cargo");
+    assert!(
+        output.status.success(),
+        "building the sample plugin failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).expect("cargo output not utf-8");
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|msg| msg["reason"] == "compiler-artifact")
+        .filter(|msg| msg["target"]["name"] == "vidra_plugin_checkerboard")
+        .flat_map(|msg| {
+            msg["filenames"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|f| f.as_str().map(PathBuf::from))
+        })
+        .find(|f| {
+            matches!(
+                f.extension().and_then(|e| e.to_str()),
+                Some("so" | "dylib" | "dll")
+            )
+        })
+        .expect("no library artifact for the sample plugin")
+}
+
+fn vidra(home: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_vidra"));
+    cmd.current_dir(home).env("VIDRA_HOME_DIR", home);
+    cmd
+}
+
+#[test]
+fn installed_plugin_renders_and_is_checked() {
+    let library = build_checkerboard();
+    let home = std::env::temp_dir().join(format!("vidra_dynamic_plugins_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&home);
+    std::fs::create_dir_all(&home).unwrap();
+
+    let install = vidra(&home)
+        .args(["plugins", "install", "checkerboard", "--path"])
+        .arg(&library)
+        .output()
+        .expect("failed to run vidra plugins install");
+    assert!(
+        install.status.success(),
+        "install failed: {}",
+        String::from_utf8_lossy(&install.stderr)
+    );
+
+    let source = home.join("board.vidra");
+    std::fs::write(
+        &source,
+        r#"project(64, 64, 10) {
+    scene("main", 0.1s) {
+        layer("board") {
+            plugin(checkerboard, size: 16, color: #FF0000)
+        }
+    }
+}
+"#,
+    )
+    .unwrap();
+    let out = home.join("board.png");
+    let render = vidra(&home)
+        .arg("render")
+        .arg(&source)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("failed to run vidra render");
+    assert!(
+        render.status.success(),
+        "render failed: {}",
+        String::from_utf8_lossy(&render.stderr)
+    );
+
+    let image = image::open(&out)
+        .expect("failed to decode render")
+        .to_rgba8();
+    assert_eq!(image.get_pixel(4, 4).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(20, 20).0, [255, 0, 0, 255]);
+    assert_ne!(image.get_pixel(20, 4).0, [255, 0, 0, 255]);
+
+    // The installed plugin's parameters are known to the checker.
+    std::fs::write(
+        &source,
+        r#"project(64, 64, 10) {
+    scene("main", 0.1s) {
+        layer("board") {
+            plugin(checkerboard, sides: 3)
+        }
+    }
+}
+"#,
+    )
+    .unwrap();
+    let render = vidra(&home)
+        .arg("render")
+        .arg(&source)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("failed to run vidra render");
+    assert!(!render.status.success());
+    let output = format!(
+        "{}{}",
+        String::from_utf8_lossy(&render.stdout),
+        String::from_utf8_lossy(&render.stderr)
+    );
+    assert!(output.contains("sides"), "unexpected output: {output}");
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn installed_wasm_plugin_runs_sandboxed() {
+    let wat = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../examples/plugins/posterize/posterize.wat");
+    let module = wat::parse_file(&wat).expect("failed to compile the sample plugin");
+    let home = std::env::temp_dir().join(format!("vidra_wasm_plugins_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&home);
+    std::fs::create_dir_all(&home).unwrap();
+    let library = home.join("posterize.wasm");
+    std::fs::write(&library, module).unwrap();
+
+    let install = vidra(&home)
+        .args([
+            "plugins",
+            "install",
+            "posterize",
+            "--fuel",
+            "50000000",
+            "--path",
+        ])
+        .arg(&library)
+        .output()
+        .expect("failed to run vidra plugins install");
+    assert!(
+        install.status.success(),
+        "install failed: {}",
+        String::from_utf8_lossy(&install.stderr)
+    );
+    let info = vidra(&home)
+        .args(["plugins", "info", "posterize"])
+        .output()
+        .expect("failed to run vidra plugins info");
+    assert!(String::from_utf8_lossy(&info.stdout).contains("50000000 fuel"));
+
+    let source = home.join("poster.vidra");
+    std::fs::write(
+        &source,
+        r#"project(32, 32, 10) {
+    scene("main", 0.1s) {
+        layer("light") {
+            solid(#A0A0A0)
+            effect(posterize, levels: 2)
+        }
+    }
+}
+"#,
+    )
+    .unwrap();
+    let out = home.join("poster.png");
+    let render = vidra(&home)
+        .arg("render")
+        .arg(&source)
+        .arg("-o")
+        .arg(&out)
+        .output()
+        .expect("failed to run vidra render");
+    assert!(
+        render.status.success(),
+        "render failed: {}",
+        String::from_utf8_lossy(&render.stderr)
+    );
+
+    let image = image::open(&out)
+        .expect("failed to decode render")
+        .to_rgba8();
+    assert_eq!(image.get_pixel(16, 16).0, [255, 255, 255, 255]);
+
+    let _ = std::fs::remove_dir_all(&home);
+}
@@ -0,0 +1,233 @@
+//! Blend modes, following the W3C Compositing and Blending Level 1 spec.
+//!
+//! Colors are blended in straight (non-premultiplied) RGB in the 0–1 range, then
+//! composited source-over: where the backdrop is transparent the layer shows unblended,
+//! and where the layer is transparent the backdrop shows unchanged. The math runs in
+//! `f64` and rounds once at the end, so results are reproducible on every platform.
+//! `compositor.wgsl` in `vidra-render` mirrors these formulas for the GPU.
+
+use crate::types::BlendMode;
+
+type Rgb = [f64; 3];
+
+/// Composite one straight-alpha `src` pixel over `dst` with `mode`.
+pub fn blend_pixel(mode: BlendMode, src: [u8; 4], dst: [u8; 4]) -> [u8; 4] {
+    let sa = src[3] as f64 / 255.0;
+    if sa == 0.0 {
+        return dst;
+    }
+    let da = dst[3] as f64 / 255.0;
+    let cs = [src[0], src[1], src[2]].map(|c| c as f64 / 255.0);
+    let cb = [dst[0], dst[1], dst[2]].map(|c| c as f64 / 255.0);
+
+    let blended = blend(mode, cb, cs);
+    let out_a = sa + da * (1.0 - sa);
+    let mut out = [0u8; 4];
+    for i in 0..3 {
+        // The source color, mixed towards the blend result where there is a backdrop.
+        let mixed = (1.0 - da) * cs[i] + da * blended[i];
+        let premultiplied = sa * mixed + (1.0 - sa) * da * cb[i];
+        out[i] = to_u8(premultiplied / out_a);
+    }
+    out[3] = to_u8(out_a);
+    out
+}
+
+/// The blend function `B(Cb, Cs)` of `mode` for backdrop `cb` and source `cs`.
+pub fn blend(mode: BlendMode, cb: Rgb, cs: Rgb) -> Rgb {
+    match mode {
+        BlendMode::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+        BlendMode::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+        BlendMode::Color => set_lum(cs, lum(cb)),
+        BlendMode::Luminosity => set_lum(cb, lum(cs)),
+        _ => [0, 1, 2].map(|i| blend_channel(mode, cb[i], cs[i])),
+    }
+}
+
+/// Separable blend function of one channel. Non-separable modes return `cs`.
+fn blend_channel(mode: BlendMode, cb: f64, cs: f64) -> f64 {
+    match mode {
+        BlendMode::Multiply => cb * cs,
+        BlendMode::Screen => screen(cb, cs),
+        BlendMode::Overlay => hard_light(cs, cb),
+        BlendMode::Add => (cb + cs).min(1.0),
+        BlendMode::Darken => cb.min(cs),
+        BlendMode::Lighten => cb.max(cs),
+        BlendMode::ColorDodge => {
+            if cb == 0.0 {
+                0.0
+            } else if cs >= 1.0 {
+                1.0
+            } else {
+                (cb / (1.0 - cs)).min(1.0)
+            }
+        }
+        BlendMode::ColorBurn => {
+            if cb >= 1.0 {
+                1.0
+            } else if cs == 0.0 {
+                0.0
+            } else {
+                1.0 - ((1.0 - cb) / cs).min(1.0)
+            }
+        }
+        BlendMode::HardLight => hard_light(cb, cs),
+        BlendMode::SoftLight => {
+            if cs <= 0.5 {
+                cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+            } else {
+                let d = if cb <= 0.25 {
+                    ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                } else {
+                    cb.sqrt()
+                };
+                cb + (2.0 * cs - 1.0) * (d - cb)
+            }
+        }
+        BlendMode::Difference => (cb - cs).abs(),
+        BlendMode::Exclusion => cb + cs - 2.0 * cb * cs,
+        BlendMode::Normal
+        | BlendMode::Hue
+        | BlendMode::Saturation
+        | BlendMode::Color
+        | BlendMode::Luminosity => cs,
+    }
+}
+
+fn screen(cb: f64, cs: f64) -> f64 {
+    cb + cs - cb * cs
+}
+
+fn hard_light(cb: f64, cs: f64) -> f64 {
+    if cs <= 0.5 {
+        cb * 2.0 * cs
+    } else {
+        screen(cb, 2.0 * cs - 1.0)
+    }
+}
+
+fn lum(c: Rgb) -> f64 {
+    0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2]
+}
+
+/// Bring `c` back into gamut while keeping its luminosity.
+fn clip_color(c: Rgb) -> Rgb {
+    let l = lum(c);
+    let n = c[0].min(c[1]).min(c[2]);
+    let x = c[0].max(c[1]).max(c[2]);
+    c.map(|v| {
+        let mut v = v;
+        if n < 0.0 {
+            v = l + (v - l) * l / (l - n);
+        }
+        if x > 1.0 {
+            v = l + (v - l) * (1.0 - l) / (x - l);
+        }
+        v
+    })
+}
+
+fn set_lum(c: Rgb, l: f64) -> Rgb {
+    let d = l - lum(c);
+    clip_color(c.map(|v| v + d))
+}
+
+fn sat(c: Rgb) -> f64 {
+    c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2])
+}
+
+/// `c` with its saturation (max − min) set to `s`, keeping the order of its channels.
+fn set_sat(c: Rgb, s: f64) -> Rgb {
+    let mut idx = [0, 1, 2];
+    idx.sort_by(|&a, &b| c[a].total_cmp(&c[b]));
+    let [min, mid, max] = idx;
+    let mut out = [0.0; 3];
+    if c[max] > c[min] {
+        out[mid] = (c[mid] - c[min]) * s / (c[max] - c[min]);
+        out[max] = s;
+    }
+    out
+}
+
+fn to_u8(v: f64) -> u8 {
+    (v * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAY: [u8; 4] = [128, 128, 128, 255];
+
+    #[test]
+    fn test_separable_modes_on_opaque_pixels() {
+        let src = [255, 128, 0, 255];
+        let dst = [64, 128, 192, 255];
+        let px = |mode| blend_pixel(mode, src, dst);
+        assert_eq!(px(BlendMode::Normal), src);
+        assert_eq!(px(BlendMode::Multiply), [64, 64, 0, 255]);
+        assert_eq!(px(BlendMode::Screen), [255, 192, 192, 255]);
+        assert_eq!(px(BlendMode::Add), [255, 255, 192, 255]);
+        assert_eq!(px(BlendMode::Darken), [64, 128, 0, 255]);
+        assert_eq!(px(BlendMode::Lighten), [255, 128, 192, 255]);
+        assert_eq!(px(BlendMode::Difference), [191, 0, 192, 255]);
+        assert_eq!(px(BlendMode::Exclusion), [191, 127, 192, 255]);
+        assert_eq!(px(BlendMode::ColorDodge), [255, 255, 192, 255]);
+        assert_eq!(px(BlendMode::ColorBurn), [64, 2, 0, 255]);
+    }
+
+    #[test]
+    fn test_light_modes_around_mid_gray() {
+        let dst = [40, 120, 220, 255];
+        // Darker than mid-gray multiplies, lighter screens; either way close to a no-op.
+        assert_eq!(
+            blend_pixel(BlendMode::HardLight, [127, 127, 127, 255], dst),
+            [40, 120, 219, 255]
+        );
+        assert_eq!(
+            blend_pixel(BlendMode::HardLight, GRAY, dst),
+            [41, 121, 220, 255]
+        );
+        // Soft light with black squares the backdrop.
+        assert_eq!(
+            blend_pixel(BlendMode::SoftLight, [0, 0, 0, 255], dst),
+            [6, 56, 190, 255]
+        );
+        // Overlay is hard light with the layers swapped.
+        assert_eq!(
+            blend_pixel(BlendMode::Overlay, dst, GRAY),
+            [41, 121, 220, 255]
+        );
+    }
+
+    #[test]
+    fn test_non_separable_modes() {
+        let red = [255, 0, 0, 255];
+        // Gray has no hue or saturation to give.
+        assert_eq!(
+            blend_pixel(BlendMode::Saturation, GRAY, red),
+            [77, 77, 77, 255]
+        );
+        assert_eq!(blend_pixel(BlendMode::Color, red, GRAY), [255, 74, 74, 255]);
+        assert_eq!(
+            blend_pixel(BlendMode::Luminosity, GRAY, red),
+            [255, 74, 74, 255]
+        );
+        let blue = [0, 0, 255, 255];
+        // Blue's hue at red's luminosity, which is too dark for pure blue.
+        assert_eq!(blend_pixel(BlendMode::Hue, blue, red), [54, 54, 255, 255]);
+    }
+
+    #[test]
+    fn test_blending_needs_a_backdrop() {
+        let src = [200, 100, 50, 255];
+        // Over transparent pixels the layer shows as it is, whatever the mode.
+        for mode in BlendMode::ALL {
+            assert_eq!(blend_pixel(mode, src, [0, 0, 0, 0]), src, "{mode:?}");
+            assert_eq!(blend_pixel(mode, [0, 0, 0, 0], GRAY), GRAY, "{mode:?}");
+        }
+        // Half-transparent layer: halfway between the backdrop and the blend result.
+        let out = blend_pixel(BlendMode::Multiply, [0, 0, 0, 128], [200, 200, 200, 255]);
+        assert_eq!(out, [100, 100, 100, 255]);
+    }
+}
@@ -72,6 +72,84 @@ impl Color {
         }
     }
 
+    /// Interpolate between two colors in `space`.
+    ///
+    /// Alpha is always interpolated linearly. `t` may leave [0, 1] (e.g. with overshooting
+    /// easings); the resulting channels are clamped to [0, 1].
+    pub fn mix(&self, other: &Color, t: f32, space: ColorSpace) -> Color {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let [r, g, b] = match space {
+            ColorSpace::Srgb => [
+                lerp(self.r, other.r),
+                lerp(self.g, other.g),
+                lerp(self.b, other.b),
+            ],
+            ColorSpace::LinearRgb => {
+                let (x, y) = (self.linear_rgb(), other.linear_rgb());
+                Color::srgb_from_linear([lerp(x[0], y[0]), lerp(x[1], y[1]), lerp(x[2], y[2])])
+            }
+            ColorSpace::Oklab => {
+                let (x, y) = (self.oklab(), other.oklab());
+                Color::srgb_from_oklab([lerp(x[0], y[0]), lerp(x[1], y[1]), lerp(x[2], y[2])])
+            }
+        };
+        Color {
+            r: r.clamp(0.0, 1.0),
+            g: g.clamp(0.0, 1.0),
+            b: b.clamp(0.0, 1.0),
+            a: lerp(self.a, other.a).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Linear-light RGB components.
+    fn linear_rgb(self) -> [f32; 3] {
+        let decode = |c: f32| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        [decode(self.r), decode(self.g), decode(self.b)]
+    }
+
+    /// sRGB components from linear-light RGB.
+    fn srgb_from_linear(rgb: [f32; 3]) -> [f32; 3] {
+        let encode = |c: f32| {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.max(0.0).powf(1.0 / 2.4) - 0.055
+            }
+        };
+        rgb.map(encode)
+    }
+
+    /// OKLab `[L, a, b]` components (Björn Ottosson's matrices).
+    fn oklab(self) -> [f32; 3] {
+        let [r, g, b] = self.linear_rgb();
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+        [
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        ]
+    }
+
+    /// sRGB components from OKLab.
+    fn srgb_from_oklab([l, a, b]: [f32; 3]) -> [f32; 3] {
+        let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+        let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+        let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+        Color::srgb_from_linear([
+            4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+            -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+            -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+        ])
+    }
+
     // --- Named constants ---
 
     pub const TRANSPARENT: Color = Color {
@@ -129,6 +207,18 @@ impl fmt::Display for Color {
     }
 }
 
+/// Color space in which colors are interpolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorSpace {
+    /// The gamma-encoded sRGB components as stored. Mixes of saturated colors turn dark.
+    Srgb,
+    /// Linear-light RGB, as light physically adds up.
+    LinearRgb,
+    /// OKLab, where equal steps look equally different: the most even-looking blends.
+    #[default]
+    Oklab,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ColorError {
     #[error("invalid hex color string")]
@@ -173,6 +263,38 @@ mod tests {
         assert!((mid.b - 0.5).abs() < 0.01);
     }
 
+    #[test]
+    fn test_color_mix_spaces() {
+        let (red, green) = (Color::RED, Color::GREEN);
+        for space in [ColorSpace::Srgb, ColorSpace::LinearRgb, ColorSpace::Oklab] {
+            for (t, expected) in [(0.0, red), (1.0, green)] {
+                let c = red.mix(&green, t, space);
+                assert!(
+                    (c.r - expected.r).abs() < 1e-3
+                        && (c.g - expected.g).abs() < 1e-3
+                        && (c.b - expected.b).abs() < 1e-3,
+                    "{:?} at {}: {:?}",
+                    space,
+                    t,
+                    c
+                );
+            }
+        }
+
+        let srgb = red.mix(&green, 0.5, ColorSpace::Srgb);
+        let linear = red.mix(&green, 0.5, ColorSpace::LinearRgb);
+        let oklab = red.mix(&green, 0.5, ColorSpace::Oklab);
+        assert_eq!(srgb.to_rgba8(), [127, 127, 0, 255]);
+        // Linear light encodes 0.5 as about 0.735.
+        assert_eq!(linear.to_rgba8(), [187, 187, 0, 255]);
+        // OKLab keeps the midpoint bright and slightly warm.
+        assert!(oklab.r > srgb.r && oklab.g > srgb.g);
+        assert!((oklab.a - 1.0).abs() < 1e-6);
+
+        let clear = Color::rgba(0.0, 0.0, 1.0, 0.0);
+        assert!((Color::BLUE.mix(&clear, 0.25, ColorSpace::Oklab).a - 0.75).abs() < 1e-6);
+    }
+
     #[test]
     fn test_color_display() {
         assert_eq!(format!("{}", Color::RED), "#FF0000");
@@ -50,6 +50,9 @@ pub enum VidraError {
     #[error("unsupported feature: {0}")]
     Unsupported(String),
 
+    #[error("plugin error: {0}")]
+    Plugin(String),
+
     #[error("{0}")]
     Other(String),
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::types::BlendMode;
+
 /// Pixel format of a frame buffer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PixelFormat {
@@ -94,6 +96,12 @@ impl FrameBuffer {
         }
     }
 
+    /// Bilinearly interpolate the RGBA value at a fractional pixel coordinate (integer
+    /// coordinates hit pixels exactly). Coordinates outside the buffer clamp to its edge.
+    pub fn sample_bilinear(&self, x: f64, y: f64) -> [u8; 4] {
+        sample_bilinear_rgba8(self, x, y)
+    }
+
     /// Set the RGBA value at a pixel coordinate. No-op if out of bounds.
     pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
         if x >= self.width || y >= self.height {
@@ -147,9 +155,25 @@ impl FrameBuffer {
         }
     }
 
+    /// Scale every pixel's alpha by `factor` (0.0–1.0).
+    pub fn multiply_alpha(&mut self, factor: f64) {
+        if self.format != PixelFormat::Rgba8 || factor >= 1.0 {
+            return;
+        }
+        let scale = (factor.max(0.0) * 255.0).round() as u32;
+        for px in self.data.chunks_exact_mut(4) {
+            px[3] = ((px[3] as u32 * scale + 127) / 255) as u8;
+        }
+    }
+
     /// Alpha-composite `src` on top of `self` at position (dx, dy).
     /// Uses highly optimized SIMD-friendly integer math for auto-vectorization.
     pub fn composite_over(&mut self, src: &FrameBuffer, dx: i32, dy: i32) {
+        self.composite_blend(src, dx, dy, BlendMode::Normal);
+    }
+
+    /// Composite `src` on top of `self` at position (dx, dy), blending colors with `mode`.
+    pub fn composite_blend(&mut self, src: &FrameBuffer, dx: i32, dy: i32, mode: BlendMode) {
         if self.format != PixelFormat::Rgba8 || src.format != PixelFormat::Rgba8 {
             // Fallback or ignore for unsupported formats in this fast path
             return;
@@ -194,38 +218,7 @@ impl FrameBuffer {
 
             // 4 bytes per pixel loop (auto-vectorizes well)
             for (s, d) in src_slice.chunks_exact(4).zip(dst_slice.chunks_exact_mut(4)) {
-                let sa = s[3] as u32;
-                if sa == 0 {
-                    continue;
-                }
-                if sa == 255 {
-                    d.copy_from_slice(s);
-                    continue;
-                }
-
-                let da = d[3] as u32;
-                let inv_sa = 255 - sa;
-                let out_a = sa + ((da * inv_sa) / 255);
-
-                if out_a == 0 {
-                    continue;
-                }
-
-                let s_r = s[0] as u32;
-                let s_g = s[1] as u32;
-                let s_b = s[2] as u32;
-                let d_r = d[0] as u32;
-                let d_g = d[1] as u32;
-                let d_b = d[2] as u32;
-
-                let out_r = (s_r * sa * 255 + d_r * da * inv_sa) / (out_a * 255);
-                let out_g = (s_g * sa * 255 + d_g * da * inv_sa) / (out_a * 255);
-                let out_b = (s_b * sa * 255 + d_b * da * inv_sa) / (out_a * 255);
-
-                d[0] = out_r as u8;
-                d[1] = out_g as u8;
-                d[2] = out_b as u8;
-                d[3] = out_a as u8;
+                composite_pixel(mode, s, d);
             }
         }
     }
@@ -238,6 +231,16 @@ impl FrameBuffer {
     /// This is a CPU fallback intended for 2.5D transforms (perspective/tilt). It uses inverse
     /// mapping + bilinear sampling.
     pub fn composite_over_projected(&mut self, src: &FrameBuffer, dst_corners: [[f64; 2]; 4]) {
+        self.composite_blend_projected(src, dst_corners, BlendMode::Normal);
+    }
+
+    /// Like [`FrameBuffer::composite_over_projected`], blending colors with `mode`.
+    pub fn composite_blend_projected(
+        &mut self,
+        src: &FrameBuffer,
+        dst_corners: [[f64; 2]; 4],
+        mode: BlendMode,
+    ) {
         if self.format != PixelFormat::Rgba8 || src.format != PixelFormat::Rgba8 {
             return;
         }
@@ -302,51 +305,57 @@ impl FrameBuffer {
                 }
 
                 let s = sample_bilinear_rgba8(src, sx, sy);
-                let sa = s[3] as u32;
-                if sa == 0 {
-                    continue;
-                }
-
                 let dst_idx = row_off + (x as usize) * 4;
-                let d = [
-                    self.data[dst_idx],
-                    self.data[dst_idx + 1],
-                    self.data[dst_idx + 2],
-                    self.data[dst_idx + 3],
-                ];
-
-                if sa == 255 {
-                    self.data[dst_idx..dst_idx + 4].copy_from_slice(&s);
-                    continue;
-                }
-
-                let da = d[3] as u32;
-                let inv_sa = 255 - sa;
-                let out_a = sa + ((da * inv_sa) / 255);
-                if out_a == 0 {
-                    continue;
-                }
-
-                let s_r = s[0] as u32;
-                let s_g = s[1] as u32;
-                let s_b = s[2] as u32;
-                let d_r = d[0] as u32;
-                let d_g = d[1] as u32;
-                let d_b = d[2] as u32;
-
-                let out_r = (s_r * sa * 255 + d_r * da * inv_sa) / (out_a * 255);
-                let out_g = (s_g * sa * 255 + d_g * da * inv_sa) / (out_a * 255);
-                let out_b = (s_b * sa * 255 + d_b * da * inv_sa) / (out_a * 255);
-
-                self.data[dst_idx] = out_r as u8;
-                self.data[dst_idx + 1] = out_g as u8;
-                self.data[dst_idx + 2] = out_b as u8;
-                self.data[dst_idx + 3] = out_a as u8;
+                composite_pixel(mode, &s, &mut self.data[dst_idx..dst_idx + 4]);
             }
         }
     }
 }
 
+/// Composite one straight-alpha pixel `s` onto `d`, blending colors with `mode`.
+#[inline]
+fn composite_pixel(mode: BlendMode, s: &[u8], d: &mut [u8]) {
+    if mode != BlendMode::Normal {
+        let out =
+            crate::blend::blend_pixel(mode, [s[0], s[1], s[2], s[3]], [d[0], d[1], d[2], d[3]]);
+        d.copy_from_slice(&out);
+        return;
+    }
+
+    let sa = s[3] as u32;
+    if sa == 0 {
+        return;
+    }
+    if sa == 255 {
+        d.copy_from_slice(s);
+        return;
+    }
+
+    let da = d[3] as u32;
+    let inv_sa = 255 - sa;
+    let out_a = sa + ((da * inv_sa) / 255);
+
+    if out_a == 0 {
+        return;
+    }
+
+    let s_r = s[0] as u32;
+    let s_g = s[1] as u32;
+    let s_b = s[2] as u32;
+    let d_r = d[0] as u32;
+    let d_g = d[1] as u32;
+    let d_b = d[2] as u32;
+
+    let out_r = (s_r * sa * 255 + d_r * da * inv_sa) / (out_a * 255);
+    let out_g = (s_g * sa * 255 + d_g * da * inv_sa) / (out_a * 255);
+    let out_b = (s_b * sa * 255 + d_b * da * inv_sa) / (out_a * 255);
+
+    d[0] = out_r as u8;
+    d[1] = out_g as u8;
+    d[2] = out_b as u8;
+    d[3] = out_a as u8;
+}
+
 fn sample_bilinear_rgba8(src: &FrameBuffer, x: f64, y: f64) -> [u8; 4] {
     let w = src.width as i32;
     let h = src.height as i32;
@@ -535,11 +544,51 @@ impl std::fmt::Display for Frame {
     }
 }
 
+/// A consumer of rendered frames, fed one frame at a time in presentation order.
+///
+/// Encoders implement this so a render can be streamed straight into the
+/// output file without holding the whole video in memory.
+pub trait FrameSink {
+    /// Consume the next frame in order.
+    fn write_frame(&mut self, frame: FrameBuffer) -> crate::VidraResult<()>;
+
+    /// Flush and finalize the output once every frame has been written.
+    fn finish(&mut self) -> crate::VidraResult<()>;
+}
+
+/// Collecting sink — keeps every frame in memory.
+impl FrameSink for Vec<FrameBuffer> {
+    fn write_frame(&mut self, frame: FrameBuffer) -> crate::VidraResult<()> {
+        self.push(frame);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> crate::VidraResult<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::Color;
 
+    #[test]
+    fn test_vec_frame_sink_collects_in_order() {
+        let mut sink: Vec<FrameBuffer> = Vec::new();
+        for i in 0..3u8 {
+            sink.write_frame(FrameBuffer::solid(
+                1,
+                1,
+                &Color::rgb(i as f32 / 255.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        }
+        sink.finish().unwrap();
+        assert_eq!(sink.len(), 3);
+        assert_eq!(sink[2].get_pixel(0, 0), Some([2, 0, 0, 255]));
+    }
+
     #[test]
     fn test_frame_buffer_new() {
         let fb = FrameBuffer::new(1920, 1080, PixelFormat::Rgba8);
@@ -619,6 +668,22 @@ mod tests {
         assert_eq!(dst.get_pixel(2, 3), Some([10, 20, 30, 255]));
     }
 
+    #[test]
+    fn test_composite_blend_blit_and_projected_agree() {
+        let backdrop = FrameBuffer::solid(4, 4, &Color::rgb(0.5, 0.5, 0.5));
+        let src = FrameBuffer::solid(2, 2, &Color::rgb(1.0, 0.0, 0.5));
+
+        let mut blit = backdrop.clone();
+        blit.composite_blend(&src, 1, 1, BlendMode::Multiply);
+        assert_eq!(blit.get_pixel(1, 1), Some([127, 0, 63, 255]));
+        assert_eq!(blit.get_pixel(0, 0), Some([127, 127, 127, 255]));
+
+        let mut projected = backdrop;
+        let corners = [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]];
+        projected.composite_blend_projected(&src, corners, BlendMode::Multiply);
+        assert_eq!(projected.data, blit.data);
+    }
+
     #[test]
     fn test_frame_to_timestamp() {
         let frame = Frame::new(30);
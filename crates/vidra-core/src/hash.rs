@@ -36,36 +36,59 @@ impl std::fmt::Display for ContentHash {
     }
 }
 
+/// Incremental hasher for content hashes built from several parts,
+/// such as render cache keys.
+#[derive(Debug, Clone, Default)]
+pub struct ContentHasher {
+    hasher: Sha256,
+}
+
+impl ContentHasher {
+    /// Create an empty hasher.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed raw bytes into the hash.
+    pub fn update(&mut self, bytes: impl AsRef<[u8]>) {
+        self.hasher.update(bytes);
+    }
+
+    /// Feed a frame buffer (dimensions, format and pixel data) into the hash.
+    pub fn update_frame(&mut self, frame: &FrameBuffer) {
+        // Include dimensions and format in the hash so different-sized
+        // buffers with identical pixel data produce different hashes.
+        self.hasher.update(frame.width.to_le_bytes());
+        self.hasher.update(frame.height.to_le_bytes());
+        self.hasher.update([frame.format as u8]);
+        self.hasher.update(&frame.data);
+    }
+
+    /// Finish hashing and return the digest.
+    pub fn finish(self) -> ContentHash {
+        let result = self.hasher.finalize();
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&result);
+        ContentHash::from_bytes(bytes)
+    }
+}
+
 /// Compute the content hash of a single frame buffer.
 pub fn hash_frame(frame: &FrameBuffer) -> ContentHash {
-    let mut hasher = Sha256::new();
-    // Include dimensions and format in the hash so different-sized
-    // buffers with identical pixel data produce different hashes.
-    hasher.update(frame.width.to_le_bytes());
-    hasher.update(frame.height.to_le_bytes());
-    hasher.update(&[frame.format as u8]);
-    hasher.update(&frame.data);
-    let result = hasher.finalize();
-    let mut bytes = [0u8; 32];
-    bytes.copy_from_slice(&result);
-    ContentHash::from_bytes(bytes)
+    let mut hasher = ContentHasher::new();
+    hasher.update_frame(frame);
+    hasher.finish()
 }
 
 /// Compute the content hash of a sequence of frames (entire render output).
 pub fn hash_frames(frames: &[FrameBuffer]) -> ContentHash {
-    let mut hasher = Sha256::new();
+    let mut hasher = ContentHasher::new();
     // Include frame count
     hasher.update((frames.len() as u64).to_le_bytes());
     for frame in frames {
-        hasher.update(frame.width.to_le_bytes());
-        hasher.update(frame.height.to_le_bytes());
-        hasher.update(&[frame.format as u8]);
-        hasher.update(&frame.data);
-    }
-    let result = hasher.finalize();
-    let mut bytes = [0u8; 32];
-    bytes.copy_from_slice(&result);
-    ContentHash::from_bytes(bytes)
+        hasher.update_frame(frame);
+    }
+    hasher.finish()
 }
 
 #[cfg(test)]
@@ -121,4 +144,18 @@ mod tests {
         let hash = hash_frame(&frame);
         assert_eq!(format!("{}", hash), hash.to_hex());
     }
+
+    #[test]
+    fn test_content_hasher_matches_hash_frame() {
+        let frame = FrameBuffer::solid(3, 2, &Color::GREEN);
+        let mut hasher = ContentHasher::new();
+        hasher.update_frame(&frame);
+        assert_eq!(hasher.finish(), hash_frame(&frame));
+
+        let mut a = ContentHasher::new();
+        a.update(b"layer");
+        let mut b = a.clone();
+        b.update(b"!");
+        assert_ne!(a.finish(), b.finish());
+    }
 }
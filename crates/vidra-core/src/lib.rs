@@ -4,12 +4,15 @@
 //! This crate contains foundational types shared across all Vidra crates:
 //! frames, colors, transforms, durations, easing functions, and error types.
 
+pub mod blend;
 pub mod color;
 pub mod config;
 pub mod error;
 pub mod frame;
 pub mod hash;
 pub mod math;
+pub mod paint;
+pub mod path;
 pub mod plugin;
 pub mod time;
 pub mod types;
@@ -17,9 +20,10 @@ pub mod vfx;
 
 pub use config::*;
 
-pub use color::Color;
+pub use color::{Color, ColorSpace};
 pub use error::{VidraError, VidraResult};
-pub use frame::{Frame, FrameBuffer, PixelFormat};
-pub use math::{Point2D, Size2D, Transform2D};
+pub use frame::{Frame, FrameBuffer, FrameSink, PixelFormat};
+pub use math::{Mat4, Point2D, Size2D, Transform2D};
+pub use paint::Paint;
 pub use time::{Duration, Timestamp};
-pub use types::{BlendMode, LayerEffect, LayerType};
+pub use types::{BlendMode, LayerEffect, LayerType, MotionBlur, StrokePosition};
@@ -114,79 +114,167 @@ impl Transform2D {
         }
     }
 
+    /// Matrix mapping a layer's local pixels (`width`×`height`, top-left at the origin)
+    /// into its parent's space: rotation about the anchor, then translation to `position`
+    /// and `translate_z`, then perspective around `position`.
+    ///
+    /// Scale is not included; rasterizers apply it to the layer's pixels.
+    pub fn matrix(&self, width: f64, height: f64) -> Mat4 {
+        let pivot = Mat4::translation(-self.anchor.x * width, -self.anchor.y * height, 0.0);
+        Mat4::translation(self.position.x, self.position.y, 0.0)
+            * Mat4::perspective(self.perspective)
+            * Mat4::translation(0.0, 0.0, self.translate_z)
+            * Mat4::rotation_y(self.rotate_y)
+            * Mat4::rotation_x(self.rotate_x)
+            * Mat4::rotation_z(self.rotation)
+            * pivot
+    }
+
     /// Project the 4 corners of a layer's local rectangle into screen space.
     ///
     /// - `width`/`height` are the layer's pixel dimensions (after any CPU scaling).
     /// - The layer's anchor defines the pivot within that rectangle.
     /// - The returned points are ordered: top-left, top-right, bottom-right, bottom-left.
     pub fn project_corners(&self, width: f64, height: f64) -> [[f64; 2]; 4] {
-        // Local corners in pixels, with pivot at anchor.
-        let ax = self.anchor.x;
-        let ay = self.anchor.y;
-        let left = -ax * width;
-        let right = (1.0 - ax) * width;
-        let top = -ay * height;
-        let bottom = (1.0 - ay) * height;
-
-        let corners = [
-            [left, top, 0.0],
-            [right, top, 0.0],
-            [right, bottom, 0.0],
-            [left, bottom, 0.0],
-        ];
-
-        let rz = self.rotation.to_radians();
-        let rx = self.rotate_x.to_radians();
-        let ry = self.rotate_y.to_radians();
-
-        let (sz, cz) = rz.sin_cos();
-        let (sx, cx) = rx.sin_cos();
-        let (sy, cy) = ry.sin_cos();
-
-        let persp = self.perspective;
-
-        let mut out = [[0.0; 2]; 4];
-        for (i, c) in corners.iter().enumerate() {
-            let mut x = c[0];
-            let mut y = c[1];
-            let mut z = c[2];
-
-            // Rotate Z (2D rotation).
-            let xz = x * cz - y * sz;
-            let yz = x * sz + y * cz;
-            x = xz;
-            y = yz;
-
-            // Rotate X.
-            let yx = y * cx - z * sx;
-            let zx = y * sx + z * cx;
-            y = yx;
-            z = zx;
-
-            // Rotate Y.
-            let xy = x * cy + z * sy;
-            let zy = -x * sy + z * cy;
-            x = xy;
-            z = zy;
-
-            // Translate.
-            x += self.position.x;
-            y += self.position.y;
-            z += self.translate_z;
-
-            // Perspective projection.
-            if persp > 0.0 {
-                let denom = (persp + z).max(1e-6);
-                let f = persp / denom;
-                x = self.position.x + (x - self.position.x) * f;
-                y = self.position.y + (y - self.position.y) * f;
-            }
+        self.matrix(width, height).project_rect(width, height)
+    }
+}
+
+/// A 4×4 homogeneous transform acting on column vectors `(x, y, z, 1)`.
+///
+/// Composes 2D and 2.5D layer transforms: `a * b` applies `b` first. Points are
+/// projected back to 2D by the perspective divide.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Mat4 {
+    /// Row-major elements.
+    pub m: [[f64; 4]; 4],
+}
 
-            out[i] = [x, y];
+impl Mat4 {
+    pub fn identity() -> Self {
+        Self {
+            m: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
         }
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Self {
+        let mut out = Self::identity();
+        out.m[0][3] = x;
+        out.m[1][3] = y;
+        out.m[2][3] = z;
+        out
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Self {
+        let mut out = Self::identity();
+        out.m[0][0] = x;
+        out.m[1][1] = y;
+        out.m[2][2] = z;
+        out
+    }
+
+    /// Rotation about the X axis, in degrees.
+    pub fn rotation_x(degrees: f64) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        let mut out = Self::identity();
+        out.m[1][1] = c;
+        out.m[1][2] = -s;
+        out.m[2][1] = s;
+        out.m[2][2] = c;
+        out
+    }
+
+    /// Rotation about the Y axis, in degrees.
+    pub fn rotation_y(degrees: f64) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        let mut out = Self::identity();
+        out.m[0][0] = c;
+        out.m[0][2] = s;
+        out.m[2][0] = -s;
+        out.m[2][2] = c;
+        out
+    }
+
+    /// Rotation in the XY plane (2D rotation), in degrees.
+    pub fn rotation_z(degrees: f64) -> Self {
+        let (s, c) = degrees.to_radians().sin_cos();
+        let mut out = Self::identity();
+        out.m[0][0] = c;
+        out.m[0][1] = -s;
+        out.m[1][0] = s;
+        out.m[1][1] = c;
+        out
+    }
 
+    /// Perspective projection onto the z = 0 plane, seen from `distance` pixels in front
+    /// of the origin. A distance <= 0 disables perspective (identity).
+    pub fn perspective(distance: f64) -> Self {
+        let mut out = Self::identity();
+        if distance > 0.0 {
+            out.m[3][2] = 1.0 / distance;
+        }
         out
     }
+
+    /// Transform the point `(x, y, 0)` and project it to 2D.
+    pub fn transform_point(&self, x: f64, y: f64) -> [f64; 2] {
+        let m = &self.m;
+        let px = m[0][0] * x + m[0][1] * y + m[0][3];
+        let py = m[1][0] * x + m[1][1] * y + m[1][3];
+        let w = (m[3][0] * x + m[3][1] * y + m[3][3]).max(1e-6);
+        [px / w, py / w]
+    }
+
+    /// The offset of a whole-pixel 2D translation: `Some` when the matrix moves the
+    /// z = 0 plane by integer amounts without scaling, rotating or projecting it.
+    pub fn pixel_offset(&self) -> Option<(i32, i32)> {
+        let m = &self.m;
+        let linear = m[0][0] == 1.0
+            && m[0][1] == 0.0
+            && m[1][0] == 0.0
+            && m[1][1] == 1.0
+            && m[3][0] == 0.0
+            && m[3][1] == 0.0
+            && m[3][3] == 1.0;
+        let (x, y) = (m[0][3], m[1][3]);
+        (linear && x.fract() == 0.0 && y.fract() == 0.0).then_some((x as i32, y as i32))
+    }
+
+    /// Project the corners of the rectangle `(0, 0)`–`(width, height)`, ordered
+    /// top-left, top-right, bottom-right, bottom-left.
+    pub fn project_rect(&self, width: f64, height: f64) -> [[f64; 2]; 4] {
+        [
+            self.transform_point(0.0, 0.0),
+            self.transform_point(width, 0.0),
+            self.transform_point(width, height),
+            self.transform_point(0.0, height),
+        ]
+    }
+}
+
+impl std::ops::Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4 { m }
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
 }
 
 impl Default for Transform2D {
@@ -233,4 +321,35 @@ mod tests {
         assert!((mid.position.x - 50.0).abs() < 0.001);
         assert!((mid.opacity - 0.5).abs() < 0.001);
     }
+
+    #[test]
+    fn test_project_corners_rotate_about_anchor() {
+        let mut t = Transform2D::identity();
+        t.position = Point2D::new(100.0, 50.0);
+        t.rotation = 90.0;
+        let corners = t.project_corners(40.0, 20.0);
+        let expected = [[110.0, 30.0], [110.0, 70.0], [90.0, 70.0], [90.0, 30.0]];
+        for (c, e) in corners.iter().zip(expected) {
+            assert!((c[0] - e[0]).abs() < 1e-9 && (c[1] - e[1]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_mat4_composition_and_perspective() {
+        // Scale, then translate: `a * b` applies `b` first.
+        let m = Mat4::translation(10.0, 20.0, 0.0) * Mat4::scale(2.0, 3.0, 1.0);
+        assert_eq!(m.transform_point(1.0, 1.0), [12.0, 23.0]);
+
+        // A point pushed away from the viewer shrinks toward the origin.
+        let m = Mat4::perspective(100.0) * Mat4::translation(0.0, 0.0, 100.0);
+        assert_eq!(m.transform_point(50.0, -20.0), [25.0, -10.0]);
+        assert_eq!(Mat4::perspective(0.0), Mat4::identity());
+
+        assert_eq!(
+            Mat4::translation(3.0, -4.0, 0.0).pixel_offset(),
+            Some((3, -4))
+        );
+        assert_eq!(Mat4::translation(0.5, 0.0, 0.0).pixel_offset(), None);
+        assert_eq!(Mat4::rotation_z(90.0).pixel_offset(), None);
+    }
 }
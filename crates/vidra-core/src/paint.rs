@@ -0,0 +1,372 @@
+//! Paints: what a solid or a shape is filled with, a flat color or a gradient.
+//!
+//! Gradient geometry is relative to the painted box, so a gradient keeps its look
+//! when the layer is resized.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::frame::{FrameBuffer, PixelFormat};
+use crate::{Color, Point2D, Size2D};
+
+/// A fill or stroke paint.
+///
+/// Serialized as a plain [`Color`] when solid, so IR written before gradients
+/// existed still reads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Paint {
+    Solid(Color),
+    Gradient(Gradient),
+}
+
+impl From<Color> for Paint {
+    fn from(color: Color) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+impl Default for Paint {
+    fn default() -> Self {
+        Paint::Solid(Color::BLACK)
+    }
+}
+
+impl Paint {
+    /// The flat color, if this is a solid paint.
+    pub fn as_solid(&self) -> Option<Color> {
+        match self {
+            Paint::Solid(color) => Some(*color),
+            Paint::Gradient(_) => None,
+        }
+    }
+
+    pub fn solid_mut(&mut self) -> Option<&mut Color> {
+        match self {
+            Paint::Solid(color) => Some(color),
+            Paint::Gradient(_) => None,
+        }
+    }
+
+    /// A sampler for this paint over a `width`×`height` box.
+    pub fn shader(&self, width: u32, height: u32) -> Shader {
+        let mut paint = self.clone();
+        if let Paint::Gradient(gradient) = &mut paint {
+            gradient.stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+        }
+        Shader {
+            paint,
+            width: width as f64,
+            height: height as f64,
+        }
+    }
+
+    /// A `width`×`height` buffer filled with the paint, its alpha scaled by `opacity`.
+    pub fn render(&self, width: u32, height: u32, opacity: f64) -> FrameBuffer {
+        if let Paint::Solid(color) = self {
+            let mut c = *color;
+            c.a *= opacity as f32;
+            return FrameBuffer::solid(width, height, &c);
+        }
+        let shader = self.shader(width, height);
+        let mut buf = FrameBuffer::new(width, height, PixelFormat::Rgba8);
+        for y in 0..height {
+            for x in 0..width {
+                let mut c = shader.color_at(x, y);
+                c.a *= opacity as f32;
+                buf.set_pixel(x, y, c.to_rgba8());
+            }
+        }
+        buf
+    }
+}
+
+impl fmt::Display for Paint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Paint::Solid(color) => write!(f, "{}", color),
+            Paint::Gradient(gradient) => {
+                let kind = match gradient.kind {
+                    GradientKind::Linear { .. } => "linear",
+                    GradientKind::Radial { .. } => "radial",
+                    GradientKind::Conic { .. } => "conic",
+                };
+                write!(f, "{} gradient", kind)?;
+                for stop in &gradient.stops {
+                    write!(f, " {} {}", stop.color, stop.offset)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A color at a position along a gradient, from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorStop {
+    pub offset: f64,
+    pub color: Color,
+}
+
+impl ColorStop {
+    pub fn new(offset: f64, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// The geometry of a gradient. Points are fractions of the painted box and angles
+/// are in degrees clockwise from "up", as in CSS.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GradientKind {
+    /// Runs through the box's center in the direction of `angle`, spanning the box so
+    /// that the first and last stops fall on opposite corners.
+    Linear { angle: f64 },
+    /// Circles around `center`; `radius` is a fraction of the distance from the center
+    /// to the farthest corner.
+    Radial { center: Point2D, radius: f64 },
+    /// Sweeps clockwise around `center`, starting at `angle`.
+    Conic { angle: f64, center: Point2D },
+}
+
+/// Colors interpolated between stops. Stops are used in order of offset; before the
+/// first and after the last, the end colors extend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<ColorStop>,
+}
+
+impl Gradient {
+    pub fn new(kind: GradientKind, stops: Vec<ColorStop>) -> Self {
+        Self { kind, stops }
+    }
+
+    pub fn linear(angle: f64, stops: Vec<ColorStop>) -> Self {
+        Self::new(GradientKind::Linear { angle }, stops)
+    }
+
+    pub fn radial(center: Point2D, radius: f64, stops: Vec<ColorStop>) -> Self {
+        Self::new(GradientKind::Radial { center, radius }, stops)
+    }
+
+    pub fn conic(angle: f64, center: Point2D, stops: Vec<ColorStop>) -> Self {
+        Self::new(GradientKind::Conic { angle, center }, stops)
+    }
+
+    /// A linear gradient over a box of `size` whose first stop falls on `start` and last
+    /// on `end`, both in box pixels. `None` when the two points coincide.
+    pub fn linear_between(
+        start: Point2D,
+        end: Point2D,
+        size: Size2D,
+        stops: &[ColorStop],
+    ) -> Option<Self> {
+        let (dx, dy) = (end.x - start.x, end.y - start.y);
+        if dx.hypot(dy) < 1e-9 {
+            return None;
+        }
+        // Place the stops where the box's gradient at the same angle passes the two
+        // end points.
+        let angle = dx.atan2(-dy).to_degrees();
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (w, h) = (size.width, size.height);
+        let length = (w * sin).abs() + (h * cos).abs();
+        let t = |p: Point2D| ((p.x - w / 2.0) * sin - (p.y - h / 2.0) * cos) / length + 0.5;
+        let (t0, t1) = (t(start), t(end));
+        let stops = stops
+            .iter()
+            .map(|s| ColorStop::new(t0 + s.offset * (t1 - t0), s.color))
+            .collect();
+        Some(Self::linear(angle, stops))
+    }
+
+    /// A radial gradient over a box of `size` whose last stop is `radius` pixels from
+    /// `center`, in box pixels. `None` when the circle or the box is empty.
+    pub fn radial_around(
+        center: Point2D,
+        radius: f64,
+        size: Size2D,
+        stops: &[ColorStop],
+    ) -> Option<Self> {
+        let (w, h) = (size.width, size.height);
+        let corner = center.x.max(w - center.x).hypot(center.y.max(h - center.y));
+        if radius <= 0.0 || corner <= 0.0 || w <= 0.0 || h <= 0.0 {
+            return None;
+        }
+        Some(Self::radial(
+            Point2D::new(center.x / w, center.y / h),
+            radius / corner,
+            stops.to_vec(),
+        ))
+    }
+
+    /// Set the angle of a linear or conic gradient. Radial gradients have none.
+    pub fn set_angle(&mut self, value: f64) {
+        match &mut self.kind {
+            GradientKind::Linear { angle } | GradientKind::Conic { angle, .. } => *angle = value,
+            GradientKind::Radial { .. } => {}
+        }
+    }
+
+    /// Position along the gradient of point `(x, y)` in a `w`×`h` box.
+    fn position(&self, x: f64, y: f64, w: f64, h: f64) -> f64 {
+        match self.kind {
+            GradientKind::Linear { angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let length = (w * sin).abs() + (h * cos).abs();
+                if length <= 0.0 {
+                    return 0.0;
+                }
+                ((x - w / 2.0) * sin - (y - h / 2.0) * cos) / length + 0.5
+            }
+            GradientKind::Radial { center, radius } => {
+                let (cx, cy) = (center.x * w, center.y * h);
+                let corner = cx.max(w - cx).hypot(cy.max(h - cy));
+                let r = radius * corner;
+                if r <= 0.0 {
+                    return 1.0;
+                }
+                (x - cx).hypot(y - cy) / r
+            }
+            GradientKind::Conic { angle, center } => {
+                let (dx, dy) = (x - center.x * w, y - center.y * h);
+                (dx.atan2(-dy).to_degrees() - angle).rem_euclid(360.0) / 360.0
+            }
+        }
+    }
+
+    /// The color at position `t`, with stops already sorted by offset.
+    fn color_at_position(&self, t: f64) -> Color {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Color::TRANSPARENT;
+        };
+        if t <= first.offset {
+            return first.color;
+        }
+        for pair in self.stops.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if t <= b.offset {
+                let span = b.offset - a.offset;
+                if span <= 0.0 {
+                    return b.color;
+                }
+                return a.color.lerp(&b.color, ((t - a.offset) / span) as f32);
+            }
+        }
+        last.color
+    }
+}
+
+/// A paint prepared for sampling many pixels of one box.
+#[derive(Debug, Clone)]
+pub struct Shader {
+    paint: Paint,
+    width: f64,
+    height: f64,
+}
+
+impl Shader {
+    /// The paint's color at the center of pixel `(x, y)`.
+    pub fn color_at(&self, x: u32, y: u32) -> Color {
+        match &self.paint {
+            Paint::Solid(color) => *color,
+            Paint::Gradient(gradient) => {
+                let t = gradient.position(x as f64 + 0.5, y as f64 + 0.5, self.width, self.height);
+                gradient.color_at_position(t)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_stops() -> Vec<ColorStop> {
+        vec![
+            ColorStop::new(0.0, Color::BLACK),
+            ColorStop::new(1.0, Color::WHITE),
+        ]
+    }
+
+    #[test]
+    fn test_linear_gradient_direction() {
+        // 90° runs left to right; 180° runs top to bottom.
+        let shader = Paint::Gradient(Gradient::linear(90.0, two_stops())).shader(10, 4);
+        assert_eq!(shader.color_at(0, 0).to_rgba8(), [12, 12, 12, 255]);
+        assert_eq!(shader.color_at(9, 3).to_rgba8(), [242, 242, 242, 255]);
+        let shader = Paint::Gradient(Gradient::linear(180.0, two_stops())).shader(4, 10);
+        assert!(shader.color_at(0, 0).r < 0.1 && shader.color_at(0, 9).r > 0.9);
+        // Diagonal gradients reach the end colors at the corners.
+        let shader = Paint::Gradient(Gradient::linear(45.0, two_stops())).shader(100, 100);
+        assert!(shader.color_at(0, 99).r < 0.02 && shader.color_at(99, 0).r > 0.98);
+    }
+
+    #[test]
+    fn test_stops_are_sorted_and_clamped() {
+        let red = Color::RED;
+        let stops = vec![
+            ColorStop::new(0.75, Color::WHITE),
+            ColorStop::new(0.25, red),
+            ColorStop::new(0.5, Color::BLACK),
+        ];
+        let shader = Paint::Gradient(Gradient::linear(90.0, stops)).shader(100, 1);
+        assert_eq!(shader.color_at(5, 0), red);
+        assert_eq!(shader.color_at(95, 0), Color::WHITE);
+        let mid = shader.color_at(37, 0);
+        assert!(mid.r > 0.4 && mid.r < 0.6 && mid.g == 0.0);
+    }
+
+    #[test]
+    fn test_radial_and_conic_positions() {
+        let center = Point2D::new(0.5, 0.5);
+        let radial = Gradient::radial(center, 1.0, two_stops());
+        assert!(radial.position(50.0, 50.0, 100.0, 100.0).abs() < 1e-9);
+        assert!((radial.position(0.0, 0.0, 100.0, 100.0) - 1.0).abs() < 1e-9);
+
+        let conic = Gradient::conic(0.0, center, two_stops());
+        assert!(conic.position(50.0, 0.0, 100.0, 100.0).abs() < 1e-9);
+        assert!((conic.position(100.0, 50.0, 100.0, 100.0) - 0.25).abs() < 1e-9);
+        assert!((conic.position(0.0, 50.0, 100.0, 100.0) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_gradients_placed_by_points() {
+        let size = Size2D::new(100.0, 50.0);
+        let linear = Gradient::linear_between(
+            Point2D::new(25.0, 10.0),
+            Point2D::new(75.0, 10.0),
+            size,
+            &two_stops(),
+        )
+        .unwrap();
+        // The end points sit where the stops were moved to.
+        let (first, last) = (linear.stops[0].offset, linear.stops[1].offset);
+        assert!((linear.position(25.0, 40.0, 100.0, 50.0) - first).abs() < 1e-9);
+        assert!((linear.position(75.0, 0.0, 100.0, 50.0) - last).abs() < 1e-9);
+        let radial =
+            Gradient::radial_around(Point2D::new(20.0, 25.0), 10.0, size, &two_stops()).unwrap();
+        assert!((radial.position(30.0, 25.0, 100.0, 50.0) - 1.0).abs() < 1e-9);
+        assert!(Gradient::linear_between(
+            Point2D::new(1.0, 1.0),
+            Point2D::new(1.0, 1.0),
+            size,
+            &[]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_paint_serde_keeps_plain_colors() {
+        let json = serde_json::to_string(&Paint::Solid(Color::RED)).unwrap();
+        assert_eq!(json, r#"{"r":1.0,"g":0.0,"b":0.0,"a":1.0}"#);
+        let gradient = Paint::Gradient(Gradient::linear(90.0, two_stops()));
+        let json = serde_json::to_string(&gradient).unwrap();
+        assert!(
+            json.starts_with(r#"{"kind":{"Linear":{"angle":90.0}}"#),
+            "{}",
+            json
+        );
+        assert_eq!(serde_json::from_str::<Paint>(&json).unwrap(), gradient);
+    }
+}
@@ -0,0 +1,1179 @@
+//! Vector paths: SVG path data, flattening, trim paths, dashes and stroke outlines.
+//!
+//! Geometry is in the shape's own pixels with y pointing down. Curves are flattened
+//! into polylines, and strokes are turned into polygons, so a renderer only needs to
+//! fill polygons (see `vidra_eval::shape`).
+
+use serde::{Deserialize, Serialize};
+
+use crate::math::{Point2D, Size2D};
+
+/// Maximum distance in pixels between a curve and its flattened polyline.
+pub const FLATTEN_TOLERANCE: f64 = 0.1;
+
+/// One drawing command of a path, in absolute coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PathSegment {
+    /// Start a new subpath.
+    MoveTo {
+        x: f64,
+        y: f64,
+    },
+    LineTo {
+        x: f64,
+        y: f64,
+    },
+    /// Quadratic Bézier curve with control point `(x1, y1)`.
+    QuadTo {
+        x1: f64,
+        y1: f64,
+        x: f64,
+        y: f64,
+    },
+    /// Cubic Bézier curve with control points `(x1, y1)` and `(x2, y2)`.
+    CubicTo {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    /// Close the current subpath with a line back to its start.
+    Close,
+}
+
+/// The geometry of a path shape.
+///
+/// Serialized as a list of segments. It also deserializes from an SVG path data
+/// string (`"M0 0 L100 0 ..."`), which is parsed into segments.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "PathDataRepr", into = "Vec<PathSegment>")]
+pub struct PathData {
+    pub segments: Vec<PathSegment>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PathDataRepr {
+    Svg(String),
+    Segments(Vec<PathSegment>),
+}
+
+impl TryFrom<PathDataRepr> for PathData {
+    type Error = PathError;
+
+    fn try_from(repr: PathDataRepr) -> Result<Self, Self::Error> {
+        match repr {
+            PathDataRepr::Svg(data) => PathData::parse_svg(&data),
+            PathDataRepr::Segments(segments) => Ok(PathData::new(segments)),
+        }
+    }
+}
+
+impl From<PathData> for Vec<PathSegment> {
+    fn from(data: PathData) -> Self {
+        data.segments
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PathError {
+    #[error("invalid path data at offset {offset}: {message}")]
+    InvalidSvg { offset: usize, message: String },
+}
+
+impl PathData {
+    pub fn new(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+
+    /// Parse SVG path data (the `d` attribute): every command of SVG 1.1, relative
+    /// and absolute. Arcs are converted to cubic curves.
+    pub fn parse_svg(data: &str) -> Result<Self, PathError> {
+        SvgPathParser::new(data).parse()
+    }
+
+    /// Flatten into polylines, one per subpath, within `tolerance` pixels.
+    pub fn flatten(&self, tolerance: f64) -> Vec<Polyline> {
+        let mut lines = Vec::new();
+        let mut current = Polyline::default();
+        let mut start = Point2D::zero();
+        let mut pen = Point2D::zero();
+
+        let finish = |line: &mut Polyline, lines: &mut Vec<Polyline>| {
+            if !line.points.is_empty() {
+                lines.push(std::mem::take(line));
+            }
+        };
+
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::MoveTo { x, y } => {
+                    finish(&mut current, &mut lines);
+                    start = Point2D::new(x, y);
+                    pen = start;
+                    continue;
+                }
+                PathSegment::Close => {
+                    if !current.points.is_empty() {
+                        current.closed = true;
+                        if current.points.len() > 1 && current.points.last() == Some(&start) {
+                            current.points.pop();
+                        }
+                    }
+                    finish(&mut current, &mut lines);
+                    pen = start;
+                    continue;
+                }
+                _ => {}
+            }
+            if current.points.is_empty() {
+                current.points.push(pen);
+            }
+            match *segment {
+                PathSegment::LineTo { x, y } => {
+                    pen = Point2D::new(x, y);
+                    current.points.push(pen);
+                }
+                PathSegment::QuadTo { x1, y1, x, y } => {
+                    let (p0, p1, p2) = (pen, Point2D::new(x1, y1), Point2D::new(x, y));
+                    let dd = len(sub(add(p0, p2), scale(p1, 2.0)));
+                    let n = subdivisions(dd / 4.0, tolerance);
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let mt = 1.0 - t;
+                        current.points.push(add(
+                            add(scale(p0, mt * mt), scale(p1, 2.0 * mt * t)),
+                            scale(p2, t * t),
+                        ));
+                    }
+                    pen = p2;
+                }
+                PathSegment::CubicTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    let (p0, p1) = (pen, Point2D::new(x1, y1));
+                    let (p2, p3) = (Point2D::new(x2, y2), Point2D::new(x, y));
+                    let dd = len(sub(add(p0, p2), scale(p1, 2.0)))
+                        .max(len(sub(add(p1, p3), scale(p2, 2.0))));
+                    let n = subdivisions(dd * 0.75, tolerance);
+                    for i in 1..=n {
+                        let t = i as f64 / n as f64;
+                        let mt = 1.0 - t;
+                        current.points.push(add(
+                            add(scale(p0, mt * mt * mt), scale(p1, 3.0 * mt * mt * t)),
+                            add(scale(p2, 3.0 * mt * t * t), scale(p3, t * t * t)),
+                        ));
+                    }
+                    pen = p3;
+                }
+                PathSegment::MoveTo { .. } | PathSegment::Close => unreachable!(),
+            }
+        }
+        finish(&mut current, &mut lines);
+        lines
+    }
+}
+
+/// Number of line segments for a curve whose second differences reach `dd`
+/// (Wang's formula).
+fn subdivisions(dd: f64, tolerance: f64) -> usize {
+    ((dd / tolerance).sqrt().ceil() as usize).clamp(1, 1000)
+}
+
+/// A flattened subpath.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<Point2D>,
+    /// Whether the last point connects back to the first.
+    pub closed: bool,
+}
+
+impl Polyline {
+    pub fn open(points: Vec<Point2D>) -> Self {
+        Self {
+            points,
+            closed: false,
+        }
+    }
+
+    /// The points in drawing order, repeating the first one at the end if closed.
+    fn path_points(&self) -> Vec<Point2D> {
+        let mut points = self.points.clone();
+        if self.closed && points.len() > 1 {
+            points.push(points[0]);
+        }
+        points
+    }
+
+    /// Arc length, including the closing segment.
+    pub fn length(&self) -> f64 {
+        self.path_points()
+            .windows(2)
+            .map(|w| len(sub(w[1], w[0])))
+            .sum()
+    }
+}
+
+/// How the interior of a self-overlapping fill is decided.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FillRule {
+    /// Inside where the path winds around the point at least once.
+    #[default]
+    NonZero,
+    /// Inside where the path crosses an odd number of times.
+    EvenOdd,
+}
+
+/// Shape of the ends of open stroked subpaths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// Shape of the corners of stroked subpaths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// How a path's stroke is drawn. The stroke color and width are those of the layer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrokeStyle {
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// Miter joins longer than this many stroke widths are beveled.
+    pub miter_limit: f64,
+    /// Alternating dash and gap lengths in pixels. Empty for a solid stroke.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dash: Vec<f64>,
+    /// Distance into the dash pattern at which each subpath starts.
+    pub dash_offset: f64,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dash: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+}
+
+/// The part of a path that is drawn, as fractions of its total length.
+///
+/// `start` and `end` are in 0–1; `offset` shifts both along the path and wraps around,
+/// so animating it moves a fixed-length segment along the path.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Trim {
+    pub start: f64,
+    pub end: f64,
+    pub offset: f64,
+}
+
+impl Default for Trim {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            end: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+impl Trim {
+    pub fn is_full(&self) -> bool {
+        let (start, end) = self.range();
+        end - start >= 1.0
+    }
+
+    fn range(&self) -> (f64, f64) {
+        let start = self.start.clamp(0.0, 1.0);
+        let end = self.end.clamp(0.0, 1.0);
+        (start.min(end), start.max(end))
+    }
+
+    /// The trimmed part of `lines`, which are treated as one path. Subpaths that are
+    /// cut become open.
+    pub fn apply(&self, lines: Vec<Polyline>) -> Vec<Polyline> {
+        if self.is_full() {
+            return lines;
+        }
+        let (start, end) = self.range();
+        let total: f64 = lines.iter().map(Polyline::length).sum();
+        if end - start <= 0.0 || total <= 0.0 {
+            return Vec::new();
+        }
+        let offset = self.offset.rem_euclid(1.0);
+        let (from, to) = (start + offset, end + offset);
+        let ranges = if to <= 1.0 {
+            vec![(from, to)]
+        } else if from >= 1.0 {
+            vec![(from - 1.0, to - 1.0)]
+        } else {
+            // Wrapped past the end: draw the end of the path first so a closed
+            // path stays continuous in drawing order.
+            vec![(from, 1.0), (0.0, to - 1.0)]
+        };
+
+        let mut out = Vec::new();
+        for (from, to) in ranges {
+            let (from, to) = (from * total, to * total);
+            let mut base = 0.0;
+            for line in &lines {
+                let length = line.length();
+                let (a, b) = ((from - base).max(0.0), (to - base).min(length));
+                if b > a {
+                    if line.closed && a <= 0.0 && b >= length {
+                        out.push(line.clone());
+                    } else {
+                        out.push(Polyline::open(slice(&line.path_points(), a, b)));
+                    }
+                }
+                base += length;
+            }
+        }
+        out
+    }
+}
+
+/// The part of the polyline `points` between arc lengths `from` and `to`.
+fn slice(points: &[Point2D], from: f64, to: f64) -> Vec<Point2D> {
+    let mut out = Vec::new();
+    let mut base = 0.0;
+    for w in points.windows(2) {
+        let length = len(sub(w[1], w[0]));
+        let (a, b) = (base, base + length);
+        if b >= from && a <= to {
+            let at = |d: f64| {
+                if length > 0.0 {
+                    w[0].lerp(&w[1], ((d - a) / length).clamp(0.0, 1.0))
+                } else {
+                    w[0]
+                }
+            };
+            if out.is_empty() {
+                out.push(at(from.max(a)));
+            }
+            out.push(at(to.min(b)));
+        }
+        base = b;
+        if base > to {
+            break;
+        }
+    }
+    if out.is_empty() {
+        out.extend(points.first());
+    }
+    out
+}
+
+/// Split `lines` into dashes. An odd-length `pattern` is repeated to make it even,
+/// and the pattern restarts at `offset` on every subpath.
+pub fn dash(lines: &[Polyline], pattern: &[f64], offset: f64) -> Vec<Polyline> {
+    if pattern.iter().sum::<f64>() <= 0.0 || pattern.iter().any(|d| *d < 0.0) {
+        return lines.to_vec();
+    }
+    let pattern: Vec<f64> = if pattern.len() % 2 == 1 {
+        pattern.iter().chain(pattern).copied().collect()
+    } else {
+        pattern.to_vec()
+    };
+    let period: f64 = pattern.iter().sum();
+
+    let mut out = Vec::new();
+    for line in lines {
+        let points = line.path_points();
+        let mut index = 0;
+        let mut phase = offset.rem_euclid(period);
+        while phase >= pattern[index] {
+            phase -= pattern[index];
+            index = (index + 1) % pattern.len();
+        }
+        let mut remaining = pattern[index] - phase;
+        let mut piece: Vec<Point2D> = Vec::new();
+        if index.is_multiple_of(2) {
+            piece.extend(points.first());
+        }
+        for w in points.windows(2) {
+            let length = len(sub(w[1], w[0]));
+            let mut pos = 0.0;
+            while length - pos > remaining {
+                pos += remaining;
+                let p = w[0].lerp(&w[1], pos / length);
+                if index.is_multiple_of(2) {
+                    piece.push(p);
+                    out.push(Polyline::open(std::mem::take(&mut piece)));
+                } else {
+                    piece.push(p);
+                }
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+            remaining -= length - pos;
+            if index.is_multiple_of(2) {
+                piece.push(w[1]);
+            }
+        }
+        if index.is_multiple_of(2) && !piece.is_empty() {
+            out.push(Polyline::open(piece));
+        }
+    }
+    out
+}
+
+/// Polygons that together cover the stroke of `lines` at `width`. Each polygon winds
+/// the same way, so filling them with the non-zero rule gives their union.
+pub fn stroke_polygons(lines: &[Polyline], width: f64, style: &StrokeStyle) -> Vec<Vec<Point2D>> {
+    let hw = width / 2.0;
+    let mut polygons = Vec::new();
+    if hw <= 0.0 {
+        return polygons;
+    }
+    for line in lines {
+        let mut points = line.points.clone();
+        points.dedup_by(|a, b| len(sub(*a, *b)) < 1e-9);
+        let closed = line.closed && points.len() > 2;
+        if closed && len(sub(points[0], points[points.len() - 1])) < 1e-9 {
+            points.pop();
+        }
+
+        if points.len() == 1 {
+            // A zero-length subpath only shows its caps.
+            let p = points[0];
+            match style.cap {
+                LineCap::Butt => {}
+                LineCap::Round => polygons.push(circle(p, hw)),
+                LineCap::Square => polygons.push(vec![
+                    Point2D::new(p.x - hw, p.y - hw),
+                    Point2D::new(p.x + hw, p.y - hw),
+                    Point2D::new(p.x + hw, p.y + hw),
+                    Point2D::new(p.x - hw, p.y + hw),
+                ]),
+            }
+            continue;
+        }
+
+        let mut path = points.clone();
+        if closed {
+            path.push(points[0]);
+        }
+        for w in path.windows(2) {
+            let n = scale(normal(sub(w[1], w[0])), hw);
+            polygons.push(vec![add(w[0], n), add(w[1], n), sub(w[1], n), sub(w[0], n)]);
+        }
+
+        // Joins at every interior vertex, and at the seam of closed subpaths.
+        let count = points.len();
+        let vertices = if closed { 0..count } else { 1..count - 1 };
+        for i in vertices {
+            let prev = points[(i + count - 1) % count];
+            let next = points[(i + 1) % count];
+            polygons.extend(join(prev, points[i], next, hw, style));
+        }
+
+        if !closed {
+            let d0 = unit(sub(points[1], points[0]));
+            let d1 = unit(sub(points[count - 1], points[count - 2]));
+            polygons.extend(cap(points[0], scale(d0, -1.0), hw, style.cap));
+            polygons.extend(cap(points[count - 1], d1, hw, style.cap));
+        }
+    }
+
+    for polygon in &mut polygons {
+        if signed_area(polygon) < 0.0 {
+            polygon.reverse();
+        }
+    }
+    polygons
+}
+
+/// The polygon filling the outer corner at `v` between `prev → v` and `v → next`.
+fn join(
+    prev: Point2D,
+    v: Point2D,
+    next: Point2D,
+    hw: f64,
+    style: &StrokeStyle,
+) -> Option<Vec<Point2D>> {
+    let (d0, d1) = (unit(sub(v, prev)), unit(sub(next, v)));
+    let cross = d0.x * d1.y - d0.y * d1.x;
+    if style.join == LineJoin::Round {
+        return Some(circle(v, hw));
+    }
+    if cross.abs() < 1e-9 {
+        return None;
+    }
+    // The outer side of the corner is opposite to the direction of the turn.
+    let side = if cross > 0.0 { -hw } else { hw };
+    let (a, b) = (
+        add(v, scale(normal(d0), side)),
+        add(v, scale(normal(d1), side)),
+    );
+    if style.join == LineJoin::Miter {
+        let mid = unit(add(normal(d0), normal(d1)));
+        let cos_half = dot(mid, normal(d0));
+        if cos_half > 1e-9 && 1.0 / cos_half <= style.miter_limit {
+            let tip = add(v, scale(mid, side / cos_half));
+            return Some(vec![v, a, tip, b]);
+        }
+    }
+    Some(vec![v, a, b])
+}
+
+/// The cap polygon at the end `p` of a subpath leaving in direction `d`.
+fn cap(p: Point2D, d: Point2D, hw: f64, cap: LineCap) -> Option<Vec<Point2D>> {
+    match cap {
+        LineCap::Butt => None,
+        LineCap::Round => Some(circle(p, hw)),
+        LineCap::Square => {
+            let n = scale(normal(d), hw);
+            let e = scale(d, hw);
+            Some(vec![
+                add(p, n),
+                add(add(p, n), e),
+                add(sub(p, n), e),
+                sub(p, n),
+            ])
+        }
+    }
+}
+
+fn circle(c: Point2D, r: f64) -> Vec<Point2D> {
+    let step = if r > FLATTEN_TOLERANCE {
+        2.0 * (1.0 - FLATTEN_TOLERANCE / r).acos()
+    } else {
+        std::f64::consts::FRAC_PI_4
+    };
+    let n = ((std::f64::consts::TAU / step).ceil() as usize).clamp(8, 256);
+    (0..n)
+        .map(|i| {
+            let a = std::f64::consts::TAU * i as f64 / n as f64;
+            Point2D::new(c.x + r * a.cos(), c.y + r * a.sin())
+        })
+        .collect()
+}
+
+fn signed_area(polygon: &[Point2D]) -> f64 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Top-left and size of the smallest whole-pixel rect containing `polygons`.
+pub fn pixel_bounds<'a>(polygons: impl IntoIterator<Item = &'a [Point2D]>) -> (Point2D, Size2D) {
+    let (mut min, mut max) = (
+        Point2D::new(f64::INFINITY, f64::INFINITY),
+        Point2D::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+    );
+    for p in polygons.into_iter().flatten() {
+        min = Point2D::new(min.x.min(p.x), min.y.min(p.y));
+        max = Point2D::new(max.x.max(p.x), max.y.max(p.y));
+    }
+    if min.x > max.x {
+        return (Point2D::zero(), Size2D::new(0.0, 0.0));
+    }
+    let min = Point2D::new(min.x.floor(), min.y.floor());
+    (min, Size2D::new(max.x.ceil() - min.x, max.y.ceil() - min.y))
+}
+
+/// A path shape: geometry plus how it is filled, stroked and trimmed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PathShape {
+    pub data: PathData,
+    #[serde(default)]
+    pub fill_rule: FillRule,
+    #[serde(default)]
+    pub stroke_style: StrokeStyle,
+    #[serde(default)]
+    pub trim: Trim,
+}
+
+impl PathShape {
+    pub fn new(data: PathData) -> Self {
+        Self {
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// The flattened subpaths after trimming: the outline that is filled and stroked.
+    pub fn outlines(&self) -> Vec<Polyline> {
+        self.trim.apply(self.data.flatten(FLATTEN_TOLERANCE))
+    }
+
+    /// Polygons covering the dashed stroke of `lines` at `width`.
+    pub fn stroke_polygons(&self, lines: &[Polyline], width: f64) -> Vec<Vec<Point2D>> {
+        let style = &self.stroke_style;
+        stroke_polygons(&dash(lines, &style.dash, style.dash_offset), width, style)
+    }
+
+    /// The whole-pixel rect that holds the shape, in path coordinates: the bounds of
+    /// the untrimmed fill and, when `stroke_width` is positive, of its solid stroke.
+    /// It does not change while trim or dashes animate.
+    pub fn content_rect(&self, stroke_width: f64) -> (Point2D, Size2D) {
+        let lines = self.data.flatten(FLATTEN_TOLERANCE);
+        let stroke = stroke_polygons(&lines, stroke_width, &self.stroke_style);
+        pixel_bounds(
+            lines
+                .iter()
+                .map(|l| l.points.as_slice())
+                .chain(stroke.iter().map(Vec::as_slice)),
+        )
+    }
+}
+
+/// Parser state for SVG path data.
+struct SvgPathParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    segments: Vec<PathSegment>,
+    pen: Point2D,
+    start: Point2D,
+    /// Reflected by `S`/`s` and `T`/`t` when the previous segment has the same kind.
+    last_cubic_ctrl: Option<Point2D>,
+    last_quad_ctrl: Option<Point2D>,
+}
+
+impl<'a> SvgPathParser<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            data: data.as_bytes(),
+            pos: 0,
+            segments: Vec::new(),
+            pen: Point2D::zero(),
+            start: Point2D::zero(),
+            last_cubic_ctrl: None,
+            last_quad_ctrl: None,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> PathError {
+        PathError::InvalidSvg {
+            offset: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        self.data
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.'))
+    }
+
+    fn number(&mut self) -> Result<f64, PathError> {
+        if !self.at_number() {
+            return Err(self.error("expected a number"));
+        }
+        let start = self.pos;
+        let digits = |p: &mut Self| {
+            while p.data.get(p.pos).is_some_and(u8::is_ascii_digit) {
+                p.pos += 1;
+            }
+        };
+        if matches!(self.data[self.pos], b'-' | b'+') {
+            self.pos += 1;
+        }
+        digits(self);
+        if self.data.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        if matches!(self.data.get(self.pos), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.data.get(self.pos), Some(b'-' | b'+')) {
+                self.pos += 1;
+            }
+            digits(self);
+        }
+        std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| PathError::InvalidSvg {
+                offset: start,
+                message: "malformed number".into(),
+            })
+    }
+
+    /// An arc flag, which may be written without a separator after it.
+    fn flag(&mut self) -> Result<bool, PathError> {
+        self.skip_separators();
+        match self.data.get(self.pos) {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(self.error("expected an arc flag (0 or 1)")),
+        }
+    }
+
+    fn point(&mut self, relative: bool) -> Result<Point2D, PathError> {
+        let (x, y) = (self.number()?, self.number()?);
+        Ok(if relative {
+            Point2D::new(self.pen.x + x, self.pen.y + y)
+        } else {
+            Point2D::new(x, y)
+        })
+    }
+
+    fn parse(mut self) -> Result<PathData, PathError> {
+        loop {
+            self.skip_separators();
+            let Some(&c) = self.data.get(self.pos) else {
+                break;
+            };
+            if !b"MmLlHhVvCcSsQqTtAaZz".contains(&c) {
+                return Err(self.error(format!("unexpected '{}'", c as char)));
+            }
+            if self.segments.is_empty() && !matches!(c, b'M' | b'm') {
+                return Err(self.error("path data must start with a moveto"));
+            }
+            self.pos += 1;
+            let mut command = c;
+            loop {
+                self.command(command)?;
+                if matches!(command, b'Z' | b'z') || !self.at_number() {
+                    break;
+                }
+                // Extra coordinate pairs after a moveto are implicit linetos.
+                command = match command {
+                    b'M' => b'L',
+                    b'm' => b'l',
+                    other => other,
+                };
+            }
+        }
+        Ok(PathData::new(self.segments))
+    }
+
+    fn command(&mut self, command: u8) -> Result<(), PathError> {
+        let relative = command.is_ascii_lowercase();
+        let (mut cubic_ctrl, mut quad_ctrl) = (None, None);
+        let upper = command.to_ascii_uppercase();
+        match upper {
+            b'M' => {
+                let p = self.point(relative)?;
+                self.segments.push(PathSegment::MoveTo { x: p.x, y: p.y });
+                self.start = p;
+                self.pen = p;
+            }
+            b'L' => {
+                let p = self.point(relative)?;
+                self.line_to(p);
+            }
+            b'H' => {
+                let x = self.number()?;
+                let x = if relative { self.pen.x + x } else { x };
+                self.line_to(Point2D::new(x, self.pen.y));
+            }
+            b'V' => {
+                let y = self.number()?;
+                let y = if relative { self.pen.y + y } else { y };
+                self.line_to(Point2D::new(self.pen.x, y));
+            }
+            b'C' | b'S' => {
+                let c1 = if upper == b'C' {
+                    self.point(relative)?
+                } else {
+                    self.reflect(self.last_cubic_ctrl)
+                };
+                let c2 = self.point(relative)?;
+                let p = self.point(relative)?;
+                self.cubic_to(c1, c2, p);
+                cubic_ctrl = Some(c2);
+            }
+            b'Q' | b'T' => {
+                let c1 = if upper == b'Q' {
+                    self.point(relative)?
+                } else {
+                    self.reflect(self.last_quad_ctrl)
+                };
+                let p = self.point(relative)?;
+                self.segments.push(PathSegment::QuadTo {
+                    x1: c1.x,
+                    y1: c1.y,
+                    x: p.x,
+                    y: p.y,
+                });
+                self.pen = p;
+                quad_ctrl = Some(c1);
+            }
+            b'A' => {
+                let (rx, ry, rotation) = (self.number()?, self.number()?, self.number()?);
+                let (large_arc, sweep) = (self.flag()?, self.flag()?);
+                let p = self.point(relative)?;
+                self.arc_to(rx, ry, rotation, large_arc, sweep, p);
+            }
+            _ => {
+                self.segments.push(PathSegment::Close);
+                self.pen = self.start;
+            }
+        }
+        self.last_cubic_ctrl = cubic_ctrl;
+        self.last_quad_ctrl = quad_ctrl;
+        Ok(())
+    }
+
+    /// The previous control point mirrored through the pen, or the pen itself.
+    fn reflect(&self, ctrl: Option<Point2D>) -> Point2D {
+        ctrl.map_or(self.pen, |c| sub(scale(self.pen, 2.0), c))
+    }
+
+    fn line_to(&mut self, p: Point2D) {
+        self.segments.push(PathSegment::LineTo { x: p.x, y: p.y });
+        self.pen = p;
+    }
+
+    fn cubic_to(&mut self, c1: Point2D, c2: Point2D, p: Point2D) {
+        self.segments.push(PathSegment::CubicTo {
+            x1: c1.x,
+            y1: c1.y,
+            x2: c2.x,
+            y2: c2.y,
+            x: p.x,
+            y: p.y,
+        });
+        self.pen = p;
+    }
+
+    /// Elliptical arc to `p`, as cubic curves of at most 90° each (SVG 1.1 F.6.5).
+    fn arc_to(
+        &mut self,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        p: Point2D,
+    ) {
+        let p0 = self.pen;
+        if p0 == p {
+            return;
+        }
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 {
+            self.line_to(p);
+            return;
+        }
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let (dx, dy) = ((p0.x - p.x) / 2.0, (p0.y - p.y) / 2.0);
+        let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+        let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coef = (num / den).max(0.0).sqrt();
+        if large_arc == sweep {
+            coef = -coef;
+        }
+        let (cx1, cy1) = (coef * rx * y1 / ry, -coef * ry * x1 / rx);
+        let cx = cos * cx1 - sin * cy1 + (p0.x + p.x) / 2.0;
+        let cy = sin * cx1 + cos * cy1 + (p0.y + p.y) / 2.0;
+
+        let angle =
+            |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        let (ux, uy) = ((x1 - cx1) / rx, (y1 - cy1) / ry);
+        let (vx, vy) = ((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+        let theta = angle(1.0, 0.0, ux, uy);
+        let mut delta = angle(ux, uy, vx, vy);
+        if !sweep && delta > 0.0 {
+            delta -= std::f64::consts::TAU;
+        } else if sweep && delta < 0.0 {
+            delta += std::f64::consts::TAU;
+        }
+
+        let n = (delta.abs() / std::f64::consts::FRAC_PI_2).ceil().max(1.0) as usize;
+        let step = delta / n as f64;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        let map = |ux: f64, uy: f64| {
+            Point2D::new(
+                cx + rx * cos * ux - ry * sin * uy,
+                cy + rx * sin * ux + ry * cos * uy,
+            )
+        };
+        for i in 0..n {
+            let (t0, t1) = (theta + step * i as f64, theta + step * (i + 1) as f64);
+            let (s0, c0) = t0.sin_cos();
+            let (s1, c1) = t1.sin_cos();
+            let end = if i + 1 == n { p } else { map(c1, s1) };
+            self.cubic_to(
+                map(c0 - k * s0, s0 + k * c0),
+                map(c1 + k * s1, s1 - k * c1),
+                end,
+            );
+        }
+    }
+}
+
+fn add(a: Point2D, b: Point2D) -> Point2D {
+    Point2D::new(a.x + b.x, a.y + b.y)
+}
+
+fn sub(a: Point2D, b: Point2D) -> Point2D {
+    Point2D::new(a.x - b.x, a.y - b.y)
+}
+
+fn scale(a: Point2D, s: f64) -> Point2D {
+    Point2D::new(a.x * s, a.y * s)
+}
+
+fn dot(a: Point2D, b: Point2D) -> f64 {
+    a.x * b.x + a.y * b.y
+}
+
+fn len(a: Point2D) -> f64 {
+    a.x.hypot(a.y)
+}
+
+fn unit(a: Point2D) -> Point2D {
+    let l = len(a);
+    if l > 0.0 {
+        scale(a, 1.0 / l)
+    } else {
+        Point2D::zero()
+    }
+}
+
+/// `d` turned a quarter to the left (in y-up terms).
+fn normal(d: Point2D) -> Point2D {
+    let d = unit(d);
+    Point2D::new(-d.y, d.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pts(line: &Polyline) -> Vec<(f64, f64)> {
+        line.points.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    #[test]
+    fn test_parse_svg_commands() {
+        let data = PathData::parse_svg("M10 20 h30 v-10 L 0,0 z m5 5 l1 1 2 2").unwrap();
+        assert_eq!(
+            data.segments,
+            vec![
+                PathSegment::MoveTo { x: 10.0, y: 20.0 },
+                PathSegment::LineTo { x: 40.0, y: 20.0 },
+                PathSegment::LineTo { x: 40.0, y: 10.0 },
+                PathSegment::LineTo { x: 0.0, y: 0.0 },
+                PathSegment::Close,
+                PathSegment::MoveTo { x: 15.0, y: 25.0 },
+                PathSegment::LineTo { x: 16.0, y: 26.0 },
+                PathSegment::LineTo { x: 18.0, y: 28.0 },
+            ]
+        );
+
+        // Smooth curves reflect the previous control point; numbers need no separators.
+        let data = PathData::parse_svg("M0 0C0-10 10-10 10 0S20 10 20 0Q25-5 30 0T40 0").unwrap();
+        assert_eq!(
+            data.segments[2],
+            PathSegment::CubicTo {
+                x1: 10.0,
+                y1: 10.0,
+                x2: 20.0,
+                y2: 10.0,
+                x: 20.0,
+                y: 0.0
+            }
+        );
+        assert_eq!(
+            data.segments[4],
+            PathSegment::QuadTo {
+                x1: 35.0,
+                y1: 5.0,
+                x: 40.0,
+                y: 0.0
+            }
+        );
+
+        assert!(PathData::parse_svg("L10 10").is_err());
+        assert!(PathData::parse_svg("M0 0 L10").is_err());
+        assert!(PathData::parse_svg("M0 0 X").is_err());
+    }
+
+    #[test]
+    fn test_arc_becomes_cubic_curves_on_the_ellipse() {
+        // Half of a circle of radius 10 around (10, 0), with flags written together.
+        let data = PathData::parse_svg("M0 0 A10 10 0 01 20 0").unwrap();
+        assert_eq!(data.segments.len(), 3);
+        for p in &data.flatten(0.01)[0].points {
+            let r = (p.x - 10.0).hypot(p.y);
+            assert!((r - 10.0).abs() < 0.01, "{:?} is off the circle", p);
+            assert!(p.y <= 1e-9, "sweep flag 1 goes through negative y");
+        }
+    }
+
+    #[test]
+    fn test_path_data_serde_accepts_svg_or_segments() {
+        let data: PathData = serde_json::from_str("\"M0 0 L10 0\"").unwrap();
+        assert_eq!(data.segments.len(), 2);
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"MoveTo":{"x":0.0,"y":0.0}},{"LineTo":{"x":10.0,"y":0.0}}]"#
+        );
+        let back: PathData = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, data);
+        assert!(serde_json::from_str::<PathData>("\"Q\"").is_err());
+    }
+
+    #[test]
+    fn test_trim_and_offset() {
+        let line = vec![Polyline::open(vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(100.0, 0.0),
+        ])];
+        let trim = |start, end, offset| Trim { start, end, offset }.apply(line.clone());
+
+        assert_eq!(pts(&trim(0.0, 0.25, 0.0)[0]), vec![(0.0, 0.0), (25.0, 0.0)]);
+        assert_eq!(
+            pts(&trim(0.5, 0.25, 0.0)[0]),
+            vec![(25.0, 0.0), (50.0, 0.0)]
+        );
+        assert!(trim(0.3, 0.3, 0.0).is_empty());
+        // An offset past the end wraps around to the start.
+        let wrapped = trim(0.0, 0.5, 0.75);
+        assert_eq!(pts(&wrapped[0]), vec![(75.0, 0.0), (100.0, 0.0)]);
+        assert_eq!(pts(&wrapped[1]), vec![(0.0, 0.0), (25.0, 0.0)]);
+
+        // Trim runs over all subpaths as one path; a whole closed subpath stays closed.
+        let square = Polyline {
+            points: vec![
+                Point2D::new(0.0, 0.0),
+                Point2D::new(10.0, 0.0),
+                Point2D::new(10.0, 10.0),
+                Point2D::new(0.0, 10.0),
+            ],
+            closed: true,
+        };
+        let lines = vec![square.clone(), square];
+        let half = Trim {
+            start: 0.0,
+            end: 0.5,
+            offset: 0.0,
+        }
+        .apply(lines);
+        assert_eq!(half.len(), 1);
+        assert!(half[0].closed);
+    }
+
+    #[test]
+    fn test_dash_pattern() {
+        let line = vec![Polyline::open(vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(20.0, 0.0),
+        ])];
+        let dashes = dash(&line, &[5.0, 3.0], 0.0);
+        let xs: Vec<_> = dashes.iter().map(pts).collect();
+        assert_eq!(
+            xs,
+            vec![
+                vec![(0.0, 0.0), (5.0, 0.0)],
+                vec![(8.0, 0.0), (13.0, 0.0)],
+                vec![(16.0, 0.0), (20.0, 0.0)],
+            ]
+        );
+        // The offset shifts the pattern backwards along the path.
+        let shifted = dash(&line, &[5.0, 3.0], 6.0);
+        assert_eq!(pts(&shifted[0]), vec![(2.0, 0.0), (7.0, 0.0)]);
+        // An odd pattern is repeated, so `[4]` is 4 on, 4 off.
+        assert_eq!(dash(&line, &[4.0], 0.0).len(), 3);
+    }
+
+    #[test]
+    fn test_stroke_extent_follows_caps_and_joins() {
+        let corner = |cap, join| {
+            let lines = PathData::parse_svg("M0 0 L20 0 L20 20")
+                .unwrap()
+                .flatten(FLATTEN_TOLERANCE);
+            let style = StrokeStyle {
+                cap,
+                join,
+                ..Default::default()
+            };
+            let polygons = stroke_polygons(&lines, 4.0, &style);
+            assert!(polygons.iter().all(|p| signed_area(p) > 0.0));
+            let (min, size) = pixel_bounds(polygons.iter().map(Vec::as_slice));
+            (min.x, min.y, size.width, size.height)
+        };
+        // Butt caps end flush; the miter fills the outer corner out to (22, -2).
+        assert_eq!(
+            corner(LineCap::Butt, LineJoin::Miter),
+            (0.0, -2.0, 22.0, 22.0)
+        );
+        assert_eq!(
+            corner(LineCap::Square, LineJoin::Bevel),
+            (-2.0, -2.0, 24.0, 24.0)
+        );
+        assert_eq!(
+            corner(LineCap::Round, LineJoin::Round),
+            (-2.0, -2.0, 24.0, 24.0)
+        );
+    }
+
+    #[test]
+    fn test_sharp_miter_falls_back_to_bevel() {
+        let lines = PathData::parse_svg("M0 0 L20 1 L0 2")
+            .unwrap()
+            .flatten(FLATTEN_TOLERANCE);
+        let polygons = stroke_polygons(&lines, 2.0, &StrokeStyle::default());
+        let (_, size) = pixel_bounds(polygons.iter().map(Vec::as_slice));
+        assert!(size.width <= 22.0, "miter was not limited: {}", size.width);
+    }
+
+    #[test]
+    fn test_content_rect_ignores_trim() {
+        let mut shape = PathShape::new(PathData::parse_svg("M10 10 L50 10 L50 30").unwrap());
+        let rect = shape.content_rect(0.0);
+        assert_eq!((rect.0.x, rect.0.y), (10.0, 10.0));
+        assert_eq!((rect.1.width, rect.1.height), (40.0, 20.0));
+        shape.trim.end = 0.1;
+        assert_eq!(shape.content_rect(0.0), rect);
+        assert_eq!(shape.outlines()[0].length(), 6.0);
+    }
+}
@@ -0,0 +1,470 @@
+//! The C ABI between Vidra and plugins loaded from dynamic libraries.
+//!
+//! A plugin library exports [`CREATE_SYMBOL`], a [`CreateFn`] returning a
+//! [`PluginDescriptor`] for the one plugin it provides. Everything that crosses the
+//! boundary is `#[repr(C)]`: strings are pointer and length pairs, frames are RGBA8
+//! pixels owned by the host, and the plugin's entry points are `extern "C"` functions
+//! handed the descriptor's `state`. Since no Rust types cross it, a plugin only has to
+//! be built against the same [`ABI_VERSION`], not the same compiler or crate versions.
+//!
+//! Rust plugins don't write any of this by hand: [`export_plugin!`](crate::export_plugin)
+//! wraps an [`EffectPlugin`], [`LayerPlugin`] or [`TransitionPlugin`] into a descriptor.
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use super::{
+    EffectContext, EffectPlugin, LayerContext, LayerPlugin, PluginKind, TransitionContext,
+    TransitionPlugin, VidraPlugin,
+};
+use crate::frame::{FrameBuffer, PixelFormat};
+use crate::VidraError;
+
+/// Version of this ABI. Plugins built against another version are not loaded.
+pub const ABI_VERSION: u32 = 1;
+
+/// Name of the function a plugin library exports.
+pub const CREATE_SYMBOL: &str = "vidra_plugin_create";
+
+/// Signature of [`CREATE_SYMBOL`]. Returns null if the plugin failed to load.
+pub type CreateFn = unsafe extern "C" fn() -> *const PluginDescriptor;
+
+/// A borrowed UTF-8 string.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AbiStr {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl AbiStr {
+    pub fn new(s: &str) -> Self {
+        Self {
+            ptr: s.as_ptr(),
+            len: s.len(),
+        }
+    }
+
+    /// The string, or `""` if it is not UTF-8.
+    ///
+    /// # Safety
+    /// `ptr` must point to `len` readable bytes that outlive `'a`.
+    pub unsafe fn as_str<'a>(&self) -> &'a str {
+        if self.ptr.is_null() {
+            return "";
+        }
+        std::str::from_utf8(std::slice::from_raw_parts(self.ptr, self.len)).unwrap_or("")
+    }
+}
+
+/// A named number parameter, as effects and transitions take.
+#[repr(C)]
+pub struct AbiNumberParam {
+    pub name: AbiStr,
+    pub value: f64,
+}
+
+/// A named string parameter, as layers take.
+#[repr(C)]
+pub struct AbiTextParam {
+    pub name: AbiStr,
+    pub value: AbiStr,
+}
+
+/// RGBA8 pixels, `width * height * 4` bytes in rows from the top.
+#[repr(C)]
+pub struct AbiFrame {
+    pub data: *mut u8,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[repr(C)]
+pub struct AbiEffectContext {
+    pub time: f64,
+    pub fps: f64,
+    pub params: *const AbiNumberParam,
+    pub params_len: usize,
+}
+
+/// The canvas size is the size of the frame the layer is drawn into.
+#[repr(C)]
+pub struct AbiLayerContext {
+    pub frame: u32,
+    pub time: f64,
+    pub fps: f64,
+    pub params: *const AbiTextParam,
+    pub params_len: usize,
+}
+
+#[repr(C)]
+pub struct AbiTransitionContext {
+    pub progress: f64,
+    pub params: *const AbiNumberParam,
+    pub params_len: usize,
+}
+
+/// Where a failing entry point writes its message: up to `cap` bytes at `ptr`,
+/// setting `len` to the number written.
+#[repr(C)]
+pub struct AbiError {
+    pub ptr: *mut u8,
+    pub cap: usize,
+    pub len: usize,
+}
+
+impl AbiError {
+    /// Write `message`, truncated to the capacity on a character boundary.
+    ///
+    /// # Safety
+    /// `ptr` must point to `cap` writable bytes.
+    pub unsafe fn write(&mut self, message: &str) {
+        let mut len = message.len().min(self.cap);
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        std::ptr::copy_nonoverlapping(message.as_ptr(), self.ptr, len);
+        self.len = len;
+    }
+}
+
+/// Filters `frame` in place. Returns `false` after writing to the error on failure.
+pub type ApplyEffectFn = unsafe extern "C" fn(
+    state: *mut c_void,
+    frame: *mut AbiFrame,
+    ctx: *const AbiEffectContext,
+    error: *mut AbiError,
+) -> bool;
+
+/// Draws into `out`, which is transparent and the size of the canvas.
+pub type RenderLayerFn = unsafe extern "C" fn(
+    state: *mut c_void,
+    ctx: *const AbiLayerContext,
+    out: *mut AbiFrame,
+    error: *mut AbiError,
+) -> bool;
+
+/// Blends two frames of the same size into `out`, which is that size too.
+pub type ApplyTransitionFn = unsafe extern "C" fn(
+    state: *mut c_void,
+    outgoing: *const AbiFrame,
+    incoming: *const AbiFrame,
+    ctx: *const AbiTransitionContext,
+    out: *mut AbiFrame,
+    error: *mut AbiError,
+) -> bool;
+
+/// Describes the plugin a library provides. `abi_version` comes first so it can be
+/// read whatever version the rest of the layout is.
+///
+/// The entry points may be called from several threads at once.
+#[repr(C)]
+pub struct PluginDescriptor {
+    pub abi_version: u32,
+    /// One of the `KIND_*` constants.
+    pub kind: u32,
+    /// The name VidraScript refers to the plugin by.
+    pub name: AbiStr,
+    pub id: AbiStr,
+    pub display_name: AbiStr,
+    pub version: AbiStr,
+    pub author: AbiStr,
+    pub description: AbiStr,
+    /// Parameter names, as [`VidraPlugin::params`].
+    pub params: *const AbiStr,
+    pub params_len: usize,
+    /// Passed to every entry point.
+    pub state: *mut c_void,
+    /// Set for effects.
+    pub apply_effect: Option<ApplyEffectFn>,
+    /// Set for layers.
+    pub render_layer: Option<RenderLayerFn>,
+    /// Set for transitions.
+    pub apply_transition: Option<ApplyTransitionFn>,
+    /// Frees the plugin; the descriptor must not be used afterwards.
+    pub destroy: unsafe extern "C" fn(state: *mut c_void),
+}
+
+pub const KIND_EFFECT: u32 = 0;
+pub const KIND_LAYER: u32 = 1;
+pub const KIND_TRANSITION: u32 = 2;
+
+impl PluginKind {
+    pub(crate) fn from_abi(kind: u32) -> Option<Self> {
+        match kind {
+            KIND_EFFECT => Some(PluginKind::Effect),
+            KIND_LAYER => Some(PluginKind::Layer),
+            KIND_TRANSITION => Some(PluginKind::Transition),
+            _ => None,
+        }
+    }
+}
+
+/// Copy `frame`'s pixels into a buffer.
+///
+/// # Safety
+/// `frame` must describe `width * height * 4` readable bytes.
+pub unsafe fn frame_to_buffer(frame: &AbiFrame) -> FrameBuffer {
+    let len = frame.width as usize * frame.height as usize * 4;
+    FrameBuffer {
+        data: std::slice::from_raw_parts(frame.data, len).to_vec(),
+        width: frame.width,
+        height: frame.height,
+        format: PixelFormat::Rgba8,
+    }
+}
+
+/// Copy `buffer` into `frame`, which must be the same size.
+///
+/// # Safety
+/// `frame` must describe `width * height * 4` writable bytes.
+unsafe fn copy_to_frame(buffer: &FrameBuffer, frame: &mut AbiFrame) -> Result<(), VidraError> {
+    if (buffer.width, buffer.height) != (frame.width, frame.height) {
+        return Err(VidraError::Plugin(format!(
+            "drew a {}x{} frame, expected {}x{}",
+            buffer.width, buffer.height, frame.width, frame.height
+        )));
+    }
+    let len = buffer
+        .data
+        .len()
+        .min(frame.width as usize * frame.height as usize * 4);
+    std::ptr::copy_nonoverlapping(buffer.data.as_ptr(), frame.data, len);
+    Ok(())
+}
+
+/// # Safety
+/// `params` must point to `len` parameters with valid names.
+unsafe fn number_params(params: *const AbiNumberParam, len: usize) -> HashMap<String, f64> {
+    if params.is_null() {
+        return HashMap::new();
+    }
+    std::slice::from_raw_parts(params, len)
+        .iter()
+        .map(|p| (p.name.as_str().to_string(), p.value))
+        .collect()
+}
+
+/// # Safety
+/// `params` must point to `len` parameters with valid names and values.
+unsafe fn text_params(params: *const AbiTextParam, len: usize) -> HashMap<String, String> {
+    if params.is_null() {
+        return HashMap::new();
+    }
+    std::slice::from_raw_parts(params, len)
+        .iter()
+        .map(|p| (p.name.as_str().to_string(), p.value.as_str().to_string()))
+        .collect()
+}
+
+/// Run an entry point, reporting errors and panics through `error`.
+unsafe fn guarded(error: *mut AbiError, f: impl FnOnce() -> Result<(), VidraError>) -> bool {
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return true,
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => "the plugin panicked".to_string(),
+    };
+    if let Some(error) = error.as_mut() {
+        error.write(&message);
+    }
+    false
+}
+
+/// A plugin and the descriptor pointing into it, kept together on the heap.
+struct Exported<P> {
+    descriptor: PluginDescriptor,
+    // The descriptor's strings point into these.
+    _strings: Vec<String>,
+    _params: Vec<AbiStr>,
+    plugin: P,
+}
+
+unsafe fn exported<'a, P>(state: *mut c_void) -> &'a P {
+    &(*(state as *const Exported<P>)).plugin
+}
+
+unsafe extern "C" fn destroy<P: VidraPlugin>(state: *mut c_void) {
+    let mut exported = Box::from_raw(state as *mut Exported<P>);
+    let _ = catch_unwind(AssertUnwindSafe(|| exported.plugin.on_unload()));
+}
+
+unsafe extern "C" fn apply_effect<P: EffectPlugin>(
+    state: *mut c_void,
+    frame: *mut AbiFrame,
+    ctx: *const AbiEffectContext,
+    error: *mut AbiError,
+) -> bool {
+    guarded(error, || {
+        let (frame, ctx) = (&mut *frame, &*ctx);
+        let mut buffer = frame_to_buffer(frame);
+        let effect_ctx = EffectContext {
+            width: frame.width,
+            height: frame.height,
+            time: ctx.time,
+            fps: ctx.fps,
+            params: number_params(ctx.params, ctx.params_len),
+        };
+        exported::<P>(state).apply(&mut buffer, &effect_ctx)?;
+        copy_to_frame(&buffer, frame)
+    })
+}
+
+unsafe extern "C" fn render_layer<P: LayerPlugin>(
+    state: *mut c_void,
+    ctx: *const AbiLayerContext,
+    out: *mut AbiFrame,
+    error: *mut AbiError,
+) -> bool {
+    guarded(error, || {
+        let (ctx, out) = (&*ctx, &mut *out);
+        let layer_ctx = LayerContext {
+            width: out.width,
+            height: out.height,
+            frame: ctx.frame,
+            time: ctx.time,
+            fps: ctx.fps,
+            params: text_params(ctx.params, ctx.params_len),
+        };
+        let buffer = exported::<P>(state).render(&layer_ctx)?;
+        copy_to_frame(&buffer, out)
+    })
+}
+
+unsafe extern "C" fn apply_transition<P: TransitionPlugin>(
+    state: *mut c_void,
+    outgoing: *const AbiFrame,
+    incoming: *const AbiFrame,
+    ctx: *const AbiTransitionContext,
+    out: *mut AbiFrame,
+    error: *mut AbiError,
+) -> bool {
+    guarded(error, || {
+        let (ctx, out) = (&*ctx, &mut *out);
+        let transition_ctx = TransitionContext {
+            width: out.width,
+            height: out.height,
+            progress: ctx.progress,
+            params: number_params(ctx.params, ctx.params_len),
+        };
+        let blended = exported::<P>(state).apply(
+            &frame_to_buffer(&*outgoing),
+            &frame_to_buffer(&*incoming),
+            &transition_ctx,
+        )?;
+        copy_to_frame(&blended, out)
+    })
+}
+
+/// Load `plugin` and box it with a descriptor of `kind`, registered as `name`.
+/// Returns null if the plugin's `on_load` fails.
+fn export<P: VidraPlugin + 'static>(
+    mut plugin: P,
+    kind: u32,
+    name: impl FnOnce(&P) -> String,
+    entry: impl FnOnce(&mut PluginDescriptor),
+) -> *const PluginDescriptor {
+    if plugin.on_load().is_err() {
+        return std::ptr::null();
+    }
+    let manifest = plugin.manifest();
+    let strings: Vec<String> = [
+        name(&plugin),
+        manifest.id,
+        manifest.name,
+        manifest.version,
+        manifest.author,
+        manifest.description,
+    ]
+    .into_iter()
+    .chain(plugin.params().iter().map(|p| p.to_string()))
+    .collect();
+    let params: Vec<AbiStr> = strings[6..].iter().map(|s| AbiStr::new(s)).collect();
+    let mut descriptor = PluginDescriptor {
+        abi_version: ABI_VERSION,
+        kind,
+        name: AbiStr::new(&strings[0]),
+        id: AbiStr::new(&strings[1]),
+        display_name: AbiStr::new(&strings[2]),
+        version: AbiStr::new(&strings[3]),
+        author: AbiStr::new(&strings[4]),
+        description: AbiStr::new(&strings[5]),
+        params: params.as_ptr(),
+        params_len: params.len(),
+        state: std::ptr::null_mut(),
+        apply_effect: None,
+        render_layer: None,
+        apply_transition: None,
+        destroy: destroy::<P>,
+    };
+    entry(&mut descriptor);
+    let exported = Box::into_raw(Box::new(Exported {
+        descriptor,
+        _strings: strings,
+        _params: params,
+        plugin,
+    }));
+    // SAFETY: `exported` was just allocated and is only freed by `destroy`.
+    unsafe {
+        (*exported).descriptor.state = exported as *mut c_void;
+        &(*exported).descriptor
+    }
+}
+
+/// Export an effect plugin; see [`export_plugin!`](crate::export_plugin).
+pub fn export_effect<P: EffectPlugin + 'static>(plugin: P) -> *const PluginDescriptor {
+    export(
+        plugin,
+        KIND_EFFECT,
+        |p| p.effect_name().to_string(),
+        |d| d.apply_effect = Some(apply_effect::<P>),
+    )
+}
+
+/// Export a layer plugin; see [`export_plugin!`](crate::export_plugin).
+pub fn export_layer<P: LayerPlugin + 'static>(plugin: P) -> *const PluginDescriptor {
+    export(
+        plugin,
+        KIND_LAYER,
+        |p| p.layer_type().to_string(),
+        |d| d.render_layer = Some(render_layer::<P>),
+    )
+}
+
+/// Export a transition plugin; see [`export_plugin!`](crate::export_plugin).
+pub fn export_transition<P: TransitionPlugin + 'static>(plugin: P) -> *const PluginDescriptor {
+    export(
+        plugin,
+        KIND_TRANSITION,
+        |p| p.transition_name().to_string(),
+        |d| d.apply_transition = Some(apply_transition::<P>),
+    )
+}
+
+/// Export a plugin from a `cdylib` crate as its `vidra_plugin_create`:
+///
+/// ```ignore
+/// vidra_core::export_plugin!(effect, MyEffect::new());
+/// ```
+///
+/// The first argument is `effect`, `layer` or `transition`; the second creates the
+/// plugin, whose `on_load` is called before the library is registered and `on_unload`
+/// when it is unloaded.
+#[macro_export]
+macro_rules! export_plugin {
+    (effect, $plugin:expr) => {
+        $crate::export_plugin!(@export export_effect, $plugin);
+    };
+    (layer, $plugin:expr) => {
+        $crate::export_plugin!(@export export_layer, $plugin);
+    };
+    (transition, $plugin:expr) => {
+        $crate::export_plugin!(@export export_transition, $plugin);
+    };
+    (@export $export:ident, $plugin:expr) => {
+        #[no_mangle]
+        pub extern "C" fn vidra_plugin_create() -> *const $crate::plugin::abi::PluginDescriptor {
+            $crate::plugin::abi::$export($plugin)
+        }
+    };
+}
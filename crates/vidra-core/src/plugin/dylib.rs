@@ -0,0 +1,291 @@
+//! Plugins loaded from dynamic libraries through the C ABI in [`super::abi`].
+
+use std::path::Path;
+
+use super::abi::{
+    self, AbiEffectContext, AbiError, AbiFrame, AbiLayerContext, AbiNumberParam, AbiStr,
+    AbiTextParam, AbiTransitionContext, CreateFn, PluginDescriptor, ABI_VERSION,
+};
+use super::{
+    EffectContext, EffectPlugin, LayerContext, LayerPlugin, PluginKind, PluginManifest,
+    TransitionContext, TransitionPlugin, VidraPlugin,
+};
+use crate::frame::{FrameBuffer, PixelFormat};
+use crate::VidraError;
+
+/// Longest error message read back from a plugin.
+const ERROR_CAPACITY: usize = 1024;
+
+/// A plugin provided by a loaded library. Implements whichever plugin trait its
+/// descriptor's kind says; the other entry points fail.
+pub(crate) struct DynamicPlugin {
+    descriptor: *const PluginDescriptor,
+    kind: PluginKind,
+    name: String,
+    manifest: PluginManifest,
+    // Borrowed from the descriptor, which lives until `destroy` in `drop`.
+    params: Vec<&'static str>,
+    // Dropped after `drop` has destroyed the plugin, so its code outlives it.
+    _library: libloading::Library,
+}
+
+// SAFETY: the ABI requires the entry points to be callable from any thread, and the
+// descriptor is not mutated after loading.
+unsafe impl Send for DynamicPlugin {}
+unsafe impl Sync for DynamicPlugin {}
+
+impl DynamicPlugin {
+    /// Load the plugin exported by the library at `path`.
+    pub(crate) fn load(path: &Path) -> Result<Self, VidraError> {
+        let fail = |message: String| {
+            VidraError::Plugin(format!("failed to load {}: {}", path.display(), message))
+        };
+        // SAFETY: loading a library runs its initializers; installing a plugin is
+        // trusting it.
+        let library = unsafe { libloading::Library::new(path) }.map_err(|e| fail(e.to_string()))?;
+        // SAFETY: the symbol is declared with this signature by the ABI.
+        let descriptor = unsafe {
+            let create = library
+                .get::<CreateFn>(abi::CREATE_SYMBOL.as_bytes())
+                .map_err(|e| fail(e.to_string()))?;
+            create()
+        };
+        if descriptor.is_null() {
+            return Err(fail("the plugin failed to initialize".into()));
+        }
+        // SAFETY: `abi_version` leads every version of the descriptor.
+        let version = unsafe { (*descriptor).abi_version };
+        if version != ABI_VERSION {
+            // A descriptor of another layout can't be destroyed safely, so it leaks.
+            return Err(fail(format!(
+                "it was built for plugin ABI v{}, but this Vidra uses v{}",
+                version, ABI_VERSION
+            )));
+        }
+
+        // SAFETY: the descriptor has this version's layout, and its strings live until
+        // it is destroyed.
+        let d = unsafe { &*descriptor };
+        let mut plugin = unsafe {
+            let params = if d.params.is_null() {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts(d.params, d.params_len)
+                    .iter()
+                    .map(|p| p.as_str())
+                    .collect()
+            };
+            DynamicPlugin {
+                descriptor,
+                kind: PluginKind::Effect,
+                name: d.name.as_str().to_string(),
+                manifest: PluginManifest {
+                    id: d.id.as_str().to_string(),
+                    name: d.display_name.as_str().to_string(),
+                    version: d.version.as_str().to_string(),
+                    author: d.author.as_str().to_string(),
+                    description: d.description.as_str().to_string(),
+                },
+                params,
+                _library: library,
+            }
+        };
+        // From here on, returning an error destroys the plugin.
+        plugin.kind = PluginKind::from_abi(d.kind)
+            .ok_or_else(|| fail(format!("unknown plugin kind {}", d.kind)))?;
+        let has_entry = match plugin.kind {
+            PluginKind::Effect => d.apply_effect.is_some(),
+            PluginKind::Layer => d.render_layer.is_some(),
+            PluginKind::Transition => d.apply_transition.is_some(),
+        };
+        if !has_entry {
+            return Err(fail(format!(
+                "the {} plugin has no entry point",
+                plugin.kind
+            )));
+        }
+        Ok(plugin)
+    }
+
+    pub(crate) fn kind(&self) -> PluginKind {
+        self.kind
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn descriptor(&self) -> &PluginDescriptor {
+        // SAFETY: valid until `drop`.
+        unsafe { &*self.descriptor }
+    }
+
+    /// Call an entry point, turning its failure into an error.
+    fn call(&self, f: impl FnOnce(*mut AbiError) -> bool) -> Result<(), VidraError> {
+        let mut message = [0u8; ERROR_CAPACITY];
+        let mut error = AbiError {
+            ptr: message.as_mut_ptr(),
+            cap: message.len(),
+            len: 0,
+        };
+        if f(&mut error) {
+            return Ok(());
+        }
+        let len = error.len.min(message.len());
+        Err(VidraError::Plugin(format!(
+            "{} plugin '{}' failed: {}",
+            self.kind,
+            self.name,
+            String::from_utf8_lossy(&message[..len])
+        )))
+    }
+}
+
+impl Drop for DynamicPlugin {
+    fn drop(&mut self) {
+        let d = self.descriptor();
+        // SAFETY: the descriptor is not used after this.
+        unsafe { (d.destroy)(d.state) };
+    }
+}
+
+fn number_params(params: &std::collections::HashMap<String, f64>) -> Vec<AbiNumberParam> {
+    params
+        .iter()
+        .map(|(name, value)| AbiNumberParam {
+            name: AbiStr::new(name),
+            value: *value,
+        })
+        .collect()
+}
+
+fn rgba(buffer: &FrameBuffer) -> Result<(), VidraError> {
+    match buffer.format {
+        PixelFormat::Rgba8 => Ok(()),
+        other => Err(VidraError::Plugin(format!(
+            "plugins take RGBA8 frames, not {:?}",
+            other
+        ))),
+    }
+}
+
+fn abi_frame(buffer: &mut FrameBuffer) -> AbiFrame {
+    AbiFrame {
+        data: buffer.data.as_mut_ptr(),
+        width: buffer.width,
+        height: buffer.height,
+    }
+}
+
+impl VidraPlugin for DynamicPlugin {
+    fn manifest(&self) -> PluginManifest {
+        self.manifest.clone()
+    }
+
+    fn params(&self) -> &[&str] {
+        &self.params
+    }
+}
+
+impl EffectPlugin for DynamicPlugin {
+    fn effect_name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&self, frame: &mut FrameBuffer, ctx: &EffectContext) -> Result<(), VidraError> {
+        rgba(frame)?;
+        let apply = self.descriptor().apply_effect.ok_or_else(|| {
+            VidraError::Plugin(format!("'{}' is not an effect plugin", self.name))
+        })?;
+        let params = number_params(&ctx.params);
+        let abi_ctx = AbiEffectContext {
+            time: ctx.time,
+            fps: ctx.fps,
+            params: params.as_ptr(),
+            params_len: params.len(),
+        };
+        let mut abi_frame = abi_frame(frame);
+        let state = self.descriptor().state;
+        // SAFETY: every pointer is valid for the duration of the call.
+        self.call(|error| unsafe { apply(state, &mut abi_frame, &abi_ctx, error) })
+    }
+}
+
+impl LayerPlugin for DynamicPlugin {
+    fn layer_type(&self) -> &str {
+        &self.name
+    }
+
+    fn render(&self, ctx: &LayerContext) -> Result<FrameBuffer, VidraError> {
+        let render = self
+            .descriptor()
+            .render_layer
+            .ok_or_else(|| VidraError::Plugin(format!("'{}' is not a layer plugin", self.name)))?;
+        let params: Vec<AbiTextParam> = ctx
+            .params
+            .iter()
+            .map(|(name, value)| AbiTextParam {
+                name: AbiStr::new(name),
+                value: AbiStr::new(value),
+            })
+            .collect();
+        let abi_ctx = AbiLayerContext {
+            frame: ctx.frame,
+            time: ctx.time,
+            fps: ctx.fps,
+            params: params.as_ptr(),
+            params_len: params.len(),
+        };
+        let mut out = FrameBuffer::new(ctx.width, ctx.height, PixelFormat::Rgba8);
+        let mut abi_out = abi_frame(&mut out);
+        let state = self.descriptor().state;
+        // SAFETY: every pointer is valid for the duration of the call.
+        self.call(|error| unsafe { render(state, &abi_ctx, &mut abi_out, error) })?;
+        Ok(out)
+    }
+}
+
+impl TransitionPlugin for DynamicPlugin {
+    fn transition_name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(
+        &self,
+        outgoing: &FrameBuffer,
+        incoming: &FrameBuffer,
+        ctx: &TransitionContext,
+    ) -> Result<FrameBuffer, VidraError> {
+        rgba(outgoing)?;
+        rgba(incoming)?;
+        let apply = self.descriptor().apply_transition.ok_or_else(|| {
+            VidraError::Plugin(format!("'{}' is not a transition plugin", self.name))
+        })?;
+        if (outgoing.width, outgoing.height) != (incoming.width, incoming.height) {
+            return Err(VidraError::Plugin(
+                "transition frames differ in size".to_string(),
+            ));
+        }
+        let params = number_params(&ctx.params);
+        let abi_ctx = AbiTransitionContext {
+            progress: ctx.progress,
+            params: params.as_ptr(),
+            params_len: params.len(),
+        };
+        // The plugin only reads these, through const pointers.
+        let read_only = |buffer: &FrameBuffer| AbiFrame {
+            data: buffer.data.as_ptr() as *mut u8,
+            width: buffer.width,
+            height: buffer.height,
+        };
+        let (abi_from, abi_to) = (read_only(outgoing), read_only(incoming));
+        let mut out = FrameBuffer::new(outgoing.width, outgoing.height, PixelFormat::Rgba8);
+        let mut abi_out = abi_frame(&mut out);
+        let state = self.descriptor().state;
+        // SAFETY: every pointer is valid for the duration of the call.
+        self.call(|error| unsafe {
+            apply(state, &abi_from, &abi_to, &abi_ctx, &mut abi_out, error)
+        })?;
+        Ok(out)
+    }
+}
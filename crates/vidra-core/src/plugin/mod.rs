@@ -5,6 +5,13 @@ use std::path::Path;
 use crate::frame::FrameBuffer;
 use crate::VidraError;
 
+pub mod abi;
+#[cfg(not(target_arch = "wasm32"))]
+mod dylib;
+#[cfg(not(target_arch = "wasm32"))]
+mod sandbox;
+pub mod wasm;
+
 // ──────────────────────────────────────────────────────────────────────────────
 // Core Plugin Trait
 // ──────────────────────────────────────────────────────────────────────────────
@@ -44,6 +51,39 @@ pub trait VidraPlugin: Send + Sync {
     fn on_unload(&mut self) -> Result<(), VidraError> {
         Ok(())
     }
+
+    /// Names of the parameters VidraScript may pass to the plugin.
+    /// An empty list leaves them unchecked.
+    fn params(&self) -> &[&str] {
+        &[]
+    }
+}
+
+/// The kinds of plugin the registry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluginKind {
+    Effect,
+    Layer,
+    Transition,
+}
+
+impl fmt::Display for PluginKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginKind::Effect => write!(f, "effect"),
+            PluginKind::Layer => write!(f, "layer"),
+            PluginKind::Transition => write!(f, "transition"),
+        }
+    }
+}
+
+/// What VidraScript may reference of a registered plugin: its name and parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginSignature {
+    pub kind: PluginKind,
+    pub name: String,
+    /// Empty when the plugin does not declare its parameters.
+    pub params: Vec<String>,
 }
 
 // ──────────────────────────────────────────────────────────────────────────────
@@ -201,6 +241,30 @@ impl PluginRegistry {
         self.transitions.get(name).map(|p| p.as_ref())
     }
 
+    /// The name and parameters of every registered plugin.
+    pub fn signatures(&self) -> Vec<PluginSignature> {
+        fn signature(kind: PluginKind, name: &str, params: &[&str]) -> PluginSignature {
+            PluginSignature {
+                kind,
+                name: name.to_string(),
+                params: params.iter().map(|p| p.to_string()).collect(),
+            }
+        }
+        let effects = self
+            .effects
+            .iter()
+            .map(|(name, p)| signature(PluginKind::Effect, name, p.params()));
+        let layers = self
+            .layers
+            .iter()
+            .map(|(name, p)| signature(PluginKind::Layer, name, p.params()));
+        let transitions = self
+            .transitions
+            .iter()
+            .map(|(name, p)| signature(PluginKind::Transition, name, p.params()));
+        effects.chain(layers).chain(transitions).collect()
+    }
+
     /// List all registered plugin manifests.
     pub fn list(&self) -> Vec<PluginManifest> {
         let mut result = Vec::new();
@@ -216,17 +280,63 @@ impl PluginRegistry {
         result
     }
 
-    /// Load a plugin from a dynamic library (.dylib/.so/.dll) at the given path.
-    ///
-    /// The library must export a `vidra_plugin_create` function with the signature:
-    /// `extern "C" fn() -> *mut dyn VidraPlugin`
+    /// Load and register the plugin in the dynamic library (.so/.dylib/.dll) at `path`.
     ///
-    /// For safety, this is stubbed in Phase 3. Full dylib loading will come later.
-    pub fn load_from_path(&mut self, _path: &Path) -> Result<(), VidraError> {
-        // Dynamic loading not yet implemented
-        Err(VidraError::Encode(
-            "Dynamic plugin loading not yet available. Register plugins programmatically.".into(),
-        ))
+    /// The library must export `vidra_plugin_create` as described in [`abi`], built
+    /// against the same [`abi::ABI_VERSION`]; see [`export_plugin!`](crate::export_plugin).
+    /// The library stays loaded while the plugin is registered.
+    pub fn load_from_path(&mut self, path: &Path) -> Result<PluginSignature, VidraError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let plugin = dylib::DynamicPlugin::load(path)?;
+            let signature = PluginSignature {
+                kind: plugin.kind(),
+                name: plugin.name().to_string(),
+                params: plugin.params().iter().map(|p| p.to_string()).collect(),
+            };
+            match plugin.kind() {
+                PluginKind::Effect => self.register_effect(Box::new(plugin))?,
+                PluginKind::Layer => self.register_layer(Box::new(plugin))?,
+                PluginKind::Transition => self.register_transition(Box::new(plugin))?,
+            }
+            Ok(signature)
+        }
+        #[cfg(target_arch = "wasm32")]
+        Err(VidraError::Unsupported(format!(
+            "loading plugin libraries ({})",
+            path.display()
+        )))
+    }
+
+    /// Load and register the plugin in a WebAssembly module, run sandboxed within
+    /// `limits`. See [`wasm`] for what the module must export.
+    pub fn load_wasm(
+        &mut self,
+        module: &[u8],
+        limits: wasm::WasmLimits,
+    ) -> Result<PluginSignature, VidraError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let plugin = sandbox::WasmPlugin::load(module, limits)?;
+            let signature = PluginSignature {
+                kind: plugin.kind(),
+                name: plugin.name().to_string(),
+                params: plugin.params().iter().map(|p| p.to_string()).collect(),
+            };
+            match plugin.kind() {
+                PluginKind::Effect => self.register_effect(Box::new(plugin))?,
+                PluginKind::Layer => self.register_layer(Box::new(plugin))?,
+                PluginKind::Transition => unreachable!("WebAssembly plugins are effects or layers"),
+            }
+            Ok(signature)
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = (module, limits);
+            Err(VidraError::Unsupported(
+                "running WebAssembly plugins".to_string(),
+            ))
+        }
     }
 }
 
@@ -253,6 +363,10 @@ mod tests {
                 description: "A test invert effect".to_string(),
             }
         }
+
+        fn params(&self) -> &[&str] {
+            &["amount"]
+        }
     }
 
     impl EffectPlugin for InvertEffect {
@@ -370,4 +484,39 @@ mod tests {
         let manifests = registry.list();
         assert_eq!(manifests.len(), 2);
     }
+
+    #[test]
+    fn test_registry_signatures() {
+        let mut registry = PluginRegistry::new();
+        registry.register_effect(Box::new(InvertEffect)).unwrap();
+        registry.register_layer(Box::new(GradientLayer)).unwrap();
+
+        let mut signatures = registry.signatures();
+        signatures.sort_by_key(|s| s.kind == PluginKind::Layer);
+        assert_eq!(
+            signatures,
+            [
+                PluginSignature {
+                    kind: PluginKind::Effect,
+                    name: "testInvert".to_string(),
+                    params: vec!["amount".to_string()],
+                },
+                PluginSignature {
+                    kind: PluginKind::Layer,
+                    name: "gradient".to_string(),
+                    params: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_from_path_missing_library() {
+        let mut registry = PluginRegistry::new();
+        let err = registry
+            .load_from_path(Path::new("/nonexistent/libvidra_plugin_missing.so"))
+            .unwrap_err();
+        assert!(matches!(err, VidraError::Plugin(_)));
+        assert!(registry.signatures().is_empty());
+    }
 }
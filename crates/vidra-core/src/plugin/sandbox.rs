@@ -0,0 +1,534 @@
+//! Plugins run from WebAssembly modules, through the interface in [`super::wasm`].
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use wasmi::core::TrapCode;
+use wasmi::{
+    Caller, Config, Engine, Extern, Instance, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use super::wasm::{WasmLimits, ABI_VERSION};
+use super::{
+    EffectContext, EffectPlugin, LayerContext, LayerPlugin, PluginKind, PluginManifest, VidraPlugin,
+};
+use crate::frame::{FrameBuffer, PixelFormat};
+use crate::VidraError;
+
+const INFO: &str = "vidra_plugin_info";
+const ALLOC: &str = "vidra_alloc";
+const APPLY_EFFECT: &str = "vidra_apply_effect";
+const RENDER_LAYER: &str = "vidra_render_layer";
+
+/// Most entries a module's table may hold. Tables only hold function references, so
+/// this is plenty for any real module.
+const MAX_TABLE_ELEMENTS: u32 = 1 << 16;
+
+type EffectFn = (i32, i32, i32, f64, f64);
+type LayerFn = (i32, i32, i32, i32, f64, f64);
+
+/// What a module says about itself through `vidra_plugin_info`.
+#[derive(Deserialize)]
+struct PluginInfo {
+    kind: String,
+    name: String,
+    #[serde(default)]
+    params: Vec<String>,
+    id: Option<String>,
+    display_name: Option<String>,
+    version: Option<String>,
+    author: Option<String>,
+    description: Option<String>,
+}
+
+/// The data of a call's store.
+struct Host {
+    limits: StoreLimits,
+    params: HashMap<String, String>,
+    /// Set by the plugin through `fail`.
+    error: Option<String>,
+}
+
+/// A plugin provided by a WebAssembly module.
+pub(crate) struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+    linker: Linker<Host>,
+    limits: WasmLimits,
+    kind: PluginKind,
+    name: String,
+    manifest: PluginManifest,
+    // Leaked, as `VidraPlugin::params` lends out `&str`s; plugins are loaded once.
+    params: Vec<&'static str>,
+}
+
+impl WasmPlugin {
+    /// Compile `module` and read its plugin info.
+    pub(crate) fn load(module: &[u8], limits: WasmLimits) -> Result<Self, VidraError> {
+        let fail = |message: String| {
+            VidraError::Plugin(format!("failed to load WebAssembly plugin: {}", message))
+        };
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, module).map_err(|e| fail(e.to_string()))?;
+        let linker = host_linker(&engine).map_err(|e| fail(e.to_string()))?;
+
+        let mut store = new_store(&engine, limits, HashMap::new());
+        let info = (|| {
+            let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+            let memory = exported_memory(&instance, &store)?;
+            let ptr = instance
+                .get_typed_func::<(), i32>(&store, INFO)?
+                .call(&mut store, ())?;
+            let info = usize::try_from(ptr)
+                .ok()
+                .and_then(|start| memory.data(&store).get(start..))
+                .and_then(|rest| rest.iter().position(|&b| b == 0).map(|end| &rest[..end]))
+                .ok_or_else(|| {
+                    wasmi::Error::new("its plugin info is not a NUL-terminated string")
+                })?;
+            let has_alloc = instance.get_typed_func::<i32, i32>(&store, ALLOC).is_ok();
+            let has_effect = instance
+                .get_typed_func::<EffectFn, i32>(&store, APPLY_EFFECT)
+                .is_ok();
+            let has_layer = instance
+                .get_typed_func::<LayerFn, i32>(&store, RENDER_LAYER)
+                .is_ok();
+            Ok::<_, wasmi::Error>((info.to_vec(), has_alloc, has_effect, has_layer))
+        })();
+        let (info, has_alloc, has_effect, has_layer) =
+            info.map_err(|e| fail(describe(&e, &limits)))?;
+
+        let info: serde_json::Value = serde_json::from_slice(&info)
+            .map_err(|e| fail(format!("its plugin info is not JSON: {}", e)))?;
+        match info.get("abi_version").and_then(|v| v.as_u64()) {
+            Some(version) if version == ABI_VERSION as u64 => {}
+            Some(version) => {
+                return Err(fail(format!(
+                    "it was built for WebAssembly plugin ABI v{}, but this Vidra uses v{}",
+                    version, ABI_VERSION
+                )))
+            }
+            None => return Err(fail("its plugin info has no abi_version".to_string())),
+        }
+        let info: PluginInfo = serde_json::from_value(info)
+            .map_err(|e| fail(format!("invalid plugin info: {}", e)))?;
+
+        let (kind, has_entry) = match info.kind.as_str() {
+            "effect" => (PluginKind::Effect, has_effect),
+            "layer" => (PluginKind::Layer, has_layer),
+            other => {
+                return Err(fail(format!(
+                    "WebAssembly plugins are effects or layers, not '{}'",
+                    other
+                )))
+            }
+        };
+        if !has_entry {
+            return Err(fail(format!("the {} plugin has no entry point", kind)));
+        }
+        if !has_alloc {
+            return Err(fail(format!("it does not export {}", ALLOC)));
+        }
+
+        Ok(WasmPlugin {
+            engine,
+            module,
+            linker,
+            limits,
+            kind,
+            manifest: PluginManifest {
+                id: info.id.unwrap_or_else(|| info.name.clone()),
+                name: info.display_name.unwrap_or_else(|| info.name.clone()),
+                version: info.version.unwrap_or_default(),
+                author: info.author.unwrap_or_default(),
+                description: info.description.unwrap_or_default(),
+            },
+            name: info.name,
+            params: info
+                .params
+                .into_iter()
+                .map(|p| &*Box::leak(p.into_boxed_str()))
+                .collect(),
+        })
+    }
+
+    pub(crate) fn kind(&self) -> PluginKind {
+        self.kind
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run an entry point on a fresh instance, with `pixels` copied into its memory and,
+    /// if it succeeds, back out.
+    fn call(
+        &self,
+        params: HashMap<String, String>,
+        pixels: &mut [u8],
+        entry: impl FnOnce(&Instance, &mut Store<Host>, i32) -> Result<i32, wasmi::Error>,
+    ) -> Result<(), VidraError> {
+        let mut store = new_store(&self.engine, self.limits, params);
+        let result = (|| {
+            let instance = self
+                .linker
+                .instantiate(&mut store, &self.module)?
+                .start(&mut store)?;
+            let memory = exported_memory(&instance, &store)?;
+            let len = i32::try_from(pixels.len())
+                .map_err(|_| wasmi::Error::new("the frame is too large"))?;
+            let ptr = instance
+                .get_typed_func::<i32, i32>(&store, ALLOC)?
+                .call(&mut store, len)?;
+            let offset = usize::try_from(ptr).map_err(|_| out_of_bounds())?;
+            memory.write(&mut store, offset, pixels)?;
+            let status = entry(&instance, &mut store, ptr)?;
+            if status == 0 {
+                memory.read(&store, offset, pixels)?;
+            }
+            Ok(status)
+        })();
+        let error = store.data_mut().error.take();
+        let message = match result {
+            Ok(0) => return Ok(()),
+            Ok(status) => error.unwrap_or_else(|| format!("returned {}", status)),
+            Err(e) => error.unwrap_or_else(|| describe(&e, &self.limits)),
+        };
+        Err(VidraError::Plugin(format!(
+            "{} plugin '{}' failed: {}",
+            self.kind, self.name, message
+        )))
+    }
+}
+
+fn new_store(engine: &Engine, limits: WasmLimits, params: HashMap<String, String>) -> Store<Host> {
+    let host = Host {
+        limits: StoreLimitsBuilder::new()
+            .memory_size(limits.memory)
+            .table_elements(MAX_TABLE_ELEMENTS)
+            .instances(1)
+            .tables(1)
+            .memories(1)
+            .build(),
+        params,
+        error: None,
+    };
+    let mut store = Store::new(engine, host);
+    store.limiter(|host| &mut host.limits);
+    store.set_fuel(limits.fuel).expect("the engine meters fuel");
+    store
+}
+
+/// The functions a module may import.
+fn host_linker(engine: &Engine) -> Result<Linker<Host>, wasmi::Error> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        "vidra",
+        "param",
+        |caller: Caller<'_, Host>, ptr: i32, len: i32| -> Result<f64, wasmi::Error> {
+            let name = guest_str(&caller, ptr, len)?;
+            let value = caller.data().params.get(&name);
+            Ok(value.and_then(|v| v.parse().ok()).unwrap_or(f64::NAN))
+        },
+    )?;
+    linker.func_wrap(
+        "vidra",
+        "param_text",
+        |mut caller: Caller<'_, Host>,
+         ptr: i32,
+         len: i32,
+         out: i32,
+         cap: i32|
+         -> Result<i32, wasmi::Error> {
+            let name = guest_str(&caller, ptr, len)?;
+            let Some(value) = caller.data().params.get(&name).cloned() else {
+                return Ok(-1);
+            };
+            let n = value.len().min(usize::try_from(cap).unwrap_or(0));
+            let out = usize::try_from(out).map_err(|_| out_of_bounds())?;
+            caller_memory(&caller)?.write(&mut caller, out, &value.as_bytes()[..n])?;
+            Ok(value.len() as i32)
+        },
+    )?;
+    linker.func_wrap(
+        "vidra",
+        "fail",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> Result<(), wasmi::Error> {
+            let memory = caller_memory(&caller)?;
+            let message = guest_bytes(memory.data(&caller), ptr, len)?;
+            caller.data_mut().error = Some(String::from_utf8_lossy(message).into_owned());
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+fn exported_memory(instance: &Instance, store: &Store<Host>) -> Result<Memory, wasmi::Error> {
+    instance
+        .get_memory(store, "memory")
+        .ok_or_else(|| wasmi::Error::new("it does not export memory"))
+}
+
+fn caller_memory(caller: &Caller<'_, Host>) -> Result<Memory, wasmi::Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("it does not export memory"))
+}
+
+fn guest_bytes(memory: &[u8], ptr: i32, len: i32) -> Result<&[u8], wasmi::Error> {
+    let start = usize::try_from(ptr).map_err(|_| out_of_bounds())?;
+    let len = usize::try_from(len).map_err(|_| out_of_bounds())?;
+    start
+        .checked_add(len)
+        .and_then(|end| memory.get(start..end))
+        .ok_or_else(out_of_bounds)
+}
+
+fn guest_str(caller: &Caller<'_, Host>, ptr: i32, len: i32) -> Result<String, wasmi::Error> {
+    let memory = caller_memory(caller)?;
+    let bytes = guest_bytes(memory.data(caller), ptr, len)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| wasmi::Error::new("a string is not UTF-8"))
+}
+
+fn out_of_bounds() -> wasmi::Error {
+    wasmi::Error::new("out of bounds memory access")
+}
+
+fn describe(error: &wasmi::Error, limits: &WasmLimits) -> String {
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => format!("it ran out of fuel ({} units)", limits.fuel),
+        _ => error.to_string(),
+    }
+}
+
+fn rgba(buffer: &FrameBuffer) -> Result<(), VidraError> {
+    match buffer.format {
+        PixelFormat::Rgba8 => Ok(()),
+        other => Err(VidraError::Plugin(format!(
+            "plugins take RGBA8 frames, not {:?}",
+            other
+        ))),
+    }
+}
+
+impl VidraPlugin for WasmPlugin {
+    fn manifest(&self) -> PluginManifest {
+        self.manifest.clone()
+    }
+
+    fn params(&self) -> &[&str] {
+        &self.params
+    }
+}
+
+impl EffectPlugin for WasmPlugin {
+    fn effect_name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&self, frame: &mut FrameBuffer, ctx: &EffectContext) -> Result<(), VidraError> {
+        rgba(frame)?;
+        let params = ctx
+            .params
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect();
+        let (width, height) = (frame.width as i32, frame.height as i32);
+        self.call(params, &mut frame.data, |instance, store, ptr| {
+            instance
+                .get_typed_func::<EffectFn, i32>(&*store, APPLY_EFFECT)?
+                .call(store, (ptr, width, height, ctx.time, ctx.fps))
+        })
+    }
+}
+
+impl LayerPlugin for WasmPlugin {
+    fn layer_type(&self) -> &str {
+        &self.name
+    }
+
+    fn render(&self, ctx: &LayerContext) -> Result<FrameBuffer, VidraError> {
+        let mut out = FrameBuffer::new(ctx.width, ctx.height, PixelFormat::Rgba8);
+        let (width, height) = (ctx.width as i32, ctx.height as i32);
+        self.call(ctx.params.clone(), &mut out.data, |instance, store, ptr| {
+            instance
+                .get_typed_func::<LayerFn, i32>(&*store, RENDER_LAYER)?
+                .call(
+                    store,
+                    (ptr, width, height, ctx.frame as i32, ctx.time, ctx.fps),
+                )
+        })?;
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A module with the given plugin info, a bump allocator at 1024, and `body`.
+    fn module(info: &str, body: &str) -> Vec<u8> {
+        let info = info.replace('"', "\\\"");
+        wat::parse_str(format!(
+            r#"(module
+                (import "vidra" "param" (func $param (param i32 i32) (result f64)))
+                (import "vidra" "param_text" (func $param_text (param i32 i32 i32 i32) (result i32)))
+                (import "vidra" "fail" (func $fail (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{info}\00")
+                (data (i32.const 512) "red")
+                (data (i32.const 600) "no red given")
+                (func (export "vidra_plugin_info") (result i32) (i32.const 0))
+                (func (export "vidra_alloc") (param $len i32) (result i32)
+                    (drop (memory.grow (i32.add (i32.div_u (local.get $len) (i32.const 65536)) (i32.const 1))))
+                    (i32.const 1024))
+                {body})"#
+        ))
+        .unwrap()
+    }
+
+    const REDDEN: &str = r#"(func (export "vidra_apply_effect")
+        (param $ptr i32) (param $w i32) (param $h i32) (param $time f64) (param $fps f64) (result i32)
+        (local $i i32) (local $end i32)
+        (local.set $end (i32.add (local.get $ptr) (i32.mul (i32.mul (local.get $w) (local.get $h)) (i32.const 4))))
+        (local.set $i (local.get $ptr))
+        (block $done (loop $next
+            (br_if $done (i32.ge_u (local.get $i) (local.get $end)))
+            (i32.store8 (local.get $i) (i32.trunc_sat_f64_u (call $param (i32.const 512) (i32.const 3))))
+            (local.set $i (i32.add (local.get $i) (i32.const 4)))
+            (br $next)))
+        (i32.const 0))"#;
+
+    const EFFECT_INFO: &str =
+        r#"{"abi_version":1,"kind":"effect","name":"redden","params":["red"]}"#;
+
+    fn effect_ctx(params: &[(&str, f64)]) -> EffectContext {
+        EffectContext {
+            width: 2,
+            height: 2,
+            time: 0.0,
+            fps: 30.0,
+            params: params.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_wasm_effect() {
+        let plugin = WasmPlugin::load(&module(EFFECT_INFO, REDDEN), WasmLimits::default()).unwrap();
+        assert_eq!(plugin.kind(), PluginKind::Effect);
+        assert_eq!(plugin.name(), "redden");
+        assert_eq!(VidraPlugin::params(&plugin), ["red"]);
+
+        let mut frame = FrameBuffer::new(2, 2, PixelFormat::Rgba8);
+        frame.data.fill(10);
+        plugin
+            .apply(&mut frame, &effect_ctx(&[("red", 200.0)]))
+            .unwrap();
+        assert_eq!(&frame.data[..8], [200, 10, 10, 10, 200, 10, 10, 10]);
+    }
+
+    #[test]
+    fn test_wasm_layer_text_params_and_failure() {
+        let body = r#"(func (export "vidra_render_layer")
+            (param $ptr i32) (param $w i32) (param $h i32) (param $frame i32)
+            (param $time f64) (param $fps f64) (result i32)
+            (if (i32.lt_s (call $param_text (i32.const 512) (i32.const 3) (local.get $ptr) (i32.const 1)) (i32.const 0))
+                (then (call $fail (i32.const 600) (i32.const 12)) (return (i32.const 1))))
+            (i32.const 0))"#;
+        let plugin = WasmPlugin::load(
+            &module(r#"{"abi_version":1,"kind":"layer","name":"tag"}"#, body),
+            WasmLimits::default(),
+        )
+        .unwrap();
+        let mut ctx = LayerContext {
+            width: 2,
+            height: 1,
+            frame: 0,
+            time: 0.0,
+            fps: 30.0,
+            params: HashMap::new(),
+        };
+        let err = plugin.render(&ctx).unwrap_err().to_string();
+        assert!(
+            err.contains("layer plugin 'tag' failed: no red given"),
+            "{}",
+            err
+        );
+
+        ctx.params.insert("red".to_string(), "#FF0000".to_string());
+        let out = plugin.render(&ctx).unwrap();
+        assert_eq!(out.data, [b'#', 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_wasm_fuel_limit() {
+        let body = r#"(func (export "vidra_apply_effect")
+            (param i32 i32 i32 f64 f64) (result i32)
+            (loop $forever (br $forever))
+            (i32.const 0))"#;
+        let limits = WasmLimits {
+            fuel: 100_000,
+            ..WasmLimits::default()
+        };
+        let plugin = WasmPlugin::load(&module(EFFECT_INFO, body), limits).unwrap();
+        let mut frame = FrameBuffer::new(2, 2, PixelFormat::Rgba8);
+        let err = plugin.apply(&mut frame, &effect_ctx(&[])).unwrap_err();
+        assert!(err.to_string().contains("ran out of fuel"), "{}", err);
+    }
+
+    #[test]
+    fn test_wasm_memory_limit() {
+        let limits = WasmLimits {
+            memory: 1 << 16,
+            ..WasmLimits::default()
+        };
+        let plugin = WasmPlugin::load(&module(EFFECT_INFO, REDDEN), limits).unwrap();
+        // The frame doesn't fit in the one page the allocator may have.
+        let mut frame = FrameBuffer::new(256, 256, PixelFormat::Rgba8);
+        assert!(plugin.apply(&mut frame, &effect_ctx(&[])).is_err());
+    }
+
+    #[test]
+    fn test_wasm_table_limit() {
+        let huge_table = module(
+            EFFECT_INFO,
+            &format!("(table 100000000 funcref) {}", REDDEN),
+        );
+        let err = match WasmPlugin::load(&huge_table, WasmLimits::default()) {
+            Ok(_) => panic!("loaded a module with a huge table"),
+            Err(e) => e.to_string(),
+        };
+        assert!(err.contains("table"), "{}", err);
+    }
+
+    #[test]
+    fn test_wasm_load_errors() {
+        let load = |wasm: &[u8]| match WasmPlugin::load(wasm, WasmLimits::default()) {
+            Ok(_) => panic!("loaded"),
+            Err(e) => e.to_string(),
+        };
+
+        let old = load(&module(
+            r#"{"abi_version":0,"kind":"effect","name":"redden"}"#,
+            REDDEN,
+        ));
+        assert!(old.contains("ABI v0"), "{}", old);
+
+        let no_entry = load(&module(
+            r#"{"abi_version":1,"kind":"layer","name":"x"}"#,
+            REDDEN,
+        ));
+        assert!(no_entry.contains("no entry point"), "{}", no_entry);
+
+        // Nothing outside the plugin interface can be imported.
+        let clock = wat::parse_str(
+            r#"(module (import "env" "clock" (func (result f64))) (memory (export "memory") 1))"#,
+        )
+        .unwrap();
+        assert!(load(&clock).contains("clock"));
+    }
+}
@@ -0,0 +1,56 @@
+//! The interface between Vidra and sandboxed WebAssembly plugins.
+//!
+//! A WebAssembly plugin is a module run by an interpreter inside Vidra. Unlike a
+//! [dynamic library](super::abi) it can only reach its own memory and the host functions
+//! below, and every call runs within [`WasmLimits`], with one memory and one table of
+//! at most 65536 entries. Each call also gets a fresh
+//! instance, so a plugin's output depends only on its inputs and a frame renders the
+//! same every time, which keeps content hashes meaningful.
+//!
+//! The module exports:
+//!
+//! - `memory`, its linear memory.
+//! - `vidra_plugin_info() -> i32`, the address of a NUL-terminated JSON object
+//!   describing the plugin: `abi_version` (which must be [`ABI_VERSION`]), `kind`
+//!   (`"effect"` or `"layer"`), `name`, and optionally `params` (a list of names), `id`,
+//!   `display_name`, `version`, `author` and `description`.
+//! - `vidra_alloc(len: i32) -> i32`, the address of `len` bytes the host may write the
+//!   frame to.
+//! - For an effect, `vidra_apply_effect(ptr: i32, width: i32, height: i32, time: f64,
+//!   fps: f64) -> i32`, which changes the RGBA8 frame at `ptr` in place.
+//! - For a layer, `vidra_render_layer(ptr: i32, width: i32, height: i32, frame: i32,
+//!   time: f64, fps: f64) -> i32`, which draws into the transparent RGBA8 frame at `ptr`.
+//!
+//! The entry points return 0 on success. The module may import these from `vidra`, and
+//! nothing else:
+//!
+//! - `param(name_ptr: i32, name_len: i32) -> f64`, a parameter as a number, or NaN if it
+//!   is not set or not a number.
+//! - `param_text(name_ptr: i32, name_len: i32, out_ptr: i32, out_cap: i32) -> i32`,
+//!   which copies up to `out_cap` bytes of a parameter's text to `out_ptr` and returns
+//!   its full length, or -1 if it is not set.
+//! - `fail(msg_ptr: i32, msg_len: i32)`, which sets the error reported when the call
+//!   fails.
+
+use serde::{Deserialize, Serialize};
+
+/// Version of this interface. Modules built for another version are not loaded.
+pub const ABI_VERSION: u32 = 1;
+
+/// What a WebAssembly plugin may use in a single call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmLimits {
+    /// Fuel for one call, about one unit per instruction executed.
+    pub fuel: u64,
+    /// Most bytes of linear memory the module may have.
+    pub memory: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 4_000_000_000,
+            memory: 256 << 20,
+        }
+    }
+}
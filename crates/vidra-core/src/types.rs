@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{Color, Point2D};
+
 /// The kind of content a layer holds.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LayerType {
@@ -27,6 +29,8 @@ pub enum LayerType {
     Shader,
     /// Web scene captured via browser.
     Web,
+    /// Content drawn by a layer plugin.
+    Plugin,
 }
 
 impl std::fmt::Display for LayerType {
@@ -44,6 +48,7 @@ impl std::fmt::Display for LayerType {
             LayerType::AutoCaption => write!(f, "autocaption"),
             LayerType::Shader => write!(f, "shader"),
             LayerType::Web => write!(f, "web"),
+            LayerType::Plugin => write!(f, "plugin"),
         }
     }
 }
@@ -76,17 +81,125 @@ pub enum LayerEffect {
     ///
     /// `path` may be a local path or an http(s) URL (the CLI/dev server can cache remote URLs).
     Lut { path: String, intensity: f64 },
+
+    /// An effect provided by a registered `EffectPlugin`, looked up by name at render time.
+    Plugin {
+        name: String,
+        params: std::collections::HashMap<String, f64>,
+    },
+
+    /// The layer's silhouette drawn beneath it: grown by `spread` pixels, blurred over
+    /// `blur` pixels and moved by `offset`.
+    DropShadow {
+        offset: Point2D,
+        blur: f64,
+        color: Color,
+        spread: f64,
+    },
+    /// The layer's silhouette grown by `spread` pixels and blurred over `blur` pixels,
+    /// drawn beneath it.
+    OuterGlow {
+        blur: f64,
+        color: Color,
+        spread: f64,
+    },
+    /// A shadow inside the layer, cast by its edges as if it were cut out: the area
+    /// around the layer grown by `spread` pixels, blurred over `blur` pixels and moved by
+    /// `offset`.
+    InnerShadow {
+        offset: Point2D,
+        blur: f64,
+        color: Color,
+        spread: f64,
+    },
+    /// An outline `width` pixels wide along the edge of the layer's alpha.
+    Stroke {
+        width: f64,
+        color: Color,
+        position: StrokePosition,
+    },
+    /// Makes the layer look raised: its edges slope over `size` pixels, `depth` times as
+    /// steep as 45°, lit from `angle` degrees (counter-clockwise from the right) with
+    /// `highlight` on the slopes facing the light and `shadow` on the others.
+    Bevel {
+        size: f64,
+        depth: f64,
+        angle: f64,
+        highlight: Color,
+        shadow: Color,
+    },
+}
+
+/// Where a [`LayerEffect::Stroke`] is drawn relative to the layer's edge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StrokePosition {
+    #[default]
+    Outside,
+    Inside,
+    /// Half inside, half outside.
+    Center,
 }
 
 /// Blend mode for layer compositing.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Apart from `Add`, these are the blend modes of the W3C Compositing and Blending
+/// spec; see [`crate::blend`] for the formulas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BlendMode {
     /// Standard alpha blending (Porter-Duff "over").
     Normal,
     Multiply,
     Screen,
     Overlay,
+    /// Sum of both colors, clamped to white (linear dodge).
     Add,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    /// Hue of the layer with the saturation and luminosity of the backdrop.
+    Hue,
+    /// Saturation of the layer with the hue and luminosity of the backdrop.
+    Saturation,
+    /// Hue and saturation of the layer with the luminosity of the backdrop.
+    Color,
+    /// Luminosity of the layer with the hue and saturation of the backdrop.
+    Luminosity,
+}
+
+impl BlendMode {
+    /// Every blend mode, in declaration order.
+    pub const ALL: [BlendMode; 17] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::Add,
+        BlendMode::Darken,
+        BlendMode::Lighten,
+        BlendMode::ColorDodge,
+        BlendMode::ColorBurn,
+        BlendMode::HardLight,
+        BlendMode::SoftLight,
+        BlendMode::Difference,
+        BlendMode::Exclusion,
+        BlendMode::Hue,
+        BlendMode::Saturation,
+        BlendMode::Color,
+        BlendMode::Luminosity,
+    ];
+
+    /// Whether each color channel blends independently of the others.
+    pub fn is_separable(&self) -> bool {
+        !matches!(
+            self,
+            BlendMode::Hue | BlendMode::Saturation | BlendMode::Color | BlendMode::Luminosity
+        )
+    }
 }
 
 impl Default for BlendMode {
@@ -95,8 +208,57 @@ impl Default for BlendMode {
     }
 }
 
+/// Motion blur settings: how long the virtual shutter stays open during a frame and
+/// how many sub-frame samples are averaged while it is.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MotionBlur {
+    /// Shutter angle in degrees; 360° keeps the shutter open for the whole frame.
+    pub shutter_angle: f64,
+    /// Number of sub-frame samples per frame.
+    pub samples: u32,
+}
+
+impl MotionBlur {
+    /// The film-standard 180° shutter.
+    pub const DEFAULT_SHUTTER_ANGLE: f64 = 180.0;
+    pub const DEFAULT_SAMPLES: u32 = 8;
+
+    pub fn new(shutter_angle: f64, samples: u32) -> Self {
+        Self {
+            shutter_angle,
+            samples,
+        }
+    }
+
+    /// Settings that turn motion blur off (e.g. for one layer of a blurred project).
+    pub fn off() -> Self {
+        Self::new(0.0, 1)
+    }
+
+    /// Whether these settings take more than one sample over an open shutter.
+    pub fn is_enabled(&self) -> bool {
+        self.samples > 1 && self.shutter_angle > 0.0
+    }
+
+    /// Time of each sample relative to the frame, in frames. Samples are spread evenly
+    /// over the open shutter, centered on the frame time.
+    pub fn sample_offsets(&self) -> Vec<f64> {
+        let open = self.shutter_angle.clamp(0.0, 360.0) / 360.0;
+        let n = self.samples.max(1);
+        (0..n)
+            .map(|i| open * ((i as f64 + 0.5) / n as f64 - 0.5))
+            .collect()
+    }
+}
+
+impl Default for MotionBlur {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SHUTTER_ANGLE, Self::DEFAULT_SAMPLES)
+    }
+}
+
 /// Easing function for animation interpolation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Easing {
     Linear,
     EaseIn,
@@ -106,6 +268,48 @@ pub enum Easing {
     CubicOut,
     CubicInOut,
     EaseOutBack,
+    EaseInBack,
+    EaseInOutBack,
+    EaseInQuart,
+    EaseOutQuart,
+    EaseInOutQuart,
+    EaseInQuint,
+    EaseOutQuint,
+    EaseInOutQuint,
+    EaseInSine,
+    EaseOutSine,
+    EaseInOutSine,
+    EaseInExpo,
+    EaseOutExpo,
+    EaseInOutExpo,
+    EaseInCirc,
+    EaseOutCirc,
+    EaseInOutCirc,
+    EaseInElastic,
+    EaseOutElastic,
+    EaseInOutElastic,
+    EaseInBounce,
+    EaseOutBounce,
+    EaseInOutBounce,
+    /// CSS `cubic-bezier(x1, y1, x2, y2)`: a Bézier curve from (0, 0) to (1, 1) with
+    /// control points (x1, y1) and (x2, y2). The x values are clamped to [0, 1].
+    CubicBezier(f64, f64, f64, f64),
+    /// CSS `steps(n, position)`: n equal steps instead of a smooth change.
+    Steps(u32, StepPosition),
+}
+
+/// Where the jumps of a [`Easing::Steps`] easing happen, as in CSS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StepPosition {
+    /// The first jump happens at the start.
+    JumpStart,
+    /// The last jump happens at the end.
+    #[default]
+    JumpEnd,
+    /// No jump at either end: the steps are spread between the start and end values.
+    JumpNone,
+    /// Jumps at both ends.
+    JumpBoth,
 }
 
 impl Default for Easing {
@@ -117,8 +321,10 @@ impl Default for Easing {
 impl Easing {
     /// Apply the easing function to a normalized time value t in [0, 1].
     pub fn apply(&self, t: f64) -> f64 {
+        use std::f64::consts::PI;
+
         let t = t.clamp(0.0, 1.0);
-        match self {
+        match *self {
             Easing::Linear => t,
             Easing::EaseIn => t * t,
             Easing::EaseOut => t * (2.0 - t),
@@ -142,14 +348,136 @@ impl Easing {
                     0.5 * t1 * t1 * t1 + 1.0
                 }
             }
-            Easing::EaseOutBack => {
+            Easing::EaseOutBack => 1.0 - Easing::EaseInBack.apply(1.0 - t),
+            Easing::EaseInBack => {
                 let c1 = 1.70158;
-                let c3 = c1 + 1.0;
-                let t1 = t - 1.0;
-                1.0 + c3 * (t1 * t1 * t1) + c1 * (t1 * t1)
+                (c1 + 1.0) * t * t * t - c1 * t * t
+            }
+            Easing::EaseInOutBack => in_out(t, |t| {
+                let c2 = 1.70158 * 1.525;
+                (c2 + 1.0) * t * t * t - c2 * t * t
+            }),
+            Easing::EaseInQuart => t.powi(4),
+            Easing::EaseOutQuart => 1.0 - (1.0 - t).powi(4),
+            Easing::EaseInOutQuart => in_out(t, |t| t.powi(4)),
+            Easing::EaseInQuint => t.powi(5),
+            Easing::EaseOutQuint => 1.0 - (1.0 - t).powi(5),
+            Easing::EaseInOutQuint => in_out(t, |t| t.powi(5)),
+            Easing::EaseInSine => 1.0 - (t * PI / 2.0).cos(),
+            Easing::EaseOutSine => (t * PI / 2.0).sin(),
+            Easing::EaseInOutSine => (1.0 - (t * PI).cos()) / 2.0,
+            Easing::EaseInExpo => expo_in(t),
+            Easing::EaseOutExpo => 1.0 - expo_in(1.0 - t),
+            Easing::EaseInOutExpo => in_out(t, expo_in),
+            Easing::EaseInCirc => 1.0 - (1.0 - t * t).sqrt(),
+            Easing::EaseOutCirc => (1.0 - (t - 1.0) * (t - 1.0)).sqrt(),
+            Easing::EaseInOutCirc => in_out(t, |t| 1.0 - (1.0 - t * t).sqrt()),
+            Easing::EaseInElastic => elastic_in(t, 0.3),
+            Easing::EaseOutElastic => 1.0 - elastic_in(1.0 - t, 0.3),
+            Easing::EaseInOutElastic => in_out(t, |t| elastic_in(t, 0.45)),
+            Easing::EaseInBounce => 1.0 - bounce_out(1.0 - t),
+            Easing::EaseOutBounce => bounce_out(t),
+            Easing::EaseInOutBounce => in_out(t, |t| 1.0 - bounce_out(1.0 - t)),
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                cubic_bezier(x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2, t)
             }
+            Easing::Steps(steps, position) => {
+                let steps = steps.max(1);
+                let mut step = (t * steps as f64).floor();
+                if matches!(position, StepPosition::JumpStart | StepPosition::JumpBoth) {
+                    step += 1.0;
+                }
+                let jumps = match position {
+                    StepPosition::JumpStart | StepPosition::JumpEnd => steps,
+                    StepPosition::JumpNone => steps.saturating_sub(1).max(1),
+                    StepPosition::JumpBoth => steps + 1,
+                } as f64;
+                step.min(jumps) / jumps
+            }
+        }
+    }
+}
+
+/// The in-out form of an ease-in curve: the curve over the first half, mirrored over
+/// the second.
+fn in_out(t: f64, ease_in: impl Fn(f64) -> f64) -> f64 {
+    if t < 0.5 {
+        ease_in(2.0 * t) / 2.0
+    } else {
+        1.0 - ease_in(2.0 - 2.0 * t) / 2.0
+    }
+}
+
+fn expo_in(t: f64) -> f64 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        (2.0f64).powf(10.0 * t - 10.0)
+    }
+}
+
+/// A decaying sine wave of the given period, ending at 1.
+fn elastic_in(t: f64, period: f64) -> f64 {
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+    let shift = period / 4.0;
+    -(2.0f64).powf(10.0 * t - 10.0) * ((t - 1.0 - shift) * std::f64::consts::TAU / period).sin()
+}
+
+fn bounce_out(t: f64) -> f64 {
+    const N: f64 = 7.5625;
+    const D: f64 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// The y of the unit cubic Bézier at x, solving for the curve parameter with Newton's
+/// method and falling back to bisection where the slope is too flat.
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    let curve = |a: f64, b: f64, s: f64| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * a + 3.0 * r * s * s * b + s * s * s
+    };
+    let slope = |a: f64, b: f64, s: f64| {
+        let r = 1.0 - s;
+        3.0 * r * r * a + 6.0 * r * s * (b - a) + 3.0 * s * s * (1.0 - b)
+    };
+
+    let mut s = x;
+    for _ in 0..8 {
+        let error = curve(x1, x2, s) - x;
+        if error.abs() < 1e-9 {
+            return curve(y1, y2, s);
+        }
+        let d = slope(x1, x2, s);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        s -= error / d;
+    }
+
+    let (mut lo, mut hi) = (0.0, 1.0);
+    s = x;
+    for _ in 0..60 {
+        if curve(x1, x2, s) < x {
+            lo = s;
+        } else {
+            hi = s;
         }
+        s = (lo + hi) / 2.0;
     }
+    curve(y1, y2, s)
 }
 
 /// Shape variant for shape layers.
@@ -167,6 +495,8 @@ pub enum ShapeType {
         rx: f64,
         ry: f64,
     },
+    /// A Bézier path, placed so its bounds start at the layer origin.
+    Path(crate::path::PathShape),
 }
 
 /// A Brand Kit containing predefined styling rules and assets.
@@ -231,9 +561,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_penner_easings() {
+        let family = [
+            Easing::EaseInBack,
+            Easing::EaseOutBack,
+            Easing::EaseInOutBack,
+            Easing::EaseInQuart,
+            Easing::EaseOutQuart,
+            Easing::EaseInOutQuart,
+            Easing::EaseInQuint,
+            Easing::EaseOutQuint,
+            Easing::EaseInOutQuint,
+            Easing::EaseInSine,
+            Easing::EaseOutSine,
+            Easing::EaseInOutSine,
+            Easing::EaseInExpo,
+            Easing::EaseOutExpo,
+            Easing::EaseInOutExpo,
+            Easing::EaseInCirc,
+            Easing::EaseOutCirc,
+            Easing::EaseInOutCirc,
+            Easing::EaseInElastic,
+            Easing::EaseOutElastic,
+            Easing::EaseInOutElastic,
+            Easing::EaseInBounce,
+            Easing::EaseOutBounce,
+            Easing::EaseInOutBounce,
+        ];
+        for easing in family {
+            assert!(
+                easing.apply(0.0).abs() < 1e-9,
+                "{:?} should start at 0",
+                easing
+            );
+            assert!(
+                (easing.apply(1.0) - 1.0).abs() < 1e-9,
+                "{:?} should end at 1",
+                easing
+            );
+            // No jumps, including where the in-out forms switch halves.
+            for i in 0..100 {
+                let (a, b) = (i as f64 / 100.0, (i + 1) as f64 / 100.0);
+                assert!(
+                    (easing.apply(b) - easing.apply(a)).abs() < 0.2,
+                    "{:?} jumps between {} and {}",
+                    easing,
+                    a,
+                    b
+                );
+            }
+        }
+
+        assert!((Easing::EaseInExpo.apply(0.5) - 0.03125).abs() < 1e-9);
+        assert!((Easing::EaseOutBounce.apply(0.5) - 0.765625).abs() < 1e-9);
+        assert!((Easing::EaseInOutSine.apply(0.5) - 0.5).abs() < 1e-9);
+        assert!(Easing::EaseInBack.apply(0.2) < 0.0);
+        assert!(Easing::EaseOutElastic.apply(0.2) > 1.0);
+    }
+
+    #[test]
+    fn test_cubic_bezier_easing() {
+        let linear = Easing::CubicBezier(0.0, 0.0, 1.0, 1.0);
+        let ease = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+        for i in 0..=10 {
+            let t = i as f64 / 10.0;
+            assert!((linear.apply(t) - t).abs() < 1e-6);
+        }
+        // CSS `ease` at its midpoint.
+        assert!((ease.apply(0.5) - 0.8024).abs() < 1e-3);
+        assert!((ease.apply(1.0) - 1.0).abs() < 1e-9);
+
+        // Overshooting control points, and x values outside [0, 1] clamped.
+        let overshoot = Easing::CubicBezier(0.3, 1.5, 0.7, 1.5);
+        assert!(overshoot.apply(0.5) > 1.0);
+        assert_eq!(
+            Easing::CubicBezier(-1.0, 0.0, 2.0, 1.0).apply(0.3),
+            Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(0.3)
+        );
+    }
+
+    #[test]
+    fn test_steps_easing() {
+        let values =
+            |position| [0.0, 0.2, 0.5, 0.99, 1.0].map(|t| Easing::Steps(4, position).apply(t));
+        assert_eq!(values(StepPosition::JumpEnd), [0.0, 0.0, 0.5, 0.75, 1.0]);
+        assert_eq!(
+            values(StepPosition::JumpStart),
+            [0.25, 0.25, 0.75, 1.0, 1.0]
+        );
+        assert_eq!(
+            values(StepPosition::JumpNone),
+            [0.0, 0.0, 2.0 / 3.0, 1.0, 1.0]
+        );
+        assert_eq!(values(StepPosition::JumpBoth), [0.2, 0.2, 0.6, 0.8, 1.0]);
+    }
+
+    #[test]
+    fn test_easing_serialization() {
+        let json = serde_json::to_string(&Easing::Steps(3, StepPosition::JumpStart)).unwrap();
+        assert_eq!(json, r#"{"Steps":[3,"JumpStart"]}"#);
+        let bezier: Easing = serde_json::from_str(r#"{"CubicBezier":[0.4,0.0,0.2,1.0]}"#).unwrap();
+        assert_eq!(bezier, Easing::CubicBezier(0.4, 0.0, 0.2, 1.0));
+        let preset: Easing = serde_json::from_str(r#""EaseOutBounce""#).unwrap();
+        assert_eq!(preset, Easing::EaseOutBounce);
+    }
+
     #[test]
     fn test_layer_type_display() {
         assert_eq!(format!("{}", LayerType::Text), "text");
         assert_eq!(format!("{}", LayerType::Solid), "solid");
     }
+
+    #[test]
+    fn test_motion_blur_sample_offsets() {
+        let offsets = MotionBlur::new(180.0, 4).sample_offsets();
+        assert_eq!(offsets, vec![-0.1875, -0.0625, 0.0625, 0.1875]);
+        assert!(MotionBlur::default().is_enabled());
+        assert!(!MotionBlur::off().is_enabled());
+        assert_eq!(MotionBlur::off().sample_offsets(), vec![0.0]);
+    }
 }
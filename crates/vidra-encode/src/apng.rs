@@ -1,13 +1,22 @@
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use vidra_core::frame::FrameBuffer;
+use vidra_core::frame::{FrameBuffer, FrameSink};
 use vidra_core::VidraError;
 
 /// Native APNG (Animated PNG) encoder using the `png` crate.
 /// Lossless animation format ideal for stickers, UI animations, and high-quality shorts.
-pub struct ApngEncoder;
+pub struct ApngEncoder {
+    writer: Option<png::Writer<BufWriter<File>>>,
+    width: u32,
+    height: u32,
+    fps: f64,
+    delay_den: u16,
+    frame_count: u32,
+    output_path: PathBuf,
+    frames_written: u32,
+}
 
 impl ApngEncoder {
     /// Encode a sequence of RGBA frame buffers to an Animated PNG (APNG).
@@ -31,6 +40,37 @@ impl ApngEncoder {
             return Err(VidraError::Encode("no frames to encode for APNG".into()));
         }
 
+        let mut encoder = Self::open(
+            width,
+            height,
+            fps,
+            output_path,
+            loop_count,
+            frames.len() as u32,
+        )?;
+        for frame in frames {
+            encoder.write_frame(frame.clone())?;
+        }
+        encoder.finish()
+    }
+
+    /// Start a streaming APNG encode.
+    ///
+    /// The APNG header records the number of frames, so `frame_count` must be
+    /// known up front and exactly that many frames must be written before
+    /// [`FrameSink::finish`].
+    pub fn open(
+        width: u32,
+        height: u32,
+        fps: f64,
+        output_path: &Path,
+        loop_count: Option<u32>,
+        frame_count: u32,
+    ) -> Result<Self, VidraError> {
+        if frame_count == 0 {
+            return Err(VidraError::Encode("no frames to encode for APNG".into()));
+        }
+
         if let Some(parent) = output_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
@@ -41,52 +81,89 @@ impl ApngEncoder {
 
         // Frame delay: numerator / denominator seconds
         // e.g., 30fps => each frame is 1/30 seconds
-        let delay_num = 1u16;
         let delay_den = fps.round() as u16;
 
         let mut encoder = png::Encoder::new(writer, width, height);
         encoder.set_color(png::ColorType::Rgba);
         encoder.set_depth(png::BitDepth::Eight);
         encoder
-            .set_animated(frames.len() as u32, loop_count.unwrap_or(0))
+            .set_animated(frame_count, loop_count.unwrap_or(0))
             .map_err(|e| VidraError::Encode(format!("failed to set APNG animation: {}", e)))?;
         encoder
-            .set_frame_delay(delay_num, delay_den)
+            .set_frame_delay(1, delay_den)
             .map_err(|e| VidraError::Encode(format!("failed to set APNG frame delay: {}", e)))?;
 
-        let mut writer = encoder
+        let writer = encoder
             .write_header()
             .map_err(|e| VidraError::Encode(format!("failed to write APNG header: {}", e)))?;
 
-        for (i, frame) in frames.iter().enumerate() {
-            if frame.width != width || frame.height != height {
-                return Err(VidraError::Encode(format!(
-                    "frame {} has dimensions {}x{}, expected {}x{}",
-                    i, frame.width, frame.height, width, height
-                )));
-            }
-
-            // Set per-frame delay for consistent timing
-            writer.set_frame_delay(delay_num, delay_den).map_err(|e| {
-                VidraError::Encode(format!("failed to set delay on frame {}: {}", i, e))
-            })?;
+        Ok(Self {
+            writer: Some(writer),
+            width,
+            height,
+            fps,
+            delay_den,
+            frame_count,
+            output_path: output_path.to_path_buf(),
+            frames_written: 0,
+        })
+    }
+}
 
-            writer.write_image_data(&frame.data).map_err(|e| {
-                VidraError::Encode(format!("failed to write APNG frame {}: {}", i, e))
-            })?;
+impl FrameSink for ApngEncoder {
+    fn write_frame(&mut self, frame: FrameBuffer) -> Result<(), VidraError> {
+        let i = self.frames_written;
+        if frame.width != self.width || frame.height != self.height {
+            return Err(VidraError::Encode(format!(
+                "frame {} has dimensions {}x{}, expected {}x{}",
+                i, frame.width, frame.height, self.width, self.height
+            )));
+        }
+        if i >= self.frame_count {
+            return Err(VidraError::Encode(format!(
+                "APNG was opened for {} frames, got frame {}",
+                self.frame_count, i
+            )));
         }
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or_else(|| VidraError::Encode("APNG encoder already finished".into()))?;
+
+        // Set per-frame delay for consistent timing
+        writer.set_frame_delay(1, self.delay_den).map_err(|e| {
+            VidraError::Encode(format!("failed to set delay on frame {}: {}", i, e))
+        })?;
+
+        writer
+            .write_image_data(&frame.data)
+            .map_err(|e| VidraError::Encode(format!("failed to write APNG frame {}: {}", i, e)))?;
+        self.frames_written += 1;
+        Ok(())
+    }
 
+    fn finish(&mut self) -> Result<(), VidraError> {
+        if self.frames_written != self.frame_count {
+            return Err(VidraError::Encode(format!(
+                "APNG was opened for {} frames but only {} were written",
+                self.frame_count, self.frames_written
+            )));
+        }
+        let writer = self
+            .writer
+            .take()
+            .ok_or_else(|| VidraError::Encode("APNG encoder already finished".into()))?;
         writer
             .finish()
             .map_err(|e| VidraError::Encode(format!("failed to finalize APNG: {}", e)))?;
 
         tracing::info!(
             "Encoded {} frames to APNG at {} ({}x{} @ {}fps)",
-            frames.len(),
-            output_path.display(),
-            width,
-            height,
-            fps,
+            self.frames_written,
+            self.output_path.display(),
+            self.width,
+            self.height,
+            self.fps,
         );
 
         Ok(())
@@ -125,4 +202,14 @@ mod tests {
 
         let _ = std::fs::remove_file(&out);
     }
+
+    #[test]
+    fn test_apng_streaming_rejects_short_write() {
+        let out = std::env::temp_dir().join("vidra_test_apng_short.png");
+        let mut sink = ApngEncoder::open(2, 2, 10.0, &out, None, 3).unwrap();
+        sink.write_frame(FrameBuffer::new(2, 2, vidra_core::PixelFormat::Rgba8))
+            .unwrap();
+        assert!(sink.finish().is_err());
+        let _ = std::fs::remove_file(&out);
+    }
 }
@@ -1,8 +1,8 @@
 use std::io::Write;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
 
-use vidra_core::frame::FrameBuffer;
+use vidra_core::frame::{FrameBuffer, FrameSink};
 use vidra_core::VidraError;
 
 /// An audio clip to be conceptually mixed into the final video file.
@@ -28,8 +28,117 @@ enum HwEncoder {
     Libx264,
 }
 
+/// A running FFmpeg process fed raw RGBA frames over stdin.
+///
+/// Shared by the MP4 and WebM encoders.
+pub(crate) struct RawVideoPipe {
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    frames_written: u64,
+}
+
+impl RawVideoPipe {
+    /// Spawn `cmd` with a piped stdin.
+    pub(crate) fn spawn(mut cmd: Command, width: u32, height: u32) -> Result<Self, VidraError> {
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| VidraError::Encode(format!("failed to start ffmpeg: {}", e)))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| VidraError::Encode("failed to open ffmpeg stdin".into()))?;
+
+        Ok(Self {
+            child: Some(child),
+            stdin: Some(stdin),
+            width,
+            height,
+            frames_written: 0,
+        })
+    }
+
+    pub(crate) fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Write one frame of raw RGBA data to FFmpeg's stdin.
+    pub(crate) fn write(&mut self, frame: &FrameBuffer) -> Result<(), VidraError> {
+        let i = self.frames_written;
+        if frame.width != self.width || frame.height != self.height {
+            return Err(VidraError::Encode(format!(
+                "frame {} has dimensions {}x{}, expected {}x{}",
+                i, frame.width, frame.height, self.width, self.height
+            )));
+        }
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| VidraError::Encode("ffmpeg stdin already closed".into()))?;
+        if let Err(e) = stdin.write_all(&frame.data) {
+            // If write fails, wait for the child process to get its stderr instead of just returning broken pipe.
+            self.stdin = None;
+            let stderr = match self.child.take().map(|c| c.wait_with_output()) {
+                Some(Ok(output)) => String::from_utf8_lossy(&output.stderr).into_owned(),
+                _ => String::new(),
+            };
+            return Err(VidraError::Encode(format!(
+                "failed to write frame {} to ffmpeg: {}. FFmpeg stderr: {}",
+                i, e, stderr
+            )));
+        }
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    /// Close stdin and wait for FFmpeg to finish. `label` names the codec in errors.
+    pub(crate) fn finish(&mut self, label: &str) -> Result<(), VidraError> {
+        // Close stdin to signal end of input
+        self.stdin = None;
+
+        let Some(child) = self.child.take() else {
+            return Err(VidraError::Encode("ffmpeg process already finished".into()));
+        };
+        let output = child
+            .wait_with_output()
+            .map_err(|e| VidraError::Encode(format!("ffmpeg process error: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(VidraError::Encode(format!(
+                "{} failed with status {}: {}",
+                label, output.status, stderr
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RawVideoPipe {
+    fn drop(&mut self) {
+        // An unfinished pipe means the encode was abandoned; don't leave ffmpeg running.
+        self.stdin = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
 /// Encoder that shells out to FFmpeg for H.264 encoding.
-pub struct FfmpegEncoder;
+///
+/// Either call [`FfmpegEncoder::encode`] with every frame up front, or
+/// [`FfmpegEncoder::open`] a streaming session and feed it through [`FrameSink`].
+pub struct FfmpegEncoder {
+    pipe: RawVideoPipe,
+    output_path: PathBuf,
+    fps: f64,
+}
 
 impl FfmpegEncoder {
     /// Check if FFmpeg is available on the system.
@@ -143,6 +252,22 @@ impl FfmpegEncoder {
             return Err(VidraError::Encode("no frames to encode".into()));
         }
 
+        let mut encoder = Self::open(audio_tracks, width, height, fps, output_path)?;
+        for frame in frames {
+            encoder.pipe.write(frame)?;
+        }
+        encoder.finish()
+    }
+
+    /// Start a streaming H.264 encode. Frames are written as they arrive via
+    /// [`FrameSink::write_frame`]; call [`FrameSink::finish`] to finalize the file.
+    pub fn open(
+        audio_tracks: &[AudioTrack],
+        width: u32,
+        height: u32,
+        fps: f64,
+        output_path: &Path,
+    ) -> Result<Self, VidraError> {
         if !Self::is_available() {
             return Err(VidraError::Encode(
                 "ffmpeg not found in PATH. Install FFmpeg: https://ffmpeg.org/download.html".into(),
@@ -154,8 +279,6 @@ impl FfmpegEncoder {
             std::fs::create_dir_all(parent)?;
         }
 
-        let fps_str = format!("{}", fps);
-
         let mut cmd = Command::new("ffmpeg");
         cmd.arg("-y"); // Overwrite output
 
@@ -168,7 +291,7 @@ impl FfmpegEncoder {
             "-video_size",
             &format!("{}x{}", width, height),
             "-framerate",
-            &fps_str,
+            &format!("{}", fps),
             "-i",
             "-",
         ]);
@@ -249,60 +372,32 @@ impl FfmpegEncoder {
 
         cmd.arg(output_path);
 
-        let mut child = cmd
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| VidraError::Encode(format!("failed to start ffmpeg: {}", e)))?;
-
-        let mut stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| VidraError::Encode("failed to open ffmpeg stdin".into()))?;
-
-        // Write raw RGBA frame data to FFmpeg's stdin
-        for (i, frame) in frames.iter().enumerate() {
-            if frame.width != width || frame.height != height {
-                return Err(VidraError::Encode(format!(
-                    "frame {} has dimensions {}x{}, expected {}x{}",
-                    i, frame.width, frame.height, width, height
-                )));
-            }
-            if let Err(e) = stdin.write_all(&frame.data) {
-                // If write fails, wait for the child process to get its stderr instead of just returning broken pipe.
-                let output = child.wait_with_output().unwrap();
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(VidraError::Encode(format!(
-                    "failed to write frame {} to ffmpeg: {}. FFmpeg stderr: {}",
-                    i, e, stderr
-                )));
-            }
-        }
-
-        // Close stdin to signal end of input
-        drop(stdin);
+        Ok(Self {
+            pipe: RawVideoPipe::spawn(cmd, width, height)?,
+            output_path: output_path.to_path_buf(),
+            fps,
+        })
+    }
+}
 
-        // Wait for FFmpeg to finish
-        let output = child
-            .wait_with_output()
-            .map_err(|e| VidraError::Encode(format!("ffmpeg process error: {}", e)))?;
+impl FrameSink for FfmpegEncoder {
+    fn write_frame(&mut self, frame: FrameBuffer) -> Result<(), VidraError> {
+        self.pipe.write(&frame)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(VidraError::Encode(format!(
-                "ffmpeg failed with status {}: {}",
-                output.status, stderr
-            )));
+    fn finish(&mut self) -> Result<(), VidraError> {
+        if self.pipe.frames_written() == 0 {
+            return Err(VidraError::Encode("no frames to encode".into()));
         }
+        self.pipe.finish("ffmpeg")?;
 
         tracing::info!(
             "Encoded {} frames to {} ({}x{} @ {}fps)",
-            frames.len(),
-            output_path.display(),
-            width,
-            height,
-            fps
+            self.pipe.frames_written(),
+            self.output_path.display(),
+            self.pipe.width,
+            self.pipe.height,
+            self.fps
         );
 
         Ok(())
@@ -1,13 +1,22 @@
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use vidra_core::frame::FrameBuffer;
+use vidra_core::frame::{FrameBuffer, FrameSink};
 use vidra_core::VidraError;
 
 /// Native GIF encoder using the `image` crate.
 /// Ideal for short clips, stickers, and social media content.
-pub struct GifEncoder;
+pub struct GifEncoder {
+    // Taken on finish; dropping the encoder writes the GIF trailer.
+    encoder: Option<image::codecs::gif::GifEncoder<BufWriter<File>>>,
+    width: u32,
+    height: u32,
+    fps: f64,
+    delay_cs: u16,
+    output_path: PathBuf,
+    frames_written: u64,
+}
 
 impl GifEncoder {
     /// Encode a sequence of RGBA frame buffers to an animated GIF.
@@ -31,6 +40,22 @@ impl GifEncoder {
             return Err(VidraError::Encode("no frames to encode for GIF".into()));
         }
 
+        let mut encoder = Self::open(width, height, fps, output_path, loop_count)?;
+        for frame in frames {
+            encoder.write_frame(frame.clone())?;
+        }
+        encoder.finish()
+    }
+
+    /// Start a streaming GIF encode. Each frame is quantized and written as it
+    /// arrives via [`FrameSink::write_frame`].
+    pub fn open(
+        width: u32,
+        height: u32,
+        fps: f64,
+        output_path: &Path,
+        loop_count: Option<u16>,
+    ) -> Result<Self, VidraError> {
         // Ensure output directory exists
         if let Some(parent) = output_path.parent() {
             std::fs::create_dir_all(parent)?;
@@ -54,36 +79,61 @@ impl GifEncoder {
             .set_repeat(repeat)
             .map_err(|e| VidraError::Encode(format!("failed to set GIF repeat: {}", e)))?;
 
-        for (i, frame) in frames.iter().enumerate() {
-            if frame.width != width || frame.height != height {
-                return Err(VidraError::Encode(format!(
-                    "frame {} has dimensions {}x{}, expected {}x{}",
-                    i, frame.width, frame.height, width, height
-                )));
-            }
+        Ok(Self {
+            encoder: Some(encoder),
+            width,
+            height,
+            fps,
+            delay_cs,
+            output_path: output_path.to_path_buf(),
+            frames_written: 0,
+        })
+    }
+}
+
+impl FrameSink for GifEncoder {
+    fn write_frame(&mut self, frame: FrameBuffer) -> Result<(), VidraError> {
+        let i = self.frames_written;
+        if frame.width != self.width || frame.height != self.height {
+            return Err(VidraError::Encode(format!(
+                "frame {} has dimensions {}x{}, expected {}x{}",
+                i, frame.width, frame.height, self.width, self.height
+            )));
+        }
 
-            let gif_frame = image::Frame::from_parts(
-                image::RgbaImage::from_raw(width, height, frame.data.clone()).ok_or_else(|| {
-                    VidraError::Encode(format!("invalid frame data at frame {}", i))
-                })?,
-                0,
-                0,
-                image::Delay::from_numer_denom_ms(delay_cs as u32 * 10, 1),
-            );
-
-            encoder.encode_frame(gif_frame).map_err(|e| {
-                VidraError::Encode(format!("failed to encode GIF frame {}: {}", i, e))
-            })?;
+        let gif_frame = image::Frame::from_parts(
+            image::RgbaImage::from_raw(self.width, self.height, frame.data)
+                .ok_or_else(|| VidraError::Encode(format!("invalid frame data at frame {}", i)))?,
+            0,
+            0,
+            image::Delay::from_numer_denom_ms(self.delay_cs as u32 * 10, 1),
+        );
+
+        let encoder = self
+            .encoder
+            .as_mut()
+            .ok_or_else(|| VidraError::Encode("GIF encoder already finished".into()))?;
+        encoder
+            .encode_frame(gif_frame)
+            .map_err(|e| VidraError::Encode(format!("failed to encode GIF frame {}: {}", i, e)))?;
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), VidraError> {
+        if self.frames_written == 0 {
+            return Err(VidraError::Encode("no frames to encode for GIF".into()));
         }
+        drop(self.encoder.take());
 
         tracing::info!(
             "Encoded {} frames to GIF at {} ({}x{} @ {}fps, delay={}cs)",
-            frames.len(),
-            output_path.display(),
-            width,
-            height,
-            fps,
-            delay_cs,
+            self.frames_written,
+            self.output_path.display(),
+            self.width,
+            self.height,
+            self.fps,
+            self.delay_cs,
         );
 
         Ok(())
@@ -124,4 +174,29 @@ mod tests {
         // Cleanup
         let _ = std::fs::remove_file(&out);
     }
+
+    #[test]
+    fn test_gif_streaming_matches_batch() {
+        let frames: Vec<FrameBuffer> = (0..4)
+            .map(|i| FrameBuffer::solid(4, 4, &vidra_core::Color::rgb(i as f32 / 4.0, 0.0, 1.0)))
+            .collect();
+
+        let batch = std::env::temp_dir().join("vidra_test_gif_batch.gif");
+        let streamed = std::env::temp_dir().join("vidra_test_gif_stream.gif");
+        GifEncoder::encode(&frames, 4, 4, 10.0, &batch, None).unwrap();
+
+        let mut sink = GifEncoder::open(4, 4, 10.0, &streamed, None).unwrap();
+        for frame in &frames {
+            sink.write_frame(frame.clone()).unwrap();
+        }
+        sink.finish().unwrap();
+
+        assert_eq!(
+            std::fs::read(&batch).unwrap(),
+            std::fs::read(&streamed).unwrap()
+        );
+
+        let _ = std::fs::remove_file(&batch);
+        let _ = std::fs::remove_file(&streamed);
+    }
 }
@@ -7,6 +7,9 @@
 //! - `WebmEncoder` — VP9 WebM via FFmpeg subprocess (web-optimized, alpha support)
 //! - `GifEncoder` — Native animated GIF (no external dependencies)
 //! - `ApngEncoder` — Native animated PNG (lossless, no external dependencies)
+//!
+//! Every encoder also implements `vidra_core::FrameSink`, so a render can be
+//! streamed into it frame by frame via `open(..)` instead of `encode(&frames)`.
 
 pub mod apng;
 pub mod ffmpeg;
@@ -17,3 +20,5 @@ pub use apng::ApngEncoder;
 pub use ffmpeg::{AudioTrack, FfmpegEncoder};
 pub use gif::GifEncoder;
 pub use webm::WebmEncoder;
+
+pub use vidra_core::frame::FrameSink;
@@ -1,15 +1,18 @@
-use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
-use vidra_core::frame::FrameBuffer;
+use vidra_core::frame::{FrameBuffer, FrameSink};
 use vidra_core::VidraError;
 
-use crate::ffmpeg::AudioTrack;
+use crate::ffmpeg::{AudioTrack, RawVideoPipe};
 
 /// Encoder that shells out to FFmpeg for VP9 WebM encoding.
 /// Ideal for web-native video that doesn't require H.264 licensing.
-pub struct WebmEncoder;
+pub struct WebmEncoder {
+    pipe: RawVideoPipe,
+    output_path: PathBuf,
+    fps: f64,
+}
 
 impl WebmEncoder {
     /// Check if FFmpeg is available on the system.
@@ -46,6 +49,23 @@ impl WebmEncoder {
             return Err(VidraError::Encode("no frames to encode".into()));
         }
 
+        let mut encoder = Self::open(audio_tracks, width, height, fps, output_path, crf)?;
+        for frame in frames {
+            encoder.pipe.write(frame)?;
+        }
+        encoder.finish()
+    }
+
+    /// Start a streaming VP9 encode. Frames are written as they arrive via
+    /// [`FrameSink::write_frame`]; call [`FrameSink::finish`] to finalize the file.
+    pub fn open(
+        audio_tracks: &[AudioTrack],
+        width: u32,
+        height: u32,
+        fps: f64,
+        output_path: &Path,
+        crf: Option<u32>,
+    ) -> Result<Self, VidraError> {
         if !Self::is_available() {
             return Err(VidraError::Encode(
                 "ffmpeg not found in PATH. Install FFmpeg: https://ffmpeg.org/download.html".into(),
@@ -162,57 +182,32 @@ impl WebmEncoder {
 
         cmd.arg(output_path);
 
-        let mut child = cmd
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| VidraError::Encode(format!("failed to start ffmpeg: {}", e)))?;
-
-        let mut stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| VidraError::Encode("failed to open ffmpeg stdin".into()))?;
-
-        // Write raw RGBA frame data to FFmpeg's stdin
-        for (i, frame) in frames.iter().enumerate() {
-            if frame.width != width || frame.height != height {
-                return Err(VidraError::Encode(format!(
-                    "frame {} has dimensions {}x{}, expected {}x{}",
-                    i, frame.width, frame.height, width, height
-                )));
-            }
-            if let Err(e) = stdin.write_all(&frame.data) {
-                let output = child.wait_with_output().unwrap();
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(VidraError::Encode(format!(
-                    "failed to write frame {} to ffmpeg: {}. FFmpeg stderr: {}",
-                    i, e, stderr
-                )));
-            }
-        }
-
-        drop(stdin);
+        Ok(Self {
+            pipe: RawVideoPipe::spawn(cmd, width, height)?,
+            output_path: output_path.to_path_buf(),
+            fps,
+        })
+    }
+}
 
-        let output = child
-            .wait_with_output()
-            .map_err(|e| VidraError::Encode(format!("ffmpeg process error: {}", e)))?;
+impl FrameSink for WebmEncoder {
+    fn write_frame(&mut self, frame: FrameBuffer) -> Result<(), VidraError> {
+        self.pipe.write(&frame)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(VidraError::Encode(format!(
-                "ffmpeg (VP9) failed with status {}: {}",
-                output.status, stderr
-            )));
+    fn finish(&mut self) -> Result<(), VidraError> {
+        if self.pipe.frames_written() == 0 {
+            return Err(VidraError::Encode("no frames to encode".into()));
         }
+        self.pipe.finish("ffmpeg (VP9)")?;
 
         tracing::info!(
             "Encoded {} frames to WebM (VP9) at {} ({}x{} @ {}fps)",
-            frames.len(),
-            output_path.display(),
-            width,
-            height,
-            fps
+            self.pipe.frames_written(),
+            self.output_path.display(),
+            self.pipe.width,
+            self.pipe.height,
+            self.fps
         );
 
         Ok(())
@@ -0,0 +1,242 @@
+//! Text animators: how far each character, word or line of a text layer is moved,
+//! scaled, rotated, faded and blurred at a given time.
+//!
+//! Which unit a glyph belongs to, and where the units are, is only known after
+//! layout; `text` asks this module for the state of every unit and draws the glyphs.
+
+use vidra_core::Duration;
+use vidra_ir::animation::AnimatableProperty;
+use vidra_ir::text::{TextAnimator, TextRange};
+
+/// How one unit is drawn, relative to where layout put it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitState {
+    /// Pixels to move the unit by.
+    pub offset: (f64, f64),
+    /// Scale about the unit's center.
+    pub scale: (f64, f64),
+    /// Clockwise rotation about the unit's center, in degrees.
+    pub rotation: f64,
+    pub opacity: f64,
+    /// Blur radius in pixels.
+    pub blur: f64,
+}
+
+impl UnitState {
+    /// A unit drawn exactly where layout put it.
+    pub const REST: UnitState = UnitState {
+        offset: (0.0, 0.0),
+        scale: (1.0, 1.0),
+        rotation: 0.0,
+        opacity: 1.0,
+        blur: 0.0,
+    };
+
+    pub fn is_rest(&self) -> bool {
+        *self == Self::REST
+    }
+}
+
+/// The range of `animator` at scene-local `time`, with its range animations applied.
+pub fn range_at(animator: &TextAnimator, time: Duration) -> TextRange {
+    let mut range = animator.range;
+    for anim in &animator.animations {
+        let field = match anim.property {
+            AnimatableProperty::TextRangeStart => &mut range.start,
+            AnimatableProperty::TextRangeEnd => &mut range.end,
+            AnimatableProperty::TextRangeOffset => &mut range.offset,
+            _ => continue,
+        };
+        if let Some(value) = anim.evaluate(time) {
+            *field = value;
+        }
+    }
+    range
+}
+
+/// The state of each of `count` units at scene-local `time`. Units outside the
+/// animator's range are at rest.
+pub fn unit_states(animator: &TextAnimator, count: usize, time: Duration) -> Vec<UnitState> {
+    let range = range_at(animator, time);
+    (0..count)
+        .map(|index| {
+            if !range.contains(index, count) {
+                return UnitState::REST;
+            }
+            let turn = if animator.stagger < 0.0 {
+                count - 1 - index
+            } else {
+                index
+            };
+            let local = time.as_seconds() - animator.stagger.abs() * turn as f64;
+            unit_state(animator, local)
+        })
+        .collect()
+}
+
+/// The state of a unit whose animations run at `local` seconds.
+fn unit_state(animator: &TextAnimator, local: f64) -> UnitState {
+    let mut state = UnitState::REST;
+    for anim in &animator.animations {
+        // Hold the first keyframe until the unit's turn, rather than jumping to rest.
+        let time = Duration::from_seconds(local.max(anim.delay.as_seconds()));
+        if anim.property.is_vector() {
+            let Some(v) = anim.evaluate_vector(time) else {
+                continue;
+            };
+            let pair = |default: (f64, f64)| {
+                (
+                    v.first().copied().unwrap_or(default.0),
+                    v.get(1).copied().unwrap_or(default.1),
+                )
+            };
+            match anim.property {
+                AnimatableProperty::Position => state.offset = pair(state.offset),
+                AnimatableProperty::Scale => state.scale = pair(state.scale),
+                _ => {}
+            }
+            continue;
+        }
+        let Some(value) = anim.evaluate(time) else {
+            continue;
+        };
+        match anim.property {
+            AnimatableProperty::PositionX => state.offset.0 = value,
+            AnimatableProperty::PositionY => state.offset.1 = value,
+            AnimatableProperty::ScaleX => state.scale.0 = value,
+            AnimatableProperty::ScaleY => state.scale.1 = value,
+            AnimatableProperty::Rotation => state.rotation = value,
+            AnimatableProperty::Opacity => state.opacity = value.clamp(0.0, 1.0),
+            AnimatableProperty::BlurRadius => state.blur = value.max(0.0),
+            _ => {}
+        }
+    }
+    state
+}
+
+/// How far `animator` can draw a unit of `half_size` (half its width and height)
+/// outside the unit's own box, over its whole run.
+///
+/// Worked out from the keyframes, so the answer is the same at every frame and the
+/// layer does not change size as it plays. Easings that overshoot their keyframes may
+/// still be clipped.
+pub fn reach(animator: &TextAnimator, half_size: (f64, f64)) -> (f64, f64) {
+    let mut offset = (0.0f64, 0.0f64);
+    let (mut scale, mut rotates, mut blur) = (1.0f64, false, 0.0f64);
+    for anim in &animator.animations {
+        for kf in &anim.keyframes {
+            let v = kf.value.abs();
+            let x = kf.vector.first().map_or(0.0, |v| v.abs());
+            let y = kf.vector.get(1).map_or(0.0, |v| v.abs());
+            match anim.property {
+                AnimatableProperty::PositionX => offset.0 = offset.0.max(v),
+                AnimatableProperty::PositionY => offset.1 = offset.1.max(v),
+                AnimatableProperty::Position => offset = (offset.0.max(x), offset.1.max(y)),
+                AnimatableProperty::ScaleX | AnimatableProperty::ScaleY => scale = scale.max(v),
+                AnimatableProperty::Scale => scale = scale.max(x).max(y),
+                AnimatableProperty::Rotation => rotates |= v != 0.0,
+                AnimatableProperty::BlurRadius => blur = blur.max(kf.value),
+                _ => {}
+            }
+        }
+    }
+    let (w, h) = half_size;
+    let (grow_x, grow_y) = if rotates {
+        let radius = w.hypot(h) * scale;
+        (radius - w, radius - h)
+    } else {
+        (w * (scale - 1.0), h * (scale - 1.0))
+    };
+    (offset.0 + grow_x + blur, offset.1 + grow_y + blur)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_core::types::Easing;
+    use vidra_ir::animation::Animation;
+    use vidra_ir::text::TextUnit;
+
+    fn fade_up(stagger: f64) -> TextAnimator {
+        let second = Duration::from_seconds(1.0);
+        TextAnimator {
+            unit: TextUnit::Char,
+            animations: vec![
+                Animation::from_to(
+                    AnimatableProperty::Opacity,
+                    0.0,
+                    1.0,
+                    second,
+                    Easing::Linear,
+                ),
+                Animation::from_to(
+                    AnimatableProperty::PositionY,
+                    20.0,
+                    0.0,
+                    second,
+                    Easing::Linear,
+                ),
+            ],
+            stagger,
+            range: TextRange::default(),
+        }
+    }
+
+    #[test]
+    fn test_units_stagger_and_hold_first_keyframe() {
+        let states = unit_states(&fade_up(0.5), 3, Duration::from_seconds(0.5));
+        assert_eq!(states[0].opacity, 0.5);
+        assert_eq!(states[0].offset, (0.0, 10.0));
+        assert_eq!(states[1].opacity, 0.0);
+        assert_eq!(states[2].offset, (0.0, 20.0));
+
+        // A negative stagger starts from the last unit.
+        let states = unit_states(&fade_up(-0.5), 3, Duration::from_seconds(0.5));
+        assert_eq!(states[2].opacity, 0.5);
+        assert_eq!(states[0].opacity, 0.0);
+
+        let done = unit_states(&fade_up(0.5), 3, Duration::from_seconds(2.0));
+        assert!(done.iter().all(UnitState::is_rest));
+    }
+
+    #[test]
+    fn test_range_selects_units() {
+        let mut animator = fade_up(0.0);
+        animator.range = TextRange {
+            start: 0.0,
+            end: 0.5,
+            offset: 0.0,
+        };
+        let states = unit_states(&animator, 4, Duration::zero());
+        assert_eq!(
+            states.iter().map(UnitState::is_rest).collect::<Vec<_>>(),
+            [false, false, true, true]
+        );
+
+        // Sweep the range across the text: at 1s it is offset by half its width.
+        animator.animations.push(Animation::from_to(
+            AnimatableProperty::TextRangeOffset,
+            0.0,
+            1.0,
+            Duration::from_seconds(2.0),
+            Easing::Linear,
+        ));
+        let range = range_at(&animator, Duration::from_seconds(1.0));
+        assert_eq!(range.offset, 0.5);
+        assert!(!range.contains(0, 4) && range.contains(3, 4));
+    }
+
+    #[test]
+    fn test_reach_covers_keyframes() {
+        let mut animator = fade_up(0.1);
+        assert_eq!(reach(&animator, (10.0, 10.0)), (0.0, 20.0));
+        animator.animations.push(Animation::from_to(
+            AnimatableProperty::ScaleX,
+            2.0,
+            1.0,
+            Duration::from_seconds(1.0),
+            Easing::Linear,
+        ));
+        assert_eq!(reach(&animator, (10.0, 5.0)), (10.0, 25.0));
+    }
+}
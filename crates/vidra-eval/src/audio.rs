@@ -0,0 +1,193 @@
+//! Audio envelopes for audio-reactive expressions.
+//!
+//! An [`AudioEnvelope`] holds one [`AudioFrame`] of loudness features per output frame,
+//! computed from mono PCM. Renderers decode the audio; the analysis itself is plain
+//! arithmetic so every renderer gets the same numbers from the same samples.
+
+use std::collections::HashMap;
+
+use vidra_ir::asset::AssetId;
+
+/// Audio envelopes by the asset id animations reference them with.
+pub type AudioEnvelopes = HashMap<AssetId, AudioEnvelope>;
+
+/// Upper edge of the bass band, in Hz.
+const BASS_CUTOFF_HZ: f64 = 250.0;
+/// Lower edge of the treble band, in Hz.
+const TREBLE_CUTOFF_HZ: f64 = 4000.0;
+/// Length of the bass-energy history a beat is compared against.
+const BEAT_HISTORY_SECS: f64 = 1.0;
+/// How far bass energy must rise above its recent average to count as a beat.
+const BEAT_THRESHOLD: f64 = 1.5;
+/// Bass energy below this never counts as a beat (silence, noise floor).
+const BEAT_MIN_ENERGY: f64 = 1e-4;
+/// Minimum time between two beats.
+const BEAT_MIN_INTERVAL_SECS: f64 = 0.2;
+/// Time for `beat` to fall from 1 back to 0 after a beat.
+const BEAT_DECAY_SECS: f64 = 0.15;
+
+/// Loudness features of one frame of audio, each in 0–1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioFrame {
+    /// RMS level (`audio_amp`).
+    pub amp: f64,
+    /// Peak absolute sample (`audio_peak`).
+    pub peak: f64,
+    /// RMS level below 250 Hz (`audio_bass`).
+    pub bass: f64,
+    /// RMS level between 250 Hz and 4 kHz (`audio_mid`).
+    pub mid: f64,
+    /// RMS level above 4 kHz (`audio_treble`).
+    pub treble: f64,
+    /// 1 on a detected beat, falling linearly to 0 over 150 ms (`audio_beat`).
+    pub beat: f64,
+}
+
+/// Per-frame audio features of one audio file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioEnvelope {
+    /// Frames per second of `frames`.
+    pub fps: f64,
+    pub frames: Vec<AudioFrame>,
+}
+
+impl AudioEnvelope {
+    /// Analyze mono `samples` (in -1–1) into frames of `1 / fps` seconds.
+    pub fn analyze(samples: &[f32], sample_rate: u32, fps: f64) -> Self {
+        let rate = sample_rate.max(1) as f64;
+        let window = (rate / fps).round().max(1.0) as usize;
+        let bass_alpha = one_pole_alpha(BASS_CUTOFF_HZ, rate);
+        let treble_alpha = one_pole_alpha(TREBLE_CUTOFF_HZ, rate);
+
+        // Band split with two one-pole low-passes: bass = lp(250), mid = lp(4k) - bass,
+        // treble = x - lp(4k).
+        let (mut bass_lp, mut treble_lp) = (0.0f64, 0.0f64);
+        let mut frames = Vec::with_capacity(samples.len() / window + 1);
+        let mut bass_energy = Vec::with_capacity(frames.capacity());
+        for chunk in samples.chunks(window) {
+            let (mut sum, mut bass, mut mid, mut treble, mut peak) = (0.0, 0.0, 0.0, 0.0, 0.0f64);
+            for &s in chunk {
+                let x = s as f64;
+                bass_lp += bass_alpha * (x - bass_lp);
+                treble_lp += treble_alpha * (x - treble_lp);
+                sum += x * x;
+                bass += bass_lp * bass_lp;
+                mid += (treble_lp - bass_lp).powi(2);
+                treble += (x - treble_lp).powi(2);
+                peak = peak.max(x.abs());
+            }
+            let n = chunk.len() as f64;
+            let rms = |e: f64| (e / n).sqrt().clamp(0.0, 1.0);
+            frames.push(AudioFrame {
+                amp: rms(sum),
+                peak: peak.min(1.0),
+                bass: rms(bass),
+                mid: rms(mid),
+                treble: rms(treble),
+                beat: 0.0,
+            });
+            bass_energy.push(bass / n);
+        }
+
+        mark_beats(&mut frames, &bass_energy, fps);
+        Self { fps, frames }
+    }
+
+    /// Features at `seconds` into the audio; silence outside it.
+    pub fn at(&self, seconds: f64) -> AudioFrame {
+        if seconds < 0.0 || !seconds.is_finite() {
+            return AudioFrame::default();
+        }
+        // Nudge so frame boundaries computed as `n / fps` land on frame `n`.
+        let index = (seconds * self.fps + 1e-9).floor() as usize;
+        self.frames.get(index).copied().unwrap_or_default()
+    }
+}
+
+fn one_pole_alpha(cutoff_hz: f64, sample_rate: f64) -> f64 {
+    let dt = 1.0 / sample_rate;
+    let rc = 1.0 / (2.0 * std::f64::consts::PI * cutoff_hz);
+    dt / (rc + dt)
+}
+
+/// Detect bass onsets against a trailing average and write the decaying `beat` pulse.
+fn mark_beats(frames: &mut [AudioFrame], bass_energy: &[f64], fps: f64) {
+    let history = (BEAT_HISTORY_SECS * fps).round().max(1.0) as usize;
+    let min_interval = (BEAT_MIN_INTERVAL_SECS * fps).round() as usize;
+    let decay_frames = BEAT_DECAY_SECS * fps;
+
+    let mut last_beat: Option<usize> = None;
+    for i in 0..frames.len() {
+        let past = &bass_energy[i.saturating_sub(history)..i];
+        let average = if past.is_empty() {
+            0.0
+        } else {
+            past.iter().sum::<f64>() / past.len() as f64
+        };
+        let energy = bass_energy[i];
+        let ready = last_beat.is_none_or(|b| i - b >= min_interval);
+        if ready && energy > BEAT_MIN_ENERGY && energy > average * BEAT_THRESHOLD {
+            last_beat = Some(i);
+        }
+        if let Some(b) = last_beat {
+            frames[i].beat = (1.0 - (i - b) as f64 / decay_frames.max(1.0)).max(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f64, amplitude: f64, rate: u32, secs: f64) -> Vec<f32> {
+        let n = (rate as f64 * secs) as usize;
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / rate as f64;
+                (amplitude * (2.0 * std::f64::consts::PI * freq * t).sin()) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_levels_and_band_split() {
+        let rate = 22050;
+        let env = AudioEnvelope::analyze(&sine(60.0, 0.5, rate, 1.0), rate, 10.0);
+        assert_eq!(env.frames.len(), 10);
+        let f = env.at(0.55);
+        // RMS of a sine is amplitude / √2.
+        assert!((f.amp - 0.5 / 2f64.sqrt()).abs() < 0.01, "{:?}", f);
+        assert!((f.peak - 0.5).abs() < 0.01);
+        assert!(f.bass > 4.0 * f.mid && f.bass > 4.0 * f.treble, "{:?}", f);
+
+        let env = AudioEnvelope::analyze(&sine(8000.0, 0.5, rate, 1.0), rate, 10.0);
+        let f = env.at(0.55);
+        assert!(f.treble > 5.0 * f.bass && f.treble > f.mid, "{:?}", f);
+
+        assert_eq!(env.at(5.0), AudioFrame::default());
+        assert_eq!(env.at(-1.0), AudioFrame::default());
+    }
+
+    #[test]
+    fn test_beat_pulses_on_bass_onsets() {
+        let rate = 8000;
+        let fps = 20.0;
+        // Silence, then a kick every half second.
+        let mut samples = vec![0.0f32; rate as usize * 2];
+        for kick in 1..4 {
+            let start = kick * rate as usize / 2;
+            for (i, s) in sine(80.0, 0.8, rate, 0.1).into_iter().enumerate() {
+                samples[start + i] = s;
+            }
+        }
+        let env = AudioEnvelope::analyze(&samples, rate, fps);
+        let beats: Vec<usize> = (0..env.frames.len())
+            .filter(|&i| env.frames[i].beat == 1.0)
+            .collect();
+        assert_eq!(beats, vec![10, 20, 30]);
+        // The pulse decays over 150 ms (3 frames at 20 fps).
+        assert!(env.frames[11].beat > 0.0 && env.frames[11].beat < 1.0);
+        assert_eq!(env.frames[14].beat, 0.0);
+        assert!(env.frames[..10].iter().all(|f| f.beat == 0.0));
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use vidra_ir::project::Project;
+
+use crate::audio::AudioEnvelopes;
+use crate::expr::ExprCache;
+
+/// Inputs that affect evaluation besides the project itself.
+#[derive(Debug, Clone)]
+pub struct EvalContext {
+    /// Output width.
+    pub width: u32,
+    /// Output height.
+    pub height: u32,
+    /// Current frame rate.
+    pub fps: f64,
+
+    /// Mouse position in pixel coordinates for interactive previews.
+    pub mouse_x: f64,
+    pub mouse_y: f64,
+
+    /// Runtime numeric state vars (used by interactive previews).
+    pub state_vars: HashMap<String, f64>,
+
+    /// Parsed animation and action expressions, shared across frames.
+    pub exprs: Arc<ExprCache>,
+
+    /// Envelopes of the audio referenced by audio-reactive expressions.
+    pub audio: Arc<AudioEnvelopes>,
+
+    /// Offset in frames added to every evaluated time, for sub-frame samples (motion
+    /// blur). Frame-indexed content such as video and spritesheets is not affected.
+    pub subframe: f64,
+}
+
+impl EvalContext {
+    /// A non-interactive context for the project's output settings.
+    pub fn new(project: &Project) -> Self {
+        Self {
+            width: project.settings.width,
+            height: project.settings.height,
+            fps: project.settings.fps,
+            mouse_x: 0.0,
+            mouse_y: 0.0,
+            state_vars: HashMap::new(),
+            exprs: Arc::default(),
+            audio: Arc::default(),
+            subframe: 0.0,
+        }
+    }
+
+    /// Use an existing expression cache instead of a fresh one.
+    pub fn with_exprs(mut self, exprs: Arc<ExprCache>) -> Self {
+        self.exprs = exprs;
+        self
+    }
+
+    /// Use decoded audio envelopes for audio-reactive expressions.
+    pub fn with_audio(mut self, audio: Arc<AudioEnvelopes>) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    /// Evaluate at `offset` frames from each frame's time.
+    pub fn with_subframe(mut self, offset: f64) -> Self {
+        self.subframe = offset;
+        self
+    }
+
+    /// Scene-local time of a scene-local frame index (plus the sub-frame offset).
+    pub fn time_at(&self, frame: u64) -> vidra_core::Duration {
+        vidra_core::Duration::from_seconds((frame as f64 + self.subframe) / self.fps)
+    }
+}
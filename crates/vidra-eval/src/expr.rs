@@ -0,0 +1,316 @@
+//! Runtime expression evaluation (`evalexpr`) for animations and click actions.
+//!
+//! Expressions are parsed once into an [`ExprCache`] and evaluated from there on every
+//! frame.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use evalexpr::{
+    build_operator_tree, ContextWithMutableVariables, DefaultNumericTypes, HashMapContext, Node,
+    Value,
+};
+use vidra_core::{VidraError, VidraResult};
+use vidra_ir::animation::Animation;
+use vidra_ir::layer::{Layer, LayerAction};
+use vidra_ir::project::Project;
+use vidra_ir::scene::SceneId;
+
+use crate::audio::AudioFrame;
+use crate::context::EvalContext;
+
+/// Names bound by the engine; state vars with these names are ignored.
+const BUILTIN_VARS: [&str; 11] = [
+    "t",
+    "p",
+    "T",
+    "mouse_x",
+    "mouse_y",
+    "audio_amp",
+    "audio_peak",
+    "audio_bass",
+    "audio_mid",
+    "audio_treble",
+    "audio_beat",
+];
+
+/// A parsed expression.
+pub type CompiledExpr = Node<DefaultNumericTypes>;
+
+/// Parse `source` into an evaluable expression.
+pub fn compile_expr(source: &str) -> Result<CompiledExpr, String> {
+    build_operator_tree::<DefaultNumericTypes>(source).map_err(|e| e.to_string())
+}
+
+/// Parsed expressions keyed by their source text, shared by every frame of a render.
+///
+/// [`ExprCache::compile_project`] parses a project's expressions up front and reports
+/// invalid ones; expressions it has not seen are parsed on first use.
+#[derive(Default)]
+pub struct ExprCache {
+    /// `None` marks expressions that failed to parse, so they are not retried every frame.
+    compiled: RwLock<HashMap<String, Option<Arc<CompiledExpr>>>>,
+}
+
+impl ExprCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse every animation and `set` action expression in `project`.
+    ///
+    /// Fails on the first invalid expression, naming its scene and layer.
+    pub fn compile_project(&self, project: &Project) -> VidraResult<()> {
+        for scene in &project.scenes {
+            for layer in &scene.layers {
+                self.compile_layer(&scene.id, layer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_layer(&self, scene: &SceneId, layer: &Layer) -> VidraResult<()> {
+        let animation_exprs = layer.animations.iter().filter_map(|a| a.expr.as_deref());
+        let action_exprs = layer.events.iter().flat_map(|h| {
+            h.actions.iter().map(|action| match action {
+                LayerAction::SetVar { expr, .. } => expr.as_str(),
+            })
+        });
+        for expr in animation_exprs.chain(action_exprs) {
+            match compile_expr(expr) {
+                Ok(compiled) => {
+                    self.insert(expr, Some(compiled));
+                }
+                Err(e) => {
+                    self.insert(expr, None);
+                    return Err(VidraError::Compile(format!(
+                        "invalid expression `{}` in layer '{}' (scene '{}'): {}",
+                        expr, layer.id, scene, e
+                    )));
+                }
+            }
+        }
+        for child in &layer.children {
+            self.compile_layer(scene, child)?;
+        }
+        Ok(())
+    }
+
+    /// The parsed form of `source`, parsing it on first use. `None` if it is invalid.
+    pub fn get(&self, source: &str) -> Option<Arc<CompiledExpr>> {
+        if let Some(compiled) = self.compiled.read().ok()?.get(source) {
+            return compiled.clone();
+        }
+        self.insert(source, compile_expr(source).ok())
+    }
+
+    fn insert(&self, source: &str, compiled: Option<CompiledExpr>) -> Option<Arc<CompiledExpr>> {
+        let compiled = compiled.map(Arc::new);
+        if let Ok(mut map) = self.compiled.write() {
+            map.insert(source.to_string(), compiled.clone());
+        }
+        compiled
+    }
+}
+
+impl std::fmt::Debug for ExprCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = self.compiled.read().map(|m| m.len()).unwrap_or(0);
+        f.debug_struct("ExprCache").field("len", &len).finish()
+    }
+}
+
+/// Evaluate an animation at scene-local `time`.
+///
+/// Expression animations are evaluated with `t`, `p` and `T` bound to the elapsed time,
+/// progress and duration, and the `audio_*` variables bound to the features of
+/// `expr_audio` at `t`; keyframed animations are interpolated. Returns `None` before an
+/// expression animation's delay has elapsed or when the expression fails to evaluate.
+pub fn evaluate_animation(
+    ctx: &EvalContext,
+    anim: &Animation,
+    time: vidra_core::Duration,
+) -> Option<f64> {
+    let Some(expr) = anim.expr.as_deref() else {
+        return anim.evaluate(time);
+    };
+
+    let effective_secs = time.as_seconds() - anim.delay.as_seconds();
+    if effective_secs < 0.0 {
+        return None;
+    }
+
+    let duration_secs = anim
+        .expr_duration
+        .as_ref()
+        .map(|d| d.as_seconds())
+        .unwrap_or(0.0);
+    let t = if duration_secs > 0.0 {
+        effective_secs.min(duration_secs)
+    } else {
+        effective_secs
+    };
+    let p = if duration_secs > 0.0 {
+        (t / duration_secs).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+
+    let audio = anim
+        .expr_audio
+        .as_ref()
+        .and_then(|id| ctx.audio.get(id))
+        .map(|envelope| envelope.at(t))
+        .unwrap_or_default();
+
+    eval_number(ctx, expr, t, p, duration_secs, &audio)
+}
+
+/// Evaluate the right-hand side of a `set name = expr` action.
+///
+/// Time variables are bound as if the animation had finished (`t = 0`, `p = 1`, `T = 0`).
+pub fn evaluate_set_expr(ctx: &EvalContext, expr: &str) -> Option<f64> {
+    eval_number(ctx, expr, 0.0, 1.0, 0.0, &AudioFrame::default())
+}
+
+fn eval_number(
+    ctx: &EvalContext,
+    expr: &str,
+    t: f64,
+    p: f64,
+    duration: f64,
+    audio: &AudioFrame,
+) -> Option<f64> {
+    let compiled = ctx.exprs.get(expr)?;
+    let mut context = HashMapContext::new();
+    let _ = context.set_value("t".to_string(), Value::Float(t));
+    let _ = context.set_value("p".to_string(), Value::Float(p));
+    let _ = context.set_value("T".to_string(), Value::Float(duration));
+    let _ = context.set_value("mouse_x".to_string(), Value::Float(ctx.mouse_x));
+    let _ = context.set_value("mouse_y".to_string(), Value::Float(ctx.mouse_y));
+    for (name, value) in [
+        ("audio_amp", audio.amp),
+        ("audio_peak", audio.peak),
+        ("audio_bass", audio.bass),
+        ("audio_mid", audio.mid),
+        ("audio_treble", audio.treble),
+        ("audio_beat", audio.beat),
+    ] {
+        let _ = context.set_value(name.to_string(), Value::Float(value));
+    }
+    for (k, v) in &ctx.state_vars {
+        if !BUILTIN_VARS.contains(&k.as_str()) {
+            let _ = context.set_value(k.clone(), Value::Float(*v));
+        }
+    }
+
+    compiled
+        .eval_with_context(&context)
+        .ok()
+        .and_then(|v| v.as_number().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use vidra_core::Duration;
+    use vidra_ir::animation::AnimatableProperty;
+
+    fn ctx() -> EvalContext {
+        EvalContext {
+            width: 100,
+            height: 100,
+            fps: 30.0,
+            mouse_x: 10.0,
+            mouse_y: 20.0,
+            state_vars: HashMap::from([("count".to_string(), 3.0), ("t".to_string(), 99.0)]),
+            exprs: Default::default(),
+            audio: Default::default(),
+            subframe: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_expression_binds_time_and_progress() {
+        let mut anim = Animation::new(AnimatableProperty::Opacity);
+        anim.expr = Some("p * 10 + t".into());
+        anim.expr_duration = Some(Duration::from_seconds(2.0));
+        anim.delay = Duration::from_seconds(1.0);
+
+        assert_eq!(
+            evaluate_animation(&ctx(), &anim, Duration::from_seconds(0.5)),
+            None
+        );
+        let v = evaluate_animation(&ctx(), &anim, Duration::from_seconds(2.0)).unwrap();
+        assert!((v - 6.0).abs() < 1e-9);
+        // Clamped to the expression duration.
+        let v = evaluate_animation(&ctx(), &anim, Duration::from_seconds(10.0)).unwrap();
+        assert!((v - 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compile_project_names_invalid_expression_layer() {
+        use vidra_ir::layer::LayerContent;
+        use vidra_ir::project::ProjectSettings;
+        use vidra_ir::scene::Scene;
+
+        let mut project = Project::new(ProjectSettings::custom(100, 100, 30.0));
+        let mut scene = Scene::new(SceneId::new("main"), Duration::from_seconds(1.0));
+        let mut layer = Layer::new(vidra_ir::layer::LayerId::new("wobble"), LayerContent::Empty);
+        let mut anim = Animation::new(AnimatableProperty::PositionY);
+        anim.expr = Some("mouse_y * (2 +".into());
+        layer.animations.push(anim);
+        scene.add_layer(layer);
+        project.add_scene(scene);
+
+        let cache = ExprCache::new();
+        let err = cache.compile_project(&project).unwrap_err().to_string();
+        assert!(
+            err.contains("layer 'wobble'") && err.contains("scene 'main'"),
+            "{}",
+            err
+        );
+        assert!(cache.get("mouse_y * (2 +").is_none());
+
+        let first = cache.get("mouse_x * 2").unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.get("mouse_x * 2").unwrap()));
+    }
+
+    #[test]
+    fn test_audio_vars_follow_the_referenced_envelope() {
+        let id = vidra_ir::asset::AssetId::new("music.wav");
+        let loud = AudioFrame {
+            amp: 0.5,
+            bass: 0.25,
+            beat: 1.0,
+            ..Default::default()
+        };
+        let envelope = crate::audio::AudioEnvelope {
+            fps: 10.0,
+            frames: vec![AudioFrame::default(), loud],
+        };
+        let mut ctx = ctx();
+        ctx.audio = Arc::new(HashMap::from([(id.clone(), envelope)]));
+
+        let mut anim = Animation::new(AnimatableProperty::ScaleX);
+        anim.expr = Some("1 + audio_amp + audio_bass * audio_beat".into());
+        let at = |anim: &Animation, secs| {
+            evaluate_animation(&ctx, anim, Duration::from_seconds(secs)).unwrap()
+        };
+        // No audio referenced: silence.
+        assert_eq!(at(&anim, 0.1), 1.0);
+
+        anim.expr_audio = Some(id);
+        assert_eq!(at(&anim, 0.0), 1.0);
+        assert_eq!(at(&anim, 0.1), 1.75);
+        assert_eq!(at(&anim, 0.3), 1.0);
+    }
+
+    #[test]
+    fn test_set_expr_sees_state_but_not_shadowed_builtins() {
+        assert_eq!(evaluate_set_expr(&ctx(), "count + 1"), Some(4.0));
+        assert_eq!(evaluate_set_expr(&ctx(), "t + mouse_y"), Some(20.0));
+        assert_eq!(evaluate_set_expr(&ctx(), "count +"), None);
+    }
+}
@@ -0,0 +1,351 @@
+//! Whole-frame evaluation: which scenes are on screen, transitions and layout.
+
+use vidra_ir::layer::Layer;
+use vidra_ir::layout::LayoutSolver;
+use vidra_ir::project::Project;
+use vidra_ir::scene::SceneId;
+use vidra_ir::transition::TransitionType;
+
+use crate::context::EvalContext;
+use crate::layer::{
+    evaluate_layer, evaluate_layer_with_base, has_intrinsic_size, static_size, ContentMeasure,
+    LayerState,
+};
+
+/// The evaluated state of a whole frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameState {
+    pub global_frame: u64,
+    /// Scenes on screen, bottom to top: one scene, or the outgoing and incoming scenes
+    /// of a transition. Empty when the frame is past the end of the project.
+    pub scenes: Vec<SceneState>,
+    /// Set while the top scene is transitioning in over the one below it.
+    pub transition: Option<TransitionState>,
+}
+
+impl FrameState {
+    /// The top-most scene, which receives input in interactive previews.
+    pub fn top_scene(&self) -> Option<&SceneState> {
+        self.scenes.last()
+    }
+}
+
+/// The evaluated state of one scene at one frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneState {
+    pub id: SceneId,
+    /// Index of the scene in `Project::scenes`.
+    pub index: usize,
+    /// Frame index relative to the scene start.
+    pub local_frame: u64,
+    /// Top-level layers, bottom to top. Hidden layers are kept (with `visible == false`)
+    /// because they can still be referenced as masks.
+    pub layers: Vec<LayerState>,
+}
+
+impl SceneState {
+    /// Look up a top-level layer by id (used for mask references).
+    pub fn layer(&self, id: &vidra_ir::layer::LayerId) -> Option<&LayerState> {
+        self.layers.iter().find(|l| &l.id == id)
+    }
+}
+
+/// Progress of a scene transition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionState {
+    pub effect: TransitionType,
+    /// Eased progress, 0 at the start of the transition and approaching 1 at its end.
+    pub progress: f64,
+}
+
+/// Scenes on screen at `global_frame`, as `(scene index, local frame)` pairs, bottom to top.
+///
+/// A scene with a transition starts that many frames before the previous scene ends
+/// (capped at the shorter of the two scenes), so two scenes overlap during transitions.
+pub fn active_scenes(project: &Project, global_frame: u64) -> Vec<(usize, u64)> {
+    let fps = project.settings.fps;
+    let mut current_global = 0u64;
+    let mut active = Vec::new();
+
+    for (i, scene) in project.scenes.iter().enumerate() {
+        let sf = scene.frame_count(fps);
+        let trans_f = match (i, &scene.transition) {
+            (1.., Some(trans)) => {
+                let max_overlap = project.scenes[i - 1].frame_count(fps).min(sf);
+                trans.duration.frame_count(fps).min(max_overlap)
+            }
+            _ => 0,
+        };
+
+        let start_f = current_global.saturating_sub(trans_f);
+        let end_f = start_f + sf;
+
+        if global_frame >= start_f && global_frame < end_f {
+            active.push((i, global_frame - start_f));
+        }
+
+        current_global = end_f;
+    }
+
+    active
+}
+
+/// Evaluate every on-screen scene and layer at `global_frame`.
+///
+/// `measure` sizes text and image content for layers with layout constraints.
+pub fn evaluate_frame(
+    ctx: &EvalContext,
+    project: &Project,
+    global_frame: u64,
+    measure: &dyn ContentMeasure,
+) -> FrameState {
+    let active = active_scenes(project, global_frame);
+
+    let scenes: Vec<SceneState> = active
+        .iter()
+        .map(|&(index, local_frame)| evaluate_scene(ctx, project, index, local_frame, measure))
+        .collect();
+
+    let transition = match active.as_slice() {
+        [_, (index, local_frame), ..] => project.scenes[*index].transition.as_ref().map(|trans| {
+            let trans_frames = trans.duration.frame_count(ctx.fps) as f64;
+            let progress = *local_frame as f64 / trans_frames;
+            TransitionState {
+                effect: trans.effect.clone(),
+                progress: trans.easing.apply(progress),
+            }
+        }),
+        _ => None,
+    };
+
+    FrameState {
+        global_frame,
+        scenes,
+        transition,
+    }
+}
+
+fn evaluate_scene(
+    ctx: &EvalContext,
+    project: &Project,
+    index: usize,
+    local_frame: u64,
+    measure: &dyn ContentMeasure,
+) -> SceneState {
+    let scene = &project.scenes[index];
+    let mut layers: Vec<LayerState> = scene
+        .layers
+        .iter()
+        .map(|layer| evaluate_layer(ctx, layer, local_frame))
+        .collect();
+    if has_constraints(&scene.layers) {
+        let canvas = (ctx.width as f64, ctx.height as f64);
+        resolve_layout(
+            ctx,
+            &scene.layers,
+            &mut layers,
+            local_frame,
+            canvas,
+            measure,
+        );
+    }
+
+    SceneState {
+        id: scene.id.clone(),
+        index,
+        local_frame,
+        layers,
+    }
+}
+
+/// Solve the layout constraints of sibling `layers` (evaluated as `states`) within
+/// `bounds`, the unscaled size of their parent's content, then recurse into children.
+fn resolve_layout(
+    ctx: &EvalContext,
+    layers: &[Layer],
+    states: &mut [LayerState],
+    frame: u64,
+    bounds: (f64, f64),
+    measure: &dyn ContentMeasure,
+) {
+    if layers.iter().any(|l| !l.constraints.is_empty()) {
+        // Constraints are solved against each layer's current (animated, scaled) size;
+        // the solved rect then replaces the layer's static position. Position
+        // animations still take precedence.
+        let sizes: Vec<(f64, f64)> = states
+            .iter()
+            .map(|state| {
+                let (w, h) = content_size(ctx, state, measure);
+                (w * state.scale.0, h * state.scale.1)
+            })
+            .collect();
+        let input: Vec<_> = layers
+            .iter()
+            .zip(&sizes)
+            .map(|(layer, (w, h))| (layer.id.0.clone(), *w, *h, layer.constraints.clone()))
+            .collect();
+        let solved = LayoutSolver::solve(bounds.0, bounds.1, &input);
+
+        for ((i, layer), (_, rect)) in layers.iter().enumerate().zip(solved) {
+            if layer.constraints.is_empty() {
+                continue;
+            }
+            // Shift by the anchor offset the rasterizer will subtract, so the layer's
+            // top-left lands on the solved rect.
+            let (w, h) = sizes[i];
+            let (ax, ay) = if has_intrinsic_size(&layer.content) {
+                (layer.transform.anchor.x * w, layer.transform.anchor.y * h)
+            } else {
+                (0.0, 0.0)
+            };
+            let mut state =
+                evaluate_layer_with_base(ctx, layer, frame, Some((rect.x + ax, rect.y + ay)));
+            state.layout = Some(rect);
+            states[i] = state;
+        }
+    }
+
+    for (layer, state) in layers.iter().zip(states.iter_mut()) {
+        if has_constraints(&layer.children) {
+            let size = content_size(ctx, state, measure);
+            resolve_layout(
+                ctx,
+                &layer.children,
+                &mut state.children,
+                frame,
+                size,
+                measure,
+            );
+        }
+    }
+}
+
+/// Whether any of `layers` or their descendants has layout constraints.
+fn has_constraints(layers: &[Layer]) -> bool {
+    layers
+        .iter()
+        .any(|l| !l.constraints.is_empty() || has_constraints(&l.children))
+}
+
+/// Unscaled pixel size of a layer's content, or zero if it can't be measured.
+fn content_size(ctx: &EvalContext, state: &LayerState, measure: &dyn ContentMeasure) -> (f64, f64) {
+    measure
+        .measure(&state.content)
+        .map(|(w, h)| (w as f64, h as f64))
+        .or_else(|| static_size(ctx, &state.content))
+        .unwrap_or((0.0, 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_core::types::{Easing, ShapeType};
+    use vidra_core::{Color, Duration};
+    use vidra_ir::layer::{Layer, LayerContent, LayerId};
+    use vidra_ir::layout::{CenterAxis, LayoutConstraint};
+    use vidra_ir::project::ProjectSettings;
+    use vidra_ir::scene::Scene;
+    use vidra_ir::transition::Transition;
+
+    fn project_with_transition() -> Project {
+        let mut project = Project::new(ProjectSettings::custom(320, 240, 10.0));
+        project.add_scene(Scene::new(SceneId::new("a"), Duration::from_seconds(1.0)));
+        let mut b = Scene::new(SceneId::new("b"), Duration::from_seconds(1.0));
+        b.transition = Some(Transition {
+            effect: TransitionType::Crossfade,
+            duration: Duration::from_seconds(0.4),
+            easing: Easing::Linear,
+        });
+        project.add_scene(b);
+        project
+    }
+
+    #[test]
+    fn test_active_scenes_overlap_during_transition() {
+        let project = project_with_transition();
+        assert_eq!(active_scenes(&project, 0), vec![(0, 0)]);
+        assert_eq!(active_scenes(&project, 5), vec![(0, 5)]);
+        assert_eq!(active_scenes(&project, 6), vec![(0, 6), (1, 0)]);
+        assert_eq!(active_scenes(&project, 9), vec![(0, 9), (1, 3)]);
+        assert_eq!(active_scenes(&project, 10), vec![(1, 4)]);
+        assert_eq!(active_scenes(&project, 15), vec![(1, 9)]);
+        assert!(active_scenes(&project, 16).is_empty());
+    }
+
+    #[test]
+    fn test_transition_progress() {
+        let project = project_with_transition();
+        let ctx = EvalContext::new(&project);
+        let state = evaluate_frame(&ctx, &project, 8, &());
+        assert_eq!(state.scenes.len(), 2);
+        assert_eq!(state.top_scene().unwrap().id, SceneId::new("b"));
+        let transition = state.transition.unwrap();
+        assert_eq!(transition.effect, TransitionType::Crossfade);
+        assert!((transition.progress - 0.5).abs() < 1e-9);
+
+        assert_eq!(evaluate_frame(&ctx, &project, 12, &()).transition, None);
+    }
+
+    #[test]
+    fn test_layout_constraints_place_layer() {
+        let mut project = Project::new(ProjectSettings::custom(320, 240, 10.0));
+        let mut scene = Scene::new(SceneId::new("main"), Duration::from_seconds(1.0));
+        let mut layer = Layer::new(
+            LayerId::new("box"),
+            LayerContent::Shape {
+                shape: ShapeType::Rect {
+                    width: 100.0,
+                    height: 40.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Color::WHITE.into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        );
+        layer.constraints = vec![LayoutConstraint::Center(CenterAxis::Both)];
+        scene.add_layer(layer);
+        project.add_scene(scene);
+
+        let ctx = EvalContext::new(&project);
+        let state = evaluate_frame(&ctx, &project, 0, &());
+        let layer = &state.scenes[0].layers[0];
+        let rect = layer.layout.unwrap();
+        assert_eq!((rect.x, rect.y), (110.0, 100.0));
+        assert_eq!(layer.anchored_origin(100, 40), (110, 100));
+    }
+
+    #[test]
+    fn test_child_constraints_resolve_within_parent() {
+        let rect = |w: f64, h: f64| LayerContent::Shape {
+            shape: ShapeType::Rect {
+                width: w,
+                height: h,
+                corner_radius: 0.0,
+            },
+            fill: Some(Color::WHITE.into()),
+            stroke: None,
+            stroke_width: 0.0,
+        };
+        let mut child = Layer::new(LayerId::new("label"), rect(20.0, 10.0));
+        child.constraints = vec![LayoutConstraint::Center(CenterAxis::Both)];
+        let mut parent =
+            Layer::new(LayerId::new("card"), rect(200.0, 100.0)).with_position(50.0, 50.0);
+        parent.add_child(child);
+
+        let mut project = Project::new(ProjectSettings::custom(320, 240, 10.0));
+        let mut scene = Scene::new(SceneId::new("main"), Duration::from_seconds(1.0));
+        scene.add_layer(parent);
+        project.add_scene(scene);
+
+        let ctx = EvalContext::new(&project);
+        let state = evaluate_frame(&ctx, &project, 0, &());
+        let card = &state.scenes[0].layers[0];
+        assert_eq!(card.position, (50, 50));
+        // Centered in the card's 200×100 content, not in the 320×240 canvas.
+        let label = &card.children[0];
+        let rect = label.layout.unwrap();
+        assert_eq!((rect.x, rect.y), (90.0, 45.0));
+        assert_eq!(label.anchored_origin(20, 10), (90, 45));
+    }
+}
@@ -0,0 +1,742 @@
+//! Animated per-layer state.
+
+use vidra_core::types::{LayerEffect, ShapeType};
+use vidra_core::{BlendMode, Color, Mat4, Paint, Transform2D};
+use vidra_ir::animation::AnimatableProperty;
+use vidra_ir::layer::{Layer, LayerContent, LayerId};
+use vidra_ir::layout::ResolvedLayout;
+use vidra_ir::mask::{MatteMode, ShapeMask};
+
+use crate::context::EvalContext;
+use crate::expr::evaluate_animation;
+
+/// The evaluated state of one layer at one frame.
+///
+/// Everything a rasterizer needs is already resolved here; the only thing left to it is
+/// the layer's pixel size, which for text and images is known only after drawing. Use
+/// [`LayerState::anchored_origin`] once that size is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerState {
+    pub id: LayerId,
+    /// Whether the layer (and its children) should be drawn.
+    pub visible: bool,
+    /// Layer content with animated properties (colors, font size, corner radius, ...) applied.
+    pub content: LayerContent,
+    /// Scene-local time of the frame, at which text animators run per character.
+    pub time: vidra_core::Duration,
+    /// Layer effects with animated parameters applied.
+    pub effects: Vec<LayerEffect>,
+    /// Animated position in the parent's unscaled pixels (the canvas for top-level
+    /// layers), before the anchor offset.
+    pub position: (i32, i32),
+    /// Animated scale factors.
+    pub scale: (f64, f64),
+    /// Animated opacity, clamped to 0–1.
+    pub opacity: f64,
+    /// Transform for projective compositing: `position` plus the animated rotation and
+    /// 2.5D parameters. Scale is already applied by the rasterizer and is not animated here.
+    pub transform: Transform2D,
+    pub blend_mode: BlendMode,
+    /// Optional track matte layer, looked up among the scene's top-level layers.
+    pub mask: Option<LayerId>,
+    pub matte_mode: MatteMode,
+    /// Shape mask with animated position and size applied.
+    pub shape_mask: Option<ShapeMask>,
+    /// Animated feather radius of the matte and shape mask, in pixels.
+    pub mask_feather: f64,
+    /// Resolved layout rect, when the layer has layout constraints.
+    pub layout: Option<ResolvedLayout>,
+    pub children: Vec<LayerState>,
+}
+
+impl LayerState {
+    /// Whether the content has an intrinsic bounding box (as opposed to a full-canvas fill).
+    /// Only layers with intrinsic sizes have anchor-point offsets applied.
+    pub fn has_intrinsic_size(&self) -> bool {
+        has_intrinsic_size(&self.content)
+    }
+
+    /// Top-left corner of the layer's `width`×`height` buffer in parent pixels.
+    pub fn anchored_origin(&self, width: u32, height: u32) -> (i32, i32) {
+        let (dx, dy) = self.position;
+        if self.has_intrinsic_size() {
+            let cx = dx - (width as f64 * self.transform.anchor.x).round() as i32;
+            let cy = dy - (height as f64 * self.transform.anchor.y).round() as i32;
+            (cx, cy)
+        } else {
+            (dx, dy)
+        }
+    }
+
+    /// Whether the layer must be composited through a projective warp rather than a blit.
+    pub fn needs_projective_composite(&self) -> bool {
+        let t = &self.transform;
+        t.rotation.abs() > f64::EPSILON
+            || t.translate_z.abs() > f64::EPSILON
+            || t.rotate_x.abs() > f64::EPSILON
+            || t.rotate_y.abs() > f64::EPSILON
+            || t.perspective > 0.0
+    }
+
+    /// Matrix mapping the layer's `width`×`height` content pixels (scale applied) into
+    /// its parent's space. Layers without rotation or 2.5D parameters translate to their
+    /// [`LayerState::anchored_origin`]; full-canvas content keeps its top-left at
+    /// `position` and only rotates about its anchor.
+    pub fn local_matrix(&self, width: u32, height: u32) -> Mat4 {
+        if !self.needs_projective_composite() {
+            let (x, y) = self.anchored_origin(width, height);
+            return Mat4::translation(x as f64, y as f64, 0.0);
+        }
+        let (w, h) = (width as f64, height as f64);
+        let m = self.transform.matrix(w, h);
+        if self.has_intrinsic_size() {
+            m
+        } else {
+            let a = self.transform.anchor;
+            Mat4::translation(a.x * w, a.y * h, 0.0) * m
+        }
+    }
+
+    /// Matrix mapping the space children are positioned in (this layer's unscaled
+    /// content pixels) into this layer's content pixels.
+    pub fn child_matrix(&self) -> Mat4 {
+        Mat4::scale(self.scale.0, self.scale.1, 1.0)
+    }
+
+    /// Where a `size` buffer of this layer lands, given that its top-left sits at
+    /// `origin` relative to the layer's `content`-sized rect, and that `parent` maps the
+    /// parent's space into the target. Whole-pixel translations are blits; anything else
+    /// is a projective warp.
+    pub fn placement(
+        &self,
+        parent: &Mat4,
+        content: (u32, u32),
+        origin: (i32, i32),
+        size: (u32, u32),
+    ) -> Placement {
+        let m = *parent
+            * self.local_matrix(content.0, content.1)
+            * Mat4::translation(origin.0 as f64, origin.1 as f64, 0.0);
+        Placement::of(&m, size)
+    }
+}
+
+/// How a layer buffer is composited into its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    /// Copy with its top-left at these pixel coordinates.
+    Blit(i32, i32),
+    /// Warp onto this quad (top-left, top-right, bottom-right, bottom-left).
+    Warp([[f64; 2]; 4]),
+}
+
+impl Placement {
+    /// Where matrix `m` puts a `size` buffer.
+    pub fn of(m: &Mat4, size: (u32, u32)) -> Placement {
+        match m.pixel_offset() {
+            Some((x, y)) => Placement::Blit(x, y),
+            None => Placement::Warp(m.project_rect(size.0 as f64, size.1 as f64)),
+        }
+    }
+
+    /// The same placement moved by `(dx, dy)` pixels.
+    pub fn offset(self, dx: i32, dy: i32) -> Placement {
+        match self {
+            Placement::Blit(x, y) => Placement::Blit(x + dx, y + dy),
+            Placement::Warp(quad) => {
+                Placement::Warp(quad.map(|[x, y]| [x + dx as f64, y + dy as f64]))
+            }
+        }
+    }
+
+    /// Pixel bounding box `(x, y, width, height)` of a `width`×`height` buffer placed here.
+    pub fn bounds(&self, width: u32, height: u32) -> (i32, i32, u32, u32) {
+        match self {
+            Placement::Blit(x, y) => (*x, *y, width, height),
+            Placement::Warp(quad) => {
+                let (mut x0, mut y0) = (f64::INFINITY, f64::INFINITY);
+                let (mut x1, mut y1) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+                for [x, y] in quad {
+                    x0 = x0.min(*x);
+                    y0 = y0.min(*y);
+                    x1 = x1.max(*x);
+                    y1 = y1.max(*y);
+                }
+                let (x0, y0) = (x0.floor(), y0.floor());
+                (
+                    x0 as i32,
+                    y0 as i32,
+                    (x1.ceil() - x0).max(0.0) as u32,
+                    (y1.ceil() - y0).max(0.0) as u32,
+                )
+            }
+        }
+    }
+}
+
+/// Supplies the size of content that only a rasterizer can measure (text, images).
+///
+/// Used to resolve layout constraints; content with a size known from the IR alone
+/// (shapes, web viewports, spritesheet cells, full-canvas fills) is never measured.
+pub trait ContentMeasure {
+    /// Unscaled pixel size of `content`, or `None` if unknown.
+    fn measure(&self, content: &LayerContent) -> Option<(u32, u32)>;
+}
+
+/// Measures nothing: measured content is treated as zero-sized.
+impl ContentMeasure for () {
+    fn measure(&self, _content: &LayerContent) -> Option<(u32, u32)> {
+        None
+    }
+}
+
+/// Evaluate `layer` and its children at scene-local `frame`.
+pub fn evaluate_layer(ctx: &EvalContext, layer: &Layer, frame: u64) -> LayerState {
+    evaluate_layer_with_base(ctx, layer, frame, None)
+}
+
+/// Like [`evaluate_layer`], but with the un-animated position replaced by `base`.
+pub(crate) fn evaluate_layer_with_base(
+    ctx: &EvalContext,
+    layer: &Layer,
+    frame: u64,
+    base: Option<(f64, f64)>,
+) -> LayerState {
+    let time = ctx.time_at(frame);
+    let mut content = layer.content.clone();
+    let mut effects = layer.effects.clone();
+    let mut t = layer.transform;
+    if let Some((x, y)) = base {
+        t.position.x = x;
+        t.position.y = y;
+    }
+    let mut opacity = t.opacity;
+    let mut shape_mask = layer.shape_mask.clone();
+    let mut mask_feather = layer.mask_feather;
+
+    let mut orientation = 0.0;
+
+    for anim in &layer.animations {
+        if anim.property.is_vector() {
+            let Some(v) = anim.evaluate_vector(time) else {
+                continue;
+            };
+            let component = |i: usize, default: f64| v.get(i).copied().unwrap_or(default);
+            match anim.property {
+                AnimatableProperty::Position => {
+                    t.position.x = component(0, t.position.x);
+                    t.position.y = component(1, t.position.y);
+                    if anim.auto_orient {
+                        orientation += anim.orientation(time).unwrap_or(0.0);
+                    }
+                }
+                AnimatableProperty::Scale => {
+                    t.scale.x = component(0, t.scale.x);
+                    t.scale.y = component(1, t.scale.y);
+                }
+                _ => {
+                    if let Some(c) = solid_color(&mut content) {
+                        *c = Color::rgba(
+                            component(0, c.r as f64) as f32,
+                            component(1, c.g as f64) as f32,
+                            component(2, c.b as f64) as f32,
+                            component(3, c.a as f64) as f32,
+                        );
+                    }
+                }
+            }
+            continue;
+        }
+        let Some(value) = evaluate_animation(ctx, anim, time) else {
+            continue;
+        };
+        match anim.property {
+            AnimatableProperty::PositionX => t.position.x = value,
+            AnimatableProperty::PositionY => t.position.y = value,
+            AnimatableProperty::ScaleX => t.scale.x = value,
+            AnimatableProperty::ScaleY => t.scale.y = value,
+            AnimatableProperty::Rotation => t.rotation = value,
+            AnimatableProperty::TranslateZ => t.translate_z = value,
+            AnimatableProperty::RotateX => t.rotate_x = value,
+            AnimatableProperty::RotateY => t.rotate_y = value,
+            AnimatableProperty::Perspective => t.perspective = value,
+            AnimatableProperty::Opacity => opacity = value,
+            AnimatableProperty::ColorR
+            | AnimatableProperty::ColorG
+            | AnimatableProperty::ColorB
+            | AnimatableProperty::ColorA => {
+                if let Some(c) = solid_color(&mut content) {
+                    match anim.property {
+                        AnimatableProperty::ColorR => c.r = value as f32,
+                        AnimatableProperty::ColorG => c.g = value as f32,
+                        AnimatableProperty::ColorB => c.b = value as f32,
+                        _ => c.a = value as f32,
+                    }
+                }
+            }
+            AnimatableProperty::GradientAngle | AnimatableProperty::GradientStop(_) => {
+                let paint = match &mut content {
+                    LayerContent::Solid { color } => Some(color),
+                    LayerContent::Shape { fill, .. } => fill.as_mut(),
+                    _ => None,
+                };
+                if let Some(Paint::Gradient(gradient)) = paint {
+                    match anim.property {
+                        AnimatableProperty::GradientStop(index) => {
+                            if let Some(stop) = gradient.stops.get_mut(index) {
+                                stop.offset = value;
+                            }
+                        }
+                        _ => gradient.set_angle(value),
+                    }
+                }
+            }
+            AnimatableProperty::FontSize => {
+                if let LayerContent::Text { font_size, .. } = &mut content {
+                    *font_size = value;
+                }
+            }
+            AnimatableProperty::CornerRadius => {
+                if let LayerContent::Shape {
+                    shape: ShapeType::Rect { corner_radius, .. },
+                    ..
+                } = &mut content
+                {
+                    *corner_radius = value;
+                }
+            }
+            AnimatableProperty::StrokeWidth => {
+                if let LayerContent::Shape { stroke_width, .. } = &mut content {
+                    *stroke_width = value;
+                }
+            }
+            AnimatableProperty::TrimStart
+            | AnimatableProperty::TrimEnd
+            | AnimatableProperty::TrimOffset => {
+                if let LayerContent::Shape {
+                    shape: ShapeType::Path(path),
+                    ..
+                } = &mut content
+                {
+                    match anim.property {
+                        AnimatableProperty::TrimStart => path.trim.start = value,
+                        AnimatableProperty::TrimEnd => path.trim.end = value,
+                        _ => path.trim.offset = value,
+                    }
+                }
+            }
+            AnimatableProperty::Volume => {
+                if let LayerContent::Audio { volume, .. } | LayerContent::TTS { volume, .. } =
+                    &mut content
+                {
+                    *volume = value;
+                }
+            }
+            AnimatableProperty::BlurRadius => {
+                for effect in &mut effects {
+                    if let LayerEffect::Blur(radius) = effect {
+                        *radius = value;
+                    }
+                }
+            }
+            AnimatableProperty::BrightnessLevel => {
+                for effect in &mut effects {
+                    if let LayerEffect::Brightness(level) = effect {
+                        *level = value;
+                    }
+                }
+            }
+            AnimatableProperty::ShadowX
+            | AnimatableProperty::ShadowY
+            | AnimatableProperty::ShadowBlur
+            | AnimatableProperty::ShadowSpread
+            | AnimatableProperty::InnerShadowX
+            | AnimatableProperty::InnerShadowY
+            | AnimatableProperty::InnerShadowBlur
+            | AnimatableProperty::InnerShadowSpread
+            | AnimatableProperty::GlowBlur
+            | AnimatableProperty::GlowSpread
+            | AnimatableProperty::OutlineWidth
+            | AnimatableProperty::BevelSize
+            | AnimatableProperty::BevelDepth
+            | AnimatableProperty::BevelAngle => {
+                for effect in &mut effects {
+                    animate_style(effect, &anim.property, value);
+                }
+            }
+            AnimatableProperty::MaskX
+            | AnimatableProperty::MaskY
+            | AnimatableProperty::MaskWidth
+            | AnimatableProperty::MaskHeight => {
+                if let Some(mask) = &mut shape_mask {
+                    match anim.property {
+                        AnimatableProperty::MaskX => mask.position.x = value,
+                        AnimatableProperty::MaskY => mask.position.y = value,
+                        AnimatableProperty::MaskWidth => mask.set_width(value),
+                        _ => mask.set_height(value),
+                    }
+                }
+            }
+            AnimatableProperty::MaskFeather => mask_feather = value,
+            AnimatableProperty::CropTop
+            | AnimatableProperty::CropRight
+            | AnimatableProperty::CropBottom
+            | AnimatableProperty::CropLeft => {}
+            // Only meaningful among a text animator's own animations.
+            AnimatableProperty::TextRangeStart
+            | AnimatableProperty::TextRangeEnd
+            | AnimatableProperty::TextRangeOffset => {}
+            AnimatableProperty::Position
+            | AnimatableProperty::Scale
+            | AnimatableProperty::Color => unreachable!("vector properties are handled above"),
+        }
+    }
+    t.rotation += orientation;
+
+    let position = (t.position.x as i32, t.position.y as i32);
+    let scale = (t.scale.x, t.scale.y);
+    t.position.x = position.0 as f64;
+    t.position.y = position.1 as f64;
+    t.scale = layer.transform.scale;
+    t.opacity = layer.transform.opacity;
+
+    LayerState {
+        id: layer.id.clone(),
+        visible: layer.visible,
+        content,
+        time,
+        effects,
+        position,
+        scale,
+        opacity: opacity.clamp(0.0, 1.0),
+        transform: t,
+        blend_mode: layer.blend_mode,
+        mask: layer.mask.clone(),
+        matte_mode: layer.matte_mode,
+        shape_mask,
+        mask_feather: mask_feather.max(0.0),
+        layout: None,
+        children: layer
+            .children
+            .iter()
+            .map(|child| evaluate_layer(ctx, child, frame))
+            .collect(),
+    }
+}
+
+/// Set the parameter of `effect` that `property` animates, if it is that kind of style.
+fn animate_style(effect: &mut LayerEffect, property: &AnimatableProperty, value: f64) {
+    use AnimatableProperty as P;
+    match (effect, property) {
+        (LayerEffect::DropShadow { offset, .. }, P::ShadowX)
+        | (LayerEffect::InnerShadow { offset, .. }, P::InnerShadowX) => offset.x = value,
+        (LayerEffect::DropShadow { offset, .. }, P::ShadowY)
+        | (LayerEffect::InnerShadow { offset, .. }, P::InnerShadowY) => offset.y = value,
+        (LayerEffect::DropShadow { blur, .. }, P::ShadowBlur)
+        | (LayerEffect::InnerShadow { blur, .. }, P::InnerShadowBlur)
+        | (LayerEffect::OuterGlow { blur, .. }, P::GlowBlur) => *blur = value.max(0.0),
+        (LayerEffect::DropShadow { spread, .. }, P::ShadowSpread)
+        | (LayerEffect::InnerShadow { spread, .. }, P::InnerShadowSpread)
+        | (LayerEffect::OuterGlow { spread, .. }, P::GlowSpread) => *spread = value,
+        (LayerEffect::Stroke { width, .. }, P::OutlineWidth) => *width = value.max(0.0),
+        (LayerEffect::Bevel { size, .. }, P::BevelSize) => *size = value.max(0.0),
+        (LayerEffect::Bevel { depth, .. }, P::BevelDepth) => *depth = value,
+        (LayerEffect::Bevel { angle, .. }, P::BevelAngle) => *angle = value,
+        _ => {}
+    }
+}
+
+/// The flat color of text, a solid or a shape fill. Gradients keep their stop colors.
+fn solid_color(content: &mut LayerContent) -> Option<&mut Color> {
+    match content {
+        LayerContent::Text { color, .. } => Some(color),
+        LayerContent::Solid { color } => color.solid_mut(),
+        LayerContent::Shape { fill, .. } => fill.as_mut().and_then(Paint::solid_mut),
+        _ => None,
+    }
+}
+
+/// Whether `content` has an intrinsic bounding box (as opposed to a full-canvas fill).
+pub fn has_intrinsic_size(content: &LayerContent) -> bool {
+    matches!(
+        content,
+        LayerContent::Text { .. }
+            | LayerContent::Image { .. }
+            | LayerContent::Svg { .. }
+            | LayerContent::Video { .. }
+            | LayerContent::Shape { .. }
+            | LayerContent::TTS { .. }
+            | LayerContent::AutoCaption { .. }
+            | LayerContent::Waveform { .. }
+            | LayerContent::Spritesheet { .. }
+            | LayerContent::Web { .. }
+    )
+}
+
+/// Unscaled pixel size of `content` when it follows from the IR alone.
+pub(crate) fn static_size(ctx: &EvalContext, content: &LayerContent) -> Option<(f64, f64)> {
+    match content {
+        LayerContent::Shape {
+            shape,
+            stroke,
+            stroke_width,
+            ..
+        } => match shape {
+            ShapeType::Rect { width, height, .. } => Some((*width, *height)),
+            ShapeType::Circle { radius } => Some((radius * 2.0, radius * 2.0)),
+            ShapeType::Ellipse { rx, ry } => Some((rx * 2.0, ry * 2.0)),
+            ShapeType::Path(path) => {
+                let width = if stroke.is_some() { *stroke_width } else { 0.0 };
+                let (_, size) = path.content_rect(width);
+                Some((size.width, size.height))
+            }
+        },
+        LayerContent::Spritesheet {
+            frame_width,
+            frame_height,
+            ..
+        } => Some((*frame_width as f64, *frame_height as f64)),
+        LayerContent::Web {
+            viewport_width,
+            viewport_height,
+            ..
+        } => Some((*viewport_width as f64, *viewport_height as f64)),
+        LayerContent::Solid { .. }
+        | LayerContent::Shader { .. }
+        | LayerContent::Plugin { .. }
+        | LayerContent::Empty => Some((ctx.width as f64, ctx.height as f64)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_core::{Color, Duration};
+    use vidra_ir::animation::Animation;
+
+    fn ctx() -> EvalContext {
+        EvalContext {
+            width: 320,
+            height: 240,
+            fps: 10.0,
+            mouse_x: 0.0,
+            mouse_y: 0.0,
+            state_vars: Default::default(),
+            exprs: Default::default(),
+            audio: Default::default(),
+            subframe: 0.0,
+        }
+    }
+
+    fn rect_layer() -> Layer {
+        Layer::new(
+            LayerId::new("box"),
+            LayerContent::Shape {
+                shape: ShapeType::Rect {
+                    width: 40.0,
+                    height: 20.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Color::RED.into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(100.7, 50.2)
+    }
+
+    #[test]
+    fn test_static_layer_state() {
+        let state = evaluate_layer(&ctx(), &rect_layer(), 0);
+        assert_eq!(state.position, (100, 50));
+        assert_eq!(state.scale, (1.0, 1.0));
+        assert_eq!(state.opacity, 1.0);
+        assert!(!state.needs_projective_composite());
+        // Default anchor is the center of the layer's buffer.
+        assert_eq!(state.anchored_origin(40, 20), (80, 40));
+    }
+
+    #[test]
+    fn test_animations_apply_to_transform_content_and_effects() {
+        let mut layer = rect_layer();
+        layer.effects.push(LayerEffect::Blur(1.0));
+        for (property, from, to) in [
+            (AnimatableProperty::PositionX, 0.0, 200.0),
+            (AnimatableProperty::Opacity, 0.0, 2.0),
+            (AnimatableProperty::Rotation, 0.0, 90.0),
+            (AnimatableProperty::ColorG, 0.0, 1.0),
+            (AnimatableProperty::BlurRadius, 0.0, 8.0),
+        ] {
+            layer.animations.push(Animation::from_to(
+                property,
+                from,
+                to,
+                Duration::from_seconds(1.0),
+                vidra_core::types::Easing::Linear,
+            ));
+        }
+
+        let state = evaluate_layer(&ctx(), &layer, 5);
+        assert_eq!(state.position, (100, 50));
+        assert_eq!(state.opacity, 1.0);
+        assert!((state.transform.rotation - 45.0).abs() < 1e-9);
+        assert!(state.needs_projective_composite());
+        let LayerContent::Shape {
+            fill: Some(fill), ..
+        } = &state.content
+        else {
+            panic!("expected shape content");
+        };
+        assert!((fill.as_solid().unwrap().g - 0.5).abs() < 1e-6);
+        assert_eq!(state.effects, vec![LayerEffect::Blur(4.0)]);
+    }
+
+    #[test]
+    fn test_animations_apply_to_the_matching_layer_style() {
+        let mut layer = rect_layer();
+        let shadow = LayerEffect::DropShadow {
+            offset: vidra_core::Point2D::new(0.0, 4.0),
+            blur: 2.0,
+            color: Color::BLACK,
+            spread: 0.0,
+        };
+        let glow = LayerEffect::OuterGlow {
+            blur: 2.0,
+            color: Color::WHITE,
+            spread: 0.0,
+        };
+        layer.effects = vec![shadow, glow.clone()];
+        for (property, from, to) in [
+            (AnimatableProperty::ShadowX, 0.0, 10.0),
+            (AnimatableProperty::ShadowBlur, 0.0, 20.0),
+        ] {
+            layer.animations.push(Animation::from_to(
+                property,
+                from,
+                to,
+                Duration::from_seconds(1.0),
+                vidra_core::types::Easing::Linear,
+            ));
+        }
+
+        let state = evaluate_layer(&ctx(), &layer, 5);
+        assert_eq!(
+            state.effects,
+            vec![
+                LayerEffect::DropShadow {
+                    offset: vidra_core::Point2D::new(5.0, 4.0),
+                    blur: 10.0,
+                    color: Color::BLACK,
+                    spread: 0.0,
+                },
+                glow,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subframe_offsets_animation_time() {
+        let layer = rect_layer().with_animation(Animation::from_to(
+            AnimatableProperty::PositionX,
+            0.0,
+            100.0,
+            Duration::from_seconds(1.0),
+            vidra_core::types::Easing::Linear,
+        ));
+        assert_eq!(evaluate_layer(&ctx(), &layer, 5).position.0, 50);
+        let ctx = ctx().with_subframe(0.5);
+        assert_eq!(evaluate_layer(&ctx, &layer, 5).position.0, 55);
+        // Sub-frame times before the start clamp to 0.
+        let ctx = ctx.with_subframe(-0.5);
+        assert_eq!(evaluate_layer(&ctx, &layer, 0).position.0, 0);
+    }
+
+    #[test]
+    fn test_stepped_and_held_keyframes() {
+        use vidra_core::types::{Easing, StepPosition};
+        use vidra_ir::animation::Keyframe;
+
+        let mut x = Animation::new(AnimatableProperty::PositionX);
+        x.add_keyframe(Keyframe::new(Duration::zero(), 0.0).with_hold());
+        x.add_keyframe(Keyframe::new(Duration::from_seconds(1.0), 100.0));
+        let y = Animation::from_to(
+            AnimatableProperty::PositionY,
+            0.0,
+            100.0,
+            Duration::from_seconds(1.0),
+            Easing::Steps(4, StepPosition::JumpEnd),
+        );
+        let layer = rect_layer().with_animation(x).with_animation(y);
+
+        assert_eq!(evaluate_layer(&ctx(), &layer, 6).position, (0, 50));
+        assert_eq!(evaluate_layer(&ctx(), &layer, 9).position, (0, 75));
+        assert_eq!(evaluate_layer(&ctx(), &layer, 10).position, (100, 100));
+    }
+
+    #[test]
+    fn test_vector_animations_and_auto_orient() {
+        use vidra_core::types::Easing;
+
+        let mut position = Animation::from_to_vector(
+            AnimatableProperty::Position,
+            vec![0.0, 0.0],
+            vec![100.0, 100.0],
+            Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        position.auto_orient = true;
+        let scale = Animation::from_to_vector(
+            AnimatableProperty::Scale,
+            vec![1.0, 1.0],
+            vec![3.0, 2.0],
+            Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        let color = Animation::from_to_vector(
+            AnimatableProperty::Color,
+            vec![1.0, 0.0, 0.0, 1.0],
+            vec![1.0, 0.0, 0.0, 0.0],
+            Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        let rotation = Animation::from_to(
+            AnimatableProperty::Rotation,
+            10.0,
+            10.0,
+            Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        let layer = rect_layer()
+            .with_animation(position)
+            .with_animation(scale)
+            .with_animation(color)
+            .with_animation(rotation);
+
+        let state = evaluate_layer(&ctx(), &layer, 5);
+        assert_eq!(state.position, (50, 50));
+        assert_eq!(state.scale, (2.0, 1.5));
+        // Heading down-right, on top of the animated rotation.
+        assert!((state.transform.rotation - 55.0).abs() < 1e-6);
+        let LayerContent::Shape {
+            fill: Some(fill), ..
+        } = &state.content
+        else {
+            panic!("expected shape content");
+        };
+        assert!((fill.as_solid().unwrap().a - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_full_canvas_layers_ignore_anchor() {
+        let layer = Layer::new(
+            LayerId::new("bg"),
+            LayerContent::Solid {
+                color: Color::BLUE.into(),
+            },
+        )
+        .with_position(5.0, 6.0);
+        let state = evaluate_layer(&ctx(), &layer, 0);
+        assert_eq!(state.anchored_origin(320, 240), (5, 6));
+    }
+}
@@ -0,0 +1,31 @@
+//! # vidra-eval
+//!
+//! Per-frame evaluation of a Vidra project, shared by the native and WASM renderers.
+//!
+//! Given a project and a global frame index this crate works out which scenes are on
+//! screen, how far a transition has progressed, and the animated state of every layer
+//! (content, effects, transform, opacity, resolved layout, visibility). It has no GPU,
+//! threading or platform dependencies, so renderers only have to rasterize the
+//! [`FrameState`] it produces.
+
+pub mod animator;
+pub mod audio;
+pub mod context;
+pub mod expr;
+pub mod frame;
+pub mod layer;
+pub mod mask;
+pub mod media;
+pub mod raster;
+pub mod shape;
+pub mod svg;
+pub mod text;
+
+pub use audio::{AudioEnvelope, AudioEnvelopes, AudioFrame};
+pub use context::EvalContext;
+pub use expr::ExprCache;
+pub use frame::{active_scenes, evaluate_frame, FrameState, SceneState, TransitionState};
+pub use layer::{evaluate_layer, ContentMeasure, LayerState, Placement};
+pub use media::{expand_media, MediaReport, MediaSource};
+pub use raster::LayerBuffer;
+pub use text::FontBook;
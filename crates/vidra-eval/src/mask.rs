@@ -0,0 +1,341 @@
+//! Track mattes and shape masks, applied identically by every renderer.
+//!
+//! Both kinds of mask come down to a coverage map over the masked layer's buffer,
+//! which is feathered by the layer's `mask_feather` and multiplied into its alpha.
+
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::path::PathShape;
+use vidra_core::types::ShapeType;
+use vidra_core::Point2D;
+use vidra_ir::mask::{MatteMode, ShapeMask};
+
+use crate::layer::LayerState;
+use crate::raster::LayerBuffer;
+use crate::shape::polygon_coverage;
+
+/// Clip `output`, the grouped buffer of `layer`, to the layer's shape mask (if any).
+pub fn apply_shape_mask(output: &mut LayerBuffer, layer: &LayerState) {
+    let Some(mask) = &layer.shape_mask else {
+        return;
+    };
+    let (width, height) = (output.buffer.width, output.buffer.height);
+    // The mask is placed in unscaled content pixels; the buffer starts at `origin`.
+    let (sx, sy) = (
+        layer.scale.0 * output.density.0,
+        layer.scale.1 * output.density.1,
+    );
+    let left = mask.position.x * sx - output.origin.0 as f64;
+    let top = mask.position.y * sy - output.origin.1 as f64;
+    let coverage = shape_coverage(mask, (sx, sy), (left, top), width, height);
+    let feather = layer.mask_feather * output.density.0.max(output.density.1);
+    apply_coverage(&mut output.buffer, coverage, feather);
+}
+
+/// Mask `output`, the grouped buffer of `layer`, by `matte`, the grouped buffer of its
+/// track matte layer `matte_layer`, read in the layer's matte mode.
+///
+/// The two buffers are aligned by their 2D anchored positions in the parent's space;
+/// rotation and 2.5D transforms of either layer are not taken into account.
+pub fn apply_track_matte(
+    output: &mut LayerBuffer,
+    layer: &LayerState,
+    matte: &LayerBuffer,
+    matte_layer: &LayerState,
+) {
+    let (cx, cy) = layer.anchored_origin(output.content_size.0, output.content_size.1);
+    let (mx, my) = matte_layer.anchored_origin(matte.content_size.0, matte.content_size.1);
+    let offset = (
+        mx + matte.origin.0 - (cx + output.origin.0),
+        my + matte.origin.1 - (cy + output.origin.1),
+    );
+    let coverage = matte_coverage(
+        &matte.buffer,
+        layer.matte_mode,
+        offset,
+        output.buffer.width,
+        output.buffer.height,
+    );
+    apply_coverage(&mut output.buffer, coverage, layer.mask_feather);
+}
+
+/// Feather `coverage` by `feather` pixels and multiply it into `buffer`'s alpha.
+fn apply_coverage(buffer: &mut FrameBuffer, mut coverage: Vec<u8>, feather: f64) {
+    if buffer.format != PixelFormat::Rgba8 {
+        return;
+    }
+    feather_coverage(&mut coverage, buffer.width, buffer.height, feather);
+    for (px, &c) in buffer.data.chunks_exact_mut(4).zip(&coverage) {
+        px[3] = ((px[3] as u32 * c as u32 + 127) / 255) as u8;
+    }
+}
+
+/// Per-pixel coverage of a `width`×`height` buffer by `matte` placed at `offset`.
+/// Pixels the matte does not reach count as fully transparent and black.
+fn matte_coverage(
+    matte: &FrameBuffer,
+    mode: MatteMode,
+    offset: (i32, i32),
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let mut coverage = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let value = matte
+                .get_pixel((x - offset.0) as u32, (y - offset.1) as u32)
+                .filter(|_| x >= offset.0 && y >= offset.1)
+                .map_or(0, |px| matte_value(px, mode));
+            coverage.push(if mode.is_inverted() {
+                255 - value
+            } else {
+                value
+            });
+        }
+    }
+    coverage
+}
+
+/// Coverage of one matte pixel before inversion: its alpha, or its Rec. 709 luminance
+/// weighted by alpha.
+fn matte_value(px: [u8; 4], mode: MatteMode) -> u8 {
+    match mode {
+        MatteMode::Alpha | MatteMode::AlphaInverted => px[3],
+        MatteMode::Luma | MatteMode::LumaInverted => {
+            let [r, g, b, a] = px.map(u32::from);
+            let luma = (2126 * r + 7152 * g + 722 * b + 5000) / 10000;
+            ((luma * a + 127) / 255) as u8
+        }
+    }
+}
+
+/// Anti-aliased coverage of `mask` drawn at `scale` with its bounding box's top-left
+/// at `(left, top)` of a `width`×`height` buffer.
+fn shape_coverage(
+    mask: &ShapeMask,
+    scale: (f64, f64),
+    (left, top): (f64, f64),
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let (w, h) = mask.size();
+    let (hw, hh) = (w * scale.0 / 2.0, h * scale.1 / 2.0);
+    let (cx, cy) = (left + hw, top + hh);
+    let corner = match &mask.shape {
+        ShapeType::Rect { corner_radius, .. } => {
+            Some((corner_radius * scale.0.min(scale.1)).clamp(0.0, hw.min(hh)))
+        }
+        ShapeType::Circle { .. } | ShapeType::Ellipse { .. } => None,
+        ShapeType::Path(path) => {
+            return path_coverage(path, mask.inverted, scale, (left, top), width, height)
+        }
+    };
+
+    let mut coverage = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let inside = if hw <= 0.0 || hh <= 0.0 {
+                0.0
+            } else {
+                let (px, py) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+                let distance = match corner {
+                    Some(r) => rounded_rect_distance(px, py, hw, hh, r),
+                    None => ellipse_distance(px, py, hw, hh),
+                };
+                (0.5 - distance).clamp(0.0, 1.0)
+            };
+            let value = (inside * 255.0).round() as u8;
+            coverage.push(if mask.inverted { 255 - value } else { value });
+        }
+    }
+    coverage
+}
+
+/// Coverage of a path mask's fill, placed like [`shape_coverage`].
+fn path_coverage(
+    path: &PathShape,
+    inverted: bool,
+    scale: (f64, f64),
+    (left, top): (f64, f64),
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let (origin, _) = path.content_rect(0.0);
+    let polygons: Vec<Vec<Point2D>> = path
+        .outlines()
+        .into_iter()
+        .map(|line| {
+            line.points
+                .iter()
+                .map(|p| {
+                    Point2D::new(
+                        (p.x - origin.x) * scale.0 + left,
+                        (p.y - origin.y) * scale.1 + top,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    polygon_coverage(&polygons, path.fill_rule, width, height)
+        .into_iter()
+        .map(|c| {
+            let value = (c * 255.0).round() as u8;
+            if inverted {
+                255 - value
+            } else {
+                value
+            }
+        })
+        .collect()
+}
+
+/// Signed distance from `(px, py)` to a rect of half-size `hw`×`hh` with corner radius
+/// `r`, centered on the origin.
+fn rounded_rect_distance(px: f64, py: f64, hw: f64, hh: f64, r: f64) -> f64 {
+    let qx = px.abs() - hw + r;
+    let qy = py.abs() - hh + r;
+    qx.max(0.0).hypot(qy.max(0.0)) + qx.max(qy).min(0.0) - r
+}
+
+/// Approximate signed distance from `(px, py)` to an ellipse with radii `rx`, `ry`
+/// centered on the origin (exact for circles).
+fn ellipse_distance(px: f64, py: f64, rx: f64, ry: f64) -> f64 {
+    let k0 = (px / rx).hypot(py / ry);
+    let k1 = (px / (rx * rx)).hypot(py / (ry * ry));
+    if k1 == 0.0 {
+        return -rx.min(ry);
+    }
+    k0 * (k0 - 1.0) / k1
+}
+
+/// Soften `coverage` with three box blur passes, which spread an edge over roughly
+/// `feather` pixels on either side. Edges of the buffer are extended outwards.
+pub(crate) fn feather_coverage(coverage: &mut [u8], width: u32, height: u32, feather: f64) {
+    let radius = (feather / 3.0).ceil() as usize;
+    if radius == 0 || width == 0 || height == 0 {
+        return;
+    }
+    let (w, h) = (width as usize, height as usize);
+    let mut line = Vec::new();
+    for _ in 0..3 {
+        for row in coverage.chunks_exact_mut(w) {
+            box_blur_line(row, 1, radius, &mut line);
+        }
+        for x in 0..w {
+            box_blur_line(&mut coverage[x..], w, radius, &mut line);
+        }
+    }
+    debug_assert_eq!(coverage.len(), w * h);
+}
+
+/// Box blur every `stride`-th value of `data` (starting at index 0) in place.
+fn box_blur_line(data: &mut [u8], stride: usize, radius: usize, line: &mut Vec<u8>) {
+    line.clear();
+    line.extend(data.iter().step_by(stride));
+    let n = line.len();
+    let at = |i: isize| line[i.clamp(0, n as isize - 1) as usize] as u32;
+    let r = radius as isize;
+    let window = 2 * radius as u32 + 1;
+    let mut sum: u32 = (-r..=r).map(at).sum();
+    for i in 0..n {
+        data[i * stride] = ((sum + window / 2) / window) as u8;
+        let i = i as isize;
+        sum = sum + at(i + r + 1) - at(i - r);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::EvalContext;
+    use crate::layer::evaluate_layer;
+    use vidra_core::Color;
+    use vidra_ir::layer::{Layer, LayerContent, LayerId};
+    use vidra_ir::project::{Project, ProjectSettings};
+
+    fn state(layer: Layer) -> LayerState {
+        let ctx = EvalContext::new(&Project::new(ProjectSettings::custom(16, 1, 10.0)));
+        evaluate_layer(&ctx, &layer, 0)
+    }
+
+    fn solid(id: &str) -> Layer {
+        Layer::new(
+            LayerId::new(id),
+            LayerContent::Solid {
+                color: Color::WHITE.into(),
+            },
+        )
+    }
+
+    fn alphas(buf: &LayerBuffer) -> Vec<u8> {
+        buf.buffer.data.chunks_exact(4).map(|px| px[3]).collect()
+    }
+
+    #[test]
+    fn test_track_matte_modes() {
+        let mut matte = FrameBuffer::new(4, 1, PixelFormat::Rgba8);
+        matte.set_pixel(0, 0, [255, 255, 255, 255]);
+        matte.set_pixel(1, 0, [0, 0, 0, 255]);
+        matte.set_pixel(2, 0, [255, 255, 255, 0]);
+        matte.set_pixel(3, 0, [128, 128, 128, 255]);
+        let matte = LayerBuffer::new(matte);
+        let matte_layer = state(solid("matte"));
+
+        let masked = |mode| {
+            let layer = state(solid("title").with_matte(LayerId::new("matte"), mode));
+            let mut output = LayerBuffer::new(FrameBuffer::solid(4, 1, &Color::WHITE));
+            apply_track_matte(&mut output, &layer, &matte, &matte_layer);
+            alphas(&output)
+        };
+        assert_eq!(masked(MatteMode::Alpha), vec![255, 255, 0, 255]);
+        assert_eq!(masked(MatteMode::AlphaInverted), vec![0, 0, 255, 0]);
+        assert_eq!(masked(MatteMode::Luma), vec![255, 0, 0, 128]);
+        assert_eq!(masked(MatteMode::LumaInverted), vec![0, 255, 255, 127]);
+    }
+
+    #[test]
+    fn test_shape_mask_scales_with_layer() {
+        let rect = ShapeType::Rect {
+            width: 2.0,
+            height: 1.0,
+            corner_radius: 0.0,
+        };
+        let layer = state(
+            solid("bar")
+                .with_scale(2.0, 1.0)
+                .with_shape_mask(ShapeMask::new(rect.clone(), 1.0, 0.0)),
+        );
+        let mut output = LayerBuffer::new(FrameBuffer::solid(8, 1, &Color::WHITE));
+        apply_shape_mask(&mut output, &layer);
+        assert_eq!(alphas(&output), vec![0, 0, 255, 255, 255, 255, 0, 0]);
+
+        let mut inverted = ShapeMask::new(rect, 1.0, 0.0);
+        inverted.inverted = true;
+        let layer = state(solid("bar").with_shape_mask(inverted));
+        let mut output = LayerBuffer::new(FrameBuffer::solid(4, 1, &Color::WHITE));
+        apply_shape_mask(&mut output, &layer);
+        assert_eq!(alphas(&output), vec![255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_feather_softens_mask_edge() {
+        let rect = ShapeType::Rect {
+            width: 8.0,
+            height: 1.0,
+            corner_radius: 0.0,
+        };
+        let mut layer = solid("bar").with_shape_mask(ShapeMask::new(rect, 8.0, 0.0));
+        layer.mask_feather = 6.0;
+        let layer = state(layer);
+        let mut output = LayerBuffer::new(FrameBuffer::solid(16, 1, &Color::WHITE));
+        apply_shape_mask(&mut output, &layer);
+
+        let a = alphas(&output);
+        assert_eq!((a[0], a[15]), (0, 255));
+        assert!(a[7] > 0 && a[8] < 255, "edge is not softened: {:?}", a);
+        assert!(
+            a.windows(2).all(|w| w[0] <= w[1]),
+            "ramp is not monotonic: {:?}",
+            a
+        );
+    }
+}
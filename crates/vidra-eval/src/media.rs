@@ -0,0 +1,218 @@
+//! Media layers that expand into ordinary layers before evaluation.
+//!
+//! SVG layers that ask for it (`groups: true`) get a child layer per `<g id>` group, and
+//! Lottie layers are replaced by the layers of the imported animation. Renderers run
+//! [`expand_media`] when they load a project, so the same source gives every renderer
+//! the same layer tree.
+
+use std::sync::Arc;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use vidra_core::VidraError;
+use vidra_ir::asset::{AssetId, AssetRegistry};
+use vidra_ir::layer::{Layer, LayerContent};
+use vidra_ir::lottie::{ImportIssue, LottieImport};
+use vidra_ir::Project;
+
+use crate::svg::SvgDocument;
+
+/// Where [`expand_media`] gets the documents media layers refer to. Assets are
+/// registered in `assets` or named by their path. A source returns `None` for a document
+/// it doesn't have (yet), and the layer is left as it is.
+pub trait MediaSource {
+    /// The SVG document of the asset `id`.
+    fn svg(
+        &self,
+        assets: &AssetRegistry,
+        id: &AssetId,
+    ) -> Result<Option<Arc<SvgDocument>>, VidraError>;
+
+    /// The Lottie animation of the asset `id`, imported. Images it references by a
+    /// relative path should already point at where the renderer finds them.
+    fn lottie(
+        &self,
+        assets: &AssetRegistry,
+        id: &AssetId,
+    ) -> Result<Option<LottieImport>, VidraError>;
+}
+
+/// What [`expand_media`] did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaReport {
+    pub svg_layers_expanded: usize,
+    pub lottie_layers_imported: usize,
+    /// Parts of Lottie animations that were imported differently or left out.
+    pub lottie_issues: Vec<ImportIssue>,
+}
+
+/// Expand the SVG group and Lottie layers of `project` in place, registering the images
+/// of Lottie animations as assets. Expanded layers are left as ordinary layers, so
+/// expanding a project twice changes nothing.
+pub fn expand_media(
+    project: &mut Project,
+    source: &impl MediaSource,
+) -> Result<MediaReport, VidraError> {
+    let mut report = MediaReport::default();
+    let Project { assets, scenes, .. } = project;
+    for scene in scenes {
+        for layer in &mut scene.layers {
+            expand_layer(layer, assets, source, &mut report)?;
+        }
+    }
+    Ok(report)
+}
+
+fn expand_layer(
+    layer: &mut Layer,
+    assets: &mut AssetRegistry,
+    source: &impl MediaSource,
+    report: &mut MediaReport,
+) -> Result<(), VidraError> {
+    if let LayerContent::Svg {
+        asset_id,
+        expand_groups: true,
+        ..
+    } = &layer.content
+    {
+        if let Some(doc) = source.svg(assets, asset_id)? {
+            report.svg_layers_expanded += doc.expand_groups(layer);
+        }
+    }
+
+    if let LayerContent::Lottie { asset_id } = &layer.content {
+        let Some(import) = source.lottie(assets, asset_id)? else {
+            return Ok(());
+        };
+        let mut import = import.nested();
+        import.scope_assets(&asset_id.0);
+        report.lottie_issues.append(&mut import.issues);
+        for asset in import.assets {
+            assets.register(asset);
+        }
+        layer.content = LayerContent::Empty;
+        layer.children.splice(0..0, import.layers);
+        report.lottie_layers_imported += 1;
+    }
+
+    for child in &mut layer.children {
+        expand_layer(child, assets, source, report)?;
+    }
+    Ok(())
+}
+
+/// The bytes of a base64 `data:` URI, such as the path of an image embedded in a Lottie
+/// animation. `None` if `uri` is not a `data:` URI.
+pub fn data_uri_bytes(uri: &str) -> Option<Result<Vec<u8>, VidraError>> {
+    let rest = uri.strip_prefix("data:")?;
+    let decoded = match rest.split_once(',') {
+        Some((header, payload)) if header.ends_with(";base64") => BASE64_STANDARD
+            .decode(payload.trim())
+            .map_err(|e| VidraError::InvalidArgument(format!("invalid base64 data URI: {}", e))),
+        _ => Err(VidraError::InvalidArgument(
+            "only base64 data URIs are supported".into(),
+        )),
+    };
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_ir::asset::{Asset, AssetType};
+    use vidra_ir::layer::LayerId;
+    use vidra_ir::scene::{Scene, SceneId};
+    use vidra_ir::ProjectSettings;
+
+    const BADGE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20">
+        <g id="left"><rect width="20" height="20" fill="red"/></g>
+        <g id="right"><rect x="20" width="20" height="20" fill="blue"/></g>
+    </svg>"#;
+
+    const ANIMATION: &str = r#"{
+        "v": "5.7.0", "fr": 30, "ip": 0, "op": 30, "w": 100, "h": 100,
+        "assets": [{ "id": "image_0", "w": 1, "h": 1, "u": "images/", "p": "dot.png" }],
+        "layers": [{
+            "ddd": 0, "ind": 1, "ty": 2, "nm": "dot", "refId": "image_0", "ip": 0, "op": 30,
+            "st": 0, "ks": {}
+        }]
+    }"#;
+
+    struct Documents;
+
+    impl MediaSource for Documents {
+        fn svg(
+            &self,
+            _: &AssetRegistry,
+            id: &AssetId,
+        ) -> Result<Option<Arc<SvgDocument>>, VidraError> {
+            assert_eq!(id.0, "badge");
+            Ok(Some(Arc::new(SvgDocument::parse(BADGE)?)))
+        }
+
+        fn lottie(
+            &self,
+            _: &AssetRegistry,
+            id: &AssetId,
+        ) -> Result<Option<LottieImport>, VidraError> {
+            assert_eq!(id.0, "anim");
+            vidra_ir::lottie::import(ANIMATION).map(Some)
+        }
+    }
+
+    #[test]
+    fn test_expand_media() {
+        let mut project = Project::new(ProjectSettings::custom(100, 100, 30.0));
+        project.assets.register(Asset::new(
+            AssetId::new("anim"),
+            AssetType::Lottie,
+            "anim.json",
+        ));
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(1.0));
+        scene.add_layer(Layer::new(
+            LayerId::new("badge"),
+            LayerContent::Svg {
+                asset_id: AssetId::new("badge"),
+                element: None,
+                exclude: Vec::new(),
+                expand_groups: true,
+            },
+        ));
+        scene.add_layer(Layer::new(
+            LayerId::new("anim"),
+            LayerContent::Lottie {
+                asset_id: AssetId::new("anim"),
+            },
+        ));
+        project.add_scene(scene);
+
+        let report = expand_media(&mut project, &Documents).unwrap();
+        assert_eq!(report.svg_layers_expanded, 3);
+        assert_eq!(report.lottie_layers_imported, 1);
+
+        let layers = &project.scenes[0].layers;
+        let groups: Vec<_> = layers[0].children.iter().map(|c| c.id.0.as_str()).collect();
+        assert_eq!(groups, ["left", "right"]);
+        assert!(matches!(layers[1].content, LayerContent::Empty));
+        assert_eq!(layers[1].children.len(), 1);
+        assert!(project.assets.get(&AssetId::new("anim/image_0")).is_some());
+
+        // Nothing is left to expand.
+        assert_eq!(
+            expand_media(&mut project, &Documents).unwrap(),
+            MediaReport::default()
+        );
+    }
+
+    #[test]
+    fn test_data_uri_bytes() {
+        assert_eq!(
+            data_uri_bytes("data:image/png;base64,aGk=")
+                .unwrap()
+                .unwrap(),
+            b"hi"
+        );
+        assert!(data_uri_bytes("data:text/plain,hi").unwrap().is_err());
+        assert!(data_uri_bytes("images/dot.png").is_none());
+    }
+}
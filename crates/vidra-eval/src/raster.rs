@@ -0,0 +1,640 @@
+//! Pure-CPU pixel helpers that every renderer applies identically.
+
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::{BlendMode, Mat4};
+use vidra_ir::transition::{Direction, TransitionType};
+
+use crate::frame::TransitionState;
+use crate::layer::{LayerState, Placement};
+use crate::mask::feather_coverage;
+
+/// A layer rasterized together with its children, in the layer's own pixel space.
+#[derive(Debug, Clone)]
+pub struct LayerBuffer {
+    pub buffer: FrameBuffer,
+    /// Position of `buffer`'s top-left relative to the layer's content rect; negative
+    /// when children reach above or left of the content.
+    pub origin: (i32, i32),
+    /// Pixel size of the layer's own content (scale applied), which its anchor refers to.
+    pub content_size: (u32, u32),
+    /// Buffer pixels per content pixel. Above 1 when vector content was drawn at the
+    /// scale its ancestors will show it at, so that it stays sharp.
+    pub density: (f64, f64),
+}
+
+impl LayerBuffer {
+    /// A layer drawn without children.
+    pub fn new(content: FrameBuffer) -> Self {
+        Self {
+            content_size: (content.width, content.height),
+            origin: (0, 0),
+            density: (1.0, 1.0),
+            buffer: content,
+        }
+    }
+
+    /// Draw `children` (each already grouped with its own children) over `layer`'s
+    /// rasterized `content`, which was drawn at `density`.
+    ///
+    /// Children are placed through the layer's child space, so they inherit its scale,
+    /// and faded by its opacity; each blends with what is below it in the group using its
+    /// own blend mode. The layer's own transform then moves the whole group.
+    /// The buffer grows to fit children outside the content rect, up to `margin` pixels
+    /// past it on each side. Children drawn denser than the layer's scale calls for
+    /// raise the group's density, so they are copied rather than scaled down.
+    pub fn group(
+        content: FrameBuffer,
+        density: (f64, f64),
+        layer: &LayerState,
+        children: &[(&LayerState, LayerBuffer)],
+        margin: (u32, u32),
+    ) -> Self {
+        let content_size = (
+            (content.width as f64 / density.0).round() as u32,
+            (content.height as f64 / density.1).round() as u32,
+        );
+        let (sx, sy) = layer.scale;
+        let mut dense = density;
+        for (_, buf) in children.iter().filter(|(_, b)| b.density != (1.0, 1.0)) {
+            if sx > 0.0 && sy > 0.0 {
+                dense.0 = dense.0.max(buf.density.0 / sx);
+                dense.1 = dense.1.max(buf.density.1 / sy);
+            }
+        }
+        let content = if dense == density {
+            content
+        } else {
+            resample(&content, dense.0 / density.0, dense.1 / density.1)
+        };
+
+        let (cw, ch) = (content.width as i32, content.height as i32);
+        let space = Mat4::scale(dense.0, dense.1, 1.0) * layer.child_matrix();
+        let placed: Vec<(Placement, &LayerState, &LayerBuffer)> = children
+            .iter()
+            .map(|(child, buf)| (buf.placement(child, &space), *child, buf))
+            .collect();
+
+        let (mut x0, mut y0, mut x1, mut y1) = (0, 0, cw, ch);
+        for (placement, _, buf) in &placed {
+            let (x, y, w, h) = placement.bounds(buf.buffer.width, buf.buffer.height);
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x.saturating_add(w as i32));
+            y1 = y1.max(y.saturating_add(h as i32));
+        }
+        let margin = (
+            (margin.0 as f64 * dense.0) as u32,
+            (margin.1 as f64 * dense.1) as u32,
+        );
+        let (mx, my) = (margin.0 as i32, margin.1 as i32);
+        let (x0, y0) = (x0.max(-mx), y0.max(-my));
+        let (x1, y1) = (x1.min(cw + mx), y1.min(ch + my));
+
+        let mut group = Self::new(content);
+        group.content_size = content_size;
+        group.density = dense;
+        if (x0, y0, x1, y1) != (0, 0, cw, ch) {
+            let mut buffer =
+                FrameBuffer::new((x1 - x0) as u32, (y1 - y0) as u32, PixelFormat::Rgba8);
+            buffer.composite_over(&group.buffer, -x0, -y0);
+            group.buffer = buffer;
+            group.origin = (x0, y0);
+        }
+        if layer.opacity <= 0.0 {
+            return group;
+        }
+        for (placement, child, buf) in placed {
+            let placement = placement.offset(-x0, -y0);
+            if layer.opacity < 1.0 {
+                let mut faded = buf.buffer.clone();
+                faded.multiply_alpha(layer.opacity);
+                composite_placed(&mut group.buffer, &faded, placement, child.blend_mode);
+            } else {
+                composite_placed(&mut group.buffer, &buf.buffer, placement, child.blend_mode);
+            }
+        }
+        group
+    }
+
+    /// Where this buffer of `layer` lands in the target `parent` maps into.
+    pub fn placement(&self, layer: &LayerState, parent: &Mat4) -> Placement {
+        let (dx, dy) = self.density;
+        let m = *parent
+            * layer.local_matrix(self.content_size.0, self.content_size.1)
+            * Mat4::scale(1.0 / dx, 1.0 / dy, 1.0)
+            * Mat4::translation(self.origin.0 as f64, self.origin.1 as f64, 0.0);
+        Placement::of(&m, (self.buffer.width, self.buffer.height))
+    }
+
+    /// Grow the buffer by transparent margins, keeping its content where it was placed.
+    pub fn pad(&mut self, left: u32, top: u32, right: u32, bottom: u32) {
+        if left == 0 && top == 0 && right == 0 && bottom == 0 {
+            return;
+        }
+        let mut buffer = FrameBuffer::new(
+            self.buffer.width.saturating_add(left).saturating_add(right),
+            self.buffer
+                .height
+                .saturating_add(top)
+                .saturating_add(bottom),
+            PixelFormat::Rgba8,
+        );
+        buffer.composite_over(&self.buffer, left as i32, top as i32);
+        self.buffer = buffer;
+        self.origin = (self.origin.0 - left as i32, self.origin.1 - top as i32);
+    }
+}
+
+/// `src` resized by `(sx, sy)`.
+fn resample(src: &FrameBuffer, sx: f64, sy: f64) -> FrameBuffer {
+    let (w, h) = (src.width as f64 * sx, src.height as f64 * sy);
+    let mut out = FrameBuffer::new(w.round() as u32, h.round() as u32, PixelFormat::Rgba8);
+    let quad = [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]];
+    out.composite_over_projected(src, quad);
+    out
+}
+
+/// Composite `src` into `dst` at `placement`, blending colors with `mode`.
+pub fn composite_placed(
+    dst: &mut FrameBuffer,
+    src: &FrameBuffer,
+    placement: Placement,
+    mode: BlendMode,
+) {
+    match placement {
+        Placement::Blit(x, y) => dst.composite_blend(src, x, y, mode),
+        Placement::Warp(quad) => dst.composite_blend_projected(src, quad, mode),
+    }
+}
+
+/// Blend the outgoing and incoming scene frames of a transition.
+///
+/// Both frames are expected to be canvas-sized (`width`×`height`). Shader and plugin
+/// transitions need the host renderer; given one, this crossfades.
+pub fn blend_transition(
+    outgoing: &FrameBuffer,
+    incoming: &FrameBuffer,
+    transition: &TransitionState,
+    width: u32,
+    height: u32,
+) -> FrameBuffer {
+    let progress = transition.progress;
+    match &transition.effect {
+        TransitionType::Wipe { direction }
+        | TransitionType::Push { direction }
+        | TransitionType::Slide { direction } => slide(
+            outgoing,
+            incoming,
+            &transition.effect,
+            *direction,
+            progress,
+            width,
+            height,
+        ),
+        TransitionType::Zoom { scale } => {
+            let scale = if *scale > 0.0 { *scale } else { 1.0 };
+            let out_scale = 1.0 + (scale - 1.0) * progress;
+            per_pixel(width, height, |x, y| {
+                mix(
+                    zoomed(outgoing, out_scale, x, y),
+                    zoomed(incoming, out_scale / scale, x, y),
+                    progress,
+                )
+            })
+        }
+        TransitionType::Iris { center } => {
+            let (cx, cy) = (center.x * width as f64, center.y * height as f64);
+            let reach = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]
+                .iter()
+                .map(|(fx, fy)| (fx * width as f64 - cx).hypot(fy * height as f64 - cy))
+                .fold(0.0, f64::max);
+            // One pixel wider than the farthest corner, so the edge clears the frame.
+            let radius = (reach + 1.0) * progress;
+            per_pixel(width, height, |x, y| {
+                let d = (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy);
+                mix(
+                    outgoing.get_pixel(x, y).unwrap_or(CLEAR),
+                    incoming.get_pixel(x, y).unwrap_or(CLEAR),
+                    (radius - d).clamp(0.0, 1.0),
+                )
+            })
+        }
+        TransitionType::ClockWipe { angle } => {
+            let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+            let sweep = progress * 360.0;
+            per_pixel(width, height, |x, y| {
+                let (dx, dy) = (x as f64 + 0.5 - cx, y as f64 + 0.5 - cy);
+                let hand = (dx.atan2(-dy).to_degrees() - angle).rem_euclid(360.0);
+                let src = if hand < sweep { incoming } else { outgoing };
+                src.get_pixel(x, y).unwrap_or(CLEAR)
+            })
+        }
+        TransitionType::BlurDissolve { radius } => {
+            let radius = radius * (1.0 - (2.0 * progress - 1.0).abs());
+            let (outgoing, incoming) = (blur_rgba(outgoing, radius), blur_rgba(incoming, radius));
+            crossfade(&outgoing, &incoming, progress, width, height)
+        }
+        TransitionType::Glitch { intensity } => {
+            glitch(outgoing, incoming, *intensity, progress, width, height)
+        }
+        TransitionType::Crossfade
+        | TransitionType::Shader { .. }
+        | TransitionType::Plugin { .. } => crossfade(outgoing, incoming, progress, width, height),
+    }
+}
+
+const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+/// A `width`×`height` buffer with every pixel computed by `pixel(x, y)`.
+fn per_pixel(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 4]) -> FrameBuffer {
+    let mut out = FrameBuffer::new(width, height, PixelFormat::Rgba8);
+    for y in 0..height {
+        for x in 0..width {
+            out.set_pixel(x, y, pixel(x, y));
+        }
+    }
+    out
+}
+
+fn mix(a: [u8; 4], b: [u8; 4], t: f64) -> [u8; 4] {
+    std::array::from_fn(|i| (a[i] as f64 * (1.0 - t) + b[i] as f64 * t) as u8)
+}
+
+fn crossfade(
+    outgoing: &FrameBuffer,
+    incoming: &FrameBuffer,
+    progress: f64,
+    width: u32,
+    height: u32,
+) -> FrameBuffer {
+    per_pixel(width, height, |x, y| {
+        mix(
+            outgoing.get_pixel(x, y).unwrap_or(CLEAR),
+            incoming.get_pixel(x, y).unwrap_or(CLEAR),
+            progress,
+        )
+    })
+}
+
+/// Pixel `(x, y)` of `frame` scaled by `scale` about the frame's center; clear where the
+/// scaled frame doesn't reach.
+fn zoomed(frame: &FrameBuffer, scale: f64, x: u32, y: u32) -> [u8; 4] {
+    let (w, h) = (frame.width as f64, frame.height as f64);
+    let sx = (w - 1.0) / 2.0 + (x as f64 - (w - 1.0) / 2.0) / scale;
+    let sy = (h - 1.0) / 2.0 + (y as f64 - (h - 1.0) / 2.0) / scale;
+    if sx < -0.5 || sy < -0.5 || sx > w - 0.5 || sy > h - 0.5 {
+        return CLEAR;
+    }
+    frame.sample_bilinear(sx, sy)
+}
+
+/// Wipe, push and slide transitions.
+fn slide(
+    outgoing: &FrameBuffer,
+    incoming: &FrameBuffer,
+    effect: &TransitionType,
+    direction: Direction,
+    progress: f64,
+    width: u32,
+    height: u32,
+) -> FrameBuffer {
+    let mut out = outgoing.clone();
+    let offset_x = (width as f64 * progress) as u32;
+    let offset_y = (height as f64 * progress) as u32;
+    for y in 0..height {
+        for x in 0..width {
+            // Where the incoming scene covers (x, y), and which of its pixels slid there.
+            let incoming_src = match direction {
+                Direction::Left => (x >= width - offset_x).then(|| (x - (width - offset_x), y)),
+                Direction::Up => (y >= height - offset_y).then(|| (x, y - (height - offset_y))),
+                Direction::Down => (y < offset_y).then(|| (x, height - offset_y + y)),
+                Direction::Right => (x < offset_x).then(|| (width - offset_x + x, y)),
+            };
+            let px = match (effect, incoming_src) {
+                (TransitionType::Wipe { .. }, Some(_)) => incoming.get_pixel(x, y),
+                (_, Some((sx, sy))) => incoming.get_pixel(sx, sy),
+                (TransitionType::Push { .. }, None) => {
+                    // The outgoing scene is pushed out by the same offset.
+                    let (sx, sy) = match direction {
+                        Direction::Left => (x + offset_x, y),
+                        Direction::Up => (x, y + offset_y),
+                        Direction::Down => (x, y - offset_y),
+                        Direction::Right => (x - offset_x, y),
+                    };
+                    outgoing.get_pixel(sx, sy)
+                }
+                _ => continue,
+            };
+            out.set_pixel(x, y, px.unwrap_or(CLEAR));
+        }
+    }
+    out
+}
+
+/// Blur every channel of `frame` as [`feather_coverage`] softens a mask.
+fn blur_rgba(frame: &FrameBuffer, radius: f64) -> FrameBuffer {
+    let mut out = frame.clone();
+    let mut plane = vec![0u8; frame.pixel_count()];
+    for c in 0..4 {
+        for (value, px) in plane.iter_mut().zip(frame.data.chunks_exact(4)) {
+            *value = px[c];
+        }
+        feather_coverage(&mut plane, frame.width, frame.height, radius);
+        for (value, px) in plane.iter().zip(out.data.chunks_exact_mut(4)) {
+            px[c] = *value;
+        }
+    }
+    out
+}
+
+/// Tear horizontal bands of the frame sideways and split their color channels, cutting
+/// from the outgoing to the incoming scene halfway with a few bands cutting early or late.
+fn glitch(
+    outgoing: &FrameBuffer,
+    incoming: &FrameBuffer,
+    intensity: f64,
+    progress: f64,
+    width: u32,
+    height: u32,
+) -> FrameBuffer {
+    // 0 at either end of the transition and 1 halfway.
+    let peak = 1.0 - (2.0 * progress - 1.0).abs();
+    let tear = intensity * peak * width as f64;
+    let split = (tear / 4.0).round() as i64;
+    // The bands are cut anew a dozen times over the transition.
+    let step = (progress * 12.0).floor() as u64;
+    let band_height = (height / 16).max(1);
+    per_pixel(width, height, |x, y| {
+        let band = (y / band_height) as u64;
+        let shift = if noise(band, step, 0) < 0.6 {
+            ((noise(band, step, 1) * 2.0 - 1.0) * tear).round() as i64
+        } else {
+            0
+        };
+        let early = noise(band, step, 2) < peak * 0.5;
+        let src = if (progress >= 0.5) != early {
+            incoming
+        } else {
+            outgoing
+        };
+        let at = |dx: i64| {
+            let sx = (x as i64 + shift + dx).rem_euclid(width as i64) as u32;
+            src.get_pixel(sx, y).unwrap_or(CLEAR)
+        };
+        let (red, green, blue) = (at(split), at(0), at(-split));
+        [red[0], green[1], blue[2], green[3]]
+    })
+}
+
+/// A repeatable pseudo-random number in `[0, 1)` for a glitch band at a step.
+fn noise(band: u64, step: u64, salt: u64) -> f64 {
+    let mut z = band.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ step.wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ salt.wrapping_mul(0x1656_67B1_9E37_79F9);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Cut the current cell out of a spritesheet.
+///
+/// The cell advances at `sheet_fps` over scene-local time and wraps after `frame_count`
+/// cells (or every cell in the sheet), starting from `start_frame`.
+#[allow(clippy::too_many_arguments)]
+pub fn spritesheet_frame(
+    sheet: &FrameBuffer,
+    frame_w: u32,
+    frame_h: u32,
+    sheet_fps: f64,
+    start_frame: u32,
+    frame_count: Option<u32>,
+    local_frame: u64,
+    timeline_fps: f64,
+) -> FrameBuffer {
+    if sheet.format != PixelFormat::Rgba8 || frame_w == 0 || frame_h == 0 {
+        return sheet.clone();
+    }
+
+    let cols = (sheet.width / frame_w).max(1);
+    let rows = (sheet.height / frame_h).max(1);
+    let derived_total = cols.saturating_mul(rows);
+    let total = frame_count
+        .unwrap_or(derived_total)
+        .max(1)
+        .min(derived_total.max(1));
+
+    let t = local_frame as f64 / timeline_fps;
+    let idx = if sheet_fps <= 0.0 {
+        0
+    } else {
+        ((t * sheet_fps).floor() as u32) % total
+    };
+
+    let frame_idx = start_frame.saturating_add(idx) % total;
+    let x = (frame_idx % cols) * frame_w;
+    let y = (frame_idx / cols) * frame_h;
+
+    let mut out = FrameBuffer::new(frame_w, frame_h, PixelFormat::Rgba8);
+    for yy in 0..frame_h {
+        for xx in 0..frame_w {
+            if let Some(px) = sheet.get_pixel(x + xx, y + yy) {
+                out.set_pixel(xx, yy, px);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::EvalContext;
+    use crate::layer::evaluate_layer;
+    use vidra_core::types::ShapeType;
+    use vidra_core::Color;
+    use vidra_ir::layer::{Layer, LayerContent, LayerId};
+    use vidra_ir::project::{Project, ProjectSettings};
+
+    fn transition(effect: TransitionType, progress: f64) -> TransitionState {
+        TransitionState { effect, progress }
+    }
+
+    #[test]
+    fn test_push_right_shifts_both_scenes() {
+        let mut outgoing = FrameBuffer::new(4, 1, PixelFormat::Rgba8);
+        let mut incoming = FrameBuffer::new(4, 1, PixelFormat::Rgba8);
+        for x in 0..4 {
+            outgoing.set_pixel(x, 0, [x as u8, 0, 0, 255]);
+            incoming.set_pixel(x, 0, [10 + x as u8, 0, 0, 255]);
+        }
+        let push = TransitionType::Push {
+            direction: Direction::Right,
+        };
+        let out = blend_transition(&outgoing, &incoming, &transition(push, 0.5), 4, 1);
+        let reds: Vec<u8> = (0..4).map(|x| out.get_pixel(x, 0).unwrap()[0]).collect();
+        assert_eq!(reds, vec![12, 13, 0, 1]);
+
+        let slide = TransitionType::Slide {
+            direction: Direction::Right,
+        };
+        let out = blend_transition(&outgoing, &incoming, &transition(slide, 0.5), 4, 1);
+        let reds: Vec<u8> = (0..4).map(|x| out.get_pixel(x, 0).unwrap()[0]).collect();
+        assert_eq!(reds, vec![12, 13, 2, 3]);
+
+        let wipe = TransitionType::Wipe {
+            direction: Direction::Right,
+        };
+        let out = blend_transition(&outgoing, &incoming, &transition(wipe, 0.5), 4, 1);
+        let reds: Vec<u8> = (0..4).map(|x| out.get_pixel(x, 0).unwrap()[0]).collect();
+        assert_eq!(reds, vec![10, 11, 2, 3]);
+    }
+
+    #[test]
+    fn test_crossfade_midpoint() {
+        let a = FrameBuffer::solid(2, 2, &Color::BLACK);
+        let b = FrameBuffer::solid(2, 2, &Color::WHITE);
+        let out = blend_transition(&a, &b, &transition(TransitionType::Crossfade, 0.5), 2, 2);
+        assert_eq!(out.get_pixel(1, 1), Some([127, 127, 127, 255]));
+    }
+
+    #[test]
+    fn test_shaped_transitions_run_from_outgoing_to_incoming() {
+        let a = FrameBuffer::solid(8, 8, &Color::BLACK);
+        let b = FrameBuffer::solid(8, 8, &Color::WHITE);
+        let effects = [
+            TransitionType::Zoom { scale: 1.5 },
+            TransitionType::Iris {
+                center: vidra_core::Point2D::new(0.25, 0.5),
+            },
+            TransitionType::ClockWipe { angle: 90.0 },
+            TransitionType::BlurDissolve { radius: 4.0 },
+            TransitionType::Glitch { intensity: 0.2 },
+        ];
+        for effect in effects {
+            let start = blend_transition(&a, &b, &transition(effect.clone(), 0.0), 8, 8);
+            let end = blend_transition(&a, &b, &transition(effect.clone(), 1.0), 8, 8);
+            assert_eq!(
+                start.data, a.data,
+                "{:?} starts on the outgoing scene",
+                effect
+            );
+            assert_eq!(end.data, b.data, "{:?} ends on the incoming scene", effect);
+        }
+    }
+
+    #[test]
+    fn test_iris_and_clock_wipe_reveal_in_shape() {
+        let a = FrameBuffer::solid(8, 8, &Color::BLACK);
+        let b = FrameBuffer::solid(8, 8, &Color::WHITE);
+        let red = |out: &FrameBuffer, x, y| out.get_pixel(x, y).unwrap()[0];
+
+        let iris = TransitionType::Iris {
+            center: vidra_core::Point2D::new(0.5, 0.5),
+        };
+        let out = blend_transition(&a, &b, &transition(iris, 0.3), 8, 8);
+        assert_eq!((red(&out, 4, 4), red(&out, 0, 0)), (255, 0));
+
+        // A quarter of the way round from twelve o'clock: only the top right is revealed.
+        let clock = TransitionType::ClockWipe { angle: 0.0 };
+        let out = blend_transition(&a, &b, &transition(clock, 0.25), 8, 8);
+        let quadrants = [(5, 2), (5, 5), (2, 5), (2, 2)].map(|(x, y)| red(&out, x, y));
+        assert_eq!(quadrants, [255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_spritesheet_frame_advances_and_wraps() {
+        // 2×1 cells of 2×2 pixels; cell 0 red, cell 1 blue.
+        let mut sheet = FrameBuffer::new(4, 2, PixelFormat::Rgba8);
+        for y in 0..2 {
+            for x in 0..4 {
+                let px = if x < 2 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                };
+                sheet.set_pixel(x, y, px);
+            }
+        }
+        let cell = |frame| spritesheet_frame(&sheet, 2, 2, 10.0, 0, None, frame, 10.0);
+        assert_eq!(cell(0).get_pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(cell(1).get_pixel(0, 0), Some([0, 0, 255, 255]));
+        assert_eq!(cell(2).get_pixel(1, 1), Some([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_group_children_inherit_scale_and_opacity() {
+        let rect = |w: f64| LayerContent::Shape {
+            shape: ShapeType::Rect {
+                width: w,
+                height: w,
+                corner_radius: 0.0,
+            },
+            fill: Some(Color::WHITE.into()),
+            stroke: None,
+            stroke_width: 0.0,
+        };
+        let mut parent = Layer::new(LayerId::new("card"), rect(10.0))
+            .with_scale(2.0, 2.0)
+            .with_opacity(0.5);
+        // Centered at (12, 2) in the card's unscaled pixels: partly right of the card.
+        parent.add_child(Layer::new(LayerId::new("dot"), rect(4.0)).with_position(12.0, 2.0));
+        let ctx = EvalContext::new(&Project::new(ProjectSettings::custom(64, 64, 10.0)));
+        let card = evaluate_layer(&ctx, &parent, 0);
+        let dot = &card.children[0];
+
+        let content = FrameBuffer::solid(20, 20, &Color::RED);
+        let blue = LayerBuffer::new(FrameBuffer::solid(4, 4, &Color::BLUE));
+        let group = LayerBuffer::group(content, (1.0, 1.0), &card, &[(dot, blue)], (64, 64));
+
+        // The dot covers x 20..28 and y 0..8 once scaled, so the group grows to 28 wide.
+        assert_eq!((group.buffer.width, group.buffer.height), (28, 20));
+        assert_eq!(group.origin, (0, 0));
+        assert_eq!(group.content_size, (20, 20));
+        assert_eq!(group.buffer.get_pixel(24, 4), Some([0, 0, 255, 128]));
+        assert_eq!(group.buffer.get_pixel(24, 12), Some([0, 0, 0, 0]));
+        assert_eq!(group.buffer.get_pixel(5, 5), Some([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_dense_children_are_copied_not_resampled() {
+        let mut parent = Layer::new(LayerId::new("card"), LayerContent::Empty).with_scale(2.0, 2.0);
+        parent.add_child(Layer::new(LayerId::new("dot"), LayerContent::Empty));
+        let ctx = EvalContext::new(&Project::new(ProjectSettings::custom(64, 64, 10.0)));
+        let card = evaluate_layer(&ctx, &parent, 0);
+        let dot = &card.children[0];
+
+        // A 4x4 dot drawn at twice its size, marked red in its top-left pixel.
+        let mut sharp = LayerBuffer::new(FrameBuffer::solid(8, 8, &Color::BLUE));
+        sharp.buffer.set_pixel(0, 0, [255, 0, 0, 255]);
+        sharp.density = (2.0, 2.0);
+        sharp.content_size = (4, 4);
+
+        let content = FrameBuffer::solid(20, 20, &Color::TRANSPARENT);
+        let group = LayerBuffer::group(
+            content,
+            (1.0, 1.0),
+            &card,
+            &[(dot, sharp.clone())],
+            (64, 64),
+        );
+        // The parent's scale is the child's density: a one-to-one copy.
+        assert_eq!(group.density, (1.0, 1.0));
+        assert_eq!(group.buffer.get_pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(group.buffer.get_pixel(1, 0), Some([0, 0, 255, 255]));
+
+        // Without a parent scale to absorb it, the group itself becomes dense.
+        let unscaled = evaluate_layer(
+            &ctx,
+            &Layer::new(LayerId::new("box"), LayerContent::Empty),
+            0,
+        );
+        let content = FrameBuffer::solid(10, 10, &Color::WHITE);
+        let group = LayerBuffer::group(content, (1.0, 1.0), &unscaled, &[(dot, sharp)], (64, 64));
+        assert_eq!(group.density, (2.0, 2.0));
+        assert_eq!(group.content_size, (10, 10));
+        assert_eq!((group.buffer.width, group.buffer.height), (20, 20));
+        assert_eq!(group.buffer.get_pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(group.buffer.get_pixel(19, 19), Some([255, 255, 255, 255]));
+    }
+}
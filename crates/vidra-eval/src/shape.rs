@@ -0,0 +1,298 @@
+//! Rasterization of shape layers, shared by every renderer. Paths are anti-aliased.
+
+use vidra_core::blend::blend_pixel;
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::paint::Shader;
+use vidra_core::path::{FillRule, PathShape};
+use vidra_core::types::ShapeType;
+use vidra_core::{BlendMode, Color, Paint, Point2D};
+
+/// Vertical samples per pixel row. Horizontal coverage is computed exactly.
+const SUBSAMPLES: usize = 16;
+
+/// Draw a shape layer's content at its unscaled size, with alpha scaled by `opacity`.
+///
+/// Rects, circles and ellipses are filled, white when no fill is set; paths are filled
+/// and stroked (see [`render_path`]).
+pub fn render_shape(
+    shape: &ShapeType,
+    fill: Option<&Paint>,
+    stroke: Option<&Paint>,
+    stroke_width: f64,
+    opacity: f64,
+) -> FrameBuffer {
+    let white = Paint::Solid(Color::WHITE);
+    let area = fill.unwrap_or(&white);
+    match shape {
+        ShapeType::Rect { width, height, .. } => {
+            area.render(*width as u32, *height as u32, opacity)
+        }
+        ShapeType::Circle { radius } => {
+            let size = (*radius * 2.0) as u32;
+            fill_where(area, size, size, opacity, |x, y| {
+                let (dx, dy) = (x - radius, y - radius);
+                dx * dx + dy * dy <= radius * radius
+            })
+        }
+        ShapeType::Ellipse { rx, ry } => {
+            let (w, h) = ((*rx * 2.0) as u32, (*ry * 2.0) as u32);
+            fill_where(area, w, h, opacity, |x, y| {
+                let (dx, dy) = ((x - rx) / rx, (y - ry) / ry);
+                dx * dx + dy * dy <= 1.0
+            })
+        }
+        ShapeType::Path(path) => render_path(path, fill, stroke, stroke_width, opacity),
+    }
+}
+
+/// A `width`×`height` buffer painted at the pixels whose top-left corner is `inside`.
+fn fill_where(
+    paint: &Paint,
+    width: u32,
+    height: u32,
+    opacity: f64,
+    inside: impl Fn(f64, f64) -> bool,
+) -> FrameBuffer {
+    let shader = paint.shader(width, height);
+    let mut buf = FrameBuffer::new(width, height, PixelFormat::Rgba8);
+    for y in 0..height {
+        for x in 0..width {
+            if inside(x as f64, y as f64) {
+                let mut c = shader.color_at(x, y);
+                c.a *= opacity as f32;
+                buf.set_pixel(x, y, c.to_rgba8());
+            }
+        }
+    }
+    buf
+}
+
+/// Draw `path` into a buffer the size of its content rect: the fill, then the stroke
+/// over it. Without a stroke an unset fill is white, like the other shapes.
+pub fn render_path(
+    path: &PathShape,
+    fill: Option<&Paint>,
+    stroke: Option<&Paint>,
+    stroke_width: f64,
+    opacity: f64,
+) -> FrameBuffer {
+    let stroke = stroke.filter(|_| stroke_width > 0.0);
+    let white = Paint::Solid(Color::WHITE);
+    let fill = match (fill, stroke) {
+        (None, None) => Some(&white),
+        (fill, _) => fill,
+    };
+    let (origin, size) = path.content_rect(if stroke.is_some() { stroke_width } else { 0.0 });
+    let (width, height) = (size.width as u32, size.height as u32);
+    let mut buf = FrameBuffer::new(width, height, PixelFormat::Rgba8);
+
+    let mut lines = path.outlines();
+    for line in &mut lines {
+        for p in &mut line.points {
+            *p = Point2D::new(p.x - origin.x, p.y - origin.y);
+        }
+    }
+    if let Some(fill) = fill {
+        let polygons: Vec<Vec<Point2D>> = lines.iter().map(|l| l.points.clone()).collect();
+        let coverage = polygon_coverage(&polygons, path.fill_rule, width, height);
+        paint(&mut buf, &coverage, &fill.shader(width, height), opacity);
+    }
+    if let Some(stroke) = stroke {
+        let polygons = path.stroke_polygons(&lines, stroke_width);
+        let coverage = polygon_coverage(&polygons, FillRule::NonZero, width, height);
+        paint(&mut buf, &coverage, &stroke.shader(width, height), opacity);
+    }
+    buf
+}
+
+/// Composite the shader's colors over `buf` wherever `coverage` is non-zero.
+fn paint(buf: &mut FrameBuffer, coverage: &[f32], shader: &Shader, opacity: f64) {
+    for (i, cov) in coverage.iter().enumerate() {
+        if *cov <= 0.0 {
+            continue;
+        }
+        let (x, y) = (
+            (i % buf.width as usize) as u32,
+            (i / buf.width as usize) as u32,
+        );
+        let color = shader.color_at(x, y);
+        let [r, g, b, _] = color.to_rgba8();
+        let alpha = color.a as f64 * opacity * *cov as f64;
+        let a = (alpha * 255.0).round().clamp(0.0, 255.0) as u8;
+        let dst = buf.get_pixel(x, y).unwrap_or_default();
+        buf.set_pixel(x, y, blend_pixel(BlendMode::Normal, [r, g, b, a], dst));
+    }
+}
+
+/// A polygon edge, stored top to bottom.
+struct Edge {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    winding: i32,
+}
+
+/// Fraction of each pixel of a `width`×`height` buffer covered by the closed
+/// `polygons` under `rule`, row by row.
+pub(crate) fn polygon_coverage(
+    polygons: &[Vec<Point2D>],
+    rule: FillRule,
+    width: u32,
+    height: u32,
+) -> Vec<f32> {
+    let (w, h) = (width as usize, height as usize);
+    let mut coverage = vec![0.0f32; w * h];
+    let mut edges: Vec<Edge> = Vec::new();
+    for polygon in polygons {
+        for (i, a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+            if a.y == b.y {
+                continue;
+            }
+            let (top, bottom, winding) = if a.y < b.y { (*a, b, 1) } else { (b, *a, -1) };
+            edges.push(Edge {
+                x0: top.x,
+                y0: top.y,
+                x1: bottom.x,
+                y1: bottom.y,
+                winding,
+            });
+        }
+    }
+    edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+
+    let weight = 1.0 / SUBSAMPLES as f32;
+    let mut active: Vec<usize> = Vec::new();
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    let mut next = 0;
+    for (row, cells) in coverage.chunks_exact_mut(w.max(1)).take(h).enumerate() {
+        for sample in 0..SUBSAMPLES {
+            let y = row as f64 + (sample as f64 + 0.5) / SUBSAMPLES as f64;
+            while next < edges.len() && edges[next].y0 <= y {
+                active.push(next);
+                next += 1;
+            }
+            active.retain(|&e| edges[e].y1 > y);
+
+            crossings.clear();
+            crossings.extend(active.iter().map(|&e| {
+                let e = &edges[e];
+                let t = (y - e.y0) / (e.y1 - e.y0);
+                (e.x0 + (e.x1 - e.x0) * t, e.winding)
+            }));
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+                if inside {
+                    add_span(cells, pair[0].0, pair[1].0, weight);
+                }
+            }
+        }
+    }
+    for c in &mut coverage {
+        *c = c.min(1.0);
+    }
+    coverage
+}
+
+/// Add `weight` times the covered length of `[x0, x1)` to each cell of a row.
+fn add_span(cells: &mut [f32], x0: f64, x1: f64, weight: f32) {
+    let x0 = x0.clamp(0.0, cells.len() as f64);
+    let x1 = x1.clamp(0.0, cells.len() as f64);
+    if x1 <= x0 {
+        return;
+    }
+    let (i0, i1) = (x0.floor() as usize, x1.floor() as usize);
+    if i0 == i1 {
+        cells[i0] += (x1 - x0) as f32 * weight;
+        return;
+    }
+    cells[i0] += (i0 as f64 + 1.0 - x0) as f32 * weight;
+    for cell in &mut cells[i0 + 1..i1] {
+        *cell += weight;
+    }
+    if i1 < cells.len() {
+        cells[i1] += (x1 - i1 as f64) as f32 * weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_core::paint::{ColorStop, Gradient};
+    use vidra_core::path::{LineCap, PathData};
+
+    fn shape(d: &str) -> PathShape {
+        PathShape::new(PathData::parse_svg(d).unwrap())
+    }
+
+    #[test]
+    fn test_fill_rules_and_antialiasing() {
+        // Two nested squares winding the same way: even-odd punches a hole.
+        let mut path = shape("M0 0 H20 V20 H0 Z M5 5 H15 V15 H5 Z");
+        let red = Paint::Solid(Color::rgb(1.0, 0.0, 0.0));
+        let red = Some(&red);
+        let buf = render_path(&path, red, None, 0.0, 1.0);
+        assert_eq!((buf.width, buf.height), (20, 20));
+        assert_eq!(buf.get_pixel(10, 10), Some([255, 0, 0, 255]));
+        path.fill_rule = FillRule::EvenOdd;
+        let buf = render_path(&path, red, None, 0.0, 1.0);
+        assert_eq!(buf.get_pixel(10, 10).unwrap()[3], 0);
+        assert_eq!(buf.get_pixel(2, 2).unwrap()[3], 255);
+
+        // A diagonal edge through pixel centres covers them by half.
+        let buf = render_path(&shape("M0 0 L10 0 L10 10 Z"), red, None, 0.0, 1.0);
+        assert_eq!(buf.get_pixel(4, 4).unwrap()[3], 128);
+        assert_eq!(buf.get_pixel(8, 2).unwrap()[3], 255);
+        assert_eq!(buf.get_pixel(2, 8).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_stroke_draws_over_fill_and_follows_trim() {
+        let mut path = shape("M0 0 L40 0");
+        path.stroke_style.cap = LineCap::Square;
+        let blue = Paint::Solid(Color::rgb(0.0, 0.0, 1.0));
+        let blue = Some(&blue);
+        let buf = render_path(&path, None, blue, 4.0, 1.0);
+        // Square caps extend the 4px stroke by 2px at each end.
+        assert_eq!((buf.width, buf.height), (44, 4));
+        assert_eq!(buf.get_pixel(0, 1), Some([0, 0, 255, 255]));
+        assert_eq!(buf.get_pixel(43, 1), Some([0, 0, 255, 255]));
+
+        // Trimming keeps the buffer size but draws only the first quarter.
+        path.trim.end = 0.25;
+        let trimmed = render_path(&path, None, blue, 4.0, 1.0);
+        assert_eq!((trimmed.width, trimmed.height), (44, 4));
+        assert_eq!(trimmed.get_pixel(13, 1).unwrap()[3], 255);
+        assert_eq!(trimmed.get_pixel(20, 1).unwrap()[3], 0);
+    }
+
+    #[test]
+    fn test_gradient_fill_follows_the_shape_box() {
+        let stops = vec![
+            ColorStop::new(0.0, Color::BLACK),
+            ColorStop::new(1.0, Color::WHITE),
+        ];
+        let gradient = Paint::Gradient(Gradient::linear(90.0, stops));
+        let circle = ShapeType::Circle { radius: 10.0 };
+        let buf = render_shape(&circle, Some(&gradient), None, 0.0, 1.0);
+        let (left, right) = (
+            buf.get_pixel(1, 10).unwrap(),
+            buf.get_pixel(18, 10).unwrap(),
+        );
+        assert!(left[0] < 40 && right[0] > 215, "{:?} {:?}", left, right);
+        assert_eq!(buf.get_pixel(0, 0).unwrap()[3], 0);
+
+        // Half opacity halves alpha but keeps the gradient's colors.
+        let half = render_shape(&circle, Some(&gradient), None, 0.0, 0.5);
+        assert_eq!(half.get_pixel(18, 10).unwrap()[..3], right[..3]);
+        assert_eq!(half.get_pixel(18, 10).unwrap()[3], 127);
+    }
+}
@@ -0,0 +1,1226 @@
+//! SVG documents parsed into paths and paints, rasterized at whatever size they are shown.
+//!
+//! The supported subset is what logos and icons exported from design tools use: `g`,
+//! `path`, `rect`, `circle`, `ellipse`, `line`, `polyline`, `polygon` and `use`, with
+//! transforms, presentation attributes, `style` declarations, group opacity, and linear
+//! and radial gradients. Text, embedded images, filters, masks, clip paths and CSS style
+//! sheets are ignored; convert text to outlines before exporting.
+
+use std::collections::HashMap;
+
+use vidra_core::blend::blend_pixel;
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::paint::{ColorStop, Gradient};
+use vidra_core::path::{
+    pixel_bounds, stroke_polygons, FillRule, LineCap, LineJoin, PathData, PathSegment, PathShape,
+    StrokeStyle,
+};
+use vidra_core::{BlendMode, Color, Mat4, Paint, Point2D, Size2D, VidraError};
+use vidra_ir::layer::{Layer, LayerContent, LayerId};
+
+use crate::shape::polygon_coverage;
+
+/// Nested `use` references deeper than this are dropped, which also breaks cycles.
+const MAX_USE_DEPTH: usize = 16;
+
+/// A parsed SVG document.
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    /// The viewport size in pixels, from `width`/`height` or else the `viewBox`.
+    pub size: Size2D,
+    root: Node,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    id: Option<String>,
+    /// Maps this node's coordinates into its parent's.
+    transform: Mat4,
+    opacity: f64,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    /// A container. `is_group` is set for `<g>` elements, the ones exposed as layers.
+    Group {
+        children: Vec<Node>,
+        is_group: bool,
+    },
+    Shape(Box<Shape>),
+}
+
+#[derive(Debug, Clone)]
+struct Shape {
+    data: PathData,
+    fill: Option<SvgPaint>,
+    fill_rule: FillRule,
+    stroke: Option<SvgPaint>,
+    stroke_width: f64,
+    stroke_style: StrokeStyle,
+}
+
+/// A fill or stroke as written in the document. Gradients are resolved against the
+/// painted shape when it is drawn.
+#[derive(Debug, Clone)]
+enum SvgPaint {
+    Color(Color),
+    Gradient(SvgGradient),
+}
+
+#[derive(Debug, Clone)]
+struct SvgGradient {
+    kind: SvgGradientKind,
+    /// Whether the geometry is in fractions of the shape's bounding box rather than in
+    /// user coordinates.
+    bounding_box: bool,
+    transform: Mat4,
+    stops: Vec<ColorStop>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SvgGradientKind {
+    Linear { start: Point2D, end: Point2D },
+    Radial { center: Point2D, radius: f64 },
+}
+
+impl SvgDocument {
+    /// Parse an SVG document. Elements that can't be drawn are skipped; only malformed
+    /// XML or a root element other than `<svg>` is an error.
+    pub fn parse(text: &str) -> Result<Self, VidraError> {
+        let doc = roxmltree::Document::parse(text)
+            .map_err(|e| VidraError::InvalidArgument(format!("invalid SVG: {}", e)))?;
+        let root = doc.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err(VidraError::InvalidArgument(format!(
+                "invalid SVG: the root element is <{}>, not <svg>",
+                root.tag_name().name()
+            )));
+        }
+
+        let view_box = root.attribute("viewBox").and_then(|v| {
+            let n = numbers(v);
+            (n.len() == 4 && n[2] > 0.0 && n[3] > 0.0).then(|| (n[0], n[1], n[2], n[3]))
+        });
+        let (vb_w, vb_h) = view_box.map_or((300.0, 150.0), |vb| (vb.2, vb.3));
+        let width = root.attribute("width").and_then(length).unwrap_or(vb_w);
+        let height = root.attribute("height").and_then(length).unwrap_or(vb_h);
+
+        let ids = doc
+            .descendants()
+            .filter_map(|n| Some((n.attribute("id")?, n)))
+            .collect();
+        let parser = Parser {
+            ids,
+            viewport: Size2D::new(vb_w, vb_h),
+        };
+        let mut root_node = parser
+            .node(root, &Style::default(), 0)
+            .unwrap_or_else(|| Node::group(Vec::new()));
+        // The root's own x/y don't apply; its viewBox maps onto the viewport, centered
+        // and uniformly scaled to fit as with the default `preserveAspectRatio`.
+        root_node.transform = match view_box {
+            Some((x, y, w, h)) => {
+                let scale = (width / w).min(height / h);
+                Mat4::translation((width - w * scale) / 2.0, (height - h * scale) / 2.0, 0.0)
+                    * Mat4::scale(scale, scale, 1.0)
+                    * Mat4::translation(-x, -y, 0.0)
+            }
+            None => Mat4::identity(),
+        };
+
+        Ok(Self {
+            size: Size2D::new(width, height),
+            root: root_node,
+        })
+    }
+
+    /// The unscaled rect `element` is drawn in, in document pixels: the whole viewport
+    /// when `element` is `None`, otherwise the whole-pixel bounds of the element's fill
+    /// and stroke. `None` if there is no element with that id.
+    pub fn content_rect(&self, element: Option<&str>) -> Option<(Point2D, Size2D)> {
+        let Some(id) = element else {
+            return Some((Point2D::zero(), self.size));
+        };
+        let (parent, node, _) = self.root.find(id, Mat4::identity(), 1.0)?;
+        let mut polygons = Vec::new();
+        node.outline_polygons(&parent, &mut polygons);
+        Some(pixel_bounds(polygons.iter().map(Vec::as_slice)))
+    }
+
+    /// Draw `element` (or the whole document), leaving out the elements with ids in
+    /// `exclude`, at `scale` times its [`content_rect`](Self::content_rect) size and with
+    /// alpha scaled by `opacity`.
+    pub fn render(
+        &self,
+        element: Option<&str>,
+        exclude: &[String],
+        scale: (f64, f64),
+        opacity: f64,
+    ) -> FrameBuffer {
+        let empty = || FrameBuffer::new(1, 1, PixelFormat::Rgba8);
+        let found = match element {
+            Some(id) => self.root.find(id, Mat4::identity(), 1.0),
+            None => Some((Mat4::identity(), &self.root, 1.0)),
+        };
+        let (Some((parent, node, parent_opacity)), Some((origin, size))) =
+            (found, self.content_rect(element))
+        else {
+            return empty();
+        };
+        let width = (size.width * scale.0).round();
+        let height = (size.height * scale.1).round();
+        if width < 1.0 || height < 1.0 {
+            return empty();
+        }
+
+        let mut buf = FrameBuffer::new(width as u32, height as u32, PixelFormat::Rgba8);
+        let device = Mat4::scale(width / size.width, height / size.height, 1.0)
+            * Mat4::translation(-origin.x, -origin.y, 0.0)
+            * parent;
+        node.draw(&mut buf, &device, parent_opacity * opacity, exclude);
+        buf
+    }
+
+    /// Give an SVG layer that asks for it (see `LayerContent::Svg::expand_groups`) a
+    /// child layer for each `<g id>` group it draws, recursively, and return how many
+    /// layers were expanded.
+    ///
+    /// A group goes to the existing child layer with its id when that layer has no
+    /// content of its own, so animations can be written for it ahead of time; otherwise
+    /// a new child layer is added. Group layers come first, in document order, and are
+    /// positioned over where the group sits. The expanded layer stops drawing them.
+    pub fn expand_groups(&self, layer: &mut Layer) -> usize {
+        let LayerContent::Svg {
+            asset_id,
+            element,
+            exclude,
+            expand_groups,
+        } = &mut layer.content
+        else {
+            return 0;
+        };
+        if !*expand_groups {
+            return 0;
+        }
+        *expand_groups = false;
+
+        let found = match element.as_deref() {
+            Some(id) => self.root.find(id, Mat4::identity(), 1.0),
+            None => Some((Mat4::identity(), &self.root, 1.0)),
+        };
+        let (Some((_, node, _)), Some((origin, _))) =
+            (found, self.content_rect(element.as_deref()))
+        else {
+            return 0;
+        };
+        let mut groups = Vec::new();
+        node.exposed_groups(&mut groups);
+        let asset_id = asset_id.clone();
+        exclude.extend(groups.iter().cloned());
+
+        let mut expanded = 1;
+        let mut rest = std::mem::take(&mut layer.children);
+        for id in groups {
+            let Some((rect_origin, size)) = self.content_rect(Some(&id)) else {
+                continue;
+            };
+            let content = LayerContent::Svg {
+                asset_id: asset_id.clone(),
+                element: Some(id.clone()),
+                exclude: Vec::new(),
+                expand_groups: true,
+            };
+            let declared = rest
+                .iter()
+                .position(|c| c.id.0 == id && matches!(c.content, LayerContent::Empty));
+            let mut child = match declared {
+                Some(i) => {
+                    let mut child = rest.remove(i);
+                    child.content = content;
+                    child
+                }
+                None => Layer::new(LayerId::new(id.clone()), content),
+            };
+            child.transform.position = Point2D::new(
+                rect_origin.x - origin.x + size.width / 2.0,
+                rect_origin.y - origin.y + size.height / 2.0,
+            );
+            expanded += self.expand_groups(&mut child);
+            layer.children.push(child);
+        }
+        layer.children.append(&mut rest);
+        expanded
+    }
+}
+
+impl Node {
+    fn group(children: Vec<Node>) -> Self {
+        Self {
+            id: None,
+            transform: Mat4::identity(),
+            opacity: 1.0,
+            kind: NodeKind::Group {
+                children,
+                is_group: false,
+            },
+        }
+    }
+
+    fn clear_ids(&mut self) {
+        self.id = None;
+        if let NodeKind::Group { children, .. } = &mut self.kind {
+            children.iter_mut().for_each(Node::clear_ids);
+        }
+    }
+
+    /// The node with `id`, with the transform and opacity its parent passes down.
+    fn find(&self, id: &str, parent: Mat4, opacity: f64) -> Option<(Mat4, &Node, f64)> {
+        if self.id.as_deref() == Some(id) {
+            return Some((parent, self, opacity));
+        }
+        let NodeKind::Group { children, .. } = &self.kind else {
+            return None;
+        };
+        let ctm = parent * self.transform;
+        children
+            .iter()
+            .find_map(|child| child.find(id, ctm, opacity * self.opacity))
+    }
+
+    /// Ids of the `<g id>` groups directly inside this node, looking through groups
+    /// without ids.
+    fn exposed_groups(&self, out: &mut Vec<String>) {
+        let NodeKind::Group { children, .. } = &self.kind else {
+            return;
+        };
+        for child in children {
+            match (&child.id, &child.kind) {
+                (Some(id), NodeKind::Group { is_group: true, .. }) => out.push(id.clone()),
+                (None, NodeKind::Group { .. }) => child.exposed_groups(out),
+                _ => {}
+            }
+        }
+    }
+
+    /// The untrimmed fill outlines and solid strokes of every shape in this node, mapped
+    /// by `parent`.
+    fn outline_polygons(&self, parent: &Mat4, out: &mut Vec<Vec<Point2D>>) {
+        let ctm = *parent * self.transform;
+        match &self.kind {
+            NodeKind::Group { children, .. } => {
+                for child in children {
+                    child.outline_polygons(&ctm, out);
+                }
+            }
+            NodeKind::Shape(shape) => {
+                let path = shape.device_path(&ctm);
+                let lines = path.data.flatten(FLATTEN_TOLERANCE);
+                if shape.stroke.is_some() {
+                    let width = shape.stroke_width * scale_factor(&ctm);
+                    out.extend(stroke_polygons(&lines, width, &path.stroke_style));
+                }
+                out.extend(lines.into_iter().map(|l| l.points));
+            }
+        }
+    }
+
+    fn draw(&self, buf: &mut FrameBuffer, parent: &Mat4, opacity: f64, exclude: &[String]) {
+        if self.id.as_ref().is_some_and(|id| exclude.contains(id)) {
+            return;
+        }
+        let ctm = *parent * self.transform;
+        let opacity = opacity * self.opacity;
+        match &self.kind {
+            NodeKind::Group { children, .. } => {
+                for child in children {
+                    child.draw(buf, &ctm, opacity, exclude);
+                }
+            }
+            NodeKind::Shape(shape) => shape.draw(buf, &ctm, opacity),
+        }
+    }
+}
+
+/// Matches the tolerance path shapes are flattened with.
+const FLATTEN_TOLERANCE: f64 = 0.1;
+
+impl Shape {
+    /// The shape as a path in the space `ctm` maps to, with its stroke style scaled.
+    fn device_path(&self, ctm: &Mat4) -> PathShape {
+        let map = |x: f64, y: f64| {
+            let [x, y] = ctm.transform_point(x, y);
+            (x, y)
+        };
+        let segments = self
+            .data
+            .segments
+            .iter()
+            .map(|segment| match *segment {
+                PathSegment::MoveTo { x, y } => {
+                    let (x, y) = map(x, y);
+                    PathSegment::MoveTo { x, y }
+                }
+                PathSegment::LineTo { x, y } => {
+                    let (x, y) = map(x, y);
+                    PathSegment::LineTo { x, y }
+                }
+                PathSegment::QuadTo { x1, y1, x, y } => {
+                    let ((x1, y1), (x, y)) = (map(x1, y1), map(x, y));
+                    PathSegment::QuadTo { x1, y1, x, y }
+                }
+                PathSegment::CubicTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    let ((x1, y1), (x2, y2), (x, y)) = (map(x1, y1), map(x2, y2), map(x, y));
+                    PathSegment::CubicTo {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                    }
+                }
+                PathSegment::Close => PathSegment::Close,
+            })
+            .collect();
+
+        let k = scale_factor(ctm);
+        let mut path = PathShape::new(PathData::new(segments));
+        path.fill_rule = self.fill_rule;
+        path.stroke_style = StrokeStyle {
+            dash: self.stroke_style.dash.iter().map(|d| d * k).collect(),
+            dash_offset: self.stroke_style.dash_offset * k,
+            ..self.stroke_style.clone()
+        };
+        path
+    }
+
+    fn draw(&self, buf: &mut FrameBuffer, ctm: &Mat4, opacity: f64) {
+        let path = self.device_path(ctm);
+        let lines = path.outlines();
+        let user_bounds = || {
+            let lines = self.data.flatten(FLATTEN_TOLERANCE);
+            let (mut min, mut max) = (
+                Point2D::new(f64::INFINITY, f64::INFINITY),
+                Point2D::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            );
+            for p in lines.iter().flat_map(|l| &l.points) {
+                min = Point2D::new(min.x.min(p.x), min.y.min(p.y));
+                max = Point2D::new(max.x.max(p.x), max.y.max(p.y));
+            }
+            (min, max)
+        };
+        if let Some(fill) = &self.fill {
+            let polygons = lines.iter().map(|l| l.points.clone()).collect();
+            fill_polygons(
+                buf,
+                polygons,
+                path.fill_rule,
+                |origin, size| fill.to_paint(ctm, user_bounds(), origin, size),
+                opacity,
+            );
+        }
+        if let Some(stroke) = self.stroke.as_ref().filter(|_| self.stroke_width > 0.0) {
+            let polygons = path.stroke_polygons(&lines, self.stroke_width * scale_factor(ctm));
+            fill_polygons(
+                buf,
+                polygons,
+                FillRule::NonZero,
+                |origin, size| stroke.to_paint(ctm, user_bounds(), origin, size),
+                opacity,
+            );
+        }
+    }
+}
+
+/// Composite `polygons` over `buf` in the paint `paint_for` gives for the whole-pixel
+/// region they cover (its top-left and size).
+fn fill_polygons(
+    buf: &mut FrameBuffer,
+    mut polygons: Vec<Vec<Point2D>>,
+    rule: FillRule,
+    paint_for: impl FnOnce(Point2D, Size2D) -> Paint,
+    opacity: f64,
+) {
+    let (origin, size) = pixel_bounds(polygons.iter().map(Vec::as_slice));
+    let x0 = origin.x.max(0.0);
+    let y0 = origin.y.max(0.0);
+    let x1 = (origin.x + size.width).min(buf.width as f64);
+    let y1 = (origin.y + size.height).min(buf.height as f64);
+    if x1 <= x0 || y1 <= y0 {
+        return;
+    }
+    let (width, height) = ((x1 - x0) as u32, (y1 - y0) as u32);
+    for p in polygons.iter_mut().flatten() {
+        *p = Point2D::new(p.x - x0, p.y - y0);
+    }
+
+    let coverage = polygon_coverage(&polygons, rule, width, height);
+    let shader = paint_for(
+        Point2D::new(x0, y0),
+        Size2D::new(width as f64, height as f64),
+    )
+    .shader(width, height);
+    for (i, cov) in coverage.iter().enumerate() {
+        if *cov <= 0.0 {
+            continue;
+        }
+        let (x, y) = ((i % width as usize) as u32, (i / width as usize) as u32);
+        let color = shader.color_at(x, y);
+        let [r, g, b, _] = color.to_rgba8();
+        let alpha = color.a as f64 * opacity * *cov as f64;
+        let a = (alpha * 255.0).round().clamp(0.0, 255.0) as u8;
+        let (dx, dy) = (x0 as u32 + x, y0 as u32 + y);
+        let dst = buf.get_pixel(dx, dy).unwrap_or_default();
+        buf.set_pixel(dx, dy, blend_pixel(BlendMode::Normal, [r, g, b, a], dst));
+    }
+}
+
+/// How much `m` scales lengths on average, for stroke widths and dashes.
+fn scale_factor(m: &Mat4) -> f64 {
+    (m.m[0][0] * m.m[1][1] - m.m[0][1] * m.m[1][0]).abs().sqrt()
+}
+
+/// The affine transform `matrix(a b c d e f)`.
+fn affine(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Mat4 {
+    let mut m = Mat4::identity();
+    m.m[0] = [a, c, 0.0, e];
+    m.m[1] = [b, d, 0.0, f];
+    m
+}
+
+impl SvgPaint {
+    /// This paint for a shape mapped by `ctm` whose user-space bounds are `bounds`, as
+    /// a [`Paint`] over the device-space region at `origin` of `size`.
+    fn to_paint(
+        &self,
+        ctm: &Mat4,
+        bounds: (Point2D, Point2D),
+        origin: Point2D,
+        size: Size2D,
+    ) -> Paint {
+        let gradient = match self {
+            SvgPaint::Color(color) => return Paint::Solid(*color),
+            SvgPaint::Gradient(gradient) => gradient,
+        };
+        let last = gradient
+            .stops
+            .last()
+            .map_or(Color::TRANSPARENT, |s| s.color);
+        let (min, max) = bounds;
+        let units = if gradient.bounding_box {
+            let (w, h) = (max.x - min.x, max.y - min.y);
+            if w <= 0.0 || h <= 0.0 {
+                return Paint::Solid(last);
+            }
+            affine(w, 0.0, 0.0, h, min.x, min.y)
+        } else {
+            Mat4::identity()
+        };
+        let m = *ctm * units * gradient.transform;
+        let local = |p: Point2D| {
+            let [x, y] = m.transform_point(p.x, p.y);
+            Point2D::new(x - origin.x, y - origin.y)
+        };
+        let placed = match gradient.kind {
+            SvgGradientKind::Linear { start, end } => {
+                Gradient::linear_between(local(start), local(end), size, &gradient.stops)
+            }
+            SvgGradientKind::Radial { center, radius } => Gradient::radial_around(
+                local(center),
+                radius * scale_factor(&m),
+                size,
+                &gradient.stops,
+            ),
+        };
+        placed.map_or(Paint::Solid(last), Paint::Gradient)
+    }
+
+    /// The paint with its alpha scaled by `opacity`.
+    fn faded(mut self, opacity: f64) -> Self {
+        match &mut self {
+            SvgPaint::Color(color) => color.a *= opacity as f32,
+            SvgPaint::Gradient(gradient) => {
+                for stop in &mut gradient.stops {
+                    stop.color.a *= opacity as f32;
+                }
+            }
+        }
+        self
+    }
+}
+
+/// Inherited presentation properties.
+#[derive(Debug, Clone)]
+struct Style {
+    fill: Option<SvgPaint>,
+    fill_opacity: f64,
+    fill_rule: FillRule,
+    stroke: Option<SvgPaint>,
+    stroke_opacity: f64,
+    stroke_width: f64,
+    stroke_style: StrokeStyle,
+    /// `currentColor`.
+    color: Color,
+    visible: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Some(SvgPaint::Color(Color::BLACK)),
+            fill_opacity: 1.0,
+            fill_rule: FillRule::NonZero,
+            stroke: None,
+            stroke_opacity: 1.0,
+            stroke_width: 1.0,
+            stroke_style: StrokeStyle::default(),
+            color: Color::BLACK,
+            visible: true,
+        }
+    }
+}
+
+struct Parser<'a, 'input> {
+    ids: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    viewport: Size2D,
+}
+
+impl<'a, 'input> Parser<'a, 'input> {
+    /// Convert an element and its descendants; `None` for elements that draw nothing.
+    fn node(&self, el: roxmltree::Node<'a, 'input>, parent: &Style, depth: usize) -> Option<Node> {
+        if !el.is_element() || property(el, "display") == Some("none") {
+            return None;
+        }
+        let style = self.style(el, parent);
+        let kind = match el.tag_name().name() {
+            "svg" | "g" | "a" | "switch" => {
+                let children = el
+                    .children()
+                    .filter_map(|child| self.node(child, &style, depth))
+                    .collect();
+                NodeKind::Group {
+                    children,
+                    is_group: el.tag_name().name() == "g",
+                }
+            }
+            "use" => {
+                let target = el
+                    .attribute("href")
+                    .or_else(|| el.attribute(("http://www.w3.org/1999/xlink", "href")))
+                    .and_then(|href| href.strip_prefix('#'))
+                    .and_then(|id| self.ids.get(id))?;
+                if depth >= MAX_USE_DEPTH || target.ancestors().any(|a| a == el) {
+                    return None;
+                }
+                let mut child = self.node(*target, &style, depth + 1)?;
+                // The copy's ids belong to the original.
+                child.clear_ids();
+                NodeKind::Group {
+                    children: vec![child],
+                    is_group: false,
+                }
+            }
+            name => {
+                let data = self.geometry(el, name)?;
+                if !style.visible {
+                    return None;
+                }
+                NodeKind::Shape(Box::new(Shape {
+                    data,
+                    fill: style.fill.clone().map(|p| p.faded(style.fill_opacity)),
+                    fill_rule: style.fill_rule,
+                    stroke: style.stroke.clone().map(|p| p.faded(style.stroke_opacity)),
+                    stroke_width: style.stroke_width,
+                    stroke_style: style.stroke_style.clone(),
+                }))
+            }
+        };
+
+        let mut transform = el
+            .attribute("transform")
+            .map_or_else(Mat4::identity, parse_transform);
+        if matches!(el.tag_name().name(), "use" | "svg") && el.parent_element().is_some() {
+            let x = el.attribute("x").and_then(length).unwrap_or(0.0);
+            let y = el.attribute("y").and_then(length).unwrap_or(0.0);
+            transform = transform * Mat4::translation(x, y, 0.0);
+        }
+        Some(Node {
+            id: el.attribute("id").map(str::to_string),
+            transform,
+            opacity: property(el, "opacity")
+                .and_then(number_or_percent)
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0),
+            kind,
+        })
+    }
+
+    /// The path drawn by a shape element, or `None` for other elements.
+    fn geometry(&self, el: roxmltree::Node, name: &str) -> Option<PathData> {
+        let num = |attr: &str| el.attribute(attr).and_then(length);
+        let d = match name {
+            "path" => el.attribute("d")?.to_string(),
+            "rect" => {
+                let (x, y) = (num("x").unwrap_or(0.0), num("y").unwrap_or(0.0));
+                let (w, h) = (num("width")?, num("height")?);
+                if w <= 0.0 || h <= 0.0 {
+                    return None;
+                }
+                let (rx, ry) = match (num("rx"), num("ry")) {
+                    (Some(rx), Some(ry)) => (rx, ry),
+                    (Some(r), None) | (None, Some(r)) => (r, r),
+                    (None, None) => (0.0, 0.0),
+                };
+                let (rx, ry) = (rx.clamp(0.0, w / 2.0), ry.clamp(0.0, h / 2.0));
+                if rx > 0.0 && ry > 0.0 {
+                    format!(
+                        "M{} {} H{} A{rx} {ry} 0 0 1 {} {} V{} A{rx} {ry} 0 0 1 {} {} H{} \
+                         A{rx} {ry} 0 0 1 {} {} V{} A{rx} {ry} 0 0 1 {} {} Z",
+                        x + rx,
+                        y,
+                        x + w - rx,
+                        x + w,
+                        y + ry,
+                        y + h - ry,
+                        x + w - rx,
+                        y + h,
+                        x + rx,
+                        x,
+                        y + h - ry,
+                        y + ry,
+                        x + rx,
+                        y,
+                    )
+                } else {
+                    format!("M{x} {y} H{} V{} H{x} Z", x + w, y + h)
+                }
+            }
+            "circle" | "ellipse" => {
+                let (cx, cy) = (num("cx").unwrap_or(0.0), num("cy").unwrap_or(0.0));
+                let (rx, ry) = if name == "circle" {
+                    let r = num("r")?;
+                    (r, r)
+                } else {
+                    (num("rx")?, num("ry")?)
+                };
+                if rx <= 0.0 || ry <= 0.0 {
+                    return None;
+                }
+                format!(
+                    "M{} {cy} A{rx} {ry} 0 0 1 {cx} {} A{rx} {ry} 0 0 1 {} {cy} \
+                     A{rx} {ry} 0 0 1 {cx} {} A{rx} {ry} 0 0 1 {} {cy} Z",
+                    cx + rx,
+                    cy + ry,
+                    cx - rx,
+                    cy - ry,
+                    cx + rx,
+                )
+            }
+            "line" => format!(
+                "M{} {} L{} {}",
+                num("x1").unwrap_or(0.0),
+                num("y1").unwrap_or(0.0),
+                num("x2").unwrap_or(0.0),
+                num("y2").unwrap_or(0.0)
+            ),
+            "polyline" | "polygon" => {
+                let points = numbers(el.attribute("points")?);
+                let mut d = String::new();
+                for (i, pair) in points.chunks_exact(2).enumerate() {
+                    d += &format!("{}{} {} ", if i == 0 { 'M' } else { 'L' }, pair[0], pair[1]);
+                }
+                if name == "polygon" {
+                    d.push('Z');
+                }
+                d
+            }
+            _ => return None,
+        };
+        PathData::parse_svg(&d).ok()
+    }
+
+    /// The element's style: its own presentation attributes over `parent`'s.
+    fn style(&self, el: roxmltree::Node, parent: &Style) -> Style {
+        let mut style = parent.clone();
+        if let Some(color) = property(el, "color").and_then(|v| parse_color(v, parent.color)) {
+            style.color = color;
+        }
+        if let Some(fill) = property(el, "fill") {
+            style.fill = self.paint(fill, style.color);
+        }
+        if let Some(stroke) = property(el, "stroke") {
+            style.stroke = self.paint(stroke, style.color);
+        }
+        let opacity = |name| property(el, name).and_then(number_or_percent);
+        if let Some(v) = opacity("fill-opacity") {
+            style.fill_opacity = v.clamp(0.0, 1.0);
+        }
+        if let Some(v) = opacity("stroke-opacity") {
+            style.stroke_opacity = v.clamp(0.0, 1.0);
+        }
+        match property(el, "fill-rule") {
+            Some("evenodd") => style.fill_rule = FillRule::EvenOdd,
+            Some("nonzero") => style.fill_rule = FillRule::NonZero,
+            _ => {}
+        }
+        if let Some(width) = property(el, "stroke-width").and_then(length) {
+            style.stroke_width = width.max(0.0);
+        }
+        let stroke = &mut style.stroke_style;
+        match property(el, "stroke-linecap") {
+            Some("butt") => stroke.cap = LineCap::Butt,
+            Some("round") => stroke.cap = LineCap::Round,
+            Some("square") => stroke.cap = LineCap::Square,
+            _ => {}
+        }
+        match property(el, "stroke-linejoin") {
+            Some("miter") => stroke.join = LineJoin::Miter,
+            Some("round") => stroke.join = LineJoin::Round,
+            Some("bevel") => stroke.join = LineJoin::Bevel,
+            _ => {}
+        }
+        if let Some(limit) = property(el, "stroke-miterlimit").and_then(length) {
+            stroke.miter_limit = limit.max(1.0);
+        }
+        if let Some(dash) = property(el, "stroke-dasharray") {
+            let mut lengths = numbers(dash);
+            if lengths.len() % 2 == 1 {
+                lengths.extend_from_within(..);
+            }
+            let valid = lengths.iter().all(|l| *l >= 0.0) && lengths.iter().sum::<f64>() > 0.0;
+            stroke.dash = if valid { lengths } else { Vec::new() };
+        }
+        if let Some(offset) = property(el, "stroke-dashoffset").and_then(length) {
+            stroke.dash_offset = offset;
+        }
+        match property(el, "visibility") {
+            Some("hidden") | Some("collapse") => style.visible = false,
+            Some("visible") => style.visible = true,
+            _ => {}
+        }
+        style
+    }
+
+    /// A `fill` or `stroke` value: `none`, a color, or `url(#id)` with an optional
+    /// fallback color.
+    fn paint(&self, value: &str, current: Color) -> Option<SvgPaint> {
+        let value = value.trim();
+        if let Some(rest) = value.strip_prefix("url(") {
+            let (reference, fallback) = rest.split_once(')')?;
+            let id = reference
+                .trim()
+                .trim_matches(['\'', '"'])
+                .strip_prefix('#')?;
+            return match self.ids.get(id).and_then(|el| self.gradient(*el)) {
+                Some(gradient) => Some(gradient),
+                None => self.paint(fallback, current),
+            };
+        }
+        match value {
+            "" | "none" => None,
+            _ => parse_color(value, current).map(SvgPaint::Color),
+        }
+    }
+
+    /// A `linearGradient` or `radialGradient`, taking attributes and stops it doesn't
+    /// set itself from the gradient it references with `href`.
+    fn gradient(&self, el: roxmltree::Node) -> Option<SvgPaint> {
+        let mut chain = vec![el];
+        while chain.len() < MAX_USE_DEPTH {
+            let next = chain
+                .last()
+                .and_then(|g| {
+                    g.attribute("href")
+                        .or_else(|| g.attribute(("http://www.w3.org/1999/xlink", "href")))
+                })
+                .and_then(|href| href.strip_prefix('#'))
+                .and_then(|id| self.ids.get(id));
+            match next {
+                Some(next) if !chain.contains(next) => chain.push(*next),
+                _ => break,
+            }
+        }
+        let attr = |name: &str| chain.iter().find_map(|g| g.attribute(name));
+        let bounding_box = attr("gradientUnits") != Some("userSpaceOnUse");
+        // Percentages are of the bounding box, or of the viewport in user space.
+        let coord = |name: &str, default: f64, extent: f64| {
+            let Some(value) = attr(name) else {
+                return default * if bounding_box { 1.0 } else { extent };
+            };
+            match value.trim().strip_suffix('%') {
+                Some(percent) => {
+                    let fraction = percent.trim().parse::<f64>().unwrap_or(0.0) / 100.0;
+                    fraction * if bounding_box { 1.0 } else { extent }
+                }
+                None => length(value).unwrap_or(0.0),
+            }
+        };
+        let (vw, vh) = (self.viewport.width, self.viewport.height);
+        let kind = match el.tag_name().name() {
+            "linearGradient" => SvgGradientKind::Linear {
+                start: Point2D::new(coord("x1", 0.0, vw), coord("y1", 0.0, vh)),
+                end: Point2D::new(coord("x2", 1.0, vw), coord("y2", 0.0, vh)),
+            },
+            "radialGradient" => SvgGradientKind::Radial {
+                center: Point2D::new(coord("cx", 0.5, vw), coord("cy", 0.5, vh)),
+                radius: coord(
+                    "r",
+                    0.5,
+                    (vw * vw + vh * vh).sqrt() / std::f64::consts::SQRT_2,
+                ),
+            },
+            _ => return None,
+        };
+
+        let stop_elements = chain
+            .iter()
+            .map(|g| {
+                g.children()
+                    .filter(|c| c.has_tag_name("stop"))
+                    .collect::<Vec<_>>()
+            })
+            .find(|stops| !stops.is_empty())
+            .unwrap_or_default();
+        let mut stops: Vec<ColorStop> = Vec::new();
+        for stop in stop_elements {
+            let offset = stop
+                .attribute("offset")
+                .and_then(number_or_percent)
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0);
+            // Offsets never go backwards.
+            let offset = stops.last().map_or(offset, |s| offset.max(s.offset));
+            let mut color = property(stop, "stop-color")
+                .and_then(|v| parse_color(v, Color::BLACK))
+                .unwrap_or(Color::BLACK);
+            color.a *= property(stop, "stop-opacity")
+                .and_then(number_or_percent)
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0) as f32;
+            stops.push(ColorStop::new(offset, color));
+        }
+        match stops.len() {
+            0 => None,
+            1 => Some(SvgPaint::Color(stops[0].color)),
+            _ => Some(SvgPaint::Gradient(SvgGradient {
+                kind,
+                bounding_box,
+                transform: attr("gradientTransform").map_or_else(Mat4::identity, parse_transform),
+                stops,
+            })),
+        }
+    }
+}
+
+/// A presentation property of `el`: from its `style` attribute, or else the attribute
+/// of the same name.
+fn property<'a>(el: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    el.attribute("style")
+        .and_then(|style| {
+            style.split(';').rev().find_map(|declaration| {
+                let (key, value) = declaration.split_once(':')?;
+                (key.trim() == name).then(|| value.trim().trim_end_matches("!important").trim())
+            })
+        })
+        .or_else(|| el.attribute(name).map(str::trim))
+}
+
+/// A length in pixels. Relative units other than `em` aren't supported.
+fn length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let units = [
+        ("px", 1.0),
+        ("pt", 4.0 / 3.0),
+        ("pc", 16.0),
+        ("mm", 96.0 / 25.4),
+        ("cm", 96.0 / 2.54),
+        ("in", 96.0),
+        ("em", 16.0),
+    ];
+    let (number, factor) = units
+        .iter()
+        .find_map(|(unit, factor)| Some((value.strip_suffix(unit)?, *factor)))
+        .unwrap_or((value, 1.0));
+    number.trim().parse::<f64>().ok().map(|n| n * factor)
+}
+
+/// A number, or a percentage as a fraction.
+fn number_or_percent(value: &str) -> Option<f64> {
+    let value = value.trim();
+    match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().ok().map(|p| p / 100.0),
+        None => value.parse().ok(),
+    }
+}
+
+/// The numbers in a list separated by whitespace, commas, or just signs, as in SVG
+/// attributes (`"10,20 -5-5"`).
+fn numbers(value: &str) -> Vec<f64> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !(bytes[i].is_ascii_digit() || matches!(bytes[i], b'-' | b'+' | b'.')) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        if matches!(bytes[i], b'-' | b'+') {
+            i += 1;
+        }
+        let mut seen_dot = false;
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || (bytes[i] == b'.' && !seen_dot)) {
+            seen_dot |= bytes[i] == b'.';
+            i += 1;
+        }
+        if i < bytes.len() && matches!(bytes[i], b'e' | b'E') {
+            let mut j = i + 1;
+            if j < bytes.len() && matches!(bytes[j], b'-' | b'+') {
+                j += 1;
+            }
+            if j < bytes.len() && bytes[j].is_ascii_digit() {
+                i = j;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+        }
+        match value[start..i].parse() {
+            Ok(n) => out.push(n),
+            Err(_) => i = i.max(start + 1),
+        }
+    }
+    out
+}
+
+/// A `transform` attribute: a list of `matrix`, `translate`, `scale`, `rotate`, `skewX`
+/// and `skewY`, applied right to left. Unknown functions are skipped.
+fn parse_transform(value: &str) -> Mat4 {
+    let mut m = Mat4::identity();
+    let mut rest = value;
+    while let Some((name, tail)) = rest.split_once('(') {
+        let Some((args, tail)) = tail.split_once(')') else {
+            break;
+        };
+        rest = tail;
+        let a = numbers(args);
+        let arg = |i: usize, default: f64| a.get(i).copied().unwrap_or(default);
+        let t = match (
+            name.trim_matches(|c: char| c.is_whitespace() || c == ','),
+            a.len(),
+        ) {
+            ("matrix", 6) => affine(a[0], a[1], a[2], a[3], a[4], a[5]),
+            ("translate", 1 | 2) => Mat4::translation(a[0], arg(1, 0.0), 0.0),
+            ("scale", 1 | 2) => Mat4::scale(a[0], arg(1, a[0]), 1.0),
+            ("rotate", 1 | 3) => {
+                let (cx, cy) = (arg(1, 0.0), arg(2, 0.0));
+                Mat4::translation(cx, cy, 0.0)
+                    * Mat4::rotation_z(a[0])
+                    * Mat4::translation(-cx, -cy, 0.0)
+            }
+            ("skewX", 1) => affine(1.0, 0.0, a[0].to_radians().tan(), 1.0, 0.0, 0.0),
+            ("skewY", 1) => affine(1.0, a[0].to_radians().tan(), 0.0, 1.0, 0.0, 0.0),
+            _ => continue,
+        };
+        m = m * t;
+    }
+    m
+}
+
+/// A CSS color: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()`,
+/// `currentColor` or a common color name.
+fn parse_color(value: &str, current: Color) -> Option<Color> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        let expanded: String = match hex.len() {
+            3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+            _ => hex.to_string(),
+        };
+        return Color::from_hex(&expanded).ok();
+    }
+    if let Some(args) = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|v| v.strip_suffix(')'))
+    {
+        let parts: Vec<&str> = args
+            .split([',', ' ', '/'])
+            .filter(|p| !p.is_empty())
+            .collect();
+        if parts.len() < 3 {
+            return None;
+        }
+        let channel = |p: &str| match p.strip_suffix('%') {
+            Some(percent) => percent.parse::<f32>().ok().map(|v| v / 100.0),
+            None => p.parse::<f32>().ok().map(|v| v / 255.0),
+        };
+        let alpha = parts
+            .get(3)
+            .map_or(Some(1.0), |p| number_or_percent(p).map(|a| a as f32));
+        return Some(Color::rgba(
+            channel(parts[0])?.clamp(0.0, 1.0),
+            channel(parts[1])?.clamp(0.0, 1.0),
+            channel(parts[2])?.clamp(0.0, 1.0),
+            alpha?.clamp(0.0, 1.0),
+        ));
+    }
+    if value.eq_ignore_ascii_case("currentColor") {
+        return Some(current);
+    }
+    let hex = match value.to_ascii_lowercase().as_str() {
+        "black" => "000000",
+        "white" => "ffffff",
+        "red" => "ff0000",
+        "lime" => "00ff00",
+        "green" => "008000",
+        "blue" => "0000ff",
+        "yellow" => "ffff00",
+        "cyan" | "aqua" => "00ffff",
+        "magenta" | "fuchsia" => "ff00ff",
+        "gray" | "grey" => "808080",
+        "silver" => "c0c0c0",
+        "maroon" => "800000",
+        "olive" => "808000",
+        "purple" => "800080",
+        "teal" => "008080",
+        "navy" => "000080",
+        "orange" => "ffa500",
+        "pink" => "ffc0cb",
+        "brown" => "a52a2a",
+        "gold" => "ffd700",
+        "indigo" => "4b0082",
+        "violet" => "ee82ee",
+        "transparent" => return Some(Color::TRANSPARENT),
+        _ => return None,
+    };
+    Color::from_hex(hex).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BADGE: &str = r##"
+        <svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="0 0 200 100">
+          <defs>
+            <linearGradient id="fade">
+              <stop offset="0" stop-color="#000"/>
+              <stop offset="100%" stop-color="#fff"/>
+            </linearGradient>
+          </defs>
+          <rect width="200" height="100" fill="url(#fade)"/>
+          <g id="mark" transform="translate(20 20)" style="fill: red">
+            <circle cx="30" cy="30" r="30"/>
+            <g id="dot"><rect x="50" y="50" width="20" height="20" fill="blue"/></g>
+          </g>
+          <use href="#mark" x="100" opacity="0.5"/>
+        </svg>"##;
+
+    #[test]
+    fn test_renders_at_any_scale() {
+        let doc = SvgDocument::parse(BADGE).unwrap();
+        assert_eq!((doc.size.width, doc.size.height), (100.0, 50.0));
+
+        let buf = doc.render(None, &[], (1.0, 1.0), 1.0);
+        assert_eq!((buf.width, buf.height), (100, 50));
+        // The circle, at viewBox (50, 50), is drawn at half size.
+        assert_eq!(buf.get_pixel(25, 25), Some([255, 0, 0, 255]));
+        // The gradient runs left to right under everything.
+        assert!(buf.get_pixel(1, 1).unwrap()[0] < 8);
+        assert!(buf.get_pixel(98, 1).unwrap()[0] > 247);
+        // The half-transparent copy of the mark is blended over the gradient.
+        let [r, g, _, _] = buf.get_pixel(75, 25).unwrap();
+        assert!(r > 200 && g > 40 && g < 120, "{:?}", buf.get_pixel(75, 25));
+
+        let big = doc.render(None, &[], (4.0, 4.0), 1.0);
+        assert_eq!((big.width, big.height), (400, 200));
+        assert_eq!(big.get_pixel(100, 100), Some([255, 0, 0, 255]));
+        // Scaled up, the circle's edge stays one pixel wide instead of being resampled.
+        let mark = doc.render(Some("mark"), &[], (4.0, 4.0), 1.0);
+        let partial = (100..140)
+            .map(|x| mark.get_pixel(x, 60).unwrap()[3])
+            .filter(|a| *a != 0 && *a != 255)
+            .count();
+        assert!(partial <= 1, "{} partially covered pixels", partial);
+    }
+
+    #[test]
+    fn test_elements_render_in_their_own_box() {
+        let doc = SvgDocument::parse(BADGE).unwrap();
+        let (origin, size) = doc.content_rect(Some("dot")).unwrap();
+        assert_eq!(
+            (origin.x, origin.y, size.width, size.height),
+            (35.0, 35.0, 10.0, 10.0)
+        );
+        let dot = doc.render(Some("dot"), &[], (2.0, 2.0), 1.0);
+        assert_eq!((dot.width, dot.height), (20, 20));
+        assert_eq!(dot.get_pixel(10, 10), Some([0, 0, 255, 255]));
+
+        let without = doc.render(Some("mark"), &["dot".to_string()], (1.0, 1.0), 1.0);
+        let (origin, _) = doc.content_rect(Some("mark")).unwrap();
+        let (x, y) = ((40.0 - origin.x) as u32, (40.0 - origin.y) as u32);
+        assert_eq!(without.get_pixel(x, y).unwrap()[3], 0);
+        assert!(doc.content_rect(Some("missing")).is_none());
+    }
+
+    #[test]
+    fn test_expand_groups_into_child_layers() {
+        let doc = SvgDocument::parse(BADGE).unwrap();
+        let content = LayerContent::Svg {
+            asset_id: vidra_ir::asset::AssetId::new("badge.svg"),
+            element: None,
+            exclude: Vec::new(),
+            expand_groups: true,
+        };
+        let declared = Layer::new(LayerId::new("mark"), LayerContent::Empty).with_opacity(0.5);
+        let mut layer = Layer::new(LayerId::new("badge"), content);
+        layer.children.push(declared);
+
+        assert_eq!(doc.expand_groups(&mut layer), 3);
+        let LayerContent::Svg { exclude, .. } = &layer.content else {
+            panic!("still an SVG layer");
+        };
+        assert_eq!(exclude, &vec!["mark".to_string()]);
+        let mark = &layer.children[0];
+        assert_eq!(layer.children.len(), 1);
+        assert_eq!(mark.transform.opacity, 0.5);
+        assert_eq!(mark.transform.position, Point2D::new(27.5, 27.5));
+        let dot = &mark.children[0];
+        assert_eq!(dot.id, LayerId::new("dot"));
+        // Relative to the mark's box, which starts at (10, 10).
+        assert_eq!(dot.transform.position, Point2D::new(30.0, 30.0));
+        // Expanding again does nothing.
+        assert_eq!(doc.expand_groups(&mut layer), 0);
+    }
+
+    #[test]
+    fn test_attribute_parsing() {
+        assert_eq!(
+            numbers("10,20 -5-5 .5.5 1e2"),
+            vec![10.0, 20.0, -5.0, -5.0, 0.5, 0.5, 100.0]
+        );
+        assert_eq!(length("1in"), Some(96.0));
+        assert_eq!(parse_color("#f00", Color::BLACK), Some(Color::RED));
+        assert_eq!(
+            parse_color("rgb(0, 0, 255)", Color::BLACK),
+            Some(Color::rgb(0.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            parse_color("currentColor", Color::WHITE),
+            Some(Color::WHITE)
+        );
+        let m = parse_transform("translate(10 0) scale(2) rotate(90)");
+        let [x, y] = m.transform_point(1.0, 0.0);
+        assert!((x - 10.0).abs() < 1e-9 && (y - 2.0).abs() < 1e-9);
+        assert!(SvgDocument::parse("<html/>").is_err());
+        assert!(SvgDocument::parse("<svg").is_err());
+    }
+}
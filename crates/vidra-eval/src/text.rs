@@ -0,0 +1,1142 @@
+//! Text layout and rasterization shared by every renderer.
+//!
+//! Each paragraph (text between `\n`s) is split into runs of one style, font and
+//! direction. Runs are shaped with OpenType rules (ligatures, kerning, contextual
+//! forms) and laid out in Unicode bidi order. Characters a font lacks fall back,
+//! one grapheme at a time, to the other loaded fonts by family name and then to the
+//! bundled font. With a `max_width`, lines break at Unicode line break opportunities,
+//! or between graphemes inside a word too wide for a line.
+//!
+//! Everything is plain integer and `f32` arithmetic with no system fonts, so the same
+//! fonts give the same pixels on every machine.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use fontdue::{Font, FontSettings};
+use rustybuzz::{Direction, UnicodeBuffer};
+use unicode_bidi::{Level, ParagraphBidiInfo};
+use unicode_linebreak::linebreaks;
+use unicode_segmentation::UnicodeSegmentation;
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::{Color, Duration};
+use vidra_ir::text::{TextAlign, TextAnimator, TextStyle};
+
+use crate::animator::{self, UnitState};
+use crate::mask::feather_coverage;
+
+/// A parsed font file, for both shaping and rasterizing.
+#[derive(Clone)]
+pub struct FontFace {
+    data: Arc<[u8]>,
+    font: Font,
+}
+
+impl FontFace {
+    /// Parse a TrueType or OpenType font (the first face of a collection).
+    pub fn from_bytes(data: impl Into<Arc<[u8]>>) -> Result<Self, String> {
+        let data = data.into();
+        if rustybuzz::Face::from_slice(&data, 0).is_none() {
+            return Err("not an OpenType font".to_string());
+        }
+        let font = Font::from_bytes(&*data, FontSettings::default())?;
+        Ok(Self { data, font })
+    }
+
+    fn covers(&self, ch: char) -> bool {
+        self.font.lookup_glyph_index(ch) != 0
+    }
+}
+
+/// The fonts text layers can use, by family name, with a bundled font for unknown
+/// families and characters no loaded font has.
+#[derive(Clone)]
+pub struct FontBook {
+    default: FontFace,
+    /// Sorted by family name, which is also the fallback order, so that it does not
+    /// depend on the order fonts were loaded in.
+    fonts: Vec<(String, FontFace)>,
+}
+
+impl FontBook {
+    pub fn new(default: FontFace) -> Self {
+        Self {
+            default,
+            fonts: Vec::new(),
+        }
+    }
+
+    /// Add `face` as `family`, replacing any font of that name.
+    pub fn insert(&mut self, family: impl Into<String>, face: FontFace) {
+        let family = family.into();
+        match self.fonts.binary_search_by(|(name, _)| name.cmp(&family)) {
+            Ok(i) => self.fonts[i].1 = face,
+            Err(i) => self.fonts.insert(i, (family, face)),
+        }
+    }
+
+    /// The font for `family`, or the bundled font.
+    pub fn get(&self, family: &str) -> &FontFace {
+        self.find(family).unwrap_or(&self.default)
+    }
+
+    fn find(&self, family: &str) -> Option<&FontFace> {
+        self.fonts
+            .binary_search_by(|(name, _)| name.as_str().cmp(family))
+            .ok()
+            .map(|i| &self.fonts[i].1)
+    }
+
+    /// A loaded bold face of `family` ("Inter-Bold", "Inter Bold" or "InterBold").
+    fn bold(&self, family: &str) -> Option<&FontFace> {
+        ["-Bold", " Bold", "Bold"]
+            .iter()
+            .find_map(|suffix| self.find(&format!("{family}{suffix}")))
+    }
+
+    /// The face to draw `grapheme` with: `preferred` if it has every character,
+    /// else the first loaded font that does, else the bundled font. When no font
+    /// has them all, the first with the base character wins, then `preferred`.
+    fn fallback<'a>(&'a self, preferred: &'a FontFace, grapheme: &str) -> &'a FontFace {
+        let chain = || {
+            std::iter::once(preferred)
+                .chain(self.fonts.iter().map(|(_, face)| face))
+                .chain(std::iter::once(&self.default))
+        };
+        let needed = || grapheme.chars().filter(|&ch| !is_format_char(ch));
+        chain()
+            .find(|face| needed().all(|ch| face.covers(ch)))
+            .or_else(|| {
+                let base = needed().next()?;
+                chain().find(|face| face.covers(base))
+            })
+            .unwrap_or(preferred)
+    }
+}
+
+/// Joiners and variation selectors, which fonts need not map to a glyph.
+fn is_format_char(ch: char) -> bool {
+    matches!(ch, '\u{200C}' | '\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// A text layer's content (`LayerContent::Text`), borrowed for drawing.
+#[derive(Clone, Copy)]
+pub struct TextBlock<'a> {
+    pub text: &'a str,
+    pub font_family: &'a str,
+    pub font_size: f32,
+    pub color: &'a Color,
+    pub style: &'a TextStyle,
+    pub animators: &'a [TextAnimator],
+}
+
+/// Lay out and draw `block` in a buffer sized to fit it, with its animators at
+/// scene-local `time`. Every color is faded by `opacity`.
+///
+/// A stroke, shadow or animator pads the buffer by the same amount on opposite sides,
+/// so that the text stays centered on the layer's anchor. Animators pad it by as much
+/// as their keyframes could ever move a unit, so the buffer keeps its size as they play.
+pub fn render_text(
+    fonts: &FontBook,
+    block: &TextBlock,
+    time: Duration,
+    opacity: f32,
+) -> FrameBuffer {
+    let style = block.style;
+    if block.text.is_empty() {
+        return FrameBuffer::new(1, 1, PixelFormat::Rgba8);
+    }
+
+    let lines = layout(fonts, block, opacity);
+    let widest = lines.iter().map(|l| l.width).max().unwrap_or(0);
+    let width = match style.max_width {
+        Some(max_width) => max_width.ceil() as i32,
+        None => widest,
+    }
+    .max(1);
+    let height = match lines.as_slice() {
+        [line] => line.ascent + line.descent,
+        [.., last] => {
+            lines[..lines.len() - 1]
+                .iter()
+                .map(|l| l.pitch)
+                .sum::<i32>()
+                + last.ascent
+                + last.descent
+        }
+        [] => 0,
+    }
+    .max(1);
+
+    let lefts: Vec<i32> = lines
+        .iter()
+        .map(|line| match style.align {
+            TextAlign::Left => 0,
+            TextAlign::Center => (width - line.width) / 2,
+            TextAlign::Right => width - line.width,
+        })
+        .collect();
+    let motion = Motion::new(&lines, &lefts, block.animators, time);
+
+    let stroke = style.stroke.as_ref().filter(|s| s.width > 0.0);
+    let shadow = style.shadow.as_ref();
+    let decoration = if stroke.is_none() && shadow.is_none() {
+        0
+    } else {
+        let reach = shadow.map_or(0.0, |s| s.offset_x.abs().max(s.offset_y.abs()) + s.blur);
+        (stroke.map_or(0.0, |s| s.width) + reach).ceil() as i32 + 1
+    };
+    let (reach_x, reach_y) = motion.reach();
+    let pad = (
+        decoration + reach_x.ceil() as i32,
+        decoration + reach_y.ceil() as i32,
+    );
+
+    let (w, h) = ((width + 2 * pad.0) as u32, (height + 2 * pad.1) as u32);
+    let mut fill = FrameBuffer::new(w, h, PixelFormat::Rgba8);
+    let mut coverage = vec![0u8; (w * h) as usize];
+    let mut y = pad.1;
+    for (line, left) in lines.iter().zip(&lefts) {
+        line.draw(&mut fill, &mut coverage, pad.0 + left, y, |glyph| {
+            motion.transform(glyph, (pad.0 as f32, pad.1 as f32))
+        });
+        y += line.pitch;
+    }
+    if decoration == 0 {
+        return fill;
+    }
+
+    // Stroke and shadow go under the fill, each painted from the glyph coverage so
+    // that translucent text still gets a solid outline.
+    let mut out = FrameBuffer::new(w, h, PixelFormat::Rgba8);
+    let outline = stroke.map(|s| dilate(&coverage, w, h, s.width));
+    if let Some(shadow) = shadow {
+        let mut shape = outline.clone().unwrap_or_else(|| coverage.clone());
+        feather_coverage(&mut shape, w, h, shadow.blur);
+        let layer = tint(&shape, w, h, &shadow.color, opacity);
+        out.composite_over(
+            &layer,
+            shadow.offset_x.round() as i32,
+            shadow.offset_y.round() as i32,
+        );
+    }
+    if let (Some(stroke), Some(outline)) = (stroke, &outline) {
+        out.composite_over(&tint(outline, w, h, &stroke.color, opacity), 0, 0);
+    }
+    out.composite_over(&fill, 0, 0);
+    out
+}
+
+/// How a run of characters is drawn.
+#[derive(Clone, Copy)]
+struct RunStyle<'a> {
+    face: &'a FontFace,
+    size: f32,
+    rgba: [u8; 4],
+    /// Pixels of faux bold smear when no bold face is loaded.
+    embolden: usize,
+}
+
+impl RunStyle<'_> {
+    fn same(&self, other: &Self) -> bool {
+        std::ptr::eq(self.face, other.face)
+            && self.size == other.size
+            && self.rgba == other.rgba
+            && self.embolden == other.embolden
+    }
+}
+
+/// A shaped glyph, positioned relative to the pen.
+struct Glyph<'a> {
+    face: &'a FontFace,
+    id: u16,
+    size: f32,
+    rgba: [u8; 4],
+    embolden: usize,
+    x_offset: f32,
+    y_offset: f32,
+    /// Pen advance after the glyph, with letter spacing and faux bold added after the
+    /// last glyph of each cluster.
+    advance: f32,
+    /// Byte offset in the paragraph of the characters the glyph draws.
+    cluster: usize,
+    /// The character, word and line (indexed by `TextUnit as usize`) the glyph belongs
+    /// to, counted over the whole layer. Whitespace is in no character or word.
+    units: [Option<usize>; 3],
+}
+
+struct Line<'a> {
+    /// In visual order, left to right.
+    glyphs: Vec<Glyph<'a>>,
+    width: i32,
+    ascent: i32,
+    descent: i32,
+    /// Distance from this line's top to the next one's.
+    pitch: i32,
+}
+
+impl Line<'_> {
+    /// Draw the line with its left edge at `x` and top at `y`, each glyph where
+    /// `place` says the animators put it.
+    fn draw(
+        &self,
+        fb: &mut FrameBuffer,
+        coverage: &mut [u8],
+        x: i32,
+        y: i32,
+        place: impl Fn(&Glyph) -> GlyphTransform,
+    ) {
+        let mut pen = x as f32;
+        for glyph in &self.glyphs {
+            let transform = place(glyph);
+            let (metrics, bitmap) = glyph.face.font.rasterize_indexed(glyph.id, glyph.size);
+            let glyph_x = (pen + glyph.x_offset).round() as i32 + metrics.xmin;
+            let glyph_y = y + self.ascent
+                - (metrics.height as i32 + metrics.ymin)
+                - glyph.y_offset.round() as i32;
+            pen += glyph.advance;
+            if transform.opacity <= 0.0 {
+                continue;
+            }
+            let smeared = |row: &[u8], gx: usize| {
+                (gx.saturating_sub(glyph.embolden)..=gx)
+                    .filter_map(|sx| row.get(sx))
+                    .copied()
+                    .max()
+                    .unwrap_or(0)
+            };
+            let width = metrics.width + glyph.embolden;
+            if !transform.is_moved() {
+                let [r, g, b, a] = glyph.rgba;
+                for gy in 0..metrics.height {
+                    let row = &bitmap[gy * metrics.width..(gy + 1) * metrics.width];
+                    for gx in 0..width {
+                        let c = smeared(row, gx);
+                        if c == 0 {
+                            continue;
+                        }
+                        let px = glyph_x + gx as i32;
+                        let py = glyph_y + gy as i32;
+                        if px >= 0 && px < fb.width as i32 && py >= 0 && py < fb.height as i32 {
+                            let glyph_alpha =
+                                (c as f32 / 255.0) * (a as f32 / 255.0) * transform.opacity;
+                            fb.set_pixel(
+                                px as u32,
+                                py as u32,
+                                [r, g, b, (glyph_alpha * 255.0) as u8],
+                            );
+                            let i = py as usize * fb.width as usize + px as usize;
+                            coverage[i] = coverage[i].max((c as f32 * transform.opacity) as u8);
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // Blurred or moved: draw from a padded copy of the glyph's coverage.
+            let margin = transform.blur.ceil() as usize + 1;
+            let (cw, ch) = (width + 2 * margin, metrics.height + 2 * margin);
+            let mut cov = vec![0u8; cw * ch];
+            for gy in 0..metrics.height {
+                let row = &bitmap[gy * metrics.width..(gy + 1) * metrics.width];
+                for gx in 0..width {
+                    cov[(gy + margin) * cw + gx + margin] = smeared(row, gx);
+                }
+            }
+            feather_coverage(&mut cov, cw as u32, ch as u32, transform.blur as f64);
+            let origin = (
+                (glyph_x - margin as i32) as f32,
+                (glyph_y - margin as i32) as f32,
+            );
+            draw_transformed(fb, coverage, (&cov, cw, ch), origin, glyph.rgba, &transform);
+        }
+    }
+}
+
+/// Where the animators put a glyph: the affine map `[a, b, c, d, e, f]` taking a point
+/// `(x, y)` of its laid-out position to `(a·x + c·y + e, b·x + d·y + f)`, plus its fade
+/// and blur.
+#[derive(Clone, Copy)]
+struct GlyphTransform {
+    matrix: [f32; 6],
+    opacity: f32,
+    blur: f32,
+}
+
+impl GlyphTransform {
+    const IDENTITY: GlyphTransform = GlyphTransform {
+        matrix: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        opacity: 1.0,
+        blur: 0.0,
+    };
+
+    /// Whether the glyph needs resampling: it is moved, scaled, rotated or blurred.
+    fn is_moved(&self) -> bool {
+        self.matrix != Self::IDENTITY.matrix || self.blur > 0.0
+    }
+
+    /// Apply `state` to a unit centered on `center`, after this transform.
+    fn then(self, state: &UnitState, center: (f32, f32)) -> GlyphTransform {
+        let (sin, cos) = (state.rotation as f32).to_radians().sin_cos();
+        let (sx, sy) = (state.scale.0 as f32, state.scale.1 as f32);
+        let (a, b, c, d) = (cos * sx, sin * sx, -sin * sy, cos * sy);
+        let (cx, cy) = center;
+        let e = cx + state.offset.0 as f32 - (a * cx + c * cy);
+        let f = cy + state.offset.1 as f32 - (b * cx + d * cy);
+        let [ma, mb, mc, md, me, mf] = self.matrix;
+        GlyphTransform {
+            matrix: [
+                a * ma + c * mb,
+                b * ma + d * mb,
+                a * mc + c * md,
+                b * mc + d * md,
+                a * me + c * mf + e,
+                b * me + d * mf + f,
+            ],
+            opacity: self.opacity * state.opacity as f32,
+            blur: self.blur + state.blur as f32,
+        }
+    }
+}
+
+/// Draw a coverage map `cov` (with its width and height), whose top-left corner was
+/// laid out at `origin`, through `transform` with bilinear sampling. Blends over what
+/// is already drawn rather than replacing it, since moved glyphs may overlap.
+fn draw_transformed(
+    fb: &mut FrameBuffer,
+    coverage: &mut [u8],
+    (cov, cw, ch): (&[u8], usize, usize),
+    origin: (f32, f32),
+    rgba: [u8; 4],
+    transform: &GlyphTransform,
+) {
+    let [a, b, c, d, e, f] = transform.matrix;
+    let det = a * d - b * c;
+    if det.abs() < 1e-6 {
+        return;
+    }
+    let (ox, oy) = origin;
+    let corners = [
+        (ox, oy),
+        (ox + cw as f32, oy),
+        (ox, oy + ch as f32),
+        (ox + cw as f32, oy + ch as f32),
+    ]
+    .map(|(x, y)| (a * x + c * y + e, b * x + d * y + f));
+    let (mut x0, mut y0, mut x1, mut y1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for (x, y) in corners {
+        (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+    }
+    let x_range = (x0.floor().max(0.0) as u32)..(x1.ceil().min(fb.width as f32).max(0.0) as u32);
+    let y_range = (y0.floor().max(0.0) as u32)..(y1.ceil().min(fb.height as f32).max(0.0) as u32);
+
+    let at = |x: i32, y: i32| -> f32 {
+        if x < 0 || y < 0 || x >= cw as i32 || y >= ch as i32 {
+            0.0
+        } else {
+            cov[y as usize * cw + x as usize] as f32
+        }
+    };
+    let [r, g, bl, alpha] = rgba;
+    for py in y_range {
+        for px in x_range.clone() {
+            let (qx, qy) = (px as f32 + 0.5 - e, py as f32 + 0.5 - f);
+            let u = (d * qx - c * qy) / det - ox - 0.5;
+            let v = (a * qy - b * qx) / det - oy - 0.5;
+            let (fx, fy) = (u.floor(), v.floor());
+            let (tx, ty) = (u - fx, v - fy);
+            let (ix, iy) = (fx as i32, fy as i32);
+            let top = at(ix, iy) * (1.0 - tx) + at(ix + 1, iy) * tx;
+            let bottom = at(ix, iy + 1) * (1.0 - tx) + at(ix + 1, iy + 1) * tx;
+            let value = (top * (1.0 - ty) + bottom * ty) * transform.opacity;
+            if value <= 0.0 {
+                continue;
+            }
+            let src_a = value / 255.0 * (alpha as f32 / 255.0);
+            let [dr, dg, db, da] = fb.get_pixel(px, py).unwrap_or([0; 4]);
+            let dst_a = da as f32 / 255.0;
+            let out_a = src_a + dst_a * (1.0 - src_a);
+            let mix = |s: u8, d: u8| {
+                ((s as f32 * src_a + d as f32 * dst_a * (1.0 - src_a)) / out_a).round() as u8
+            };
+            fb.set_pixel(
+                px,
+                py,
+                [
+                    mix(r, dr),
+                    mix(g, dg),
+                    mix(bl, db),
+                    (out_a * 255.0).round() as u8,
+                ],
+            );
+            let i = py as usize * fb.width as usize + px as usize;
+            coverage[i] = coverage[i].max(value.round() as u8);
+        }
+    }
+}
+
+/// The state of a layer's text animators at one moment, and where their units are.
+struct Motion<'a> {
+    animators: &'a [TextAnimator],
+    /// For each animator, the state of each of its units.
+    states: Vec<Vec<UnitState>>,
+    /// For each unit kind (`TextUnit as usize`), the box `[left, top, right, bottom]`
+    /// of each unit, relative to the text's top-left corner.
+    boxes: [Vec<[f32; 4]>; 3],
+}
+
+impl<'a> Motion<'a> {
+    fn new(lines: &[Line], lefts: &[i32], animators: &'a [TextAnimator], time: Duration) -> Self {
+        let mut boxes: [Vec<[f32; 4]>; 3] = Default::default();
+        if !animators.is_empty() {
+            let mut top = 0;
+            for (line, &left) in lines.iter().zip(lefts) {
+                let mut pen = left as f32;
+                let bottom = (top + line.ascent + line.descent) as f32;
+                for glyph in &line.glyphs {
+                    let glyph_box = [pen, top as f32, pen + glyph.advance, bottom];
+                    pen += glyph.advance;
+                    for (kind, unit) in glyph.units.iter().enumerate() {
+                        let Some(unit) = *unit else { continue };
+                        let kind = &mut boxes[kind];
+                        if kind.len() <= unit {
+                            kind.resize(unit + 1, [f32::MAX, f32::MAX, f32::MIN, f32::MIN]);
+                        }
+                        let b = &mut kind[unit];
+                        *b = [
+                            b[0].min(glyph_box[0]),
+                            b[1].min(glyph_box[1]),
+                            b[2].max(glyph_box[2]),
+                            b[3].max(glyph_box[3]),
+                        ];
+                    }
+                }
+                top += line.pitch;
+            }
+        }
+        let states = animators
+            .iter()
+            .map(|a| animator::unit_states(a, boxes[a.unit as usize].len(), time))
+            .collect();
+        Self {
+            animators,
+            states,
+            boxes,
+        }
+    }
+
+    /// How far the animators can draw any glyph outside the text box.
+    fn reach(&self) -> (f64, f64) {
+        self.animators.iter().fold((0.0, 0.0), |(x, y), animator| {
+            let half = self.boxes[animator.unit as usize]
+                .iter()
+                .filter(|b| b[0] <= b[2])
+                .fold((0.0f64, 0.0f64), |(w, h), b| {
+                    (
+                        w.max((b[2] - b[0]) as f64 / 2.0),
+                        h.max((b[3] - b[1]) as f64 / 2.0),
+                    )
+                });
+            let (rx, ry) = animator::reach(animator, half);
+            (x + rx, y + ry)
+        })
+    }
+
+    /// Where the animators put `glyph` now, for text drawn at `origin`.
+    fn transform(&self, glyph: &Glyph, origin: (f32, f32)) -> GlyphTransform {
+        let mut transform = GlyphTransform::IDENTITY;
+        for (animator, states) in self.animators.iter().zip(&self.states) {
+            let kind = animator.unit as usize;
+            let Some(unit) = glyph.units[kind] else {
+                continue;
+            };
+            let state = &states[unit];
+            if state.is_rest() {
+                continue;
+            }
+            let b = self.boxes[kind][unit];
+            let center = (
+                origin.0 + (b[0] + b[2]) / 2.0,
+                origin.1 + (b[1] + b[3]) / 2.0,
+            );
+            transform = transform.then(state, center);
+        }
+        transform
+    }
+}
+
+/// One paragraph split into runs of a single style, font and bidi level.
+struct Paragraph<'t, 'a> {
+    text: &'t str,
+    /// Byte ranges with their style, in logical order.
+    runs: Vec<(Range<usize>, RunStyle<'a>)>,
+    bidi: ParagraphBidiInfo<'t>,
+    spacing: f32,
+}
+
+impl<'a> Paragraph<'_, 'a> {
+    /// Shape `range` in logical order, as if it were one line.
+    fn width(&self, range: Range<usize>) -> f32 {
+        let advance: f32 = self
+            .pieces(range)
+            .flat_map(|(piece, style)| self.shape(piece, style))
+            .map(|g| g.advance)
+            .sum();
+        advance - self.spacing
+    }
+
+    /// Glyphs of `range` in visual order.
+    fn line(&self, range: Range<usize>) -> Vec<Glyph<'a>> {
+        let (levels, runs) = self.bidi.visual_runs(range);
+        let mut glyphs = Vec::new();
+        for run in runs {
+            let mut pieces: Vec<_> = self.pieces(run.clone()).collect();
+            if levels[run.start].is_rtl() {
+                pieces.reverse();
+            }
+            for (piece, style) in pieces {
+                glyphs.extend(self.shape(piece, style));
+            }
+        }
+        glyphs
+    }
+
+    /// The parts of `range` in each style run.
+    fn pieces(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (Range<usize>, RunStyle<'a>)> + '_ {
+        self.runs.iter().filter_map(move |(run, style)| {
+            let piece = run.start.max(range.start)..run.end.min(range.end);
+            (piece.start < piece.end).then_some((piece, *style))
+        })
+    }
+
+    fn shape(&self, range: Range<usize>, style: RunStyle<'a>) -> Vec<Glyph<'a>> {
+        let face = rustybuzz::Face::from_slice(&style.face.data, 0)
+            .expect("font faces are validated when loaded");
+        let rtl = self.bidi.levels[range.start].is_rtl();
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(&self.text[range.clone()]);
+        buffer.set_direction(if rtl {
+            Direction::RightToLeft
+        } else {
+            Direction::LeftToRight
+        });
+        let shaped = rustybuzz::shape(&face, &[], buffer);
+        let scale = style.size / face.units_per_em() as f32;
+        let infos = shaped.glyph_infos();
+        let extra = self.spacing + style.embolden as f32;
+        infos
+            .iter()
+            .zip(shaped.glyph_positions())
+            .enumerate()
+            .map(|(i, (info, pos))| {
+                let cluster_end = infos.get(i + 1).is_none_or(|n| n.cluster != info.cluster);
+                Glyph {
+                    face: style.face,
+                    id: info.glyph_id as u16,
+                    size: style.size,
+                    rgba: style.rgba,
+                    embolden: style.embolden,
+                    x_offset: pos.x_offset as f32 * scale,
+                    y_offset: pos.y_offset as f32 * scale,
+                    advance: pos.x_advance as f32 * scale + if cluster_end { extra } else { 0.0 },
+                    cluster: range.start + info.cluster as usize,
+                    units: [None; 3],
+                }
+            })
+            .collect()
+    }
+
+    /// Break into lines no wider than `max_width` at line break opportunities, and
+    /// between graphemes of a word too wide for any line. Whitespace at a break is
+    /// dropped.
+    fn wrap(&self, max_width: f32) -> Vec<Range<usize>> {
+        let fits = |width: f32| width.round() <= max_width;
+        let mut lines = Vec::new();
+        let mut line_start = 0;
+        let mut line_end = 0;
+        let mut segment_start = 0;
+        for (pos, _) in linebreaks(self.text) {
+            let trimmed = segment_start + self.text[segment_start..pos].trim_end().len();
+            let segment = segment_start..pos;
+            segment_start = pos;
+            if trimmed == segment.start {
+                continue;
+            }
+            if line_end > line_start && !fits(self.width(line_start..trimmed)) {
+                lines.push(line_start..line_end);
+                line_start = segment.start;
+            }
+            if !fits(self.width(line_start..trimmed)) {
+                // Too wide even alone: fill lines grapheme by grapheme.
+                for (offset, grapheme) in self.text[line_start..trimmed].grapheme_indices(true) {
+                    let end = line_start + offset + grapheme.len();
+                    if line_end > line_start && !fits(self.width(line_start..end)) {
+                        lines.push(line_start..line_end);
+                        line_start = line_end;
+                    }
+                    line_end = end;
+                }
+            }
+            line_end = trimmed;
+        }
+        if line_end > line_start || lines.is_empty() {
+            lines.push(line_start..line_end);
+        }
+        lines
+    }
+}
+
+/// Resolve every character's style and break the text into lines.
+fn layout<'a>(fonts: &'a FontBook, block: &TextBlock, opacity: f32) -> Vec<Line<'a>> {
+    let TextBlock {
+        font_family,
+        font_size,
+        color,
+        style,
+        ..
+    } = *block;
+    let line_height = style.line_height() as f32;
+    let mut lines = Vec::new();
+    let mut index = 0;
+    let (mut chars, mut words) = (0, 0);
+    for text in block.text.split('\n') {
+        let mut runs: Vec<(Range<usize>, RunStyle)> = Vec::new();
+        // Byte offset of each grapheme with its character and word, if it has them.
+        let mut units: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+        let mut in_word = false;
+        for (offset, grapheme) in text.grapheme_indices(true) {
+            if grapheme.trim().is_empty() {
+                in_word = false;
+                units.push((offset, None, None));
+            } else {
+                if !in_word {
+                    (in_word, words) = (true, words + 1);
+                }
+                units.push((offset, Some(chars), Some(words - 1)));
+                chars += 1;
+            }
+
+            let first = index + text[..offset].chars().count();
+            let (mut family, mut size, mut color, mut bold) =
+                (font_family, font_size, *color, style.bold);
+            for span in style
+                .spans
+                .iter()
+                .filter(|s| (s.start..s.end).contains(&first))
+            {
+                family = span.font_family.as_deref().unwrap_or(family);
+                size = span.size.map_or(size, |s| s as f32);
+                color = span.color.unwrap_or(color);
+                bold = span.bold.unwrap_or(bold);
+            }
+            let bold_face = bold.then(|| fonts.bold(family)).flatten();
+            let preferred = bold_face.unwrap_or_else(|| fonts.get(family));
+            color.a *= opacity;
+            let run = RunStyle {
+                face: fonts.fallback(preferred, grapheme),
+                size,
+                rgba: color.to_rgba8(),
+                embolden: if bold && bold_face.is_none() {
+                    (size / 24.0).round().max(1.0) as usize
+                } else {
+                    0
+                },
+            };
+            let range = offset..offset + grapheme.len();
+            match runs.last_mut() {
+                Some((last, last_style)) if last_style.same(&run) => last.end = range.end,
+                _ => runs.push((range, run)),
+            }
+        }
+        index += text.chars().count() + 1;
+
+        let bidi = ParagraphBidiInfo::new(text, None);
+        let paragraph = Paragraph {
+            text,
+            runs: split_at_level_changes(runs, text, &bidi.levels),
+            bidi,
+            spacing: style.letter_spacing as f32,
+        };
+        let ranges = match style.max_width {
+            Some(max_width) => paragraph.wrap(max_width as f32),
+            None => std::iter::once(0..text.len()).collect(),
+        };
+        for range in ranges {
+            let width = if range.is_empty() {
+                0
+            } else {
+                paragraph.width(range.clone()).ceil() as i32
+            };
+            let mut glyphs = paragraph.line(range);
+            for glyph in &mut glyphs {
+                let at = units
+                    .partition_point(|u| u.0 <= glyph.cluster)
+                    .saturating_sub(1);
+                let (_, char, word) = units[at];
+                glyph.units = [char, word, Some(lines.len())];
+            }
+            lines.push(measure(
+                glyphs,
+                width,
+                fonts.get(font_family),
+                font_size,
+                line_height,
+            ));
+        }
+    }
+    lines
+}
+
+/// Split style runs wherever the bidi embedding level changes, so that each run is
+/// shaped in one direction.
+fn split_at_level_changes<'a>(
+    runs: Vec<(Range<usize>, RunStyle<'a>)>,
+    text: &str,
+    levels: &[Level],
+) -> Vec<(Range<usize>, RunStyle<'a>)> {
+    let mut split = Vec::with_capacity(runs.len());
+    for (run, style) in runs {
+        let mut start = run.start;
+        for (i, _) in text[run.clone()].char_indices().skip(1) {
+            let at = run.start + i;
+            if levels[at] != levels[start] {
+                split.push((start..at, style));
+                start = at;
+            }
+        }
+        split.push((start..run.end, style));
+    }
+    split
+}
+
+/// Vertical metrics of a line of glyphs. Empty lines take the metrics of a space.
+fn measure<'a>(
+    glyphs: Vec<Glyph<'a>>,
+    width: i32,
+    face: &FontFace,
+    font_size: f32,
+    line_height: f32,
+) -> Line<'a> {
+    let (mut ascent, mut descent, mut size) = (0, 0, font_size);
+    if glyphs.is_empty() {
+        let metrics = face.font.metrics(' ', font_size);
+        (ascent, descent) = (metrics.height as i32 + metrics.ymin, -metrics.ymin);
+    } else {
+        size = glyphs.iter().map(|g| g.size).fold(0.0, f32::max);
+    }
+    for glyph in &glyphs {
+        let metrics = glyph.face.font.metrics_indexed(glyph.id, glyph.size);
+        let raise = glyph.y_offset.round() as i32;
+        ascent = ascent.max(metrics.height as i32 + metrics.ymin + raise);
+        descent = descent.max(-metrics.ymin - raise);
+    }
+    Line {
+        glyphs,
+        width,
+        ascent,
+        descent,
+        pitch: (size * line_height) as i32,
+    }
+}
+
+/// Grow `coverage` outwards by `radius` pixels, with an anti-aliased edge.
+fn dilate(coverage: &[u8], width: u32, height: u32, radius: f64) -> Vec<u8> {
+    let reach = radius.ceil() as i32;
+    let kernel: Vec<(i32, i32, f64)> = (-reach..=reach)
+        .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+        .filter_map(|(dx, dy)| {
+            let weight = (radius + 0.5 - f64::from(dx).hypot(f64::from(dy))).clamp(0.0, 1.0);
+            (weight > 0.0).then_some((dx, dy, weight))
+        })
+        .collect();
+    let (w, h) = (width as i32, height as i32);
+    let mut out = vec![0u8; coverage.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let mut best = 0.0f64;
+            for &(dx, dy, weight) in &kernel {
+                let (sx, sy) = (x + dx, y + dy);
+                if sx >= 0 && sx < w && sy >= 0 && sy < h {
+                    best = best.max(coverage[(sy * w + sx) as usize] as f64 * weight);
+                }
+            }
+            out[(y * w + x) as usize] = best.round() as u8;
+        }
+    }
+    out
+}
+
+/// A buffer of `color` whose alpha follows `coverage`.
+fn tint(coverage: &[u8], width: u32, height: u32, color: &Color, opacity: f32) -> FrameBuffer {
+    let mut color = *color;
+    color.a *= opacity;
+    let [r, g, b, a] = color.to_rgba8();
+    let mut fb = FrameBuffer::new(width, height, PixelFormat::Rgba8);
+    for (px, &c) in fb.data.chunks_exact_mut(4).zip(coverage) {
+        px.copy_from_slice(&[r, g, b, ((c as u32 * a as u32 + 127) / 255) as u8]);
+    }
+    fb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_core::types::Easing;
+    use vidra_ir::animation::{AnimatableProperty, Animation};
+    use vidra_ir::text::{TextShadow, TextSpan, TextStroke, TextUnit};
+
+    fn fonts() -> FontBook {
+        let inter = include_bytes!("../../vidra-render/assets/Inter-Regular.ttf");
+        FontBook::new(FontFace::from_bytes(inter.as_slice()).unwrap())
+    }
+
+    fn block<'a>(text: &'a str, style: &'a TextStyle) -> TextBlock<'a> {
+        TextBlock {
+            text,
+            font_family: "Inter",
+            font_size: 24.0,
+            color: &Color::WHITE,
+            style,
+            animators: &[],
+        }
+    }
+
+    fn render(text: &str, style: &TextStyle) -> FrameBuffer {
+        render_text(&fonts(), &block(text, style), Duration::zero(), 1.0)
+    }
+
+    fn rows_with_ink(fb: &FrameBuffer) -> usize {
+        (0..fb.height)
+            .filter(|&y| (0..fb.width).any(|x| fb.get_pixel(x, y).is_some_and(|p| p[3] > 0)))
+            .count()
+    }
+
+    #[test]
+    fn test_wrap_to_max_width() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let plain = render(text, &TextStyle::default());
+        let boxed = render(
+            text,
+            &TextStyle {
+                max_width: Some(160.0),
+                ..TextStyle::default()
+            },
+        );
+        assert_eq!(boxed.width, 160);
+        assert!(plain.width > 160);
+        assert!(boxed.height >= plain.height * 3, "wraps onto several lines");
+
+        // A single word wider than the box is split between characters.
+        let word = render(
+            "Supercalifragilistic",
+            &TextStyle {
+                max_width: Some(60.0),
+                ..TextStyle::default()
+            },
+        );
+        assert_eq!(word.width, 60);
+        assert!(word.height > plain.height * 2);
+    }
+
+    #[test]
+    fn test_spacing_and_spans() {
+        let plain = render("Hello", &TextStyle::default());
+        let spaced = render(
+            "Hello",
+            &TextStyle {
+                letter_spacing: 4.0,
+                ..TextStyle::default()
+            },
+        );
+        assert_eq!(spaced.width, plain.width + 16);
+
+        let tall = render(
+            "a\nb",
+            &TextStyle {
+                line_height: Some(3.0),
+                ..TextStyle::default()
+            },
+        );
+        assert!(tall.height >= 72);
+
+        let styled = render(
+            "Hello",
+            &TextStyle {
+                spans: vec![TextSpan {
+                    start: 1,
+                    end: 3,
+                    size: Some(48.0),
+                    bold: Some(true),
+                    color: Some(Color::RED),
+                    ..TextSpan::default()
+                }],
+                ..TextStyle::default()
+            },
+        );
+        assert!(styled.width > plain.width && styled.height > plain.height);
+        let red = styled
+            .data
+            .chunks_exact(4)
+            .any(|p| p[3] > 0 && p[0] > 0 && p[1] == 0);
+        assert!(red, "span color applies");
+    }
+
+    #[test]
+    fn test_stroke_and_shadow() {
+        let plain = render("Hi", &TextStyle::default());
+        let stroked = render(
+            "Hi",
+            &TextStyle {
+                stroke: Some(TextStroke {
+                    color: Color::BLACK,
+                    width: 3.0,
+                }),
+                ..TextStyle::default()
+            },
+        );
+        // Padded symmetrically, with the outline showing around the white fill.
+        assert_eq!(stroked.width - plain.width, stroked.height - plain.height);
+        assert!(rows_with_ink(&stroked) > rows_with_ink(&plain));
+        let black = stroked
+            .data
+            .chunks_exact(4)
+            .any(|p| p[3] == 255 && p[0] == 0);
+        let white = stroked
+            .data
+            .chunks_exact(4)
+            .any(|p| p[3] == 255 && p[0] == 255);
+        assert!(black && white);
+
+        let shadowed = render(
+            "Hi",
+            &TextStyle {
+                shadow: Some(TextShadow {
+                    color: Color::BLACK,
+                    offset_x: 0.0,
+                    offset_y: 6.0,
+                    blur: 0.0,
+                }),
+                ..TextStyle::default()
+            },
+        );
+        assert_eq!(rows_with_ink(&shadowed), rows_with_ink(&plain) + 6);
+    }
+
+    #[test]
+    fn test_shaping_bidi_and_fallback() {
+        // Kerning pulls "AV" closer than its letters set apart.
+        let plain = TextStyle::default();
+        let (a, v, av) = (
+            render("A", &plain),
+            render("V", &plain),
+            render("AV", &plain),
+        );
+        assert!(av.width < a.width + v.width);
+
+        // A right-to-left override draws "abc" as "cba".
+        let reversed = render("\u{202E}abc\u{202C}", &plain);
+        let cba = render("cba", &plain);
+        assert_eq!((reversed.width, reversed.height), (cba.width, cba.height));
+        assert_eq!(reversed.data, cba.data);
+
+        // Characters the family lacks come from the other loaded fonts.
+        let mut fonts = fonts();
+        let roboto = include_bytes!("../../../fonts/Roboto-Regular.ttf");
+        fonts.insert("Roboto", FontFace::from_bytes(roboto.as_slice()).unwrap());
+        let inter = fonts.get("Inter");
+        assert!(!inter.covers('ɕ'));
+        assert!(std::ptr::eq(
+            fonts.fallback(inter, "ɕ"),
+            fonts.get("Roboto")
+        ));
+        assert!(std::ptr::eq(fonts.fallback(inter, "e\u{301}"), inter));
+    }
+
+    #[test]
+    fn test_wrap_at_graphemes() {
+        // Combining marks stay with their base letter when a word is split.
+        let style = TextStyle {
+            max_width: Some(30.0),
+            ..TextStyle::default()
+        };
+        let fonts = fonts();
+        let text = "e\u{301}e\u{301}e\u{301}e\u{301}";
+        let glyphs = |style: &TextStyle| {
+            let lines = layout(&fonts, &block(text, style), 1.0);
+            (
+                lines.len(),
+                lines.iter().map(|l| l.glyphs.len()).sum::<usize>(),
+            )
+        };
+        // A mark split from its letter would be shaped on a dotted circle of its own.
+        let (lines, wrapped) = glyphs(&style);
+        assert!(lines > 1);
+        assert_eq!(wrapped, glyphs(&TextStyle::default()).1);
+    }
+
+    #[test]
+    fn test_animators_stagger_words() {
+        let style = TextStyle::default();
+        let second = Duration::from_seconds(1.0);
+        let fade_in = [TextAnimator {
+            unit: TextUnit::Word,
+            animations: vec![Animation::from_to(
+                AnimatableProperty::Opacity,
+                0.0,
+                1.0,
+                second,
+                Easing::Linear,
+            )],
+            stagger: 1.0,
+            range: Default::default(),
+        }];
+        let at = |animators: &[TextAnimator], secs: f64| {
+            let block = TextBlock {
+                animators,
+                ..block("Hi there", &style)
+            };
+            render_text(&fonts(), &block, Duration::from_seconds(secs), 1.0)
+        };
+        let plain = render("Hi there", &style);
+        let ink_columns = |fb: &FrameBuffer| {
+            (0..fb.width)
+                .filter(|&x| (0..fb.height).any(|y| fb.get_pixel(x, y).is_some_and(|p| p[3] > 0)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(rows_with_ink(&at(&fade_in, 0.0)), 0);
+        // The first word has arrived, the second is waiting its turn.
+        let half = at(&fade_in, 1.0);
+        assert_eq!((half.width, half.height), (plain.width, plain.height));
+        let (shown, all) = (ink_columns(&half), ink_columns(&plain));
+        assert!(!shown.is_empty() && shown.len() < all.len());
+        assert_eq!(shown, all[..shown.len()]);
+        // Done, every glyph is drawn exactly as without the animator.
+        assert_eq!(at(&fade_in, 2.0).data, plain.data);
+
+        // Moving units pads the buffer so they are not clipped.
+        let rise = [TextAnimator {
+            unit: TextUnit::Char,
+            animations: vec![Animation::from_to(
+                AnimatableProperty::PositionY,
+                20.0,
+                0.0,
+                second,
+                Easing::EaseOut,
+            )],
+            ..Default::default()
+        }];
+        let start = at(&rise, 0.0);
+        assert_eq!(start.height, plain.height + 40);
+        assert_eq!(rows_with_ink(&start), rows_with_ink(&plain));
+        let ink_top = |fb: &FrameBuffer| {
+            (0..fb.height)
+                .find(|&y| (0..fb.width).any(|x| fb.get_pixel(x, y).is_some_and(|p| p[3] > 0)))
+                .unwrap()
+        };
+        assert_eq!(ink_top(&start), ink_top(&at(&rise, 1.0)) + 20);
+    }
+}
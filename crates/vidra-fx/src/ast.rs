@@ -1,7 +1,16 @@
 use crate::lexer::Span;
 
+/// Whether a definition filters one layer (`@effect`) or blends two scenes
+/// (`@transition`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectKind {
+    Effect,
+    Transition,
+}
+
 #[derive(Debug, Clone)]
 pub struct EffectDef {
+    pub kind: EffectKind,
     pub name: String,
     pub params: Vec<Param>,
     pub body: Vec<Statement>,
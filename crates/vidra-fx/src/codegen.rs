@@ -13,8 +13,9 @@ impl Codegen {
     }
 
     pub fn generate(&mut self, ast: &EffectDef) -> Result<String, VidraError> {
-        self.wgsl.push_str(
-            "
+        match ast.kind {
+            EffectKind::Effect => self.wgsl.push_str(
+                "
 // VidraFX Generated Shader
 @group(0) @binding(0) var t_in: texture_2d<f32>;
 @group(0) @binding(1) var t_out: texture_storage_2d<rgba8unorm, write>;
@@ -23,7 +24,20 @@ struct Params {
     effect_type: u32,
     time: f32,
 ",
-        );
+            ),
+            // `from()` is the outgoing scene and `to()` the incoming one.
+            EffectKind::Transition => self.wgsl.push_str(
+                "
+// VidraFX Generated Transition
+@group(0) @binding(0) var t_from: texture_2d<f32>;
+@group(0) @binding(1) var t_out: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(3) var t_to: texture_2d<f32>;
+
+struct Params {
+    progress: f32,
+",
+            ),
+        }
         for (_i, param) in ast.params.iter().enumerate() {
             self.wgsl.push_str(&format!("    p_{}: f32,\n", param.name));
         }
@@ -73,12 +87,29 @@ fn fbm(uv: vec2<f32>) -> f32 {
 
 @compute @workgroup_size(16, 16)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let size = textureDimensions(t_in);
+");
+        let input = match ast.kind {
+            EffectKind::Effect => "t_in",
+            EffectKind::Transition => "t_from",
+        };
+        self.wgsl.push_str(&format!(
+            "    let size = textureDimensions({});
     let coords = vec2<i32>(global_id.xy);
-    if (coords.x >= i32(size.x) || coords.y >= i32(size.y)) { return; }
-    
+    if (coords.x >= i32(size.x) || coords.y >= i32(size.y)) {{ return; }}
+
     let uv = vec2<f32>(coords) / vec2<f32>(size);
-");
+",
+            input
+        ));
+        if ast.kind == EffectKind::Transition {
+            self.wgsl.push_str("    let progress = params.progress;\n");
+        }
+        for param in &ast.params {
+            self.wgsl.push_str(&format!(
+                "    let {} = params.p_{};\n",
+                param.name, param.name
+            ));
+        }
 
         // Body
         let mut last_expr = String::new();
@@ -178,6 +209,8 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
         match name {
             "source" => Ok("textureLoad(t_in, coords, 0)".to_string()),
+            "from" => Ok("textureLoad(t_from, coords, 0)".to_string()),
+            "to" => Ok("textureLoad(t_to, coords, 0)".to_string()),
             "noise" => {
                 let scale = arg_strs.get(0).unwrap_or(&"1.0".to_string()).clone();
                 let speed = arg_strs.get(1).unwrap_or(&"1.0".to_string()).clone();
@@ -22,6 +22,7 @@ impl Span {
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
     Effect,
+    Transition,
     Let,
     Pipe, // ->
 
@@ -50,6 +51,7 @@ impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TokenKind::Effect => write!(f, "@effect"),
+            TokenKind::Transition => write!(f, "@transition"),
             TokenKind::Let => write!(f, "let"),
             TokenKind::Pipe => write!(f, "->"),
             TokenKind::Identifier(s) => write!(f, "{}", s),
@@ -223,6 +225,8 @@ impl Lexer {
                 let ident = self.read_while(|c| c.is_alphabetic());
                 if ident == "effect" {
                     TokenKind::Effect
+                } else if ident == "transition" {
+                    TokenKind::Transition
                 } else {
                     return Err(vidra_core::VidraError::parse(
                         format!("Unknown decorator: @{}", ident),
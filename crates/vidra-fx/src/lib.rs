@@ -18,6 +18,38 @@ pub fn compile(src: &str) -> Result<String, VidraError> {
     Ok(wgsl)
 }
 
+/// A VidraFX `@transition` compiled to WGSL.
+#[derive(Debug, Clone)]
+pub struct CompiledTransition {
+    pub wgsl: String,
+    /// The transition's parameters, in the order the shader's uniforms declare them.
+    pub params: Vec<ast::Param>,
+}
+
+/// Compile the source of a VidraFX `@transition` into a WGSL compute shader that reads
+/// the outgoing scene at binding 0 and the incoming scene at binding 3, writes binding 1,
+/// and takes `progress` followed by the parameters as uniforms at binding 2.
+pub fn compile_transition(src: &str) -> Result<CompiledTransition, VidraError> {
+    let mut lexer = lexer::Lexer::new(src);
+    let tokens = lexer.tokenize()?;
+    let mut parser = parser::Parser::new(tokens, src);
+    let ast = parser.parse()?;
+    if ast.kind != ast::EffectKind::Transition {
+        return Err(VidraError::parse(
+            format!("'{}' is an @effect, not a @transition", ast.name),
+            src,
+            ast.span.line,
+            ast.span.column,
+        ));
+    }
+
+    let wgsl = codegen::Codegen::new().generate(&ast)?;
+    Ok(CompiledTransition {
+        wgsl,
+        params: ast.params,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,4 +91,24 @@ mod tests {
         assert!(wgsl.contains("textureLoad"));
         assert!(wgsl.contains("textureStore"));
     }
+
+    #[test]
+    fn test_compile_transition_binds_both_scenes() {
+        let src = r#"
+        @transition softWipe(softness: 0.1) {
+            blend(from(), to(), progress)
+        }
+        "#;
+
+        let compiled = compile_transition(src).expect("Compilation failed");
+        assert!(compiled.wgsl.contains("var t_from"));
+        assert!(compiled.wgsl.contains("@binding(3) var t_to"));
+        assert!(compiled.wgsl.contains("let softness = params.p_softness;"));
+        assert!(compiled.wgsl.contains(
+            "mix(textureLoad(t_from, coords, 0), textureLoad(t_to, coords, 0), progress)"
+        ));
+        assert_eq!(compiled.params[0].default_value, Some(0.1));
+
+        assert!(compile_transition("@effect basic() { source() }").is_err());
+    }
 }
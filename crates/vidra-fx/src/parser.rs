@@ -50,7 +50,22 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_effect_def(&mut self) -> Result<EffectDef, VidraError> {
-        let start_span = self.expect(TokenKind::Effect)?.span;
+        let start = self
+            .advance()
+            .ok_or_else(|| VidraError::parse("Unexpected end of input", self.source, 0, 0))?;
+        let kind = match start.kind {
+            TokenKind::Effect => EffectKind::Effect,
+            TokenKind::Transition => EffectKind::Transition,
+            other => {
+                return Err(VidraError::parse(
+                    format!("Expected @effect or @transition, found {:?}", other),
+                    self.source,
+                    start.span.line,
+                    start.span.column,
+                ))
+            }
+        };
+        let start_span = start.span;
 
         let name_token = self.advance().ok_or_else(|| {
             VidraError::parse(
@@ -104,6 +119,7 @@ impl<'a> Parser<'a> {
         let end_span = self.expect(TokenKind::RightBrace)?.span;
 
         Ok(EffectDef {
+            kind,
             name,
             params,
             body,
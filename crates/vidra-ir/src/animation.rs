@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use vidra_core::types::Easing;
+use vidra_core::{Color, ColorSpace};
 
 /// Identifies the property being animated.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,6 +29,58 @@ pub enum AnimatableProperty {
     Volume,
     BlurRadius,
     BrightnessLevel,
+    MaskX,
+    MaskY,
+    MaskWidth,
+    MaskHeight,
+    MaskFeather,
+    ShadowX,
+    ShadowY,
+    ShadowBlur,
+    ShadowSpread,
+    InnerShadowX,
+    InnerShadowY,
+    InnerShadowBlur,
+    InnerShadowSpread,
+    GlowBlur,
+    GlowSpread,
+    /// Width of a `Stroke` layer style (`StrokeWidth` is a shape's own stroke).
+    OutlineWidth,
+    BevelSize,
+    BevelDepth,
+    BevelAngle,
+    TrimStart,
+    TrimEnd,
+    TrimOffset,
+    /// Angle of a linear or conic gradient fill.
+    GradientAngle,
+    /// Offset of the gradient fill's color stop at this index.
+    GradientStop(usize),
+    /// Position as a point `[x, y]`. Keyframes may carry spatial tangents, making the
+    /// layer follow a curved motion path.
+    Position,
+    /// Scale as `[x, y]`.
+    Scale,
+    /// Fill color as `[r, g, b, a]` (0–1), interpolated in the animation's color space.
+    Color,
+    /// Start of a text animator's range (see [`crate::text::TextRange`]). Only
+    /// meaningful in a [`crate::text::TextAnimator`]'s animations.
+    TextRangeStart,
+    /// End of a text animator's range.
+    TextRangeEnd,
+    /// Offset of a text animator's range.
+    TextRangeOffset,
+}
+
+impl AnimatableProperty {
+    /// Whether keyframes of this property hold a vector (`Keyframe::vector`) rather than a
+    /// single value.
+    pub fn is_vector(&self) -> bool {
+        matches!(
+            self,
+            AnimatableProperty::Position | AnimatableProperty::Scale | AnimatableProperty::Color
+        )
+    }
 }
 
 impl std::fmt::Display for AnimatableProperty {
@@ -57,19 +110,66 @@ impl std::fmt::Display for AnimatableProperty {
             AnimatableProperty::Volume => write!(f, "volume"),
             AnimatableProperty::BlurRadius => write!(f, "blurRadius"),
             AnimatableProperty::BrightnessLevel => write!(f, "brightnessLevel"),
+            AnimatableProperty::MaskX => write!(f, "mask.x"),
+            AnimatableProperty::MaskY => write!(f, "mask.y"),
+            AnimatableProperty::MaskWidth => write!(f, "maskWidth"),
+            AnimatableProperty::MaskHeight => write!(f, "maskHeight"),
+            AnimatableProperty::MaskFeather => write!(f, "maskFeather"),
+            AnimatableProperty::ShadowX => write!(f, "shadow.x"),
+            AnimatableProperty::ShadowY => write!(f, "shadow.y"),
+            AnimatableProperty::ShadowBlur => write!(f, "shadowBlur"),
+            AnimatableProperty::ShadowSpread => write!(f, "shadowSpread"),
+            AnimatableProperty::InnerShadowX => write!(f, "innerShadow.x"),
+            AnimatableProperty::InnerShadowY => write!(f, "innerShadow.y"),
+            AnimatableProperty::InnerShadowBlur => write!(f, "innerShadowBlur"),
+            AnimatableProperty::InnerShadowSpread => write!(f, "innerShadowSpread"),
+            AnimatableProperty::GlowBlur => write!(f, "glowBlur"),
+            AnimatableProperty::GlowSpread => write!(f, "glowSpread"),
+            AnimatableProperty::OutlineWidth => write!(f, "outlineWidth"),
+            AnimatableProperty::BevelSize => write!(f, "bevelSize"),
+            AnimatableProperty::BevelDepth => write!(f, "bevelDepth"),
+            AnimatableProperty::BevelAngle => write!(f, "bevelAngle"),
+            AnimatableProperty::TrimStart => write!(f, "trimStart"),
+            AnimatableProperty::TrimEnd => write!(f, "trimEnd"),
+            AnimatableProperty::TrimOffset => write!(f, "trimOffset"),
+            AnimatableProperty::GradientAngle => write!(f, "gradientAngle"),
+            AnimatableProperty::GradientStop(index) => write!(f, "stop{}", index),
+            AnimatableProperty::Position => write!(f, "position"),
+            AnimatableProperty::Scale => write!(f, "scale"),
+            AnimatableProperty::Color => write!(f, "color"),
+            AnimatableProperty::TextRangeStart => write!(f, "rangeStart"),
+            AnimatableProperty::TextRangeEnd => write!(f, "rangeEnd"),
+            AnimatableProperty::TextRangeOffset => write!(f, "rangeOffset"),
         }
     }
 }
 
 /// A keyframe: a value at a specific time.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Keyframe {
     /// Time offset from the start of the animation.
     pub time: vidra_core::Duration,
     /// Target value at this keyframe.
+    #[serde(default)]
     pub value: f64,
+    /// Target value of a vector property (see [`AnimatableProperty::is_vector`]), used
+    /// instead of `value`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vector: Vec<f64>,
     /// Easing function to use when interpolating TO this keyframe.
     pub easing: Easing,
+    /// Hold interpolation: keep this keyframe's value until the next keyframe, then
+    /// jump to it. The next keyframe's easing is then unused.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hold: bool,
+    /// Spatial tangent of a `Position` keyframe towards the previous keyframe, relative to
+    /// its point: the second control point of the incoming motion path segment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_tangent: Option<[f64; 2]>,
+    /// Spatial tangent of a `Position` keyframe towards the next keyframe, relative to its
+    /// point: the first control point of the outgoing motion path segment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub out_tangent: Option<[f64; 2]>,
 }
 
 impl Keyframe {
@@ -77,18 +177,294 @@ impl Keyframe {
         Self {
             time,
             value,
+            vector: Vec::new(),
             easing: Easing::Linear,
+            hold: false,
+            in_tangent: None,
+            out_tangent: None,
         }
     }
 
+    /// A keyframe of a vector property.
+    pub fn vector(time: vidra_core::Duration, vector: Vec<f64>) -> Self {
+        Self {
+            vector,
+            ..Self::new(time, 0.0)
+        }
+    }
+
+    /// Set the spatial tangents of a `Position` keyframe.
+    pub fn with_tangents(mut self, in_tangent: [f64; 2], out_tangent: [f64; 2]) -> Self {
+        self.in_tangent = Some(in_tangent);
+        self.out_tangent = Some(out_tangent);
+        self
+    }
+
     pub fn with_easing(mut self, easing: Easing) -> Self {
         self.easing = easing;
         self
     }
+
+    /// Hold this keyframe's value until the next keyframe.
+    pub fn with_hold(mut self) -> Self {
+        self.hold = true;
+        self
+    }
+}
+
+/// A damped spring: `mass * x'' + damping * x' + stiffness * x = 0`, where `x` is the
+/// distance from the animation's last keyframe value.
+///
+/// The motion is evaluated in closed form, so it is exact at any time and frame rate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Spring {
+    pub mass: f64,
+    pub stiffness: f64,
+    pub damping: f64,
+    /// Velocity at the start, in property units per second.
+    #[serde(default)]
+    pub initial_velocity: f64,
+}
+
+/// The three solutions of the spring equation.
+enum SpringMotion {
+    /// Oscillates at `omega` inside an envelope decaying at `decay`.
+    Underdamped { decay: f64, omega: f64 },
+    /// Returns as fast as possible without overshooting.
+    Critical { omega: f64 },
+    /// Returns without overshooting as the sum of two exponentials, `slow` and `fast`.
+    Overdamped { slow: f64, fast: f64 },
+}
+
+impl Spring {
+    /// Distance from rest below which, along with the speed, the spring counts as settled.
+    pub const REST_DELTA: f64 = 0.001;
+    /// Longest settle time. Springs with little or no damping stop here.
+    pub const MAX_SETTLE_SECONDS: f64 = 10.0;
+
+    pub fn new(mass: f64, stiffness: f64, damping: f64, initial_velocity: f64) -> Self {
+        Self {
+            mass,
+            stiffness,
+            damping,
+            initial_velocity,
+        }
+    }
+
+    fn motion(&self) -> Option<SpringMotion> {
+        if !(self.mass > 0.0 && self.stiffness > 0.0) {
+            return None;
+        }
+        let omega = (self.stiffness / self.mass).sqrt();
+        let zeta = self.damping / (2.0 * (self.stiffness * self.mass).sqrt());
+        Some(if (zeta - 1.0).abs() < 1e-6 {
+            SpringMotion::Critical { omega }
+        } else if zeta < 1.0 {
+            SpringMotion::Underdamped {
+                decay: zeta * omega,
+                omega: omega * (1.0 - zeta * zeta).sqrt(),
+            }
+        } else {
+            let root = (zeta * zeta - 1.0).sqrt();
+            SpringMotion::Overdamped {
+                slow: -omega * (zeta - root),
+                fast: -omega * (zeta + root),
+            }
+        })
+    }
+
+    /// Distance from rest `t` seconds after starting at distance `x0`.
+    pub fn displacement(&self, x0: f64, t: f64) -> f64 {
+        let v0 = self.initial_velocity;
+        match self.motion() {
+            None => 0.0,
+            Some(SpringMotion::Underdamped { decay, omega }) => {
+                let b = (v0 + decay * x0) / omega;
+                (-decay * t).exp() * (x0 * (omega * t).cos() + b * (omega * t).sin())
+            }
+            Some(SpringMotion::Critical { omega }) => {
+                (-omega * t).exp() * (x0 + (v0 + omega * x0) * t)
+            }
+            Some(SpringMotion::Overdamped { slow, fast }) => {
+                let a = (v0 - fast * x0) / (slow - fast);
+                a * (slow * t).exp() + (x0 - a) * (fast * t).exp()
+            }
+        }
+    }
+
+    /// Time after which a spring starting at distance `x0` stays within
+    /// [`Spring::REST_DELTA`] of rest, in both distance and speed.
+    ///
+    /// Both are bounded by a decaying envelope, and this is the time the envelope
+    /// falls below the threshold, capped at [`Spring::MAX_SETTLE_SECONDS`].
+    pub fn settle_time(&self, x0: f64) -> f64 {
+        let v0 = self.initial_velocity;
+        let eps = Self::REST_DELTA;
+        let t = match self.motion() {
+            None => 0.0,
+            Some(SpringMotion::Underdamped { decay, omega }) => {
+                if decay <= 0.0 {
+                    return Self::MAX_SETTLE_SECONDS;
+                }
+                // |x| <= c e^(-decay t) and |v| <= c sqrt(decay² + omega²) e^(-decay t).
+                let c = x0.hypot((v0 + decay * x0) / omega);
+                let speed = decay.hypot(omega);
+                (c * speed.max(1.0) / eps).ln() / decay
+            }
+            Some(SpringMotion::Critical { omega }) => {
+                // |x| and |v| are both at most (c0 + c1 t) e^(-omega t). Solve
+                // ln(c0 + c1 t) - omega t = ln(eps) for its last root with Newton's method,
+                // starting from a looser closed-form bound: the function is concave, so
+                // the iterates approach the root from above.
+                let b = v0 + omega * x0;
+                let c0 = x0.abs().max(v0.abs());
+                let c1 = b.abs() * omega.max(1.0);
+                let loose = c0 + 2.0 * c1 / (std::f64::consts::E * omega);
+                let mut t = 2.0 * (loose / eps).ln() / omega;
+                for _ in 0..16 {
+                    if t <= 0.0 {
+                        break;
+                    }
+                    let g = (c0 + c1 * t).ln() - omega * t - eps.ln();
+                    let slope = c1 / (c0 + c1 * t) - omega;
+                    let next = t - g / slope;
+                    if (next - t).abs() < 1e-9 {
+                        t = next;
+                        break;
+                    }
+                    t = next;
+                }
+                t
+            }
+            Some(SpringMotion::Overdamped { slow, fast }) => {
+                let a = (v0 - fast * x0) / (slow - fast);
+                let b = x0 - a;
+                let c = (a.abs() + b.abs()).max((slow * a).abs() + (fast * b).abs());
+                (c / eps).ln() / -slow
+            }
+        };
+        t.clamp(0.0, Self::MAX_SETTLE_SECONDS)
+    }
+}
+
+/// Where an animation is at some time.
+enum Span {
+    /// Resting on keyframe `i`.
+    At(usize),
+    /// Between keyframes `i` and `i + 1`, with the eased progress from `i` to `i + 1`.
+    Between(usize, f64),
+}
+
+/// One cubic Bézier segment of a motion path, with a table for moving along it by distance.
+struct MotionSegment {
+    points: [[f64; 2]; 4],
+    /// Arc length at `u = k / SAMPLES`, for `k` in `0..=SAMPLES`.
+    lengths: [f64; MotionSegment::SAMPLES + 1],
+}
+
+impl MotionSegment {
+    const SAMPLES: usize = 64;
+
+    fn new(points: [[f64; 2]; 4]) -> Self {
+        let mut segment = Self {
+            points,
+            lengths: [0.0; Self::SAMPLES + 1],
+        };
+        let mut previous = points[0];
+        for k in 1..=Self::SAMPLES {
+            let p = segment.point(k as f64 / Self::SAMPLES as f64);
+            segment.lengths[k] =
+                segment.lengths[k - 1] + (p[0] - previous[0]).hypot(p[1] - previous[1]);
+            previous = p;
+        }
+        segment
+    }
+
+    fn point(&self, u: f64) -> [f64; 2] {
+        let [p0, p1, p2, p3] = self.points;
+        let v = 1.0 - u;
+        let (a, b, c, d) = (v * v * v, 3.0 * v * v * u, 3.0 * v * u * u, u * u * u);
+        [
+            a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+            a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+        ]
+    }
+
+    /// Curve parameter at `fraction` of the segment's length. Fractions outside [0, 1]
+    /// (from overshooting easings) extrapolate along the end tangents.
+    fn param_at_distance(&self, fraction: f64) -> f64 {
+        let total = self.lengths[Self::SAMPLES];
+        if total == 0.0 {
+            return fraction;
+        }
+        let target = fraction.clamp(0.0, 1.0) * total;
+        let k = self
+            .lengths
+            .partition_point(|&l| l < target)
+            .clamp(1, Self::SAMPLES);
+        let (l0, l1) = (self.lengths[k - 1], self.lengths[k]);
+        let within = if l1 > l0 {
+            (target - l0) / (l1 - l0)
+        } else {
+            0.0
+        };
+        (k as f64 - 1.0 + within) / Self::SAMPLES as f64
+    }
+
+    /// The point `fraction` of the way along the segment, measured by distance.
+    fn at_distance(&self, fraction: f64) -> [f64; 2] {
+        let [p0, p1, p2, p3] = self.points;
+        if p1 == p0 && p2 == p3 {
+            // No tangents: a straight line, exactly linear in distance.
+            return [
+                p0[0] + (p3[0] - p0[0]) * fraction,
+                p0[1] + (p3[1] - p0[1]) * fraction,
+            ];
+        }
+        let total = self.lengths[Self::SAMPLES];
+        let (u, overshoot) = match fraction {
+            f if f < 0.0 => (0.0, f),
+            f if f > 1.0 => (1.0, f - 1.0),
+            f => (self.param_at_distance(f), 0.0),
+        };
+        let p = self.point(u);
+        if overshoot == 0.0 {
+            return p;
+        }
+        match self.direction(u) {
+            Some([dx, dy]) => {
+                let scale = overshoot * total / dx.hypot(dy);
+                [p[0] + dx * scale, p[1] + dy * scale]
+            }
+            None => p,
+        }
+    }
+
+    /// Direction of travel at `u`, or None where the segment does not move.
+    fn direction(&self, u: f64) -> Option<[f64; 2]> {
+        // A control point on its end point gives a zero derivative there, so look a
+        // little way along the curve instead.
+        const STEP: f64 = 1e-3;
+        let (u0, u1) = ((u - STEP).max(0.0), (u + STEP).min(1.0));
+        let (a, b) = (self.point(u0), self.point(u1));
+        let d = [b[0] - a[0], b[1] - a[1]];
+        (d[0].hypot(d[1]) > 1e-12).then_some(d)
+    }
+}
+
+/// A color from `[r, g, b, a]`; missing channels are 0, and alpha 1.
+fn color_of(v: &[f64]) -> Color {
+    let channel = |i: usize, default: f64| v.get(i).copied().unwrap_or(default) as f32;
+    Color::rgba(
+        channel(0, 0.0),
+        channel(1, 0.0),
+        channel(2, 0.0),
+        channel(3, 1.0),
+    )
 }
 
 /// An animation definition: a property + keyframes.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Animation {
     /// The property being animated.
     pub property: AnimatableProperty,
@@ -105,6 +481,24 @@ pub struct Animation {
     /// explicit duration.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub expr_duration: Option<vidra_core::Duration>,
+    /// Audio asset (or path) whose envelopes drive the `audio_*` variables of `expr`.
+    ///
+    /// Without one, `audio_amp`, `audio_bass`, ... evaluate to 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expr_audio: Option<crate::asset::AssetId>,
+    /// Spring driving the value from the first keyframe to the last.
+    ///
+    /// The last keyframe sits at the spring's settle time, so [`Animation::duration`] covers
+    /// the whole motion; the keyframes' easings are unused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spring: Option<Spring>,
+    /// Color space of `Color` animations; OKLab when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_space: Option<ColorSpace>,
+    /// Rotate the layer to face along its motion path (`Position` animations only). The
+    /// angle is added to the layer's rotation.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_orient: bool,
     /// Delay before the animation starts (relative to scene start).
     pub delay: vidra_core::Duration,
 }
@@ -117,6 +511,10 @@ impl Animation {
             keyframes: Vec::new(),
             expr: None,
             expr_duration: None,
+            expr_audio: None,
+            spring: None,
+            color_space: None,
+            auto_orient: false,
             delay: vidra_core::Duration::zero(),
         }
     }
@@ -137,10 +535,39 @@ impl Animation {
             ],
             expr: None,
             expr_duration: None,
+            expr_audio: None,
+            spring: None,
+            color_space: None,
+            auto_orient: false,
             delay: vidra_core::Duration::zero(),
         }
     }
 
+    /// Create a simple "from → to" animation of a vector property.
+    pub fn from_to_vector(
+        property: AnimatableProperty,
+        from: Vec<f64>,
+        to: Vec<f64>,
+        duration: vidra_core::Duration,
+        easing: Easing,
+    ) -> Self {
+        let mut anim = Self::new(property);
+        anim.keyframes = vec![
+            Keyframe::vector(vidra_core::Duration::zero(), from),
+            Keyframe::vector(duration, to).with_easing(easing),
+        ];
+        anim
+    }
+
+    /// Create a spring animation from `from` to `to`, lasting until the spring settles.
+    pub fn spring(property: AnimatableProperty, from: f64, to: f64, spring: Spring) -> Self {
+        let settle = vidra_core::Duration::from_seconds(spring.settle_time(from - to));
+        Self {
+            spring: Some(spring),
+            ..Self::from_to(property, from, to, settle, Easing::Linear)
+        }
+    }
+
     /// Set the delay.
     pub fn with_delay(mut self, delay: vidra_core::Duration) -> Self {
         self.delay = delay;
@@ -161,6 +588,101 @@ impl Animation {
     /// Evaluate the animation at a given time (relative to the animation start, after delay).
     /// Returns None if time is before the animation starts.
     pub fn evaluate(&self, time: vidra_core::Duration) -> Option<f64> {
+        let (span, secs) = self.locate(time)?;
+        let keyframes = &self.keyframes;
+        Some(match span {
+            Span::At(i) => keyframes[i].value,
+            Span::Between(..) if self.spring.is_some() => {
+                let (first, last) = (&keyframes[0], &keyframes[keyframes.len() - 1]);
+                let t = secs - first.time.as_seconds();
+                last.value
+                    + self
+                        .spring
+                        .unwrap()
+                        .displacement(first.value - last.value, t)
+            }
+            Span::Between(i, progress) => {
+                let (a, b) = (keyframes[i].value, keyframes[i + 1].value);
+                a + (b - a) * progress
+            }
+        })
+    }
+
+    /// Evaluate a vector property (see [`AnimatableProperty::is_vector`]) at a given time.
+    ///
+    /// `Position` follows the motion path set by the keyframes' spatial tangents, with the
+    /// easing spreading the distance travelled over time; `Color` is interpolated in the
+    /// animation's color space. Returns None if time is before the animation starts.
+    pub fn evaluate_vector(&self, time: vidra_core::Duration) -> Option<Vec<f64>> {
+        let (span, _) = self.locate(time)?;
+        let (i, progress) = match span {
+            Span::At(i) => return Some(self.keyframes[i].vector.clone()),
+            Span::Between(i, progress) => (i, progress),
+        };
+        let (a, b) = (&self.keyframes[i].vector, &self.keyframes[i + 1].vector);
+        Some(match self.property {
+            AnimatableProperty::Color => {
+                let space = self.color_space.unwrap_or_default();
+                let c = color_of(a).mix(&color_of(b), progress as f32, space);
+                vec![c.r as f64, c.g as f64, c.b as f64, c.a as f64]
+            }
+            AnimatableProperty::Position => {
+                let p = self.motion_segment(i).at_distance(progress);
+                p.to_vec()
+            }
+            _ => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a + (b - a) * progress)
+                .collect(),
+        })
+    }
+
+    /// Direction of travel of a `Position` animation at a given time, in degrees clockwise
+    /// from the +x axis. At a keyframe it is the direction of the segment leaving it (or,
+    /// at the last keyframe, arriving at it). None before the animation starts or when
+    /// there is no motion to follow.
+    pub fn orientation(&self, time: vidra_core::Duration) -> Option<f64> {
+        if self.property != AnimatableProperty::Position || self.keyframes.len() < 2 {
+            return None;
+        }
+        let (span, _) = self.locate(time)?;
+        let last = self.keyframes.len() - 2;
+        let (segment, u) = match span {
+            Span::At(i) if i > last => (last, 1.0),
+            Span::At(i) => (i, 0.0),
+            Span::Between(i, progress) => {
+                let segment = self.motion_segment(i);
+                (i, segment.param_at_distance(progress))
+            }
+        };
+        let [dx, dy] = self.motion_segment(segment).direction(u)?;
+        Some(dy.atan2(dx).to_degrees())
+    }
+
+    /// The motion path between keyframes `i` and `i + 1` of a `Position` animation.
+    fn motion_segment(&self, i: usize) -> MotionSegment {
+        let (a, b) = (&self.keyframes[i], &self.keyframes[i + 1]);
+        let point = |kf: &Keyframe| {
+            [
+                kf.vector.first().copied().unwrap_or(0.0),
+                kf.vector.get(1).copied().unwrap_or(0.0),
+            ]
+        };
+        let (start, end) = (point(a), point(b));
+        let out = a.out_tangent.unwrap_or([0.0, 0.0]);
+        let inn = b.in_tangent.unwrap_or([0.0, 0.0]);
+        MotionSegment::new([
+            start,
+            [start[0] + out[0], start[1] + out[1]],
+            [end[0] + inn[0], end[1] + inn[1]],
+            end,
+        ])
+    }
+
+    /// Find the keyframe or the eased progress between two keyframes at `time`, along
+    /// with the time in seconds since the animation started (after its delay).
+    fn locate(&self, time: vidra_core::Duration) -> Option<(Span, f64)> {
         if self.keyframes.is_empty() {
             return None;
         }
@@ -173,17 +695,17 @@ impl Animation {
 
         // Before first keyframe
         if effective_secs <= self.keyframes[0].time.as_seconds() {
-            return Some(self.keyframes[0].value);
+            return Some((Span::At(0), effective_secs));
         }
 
         // After last keyframe
-        let last = &self.keyframes[self.keyframes.len() - 1];
-        if effective_secs >= last.time.as_seconds() {
-            return Some(last.value);
+        let last = self.keyframes.len() - 1;
+        if effective_secs >= self.keyframes[last].time.as_seconds() {
+            return Some((Span::At(last), effective_secs));
         }
 
         // Find the two surrounding keyframes
-        for i in 0..self.keyframes.len() - 1 {
+        for i in 0..last {
             let kf_a = &self.keyframes[i];
             let kf_b = &self.keyframes[i + 1];
             let t_a = kf_a.time.as_seconds();
@@ -192,15 +714,19 @@ impl Animation {
             if effective_secs >= t_a && effective_secs <= t_b {
                 let segment_duration = t_b - t_a;
                 if segment_duration == 0.0 {
-                    return Some(kf_b.value);
+                    return Some((Span::At(i + 1), effective_secs));
+                }
+                if kf_a.hold {
+                    let held = if effective_secs < t_b { i } else { i + 1 };
+                    return Some((Span::At(held), effective_secs));
                 }
                 let local_t = (effective_secs - t_a) / segment_duration;
                 let eased_t = kf_b.easing.apply(local_t);
-                return Some(kf_a.value + (kf_b.value - kf_a.value) * eased_t);
+                return Some((Span::Between(i, eased_t), effective_secs));
             }
         }
 
-        Some(last.value)
+        Some((Span::At(last), effective_secs))
     }
 
     /// Get the total duration of the animation (from first to last keyframe).
@@ -324,4 +850,225 @@ mod tests {
         assert!((anim.keyframes[1].time.as_seconds() - 1.0).abs() < 0.001);
         assert!((anim.keyframes[2].time.as_seconds() - 2.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_animation_evaluate_hold() {
+        let mut anim = Animation::new(AnimatableProperty::PositionX);
+        anim.add_keyframe(Keyframe::new(vidra_core::Duration::zero(), 0.0));
+        anim.add_keyframe(Keyframe::new(vidra_core::Duration::from_seconds(1.0), 10.0).with_hold());
+        anim.add_keyframe(
+            Keyframe::new(vidra_core::Duration::from_seconds(2.0), 20.0)
+                .with_easing(Easing::EaseIn),
+        );
+
+        let at = |s: f64| {
+            anim.evaluate(vidra_core::Duration::from_seconds(s))
+                .unwrap()
+        };
+        assert!((at(0.5) - 5.0).abs() < 1e-9);
+        assert!((at(1.0) - 10.0).abs() < 1e-9);
+        assert!((at(1.99) - 10.0).abs() < 1e-9);
+        assert!((at(2.0) - 20.0).abs() < 1e-9);
+
+        let json = serde_json::to_value(&anim.keyframes[1]).unwrap();
+        assert_eq!(json["hold"], true);
+        assert!(serde_json::to_value(&anim.keyframes[0])
+            .unwrap()
+            .get("hold")
+            .is_none());
+    }
+
+    /// Integrate the spring equation with small RK4 steps.
+    fn integrate(spring: &Spring, x0: f64, t: f64) -> f64 {
+        let accel = |x: f64, v: f64| -(spring.stiffness * x + spring.damping * v) / spring.mass;
+        let steps = 20_000;
+        let dt = t / steps as f64;
+        let (mut x, mut v) = (x0, spring.initial_velocity);
+        for _ in 0..steps {
+            let (k1x, k1v) = (v, accel(x, v));
+            let (k2x, k2v) = (
+                v + k1v * dt / 2.0,
+                accel(x + k1x * dt / 2.0, v + k1v * dt / 2.0),
+            );
+            let (k3x, k3v) = (
+                v + k2v * dt / 2.0,
+                accel(x + k2x * dt / 2.0, v + k2v * dt / 2.0),
+            );
+            let (k4x, k4v) = (v + k3v * dt, accel(x + k3x * dt, v + k3v * dt));
+            x += (k1x + 2.0 * k2x + 2.0 * k3x + k4x) * dt / 6.0;
+            v += (k1v + 2.0 * k2v + 2.0 * k3v + k4v) * dt / 6.0;
+        }
+        x
+    }
+
+    #[test]
+    fn test_spring_closed_form_matches_integration() {
+        let springs = [
+            Spring::new(1.0, 100.0, 5.0, 0.0),   // underdamped
+            Spring::new(2.0, 50.0, 20.0, 30.0),  // critically damped
+            Spring::new(1.0, 40.0, 30.0, -80.0), // overdamped
+        ];
+        for spring in springs {
+            for t in [0.0, 0.1, 0.37, 1.0, 2.5] {
+                let exact = spring.displacement(-100.0, t);
+                let numeric = integrate(&spring, -100.0, t);
+                assert!(
+                    (exact - numeric).abs() < 1e-6,
+                    "{:?} at {}: {} vs {}",
+                    spring,
+                    t,
+                    exact,
+                    numeric
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_spring_settles() {
+        for spring in [
+            Spring::new(1.0, 100.0, 5.0, 0.0),
+            Spring::new(1.0, 100.0, 20.0, 0.0),
+            Spring::new(1.0, 40.0, 30.0, 50.0),
+        ] {
+            let settle = spring.settle_time(-100.0);
+            assert!(settle > 0.0 && settle < Spring::MAX_SETTLE_SECONDS);
+            for k in 0..100 {
+                let t = settle + k as f64 * 0.013;
+                let x = spring.displacement(-100.0, t);
+                let v = (spring.displacement(-100.0, t + 1e-6) - x) / 1e-6;
+                assert!(x.abs() < Spring::REST_DELTA && v.abs() < 2.0 * Spring::REST_DELTA);
+            }
+        }
+        assert_eq!(Spring::new(1.0, 100.0, 0.0, 0.0).settle_time(1.0), 10.0);
+        assert_eq!(Spring::new(1.0, 100.0, 5.0, 0.0).settle_time(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_spring_animation() {
+        let spring = Spring::new(1.0, 170.0, 26.0, 0.0);
+        let anim = Animation::spring(AnimatableProperty::PositionX, 0.0, 100.0, spring);
+        assert_eq!(anim.keyframes.len(), 2);
+        let settle = spring.settle_time(-100.0);
+        assert!((anim.duration().as_seconds() - settle).abs() < 1e-9);
+
+        let at = |s: f64| {
+            anim.evaluate(vidra_core::Duration::from_seconds(s))
+                .unwrap()
+        };
+        assert_eq!(at(0.0), 0.0);
+        assert!((at(0.2) - (100.0 + spring.displacement(-100.0, 0.2))).abs() < 1e-9);
+        assert_eq!(at(settle + 1.0), 100.0);
+
+        let json = serde_json::to_string(&anim).unwrap();
+        let back: Animation = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.spring, Some(spring));
+        assert!(
+            (back
+                .evaluate(vidra_core::Duration::from_seconds(0.2))
+                .unwrap()
+                - at(0.2))
+            .abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_vector_and_color_animations() {
+        let scale = Animation::from_to_vector(
+            AnimatableProperty::Scale,
+            vec![1.0, 2.0],
+            vec![3.0, 0.0],
+            vidra_core::Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        let at = |anim: &Animation, s: f64| {
+            anim.evaluate_vector(vidra_core::Duration::from_seconds(s))
+                .unwrap()
+        };
+        assert_eq!(at(&scale, 0.5), vec![2.0, 1.0]);
+        assert_eq!(at(&scale, 2.0), vec![3.0, 0.0]);
+
+        let mut color = Animation::from_to_vector(
+            AnimatableProperty::Color,
+            vec![1.0, 0.0, 0.0, 1.0],
+            vec![0.0, 1.0, 0.0, 0.5],
+            vidra_core::Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        let oklab = at(&color, 0.5);
+        color.color_space = Some(ColorSpace::Srgb);
+        let srgb = at(&color, 0.5);
+        assert!((srgb[0] - 0.5).abs() < 1e-6 && (srgb[1] - 0.5).abs() < 1e-6);
+        assert!(oklab[0] > srgb[0] && oklab[1] > srgb[1]);
+        assert!((oklab[3] - 0.75).abs() < 1e-6);
+
+        let json = serde_json::to_value(&color).unwrap();
+        assert_eq!(json["color_space"], "Srgb");
+        assert_eq!(json["keyframes"][1]["vector"][3], 0.5);
+        let back: Animation = serde_json::from_value(json).unwrap();
+        assert_eq!(at(&back, 0.5), srgb);
+    }
+
+    #[test]
+    fn test_motion_path_and_orientation() {
+        // A quarter circle from (100, 0) to (0, 100), clockwise on screen.
+        let k = 0.552_284_75 * 100.0;
+        let mut anim = Animation::new(AnimatableProperty::Position);
+        anim.add_keyframe(
+            Keyframe::vector(vidra_core::Duration::zero(), vec![100.0, 0.0])
+                .with_tangents([0.0, 0.0], [0.0, k]),
+        );
+        anim.add_keyframe(
+            Keyframe::vector(vidra_core::Duration::from_seconds(1.0), vec![0.0, 100.0])
+                .with_tangents([k, 0.0], [0.0, 0.0]),
+        );
+
+        let at = |s: f64| {
+            anim.evaluate_vector(vidra_core::Duration::from_seconds(s))
+                .unwrap()
+        };
+        let orientation = |s: f64| {
+            anim.orientation(vidra_core::Duration::from_seconds(s))
+                .unwrap()
+        };
+        // Halfway in time is halfway along the arc, at 45 degrees.
+        let mid = at(0.5);
+        let r = 100.0 * std::f64::consts::FRAC_1_SQRT_2;
+        assert!(
+            (mid[0] - r).abs() < 0.1 && (mid[1] - r).abs() < 0.1,
+            "{:?}",
+            mid
+        );
+        // Constant speed: a quarter of the time covers a quarter of the arc (22.5°).
+        let quarter = at(0.25);
+        let angle = quarter[1].atan2(quarter[0]).to_degrees();
+        assert!((angle - 22.5).abs() < 0.2, "{}", angle);
+        for radius in [quarter[0].hypot(quarter[1]), mid[0].hypot(mid[1])] {
+            assert!((radius - 100.0).abs() < 0.1);
+        }
+
+        // Moving down, then down-left, then left.
+        assert!((orientation(0.0) - 90.0).abs() < 0.5);
+        assert!((orientation(0.5) - 135.0).abs() < 0.5);
+        assert!((orientation(1.0) - 180.0).abs() < 0.5);
+        assert!((orientation(5.0) - 180.0).abs() < 0.5);
+
+        // Without tangents the path is a straight line.
+        let line = Animation::from_to_vector(
+            AnimatableProperty::Position,
+            vec![0.0, 0.0],
+            vec![100.0, -100.0],
+            vidra_core::Duration::from_seconds(1.0),
+            Easing::Linear,
+        );
+        let p = line
+            .evaluate_vector(vidra_core::Duration::from_seconds(0.3))
+            .unwrap();
+        assert!((p[0] - 30.0).abs() < 1e-6 && (p[1] + 30.0).abs() < 1e-6);
+        let heading = line
+            .orientation(vidra_core::Duration::from_seconds(0.3))
+            .unwrap();
+        assert!((heading + 45.0).abs() < 1e-6);
+    }
 }
@@ -22,6 +22,10 @@ impl std::fmt::Display for AssetId {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AssetType {
     Image,
+    /// A vector image, drawn at whatever size it is shown.
+    Svg,
+    /// A Lottie (Bodymovin JSON) animation.
+    Lottie,
     Video,
     Audio,
     Font,
@@ -33,6 +37,8 @@ impl std::fmt::Display for AssetType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             AssetType::Image => write!(f, "image"),
+            AssetType::Svg => write!(f, "svg"),
+            AssetType::Lottie => write!(f, "lottie"),
             AssetType::Video => write!(f, "video"),
             AssetType::Audio => write!(f, "audio"),
             AssetType::Font => write!(f, "font"),
@@ -1,11 +1,12 @@
 use crate::animation::{AnimatableProperty, Animation, Keyframe};
 use crate::asset::{Asset, AssetId, AssetRegistry, AssetType};
 use crate::layer::{Layer, LayerContent, LayerId};
+use crate::mask::MatteMode;
 use crate::project::{Project, ProjectSettings};
 use crate::scene::{Scene, SceneId};
 
 use vidra_core::types::Easing;
-use vidra_core::{BlendMode, Color, Duration, Point2D, Transform2D};
+use vidra_core::{BlendMode, Color, Duration, MotionBlur, Point2D, Transform2D};
 
 /// A builder for constructing a Vidra IR Project programmatically.
 /// Useful for SDKs, programmatic generation, and unit testing.
@@ -23,6 +24,7 @@ impl ProjectBuilder {
                     height,
                     fps,
                     background: Color::BLACK,
+                    motion_blur: None,
                 },
                 scenes: Vec::new(),
                 assets: AssetRegistry::new(),
@@ -47,6 +49,12 @@ impl ProjectBuilder {
         self
     }
 
+    /// Enable motion blur for every layer that does not override it.
+    pub fn motion_blur(&mut self, motion_blur: MotionBlur) -> &mut Self {
+        self.project.settings.motion_blur = Some(motion_blur);
+        self
+    }
+
     /// Add a scene to the project.
     pub fn add_scene(&mut self, scene: Scene) -> &mut Self {
         self.project.scenes.push(scene);
@@ -107,7 +115,11 @@ impl LayerBuilder {
                 visible: true,
                 children: Vec::new(),
                 mask: None,
+                matte_mode: MatteMode::Alpha,
+                shape_mask: None,
+                mask_feather: 0.0,
                 constraints: Vec::new(),
+                motion_blur: None,
             },
         }
     }
@@ -137,6 +149,12 @@ impl LayerBuilder {
         self
     }
 
+    /// Override the project's motion blur for this layer.
+    pub fn motion_blur(&mut self, motion_blur: MotionBlur) -> &mut Self {
+        self.layer.motion_blur = Some(motion_blur);
+        self
+    }
+
     /// Add a child layer.
     pub fn add_child(&mut self, child: Layer) -> &mut Self {
         self.layer.children.push(child);
@@ -168,17 +186,19 @@ impl AnimationBuilder {
                 keyframes: Vec::new(),
                 expr: None,
                 expr_duration: None,
+                expr_audio: None,
+                spring: None,
+                color_space: None,
+                auto_orient: false,
                 delay: Duration::zero(),
             },
         }
     }
 
     pub fn add_keyframe(&mut self, time: f64, value: f64, easing: Easing) -> &mut Self {
-        self.animation.keyframes.push(Keyframe {
-            time: Duration::from_seconds(time),
-            value,
-            easing,
-        });
+        self.animation
+            .keyframes
+            .push(Keyframe::new(Duration::from_seconds(time), value).with_easing(easing));
         self
     }
 
@@ -2,8 +2,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::animation::Animation;
 use crate::asset::AssetId;
+use crate::mask::{MatteMode, ShapeMask};
+use crate::text::{TextAnimator, TextStyle};
 use vidra_core::types::ShapeType;
-use vidra_core::{BlendMode, Color, Transform2D};
+use vidra_core::{BlendMode, Color, Paint, Transform2D};
 
 /// Mode for capturing a web scene.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -50,7 +52,7 @@ impl std::fmt::Display for LayerId {
 }
 
 /// The content of a layer — what it renders.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum LayerContent {
     /// A text layer.
     Text {
@@ -58,10 +60,36 @@ pub enum LayerContent {
         font_family: String,
         font_size: f64,
         color: Color,
+        /// Wrapping, spacing, inline spans and decoration. Plain text when default.
+        #[serde(default, skip_serializing_if = "TextStyle::is_plain")]
+        style: TextStyle,
+        /// Per-character, per-word or per-line animation, applied in order.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        animators: Vec<TextAnimator>,
     },
     /// An image layer referencing an asset.
     Image { asset_id: AssetId },
 
+    /// A vector image from an SVG asset, rasterized at the layer's scale every frame.
+    Svg {
+        asset_id: AssetId,
+        /// Draw only the element with this id, in a box around it. The whole document
+        /// when `None`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        element: Option<String>,
+        /// Ids of elements left out because child layers draw them.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        exclude: Vec<String>,
+        /// Turn the drawn element's `<g id>` groups into child layers when the project
+        /// is prepared for rendering.
+        #[serde(default)]
+        expand_groups: bool,
+    },
+
+    /// A Lottie animation from a JSON asset. Its layers are imported as children of
+    /// this one when the project is prepared for rendering; until then it draws nothing.
+    Lottie { asset_id: AssetId },
+
     /// A spritesheet layer (tile animation) referencing an image asset.
     ///
     /// The renderer selects a frame from the sheet over time based on `fps` and `start_frame`.
@@ -126,12 +154,12 @@ pub enum LayerContent {
     /// A geometric shape.
     Shape {
         shape: ShapeType,
-        fill: Option<Color>,
-        stroke: Option<Color>,
+        fill: Option<Paint>,
+        stroke: Option<Paint>,
         stroke_width: f64,
     },
-    /// A solid color fill.
-    Solid { color: Color },
+    /// A solid color or gradient fill.
+    Solid { color: Paint },
     /// A custom WGSL shader execution.
     Shader { asset_id: AssetId },
     /// A web scene captured via a headless browser.
@@ -145,6 +173,12 @@ pub enum LayerContent {
         #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
         variables: std::collections::HashMap<String, f64>,
     },
+    /// Content drawn by a registered `LayerPlugin`, looked up by name at render time.
+    Plugin {
+        name: String,
+        #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+        props: std::collections::HashMap<String, String>,
+    },
     /// An empty content block (useful for grouping layers into components).
     Empty,
 }
@@ -171,10 +205,23 @@ pub struct Layer {
     pub visible: bool,
     /// Child layers (for nesting / component hierarchy).
     pub children: Vec<Layer>,
-    /// Optional mask layer (alpha channel is used to mask this layer).
+    /// Optional track matte: a top-level layer of the same scene whose pixels mask this one.
     pub mask: Option<LayerId>,
+    /// How the track matte's pixels are read.
+    #[serde(default, skip_serializing_if = "MatteMode::is_alpha")]
+    pub matte_mode: MatteMode,
+    /// Optional shape clipping this layer and its children.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shape_mask: Option<ShapeMask>,
+    /// Feather radius in pixels, softening the edges of the track matte and shape mask.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub mask_feather: f64,
     /// Layout constraints for responsive positioning.
     pub constraints: Vec<crate::layout::LayoutConstraint>,
+    /// Motion blur override for this layer and its children; `None` uses the project's.
+    /// Only read on top-level layers, whose children are blurred along with them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motion_blur: Option<vidra_core::MotionBlur>,
 }
 
 impl Layer {
@@ -191,7 +238,11 @@ impl Layer {
             visible: true,
             children: Vec::new(),
             mask: None,
+            matte_mode: MatteMode::Alpha,
+            shape_mask: None,
+            mask_feather: 0.0,
             constraints: Vec::new(),
+            motion_blur: None,
         }
     }
 
@@ -228,7 +279,7 @@ impl Layer {
     pub fn layer_type(&self) -> vidra_core::LayerType {
         match &self.content {
             LayerContent::Text { .. } => vidra_core::LayerType::Text,
-            LayerContent::Image { .. } => vidra_core::LayerType::Image,
+            LayerContent::Image { .. } | LayerContent::Svg { .. } => vidra_core::LayerType::Image,
             LayerContent::Spritesheet { .. } => vidra_core::LayerType::Image,
             LayerContent::Video { .. } => vidra_core::LayerType::Video,
             LayerContent::Audio { .. } => vidra_core::LayerType::Audio,
@@ -239,14 +290,37 @@ impl Layer {
             LayerContent::AutoCaption { .. } => vidra_core::LayerType::AutoCaption,
             LayerContent::Shader { .. } => vidra_core::LayerType::Shader,
             LayerContent::Web { .. } => vidra_core::LayerType::Web,
-            LayerContent::Empty => vidra_core::LayerType::Component,
+            LayerContent::Plugin { .. } => vidra_core::LayerType::Plugin,
+            LayerContent::Lottie { .. } | LayerContent::Empty => vidra_core::LayerType::Component,
         }
     }
+    /// Whether this layer or any of its children is animated.
+    pub fn is_animated(&self) -> bool {
+        !self.animations.is_empty() || self.children.iter().any(Layer::is_animated)
+    }
+
     /// Builder: add an effect.
     pub fn with_effect(mut self, effect: vidra_core::types::LayerEffect) -> Self {
         self.effects.push(effect);
         self
     }
+
+    /// Builder: use layer `id` as a track matte read in `mode`.
+    pub fn with_matte(mut self, id: LayerId, mode: MatteMode) -> Self {
+        self.mask = Some(id);
+        self.matte_mode = mode;
+        self
+    }
+
+    /// Builder: clip the layer to a shape.
+    pub fn with_shape_mask(mut self, mask: ShapeMask) -> Self {
+        self.shape_mask = Some(mask);
+        self
+    }
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
 }
 
 #[cfg(test)]
@@ -262,6 +336,8 @@ mod tests {
                 font_family: "Inter".into(),
                 font_size: 48.0,
                 color: Color::WHITE,
+                style: TextStyle::default(),
+                animators: Vec::new(),
             },
         );
         assert_eq!(layer.id.0, "title");
@@ -274,7 +350,9 @@ mod tests {
     fn test_layer_builders() {
         let layer = Layer::new(
             LayerId::new("bg"),
-            LayerContent::Solid { color: Color::BLUE },
+            LayerContent::Solid {
+                color: Color::BLUE.into(),
+            },
         )
         .with_position(100.0, 200.0)
         .with_opacity(0.8);
@@ -289,12 +367,14 @@ mod tests {
         let mut parent = Layer::new(
             LayerId::new("group"),
             LayerContent::Solid {
-                color: Color::TRANSPARENT,
+                color: Color::TRANSPARENT.into(),
             },
         );
         let child = Layer::new(
             LayerId::new("child"),
-            LayerContent::Solid { color: Color::RED },
+            LayerContent::Solid {
+                color: Color::RED.into(),
+            },
         );
         parent.add_child(child);
         assert_eq!(parent.children.len(), 1);
@@ -339,4 +419,18 @@ mod tests {
             _ => panic!("Expected LayerContent::Web"),
         }
     }
+
+    #[test]
+    fn test_mask_fields_default_when_absent() {
+        let layer = Layer::new(LayerId::new("plain"), LayerContent::Empty);
+        let json = serde_json::to_value(&layer).unwrap();
+        assert!(json.get("matte_mode").is_none());
+        assert!(json.get("shape_mask").is_none());
+        assert!(json.get("mask_feather").is_none());
+
+        let back: Layer = serde_json::from_value(json).unwrap();
+        assert_eq!(back.matte_mode, MatteMode::Alpha);
+        assert!(back.shape_mask.is_none());
+        assert_eq!(back.mask_feather, 0.0);
+    }
 }
@@ -10,16 +10,21 @@ pub mod asset;
 pub mod data;
 pub mod layer;
 pub mod layout;
+pub mod lottie;
+pub mod mask;
 pub mod project;
 pub mod scene;
+pub mod text;
 pub mod validate;
 
 pub use animation::{Animation, Keyframe};
 pub use asset::{Asset, AssetId, AssetRegistry, AssetType};
 pub use layer::{Layer, LayerContent, LayerId};
 pub use layout::{LayoutConstraint, LayoutSolver, ResolvedLayout};
+pub use mask::{MatteMode, ShapeMask};
 pub use project::{Project, ProjectSettings};
 pub use scene::{Scene, SceneId};
+pub use text::{TextAlign, TextAnimator, TextStyle};
 pub mod builder;
 pub mod crdt;
 pub mod transition;
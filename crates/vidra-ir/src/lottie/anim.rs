@@ -0,0 +1,559 @@
+//! Lottie animated properties, converted to Vidra keyframes.
+//!
+//! Lottie eases every keyframe segment with its own cubic Bézier and can move a
+//! position along a curved motion path. Eased, linear and held segments map to
+//! keyframes directly. Motion paths are sampled once per Lottie frame into linear
+//! keyframes, which matches the source exactly on every frame it was authored at.
+
+use serde_json::Value;
+use vidra_core::types::Easing;
+use vidra_core::Duration;
+
+use crate::animation::{AnimatableProperty, Animation, Keyframe};
+
+/// Maps Lottie frame numbers to scene seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Timing {
+    pub fps: f64,
+    /// Frames added to a frame number before it is converted.
+    pub offset: f64,
+}
+
+impl Timing {
+    pub fn seconds(&self, frame: f64) -> f64 {
+        (frame + self.offset) / self.fps
+    }
+
+    /// The timing of a layer that starts `frames` later.
+    pub fn shifted(&self, frames: f64) -> Timing {
+        Timing {
+            fps: self.fps,
+            offset: self.offset + frames,
+        }
+    }
+
+    /// Seconds half a frame before `frame`, where in/out points switch, so the switch
+    /// lands between two rendered frames.
+    pub fn before(&self, frame: f64) -> f64 {
+        self.seconds(frame - 0.5)
+    }
+}
+
+/// A curve through keyframe points. Two points at the same time jump from the first
+/// value to the second just after it.
+pub(super) type Track = Vec<Point>;
+
+/// A value at a time in seconds, reached from the previous point with `easing`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Point {
+    pub time: f64,
+    pub value: f64,
+    pub easing: Easing,
+    /// Keep the value until the next point.
+    pub hold: bool,
+}
+
+impl Point {
+    pub fn at(time: f64, value: f64) -> Point {
+        Point {
+            time,
+            value,
+            easing: Easing::Linear,
+            hold: false,
+        }
+    }
+}
+
+/// The numbers of a Lottie value: a number or an array of numbers.
+pub(super) fn numbers(value: &Value) -> Vec<f64> {
+    match value {
+        Value::Number(n) => n.as_f64().into_iter().collect(),
+        Value::Array(items) => items.iter().filter_map(Value::as_f64).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// An animatable Lottie property: `{"a": 0, "k": value}` or `{"a": 1, "k": [keyframes]}`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Prop<'a>(pub &'a Value);
+
+impl<'a> Prop<'a> {
+    /// Whether the property has keyframes.
+    pub fn is_animated(&self) -> bool {
+        self.keys().is_some_and(|keys| keys.len() > 1)
+    }
+
+    /// Whether an expression drives the property.
+    pub fn has_expression(&self) -> bool {
+        self.0.get("x").is_some_and(Value::is_string)
+    }
+
+    fn keys(&self) -> Option<&'a Vec<Value>> {
+        let keys = self.0.get("k")?.as_array()?;
+        keys.first()
+            .is_some_and(|k| k.get("t").is_some())
+            .then_some(keys)
+    }
+
+    /// The static value, or the value of the first keyframe, as it appears in the JSON.
+    pub fn initial_value(&self) -> Option<&'a Value> {
+        match self.keys() {
+            Some(keys) => keys.first().and_then(|k| k.get("s")),
+            None => self.0.get("k"),
+        }
+    }
+
+    /// The static value, or the value of the first keyframe.
+    pub fn initial(&self) -> Vec<f64> {
+        self.initial_value().map(numbers).unwrap_or_default()
+    }
+
+    /// Every value the property takes at a keyframe.
+    pub fn values(&self) -> Vec<Vec<f64>> {
+        match self.keys() {
+            Some(keys) => keys
+                .iter()
+                .flat_map(|k| [k.get("s"), k.get("e")])
+                .flatten()
+                .map(numbers)
+                .collect(),
+            None => vec![self.initial()],
+        }
+    }
+
+    /// One track per component of the value. Positions with spatial tangents follow
+    /// their motion path.
+    pub fn tracks(&self, timing: Timing) -> Vec<Track> {
+        let Some(keys) = self.keys() else {
+            return self
+                .initial()
+                .into_iter()
+                .map(|v| vec![Point::at(0.0, v)])
+                .collect();
+        };
+        let keys: Vec<Key> = keys.iter().map(Key::parse).collect();
+        let Some(first) = keys.iter().find_map(|k| k.start.clone()) else {
+            return Vec::new();
+        };
+        let dims = first.len();
+        let linear = vec![Easing::Linear; dims];
+        // Lottie frame, value, easing of each component, hold.
+        let mut points = vec![(keys[0].time, first.clone(), linear.clone(), false)];
+        let mut current = first;
+        for pair in keys.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            let from = a.start.clone().unwrap_or(current);
+            let to = b
+                .start
+                .clone()
+                .or_else(|| a.end.clone())
+                .unwrap_or_else(|| from.clone());
+            current = to.clone();
+            if b.time <= a.time {
+                points.push((b.time, to, linear.clone(), false));
+                continue;
+            }
+            if points.last().is_some_and(|p| p.1 != from) {
+                points.push((a.time, from.clone(), linear.clone(), false));
+            }
+            if a.hold {
+                if let Some(last) = points.last_mut() {
+                    last.3 = true;
+                }
+                points.push((b.time, to, linear.clone(), false));
+                continue;
+            }
+            let eases: Vec<Easing> = (0..dims).map(|d| ease(a, d)).collect();
+            let Some(path) = MotionPath::between(&from, &to, a.to.as_deref(), a.ti.as_deref())
+            else {
+                points.push((b.time, to, eases, false));
+                continue;
+            };
+            let mut frame = a.time.floor() + 1.0;
+            while frame < b.time {
+                let x = (frame - a.time) / (b.time - a.time);
+                points.push((frame, path.at(eases[0].apply(x)), linear.clone(), false));
+                frame += 1.0;
+            }
+            points.push((b.time, to, linear.clone(), false));
+        }
+
+        (0..dims)
+            .map(|d| {
+                let track = points
+                    .iter()
+                    .map(|(frame, value, eases, hold)| Point {
+                        time: timing.seconds(*frame),
+                        value: component(value, d),
+                        easing: eases[d],
+                        hold: *hold,
+                    })
+                    .collect();
+                clip_negative(track)
+            })
+            .collect()
+    }
+}
+
+fn component(value: &[f64], index: usize) -> f64 {
+    value.get(index).copied().unwrap_or(0.0)
+}
+
+/// One Lottie keyframe. Its easing handles and tangents shape the segment to the next.
+struct Key {
+    time: f64,
+    start: Option<Vec<f64>>,
+    /// Segment end value, written by older exporters instead of the next `start`.
+    end: Option<Vec<f64>>,
+    hold: bool,
+    out_handle: Option<(Vec<f64>, Vec<f64>)>,
+    in_handle: Option<(Vec<f64>, Vec<f64>)>,
+    to: Option<Vec<f64>>,
+    ti: Option<Vec<f64>>,
+}
+
+impl Key {
+    fn parse(json: &Value) -> Key {
+        let handle = |name: &str| json.get(name).map(|h| (numbers(&h["x"]), numbers(&h["y"])));
+        Key {
+            time: json["t"].as_f64().unwrap_or(0.0),
+            start: json.get("s").map(numbers),
+            end: json.get("e").map(numbers),
+            hold: json["h"].as_f64() == Some(1.0) || json["h"].as_bool() == Some(true),
+            out_handle: handle("o"),
+            in_handle: handle("i"),
+            to: json.get("to").map(numbers),
+            ti: json.get("ti").map(numbers),
+        }
+    }
+}
+
+/// The easing of one component of the segment starting at `key`.
+fn ease(key: &Key, dim: usize) -> Easing {
+    let (Some((ox, oy)), Some((ix, iy))) = (&key.out_handle, &key.in_handle) else {
+        return Easing::Linear;
+    };
+    let pick = |values: &[f64]| values.get(dim).or(values.last()).copied().unwrap_or(0.0);
+    let (x1, y1, x2, y2) = (pick(ox), pick(oy), pick(ix), pick(iy));
+    // Handles on the diagonal leave the segment linear.
+    if (x1 - y1).abs() < 1e-6 && (x2 - y2).abs() < 1e-6 {
+        Easing::Linear
+    } else {
+        Easing::CubicBezier(x1.clamp(0.0, 1.0), y1, x2.clamp(0.0, 1.0), y2)
+    }
+}
+
+/// `easing` split at progress `x`: the easing of the part before it and of the part
+/// after it, each rescaled to run from 0 to 1. Only linear and cubic Bézier easings are
+/// split exactly; Lottie uses no others.
+fn split(easing: Easing, x: f64) -> (Easing, Easing) {
+    let Easing::CubicBezier(x1, y1, x2, y2) = easing else {
+        return (easing, easing);
+    };
+    let y = easing.apply(x);
+    if x <= 1e-9 || x >= 1.0 - 1e-9 || y.abs() < 1e-9 || (1.0 - y).abs() < 1e-9 {
+        return (easing, easing);
+    }
+    // The curve parameter at x; x(s) increases with s while x1 and x2 are within 0–1.
+    let curve = |a: f64, b: f64, s: f64| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * a + 3.0 * r * s * s * b + s * s * s
+    };
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..60 {
+        let mid = (lo + hi) / 2.0;
+        if curve(x1, x2, mid) < x {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    let s = (lo + hi) / 2.0;
+
+    let lerp = |p: (f64, f64), q: (f64, f64)| (p.0 + (q.0 - p.0) * s, p.1 + (q.1 - p.1) * s);
+    let (p1, p2) = ((x1, y1), (x2, y2));
+    let (p01, p12, p23) = (lerp((0.0, 0.0), p1), lerp(p1, p2), lerp(p2, (1.0, 1.0)));
+    let (left, right) = (lerp(p01, p12), lerp(p12, p23));
+    let before = Easing::CubicBezier(p01.0 / x, p01.1 / y, left.0 / x, left.1 / y);
+    let rescale = |p: (f64, f64)| ((p.0 - x) / (1.0 - x), (p.1 - y) / (1.0 - y));
+    let (r1, r2) = (rescale(right), rescale(p23));
+    (before, Easing::CubicBezier(r1.0, r1.1, r2.0, r2.1))
+}
+
+/// A curved position segment, walked at constant speed.
+struct MotionPath {
+    points: [[f64; 2]; 4],
+    /// Arc length at each of `SAMPLES` equal steps of the curve parameter.
+    lengths: Vec<f64>,
+}
+
+impl MotionPath {
+    const SAMPLES: usize = 64;
+
+    /// The curve from `from` to `to` with out tangent `to_tangent` and in tangent
+    /// `ti`, unless both tangents are zero.
+    fn between(
+        from: &[f64],
+        to: &[f64],
+        to_tangent: Option<&[f64]>,
+        ti: Option<&[f64]>,
+    ) -> Option<MotionPath> {
+        let (out_t, in_t) = (to_tangent.unwrap_or(&[]), ti.unwrap_or(&[]));
+        if from.len() < 2 || out_t.iter().chain(in_t).all(|v| v.abs() < 1e-9) {
+            return None;
+        }
+        let p0 = [from[0], from[1]];
+        let p3 = [component(to, 0), component(to, 1)];
+        let points = [
+            p0,
+            [p0[0] + component(out_t, 0), p0[1] + component(out_t, 1)],
+            [p3[0] + component(in_t, 0), p3[1] + component(in_t, 1)],
+            p3,
+        ];
+        let mut path = MotionPath {
+            points,
+            lengths: Vec::with_capacity(Self::SAMPLES + 1),
+        };
+        let mut total = 0.0;
+        let mut last = p0;
+        path.lengths.push(0.0);
+        for i in 1..=Self::SAMPLES {
+            let p = path.point(i as f64 / Self::SAMPLES as f64);
+            total += (p[0] - last[0]).hypot(p[1] - last[1]);
+            path.lengths.push(total);
+            last = p;
+        }
+        Some(path)
+    }
+
+    fn point(&self, t: f64) -> [f64; 2] {
+        let [p0, p1, p2, p3] = self.points;
+        let u = 1.0 - t;
+        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        [
+            a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+            a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+        ]
+    }
+
+    /// The point a fraction `progress` of the way along the curve's length.
+    fn at(&self, progress: f64) -> Vec<f64> {
+        let total = *self.lengths.last().unwrap_or(&0.0);
+        if total <= 0.0 {
+            return self.points[0].to_vec();
+        }
+        let target = progress * total;
+        let i = self
+            .lengths
+            .partition_point(|l| *l < target)
+            .clamp(1, Self::SAMPLES);
+        let (l0, l1) = (self.lengths[i - 1], self.lengths[i]);
+        let frac = if l1 > l0 {
+            (target - l0) / (l1 - l0)
+        } else {
+            0.0
+        };
+        let t = (i as f64 - 1.0 + frac) / Self::SAMPLES as f64;
+        self.point(t).to_vec()
+    }
+}
+
+/// The value of `track` at `time`. At a jump, the value after it.
+pub(super) fn value_at(track: &Track, time: f64) -> f64 {
+    let Some(first) = track.first() else {
+        return 0.0;
+    };
+    if time < first.time {
+        return first.value;
+    }
+    let i = track.partition_point(|p| p.time <= time);
+    if i >= track.len() {
+        return track[track.len() - 1].value;
+    }
+    let (a, b) = (track[i - 1], track[i]);
+    if a.hold {
+        return a.value;
+    }
+    a.value + (b.value - a.value) * b.easing.apply((time - a.time) / (b.time - a.time))
+}
+
+/// The part of `track` from `time` on, starting with a point at `time`.
+fn after(track: &Track, time: f64) -> Track {
+    let i = track.partition_point(|p| p.time <= time);
+    let mut start = Point::at(time, value_at(track, time));
+    let mut rest = track[i..].to_vec();
+    if let (Some(before), Some(next)) = (i.checked_sub(1).map(|j| track[j]), rest.first_mut()) {
+        start.hold = before.hold;
+        let x = (time - before.time) / (next.time - before.time);
+        next.easing = split(next.easing, x).1;
+    }
+    let mut cut = vec![start];
+    cut.extend(rest);
+    cut
+}
+
+/// The part of `track` before `time`, ending with a point at `time`.
+fn until(track: &Track, time: f64) -> Track {
+    let i = track.partition_point(|p| p.time < time);
+    let mut cut = track[..i].to_vec();
+    match (cut.last(), track.get(i)) {
+        (Some(before), Some(next)) => {
+            let x = (time - before.time) / (next.time - before.time);
+            let mut end = Point::at(time, value_at(track, time));
+            if before.hold {
+                end.value = before.value;
+            } else {
+                end.easing = split(next.easing, x).0;
+            }
+            cut.push(end);
+        }
+        (Some(before), None) => cut.push(Point::at(time, before.value)),
+        (None, _) => cut.push(Point::at(time, value_at(track, time))),
+    }
+    cut
+}
+
+/// `track` without its points before zero, which a scene never shows.
+fn clip_negative(track: Track) -> Track {
+    if track.first().is_none_or(|p| p.time >= 0.0) {
+        return track;
+    }
+    after(&track, 0.0)
+}
+
+/// `track` shown only between a layer's in and out points: zero before `start` and
+/// from `end` on. A bound is `None` when the layer is already in (or still in) there.
+pub(super) fn window(track: &Track, start: Option<f64>, end: Option<f64>) -> Track {
+    let mut windowed = track.clone();
+    if let Some(end) = end {
+        windowed = until(&windowed, end);
+        windowed.push(Point::at(end, 0.0));
+    }
+    if let Some(start) = start {
+        let shown = after(&windowed, start);
+        windowed = vec![Point::at(start, 0.0)];
+        windowed.extend(shown);
+    }
+    windowed
+}
+
+/// An animation of `property` through the points of `track`, mapped by `map`.
+pub(super) fn animation(
+    property: AnimatableProperty,
+    track: &Track,
+    map: impl Fn(f64) -> f64,
+) -> Animation {
+    let mut animation = Animation::new(property);
+    animation.keyframes = track
+        .iter()
+        .map(|p| Keyframe {
+            easing: p.easing,
+            hold: p.hold,
+            ..Keyframe::new(Duration::from_seconds(p.time), map(p.value))
+        })
+        .collect();
+    animation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TIMING: Timing = Timing {
+        fps: 10.0,
+        offset: 0.0,
+    };
+
+    #[test]
+    fn test_static_and_linear_properties() {
+        let prop = json!({"a": 0, "k": [10, 20]});
+        assert!(!Prop(&prop).is_animated());
+        assert_eq!(Prop(&prop).initial(), vec![10.0, 20.0]);
+
+        let prop = json!({"a": 1, "k": [
+            {"t": 0, "s": [0], "o": {"x": [0.5], "y": [0.5]}, "i": {"x": [0.5], "y": [0.5]}},
+            {"t": 10, "s": [100]}
+        ]});
+        let tracks = Prop(&prop).tracks(TIMING);
+        assert_eq!(
+            tracks,
+            vec![vec![Point::at(0.0, 0.0), Point::at(1.0, 100.0)]]
+        );
+    }
+
+    #[test]
+    fn test_bezier_segments_become_eased_keyframes() {
+        // Ease in-out: slow at both ends, halfway at the middle frame.
+        let prop = json!({"a": 1, "k": [
+            {"t": 0, "s": [0], "o": {"x": 0.42, "y": 0}, "i": {"x": 0.58, "y": 1}},
+            {"t": 10, "s": [100]}
+        ]});
+        let track = &Prop(&prop).tracks(TIMING)[0];
+        assert_eq!(track.len(), 2);
+        assert_eq!(track[1].easing, Easing::CubicBezier(0.42, 0.0, 0.58, 1.0));
+        assert!((value_at(track, 0.5) - 50.0).abs() < 1e-6);
+        assert!(value_at(track, 0.1) < 5.0);
+        assert!(value_at(track, 0.9) > 95.0);
+    }
+
+    #[test]
+    fn test_hold_keyframes() {
+        let prop = json!({"a": 1, "k": [
+            {"t": 0, "s": [1], "h": 1},
+            {"t": 5, "s": [2]}
+        ]});
+        let track = &Prop(&prop).tracks(TIMING)[0];
+        assert!(track[0].hold);
+        assert_eq!(value_at(track, 0.49), 1.0);
+        assert_eq!(value_at(track, 0.5), 2.0);
+    }
+
+    #[test]
+    fn test_old_format_end_values_and_offsets() {
+        // Older exporters give each segment an `e` and end with a bare time.
+        let prop = json!({"a": 1, "k": [{"t": 10, "s": [0], "e": [50]}, {"t": 20}]});
+        let track = &Prop(&prop).tracks(TIMING.shifted(-15.0))[0];
+        // Frame 15 is the scene start: the part before it is cut off.
+        assert_eq!(track[0], Point::at(0.0, 25.0));
+        assert_eq!(track[1], Point::at(0.5, 50.0));
+    }
+
+    #[test]
+    fn test_cutting_an_eased_segment_keeps_its_curve() {
+        let prop = json!({"a": 1, "k": [
+            {"t": 0, "s": [0], "o": {"x": 0.7, "y": 0.1}, "i": {"x": 0.2, "y": 1.3}},
+            {"t": 10, "s": [100]}
+        ]});
+        let track = &Prop(&prop).tracks(TIMING)[0];
+        let cut = window(track, Some(0.3), Some(0.8));
+        for time in [0.35, 0.5, 0.65, 0.79] {
+            assert!((value_at(&cut, time) - value_at(track, time)).abs() < 1e-6);
+        }
+        assert_eq!(value_at(&cut, 0.25), 0.0);
+        assert_eq!(value_at(&cut, 0.85), 0.0);
+    }
+
+    #[test]
+    fn test_motion_paths_follow_tangents_at_even_speed() {
+        // An arch from (0, 0) to (100, 0), 60 px high in the middle.
+        let prop = json!({"a": 1, "k": [
+            {"t": 0, "s": [0, 0], "to": [0, -80], "ti": [0, -80]},
+            {"t": 10, "s": [100, 0]}
+        ]});
+        let tracks = Prop(&prop).tracks(TIMING);
+        let (x, y) = (value_at(&tracks[0], 0.5), value_at(&tracks[1], 0.5));
+        assert!((x - 50.0).abs() < 0.5, "{}", x);
+        assert!((y + 60.0).abs() < 0.5, "{}", y);
+    }
+
+    #[test]
+    fn test_window_hides_outside_in_and_out_points() {
+        let track = vec![Point::at(0.0, 1.0)];
+        let windowed = window(&track, Some(0.25), Some(0.75));
+        assert_eq!(value_at(&windowed, 0.2), 0.0);
+        assert_eq!(value_at(&windowed, 0.3), 1.0);
+        assert_eq!(value_at(&windowed, 0.7), 1.0);
+        assert_eq!(value_at(&windowed, 0.8), 0.0);
+    }
+}
@@ -0,0 +1,1044 @@
+//! Lottie (Bodymovin JSON) import.
+//!
+//! [`import`] turns an After Effects animation exported as Lottie into Vidra layers:
+//! precomps, shape, solid, image, text and null layers, their transforms and
+//! parenting, keyframes with Bézier easing, masks and track mattes.
+//!
+//! Every Lottie layer becomes an empty layer carrying its transform, with the anchor
+//! point at its origin and its content as children, offset by the anchor. Parents
+//! become empty layers wrapped around their children. Anything the IR cannot express
+//! is imported as closely as possible and listed in [`LottieImport::issues`].
+
+mod anim;
+mod shapes;
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+use vidra_core::types::ShapeType;
+use vidra_core::{BlendMode, Color, Duration, Point2D, VidraError};
+
+use crate::animation::AnimatableProperty;
+use crate::asset::{Asset, AssetId, AssetType};
+use crate::layer::{Layer, LayerContent, LayerId};
+use crate::mask::{MatteMode, ShapeMask};
+use crate::project::{Project, ProjectSettings};
+use crate::scene::{Scene, SceneId};
+use crate::text::{TextAlign, TextStyle};
+
+use anim::{Prop, Timing};
+
+/// Something in the Lottie file that was imported differently or left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportIssue {
+    /// Id of the layer it concerns.
+    pub location: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// An imported Lottie animation.
+#[derive(Debug, Clone)]
+pub struct LottieImport {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    pub duration: Duration,
+    /// The root composition's layers, bottom to top.
+    pub layers: Vec<Layer>,
+    /// Images used by image layers. Embedded images have a `data:` URI as their path.
+    pub assets: Vec<Asset>,
+    pub issues: Vec<ImportIssue>,
+}
+
+impl LottieImport {
+    /// A project with the animation as its only scene.
+    pub fn into_project(self) -> Project {
+        let mut project = Project::new(ProjectSettings::custom(self.width, self.height, self.fps));
+        for asset in self.assets {
+            project.assets.register(asset);
+        }
+        let name = if self.name.is_empty() {
+            "lottie".to_string()
+        } else {
+            self.name
+        };
+        let mut scene = Scene::new(SceneId::new(name), self.duration);
+        scene.layers = self.layers;
+        project.add_scene(scene);
+        project
+    }
+
+    /// Prepare the layers to be nested in another layer. Track mattes only work between
+    /// a scene's top-level layers, so they are dropped and reported.
+    pub fn nested(mut self) -> Self {
+        for layer in &mut self.layers {
+            if layer.mask.take().is_some() {
+                self.issues.push(ImportIssue {
+                    location: layer.id.0.clone(),
+                    message: "track mattes only work on top-level layers; drawn without it".into(),
+                });
+            }
+        }
+        self
+    }
+
+    /// Resolve images referenced by a relative path against `dir`, the animation's
+    /// directory. Embedded (`data:`) and remote images are left as they are.
+    pub fn resolve_image_paths(&mut self, dir: &std::path::Path) {
+        for asset in &mut self.assets {
+            let path = asset.path.to_string_lossy();
+            if asset.path.is_relative() && !path.starts_with("data:") && !path.contains("://") {
+                asset.path = dir.join(&asset.path);
+            }
+        }
+    }
+
+    /// Prefix every asset id with `scope/`, so several imports can share a project.
+    pub fn scope_assets(&mut self, scope: &str) {
+        for asset in &mut self.assets {
+            asset.id = AssetId::new(format!("{}/{}", scope, asset.id));
+        }
+        for layer in &mut self.layers {
+            scope_layer_assets(layer, scope);
+        }
+    }
+}
+
+fn scope_layer_assets(layer: &mut Layer, scope: &str) {
+    if let LayerContent::Image { asset_id } = &mut layer.content {
+        *asset_id = AssetId::new(format!("{}/{}", scope, asset_id));
+    }
+    for child in &mut layer.children {
+        scope_layer_assets(child, scope);
+    }
+}
+
+/// Import a Lottie animation from its JSON.
+pub fn import(json: &str) -> Result<LottieImport, VidraError> {
+    let root: Value = serde_json::from_str(json)?;
+    let number = |name: &str| {
+        root[name].as_f64().ok_or_else(|| {
+            VidraError::InvalidArgument(format!("not a Lottie animation: missing '{}'", name))
+        })
+    };
+    let (fps, ip, op) = (number("fr")?, number("ip")?, number("op")?);
+    let (width, height) = (number("w")?, number("h")?);
+    if fps <= 0.0 {
+        return Err(VidraError::InvalidArgument(
+            "Lottie frame rate must be positive".into(),
+        ));
+    }
+    let layers = root["layers"].as_array().ok_or_else(|| {
+        VidraError::InvalidArgument("not a Lottie animation: missing 'layers'".into())
+    })?;
+
+    let assets = root["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| Some((a["id"].as_str()?, a)))
+        .collect();
+    let fonts = root["fonts"]["list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| Some((f["fName"].as_str()?, f["fFamily"].as_str()?)))
+        .collect();
+    let duration = ((op - ip) / fps).max(0.0);
+    let mut importer = Importer {
+        duration,
+        assets,
+        fonts,
+        ids: HashSet::new(),
+        images: Vec::new(),
+        issues: Vec::new(),
+        expanding: Vec::new(),
+    };
+    let comp = Comp {
+        layers,
+        timing: Timing { fps, offset: -ip },
+        prefix: String::new(),
+        offset: Point2D::zero(),
+        nested: false,
+    };
+    let layers = importer.composition(&comp);
+
+    Ok(LottieImport {
+        name: root["nm"].as_str().unwrap_or_default().to_string(),
+        width: width.round().max(1.0) as u32,
+        height: height.round().max(1.0) as u32,
+        fps,
+        duration: Duration::from_seconds(duration),
+        layers,
+        assets: importer.images,
+        issues: importer.issues,
+    })
+}
+
+/// A composition being imported: the root or a precomp.
+struct Comp<'a> {
+    layers: &'a [Value],
+    timing: Timing,
+    /// Prefix of the ids of its layers.
+    prefix: String,
+    /// Where its origin sits in the layer it is imported into.
+    offset: Point2D,
+    /// Whether its layers end up nested in another layer.
+    nested: bool,
+}
+
+impl<'a> Comp<'a> {
+    fn by_index(&self, ind: &Value) -> Option<(usize, &'a Value)> {
+        let ind = ind.as_f64()?;
+        self.layers
+            .iter()
+            .enumerate()
+            .find(|(_, l)| l["ind"].as_f64() == Some(ind))
+    }
+}
+
+struct Importer<'a> {
+    /// Scene length in seconds.
+    duration: f64,
+    assets: HashMap<&'a str, &'a Value>,
+    /// Font families by font name.
+    fonts: HashMap<&'a str, &'a str>,
+    ids: HashSet<String>,
+    images: Vec<Asset>,
+    issues: Vec<ImportIssue>,
+    /// Precomps being imported, to stop at one that contains itself.
+    expanding: Vec<&'a str>,
+}
+
+impl<'a> Importer<'a> {
+    fn report(&mut self, location: &str, message: impl Into<String>) {
+        self.issues.push(ImportIssue {
+            location: location.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// `base`, or `base` with a number after it if a layer already has that id.
+    fn unique_id(&mut self, base: String) -> LayerId {
+        let mut id = base.clone();
+        let mut n = 2;
+        while !self.ids.insert(id.clone()) {
+            id = format!("{} {}", base, n);
+            n += 1;
+        }
+        LayerId::new(id)
+    }
+
+    /// The layers of `comp`, bottom to top, with track mattes resolved.
+    fn composition(&mut self, comp: &Comp<'a>) -> Vec<Layer> {
+        let mut built: Vec<(usize, Layer)> = Vec::new();
+        for (index, json) in comp.layers.iter().enumerate().rev() {
+            if let Some(layer) = self.layer(comp, json) {
+                built.push((index, layer));
+            }
+        }
+
+        // A matte layer sits above the layer it mattes, unless `tp` names it.
+        for i in 0..built.len() {
+            let index = built[i].0;
+            let json = &comp.layers[index];
+            let mode = match json["tt"].as_f64().map(|v| v as i64) {
+                Some(1) => MatteMode::Alpha,
+                Some(2) => MatteMode::AlphaInverted,
+                Some(3) => MatteMode::Luma,
+                Some(4) => MatteMode::LumaInverted,
+                _ => continue,
+            };
+            let source = match comp.by_index(&json["tp"]) {
+                Some((source, _)) => Some(source),
+                None => index.checked_sub(1),
+            };
+            let Some(s) = source.and_then(|source| built.iter().position(|(j, _)| *j == source))
+            else {
+                let location = built[i].1.id.0.clone();
+                self.report(&location, "track matte layer not found; drawn without it");
+                continue;
+            };
+            if comp.nested {
+                let location = built[i].1.id.0.clone();
+                self.report(
+                    &location,
+                    "track mattes only work on top-level layers; drawn without it",
+                );
+                continue;
+            }
+            // Mattes are aligned without rotation, so rotated layers get an upright frame.
+            for k in [i, s] {
+                if is_rotated(&built[k].1) {
+                    let layer = std::mem::replace(
+                        &mut built[k].1,
+                        Layer::new(LayerId::new(""), LayerContent::Empty),
+                    );
+                    built[k].1 = self.upright(layer);
+                }
+            }
+            let source_id = built[s].1.id.clone();
+            built[i].1 = std::mem::replace(
+                &mut built[i].1,
+                Layer::new(LayerId::new(""), LayerContent::Empty),
+            )
+            .with_matte(source_id, mode);
+        }
+        for (index, layer) in &mut built {
+            if comp.layers[*index]["td"].as_f64().unwrap_or(0.0) != 0.0 {
+                layer.visible = false;
+            }
+        }
+        built.into_iter().map(|(_, layer)| layer).collect()
+    }
+
+    /// `layer` in an unrotated empty layer that takes over its blend mode.
+    fn upright(&mut self, mut layer: Layer) -> Layer {
+        let mut frame = Layer::new(
+            self.unique_id(format!("{} (matte)", layer.id)),
+            LayerContent::Empty,
+        );
+        frame.transform.anchor = Point2D::zero();
+        frame.blend_mode = std::mem::replace(&mut layer.blend_mode, BlendMode::Normal);
+        frame.add_child(layer);
+        frame
+    }
+
+    /// One Lottie layer with its parents around it, or `None` when nothing of it shows.
+    fn layer(&mut self, comp: &Comp<'a>, json: &'a Value) -> Option<Layer> {
+        let ty = json["ty"].as_f64().unwrap_or(-1.0) as i64;
+        let name = layer_name(json);
+        if json["hd"].as_bool() == Some(true) || ty == 3 {
+            return None;
+        }
+        let id = self.unique_id(format!("{}{}", comp.prefix, name));
+        let location = id.0.clone();
+        if !(0..=5).contains(&ty) {
+            self.report(
+                &location,
+                format!("{} layers are not supported", layer_type_name(ty)),
+            );
+            self.ids.remove(&location);
+            return None;
+        }
+        if json["ddd"].as_f64() == Some(1.0) {
+            self.report(&location, "3D layer imported as 2D");
+        }
+        if json["ao"].as_f64() == Some(1.0) {
+            self.report(&location, "auto-orient is not supported");
+        }
+        if json["sr"].as_f64().is_some_and(|sr| sr != 1.0) {
+            self.report(&location, "time stretch is not supported");
+        }
+        if json.get("tm").is_some() {
+            self.report(&location, "time remapping is not supported");
+        }
+        for effect in json["ef"].as_array().into_iter().flatten() {
+            let effect = effect["nm"].as_str().unwrap_or("unnamed");
+            self.report(&location, format!("effect '{}' is not supported", effect));
+        }
+
+        let parents = self.parents(comp, json, &location);
+        let timing = comp.timing.shifted(json["st"].as_f64().unwrap_or(0.0));
+        let ks = &json["ks"];
+        let offset = match parents.first() {
+            Some(parent) => negate(anchor_of(&parent["ks"])),
+            None => comp.offset,
+        };
+        let (mut node, anchor) = self.transform_node(id, ks, offset, timing);
+        self.layer_opacity(&mut node, comp, json, timing);
+
+        let offset = negate(anchor);
+        match ty {
+            0 => self.precomp(&mut node, json, offset, timing),
+            1 => node.children.extend(self.solid(json, &location, offset)),
+            2 => node.children.extend(self.image(json, &location, offset)),
+            4 => {
+                let items = json["shapes"].as_array().map_or(&[][..], Vec::as_slice);
+                node.children = shapes::shape_layers(self, items, &location, offset, timing);
+            }
+            5 => node.children.extend(self.text(json, &location, offset)),
+            _ => {}
+        }
+        if let Some(masks) = json["masksProperties"].as_array() {
+            self.masks(&mut node, masks, anchor);
+        }
+
+        // Parents, innermost first, each placed by its own parent's anchor point.
+        for (i, parent) in parents.iter().enumerate() {
+            let offset = match parents.get(i + 1) {
+                Some(grandparent) => negate(anchor_of(&grandparent["ks"])),
+                None => comp.offset,
+            };
+            let parent_id = self.unique_id(format!("{} (parent {})", node.id, layer_name(parent)));
+            let timing = comp.timing.shifted(parent["st"].as_f64().unwrap_or(0.0));
+            let (mut outer, _) = self.transform_node(parent_id, &parent["ks"], offset, timing);
+            outer.add_child(node);
+            node = outer;
+        }
+
+        node.blend_mode = match json["bm"].as_f64().unwrap_or(0.0) as i64 {
+            0 => BlendMode::Normal,
+            1 => BlendMode::Multiply,
+            2 => BlendMode::Screen,
+            3 => BlendMode::Overlay,
+            4 => BlendMode::Darken,
+            5 => BlendMode::Lighten,
+            6 => BlendMode::ColorDodge,
+            7 => BlendMode::ColorBurn,
+            8 => BlendMode::HardLight,
+            9 => BlendMode::SoftLight,
+            10 => BlendMode::Difference,
+            11 => BlendMode::Exclusion,
+            12 => BlendMode::Hue,
+            13 => BlendMode::Saturation,
+            14 => BlendMode::Color,
+            15 => BlendMode::Luminosity,
+            16 => BlendMode::Add,
+            other => {
+                self.report(&location, format!("blend mode {} is not supported", other));
+                BlendMode::Normal
+            }
+        };
+        Some(node)
+    }
+
+    /// The chain of parents of `json`, nearest first.
+    fn parents(&mut self, comp: &Comp<'a>, json: &'a Value, location: &str) -> Vec<&'a Value> {
+        let mut parents: Vec<&'a Value> = Vec::new();
+        let mut current = json;
+        while let Some(parent) = current.get("parent") {
+            let Some((_, parent)) = comp.by_index(parent) else {
+                self.report(location, "parent layer not found");
+                break;
+            };
+            if std::ptr::eq(parent, json) || parents.iter().any(|p| std::ptr::eq(*p, parent)) {
+                self.report(location, "parenting loop ignored");
+                break;
+            }
+            parents.push(parent);
+            current = parent;
+        }
+        parents
+    }
+
+    /// An empty layer carrying a Lottie transform (a layer's `ks` or a group's `tr`)
+    /// whose origin is placed at `offset`, with the anchor point its content is
+    /// offset by. Opacity is left to the caller.
+    fn transform_node(
+        &mut self,
+        id: LayerId,
+        ks: &Value,
+        offset: Point2D,
+        timing: Timing,
+    ) -> (Layer, Point2D) {
+        let mut node = Layer::new(id, LayerContent::Empty);
+        node.transform.anchor = Point2D::zero();
+        let location = node.id.0.clone();
+
+        if Prop(&ks["a"]).is_animated() {
+            self.report(&location, "animated anchor point uses its first value");
+        }
+        let anchor = anchor_of(ks);
+
+        let position = &ks["p"];
+        let shift = [offset.x, offset.y];
+        let (x, y) = if position["s"].as_bool() == Some(true) {
+            let x = self.animate(
+                &mut node,
+                &position["x"],
+                timing,
+                &[AnimatableProperty::PositionX],
+                |_, v| v + offset.x,
+            );
+            let y = self.animate(
+                &mut node,
+                &position["y"],
+                timing,
+                &[AnimatableProperty::PositionY],
+                |_, v| v + offset.y,
+            );
+            (first(&x, 0.0), first(&y, 0.0))
+        } else {
+            let p = self.animate(
+                &mut node,
+                position,
+                timing,
+                &[AnimatableProperty::PositionX, AnimatableProperty::PositionY],
+                |i, v| v + shift[i],
+            );
+            (first(&p, 0.0), p.get(1).copied().unwrap_or(0.0))
+        };
+        node.transform.position = Point2D::new(x + offset.x, y + offset.y);
+
+        let scale = self.animate(
+            &mut node,
+            &ks["s"],
+            timing,
+            &[AnimatableProperty::ScaleX, AnimatableProperty::ScaleY],
+            |_, v| v / 100.0,
+        );
+        node.transform.scale = Point2D::new(
+            first(&scale, 100.0) / 100.0,
+            scale.get(1).copied().unwrap_or(100.0) / 100.0,
+        );
+        if Prop(&ks["s"]).values().iter().flatten().any(|v| *v < 0.0) {
+            self.report(&location, "negative (mirroring) scale is not supported");
+        }
+
+        let rotation = if ks.get("rz").is_some() {
+            &ks["rz"]
+        } else {
+            &ks["r"]
+        };
+        let rotation = self.animate(
+            &mut node,
+            rotation,
+            timing,
+            &[AnimatableProperty::Rotation],
+            |_, v| v,
+        );
+        node.transform.rotation = first(&rotation, 0.0);
+
+        let skew = Prop(&ks["sk"]);
+        if skew.is_animated() || first(&skew.initial(), 0.0) != 0.0 {
+            self.report(&location, "skew is not supported");
+        }
+        (node, anchor)
+    }
+
+    /// The initial value of `prop`. When it is animated, adds to `layer` an animation
+    /// of component `i` to `properties[i]`, mapped by `map(i, value)`.
+    fn animate(
+        &mut self,
+        layer: &mut Layer,
+        prop: &Value,
+        timing: Timing,
+        properties: &[AnimatableProperty],
+        map: impl Fn(usize, f64) -> f64,
+    ) -> Vec<f64> {
+        let prop = Prop(prop);
+        if prop.has_expression() {
+            let location = layer.id.0.clone();
+            self.report(
+                &location,
+                format!(
+                    "expression on {} ignored; its keyframes are used",
+                    properties[0]
+                ),
+            );
+        }
+        if prop.is_animated() {
+            let tracks = prop.tracks(timing);
+            for (i, (property, track)) in properties.iter().zip(&tracks).enumerate() {
+                layer
+                    .animations
+                    .push(anim::animation(property.clone(), track, |v| map(i, v)));
+            }
+        }
+        prop.initial()
+    }
+
+    /// Opacity of a layer, which is also hidden outside its in and out points.
+    fn layer_opacity(&mut self, node: &mut Layer, comp: &Comp<'a>, json: &Value, timing: Timing) {
+        let prop = &json["ks"]["o"];
+        if Prop(prop).has_expression() {
+            let location = node.id.0.clone();
+            self.report(
+                &location,
+                "expression on opacity ignored; its keyframes are used",
+            );
+        }
+        let opacity = first(&Prop(prop).initial(), 100.0) / 100.0;
+        node.transform.opacity = opacity;
+        let track: anim::Track = if Prop(prop).is_animated() {
+            let mut track = Prop(prop).tracks(timing).swap_remove(0);
+            for point in &mut track {
+                point.value /= 100.0;
+            }
+            track
+        } else {
+            vec![anim::Point::at(0.0, opacity)]
+        };
+        let bound = |frame: Option<f64>| frame.map(|f| comp.timing.before(f));
+        let start = bound(json["ip"].as_f64()).filter(|t| *t > 0.0);
+        let end = bound(json["op"].as_f64()).filter(|t| *t < self.duration);
+        if start.is_none() && end.is_none() && track.len() == 1 {
+            return;
+        }
+        let track = anim::window(&track, start, end);
+        node.animations
+            .push(anim::animation(AnimatableProperty::Opacity, &track, |v| v));
+    }
+
+    /// The first active mask of a layer, as its shape mask.
+    fn masks(&mut self, node: &mut Layer, masks: &[Value], anchor: Point2D) {
+        let location = node.id.0.clone();
+        let active: Vec<&Value> = masks.iter().filter(|m| m["mode"] != "n").collect();
+        let Some(mask) = active.first() else {
+            return;
+        };
+        if active.len() > 1 {
+            self.report(
+                &location,
+                format!("only the first of {} masks is applied", active.len()),
+            );
+        }
+        if node.shape_mask.is_some() {
+            self.report(
+                &location,
+                "mask replaces the precomp's clipping to its size",
+            );
+        }
+        let path = Prop(&mask["pt"]);
+        if path.is_animated() {
+            self.report(&location, "animated mask path uses its first keyframe");
+        }
+        let opacity = Prop(&mask["o"]);
+        if opacity.is_animated() || first(&opacity.initial(), 100.0) != 100.0 {
+            self.report(&location, "mask opacity is not supported");
+        }
+        let expansion = Prop(&mask["x"]);
+        if expansion.is_animated() || first(&expansion.initial(), 0.0) != 0.0 {
+            self.report(&location, "mask expansion is not supported");
+        }
+        let mode = mask["mode"].as_str().unwrap_or("a");
+        if !matches!(mode, "a" | "s") {
+            self.report(&location, format!("mask mode '{}' applied as add", mode));
+        }
+        let Some(data) = path.initial_value().and_then(shapes::bezier_path) else {
+            self.report(&location, "mask without a path ignored");
+            return;
+        };
+        let shape = vidra_core::path::PathShape::new(data);
+        let (origin, _) = shape.content_rect(0.0);
+        let mut shape_mask = ShapeMask::new(
+            ShapeType::Path(shape),
+            origin.x - anchor.x,
+            origin.y - anchor.y,
+        );
+        shape_mask.inverted = (mode == "s") != (mask["inv"].as_bool() == Some(true));
+        node.shape_mask = Some(shape_mask);
+    }
+
+    /// A precomp's layers as children of `node`, clipped to the precomp's size.
+    fn precomp(&mut self, node: &mut Layer, json: &'a Value, offset: Point2D, timing: Timing) {
+        let location = node.id.0.clone();
+        let ref_id = json["refId"].as_str().unwrap_or_default();
+        let Some(layers) = self
+            .assets
+            .get(ref_id)
+            .and_then(|asset| asset["layers"].as_array())
+        else {
+            self.report(&location, format!("precomp '{}' not found", ref_id));
+            return;
+        };
+        if self.expanding.contains(&ref_id) {
+            self.report(&location, format!("precomp '{}' contains itself", ref_id));
+            return;
+        }
+        self.expanding.push(ref_id);
+        let comp = Comp {
+            layers,
+            timing,
+            prefix: format!("{}/", node.id),
+            offset,
+            nested: true,
+        };
+        node.children = self.composition(&comp);
+        self.expanding.pop();
+
+        if let (Some(w), Some(h)) = (json["w"].as_f64(), json["h"].as_f64()) {
+            let clip = ShapeType::Rect {
+                width: w,
+                height: h,
+                corner_radius: 0.0,
+            };
+            node.shape_mask = Some(ShapeMask::new(clip, offset.x, offset.y));
+        }
+    }
+
+    /// A solid layer's rectangle.
+    fn solid(&mut self, json: &Value, location: &str, offset: Point2D) -> Option<Layer> {
+        let color = json["sc"]
+            .as_str()
+            .and_then(|hex| Color::from_hex(hex).ok())
+            .unwrap_or(Color::BLACK);
+        let shape = ShapeType::Rect {
+            width: json["sw"].as_f64().unwrap_or(0.0),
+            height: json["sh"].as_f64().unwrap_or(0.0),
+            corner_radius: 0.0,
+        };
+        let content = LayerContent::Shape {
+            shape,
+            fill: Some(color.into()),
+            stroke: None,
+            stroke_width: 0.0,
+        };
+        Some(self.content_layer(location, "solid", content, offset, Point2D::zero()))
+    }
+
+    /// An image layer's picture, registering its asset on first use.
+    fn image(&mut self, json: &Value, location: &str, offset: Point2D) -> Option<Layer> {
+        let ref_id = json["refId"].as_str().unwrap_or_default();
+        let Some(asset) = self.assets.get(ref_id).copied() else {
+            self.report(location, format!("image '{}' not found", ref_id));
+            return None;
+        };
+        let asset_id = AssetId::new(ref_id);
+        if !self.images.iter().any(|a| a.id == asset_id) {
+            let file = asset["p"].as_str().unwrap_or_default();
+            let path = if asset["e"].as_f64() == Some(1.0) || file.starts_with("data:") {
+                file.to_string()
+            } else {
+                format!("{}{}", asset["u"].as_str().unwrap_or_default(), file)
+            };
+            self.images
+                .push(Asset::new(asset_id.clone(), AssetType::Image, path).with_name(ref_id));
+        }
+        let content = LayerContent::Image { asset_id };
+        Some(self.content_layer(location, "image", content, offset, Point2D::zero()))
+    }
+
+    /// A text layer's first text document as text content. Lottie places text on its
+    /// baseline; the text's box is raised by an approximate ascent to match.
+    fn text(&mut self, json: &Value, location: &str, offset: Point2D) -> Option<Layer> {
+        let data = &json["t"];
+        let documents = data["d"]["k"].as_array()?;
+        if documents.len() > 1 {
+            self.report(location, "animated text uses its first keyframe");
+        }
+        if data["a"].as_array().is_some_and(|a| !a.is_empty()) {
+            self.report(location, "text animators are not supported");
+        }
+        if data["p"].get("m").is_some() {
+            self.report(location, "text on a path is not supported");
+        }
+        let doc = &documents.first()?["s"];
+        if doc.get("sz").is_some() {
+            self.report(location, "paragraph text is laid out as point text");
+        }
+        let font_size = doc["s"].as_f64().unwrap_or(12.0);
+        let font = doc["f"].as_str().unwrap_or_default();
+        let font_family = self.fonts.get(font).copied().unwrap_or(font).to_string();
+        let color = shapes::color(&numbers_or(&doc["fc"], &[0.0, 0.0, 0.0]));
+        let (justify, align) = match doc["j"].as_f64().unwrap_or(0.0) as i64 {
+            1 => (1.0, TextAlign::Right),
+            2 => (0.5, TextAlign::Center),
+            _ => (0.0, TextAlign::Left),
+        };
+        // Tracking is in thousandths of an em.
+        let style = TextStyle {
+            align,
+            letter_spacing: doc["tr"].as_f64().unwrap_or(0.0) / 1000.0 * font_size,
+            ..TextStyle::default()
+        };
+        let content = LayerContent::Text {
+            text: doc["t"].as_str().unwrap_or_default().replace('\r', "\n"),
+            font_family,
+            font_size,
+            color,
+            style,
+            animators: Vec::new(),
+        };
+        let offset = Point2D::new(offset.x, offset.y - font_size * 0.8);
+        Some(self.content_layer(
+            location,
+            "text",
+            content,
+            offset,
+            Point2D::new(justify, 0.0),
+        ))
+    }
+
+    fn content_layer(
+        &mut self,
+        location: &str,
+        name: &str,
+        content: LayerContent,
+        position: Point2D,
+        anchor: Point2D,
+    ) -> Layer {
+        let mut layer = Layer::new(self.unique_id(format!("{}/{}", location, name)), content);
+        layer.transform.position = position;
+        layer.transform.anchor = anchor;
+        layer
+    }
+}
+
+fn layer_name(json: &Value) -> String {
+    match json["nm"].as_str() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => format!("Layer {}", json["ind"].as_f64().unwrap_or(0.0)),
+    }
+}
+
+fn layer_type_name(ty: i64) -> String {
+    match ty {
+        6 => "audio".into(),
+        7 | 9 => "video".into(),
+        8 => "image sequence".into(),
+        10 => "image placeholder".into(),
+        11 => "guide".into(),
+        12 => "adjustment".into(),
+        13 => "camera".into(),
+        14 => "light".into(),
+        15 => "data".into(),
+        other => format!("type {}", other),
+    }
+}
+
+/// The initial anchor point of a transform.
+fn anchor_of(ks: &Value) -> Point2D {
+    let a = Prop(&ks["a"]).initial();
+    Point2D::new(first(&a, 0.0), a.get(1).copied().unwrap_or(0.0))
+}
+
+fn negate(p: Point2D) -> Point2D {
+    Point2D::new(-p.x, -p.y)
+}
+
+fn first(values: &[f64], default: f64) -> f64 {
+    values.first().copied().unwrap_or(default)
+}
+
+fn numbers_or(value: &Value, default: &[f64]) -> Vec<f64> {
+    let numbers = anim::numbers(value);
+    if numbers.is_empty() {
+        default.to_vec()
+    } else {
+        numbers
+    }
+}
+
+/// Whether a layer rotates, which track mattes do not follow.
+fn is_rotated(layer: &Layer) -> bool {
+    layer.transform.rotation != 0.0
+        || layer
+            .animations
+            .iter()
+            .any(|a| a.property == AnimatableProperty::Rotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use vidra_core::types::Easing;
+
+    fn animation(layers: Value, assets: Value) -> LottieImport {
+        let json = json!({
+            "v": "5.7.0", "fr": 10, "ip": 0, "op": 20, "w": 200, "h": 100, "nm": "test",
+            "assets": assets,
+            "fonts": {"list": [{"fName": "Inter-Bold", "fFamily": "Inter"}]},
+            "layers": layers
+        });
+        import(&json.to_string()).unwrap()
+    }
+
+    fn transform(p: Value, a: Value) -> Value {
+        json!({"p": {"a": 0, "k": p}, "a": {"a": 0, "k": a}, "s": {"a": 0, "k": [100, 100]},
+               "r": {"a": 0, "k": 0}, "o": {"a": 0, "k": 100}})
+    }
+
+    fn messages(import: &LottieImport) -> Vec<String> {
+        import.issues.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_shape_layer_places_paths_by_anchor_point() {
+        let import = animation(
+            json!([{
+                "ty": 4, "nm": "Dot", "ind": 1, "ip": 0, "op": 20, "st": 0,
+                "ks": transform(json!([100, 50]), json!([10, 10])),
+                "shapes": [{"ty": "gr", "nm": "Circle", "it": [
+                    {"ty": "el", "p": {"a": 0, "k": [10, 10]}, "s": {"a": 0, "k": [20, 20]}},
+                    {"ty": "fl", "nm": "Fill", "c": {"a": 0, "k": [1, 0, 0, 1]},
+                     "o": {"a": 0, "k": 50}, "r": 1},
+                    {"ty": "tr", "p": {"a": 0, "k": [0, 0]}, "a": {"a": 0, "k": [0, 0]},
+                     "s": {"a": 0, "k": [100, 100]}, "r": {"a": 0, "k": 0},
+                     "o": {"a": 0, "k": 100}}
+                ]}]
+            }]),
+            json!([]),
+        );
+        assert!(import.issues.is_empty(), "{:?}", messages(&import));
+        let node = &import.layers[0];
+        assert_eq!(node.id.0, "Dot");
+        assert_eq!(node.transform.position, Point2D::new(100.0, 50.0));
+        assert_eq!(node.transform.anchor, Point2D::zero());
+        // The untransformed group folds away; its fill is drawn by one path layer.
+        let fill = &node.children[0];
+        assert_eq!(fill.id.0, "Dot/Circle/Fill");
+        assert_eq!(fill.transform.position, Point2D::new(-10.0, -10.0));
+        let LayerContent::Shape {
+            fill: Some(paint),
+            stroke: None,
+            ..
+        } = &fill.content
+        else {
+            panic!("expected a filled shape, got {:?}", fill.content);
+        };
+        assert_eq!(paint.as_solid(), Some(Color::rgba(1.0, 0.0, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn test_parents_timing_and_eased_keyframes() {
+        let mut child = transform(json!([0, 0]), json!([0, 0]));
+        child["p"] = json!({"a": 1, "k": [
+            {"t": 0, "s": [0, 0], "o": {"x": 0.42, "y": 0}, "i": {"x": 0.58, "y": 1}},
+            {"t": 10, "s": [100, 0]}
+        ]});
+        let import = animation(
+            json!([
+                {"ty": 4, "nm": "Box", "ind": 1, "parent": 2, "ip": 5, "op": 20, "st": 5,
+                 "ks": child,
+                 "shapes": [
+                    {"ty": "rc", "p": {"a": 0, "k": [0, 0]}, "s": {"a": 0, "k": [10, 10]},
+                     "r": {"a": 0, "k": 0}},
+                    {"ty": "st", "nm": "Stroke", "c": {"a": 0, "k": [0, 0, 1, 1]},
+                     "o": {"a": 0, "k": 100}, "w": {"a": 0, "k": 2}, "lc": 1, "lj": 1}
+                 ]},
+                {"ty": 3, "nm": "Rig", "ind": 2, "ip": 0, "op": 20, "st": 0,
+                 "ks": transform(json!([50, 50]), json!([0, 0]))}
+            ]),
+            json!([]),
+        );
+        // The null itself draws nothing; it wraps its child as a parent.
+        assert_eq!(import.layers.len(), 1);
+        let rig = &import.layers[0];
+        assert_eq!(rig.id.0, "Box (parent Rig)");
+        assert_eq!(rig.transform.position, Point2D::new(50.0, 50.0));
+        let boxed = &rig.children[0];
+        let x = boxed
+            .animations
+            .iter()
+            .find(|a| a.property == AnimatableProperty::PositionX)
+            .unwrap();
+        // Start time shifts the keyframes by half a second; the easing is kept.
+        assert_eq!(x.keyframes.len(), 2);
+        assert_eq!(x.keyframes[0].time.as_seconds(), 0.5);
+        assert_eq!(x.keyframes[1].time.as_seconds(), 1.5);
+        assert_eq!(
+            x.keyframes[1].easing,
+            Easing::CubicBezier(0.42, 0.0, 0.58, 1.0)
+        );
+        let mid = x.evaluate(Duration::from_seconds(1.0)).unwrap();
+        assert!((mid - 50.0).abs() < 1e-6);
+        // Hidden before its in point.
+        let opacity = boxed
+            .animations
+            .iter()
+            .find(|a| a.property == AnimatableProperty::Opacity)
+            .unwrap();
+        assert_eq!(opacity.evaluate(Duration::from_seconds(0.4)), Some(0.0));
+        assert_eq!(opacity.evaluate(Duration::from_seconds(0.5)), Some(1.0));
+    }
+
+    #[test]
+    fn test_precomps_masks_and_mattes() {
+        let import = animation(
+            json!([
+                {"ty": 1, "nm": "Matte", "ind": 1, "td": 1, "ip": 0, "op": 20, "st": 0,
+                 "ks": transform(json!([0, 0]), json!([0, 0])),
+                 "sc": "#ffffff", "sw": 100, "sh": 100},
+                {"ty": 0, "nm": "Comp", "ind": 2, "tt": 1, "refId": "comp_0", "w": 200,
+                 "h": 100, "ip": 0, "op": 20, "st": 0,
+                 "ks": transform(json!([0, 0]), json!([0, 0])),
+                 "hasMask": true,
+                 "masksProperties": [{"mode": "s", "inv": false,
+                    "pt": {"a": 0, "k": {"c": true, "v": [[10, 10], [30, 10], [30, 30]],
+                           "i": [[0, 0], [0, 0], [0, 0]], "o": [[0, 0], [0, 0], [0, 0]]}},
+                    "o": {"a": 0, "k": 100}, "x": {"a": 0, "k": 0}}]}
+            ]),
+            json!([{"id": "comp_0", "layers": [
+                {"ty": 5, "nm": "Title", "ind": 1, "ip": 0, "op": 20, "st": 0,
+                 "ks": transform(json!([20, 40]), json!([0, 0])),
+                 "t": {"d": {"k": [{"t": 0, "s": {"t": "Hi", "s": 20, "f": "Inter-Bold",
+                                                   "fc": [1, 1, 1], "j": 2, "tr": 50}}]},
+                       "a": []}}
+            ]}]),
+        );
+        assert_eq!(
+            messages(&import),
+            vec!["Comp: mask replaces the precomp's clipping to its size"]
+        );
+        let (comp, matte) = (&import.layers[0], &import.layers[1]);
+        assert_eq!(matte.id.0, "Matte");
+        assert!(!matte.visible);
+        assert_eq!(comp.mask, Some(LayerId::new("Matte")));
+        let mask = comp.shape_mask.as_ref().unwrap();
+        assert!(mask.inverted);
+        assert_eq!(mask.position, Point2D::new(10.0, 10.0));
+
+        let title = &comp.children[0];
+        assert_eq!(title.id.0, "Comp/Title");
+        let text = &title.children[0];
+        let LayerContent::Text {
+            text,
+            font_family,
+            style,
+            ..
+        } = &text.content
+        else {
+            panic!("expected text");
+        };
+        assert_eq!((text.as_str(), font_family.as_str()), ("Hi", "Inter"));
+        assert_eq!(
+            (style.align, style.letter_spacing),
+            (TextAlign::Center, 1.0)
+        );
+        assert_eq!(title.children[0].transform.anchor, Point2D::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn test_unsupported_features_are_reported() {
+        let import = animation(
+            json!([
+                {"ty": 4, "nm": "Shapes", "ind": 1, "ip": 0, "op": 20, "st": 0, "ao": 1,
+                 "ks": transform(json!([0, 0]), json!([0, 0])),
+                 "ef": [{"nm": "Gaussian Blur"}],
+                 "shapes": [
+                    {"ty": "rp", "nm": "Repeater 1"},
+                    {"ty": "sh", "ks": {"a": 1, "k": [
+                        {"t": 0, "s": [{"c": false, "v": [[0, 0], [10, 0]],
+                                        "i": [[0, 0], [0, 0]], "o": [[0, 0], [0, 0]]}]},
+                        {"t": 10, "s": [{"c": false, "v": [[0, 0], [20, 0]],
+                                         "i": [[0, 0], [0, 0]], "o": [[0, 0], [0, 0]]}]}
+                    ]}},
+                    {"ty": "st", "c": {"a": 0, "k": [1, 1, 1, 1]}, "o": {"a": 0, "k": 100},
+                     "w": {"a": 0, "k": 1}}
+                 ]},
+                {"ty": 13, "nm": "Camera", "ind": 2, "ip": 0, "op": 20, "st": 0}
+            ]),
+            json!([]),
+        );
+        assert_eq!(
+            messages(&import),
+            vec![
+                "Camera: camera layers are not supported",
+                "Shapes: auto-orient is not supported",
+                "Shapes: effect 'Gaussian Blur' is not supported",
+                "Shapes/Repeater 1: repeaters are not supported",
+                "Shapes/sh: animated geometry uses its first keyframe",
+            ]
+        );
+        // What could be imported still is.
+        assert_eq!(import.layers.len(), 1);
+        assert_eq!(import.layers[0].children.len(), 1);
+    }
+
+    #[test]
+    fn test_rejects_json_that_is_not_lottie() {
+        let err = import(r#"{"layers": []}"#).unwrap_err();
+        assert!(err.to_string().contains("missing 'fr'"), "{}", err);
+        assert!(import("not json").is_err());
+    }
+}
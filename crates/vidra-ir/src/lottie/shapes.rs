@@ -0,0 +1,655 @@
+//! Lottie shape layers: groups of paths painted by fills, strokes and gradients.
+//!
+//! A paint draws every path listed above it in its group, including those in the
+//! groups above it, and whatever is listed first is drawn on top. Each paint becomes
+//! one path shape layer drawing the paths it applies to, and groups with a transform
+//! become empty layers around theirs.
+
+use serde_json::Value;
+use vidra_core::paint::{ColorStop, Gradient};
+use vidra_core::path::{FillRule, LineCap, LineJoin, PathData, PathSegment, PathShape};
+use vidra_core::types::ShapeType;
+use vidra_core::{Color, Paint, Point2D};
+
+use super::anim::{numbers, Prop, Timing};
+use super::{first, Importer};
+use crate::animation::AnimatableProperty;
+use crate::layer::{Layer, LayerContent};
+
+/// Control point distance of a cubic Bézier quarter circle, relative to its radius.
+const KAPPA: f64 = 0.552_284_749_8;
+
+/// The layers drawing a shape layer's `items`, bottom to top, in a parent whose origin
+/// sits at `offset` in Lottie coordinates.
+pub(super) fn shape_layers(
+    importer: &mut Importer,
+    items: &[Value],
+    parent: &str,
+    offset: Point2D,
+    timing: Timing,
+) -> Vec<Layer> {
+    group(importer, items, &[], parent, offset, timing)
+}
+
+/// The layers of a group's `items`, bottom to top. `inherited` are the paints and trims
+/// listed after the group in its ancestors, which apply to its paths too.
+fn group(
+    importer: &mut Importer,
+    items: &[Value],
+    inherited: &[&Value],
+    parent: &str,
+    offset: Point2D,
+    timing: Timing,
+) -> Vec<Layer> {
+    let items: Vec<&Value> = items
+        .iter()
+        .filter(|item| item["hd"].as_bool() != Some(true))
+        .collect();
+    let mut paths: Vec<PathData> = Vec::new();
+    let mut top_first: Vec<Layer> = Vec::new();
+    for (k, item) in items.iter().enumerate() {
+        let ty = item["ty"].as_str().unwrap_or_default();
+        let location = format!("{}/{}", parent, item_name(item));
+        match ty {
+            "sh" | "rc" | "el" | "sr" => paths.extend(geometry(importer, item, &location)),
+            "fl" | "st" | "gf" | "gs" => {
+                let trim = last_trim(importer, &items[..k], &location);
+                top_first.extend(paint_layer(
+                    importer, item, &paths, trim, parent, offset, timing,
+                ));
+            }
+            "gr" => {
+                let mut below: Vec<&Value> = items[k + 1..]
+                    .iter()
+                    .copied()
+                    .filter(|i| is_modifier(i))
+                    .collect();
+                below.extend(inherited);
+                let mut layers = group_item(importer, item, &below, parent, offset, timing);
+                layers.reverse();
+                top_first.extend(layers);
+            }
+            "tm" | "tr" => {}
+            other => {
+                let what = match other {
+                    "mm" => "merge paths",
+                    "rp" => "repeaters",
+                    "rd" => "rounded corners",
+                    "op" => "offset paths",
+                    "pb" => "pucker & bloat",
+                    "tw" => "twist",
+                    "zz" => "zig zag",
+                    _ => "this shape item",
+                };
+                importer.report(&location, format!("{} are not supported", what));
+            }
+        }
+    }
+
+    // Paints of the ancestors draw all of the group's paths, below everything in it.
+    for (q, paint) in inherited.iter().enumerate() {
+        if is_paint(paint) {
+            let location = format!("{}/{}", parent, item_name(paint));
+            let trim = last_trim(importer, &inherited[..q], &location)
+                .or_else(|| last_trim(importer, &items, &location));
+            top_first.extend(paint_layer(
+                importer, paint, &paths, trim, parent, offset, timing,
+            ));
+        }
+    }
+    top_first.reverse();
+    top_first
+}
+
+/// A group's layers. Transforms that only move it are folded into the position of its
+/// layers; others get an empty layer around them.
+fn group_item(
+    importer: &mut Importer,
+    item: &Value,
+    inherited: &[&Value],
+    parent: &str,
+    offset: Point2D,
+    timing: Timing,
+) -> Vec<Layer> {
+    let name = format!("{}/{}", parent, item_name(item));
+    let items = item["it"].as_array().map_or(&[][..], Vec::as_slice);
+    let tr = items
+        .iter()
+        .rev()
+        .find(|i| i["ty"] == "tr")
+        .unwrap_or(&Value::Null);
+    let is = |prop: &Value, value: f64| {
+        let prop = Prop(prop);
+        !prop.is_animated() && prop.initial().iter().all(|v| *v == value)
+    };
+    let moves_only = is(&tr["s"], 100.0)
+        && is(&tr["r"], 0.0)
+        && is(&tr["o"], 100.0)
+        && is(&tr["sk"], 0.0)
+        && !Prop(&tr["p"]).is_animated()
+        && !Prop(&tr["a"]).is_animated()
+        && tr["p"]["s"].as_bool() != Some(true);
+    if moves_only {
+        let p = Prop(&tr["p"]).initial();
+        let a = Prop(&tr["a"]).initial();
+        let offset = Point2D::new(
+            offset.x + first(&p, 0.0) - first(&a, 0.0),
+            offset.y + p.get(1).copied().unwrap_or(0.0) - a.get(1).copied().unwrap_or(0.0),
+        );
+        return group(importer, items, inherited, &name, offset, timing);
+    }
+
+    let id = importer.unique_id(name);
+    let (mut node, anchor) = importer.transform_node(id, tr, offset, timing);
+    let opacity = importer.animate(
+        &mut node,
+        &tr["o"],
+        timing,
+        &[AnimatableProperty::Opacity],
+        |_, v| v / 100.0,
+    );
+    node.transform.opacity = first(&opacity, 100.0) / 100.0;
+    let parent = node.id.0.clone();
+    let children = group(
+        importer,
+        items,
+        inherited,
+        &parent,
+        Point2D::new(-anchor.x, -anchor.y),
+        timing,
+    );
+    if children.is_empty() {
+        importer.ids.remove(&parent);
+        return Vec::new();
+    }
+    node.children = children;
+    vec![node]
+}
+
+fn item_name(item: &Value) -> String {
+    match item["nm"].as_str() {
+        Some(name) if !name.is_empty() => name.to_string(),
+        _ => item["ty"].as_str().unwrap_or("item").to_string(),
+    }
+}
+
+fn is_paint(item: &Value) -> bool {
+    matches!(item["ty"].as_str(), Some("fl" | "st" | "gf" | "gs"))
+}
+
+fn is_modifier(item: &Value) -> bool {
+    is_paint(item) || item["ty"] == "tm"
+}
+
+/// The trim path nearest above a paint among `items`.
+fn last_trim<'v>(
+    importer: &mut Importer,
+    items: &[&'v Value],
+    location: &str,
+) -> Option<&'v Value> {
+    let mut trims = items.iter().rev().filter(|i| i["ty"] == "tm");
+    let trim = trims.next().copied();
+    if trims.next().is_some() {
+        importer.report(
+            location,
+            "only the nearest of several trim paths is applied",
+        );
+    }
+    trim
+}
+
+/// The path of a geometry item, with animated geometry at its first keyframe.
+fn geometry(importer: &mut Importer, item: &Value, location: &str) -> Option<PathData> {
+    let props: &[&str] = match item["ty"].as_str()? {
+        "sh" => &["ks"],
+        "rc" => &["p", "s", "r"],
+        "el" => &["p", "s"],
+        _ => &["p", "or", "ir", "r", "pt", "os", "is"],
+    };
+    if props.iter().any(|p| Prop(&item[*p]).is_animated()) {
+        importer.report(location, "animated geometry uses its first keyframe");
+    }
+    let value = |name: &str| Prop(&item[name]).initial();
+    let path = match item["ty"].as_str()? {
+        "sh" => bezier_path(Prop(&item["ks"]).initial_value()?)?,
+        "rc" => {
+            let (p, s) = (value("p"), value("s"));
+            rect(point(&p), point(&s), first(&value("r"), 0.0))
+        }
+        "el" => {
+            let (p, s) = (value("p"), value("s"));
+            ellipse(point(&p), point(&s))
+        }
+        _ => {
+            let roundness = first(&value("os"), 0.0) != 0.0 || first(&value("is"), 0.0) != 0.0;
+            if roundness {
+                importer.report(location, "star roundness is not supported");
+            }
+            let star = item["sy"].as_f64() != Some(2.0);
+            polystar(
+                point(&value("p")),
+                first(&value("pt"), 5.0).round().max(3.0) as usize,
+                first(&value("or"), 0.0),
+                star.then(|| first(&value("ir"), 0.0)),
+                first(&value("r"), 0.0),
+            )
+        }
+    };
+    Some(path)
+}
+
+fn point(values: &[f64]) -> Point2D {
+    Point2D::new(first(values, 0.0), values.get(1).copied().unwrap_or(0.0))
+}
+
+/// A Lottie bezier shape (`{"v": [...], "i": [...], "o": [...], "c": true}`) as a
+/// path. Tangents are relative to their vertex.
+pub(super) fn bezier_path(value: &Value) -> Option<PathData> {
+    let shape = match value {
+        Value::Array(items) => items.first()?,
+        other => other,
+    };
+    let list = |name: &str| -> Vec<Point2D> {
+        shape[name]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|p| point(&numbers(p)))
+            .collect()
+    };
+    let (vertices, ins, outs) = (list("v"), list("i"), list("o"));
+    let start = *vertices.first()?;
+    let tangent = |list: &[Point2D], i: usize| list.get(i).copied().unwrap_or_default();
+    let segment = |from: usize, to: usize| {
+        let (a, b) = (vertices[from], vertices[to]);
+        let (out, inn) = (tangent(&outs, from), tangent(&ins, to));
+        if out.x == 0.0 && out.y == 0.0 && inn.x == 0.0 && inn.y == 0.0 {
+            PathSegment::LineTo { x: b.x, y: b.y }
+        } else {
+            PathSegment::CubicTo {
+                x1: a.x + out.x,
+                y1: a.y + out.y,
+                x2: b.x + inn.x,
+                y2: b.y + inn.y,
+                x: b.x,
+                y: b.y,
+            }
+        }
+    };
+    let mut segments = vec![PathSegment::MoveTo {
+        x: start.x,
+        y: start.y,
+    }];
+    segments.extend((1..vertices.len()).map(|i| segment(i - 1, i)));
+    if shape["c"].as_bool() == Some(true) {
+        if vertices.len() > 1 {
+            segments.push(segment(vertices.len() - 1, 0));
+        }
+        segments.push(PathSegment::Close);
+    }
+    Some(PathData::new(segments))
+}
+
+/// A rectangle of `size` centered on `center`, with corners rounded by `radius`.
+fn rect(center: Point2D, size: Point2D, radius: f64) -> PathData {
+    let (x0, y0) = (center.x - size.x / 2.0, center.y - size.y / 2.0);
+    let (x1, y1) = (center.x + size.x / 2.0, center.y + size.y / 2.0);
+    let r = radius.min(size.x / 2.0).min(size.y / 2.0).max(0.0);
+    if r == 0.0 {
+        return PathData::new(vec![
+            PathSegment::MoveTo { x: x0, y: y0 },
+            PathSegment::LineTo { x: x1, y: y0 },
+            PathSegment::LineTo { x: x1, y: y1 },
+            PathSegment::LineTo { x: x0, y: y1 },
+            PathSegment::Close,
+        ]);
+    }
+    let k = r * (1.0 - KAPPA);
+    let corner = |x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64| PathSegment::CubicTo {
+        x1,
+        y1,
+        x2,
+        y2,
+        x,
+        y,
+    };
+    PathData::new(vec![
+        PathSegment::MoveTo { x: x0 + r, y: y0 },
+        PathSegment::LineTo { x: x1 - r, y: y0 },
+        corner(x1 - k, y0, x1, y0 + k, x1, y0 + r),
+        PathSegment::LineTo { x: x1, y: y1 - r },
+        corner(x1, y1 - k, x1 - k, y1, x1 - r, y1),
+        PathSegment::LineTo { x: x0 + r, y: y1 },
+        corner(x0 + k, y1, x0, y1 - k, x0, y1 - r),
+        PathSegment::LineTo { x: x0, y: y0 + r },
+        corner(x0, y0 + k, x0 + k, y0, x0 + r, y0),
+        PathSegment::Close,
+    ])
+}
+
+/// An ellipse of `size` centered on `center`.
+fn ellipse(center: Point2D, size: Point2D) -> PathData {
+    let (cx, cy) = (center.x, center.y);
+    let (rx, ry) = (size.x / 2.0, size.y / 2.0);
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let cubic = |x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64| PathSegment::CubicTo {
+        x1,
+        y1,
+        x2,
+        y2,
+        x,
+        y,
+    };
+    PathData::new(vec![
+        PathSegment::MoveTo { x: cx, y: cy - ry },
+        cubic(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy),
+        cubic(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry),
+        cubic(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy),
+        cubic(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry),
+        PathSegment::Close,
+    ])
+}
+
+/// A star with `points` tips, or a polygon when `inner` is `None`, with its first tip
+/// straight up before `rotation` degrees clockwise.
+fn polystar(
+    center: Point2D,
+    points: usize,
+    outer: f64,
+    inner: Option<f64>,
+    rotation: f64,
+) -> PathData {
+    let vertices = if inner.is_some() { points * 2 } else { points };
+    let mut segments = Vec::with_capacity(vertices + 1);
+    for i in 0..vertices {
+        let radius = match inner {
+            Some(inner) if i % 2 == 1 => inner,
+            _ => outer,
+        };
+        let angle = (rotation - 90.0 + 360.0 * i as f64 / vertices as f64).to_radians();
+        let (x, y) = (
+            center.x + radius * angle.cos(),
+            center.y + radius * angle.sin(),
+        );
+        segments.push(if i == 0 {
+            PathSegment::MoveTo { x, y }
+        } else {
+            PathSegment::LineTo { x, y }
+        });
+    }
+    segments.push(PathSegment::Close);
+    PathData::new(segments)
+}
+
+/// A Lottie color: components in 0–1, or in 0–255 from some older exporters.
+pub(super) fn color(values: &[f64]) -> Color {
+    let scale = if values.iter().take(3).any(|v| *v > 1.0) {
+        255.0
+    } else {
+        1.0
+    };
+    let c = |i: usize, default: f64| (values.get(i).copied().unwrap_or(default) / scale) as f32;
+    Color::rgba(c(0, 0.0), c(1, 0.0), c(2, 0.0), c(3, scale))
+}
+
+/// A path shape layer drawing `paths` with the fill or stroke `paint`.
+fn paint_layer(
+    importer: &mut Importer,
+    paint: &Value,
+    paths: &[PathData],
+    trim: Option<&Value>,
+    parent: &str,
+    offset: Point2D,
+    timing: Timing,
+) -> Option<Layer> {
+    if paths.is_empty() {
+        return None;
+    }
+    let id = importer.unique_id(format!("{}/{}", parent, item_name(paint)));
+    let location = id.0.clone();
+    let mut shape = PathShape::new(PathData::new(
+        paths.iter().flat_map(|p| p.segments.clone()).collect(),
+    ));
+    let mut layer = Layer::new(id, LayerContent::Empty);
+    let ty = paint["ty"].as_str().unwrap_or_default();
+    let is_stroke = matches!(ty, "st" | "gs");
+
+    // Opacity of the paint: folded into its colors, or the layer's when animated.
+    let opacity_prop = Prop(&paint["o"]);
+    let opacity = first(&opacity_prop.initial(), 100.0) / 100.0;
+    let fold = if opacity_prop.is_animated() {
+        importer.animate(
+            &mut layer,
+            &paint["o"],
+            timing,
+            &[AnimatableProperty::Opacity],
+            |_, v| v / 100.0,
+        );
+        1.0
+    } else {
+        opacity
+    };
+
+    let mut stroke_width = 0.0;
+    let mut width_animated = false;
+    if is_stroke {
+        let width = importer.animate(
+            &mut layer,
+            &paint["w"],
+            timing,
+            &[AnimatableProperty::StrokeWidth],
+            |_, v| v,
+        );
+        stroke_width = first(&width, 1.0);
+        width_animated = Prop(&paint["w"]).is_animated();
+        let style = &mut shape.stroke_style;
+        style.cap = match paint["lc"].as_f64().unwrap_or(2.0) as i64 {
+            1 => LineCap::Butt,
+            3 => LineCap::Square,
+            _ => LineCap::Round,
+        };
+        style.join = match paint["lj"].as_f64().unwrap_or(2.0) as i64 {
+            1 => LineJoin::Miter,
+            3 => LineJoin::Bevel,
+            _ => LineJoin::Round,
+        };
+        style.miter_limit = paint["ml"].as_f64().unwrap_or(4.0);
+        for dash in paint["d"].as_array().into_iter().flatten() {
+            if Prop(&dash["v"]).is_animated() {
+                importer.report(&location, "animated dashes use their first keyframe");
+            }
+            let value = first(&Prop(&dash["v"]).initial(), 0.0);
+            match dash["n"].as_str() {
+                Some("o") => style.dash_offset = value,
+                _ => style.dash.push(value),
+            }
+        }
+    } else if paint["r"].as_f64() == Some(2.0) {
+        shape.fill_rule = FillRule::EvenOdd;
+    }
+
+    if let Some(trim) = trim {
+        let values = importer.animate(
+            &mut layer,
+            &trim["s"],
+            timing,
+            &[AnimatableProperty::TrimStart],
+            |_, v| v / 100.0,
+        );
+        shape.trim.start = first(&values, 0.0) / 100.0;
+        let values = importer.animate(
+            &mut layer,
+            &trim["e"],
+            timing,
+            &[AnimatableProperty::TrimEnd],
+            |_, v| v / 100.0,
+        );
+        shape.trim.end = first(&values, 100.0) / 100.0;
+        let values = importer.animate(
+            &mut layer,
+            &trim["o"],
+            timing,
+            &[AnimatableProperty::TrimOffset],
+            |_, v| v / 360.0,
+        );
+        shape.trim.offset = first(&values, 0.0) / 360.0;
+        if trim["m"].as_f64() == Some(1.0) && paths.len() > 1 {
+            importer.report(
+                &location,
+                "paths trimmed simultaneously are trimmed as one path",
+            );
+        }
+    }
+
+    let (origin, size) = shape.content_rect(stroke_width);
+    let paint_value = match ty {
+        "fl" | "st" => {
+            let values = Prop(&paint["c"]).initial();
+            let mut color = color(&values);
+            color.a *= fold as f32;
+            if Prop(&paint["c"]).is_animated() {
+                if is_stroke {
+                    importer.report(&location, "animated stroke color uses its first value");
+                } else {
+                    let scale = if values.iter().take(3).any(|v| *v > 1.0) {
+                        255.0
+                    } else {
+                        1.0
+                    };
+                    importer.animate(
+                        &mut layer,
+                        &paint["c"],
+                        timing,
+                        &[
+                            AnimatableProperty::ColorR,
+                            AnimatableProperty::ColorG,
+                            AnimatableProperty::ColorB,
+                        ],
+                        |_, v| v / scale,
+                    );
+                }
+            }
+            Paint::Solid(color)
+        }
+        _ => gradient(importer, paint, &location, origin, size, fold),
+    };
+    let (fill, stroke) = if is_stroke {
+        (None, Some(paint_value))
+    } else {
+        (Some(paint_value), None)
+    };
+    layer.content = LayerContent::Shape {
+        shape: ShapeType::Path(shape),
+        fill,
+        stroke,
+        stroke_width,
+    };
+    // A stroke that changes width grows around its center.
+    if width_animated {
+        layer.transform.anchor = Point2D::new(0.5, 0.5);
+        layer.transform.position = Point2D::new(
+            origin.x + size.width / 2.0 + offset.x,
+            origin.y + size.height / 2.0 + offset.y,
+        );
+    } else {
+        layer.transform.anchor = Point2D::zero();
+        layer.transform.position = Point2D::new(origin.x + offset.x, origin.y + offset.y);
+    }
+    Some(layer)
+}
+
+/// A gradient fill or stroke placed in the shape's `origin`/`size` box, its alpha
+/// scaled by `opacity`.
+fn gradient(
+    importer: &mut Importer,
+    paint: &Value,
+    location: &str,
+    origin: Point2D,
+    size: vidra_core::Size2D,
+    opacity: f64,
+) -> Paint {
+    let animated = ["s", "e", "h", "a"]
+        .iter()
+        .any(|p| Prop(&paint[*p]).is_animated())
+        || Prop(&paint["g"]["k"]).is_animated();
+    if animated {
+        importer.report(location, "animated gradient uses its first keyframe");
+    }
+    let count = paint["g"]["p"].as_f64().unwrap_or(0.0) as usize;
+    let values = Prop(&paint["g"]["k"]).initial();
+    let colors: Vec<(f64, [f64; 3])> = values
+        .chunks_exact(4)
+        .take(count)
+        .map(|c| (c[0], [c[1], c[2], c[3]]))
+        .collect();
+    let alphas: Vec<(f64, f64)> = values
+        .get(count * 4..)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|a| (a[0], a[1]))
+        .collect();
+
+    // Color and alpha stops may sit at different offsets; a stop at each of them
+    // keeps both exactly.
+    let mut offsets: Vec<f64> = colors
+        .iter()
+        .map(|c| c.0)
+        .chain(alphas.iter().map(|a| a.0))
+        .collect();
+    offsets.sort_by(f64::total_cmp);
+    offsets.dedup();
+    let stops: Vec<ColorStop> = offsets
+        .iter()
+        .map(|&t| {
+            let rgb = [0, 1, 2].map(|i| interpolate(&colors, t, |c| c[i]));
+            let alpha = if alphas.is_empty() {
+                1.0
+            } else {
+                interpolate(&alphas, t, |a| *a)
+            };
+            let color = Color::rgba(
+                rgb[0] as f32,
+                rgb[1] as f32,
+                rgb[2] as f32,
+                (alpha * opacity) as f32,
+            );
+            ColorStop::new(t, color)
+        })
+        .collect();
+
+    let start = point(&Prop(&paint["s"]).initial());
+    let end = point(&Prop(&paint["e"]).initial());
+    let local = |p: Point2D| Point2D::new(p.x - origin.x, p.y - origin.y);
+    let placed = if paint["t"].as_f64() == Some(2.0) {
+        if first(&Prop(&paint["h"]).initial(), 0.0) != 0.0 {
+            importer.report(location, "radial gradient highlight is not supported");
+        }
+        let radius = (end.x - start.x).hypot(end.y - start.y);
+        Gradient::radial_around(local(start), radius, size, &stops)
+    } else {
+        Gradient::linear_between(local(start), local(end), size, &stops)
+    };
+    let last = stops.last().map_or(Color::TRANSPARENT, |s| s.color);
+    placed.map_or(Paint::Solid(last), Paint::Gradient)
+}
+
+/// The value at offset `t` of stops sorted by offset, linearly interpolated.
+fn interpolate<T>(stops: &[(f64, T)], t: f64, value: impl Fn(&T) -> f64) -> f64 {
+    let Some((first_offset, first_value)) = stops.first() else {
+        return 0.0;
+    };
+    if t <= *first_offset {
+        return value(first_value);
+    }
+    for pair in stops.windows(2) {
+        let ((t0, v0), (t1, v1)) = (&pair[0], &pair[1]);
+        if t <= *t1 {
+            let span = t1 - t0;
+            if span <= 0.0 {
+                return value(v1);
+            }
+            return value(v0) + (value(v1) - value(v0)) * (t - t0) / span;
+        }
+    }
+    value(&stops[stops.len() - 1].1)
+}
@@ -0,0 +1,122 @@
+//! Layer masks: track mattes read from another layer, and shape masks.
+
+use serde::{Deserialize, Serialize};
+use vidra_core::types::ShapeType;
+use vidra_core::Point2D;
+
+/// How a track matte's pixels decide where the masked layer shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatteMode {
+    /// Visible where the matte is opaque.
+    #[default]
+    Alpha,
+    /// Visible where the matte is transparent.
+    AlphaInverted,
+    /// Visible where the matte is bright (luminance times alpha).
+    Luma,
+    /// Visible where the matte is dark or transparent.
+    LumaInverted,
+}
+
+impl MatteMode {
+    pub fn is_alpha(&self) -> bool {
+        *self == MatteMode::Alpha
+    }
+
+    /// Whether coverage is flipped, so that areas the matte leaves empty are visible.
+    pub fn is_inverted(&self) -> bool {
+        matches!(self, MatteMode::AlphaInverted | MatteMode::LumaInverted)
+    }
+}
+
+/// A shape that clips its layer, in the layer's unscaled content pixels.
+///
+/// Its size and position are animatable (`maskX`, `maskY`, `maskWidth`, `maskHeight`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShapeMask {
+    pub shape: ShapeType,
+    /// Top-left corner of the shape's bounding box.
+    pub position: Point2D,
+    /// Show the layer outside the shape instead of inside it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inverted: bool,
+}
+
+impl ShapeMask {
+    pub fn new(shape: ShapeType, x: f64, y: f64) -> Self {
+        Self {
+            shape,
+            position: Point2D::new(x, y),
+            inverted: false,
+        }
+    }
+
+    /// Bounding box size of the shape.
+    pub fn size(&self) -> (f64, f64) {
+        match &self.shape {
+            ShapeType::Rect { width, height, .. } => (*width, *height),
+            ShapeType::Circle { radius } => (radius * 2.0, radius * 2.0),
+            ShapeType::Ellipse { rx, ry } => (rx * 2.0, ry * 2.0),
+            ShapeType::Path(path) => {
+                let (_, size) = path.content_rect(0.0);
+                (size.width, size.height)
+            }
+        }
+    }
+
+    /// Set the bounding box width. Circles keep their aspect and take it as diameter.
+    /// Paths keep the size of their geometry.
+    pub fn set_width(&mut self, value: f64) {
+        match &mut self.shape {
+            ShapeType::Rect { width, .. } => *width = value,
+            ShapeType::Circle { radius } => *radius = value / 2.0,
+            ShapeType::Ellipse { rx, .. } => *rx = value / 2.0,
+            ShapeType::Path(_) => {}
+        }
+    }
+
+    /// Set the bounding box height. Circles keep their aspect and take it as diameter.
+    /// Paths keep the size of their geometry.
+    pub fn set_height(&mut self, value: f64) {
+        match &mut self.shape {
+            ShapeType::Rect { height, .. } => *height = value,
+            ShapeType::Circle { radius } => *radius = value / 2.0,
+            ShapeType::Ellipse { ry, .. } => *ry = value / 2.0,
+            ShapeType::Path(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matte_mode_serde() {
+        let json = serde_json::to_string(&MatteMode::LumaInverted).unwrap();
+        assert_eq!(json, "\"luma-inverted\"");
+        let back: MatteMode = serde_json::from_str("\"alpha-inverted\"").unwrap();
+        assert_eq!(back, MatteMode::AlphaInverted);
+        assert!(back.is_inverted());
+    }
+
+    #[test]
+    fn test_shape_mask_resize() {
+        let mut mask = ShapeMask::new(ShapeType::Circle { radius: 10.0 }, 0.0, 0.0);
+        mask.set_width(50.0);
+        assert_eq!(mask.size(), (50.0, 50.0));
+
+        let mut mask = ShapeMask::new(
+            ShapeType::Rect {
+                width: 10.0,
+                height: 10.0,
+                corner_radius: 0.0,
+            },
+            0.0,
+            0.0,
+        );
+        mask.set_height(30.0);
+        assert_eq!(mask.size(), (10.0, 30.0));
+    }
+}
@@ -80,6 +80,9 @@ pub struct ProjectSettings {
     pub fps: f64,
     /// Background color.
     pub background: vidra_core::Color,
+    /// Motion blur for every layer that does not set its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motion_blur: Option<vidra_core::MotionBlur>,
 }
 
 impl ProjectSettings {
@@ -90,6 +93,7 @@ impl ProjectSettings {
             height: 1080,
             fps: 30.0,
             background: vidra_core::Color::BLACK,
+            motion_blur: None,
         }
     }
 
@@ -100,6 +104,7 @@ impl ProjectSettings {
             height: 1080,
             fps: 60.0,
             background: vidra_core::Color::BLACK,
+            motion_blur: None,
         }
     }
 
@@ -110,6 +115,7 @@ impl ProjectSettings {
             height: 2160,
             fps: 30.0,
             background: vidra_core::Color::BLACK,
+            motion_blur: None,
         }
     }
 
@@ -120,6 +126,7 @@ impl ProjectSettings {
             height,
             fps,
             background: vidra_core::Color::BLACK,
+            motion_blur: None,
         }
     }
 }
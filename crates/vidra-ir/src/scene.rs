@@ -88,7 +88,9 @@ mod tests {
         );
         let layer = Layer::new(
             LayerId::new("bg"),
-            LayerContent::Solid { color: Color::RED },
+            LayerContent::Solid {
+                color: Color::RED.into(),
+            },
         );
         scene.add_layer(layer);
         assert_eq!(scene.layers.len(), 1);
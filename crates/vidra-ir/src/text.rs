@@ -0,0 +1,311 @@
+//! Rich text styling: text boxes, inline spans, spacing, outlines and shadows, and
+//! animators that move characters, words or lines on their own.
+
+use serde::{Deserialize, Serialize};
+use vidra_core::Color;
+
+use crate::animation::Animation;
+
+/// Horizontal alignment of the lines in a text layer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Layout and decoration of a text layer. The default lays plain text out line by
+/// line, split only on `\n`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextStyle {
+    /// Wrap words onto new lines so no line is wider than this many pixels. The layer
+    /// is then exactly this wide, which keeps aligned text anchored as it changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_left")]
+    pub align: TextAlign,
+    /// Extra pixels between neighbouring characters. May be negative.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub letter_spacing: f64,
+    /// Distance between baselines as a multiple of the font size (1.3 when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_height: Option<f64>,
+    /// Draw the whole layer in bold.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<TextStroke>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<TextShadow>,
+    /// Character ranges styled differently from the rest of the layer. Later spans
+    /// win where they overlap.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<TextSpan>,
+}
+
+/// An outline drawn around every glyph, under the fill.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextStroke {
+    pub color: Color,
+    /// Outline thickness in pixels, measured outward from the glyph edge.
+    pub width: f64,
+}
+
+/// A drop shadow cast by the glyphs and their outline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextShadow {
+    pub color: Color,
+    #[serde(default)]
+    pub offset_x: f64,
+    #[serde(default)]
+    pub offset_y: f64,
+    /// Blur radius in pixels; 0 gives a hard shadow.
+    #[serde(default)]
+    pub blur: f64,
+}
+
+/// Style overrides for the characters `start..end` (character indices, counting
+/// `\n`). Unset fields keep the layer's style.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+}
+
+/// What a text animator animates as one piece.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextUnit {
+    /// A user-perceived character (grapheme cluster). Whitespace is not counted.
+    #[default]
+    Char,
+    /// A run of characters between whitespace.
+    Word,
+    /// A line as laid out, after wrapping.
+    Line,
+}
+
+/// Animates the characters, words or lines of a text layer one unit at a time.
+///
+/// Every unit runs the same `animations`, each `stagger` seconds after the one
+/// before it, and holds their first keyframes until its turn comes. They animate the
+/// unit relative to where layout put it: `PositionX`/`PositionY` (or `Position`) move it
+/// by that many pixels, `ScaleX`/`ScaleY` (or `Scale`) and `Rotation` (degrees) turn it
+/// about its center, `Opacity` fades it and `BlurRadius` blurs it. `TextRangeStart`,
+/// `TextRangeEnd` and `TextRangeOffset` animate `range` instead, once for the layer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextAnimator {
+    #[serde(default)]
+    pub unit: TextUnit,
+    pub animations: Vec<Animation>,
+    /// Seconds between the start of one unit and the next. Negative values run from
+    /// the last unit back to the first.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub stagger: f64,
+    #[serde(default, skip_serializing_if = "TextRange::is_full")]
+    pub range: TextRange,
+}
+
+/// The units a text animator applies to, in fractions of the layer's units (`0` is
+/// the first unit's start and `1` the last one's end). A unit is selected when its
+/// middle lies between `start + offset` and `end + offset`; the others are drawn as if
+/// the animator were not there.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextRange {
+    pub start: f64,
+    pub end: f64,
+    pub offset: f64,
+}
+
+impl Default for TextRange {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            end: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+impl TextRange {
+    /// Whether every unit is selected, whatever the count.
+    pub fn is_full(&self) -> bool {
+        self.start + self.offset <= 0.0 && self.end + self.offset >= 1.0
+    }
+
+    /// Whether unit `index` of `count` is selected.
+    pub fn contains(&self, index: usize, count: usize) -> bool {
+        let middle = (index as f64 + 0.5) / count as f64 - self.offset;
+        self.start.min(self.end) <= middle && middle <= self.start.max(self.end)
+    }
+}
+
+fn is_left(align: &TextAlign) -> bool {
+    *align == TextAlign::Left
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+impl TextStyle {
+    /// Line height used when none is set, as a multiple of the font size.
+    pub const DEFAULT_LINE_HEIGHT: f64 = 1.3;
+
+    /// Whether this is the default style, i.e. the layer is plain text.
+    pub fn is_plain(&self) -> bool {
+        *self == TextStyle::default()
+    }
+
+    pub fn line_height(&self) -> f64 {
+        self.line_height.unwrap_or(Self::DEFAULT_LINE_HEIGHT)
+    }
+}
+
+/// Split inline markup out of `source`, returning the plain text and its spans.
+///
+/// Understands `<b>…</b>`, `<color=#rrggbb>…</color>`, `<size=N>…</size>` and
+/// `<font=Family>…</font>`. Tags may nest; an unclosed tag runs to the end of the text.
+/// Anything else that looks like a tag, including closers with no opener, is kept as
+/// literal text.
+pub fn parse_markup(source: &str) -> (String, Vec<TextSpan>) {
+    let mut text = String::with_capacity(source.len());
+    let mut chars = 0;
+    let mut spans: Vec<TextSpan> = Vec::new();
+    // Tag name and span index of every open tag, innermost last.
+    let mut open: Vec<(&str, usize)> = Vec::new();
+    let mut rest = source;
+
+    while let Some(lt) = rest.find('<') {
+        let literal = &rest[..lt];
+        text.push_str(literal);
+        chars += literal.chars().count();
+        rest = &rest[lt..];
+
+        let tag = rest[1..].find('>').map(|gt| &rest[1..gt + 1]);
+        let consumed = match tag {
+            Some(closer) if closer.starts_with('/') => {
+                let name = &closer[1..];
+                match open.iter().rposition(|(open_name, _)| *open_name == name) {
+                    Some(pos) => {
+                        let (_, index) = open.remove(pos);
+                        spans[index].end = chars;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Some(tag) => match markup_span(tag, chars) {
+                Some((name, span)) => {
+                    open.push((name, spans.len()));
+                    spans.push(span);
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        };
+
+        if consumed {
+            rest = &rest[tag.map_or(0, str::len) + 2..];
+        } else {
+            text.push('<');
+            chars += 1;
+            rest = &rest[1..];
+        }
+    }
+    text.push_str(rest);
+    chars += rest.chars().count();
+
+    for (_, index) in open {
+        spans[index].end = chars;
+    }
+    spans.retain(|span| span.end > span.start);
+    (text, spans)
+}
+
+/// The span an opening tag starts at character `start`, with the name its closer uses.
+fn markup_span(tag: &str, start: usize) -> Option<(&str, TextSpan)> {
+    let span = TextSpan {
+        start,
+        end: start,
+        ..TextSpan::default()
+    };
+    let (name, value) = match tag.split_once('=') {
+        Some((name, value)) => (name, Some(value.trim_matches('"'))),
+        None => (tag, None),
+    };
+    let span = match (name, value) {
+        ("b", None) => TextSpan {
+            bold: Some(true),
+            ..span
+        },
+        ("color", Some(hex)) if hex.is_ascii() => TextSpan {
+            color: Some(Color::from_hex(hex).ok()?),
+            ..span
+        },
+        ("size", Some(size)) => TextSpan {
+            size: Some(size.parse().ok().filter(|s: &f64| *s > 0.0)?),
+            ..span
+        },
+        ("font", Some(family)) if !family.is_empty() => TextSpan {
+            font_family: Some(family.to_string()),
+            ..span
+        },
+        _ => return None,
+    };
+    Some((name, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_markup() {
+        let (text, spans) = parse_markup("Say <b>hi <color=#ff0000>there</color></b>!");
+        assert_eq!(text, "Say hi there!");
+        assert_eq!(spans.len(), 2);
+        assert_eq!((spans[0].start, spans[0].end), (4, 12));
+        assert_eq!(spans[0].bold, Some(true));
+        assert_eq!((spans[1].start, spans[1].end), (7, 12));
+        assert_eq!(spans[1].color, Some(Color::rgb(1.0, 0.0, 0.0)));
+
+        let (text, spans) = parse_markup("<size=48>big\nline");
+        assert_eq!(text, "big\nline");
+        assert_eq!(
+            (spans[0].start, spans[0].end, spans[0].size),
+            (0, 8, Some(48.0))
+        );
+
+        let (text, spans) = parse_markup("a < b </b> <i>x</i> <color=nope>y");
+        assert_eq!(text, "a < b </b> <i>x</i> <color=nope>y");
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_text_style_serde_defaults() {
+        assert!(TextStyle::default().is_plain());
+        assert_eq!(serde_json::to_string(&TextStyle::default()).unwrap(), "{}");
+
+        let style: TextStyle = serde_json::from_str(
+            r#"{"max_width":400,"align":"center","stroke":{"color":{"r":0,"g":0,"b":0,"a":1},"width":2}}"#,
+        )
+        .unwrap();
+        assert_eq!(style.max_width, Some(400.0));
+        assert_eq!(style.align, TextAlign::Center);
+        assert_eq!(style.stroke.as_ref().map(|s| s.width), Some(2.0));
+        assert_eq!(style.line_height(), TextStyle::DEFAULT_LINE_HEIGHT);
+    }
+}
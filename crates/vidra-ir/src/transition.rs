@@ -1,18 +1,59 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use vidra_core::types::Easing;
-use vidra_core::Duration;
+use vidra_core::{Duration, Point2D};
+
+/// The way a directional transition moves across the frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Left,
+    #[default]
+    Right,
+    Up,
+    Down,
+}
 
 /// Defines the visual effect used to transition into this scene from the previous one.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TransitionType {
     /// A smooth crossfade from the previous scene to the current scene.
     Crossfade,
-    /// The new scene slides in from a specified direction ("up", "down", "left", "right").
-    Slide { direction: String },
-    /// The new scene pushes the old scene out in a specified direction.
-    Push { direction: String },
-    /// The new scene wipes in from a specified direction.
-    Wipe { direction: String },
+    /// The new scene slides in over the old one, moving in `direction`.
+    Slide { direction: Direction },
+    /// The new scene pushes the old scene out, both moving in `direction`.
+    Push { direction: Direction },
+    /// The new scene is uncovered in place by an edge moving in `direction`.
+    Wipe { direction: Direction },
+    /// The old scene zooms by `scale` while fading out, and the new scene fades in while
+    /// zooming from `1 / scale` to its natural size. A `scale` below 1 zooms out.
+    Zoom { scale: f64 },
+    /// The new scene is revealed inside a circle growing from `center` (a fraction of
+    /// the canvas size) until it covers the frame.
+    Iris { center: Point2D },
+    /// The new scene is revealed by a hand sweeping clockwise around the center of the
+    /// frame, starting at `angle` degrees clockwise from twelve o'clock.
+    ClockWipe { angle: f64 },
+    /// A crossfade through a blur that peaks at `radius` pixels halfway.
+    BlurDissolve { radius: f64 },
+    /// Bands of the frame tear sideways and split into color channels, by up to
+    /// `intensity` of the frame width halfway, while the scenes cut over.
+    Glitch { intensity: f64 },
+    /// A VidraFX `@transition`, compiled to WGSL, with the old and new scenes bound as
+    /// its inputs. Needs a GPU; other backends crossfade.
+    Shader {
+        wgsl_source: String,
+        /// Values of the transition's parameters, in declaration order.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        params: Vec<f64>,
+    },
+    /// A transition provided by a registered `TransitionPlugin`.
+    Plugin {
+        name: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        params: HashMap<String, f64>,
+    },
 }
 
 /// A scene transition definition.
@@ -25,3 +66,20 @@ pub struct Transition {
     /// The easing curve of the transition.
     pub easing: Easing,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_direction_keeps_its_string_form() {
+        let wipe: TransitionType = serde_json::from_str(r#"{"Wipe":{"direction":"up"}}"#).unwrap();
+        assert_eq!(
+            wipe,
+            TransitionType::Wipe {
+                direction: Direction::Up
+            }
+        );
+        assert!(serde_json::from_str::<TransitionType>(r#"{"Wipe":{"direction":"upp"}}"#).is_err());
+    }
+}
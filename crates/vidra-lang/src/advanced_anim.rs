@@ -1,69 +1,20 @@
+use vidra_core::path::{PathData, PathSegment};
 use vidra_core::Duration;
 use vidra_ir::animation::{AnimatableProperty, Animation, Keyframe};
 
-pub fn compile_spring(
-    property: AnimatableProperty,
-    from: f64,
-    to: f64,
-    stiffness: f64,
-    damping: f64,
-    initial_velocity: f64,
-) -> Animation {
-    let mut anim = Animation::new(property);
-
-    // Euler integration parameters
-    let mass = 1.0;
-    let dt = 1.0 / 60.0;
-
-    let mut position = from;
-    let mut velocity = initial_velocity;
-    let mut t = 0.0;
-
-    anim.add_keyframe(Keyframe::new(Duration::from_seconds(0.0), position));
-
-    let epsilon = 0.001;
-    let mut stable_frames = 0;
-    let max_duration = 10.0; // Max 10 seconds timeout
-
-    while t < max_duration && stable_frames < 5 {
-        t += dt;
-
-        // F = -kX - cv
-        let displacement = position - to;
-        let spring_force = -stiffness * displacement;
-        let damping_force = -damping * velocity;
-
-        let acceleration = (spring_force + damping_force) / mass;
-        velocity += acceleration * dt;
-        position += velocity * dt;
-
-        anim.add_keyframe(Keyframe::new(Duration::from_seconds(t), position));
-
-        if displacement.abs() < epsilon && velocity.abs() < epsilon {
-            stable_frames += 1;
-        } else {
-            stable_frames = 0;
-        }
-    }
-
-    if anim.keyframes.len() < 2 {
-        anim.add_keyframe(Keyframe::new(Duration::from_seconds(dt), to));
-    }
-
-    // Hard set the final value to the exact `to` value to avoid drift
-    if let Some(last) = anim.keyframes.last_mut() {
-        last.value = to;
-    }
+use evalexpr::*;
 
-    anim
+/// Check that `expr` parses, returning the parser's message if it does not.
+pub fn validate_expression(expr: &str) -> Result<(), String> {
+    build_operator_tree::<DefaultNumericTypes>(expr)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
 }
 
-use evalexpr::*;
 pub fn compile_expression(
     property: AnimatableProperty,
     expr_str: &str,
     duration: f64,
-    audio_amp_samples: Option<&[f64]>,
 ) -> Animation {
     let mut anim = Animation::new(property);
 
@@ -89,14 +40,6 @@ pub fn compile_expression(
         let _ = context.set_value("p".to_string(), evalexpr::Value::Float(p));
         let _ = context.set_value("T".to_string(), evalexpr::Value::Float(duration));
 
-        if let Some(samples) = audio_amp_samples {
-            let idx = ((t / dt).round() as usize).min(samples.len().saturating_sub(1));
-            let amp = samples.get(idx).copied().unwrap_or(0.0);
-            let _ = context.set_value("audio_amp".to_string(), evalexpr::Value::Float(amp));
-        } else {
-            let _ = context.set_value("audio_amp".to_string(), evalexpr::Value::Float(0.0));
-        }
-
         let value = match compiled.eval_with_context(&context) {
             Ok(v) => v.as_number().unwrap_or(0.0),
             _ => 0.0,
@@ -109,11 +52,96 @@ pub fn compile_expression(
     anim
 }
 
-pub fn compile_path_animations(_path_data: &str, _duration: f64) -> (Animation, Animation) {
-    // Stubbed until needed
-    let mut anim_x = Animation::new(AnimatableProperty::PositionX);
-    let mut anim_y = Animation::new(AnimatableProperty::PositionY);
-    anim_x.add_keyframe(Keyframe::new(Duration::from_seconds(0.0), 0.0));
-    anim_y.add_keyframe(Keyframe::new(Duration::from_seconds(0.0), 0.0));
-    (anim_x, anim_y)
+/// A `Position` animation moving at constant speed along the first subpath of SVG path
+/// data, with one keyframe per segment end and the curves kept as spatial tangents.
+pub fn compile_path_animation(path_data: &str, duration: f64) -> Result<Animation, String> {
+    let path = PathData::parse_svg(path_data).map_err(|e| e.to_string())?;
+
+    // Each segment as cubic control points.
+    let mut curves: Vec<[[f64; 2]; 4]> = Vec::new();
+    let mut start = None;
+    let mut current = [0.0, 0.0];
+    for segment in &path.segments {
+        let (c1, c2, end) = match *segment {
+            PathSegment::MoveTo { x, y } => {
+                if start.is_some() {
+                    break;
+                }
+                start = Some([x, y]);
+                current = [x, y];
+                continue;
+            }
+            PathSegment::LineTo { x, y } => (current, [x, y], [x, y]),
+            PathSegment::QuadTo { x1, y1, x, y } => (
+                [
+                    current[0] + 2.0 / 3.0 * (x1 - current[0]),
+                    current[1] + 2.0 / 3.0 * (y1 - current[1]),
+                ],
+                [x + 2.0 / 3.0 * (x1 - x), y + 2.0 / 3.0 * (y1 - y)],
+                [x, y],
+            ),
+            PathSegment::CubicTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => ([x1, y1], [x2, y2], [x, y]),
+            PathSegment::Close => {
+                let end = start.unwrap_or(current);
+                (current, end, end)
+            }
+        };
+        curves.push([current, c1, c2, end]);
+        current = end;
+        if matches!(segment, PathSegment::Close) {
+            break;
+        }
+    }
+    let Some(origin) = start else {
+        return Err("path has no points".to_string());
+    };
+
+    let lengths: Vec<f64> = curves.iter().map(curve_length).collect();
+    let total: f64 = lengths.iter().sum();
+
+    let mut anim = Animation::new(AnimatableProperty::Position);
+    anim.add_keyframe(Keyframe::vector(Duration::zero(), origin.to_vec()));
+    let mut travelled = 0.0;
+    for (i, (curve, length)) in curves.iter().zip(&lengths).enumerate() {
+        let [p0, p1, p2, p3] = *curve;
+        anim.keyframes[i].out_tangent = Some([p1[0] - p0[0], p1[1] - p0[1]]);
+        travelled += length;
+        let time = if total > 0.0 {
+            duration * travelled / total
+        } else {
+            duration * (i + 1) as f64 / curves.len() as f64
+        };
+        let mut keyframe = Keyframe::vector(Duration::from_seconds(time), p3.to_vec());
+        keyframe.in_tangent = Some([p2[0] - p3[0], p2[1] - p3[1]]);
+        anim.keyframes.push(keyframe);
+    }
+    Ok(anim)
+}
+
+/// Length of a cubic Bézier, measured along 64 chords.
+fn curve_length(curve: &[[f64; 2]; 4]) -> f64 {
+    let [p0, p1, p2, p3] = *curve;
+    let point = |u: f64| {
+        let v = 1.0 - u;
+        let (a, b, c, d) = (v * v * v, 3.0 * v * v * u, 3.0 * v * u * u, u * u * u);
+        [
+            a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+            a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+        ]
+    };
+    let mut length = 0.0;
+    let mut previous = p0;
+    for k in 1..=64 {
+        let p = point(k as f64 / 64.0);
+        length += (p[0] - previous[0]).hypot(p[1] - previous[1]);
+        previous = p;
+    }
+    length
 }
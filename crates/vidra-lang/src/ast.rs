@@ -14,6 +14,8 @@ pub struct ProjectNode {
     pub variables: Vec<VarDefNode>,
     pub scenes: Vec<SceneNode>,
     pub components: Vec<ComponentNode>,
+    /// Project-wide settings such as `motionBlur(...)`.
+    pub settings: Vec<PropertyNode>,
     pub span: Span,
 }
 
@@ -128,10 +130,12 @@ pub enum LayerBlockItem {
         else_branch: Option<Vec<LayerBlockItem>>,
         span: Span,
     },
+    /// `transition("wipe", 1s, ease: easeInOut, direction: left)`
     Transition {
         transition_type: String,
         duration: ValueNode,
-        easing: Option<String>,
+        /// `ease` and the transition's own options.
+        args: Vec<NamedArg>,
         span: Span,
     },
     AnimationStagger {
@@ -165,6 +169,10 @@ pub enum LayerContentNode {
         path: ValueNode,
         args: Vec<NamedArg>,
     },
+    Lottie {
+        path: ValueNode,
+        args: Vec<NamedArg>,
+    },
     Audio {
         path: ValueNode,
         args: Vec<NamedArg>,
@@ -197,6 +205,11 @@ pub enum LayerContentNode {
         source: ValueNode,
         args: Vec<NamedArg>,
     },
+    /// `plugin(name, prop: value, ...)`: content drawn by a layer plugin.
+    Plugin {
+        name: ValueNode,
+        args: Vec<NamedArg>,
+    },
     /// A custom component instance
     Component {
         name: String,
@@ -235,6 +248,11 @@ pub enum ValueNode {
     Identifier(String),
     BrandReference(String), // `@brand.key`
     Array(Vec<ValueNode>),
+    /// A value built by a function, e.g. `linearGradient(90, #000000, #ffffff)`.
+    Call {
+        name: String,
+        args: Vec<ValueNode>,
+    },
 }
 
 /// A property assignment or animation on a layer.
@@ -1,7 +1,13 @@
 use crate::ast::*;
+use crate::compiler::{
+    gradient_stop_index, BLEND_MODES, BOOLEANS, COLOR_SPACES, DIRECTIONS, EASINGS, EFFECTS,
+    FILL_RULES, GRADIENTS, LAYER_STYLES, LINE_CAPS, LINE_JOINS, STEP_POSITIONS, STROKE_POSITIONS,
+    TEXT_ALIGNS, TEXT_UNITS, TRANSITIONS,
+};
 use crate::lexer::Span;
 
 use std::collections::HashMap;
+use vidra_core::plugin::{PluginKind, PluginSignature};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DiagnosticSeverity {
@@ -41,6 +47,10 @@ pub struct TypeChecker {
     components: HashMap<String, ComponentNode>,
     used_components: std::collections::HashSet<String>,
     current_scope_layers: std::collections::HashSet<String>,
+    /// Name of the layer whose properties are being checked, for diagnostics.
+    current_layer: Option<String>,
+    /// Plugins that plugin effects, layers and transitions must name, when known.
+    plugins: Option<Vec<PluginSignature>>,
 }
 
 impl TypeChecker {
@@ -51,9 +61,19 @@ impl TypeChecker {
             components: HashMap::new(),
             used_components: std::collections::HashSet::new(),
             current_scope_layers: std::collections::HashSet::new(),
+            current_layer: None,
+            plugins: None,
         }
     }
 
+    /// Check plugin effects, layers and transitions against `plugins` (see
+    /// `PluginRegistry::signatures`). Without them plugin names are not checked, since
+    /// plugins are only registered at render time.
+    pub fn with_plugins(mut self, plugins: Vec<PluginSignature>) -> Self {
+        self.plugins = Some(plugins);
+        self
+    }
+
     /// Run the type checker on the project AST. Returns diagnostics, Error if there are any hard errors.
     pub fn check(mut self, project: &ProjectNode) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
         self.check_project(project);
@@ -107,6 +127,10 @@ impl TypeChecker {
             }
         }
 
+        for setting in &proj.settings {
+            self.check_property(setting);
+        }
+
         // Check scenes
         for scene in &proj.scenes {
             self.check_scene(scene);
@@ -167,19 +191,58 @@ impl TypeChecker {
                 }
             }
             crate::ast::LayerBlockItem::Transition {
-                duration: _,
-                easing,
-                span: _,
-                transition_type: _,
+                transition_type,
+                duration,
+                args,
+                span,
             } => {
-                if let Some(_ease) = easing {
-                    // Could check easing string valid
+                self.expect_duration_or_number(duration, span);
+                let allowed = TRANSITIONS
+                    .iter()
+                    .find(|(n, _)| n == transition_type)
+                    .map(|(_, options)| *options);
+                if allowed.is_none() && transition_type != "shader" {
+                    let options: Vec<_> =
+                        args.iter().filter(|a| a.name != "ease").cloned().collect();
+                    self.check_plugin(PluginKind::Transition, transition_type, &options, span);
+                }
+                for arg in args {
+                    match (arg.name.as_str(), &arg.value) {
+                        ("ease", ValueNode::String(name)) => {
+                            if !EASINGS.iter().any(|(n, _)| n == name) {
+                                self.type_error(format!("unknown easing: {}", name), &arg.span);
+                            }
+                        }
+                        ("ease", value) => self.expect_easing(value, &arg.span),
+                        (name, _) if allowed.is_some_and(|options| !options.contains(&name)) => {
+                            self.type_error(
+                                format!(
+                                    "transition(\"{}\") has no option '{}'",
+                                    transition_type, name
+                                ),
+                                &arg.span,
+                            );
+                        }
+                        ("direction", value) => self.expect_name(value, &DIRECTIONS, &arg.span),
+                        ("center", value) => self.expect_point(value, &arg.span),
+                        ("fx", value) if transition_type == "shader" => {
+                            self.expect_string(value, &arg.span)
+                        }
+                        (_, value) => self.expect_number(value, &arg.span),
+                    }
                 }
             }
             crate::ast::LayerBlockItem::ComponentUse { .. } => {
                 // Ignore for now
             }
-            crate::ast::LayerBlockItem::AnimationStagger { animations, .. } => {
+            crate::ast::LayerBlockItem::AnimationStagger {
+                args, animations, ..
+            } => {
+                for arg in args {
+                    if arg.name == "by" {
+                        self.expect_name(&arg.value, &TEXT_UNITS, &arg.span);
+                    }
+                }
                 for prop in animations {
                     self.check_property(prop);
                 }
@@ -198,9 +261,11 @@ impl TypeChecker {
 
         self.check_layer_content(&layer.content, &layer.span);
 
+        let outer_layer = self.current_layer.replace(layer.name.clone());
         for prop in &layer.properties {
             self.check_property(prop);
         }
+        self.current_layer = outer_layer;
 
         for child in &layer.children {
             self.check_layer_block_item(child);
@@ -214,8 +279,13 @@ impl TypeChecker {
                 for arg in args {
                     match arg.name.as_str() {
                         "font" => self.expect_string(&arg.value, &arg.span),
-                        "size" => self.expect_number(&arg.value, &arg.span),
-                        "color" => self.expect_color(&arg.value, &arg.span),
+                        "size" | "maxWidth" | "letterSpacing" | "lineHeight" | "strokeWidth"
+                        | "shadowX" | "shadowY" | "shadowBlur" => {
+                            self.expect_number(&arg.value, &arg.span)
+                        }
+                        "color" | "stroke" | "shadow" => self.expect_color(&arg.value, &arg.span),
+                        "align" => self.expect_name(&arg.value, &TEXT_ALIGNS, &arg.span),
+                        "bold" => self.expect_name(&arg.value, &BOOLEANS, &arg.span),
                         _ => self.type_error(
                             format!("unknown property '{}' for text layer", arg.name),
                             &arg.span,
@@ -223,8 +293,26 @@ impl TypeChecker {
                     }
                 }
             }
-            LayerContentNode::Image { path, args: _ } => {
+            LayerContentNode::Image { path, args } => {
                 self.expect_string(path, span);
+                let svg = match path {
+                    ValueNode::String(path) => Some(path.to_ascii_lowercase().ends_with(".svg")),
+                    _ => None,
+                };
+                for arg in args {
+                    match arg.name.as_str() {
+                        "element" | "groups" if svg == Some(false) => self.type_error(
+                            format!("'{}' only applies to SVG images", arg.name),
+                            &arg.span,
+                        ),
+                        "element" => self.expect_string(&arg.value, &arg.span),
+                        "groups" => self.expect_name(&arg.value, &BOOLEANS, &arg.span),
+                        _ => self.type_error(
+                            format!("unknown property '{}' for image layer", arg.name),
+                            &arg.span,
+                        ),
+                    }
+                }
             }
             LayerContentNode::Spritesheet { path, args } => {
                 self.expect_string(path, span);
@@ -244,6 +332,15 @@ impl TypeChecker {
                     }
                 }
             }
+            LayerContentNode::Lottie { path, args } => {
+                self.expect_string(path, span);
+                for arg in args {
+                    self.type_error(
+                        format!("unknown property '{}' for lottie layer", arg.name),
+                        &arg.span,
+                    );
+                }
+            }
             LayerContentNode::Video { path, args: _ } => {
                 self.expect_string(path, span);
             }
@@ -279,17 +376,32 @@ impl TypeChecker {
                 self.expect_string(audio_source, span);
             }
             LayerContentNode::Solid { color } => {
-                self.expect_color(color, span);
+                self.expect_paint(color, span);
             }
             LayerContentNode::Shape { shape_type, args } => {
                 for arg in args {
                     match arg.name.as_str() {
-                        "fill" => self.expect_color(&arg.value, &arg.span),
-                        "stroke" => self.expect_color(&arg.value, &arg.span),
+                        "fill" => self.expect_paint(&arg.value, &arg.span),
+                        "stroke" => self.expect_paint(&arg.value, &arg.span),
                         "strokeWidth" => self.expect_number(&arg.value, &arg.span),
                         "width" | "height" | "radius" | "rx" | "ry" => {
                             self.expect_number(&arg.value, &arg.span)
                         }
+                        "d" => self.expect_string(&arg.value, &arg.span),
+                        "fillRule" => self.expect_name(&arg.value, &FILL_RULES, &arg.span),
+                        "cap" => self.expect_name(&arg.value, &LINE_CAPS, &arg.span),
+                        "join" => self.expect_name(&arg.value, &LINE_JOINS, &arg.span),
+                        "miterLimit" | "dashOffset" | "trimStart" | "trimEnd" | "trimOffset" => {
+                            self.expect_number(&arg.value, &arg.span)
+                        }
+                        "dash" => match &arg.value {
+                            ValueNode::Array(items) => {
+                                for item in items {
+                                    self.expect_number(item, &arg.span);
+                                }
+                            }
+                            other => self.expect_number(other, &arg.span),
+                        },
                         _ => self.type_error(
                             format!("unknown property '{}' for shape {}", arg.name, shape_type),
                             &arg.span,
@@ -367,6 +479,12 @@ impl TypeChecker {
                     }
                 }
             }
+            LayerContentNode::Plugin { name, args } => match name {
+                ValueNode::Identifier(name) | ValueNode::String(name) => {
+                    self.check_plugin(PluginKind::Layer, name, args, span)
+                }
+                other => self.expect_string(other, span),
+            },
             LayerContentNode::Slot => {}
             LayerContentNode::Empty => {}
         }
@@ -423,9 +541,40 @@ impl TypeChecker {
                     "blurRadius",
                     "brightness",
                     "brightnessLevel",
+                    "mask.x",
+                    "maskX",
+                    "mask.y",
+                    "maskY",
+                    "maskWidth",
+                    "maskHeight",
+                    "maskFeather",
+                    "shadow.x",
+                    "shadowX",
+                    "shadow.y",
+                    "shadowY",
+                    "shadowBlur",
+                    "shadowSpread",
+                    "innerShadow.x",
+                    "innerShadowX",
+                    "innerShadow.y",
+                    "innerShadowY",
+                    "innerShadowBlur",
+                    "innerShadowSpread",
+                    "glowBlur",
+                    "glowSpread",
+                    "outlineWidth",
+                    "bevelSize",
+                    "bevelDepth",
+                    "bevelAngle",
+                    "trimStart",
+                    "trimEnd",
+                    "trimOffset",
+                    "gradientAngle",
                 ];
 
-                if !valid_props.contains(&property_name.as_str()) {
+                if !valid_props.contains(&property_name.as_str())
+                    && gradient_stop_index(property_name).is_none()
+                {
                     self.type_error(
                         format!("cannot animate unknown property '{}'", property_name),
                         span,
@@ -434,15 +583,29 @@ impl TypeChecker {
 
                 for arg in args {
                     match arg.name.as_str() {
-                        "from" => self.expect_number(&arg.value, &arg.span),
-                        "to" => self.expect_number(&arg.value, &arg.span),
+                        "from" | "to" => match property_name.as_str() {
+                            "color" => self.expect_color(&arg.value, &arg.span),
+                            "position" | "scale" => self.expect_point(&arg.value, &arg.span),
+                            _ => self.expect_number(&arg.value, &arg.span),
+                        },
+                        "space" | "colorSpace" => {
+                            self.expect_name(&arg.value, &COLOR_SPACES, &arg.span)
+                        }
+                        "autoOrient" => self.expect_name(&arg.value, &BOOLEANS, &arg.span),
                         "duration" => self.expect_duration_or_number(&arg.value, &arg.span),
                         "delay" => self.expect_duration_or_number(&arg.value, &arg.span),
-                        "ease" | "easing" => self.expect_identifier(&arg.value, &arg.span),
+                        "ease" | "easing" => self.expect_easing(&arg.value, &arg.span),
                         "stiffness" => self.expect_number(&arg.value, &arg.span),
                         "damping" => self.expect_number(&arg.value, &arg.span),
+                        "mass" => self.expect_number(&arg.value, &arg.span),
                         "initialVelocity" | "velocity" => self.expect_number(&arg.value, &arg.span),
-                        "expr" | "expression" => self.expect_string(&arg.value, &arg.span),
+                        "expr" | "expression" => {
+                            self.expect_string(&arg.value, &arg.span);
+                            if let ValueNode::String(expr) = &arg.value {
+                                let normalized = crate::compiler::normalize_expression(expr);
+                                self.check_expression(&normalized, &arg.span);
+                            }
+                        }
                         "audio" => self.expect_string(&arg.value, &arg.span),
                         "path" => self.expect_string(&arg.value, &arg.span),
                         _ => self.type_error(
@@ -451,9 +614,23 @@ impl TypeChecker {
                         ),
                     }
                 }
+
+                if args.iter().any(|arg| arg.name == "path") {
+                    for arg in args {
+                        if matches!(arg.name.as_str(), "ease" | "easing") {
+                            self.type_error(
+                                "a motion path moves at constant speed and takes no easing",
+                                &arg.span,
+                            );
+                        }
+                    }
+                }
             }
             PropertyNode::FunctionCall {
-                name, span, args, ..
+                name,
+                span,
+                args,
+                named_args,
             } => {
                 match name.as_str() {
                     // Core transform-ish properties
@@ -486,14 +663,121 @@ impl TypeChecker {
                             self.type_error("anchor(x, y) expects 2 numeric arguments", span);
                         }
                     }
-                    // Effects / masks / presets
-                    "effect" | "mask" | "preset" => {
+                    "motionBlur" => {
+                        match args.first() {
+                            None | Some(ValueNode::Number(_)) => {}
+                            Some(ValueNode::Identifier(id)) if id == "off" => {}
+                            Some(_) => self.type_error(
+                                "motionBlur expects a shutter angle in degrees or 'off'",
+                                span,
+                            ),
+                        }
+                        for arg in named_args {
+                            match arg.name.as_str() {
+                                "shutter" | "samples" => self.expect_number(&arg.value, &arg.span),
+                                _ => self.type_error(
+                                    format!("unknown motionBlur parameter '{}'", arg.name),
+                                    &arg.span,
+                                ),
+                            }
+                        }
+                    }
+                    "mask" => {
+                        let shape = match args.first() {
+                            None => {
+                                self.type_error(
+                                    "mask expects a layer name or a shape (rect, circle, ellipse)",
+                                    span,
+                                );
+                                None
+                            }
+                            Some(ValueNode::Identifier(id))
+                                if matches!(id.as_str(), "rect" | "circle" | "ellipse") =>
+                            {
+                                Some(id.as_str())
+                            }
+                            Some(_) => None,
+                        };
+                        let shape_params: &[&str] = match shape {
+                            Some("rect") => &["x", "y", "width", "height", "cornerRadius"],
+                            Some("circle") => &["x", "y", "radius"],
+                            Some(_) => &["x", "y", "rx", "ry"],
+                            None => &[],
+                        };
+                        let modes: &[&str] = if shape.is_some() {
+                            &["alpha", "alphaInverted"]
+                        } else {
+                            &["alpha", "alphaInverted", "luma", "lumaInverted"]
+                        };
+                        for arg in named_args {
+                            match arg.name.as_str() {
+                                "mode" => match &arg.value {
+                                    ValueNode::Identifier(m) if modes.contains(&m.as_str()) => {}
+                                    _ => self.type_error(
+                                        format!("mask mode must be one of: {}", modes.join(", ")),
+                                        &arg.span,
+                                    ),
+                                },
+                                "feather" => self.expect_number(&arg.value, &arg.span),
+                                p if shape_params.contains(&p) => {
+                                    self.expect_number(&arg.value, &arg.span)
+                                }
+                                _ => self.type_error(
+                                    format!("unknown mask parameter '{}'", arg.name),
+                                    &arg.span,
+                                ),
+                            }
+                        }
+                    }
+                    "blend" => match args.first() {
+                        Some(ValueNode::Identifier(mode))
+                            if BLEND_MODES.iter().any(|(n, _)| n == mode) => {}
+                        _ => {
+                            let names: Vec<_> = BLEND_MODES.iter().map(|(n, _)| *n).collect();
+                            self.type_error(
+                                format!("blend expects one of: {}", names.join(", ")),
+                                span,
+                            );
+                        }
+                    },
+                    // Effects / presets
+                    "effect" | "preset" => {
                         if args.is_empty() {
                             self.type_error(
                                 format!("'{}' expects at least 1 argument", name),
                                 span,
                             );
                         }
+                        if let (
+                            "effect",
+                            Some(ValueNode::Identifier(effect) | ValueNode::String(effect)),
+                        ) = (name.as_str(), args.first())
+                        {
+                            if let Some((_, options)) =
+                                LAYER_STYLES.iter().find(|(n, _)| n == effect)
+                            {
+                                for arg in named_args {
+                                    match (arg.name.as_str(), &arg.value) {
+                                        (name, _) if !options.contains(&name) => self.type_error(
+                                            format!("effect({}) has no option '{}'", effect, name),
+                                            &arg.span,
+                                        ),
+                                        ("color" | "highlight" | "shadow", value) => {
+                                            self.expect_color(value, &arg.span)
+                                        }
+                                        ("position", value) => {
+                                            self.expect_name(value, &STROKE_POSITIONS, &arg.span)
+                                        }
+                                        (_, value) => self.expect_number(value, &arg.span),
+                                    }
+                                }
+                            } else if !EFFECTS.contains(&effect.as_str()) {
+                                self.check_plugin(PluginKind::Effect, effect, named_args, span);
+                                for arg in named_args {
+                                    self.expect_number(&arg.value, &arg.span);
+                                }
+                            }
+                        }
                     }
                     // Layout constraints
                     "center" | "pin" | "below" | "above" | "leftOf" | "rightOf" | "fill" => {
@@ -537,12 +821,52 @@ impl TypeChecker {
                     }
                     if a.expr.trim().is_empty() {
                         self.type_error("set action must have an expression", &a.span);
+                    } else {
+                        self.check_expression(&a.expr, &a.span);
                     }
                 }
             }
         }
     }
 
+    /// Check that a plugin of `kind` named `name` is registered and takes `args`.
+    /// Does nothing when the registered plugins are not known.
+    fn check_plugin(&mut self, kind: PluginKind, name: &str, args: &[NamedArg], span: &Span) {
+        let Some(plugins) = &self.plugins else {
+            return;
+        };
+        let Some(plugin) = plugins.iter().find(|p| p.kind == kind && p.name == name) else {
+            self.type_error(
+                format!("no {} plugin named '{}' is registered", kind, name),
+                span,
+            );
+            return;
+        };
+        if plugin.params.is_empty() {
+            return;
+        }
+        let unknown: Vec<_> = args
+            .iter()
+            .filter(|arg| !plugin.params.contains(&arg.name))
+            .collect();
+        for arg in unknown {
+            self.type_error(
+                format!("{} plugin '{}' has no parameter '{}'", kind, name, arg.name),
+                &arg.span,
+            );
+        }
+    }
+
+    fn check_expression(&mut self, expr: &str, span: &Span) {
+        if let Err(err) = crate::advanced_anim::validate_expression(expr) {
+            let message = match &self.current_layer {
+                Some(layer) => format!("invalid expression in layer '{}': {}", layer, err),
+                None => format!("invalid expression: {}", err),
+            };
+            self.type_error(message, span);
+        }
+    }
+
     // --- Type Assertions ---
 
     fn get_type_name(&self, value: &ValueNode) -> &'static str {
@@ -554,6 +878,8 @@ impl TypeChecker {
             ValueNode::Identifier(_) => "Identifier",
             ValueNode::BrandReference(_) => "BrandReference",
             ValueNode::Array(_) => "Array",
+            ValueNode::Call { name, .. } if GRADIENTS.iter().any(|(n, _)| n == name) => "Gradient",
+            ValueNode::Call { .. } => "Call",
         }
     }
 
@@ -570,6 +896,19 @@ impl TypeChecker {
         }
     }
 
+    /// Expect a number or a point `[x, y]`.
+    fn expect_point(&mut self, value: &ValueNode, span: &Span) {
+        match value {
+            ValueNode::Array(items) if items.len() == 2 => {
+                for item in items {
+                    self.expect_number(item, span);
+                }
+            }
+            ValueNode::Array(_) => self.type_error("a point is [x, y]", span),
+            other => self.expect_number(other, span),
+        }
+    }
+
     fn expect_string(&mut self, value: &ValueNode, span: &Span) {
         match value {
             ValueNode::String(_) | ValueNode::Identifier(_) | ValueNode::BrandReference(_) => {}
@@ -590,6 +929,65 @@ impl TypeChecker {
         }
     }
 
+    /// Expect a color or a gradient: up to the gradient's numeric parameters, then at
+    /// least two stops, each a color or `[color, offset]`.
+    fn expect_paint(&mut self, value: &ValueNode, span: &Span) {
+        let ValueNode::Call { name, args } = value else {
+            return self.expect_color(value, span);
+        };
+        let Some((_, max_params)) = GRADIENTS.iter().find(|(n, _)| n == name) else {
+            let names: Vec<_> = GRADIENTS.iter().map(|(n, _)| *n).collect();
+            return self.type_error(
+                format!("expected Color or one of: {}", names.join(", ")),
+                span,
+            );
+        };
+        let params = args
+            .iter()
+            .take(*max_params)
+            .take_while(|arg| matches!(arg, ValueNode::Number(_)))
+            .count();
+        let stops = &args[params..];
+        if stops.len() < 2 {
+            self.type_error(format!("{} needs at least two color stops", name), span);
+        }
+        for stop in stops {
+            match stop {
+                ValueNode::Array(items) if items.len() == 2 => {
+                    self.expect_color(&items[0], span);
+                    self.expect_number(&items[1], span);
+                }
+                ValueNode::Array(_) => {
+                    self.type_error("a color stop is [color, offset]", span);
+                }
+                other => self.expect_color(other, span),
+            }
+        }
+    }
+
+    /// Expect an easing name, `hold`, `cubicBezier(x1, y1, x2, y2)` or
+    /// `steps(n, position)`.
+    fn expect_easing(&mut self, value: &ValueNode, span: &Span) {
+        let ValueNode::Call { name, args } = value else {
+            return self.expect_identifier(value, span);
+        };
+        match (name.as_str(), args.as_slice()) {
+            ("cubicBezier", [x1, y1, x2, y2]) => {
+                for arg in [x1, y1, x2, y2] {
+                    self.expect_number(arg, span);
+                }
+            }
+            ("steps", [n]) => self.expect_number(n, span),
+            ("steps", [n, position]) => {
+                self.expect_number(n, span);
+                self.expect_name(position, &STEP_POSITIONS, span);
+            }
+            ("cubicBezier", _) => self.type_error("cubicBezier takes x1, y1, x2, y2", span),
+            ("steps", _) => self.type_error("steps takes a count and a position", span),
+            _ => self.type_error(format!("expected an easing, got {}(...)", name), span),
+        }
+    }
+
     fn expect_duration_or_number(&mut self, value: &ValueNode, span: &Span) {
         match value {
             ValueNode::Duration(_)
@@ -606,6 +1004,18 @@ impl TypeChecker {
         }
     }
 
+    /// Expect one of the identifiers in a VidraScript name table.
+    fn expect_name<T>(&mut self, value: &ValueNode, names: &[(&str, T)], span: &Span) {
+        match value {
+            ValueNode::Identifier(name) if names.iter().any(|(n, _)| n == name) => {}
+            ValueNode::BrandReference(_) => {}
+            _ => {
+                let names: Vec<_> = names.iter().map(|(n, _)| *n).collect();
+                self.type_error(format!("expected one of: {}", names.join(", ")), span);
+            }
+        }
+    }
+
     fn expect_identifier(&mut self, value: &ValueNode, span: &Span) {
         match value {
             ValueNode::Identifier(_) | ValueNode::BrandReference(_) => {}
@@ -1,16 +1,182 @@
 //! VidraScript compiler — AST → Vidra IR.
 
 use crate::ast::*;
-use vidra_core::{Color, VidraError};
-use vidra_ir::animation::{AnimatableProperty, Animation};
+use vidra_core::paint::{ColorStop, Gradient};
+use vidra_core::path::{FillRule, LineCap, LineJoin, PathData, PathShape};
+use vidra_core::types::{Easing, LayerEffect, ShapeType, StepPosition, StrokePosition};
+use vidra_core::{BlendMode, Color, ColorSpace, Paint, Point2D, VidraError};
+use vidra_ir::animation::{AnimatableProperty, Animation, Spring};
 use vidra_ir::asset::{Asset, AssetId, AssetType};
 use vidra_ir::layer::{Layer, LayerContent, LayerId};
+use vidra_ir::mask::{MatteMode, ShapeMask};
 use vidra_ir::project::{Project, ProjectSettings};
 use vidra_ir::scene::{Scene, SceneId};
+use vidra_ir::text::{self, TextAlign, TextAnimator, TextShadow, TextStroke, TextStyle, TextUnit};
+use vidra_ir::transition::{Direction, Transition, TransitionType};
 
 use std::collections::HashMap;
-use std::io::Read;
-use std::process::{Command, Stdio};
+
+/// VidraScript names of the blend modes accepted by `blend(...)`.
+pub(crate) const BLEND_MODES: [(&str, BlendMode); 17] = [
+    ("normal", BlendMode::Normal),
+    ("multiply", BlendMode::Multiply),
+    ("screen", BlendMode::Screen),
+    ("overlay", BlendMode::Overlay),
+    ("add", BlendMode::Add),
+    ("darken", BlendMode::Darken),
+    ("lighten", BlendMode::Lighten),
+    ("colorDodge", BlendMode::ColorDodge),
+    ("colorBurn", BlendMode::ColorBurn),
+    ("hardLight", BlendMode::HardLight),
+    ("softLight", BlendMode::SoftLight),
+    ("difference", BlendMode::Difference),
+    ("exclusion", BlendMode::Exclusion),
+    ("hue", BlendMode::Hue),
+    ("saturation", BlendMode::Saturation),
+    ("color", BlendMode::Color),
+    ("luminosity", BlendMode::Luminosity),
+];
+
+/// VidraScript gradient functions, with the number of leading numeric parameters each
+/// takes before its color stops.
+pub(crate) const GRADIENTS: [(&str, usize); 3] = [
+    ("linearGradient", 1),
+    ("radialGradient", 3),
+    ("conicGradient", 3),
+];
+
+/// VidraScript names of the easing presets. `cubicBezier(x1, y1, x2, y2)` and
+/// `steps(n, position)` build the others.
+pub(crate) const EASINGS: [(&str, Easing); 37] = [
+    ("linear", Easing::Linear),
+    ("easeIn", Easing::EaseIn),
+    ("easeOut", Easing::EaseOut),
+    ("easeInOut", Easing::EaseInOut),
+    ("easeInQuad", Easing::EaseIn),
+    ("easeOutQuad", Easing::EaseOut),
+    ("easeInOutQuad", Easing::EaseInOut),
+    ("cubicIn", Easing::CubicIn),
+    ("cubicOut", Easing::CubicOut),
+    ("cubicInOut", Easing::CubicInOut),
+    ("easeInCubic", Easing::CubicIn),
+    ("easeOutCubic", Easing::CubicOut),
+    ("easeInOutCubic", Easing::CubicInOut),
+    ("easeInQuart", Easing::EaseInQuart),
+    ("easeOutQuart", Easing::EaseOutQuart),
+    ("easeInOutQuart", Easing::EaseInOutQuart),
+    ("easeInQuint", Easing::EaseInQuint),
+    ("easeOutQuint", Easing::EaseOutQuint),
+    ("easeInOutQuint", Easing::EaseInOutQuint),
+    ("easeInSine", Easing::EaseInSine),
+    ("easeOutSine", Easing::EaseOutSine),
+    ("easeInOutSine", Easing::EaseInOutSine),
+    ("easeInExpo", Easing::EaseInExpo),
+    ("easeOutExpo", Easing::EaseOutExpo),
+    ("easeInOutExpo", Easing::EaseInOutExpo),
+    ("easeInCirc", Easing::EaseInCirc),
+    ("easeOutCirc", Easing::EaseOutCirc),
+    ("easeInOutCirc", Easing::EaseInOutCirc),
+    ("easeInBack", Easing::EaseInBack),
+    ("easeOutBack", Easing::EaseOutBack),
+    ("easeInOutBack", Easing::EaseInOutBack),
+    ("easeInElastic", Easing::EaseInElastic),
+    ("easeOutElastic", Easing::EaseOutElastic),
+    ("easeInOutElastic", Easing::EaseInOutElastic),
+    ("easeInBounce", Easing::EaseInBounce),
+    ("easeOutBounce", Easing::EaseOutBounce),
+    ("easeInOutBounce", Easing::EaseInOutBounce),
+];
+pub(crate) const STEP_POSITIONS: [(&str, StepPosition); 4] = [
+    ("jumpStart", StepPosition::JumpStart),
+    ("jumpEnd", StepPosition::JumpEnd),
+    ("jumpNone", StepPosition::JumpNone),
+    ("jumpBoth", StepPosition::JumpBoth),
+];
+
+/// VidraScript names of the `fillRule`, `cap` and `join` options of `shape(path, ...)`.
+pub(crate) const FILL_RULES: [(&str, FillRule); 2] = [
+    ("nonZero", FillRule::NonZero),
+    ("evenOdd", FillRule::EvenOdd),
+];
+pub(crate) const LINE_CAPS: [(&str, LineCap); 3] = [
+    ("butt", LineCap::Butt),
+    ("round", LineCap::Round),
+    ("square", LineCap::Square),
+];
+pub(crate) const BOOLEANS: [(&str, bool); 2] = [("true", true), ("false", false)];
+
+pub(crate) const COLOR_SPACES: [(&str, ColorSpace); 3] = [
+    ("oklab", ColorSpace::Oklab),
+    ("linear", ColorSpace::LinearRgb),
+    ("srgb", ColorSpace::Srgb),
+];
+pub(crate) const LINE_JOINS: [(&str, LineJoin); 3] = [
+    ("miter", LineJoin::Miter),
+    ("round", LineJoin::Round),
+    ("bevel", LineJoin::Bevel),
+];
+pub(crate) const TEXT_ALIGNS: [(&str, TextAlign); 3] = [
+    ("left", TextAlign::Left),
+    ("center", TextAlign::Center),
+    ("right", TextAlign::Right),
+];
+pub(crate) const TEXT_UNITS: [(&str, TextUnit); 3] = [
+    ("char", TextUnit::Char),
+    ("word", TextUnit::Word),
+    ("line", TextUnit::Line),
+];
+pub(crate) const DIRECTIONS: [(&str, Direction); 4] = [
+    ("left", Direction::Left),
+    ("right", Direction::Right),
+    ("up", Direction::Up),
+    ("down", Direction::Down),
+];
+
+/// The built-in transitions and the options each takes besides `ease`. `"shader"` takes
+/// its VidraFX source as `fx` and the source's parameters; any other name is a plugin.
+pub(crate) const TRANSITIONS: [(&str, &[&str]); 9] = [
+    ("crossfade", &[]),
+    ("slide", &["direction"]),
+    ("push", &["direction"]),
+    ("wipe", &["direction"]),
+    ("zoom", &["scale"]),
+    ("iris", &["center"]),
+    ("clockWipe", &["angle"]),
+    ("blurDissolve", &["radius"]),
+    ("glitch", &["intensity"]),
+];
+
+/// The built-in effects of `effect(name, ...)`; any other name is an effect plugin.
+pub(crate) const EFFECTS: [&str; 14] = [
+    "blur",
+    "grayscale",
+    "invert",
+    "brightness",
+    "contrast",
+    "saturation",
+    "hue_rotate",
+    "hueRotate",
+    "vignette",
+    "removeBackground",
+    "remove_background",
+    "remove-bg",
+    "lut",
+    "LUT",
+];
+
+/// The layer styles of `effect(name, options...)` and the options each takes.
+pub(crate) const LAYER_STYLES: [(&str, &[&str]); 5] = [
+    ("dropShadow", &["x", "y", "blur", "color", "spread"]),
+    ("innerShadow", &["x", "y", "blur", "color", "spread"]),
+    ("outerGlow", &["blur", "color", "spread"]),
+    ("stroke", &["width", "color", "position"]),
+    ("bevel", &["size", "depth", "angle", "highlight", "shadow"]),
+];
+pub(crate) const STROKE_POSITIONS: [(&str, StrokePosition); 3] = [
+    ("outside", StrokePosition::Outside),
+    ("inside", StrokePosition::Inside),
+    ("center", StrokePosition::Center),
+];
 
 /// Compiles a VidraScript AST into a Vidra IR Project.
 pub struct Compiler {
@@ -53,6 +219,8 @@ impl Compiler {
             let asset_type = match asset.asset_type.as_str() {
                 "font" => vidra_ir::asset::AssetType::Font,
                 "image" => vidra_ir::asset::AssetType::Image,
+                "svg" => vidra_ir::asset::AssetType::Svg,
+                "lottie" => vidra_ir::asset::AssetType::Lottie,
                 "video" => vidra_ir::asset::AssetType::Video,
                 "audio" => vidra_ir::asset::AssetType::Audio,
                 "lut" => vidra_ir::asset::AssetType::Lut,
@@ -70,14 +238,53 @@ impl Compiler {
             global_env.insert(var.name.clone(), var.value.clone());
         }
 
+        for setting in &ast.settings {
+            if let PropertyNode::FunctionCall {
+                name,
+                args,
+                named_args,
+                ..
+            } = setting
+            {
+                if name == "motionBlur" {
+                    project.settings.motion_blur =
+                        Some(Self::compile_motion_blur(args, named_args, &global_env)?);
+                }
+            }
+        }
+
         for scene_node in &ast.scenes {
             let scene = compiler.compile_scene(scene_node, &mut project, &global_env)?;
             project.add_scene(scene);
         }
 
+        // Audio files named directly by audio-reactive expressions become assets too, so
+        // they get resolved and fingerprinted like every other media file.
+        let mut audio_ids = Vec::new();
+        for scene in &project.scenes {
+            for layer in &scene.layers {
+                Self::collect_expr_audio(layer, &mut audio_ids);
+            }
+        }
+        for id in audio_ids {
+            if project.assets.get(&id).is_none() {
+                let path = id.0.clone();
+                project
+                    .assets
+                    .register(Asset::new(id, AssetType::Audio, path));
+            }
+        }
+
         Ok(project)
     }
 
+    fn collect_expr_audio(layer: &Layer, ids: &mut Vec<AssetId>) {
+        ids.extend(layer.animations.iter().filter_map(|a| a.expr_audio.clone()));
+        for child in &layer.children {
+            Self::collect_expr_audio(child, ids);
+        }
+    }
+
     fn extract_overrides(
         items: &[LayerBlockItem],
         overrides: &mut HashMap<String, Vec<PropertyNode>>,
@@ -121,36 +328,17 @@ impl Compiler {
         for item in &scene_node.items {
             if let crate::ast::LayerBlockItem::Transition {
                 transition_type,
-                duration: dur_val,
-                easing,
+                duration,
+                args,
                 span: _,
             } = item
             {
-                let dur = Self::value_to_f64(dur_val)?;
-                let ease = match easing.as_deref() {
-                    Some("easeIn") => vidra_core::types::Easing::EaseIn,
-                    Some("easeOut") => vidra_core::types::Easing::EaseOut,
-                    Some("easeInOut") => vidra_core::types::Easing::EaseInOut,
-                    _ => vidra_core::types::Easing::Linear,
-                };
-                let effect = match transition_type.as_str() {
-                    "crossfade" => vidra_ir::transition::TransitionType::Crossfade,
-                    "wipe" => vidra_ir::transition::TransitionType::Wipe {
-                        direction: "right".to_string(),
-                    },
-                    "push" => vidra_ir::transition::TransitionType::Push {
-                        direction: "right".to_string(),
-                    },
-                    "slide" => vidra_ir::transition::TransitionType::Slide {
-                        direction: "right".to_string(),
-                    },
-                    _ => vidra_ir::transition::TransitionType::Crossfade,
-                };
-                scene.transition = Some(vidra_ir::transition::Transition {
-                    effect,
-                    duration: vidra_core::Duration::from_seconds(dur),
-                    easing: ease,
-                });
+                scene.transition = Some(Self::compile_transition(
+                    transition_type,
+                    duration,
+                    args,
+                    global_env,
+                )?);
             } else if let crate::ast::LayerBlockItem::AnimationStagger {
                 args,
                 animations,
@@ -168,44 +356,14 @@ impl Compiler {
         }
 
         for (args, animations) in staggers {
-            let mut offset = 0.0;
-            let mut target_layers: Vec<String> = Vec::new();
-
-            for arg in &args {
-                if arg.name == "offset" {
-                    offset = Self::value_to_f64(&arg.value).unwrap_or(0.0);
-                } else if arg.name == "layers" {
-                    if let ValueNode::Array(arr) = &arg.value {
-                        for item in arr {
-                            if let Ok(layer_name) = Self::value_to_string(item) {
-                                target_layers.push(layer_name);
-                            }
-                        }
-                    }
-                }
-            }
+            Self::apply_stagger(&mut scene.layers, &args, &animations, global_env)?;
+        }
 
-            for (i, layer_name) in target_layers.iter().enumerate() {
-                if let Some(layer) = scene
-                    .layers
-                    .iter_mut()
-                    .find(|l| l.id.0.as_str() == layer_name)
-                {
-                    let total_delay_offset = offset * (i as f64);
-
-                    for anim_node in &animations {
-                        if let PropertyNode::Animation { property, args, .. } = anim_node {
-                            let anims = Self::compile_animation(property, args, global_env)?;
-                            for mut anim in anims {
-                                let existing_delay = anim.delay.as_seconds();
-                                anim.delay = vidra_core::Duration::from_seconds(
-                                    existing_delay + total_delay_offset,
-                                );
-                                layer.animations.push(anim);
-                            }
-                        }
-                    }
-                }
+        // Track matte layers are read by the layers they mask, not drawn themselves.
+        let mattes: Vec<LayerId> = scene.layers.iter().filter_map(|l| l.mask.clone()).collect();
+        for layer in &mut scene.layers {
+            if mattes.contains(&layer.id) {
+                layer.visible = false;
             }
         }
 
@@ -221,6 +379,7 @@ impl Compiler {
             ValueNode::Identifier(_) => true, // Usually identifiers themselves are truths unless evaled
             ValueNode::BrandReference(_) => true,
             ValueNode::Array(arr) => !arr.is_empty(),
+            ValueNode::Call { .. } => true,
         }
     }
 
@@ -272,17 +431,291 @@ impl Compiler {
                 // Ignore for now, handled elsewhere or unused
                 Ok(Vec::new())
             }
-            crate::ast::LayerBlockItem::AnimationStagger {
-                args: _,
-                animations: _,
-                span: _,
-            } => {
-                // To be implemented: apply stagger stagger to scene's matching layers
+            crate::ast::LayerBlockItem::AnimationStagger { .. } => {
+                // Applied by the scene or layer containing it, once its layers exist.
                 Ok(Vec::new())
             }
         }
     }
 
+    /// Apply an `animate.stagger(...) { ... }` block to the layers it names in `layers`.
+    ///
+    /// With `by: char | word | line`, each named text layer gets a text animator whose
+    /// units start `offset` seconds apart. Otherwise the named layers themselves run the
+    /// animations `offset` seconds apart, in the order listed.
+    fn apply_stagger(
+        layers: &mut [Layer],
+        args: &[NamedArg],
+        animations: &[PropertyNode],
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<(), VidraError> {
+        let mut offset = 0.0;
+        let mut target_layers: Vec<String> = Vec::new();
+        let mut by_unit = false;
+
+        for arg in args {
+            if arg.name == "offset" {
+                offset = Self::value_to_f64(&arg.value).unwrap_or(0.0);
+            } else if arg.name == "by" {
+                by_unit = true;
+            } else if arg.name == "layers" {
+                if let ValueNode::Array(arr) = &arg.value {
+                    for item in arr {
+                        if let Ok(layer_name) = Self::value_to_string(item) {
+                            target_layers.push(layer_name);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (i, layer_name) in target_layers.iter().enumerate() {
+            let Some(layer) = layers.iter_mut().find(|l| l.id.0.as_str() == layer_name) else {
+                continue;
+            };
+            if by_unit {
+                Self::add_text_animator(layer, args, animations, env)?;
+                continue;
+            }
+            let total_delay_offset = offset * (i as f64);
+
+            for anim_node in animations {
+                if let PropertyNode::Animation { property, args, .. } = anim_node {
+                    let anims = Self::compile_animation(&layer.id, property, args, env)?;
+                    for mut anim in anims {
+                        let existing_delay = anim.delay.as_seconds();
+                        anim.delay =
+                            vidra_core::Duration::from_seconds(existing_delay + total_delay_offset);
+                        layer.animations.push(anim);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the text animator of an `animate.stagger(by: ...)` block to `layer`, which
+    /// must be a text layer. `offset` is the delay between units and `rangeStart`,
+    /// `rangeEnd` and `rangeOffset` select which units are animated.
+    /// `transition("kind", duration, ease: ..., options...)`. Kinds other than the
+    /// built-in transitions and `"shader"` name transition plugins, which receive the
+    /// options as parameters.
+    fn compile_transition(
+        transition_type: &str,
+        duration: &ValueNode,
+        args: &[NamedArg],
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<Transition, VidraError> {
+        let resolve = |v: &ValueNode| match v {
+            ValueNode::Identifier(id) => env.get(id).cloned().unwrap_or_else(|| v.clone()),
+            _ => v.clone(),
+        };
+        let mut easing = Easing::Linear;
+        let mut options = Vec::new();
+        for arg in args {
+            match arg.name.as_str() {
+                "ease" => easing = Self::value_to_easing(&resolve(&arg.value))?,
+                name => options.push((name, resolve(&arg.value))),
+            }
+        }
+        let option = |name: &str| options.iter().find(|(n, _)| *n == name).map(|(_, v)| v);
+        let number =
+            |name: &str, default: f64| option(name).map_or(Ok(default), Self::value_to_f64);
+        let direction = || {
+            option("direction").map_or(Ok(Direction::default()), |v| {
+                Self::value_to_named(v, &DIRECTIONS, "transition direction")
+            })
+        };
+        if let Some((_, allowed)) = TRANSITIONS.iter().find(|(n, _)| *n == transition_type) {
+            if let Some((name, _)) = options.iter().find(|(n, _)| !allowed.contains(n)) {
+                return Err(VidraError::Compile(format!(
+                    "transition(\"{}\") has no option '{}'",
+                    transition_type, name
+                )));
+            }
+        }
+
+        let effect = match transition_type {
+            "crossfade" => TransitionType::Crossfade,
+            "slide" => TransitionType::Slide {
+                direction: direction()?,
+            },
+            "push" => TransitionType::Push {
+                direction: direction()?,
+            },
+            "wipe" => TransitionType::Wipe {
+                direction: direction()?,
+            },
+            "zoom" => TransitionType::Zoom {
+                scale: number("scale", 1.5)?,
+            },
+            "iris" => {
+                let center = option("center").map_or(Ok(vec![0.5, 0.5]), Self::value_to_point)?;
+                TransitionType::Iris {
+                    center: Point2D::new(center[0], center[1]),
+                }
+            }
+            "clockWipe" => TransitionType::ClockWipe {
+                angle: number("angle", 0.0)?,
+            },
+            "blurDissolve" => TransitionType::BlurDissolve {
+                radius: number("radius", 20.0)?,
+            },
+            "glitch" => TransitionType::Glitch {
+                intensity: number("intensity", 0.1)?,
+            },
+            "shader" => {
+                let source = option("fx").ok_or_else(|| {
+                    VidraError::Compile(
+                        "transition(\"shader\") needs the VidraFX @transition as fx: \"...\""
+                            .into(),
+                    )
+                })?;
+                let compiled = vidra_fx::compile_transition(&Self::value_to_string(source)?)?;
+                let declared = |name: &str| compiled.params.iter().any(|p| p.name == name);
+                if let Some((name, _)) = options.iter().find(|(n, _)| *n != "fx" && !declared(n)) {
+                    return Err(VidraError::Compile(format!(
+                        "the shader transition has no parameter '{}'",
+                        name
+                    )));
+                }
+                let params = compiled
+                    .params
+                    .iter()
+                    .map(|p| number(&p.name, p.default_value.unwrap_or(0.0)))
+                    .collect::<Result<_, _>>()?;
+                TransitionType::Shader {
+                    wgsl_source: compiled.wgsl,
+                    params,
+                }
+            }
+            name => TransitionType::Plugin {
+                name: name.to_string(),
+                params: options
+                    .iter()
+                    .map(|(n, v)| Ok((n.to_string(), Self::value_to_f64(v)?)))
+                    .collect::<Result<_, VidraError>>()?,
+            },
+        };
+        Ok(Transition {
+            effect,
+            duration: vidra_core::Duration::from_seconds(Self::value_to_f64(&resolve(duration))?),
+            easing,
+        })
+    }
+
+    /// `effect(style, options...)` for one of the [`LAYER_STYLES`].
+    fn compile_layer_style(
+        style: &str,
+        args: &[NamedArg],
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<LayerEffect, VidraError> {
+        let allowed = LAYER_STYLES
+            .iter()
+            .find(|(n, _)| *n == style)
+            .map_or(&[][..], |(_, options)| *options);
+        if let Some(arg) = args.iter().find(|a| !allowed.contains(&a.name.as_str())) {
+            return Err(VidraError::Compile(format!(
+                "effect({}) has no option '{}'",
+                style, arg.name
+            )));
+        }
+        let option = |name: &str| {
+            args.iter()
+                .find(|a| a.name == name)
+                .map(|a| match &a.value {
+                    ValueNode::Identifier(id) => env.get(id).cloned().unwrap_or(a.value.clone()),
+                    v => v.clone(),
+                })
+        };
+        let number =
+            |name: &str, default: f64| option(name).map_or(Ok(default), |v| Self::value_to_f64(&v));
+        let color = |name: &str, default: Color| {
+            option(name).map_or(Ok(default), |v| Self::value_to_color(&v))
+        };
+        let shadow = Color::rgba(0.0, 0.0, 0.0, 0.5);
+
+        Ok(match style {
+            "dropShadow" => LayerEffect::DropShadow {
+                offset: Point2D::new(number("x", 0.0)?, number("y", 4.0)?),
+                blur: number("blur", 8.0)?,
+                color: color("color", shadow)?,
+                spread: number("spread", 0.0)?,
+            },
+            "innerShadow" => LayerEffect::InnerShadow {
+                offset: Point2D::new(number("x", 0.0)?, number("y", 2.0)?),
+                blur: number("blur", 4.0)?,
+                color: color("color", shadow)?,
+                spread: number("spread", 0.0)?,
+            },
+            "outerGlow" => LayerEffect::OuterGlow {
+                blur: number("blur", 10.0)?,
+                color: color("color", Color::WHITE)?,
+                spread: number("spread", 0.0)?,
+            },
+            "stroke" => LayerEffect::Stroke {
+                width: number("width", 2.0)?,
+                color: color("color", Color::BLACK)?,
+                position: option("position").map_or(Ok(StrokePosition::default()), |v| {
+                    Self::value_to_named(&v, &STROKE_POSITIONS, "stroke position")
+                })?,
+            },
+            _ => LayerEffect::Bevel {
+                size: number("size", 4.0)?,
+                depth: number("depth", 1.0)?,
+                angle: number("angle", 135.0)?,
+                highlight: color("highlight", Color::rgba(1.0, 1.0, 1.0, 0.75))?,
+                shadow: color("shadow", shadow)?,
+            },
+        })
+    }
+
+    fn add_text_animator(
+        layer: &mut Layer,
+        args: &[NamedArg],
+        animations: &[PropertyNode],
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<(), VidraError> {
+        let LayerContent::Text { animators, .. } = &mut layer.content else {
+            return Err(VidraError::Compile(format!(
+                "animate.stagger(by: ...) animates the text of a text layer, but '{}' is not one",
+                layer.id
+            )));
+        };
+        let mut animator = TextAnimator::default();
+        for arg in args {
+            let value = match &arg.value {
+                ValueNode::Identifier(id) => env.get(id).unwrap_or(&arg.value),
+                value => value,
+            };
+            match arg.name.as_str() {
+                "by" => animator.unit = Self::value_to_named(value, &TEXT_UNITS, "text unit")?,
+                "offset" => animator.stagger = Self::value_to_duration(value)?,
+                "rangeStart" => animator.range.start = Self::value_to_f64(value)?,
+                "rangeEnd" => animator.range.end = Self::value_to_f64(value)?,
+                "rangeOffset" => animator.range.offset = Self::value_to_f64(value)?,
+                _ => {}
+            }
+        }
+        for node in animations {
+            let PropertyNode::Animation { property, args, .. } = node else {
+                continue;
+            };
+            for anim in Self::compile_animation(&layer.id, property, args, env)? {
+                // A unit's `scale` is uniform.
+                if property == "scale" && anim.property == AnimatableProperty::ScaleX {
+                    animator.animations.push(Animation {
+                        property: AnimatableProperty::ScaleY,
+                        ..anim.clone()
+                    });
+                }
+                animator.animations.push(anim);
+            }
+        }
+        animators.push(animator);
+        Ok(())
+    }
+
     fn compile_layer(
         &self,
         layer_node: &LayerNode,
@@ -320,13 +753,13 @@ impl Compiler {
                     layer.transform.position.y = Self::value_to_f64(&resolved_y)?;
                 }
                 PropertyNode::Animation { property, args, .. } => {
-                    let anims = Self::compile_animation(property, args, env)?;
+                    let anims = Self::compile_animation(&layer.id, property, args, env)?;
                     layer.animations.extend(anims);
                 }
                 PropertyNode::FunctionCall {
                     name,
                     args,
-                    named_args: _,
+                    named_args,
                     ..
                 } => {
                     if name == "effect" && !args.is_empty() {
@@ -513,7 +946,30 @@ impl Compiler {
                                         });
                                     }
                                 }
-                                _ => tracing::warn!("Unknown effect: {}", effect_type),
+                                style if LAYER_STYLES.iter().any(|(n, _)| *n == style) => {
+                                    layer
+                                        .effects
+                                        .push(Self::compile_layer_style(style, named_args, env)?);
+                                }
+                                // Anything else is an effect plugin, found at render time.
+                                name => {
+                                    let params = named_args
+                                        .iter()
+                                        .map(|arg| {
+                                            let value = match &arg.value {
+                                                ValueNode::Identifier(id) => {
+                                                    env.get(id).unwrap_or(&arg.value)
+                                                }
+                                                _ => &arg.value,
+                                            };
+                                            Ok((arg.name.clone(), Self::value_to_f64(value)?))
+                                        })
+                                        .collect::<Result<_, VidraError>>()?;
+                                    layer.effects.push(vidra_core::types::LayerEffect::Plugin {
+                                        name: name.to_string(),
+                                        params,
+                                    });
+                                }
                             }
                         }
                     } else if name == "preset" && !args.is_empty() {
@@ -623,14 +1079,9 @@ impl Compiler {
                             }
                         }
                     } else if name == "mask" && !args.is_empty() {
-                        let mask_layer_name = if let ValueNode::Identifier(id) = &args[0] {
-                            env.get(id).unwrap_or(&args[0])
-                        } else {
-                            &args[0]
-                        };
-                        if let Ok(mask_str) = Self::value_to_string(mask_layer_name) {
-                            layer.mask = Some(LayerId::new(mask_str));
-                        }
+                        Self::compile_mask(&mut layer, args, named_args, env)?;
+                    } else if name == "blend" && !args.is_empty() {
+                        layer.blend_mode = Self::value_to_blend_mode(&args[0])?;
                     } else if name == "center" && !args.is_empty() {
                         // center(horizontal), center(vertical), center(both) or center()
                         let axis_val =
@@ -787,6 +1238,8 @@ impl Compiler {
                         if let Some(v) = args.get(0) {
                             layer.transform.perspective = Self::value_to_f64(v).unwrap_or(0.0);
                         }
+                    } else if name == "motionBlur" {
+                        layer.motion_blur = Some(Self::compile_motion_blur(args, named_args, env)?);
                     } else {
                         // Handle generic function calls — extensible for enter/exit/etc.
                         tracing::debug!("unhandled function call: {}", name);
@@ -799,7 +1252,7 @@ impl Compiler {
                     // All start at 0 (or delay inside)
                     for ag_prop in animations {
                         if let PropertyNode::Animation { property, args, .. } = ag_prop {
-                            let anims = Self::compile_animation(property, args, env)?;
+                            let anims = Self::compile_animation(&layer.id, property, args, env)?;
                             layer.animations.extend(anims);
                         }
                     }
@@ -815,7 +1268,8 @@ impl Compiler {
                                 current_time += Self::value_to_f64(duration).unwrap_or(0.0);
                             }
                             PropertyNode::Animation { property, args, .. } => {
-                                let anims = Self::compile_animation(property, args, env)?;
+                                let anims =
+                                    Self::compile_animation(&layer.id, property, args, env)?;
                                 let mut max_dur = 0.0;
                                 for mut anim in anims {
                                     let delay = anim.delay.as_seconds() + current_time;
@@ -936,12 +1390,28 @@ impl Compiler {
             }
         } else {
             // Standard layer, process its explicit children normally
+            let mut staggers = Vec::new();
             for child_item in &layer_node.children {
+                if let crate::ast::LayerBlockItem::AnimationStagger {
+                    args, animations, ..
+                } = child_item
+                {
+                    staggers.push((args, animations));
+                    continue;
+                }
                 let compiled = self.compile_layer_block_item(child_item, project, env, slots)?;
                 for child in compiled {
                     layer.add_child(child);
                 }
             }
+            // Without a `layers` list, a stagger animates this layer's own text.
+            for (args, animations) in staggers {
+                if args.iter().any(|arg| arg.name == "layers") {
+                    Self::apply_stagger(&mut layer.children, args, animations, env)?;
+                } else {
+                    Self::add_text_animator(&mut layer, args, animations, env)?;
+                }
+            }
         }
 
         if matches!(layer_node.content, LayerContentNode::Slot) {
@@ -964,6 +1434,9 @@ impl Compiler {
                 let mut font_family = "Inter".to_string();
                 let mut font_size = 24.0;
                 let mut color = Color::WHITE;
+                let mut style = TextStyle::default();
+                let mut stroke: Option<TextStroke> = None;
+                let mut shadow: Option<TextShadow> = None;
 
                 for arg in args {
                     let val = env.get(&arg.name).unwrap_or(&arg.value);
@@ -971,6 +1444,38 @@ impl Compiler {
                         "font" => font_family = Self::value_to_string(val)?,
                         "size" => font_size = Self::value_to_f64(val)?,
                         "color" => color = Self::value_to_color(val)?,
+                        "maxWidth" => style.max_width = Some(Self::value_to_f64(val)?),
+                        "align" => {
+                            style.align = Self::value_to_named(val, &TEXT_ALIGNS, "text alignment")?
+                        }
+                        "letterSpacing" => style.letter_spacing = Self::value_to_f64(val)?,
+                        "lineHeight" => style.line_height = Some(Self::value_to_f64(val)?),
+                        "bold" => style.bold = Self::value_to_named(val, &BOOLEANS, "boolean")?,
+                        "stroke" | "strokeWidth" => {
+                            let stroke = stroke.get_or_insert(TextStroke {
+                                color: Color::BLACK,
+                                width: 2.0,
+                            });
+                            if arg.name == "stroke" {
+                                stroke.color = Self::value_to_color(val)?;
+                            } else {
+                                stroke.width = Self::value_to_f64(val)?;
+                            }
+                        }
+                        "shadow" | "shadowX" | "shadowY" | "shadowBlur" => {
+                            let shadow = shadow.get_or_insert(TextShadow {
+                                color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+                                offset_x: 0.0,
+                                offset_y: 4.0,
+                                blur: 4.0,
+                            });
+                            match arg.name.as_str() {
+                                "shadow" => shadow.color = Self::value_to_color(val)?,
+                                "shadowX" => shadow.offset_x = Self::value_to_f64(val)?,
+                                "shadowY" => shadow.offset_y = Self::value_to_f64(val)?,
+                                _ => shadow.blur = Self::value_to_f64(val)?.max(0.0),
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -980,16 +1485,21 @@ impl Compiler {
                 } else {
                     text
                 };
-                let resolved_text = Self::value_to_string(text_val)?;
+                let (resolved_text, spans) = text::parse_markup(&Self::value_to_string(text_val)?);
+                style.spans = spans;
+                style.stroke = stroke.filter(|s| s.width > 0.0);
+                style.shadow = shadow;
 
                 Ok(LayerContent::Text {
                     text: resolved_text,
                     font_family,
                     font_size,
                     color,
+                    style,
+                    animators: Vec::new(),
                 })
             }
-            LayerContentNode::Image { path, args: _args } => {
+            LayerContentNode::Image { path, args } => {
                 let path_val = if let ValueNode::Identifier(id) = path {
                     env.get(id).unwrap_or(path)
                 } else {
@@ -997,16 +1507,46 @@ impl Compiler {
                 };
                 let resolved_path = Self::value_to_string(path_val)?;
                 let asset_id = AssetId::new(resolved_path.clone());
+                let svg = std::path::Path::new(&resolved_path)
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
 
                 if project.assets.get(&asset_id).is_none() {
                     project.assets.register(Asset::new(
                         asset_id.clone(),
-                        AssetType::Image,
+                        if svg {
+                            AssetType::Svg
+                        } else {
+                            AssetType::Image
+                        },
                         resolved_path,
                     ));
                 }
+                if !svg {
+                    return Ok(LayerContent::Image { asset_id });
+                }
 
-                Ok(LayerContent::Image { asset_id })
+                let mut element = None;
+                let mut expand_groups = false;
+                for arg in args {
+                    let val = match &arg.value {
+                        ValueNode::Identifier(id) => env.get(id).unwrap_or(&arg.value),
+                        _ => &arg.value,
+                    };
+                    match arg.name.as_str() {
+                        "element" => element = Some(Self::value_to_string(val)?),
+                        "groups" => {
+                            expand_groups = Self::value_to_named(val, &BOOLEANS, "boolean")?
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(LayerContent::Svg {
+                    asset_id,
+                    element,
+                    exclude: Vec::new(),
+                    expand_groups,
+                })
             }
             LayerContentNode::Spritesheet { path, args } => {
                 let path_val = if let ValueNode::Identifier(id) = path {
@@ -1059,6 +1599,24 @@ impl Compiler {
                     frame_count,
                 })
             }
+            LayerContentNode::Lottie { path, .. } => {
+                let path_val = if let ValueNode::Identifier(id) = path {
+                    env.get(id).unwrap_or(path)
+                } else {
+                    path
+                };
+                let resolved_path = Self::value_to_string(path_val)?;
+                let asset_id = AssetId::new(resolved_path.clone());
+
+                if project.assets.get(&asset_id).is_none() {
+                    project.assets.register(Asset::new(
+                        asset_id.clone(),
+                        AssetType::Lottie,
+                        resolved_path,
+                    ));
+                }
+                Ok(LayerContent::Lottie { asset_id })
+            }
             LayerContentNode::Video { path, args } => {
                 let path_val = if let ValueNode::Identifier(id) = path {
                     env.get(id).unwrap_or(path)
@@ -1267,13 +1825,13 @@ impl Compiler {
                 } else {
                     color
                 };
-                let c = Self::value_to_color(color_val)?;
-                Ok(LayerContent::Solid { color: c })
+                let paint = Self::value_to_paint(color_val, env)?;
+                Ok(LayerContent::Solid { color: paint })
             }
             LayerContentNode::Shape { shape_type, args } => {
                 let mut stroke_color = None;
                 let mut stroke_w = 0.0;
-                let mut fill_color = Some(Color::WHITE);
+                let mut fill_color = Some(Paint::Solid(Color::WHITE));
 
                 let get_val = |key: &str| -> Option<ValueNode> {
                     args.iter().find(|a| a.name == key).map(|a| {
@@ -1286,10 +1844,10 @@ impl Compiler {
                 };
 
                 if let Some(c) = get_val("fill").or_else(|| get_val("color")) {
-                    fill_color = Some(Self::value_to_color(&c)?);
+                    fill_color = Some(Self::value_to_paint(&c, env)?);
                 }
                 if let Some(c) = get_val("stroke") {
-                    stroke_color = Some(Self::value_to_color(&c)?);
+                    stroke_color = Some(Self::value_to_paint(&c, env)?);
                 }
                 if let Some(w) = get_val("strokeWidth") {
                     stroke_w = Self::value_to_f64(&w)?;
@@ -1327,6 +1885,53 @@ impl Compiler {
                             .unwrap_or(50.0);
                         vidra_core::types::ShapeType::Ellipse { rx, ry }
                     }
+                    "path" => {
+                        let d = get_val("d").ok_or_else(|| {
+                            VidraError::Compile("shape(path) requires d: \"<path data>\"".into())
+                        })?;
+                        let data = PathData::parse_svg(&Self::value_to_string(&d)?)
+                            .map_err(|e| VidraError::Compile(e.to_string()))?;
+                        let mut path = PathShape::new(data);
+                        if let Some(v) = get_val("fillRule") {
+                            path.fill_rule = Self::value_to_named(&v, &FILL_RULES, "fill rule")?;
+                        }
+                        let style = &mut path.stroke_style;
+                        if let Some(v) = get_val("cap") {
+                            style.cap = Self::value_to_named(&v, &LINE_CAPS, "line cap")?;
+                        }
+                        if let Some(v) = get_val("join") {
+                            style.join = Self::value_to_named(&v, &LINE_JOINS, "line join")?;
+                        }
+                        if let Some(v) = get_val("miterLimit") {
+                            style.miter_limit = Self::value_to_f64(&v)?;
+                        }
+                        if let Some(v) = get_val("dash") {
+                            style.dash = match v {
+                                ValueNode::Array(items) => items
+                                    .iter()
+                                    .map(Self::value_to_f64)
+                                    .collect::<Result<_, _>>()?,
+                                other => vec![Self::value_to_f64(&other)?],
+                            };
+                        }
+                        if let Some(v) = get_val("dashOffset") {
+                            style.dash_offset = Self::value_to_f64(&v)?;
+                        }
+                        if let Some(v) = get_val("trimStart") {
+                            path.trim.start = Self::value_to_f64(&v)?;
+                        }
+                        if let Some(v) = get_val("trimEnd") {
+                            path.trim.end = Self::value_to_f64(&v)?;
+                        }
+                        if let Some(v) = get_val("trimOffset") {
+                            path.trim.offset = Self::value_to_f64(&v)?;
+                        }
+                        // A stroked path without an explicit fill is an outline.
+                        if stroke_color.is_some() && get_val("fill").is_none() {
+                            fill_color = None;
+                        }
+                        vidra_core::types::ShapeType::Path(path)
+                    }
                     _ => {
                         return Err(VidraError::Compile(format!(
                             "unknown shape type: {}",
@@ -1411,6 +2016,27 @@ impl Compiler {
                     variables,
                 })
             }
+            LayerContentNode::Plugin { name, args } => {
+                let resolve = |v: &ValueNode| match v {
+                    ValueNode::Identifier(id) => env.get(id).cloned().unwrap_or_else(|| v.clone()),
+                    _ => v.clone(),
+                };
+                let props = args
+                    .iter()
+                    .map(|arg| {
+                        let value = match resolve(&arg.value) {
+                            ValueNode::Number(n) | ValueNode::Duration(n) => n.to_string(),
+                            ValueNode::Color(hex) => format!("#{}", hex),
+                            other => Self::value_to_string(&other)?,
+                        };
+                        Ok((arg.name.clone(), value))
+                    })
+                    .collect::<Result<_, VidraError>>()?;
+                Ok(LayerContent::Plugin {
+                    name: Self::value_to_string(&resolve(name))?,
+                    props,
+                })
+            }
             LayerContentNode::Component { .. }
             | LayerContentNode::Slot
             | LayerContentNode::Empty => Ok(LayerContent::Empty),
@@ -1418,6 +2044,7 @@ impl Compiler {
     }
 
     fn compile_animation(
+        layer_id: &LayerId,
         property: &str,
         args: &[NamedArg],
         env: &HashMap<String, ValueNode>,
@@ -1434,8 +2061,8 @@ impl Compiler {
             "rotateX" | "rotate_x" => Some(AnimatableProperty::RotateX),
             "rotateY" | "rotate_y" => Some(AnimatableProperty::RotateY),
             "perspective" => Some(AnimatableProperty::Perspective),
-            "position" => None,                          // Special case for paths
-            "color" => Some(AnimatableProperty::ColorR), // Pseudo-property, handled specially
+            "position" => Some(AnimatableProperty::Position),
+            "color" => Some(AnimatableProperty::Color),
             "fontSize" => Some(AnimatableProperty::FontSize),
             "cornerRadius" => Some(AnimatableProperty::CornerRadius),
             "strokeWidth" => Some(AnimatableProperty::StrokeWidth),
@@ -1446,24 +2073,60 @@ impl Compiler {
             "volume" => Some(AnimatableProperty::Volume),
             "blur" | "blurRadius" => Some(AnimatableProperty::BlurRadius),
             "brightness" | "brightnessLevel" => Some(AnimatableProperty::BrightnessLevel),
-            _ => {
-                return Err(VidraError::Compile(format!(
-                    "unknown animatable property: {}",
-                    property
-                )));
-            }
+            "mask.x" | "maskX" => Some(AnimatableProperty::MaskX),
+            "mask.y" | "maskY" => Some(AnimatableProperty::MaskY),
+            "maskWidth" => Some(AnimatableProperty::MaskWidth),
+            "maskHeight" => Some(AnimatableProperty::MaskHeight),
+            "maskFeather" => Some(AnimatableProperty::MaskFeather),
+            "shadow.x" | "shadowX" => Some(AnimatableProperty::ShadowX),
+            "shadow.y" | "shadowY" => Some(AnimatableProperty::ShadowY),
+            "shadowBlur" => Some(AnimatableProperty::ShadowBlur),
+            "shadowSpread" => Some(AnimatableProperty::ShadowSpread),
+            "innerShadow.x" | "innerShadowX" => Some(AnimatableProperty::InnerShadowX),
+            "innerShadow.y" | "innerShadowY" => Some(AnimatableProperty::InnerShadowY),
+            "innerShadowBlur" => Some(AnimatableProperty::InnerShadowBlur),
+            "innerShadowSpread" => Some(AnimatableProperty::InnerShadowSpread),
+            "glowBlur" => Some(AnimatableProperty::GlowBlur),
+            "glowSpread" => Some(AnimatableProperty::GlowSpread),
+            "outlineWidth" => Some(AnimatableProperty::OutlineWidth),
+            "bevelSize" => Some(AnimatableProperty::BevelSize),
+            "bevelDepth" => Some(AnimatableProperty::BevelDepth),
+            "bevelAngle" => Some(AnimatableProperty::BevelAngle),
+            "trimStart" => Some(AnimatableProperty::TrimStart),
+            "trimEnd" => Some(AnimatableProperty::TrimEnd),
+            "trimOffset" => Some(AnimatableProperty::TrimOffset),
+            "gradientAngle" => Some(AnimatableProperty::GradientAngle),
+            "rangeStart" => Some(AnimatableProperty::TextRangeStart),
+            "rangeEnd" => Some(AnimatableProperty::TextRangeEnd),
+            "rangeOffset" => Some(AnimatableProperty::TextRangeOffset),
+            _ => match gradient_stop_index(property) {
+                Some(index) => Some(AnimatableProperty::GradientStop(index)),
+                None => {
+                    return Err(VidraError::Compile(format!(
+                        "unknown animatable property: {}",
+                        property
+                    )));
+                }
+            },
         };
 
         let mut from_val = 0.0;
         let mut to_val = 1.0;
         let mut from_color = None;
         let mut to_color = None;
+        let mut from_vector = None;
+        let mut to_vector = None;
+        let mut color_space = None;
+        let mut auto_orient = false;
         let mut duration = 1.0;
-        let mut easing = vidra_core::types::Easing::Linear;
+        let mut easing = Easing::Linear;
+        let mut eased = false;
+        let mut hold = false;
         let mut delay = 0.0;
 
         let mut stiffness = None;
         let mut damping = None;
+        let mut mass = None;
         let mut velocity = None;
         let mut expr = None;
         let mut path = None;
@@ -1482,6 +2145,8 @@ impl Compiler {
                 "from" => {
                     if property == "color" {
                         from_color = Some(Self::value_to_color(resolved_val)?);
+                    } else if let ValueNode::Array(_) = resolved_val {
+                        from_vector = Some(Self::value_to_point(resolved_val)?);
                     } else {
                         from_val = Self::value_to_f64(resolved_val)?;
                     }
@@ -1489,110 +2154,144 @@ impl Compiler {
                 "to" => {
                     if property == "color" {
                         to_color = Some(Self::value_to_color(resolved_val)?);
+                    } else if let ValueNode::Array(_) = resolved_val {
+                        to_vector = Some(Self::value_to_point(resolved_val)?);
                     } else {
                         to_val = Self::value_to_f64(resolved_val)?;
                     }
                 }
+                "space" | "colorSpace" => {
+                    color_space = Some(Self::value_to_named(
+                        resolved_val,
+                        &COLOR_SPACES,
+                        "color space",
+                    )?)
+                }
+                "autoOrient" => {
+                    auto_orient = Self::value_to_named(resolved_val, &BOOLEANS, "boolean")?
+                }
                 "duration" => duration = Self::value_to_duration(resolved_val)?,
                 "delay" => delay = Self::value_to_duration(resolved_val)?,
                 "ease" | "easing" => {
-                    easing = Self::value_to_easing(resolved_val)?;
+                    eased = true;
+                    if matches!(resolved_val, ValueNode::Identifier(name) if name == "hold") {
+                        hold = true;
+                    } else {
+                        easing = Self::value_to_easing(resolved_val)?;
+                    }
                 }
                 "stiffness" => stiffness = Some(Self::value_to_f64(resolved_val)?),
                 "damping" => damping = Some(Self::value_to_f64(resolved_val)?),
+                "mass" => mass = Some(Self::value_to_f64(resolved_val)?),
                 "velocity" | "initialVelocity" => {
                     velocity = Some(Self::value_to_f64(resolved_val)?)
                 }
-                "expr" | "expression" => expr = Some(Self::value_to_string(resolved_val)?),
+                "expr" | "expression" => {
+                    expr = Some((Self::value_to_string(resolved_val)?, arg.span))
+                }
                 "audio" => audio_source = Some(Self::value_to_string(resolved_val)?),
                 "path" => path = Some(Self::value_to_string(resolved_val)?),
                 _ => {}
             }
         }
 
+        if matches!(property, "position" | "color") && (expr.is_some() || stiffness.is_some()) {
+            return Err(VidraError::Compile(format!(
+                "'{}' in layer '{}' cannot follow an expression or a spring; animate its components instead",
+                property, layer_id
+            )));
+        }
+
         let mut anims = Vec::new();
 
         if property == "color" {
-            let fc = from_color.unwrap_or_else(|| Color::WHITE);
-            let tc = to_color.unwrap_or_else(|| Color::WHITE);
+            let fc = from_color.unwrap_or(Color::WHITE);
+            let tc = to_color.unwrap_or(Color::WHITE);
             let dur = vidra_core::Duration::from_seconds(duration);
-            let del = vidra_core::Duration::from_seconds(delay);
-
-            let mut ar = Animation::from_to(
-                AnimatableProperty::ColorR,
-                fc.r as f64,
-                tc.r as f64,
-                dur,
-                easing.clone(),
-            );
-            let mut ag = Animation::from_to(
-                AnimatableProperty::ColorG,
-                fc.g as f64,
-                tc.g as f64,
-                dur,
-                easing.clone(),
-            );
-            let mut ab = Animation::from_to(
-                AnimatableProperty::ColorB,
-                fc.b as f64,
-                tc.b as f64,
-                dur,
-                easing.clone(),
-            );
-            let mut aa = Animation::from_to(
-                AnimatableProperty::ColorA,
-                fc.a as f64,
-                tc.a as f64,
-                dur,
-                easing.clone(),
-            );
-
-            if delay > 0.0 {
-                ar = ar.with_delay(del);
-                ag = ag.with_delay(del);
-                ab = ab.with_delay(del);
-                aa = aa.with_delay(del);
-            }
-            anims.push(ar);
-            anims.push(ag);
-            anims.push(ab);
-            anims.push(aa);
-        } else if let Some(p) = path {
-            let (mut ax, mut ay) = crate::advanced_anim::compile_path_animations(&p, duration);
-            if delay > 0.0 {
-                ax = ax.with_delay(vidra_core::Duration::from_seconds(delay));
-                ay = ay.with_delay(vidra_core::Duration::from_seconds(delay));
-            }
-            anims.push(ax);
-            anims.push(ay);
-        } else if let Some(e) = expr {
-            let (rewritten_interactive, uses_mouse) = rewrite_interactive_state_expr(&e);
-            if uses_mouse {
-                if rewritten_interactive.contains("audio.amplitude") {
-                    return Err(VidraError::Compile(
-                        "interactive expressions with audio.amplitude are not supported yet"
-                            .to_string(),
+            if color_space.is_some() {
+                let channels = |c: Color| vec![c.r as f64, c.g as f64, c.b as f64, c.a as f64];
+                let mut anim = Animation::from_to_vector(
+                    AnimatableProperty::Color,
+                    channels(fc),
+                    channels(tc),
+                    dur,
+                    easing,
+                );
+                anim.color_space = color_space;
+                anims.push(anim);
+            } else {
+                // Without a color space the channels blend in sRGB, one animation each.
+                let channels = [
+                    (AnimatableProperty::ColorR, fc.r, tc.r),
+                    (AnimatableProperty::ColorG, fc.g, tc.g),
+                    (AnimatableProperty::ColorB, fc.b, tc.b),
+                    (AnimatableProperty::ColorA, fc.a, tc.a),
+                ];
+                for (channel, from, to) in channels {
+                    anims.push(Animation::from_to(
+                        channel,
+                        from as f64,
+                        to as f64,
+                        dur,
+                        easing,
                     ));
                 }
+            }
+            for anim in &mut anims {
+                anim.keyframes[0].hold = hold;
+                anim.delay = vidra_core::Duration::from_seconds(delay);
+            }
+        } else if let Some(p) = path {
+            if eased {
+                return Err(VidraError::Compile(format!(
+                    "the motion path in layer '{}' moves at constant speed and takes no easing",
+                    layer_id
+                )));
+            }
+            let mut a =
+                crate::advanced_anim::compile_path_animation(&p, duration).map_err(|e| {
+                    VidraError::Compile(format!(
+                        "invalid motion path `{}` in layer '{}': {}",
+                        p, layer_id, e
+                    ))
+                })?;
+            a.auto_orient = auto_orient;
+            if delay > 0.0 {
+                a = a.with_delay(vidra_core::Duration::from_seconds(delay));
+            }
+            anims.push(a);
+        } else if let Some((e, span)) = expr {
+            let (_, uses_mouse) = rewrite_interactive_state_expr(&e);
+            let normalized = normalize_expression(&e);
+            if let Err(err) = crate::advanced_anim::validate_expression(&normalized) {
+                return Err(VidraError::Compile(format!(
+                    "invalid expression `{}` in layer '{}' at {}:{}: {}",
+                    e, layer_id, span.line, span.column, err
+                )));
+            }
+            let uses_audio = AUDIO_FEATURES
+                .iter()
+                .any(|(_, var)| contains_identifier(&normalized, var));
 
+            if uses_mouse || uses_audio {
+                // Mouse and audio values are only known at render time.
                 let mut a = Animation::new(animatable.unwrap());
-                a.expr = Some(rewritten_interactive);
+                a.expr = Some(normalized);
                 a.expr_duration = Some(vidra_core::Duration::from_seconds(duration));
+                if uses_audio {
+                    a.expr_audio = audio_source
+                        .or_else(|| extract_audio_path(&e))
+                        .map(AssetId::new);
+                }
                 if delay > 0.0 {
                     a = a.with_delay(vidra_core::Duration::from_seconds(delay));
                 }
                 anims.push(a);
             } else {
-                let (rewritten, amp_samples) = Self::prepare_audio_expression(
-                    &rewritten_interactive,
-                    audio_source.as_deref(),
-                    duration,
-                )?;
                 let mut a = crate::advanced_anim::compile_expression(
                     animatable.unwrap(),
-                    &rewritten,
+                    &normalized,
                     duration,
-                    amp_samples.as_deref(),
                 );
                 if delay > 0.0 {
                     a = a.with_delay(vidra_core::Duration::from_seconds(delay));
@@ -1600,20 +2299,47 @@ impl Compiler {
                 anims.push(a);
             }
         } else if let Some(s) = stiffness {
-            let d = damping.unwrap_or(10.0);
-            let v = velocity.unwrap_or(0.0);
-            let mut a = crate::advanced_anim::compile_spring(
-                animatable.unwrap(),
-                from_val,
-                to_val,
+            let spring = Spring::new(
+                mass.unwrap_or(1.0),
                 s,
-                d,
-                v,
+                damping.unwrap_or(10.0),
+                velocity.unwrap_or(0.0),
             );
+            if !(spring.mass > 0.0 && spring.stiffness > 0.0 && spring.damping >= 0.0) {
+                return Err(VidraError::Compile(format!(
+                    "spring animation in layer '{}' needs a positive mass and stiffness and a non-negative damping",
+                    layer_id
+                )));
+            }
+            let mut a = Animation::spring(animatable.unwrap(), from_val, to_val, spring);
             if delay > 0.0 {
                 a = a.with_delay(vidra_core::Duration::from_seconds(delay));
             }
             anims.push(a);
+        } else if property == "position" || from_vector.is_some() || to_vector.is_some() {
+            let property = match property {
+                "position" => AnimatableProperty::Position,
+                "scale" => AnimatableProperty::Scale,
+                _ => {
+                    return Err(VidraError::Compile(format!(
+                        "'{}' in layer '{}' takes a single number, not a point",
+                        property, layer_id
+                    )))
+                }
+            };
+            let mut anim = Animation::from_to_vector(
+                property,
+                from_vector.unwrap_or(vec![from_val, from_val]),
+                to_vector.unwrap_or(vec![to_val, to_val]),
+                vidra_core::Duration::from_seconds(duration),
+                easing,
+            );
+            anim.keyframes[0].hold = hold;
+            anim.auto_orient = auto_orient;
+            if delay > 0.0 {
+                anim = anim.with_delay(vidra_core::Duration::from_seconds(delay));
+            }
+            anims.push(anim);
         } else {
             let mut anim = Animation::from_to(
                 animatable.unwrap(),
@@ -1622,6 +2348,7 @@ impl Compiler {
                 vidra_core::Duration::from_seconds(duration),
                 easing,
             );
+            anim.keyframes[0].hold = hold;
             if delay > 0.0 {
                 anim = anim.with_delay(vidra_core::Duration::from_seconds(delay));
             }
@@ -1631,37 +2358,114 @@ impl Compiler {
         Ok(anims)
     }
 
-    fn prepare_audio_expression(
-        expr: &str,
-        audio_source: Option<&str>,
-        duration: f64,
-    ) -> Result<(String, Option<Vec<f64>>), VidraError> {
-        // Only do extra work if the expression references audio amplitude.
-        if !expr.contains("audio.amplitude") {
-            return Ok((expr.to_string(), None));
-        }
-
-        let rewritten = rewrite_audio_amplitude_expr(expr);
+    /// `motionBlur()`, `motionBlur(180, samples: 8)`, `motionBlur(shutter: 90)` or
+    /// `motionBlur(off)`.
+    fn compile_motion_blur(
+        args: &[ValueNode],
+        named_args: &[NamedArg],
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<vidra_core::MotionBlur, VidraError> {
+        let resolve = |v: &ValueNode| -> ValueNode {
+            match v {
+                ValueNode::Identifier(id) => env.get(id).cloned().unwrap_or_else(|| v.clone()),
+                _ => v.clone(),
+            }
+        };
 
-        // Determine audio path: prefer explicit `audio:` arg; else try to extract audio.amplitude("path").
-        let audio_path = audio_source
-            .map(|s| s.to_string())
-            .or_else(|| extract_audio_amplitude_path(expr));
+        let mut blur = vidra_core::MotionBlur::default();
+        if let Some(first) = args.first() {
+            match resolve(first) {
+                ValueNode::Identifier(id) if id == "off" => {
+                    return Ok(vidra_core::MotionBlur::off())
+                }
+                v => blur.shutter_angle = Self::value_to_f64(&v)?,
+            }
+        }
+        for arg in named_args {
+            let value = Self::value_to_f64(&resolve(&arg.value))?;
+            match arg.name.as_str() {
+                "shutter" => blur.shutter_angle = value,
+                "samples" => blur.samples = value.max(1.0) as u32,
+                _ => {}
+            }
+        }
+        Ok(blur)
+    }
 
-        let Some(audio_path) = audio_path else {
-            // No audio file known; keep audio_amp=0.0.
-            return Ok((rewritten, None));
+    /// `mask(layerName, mode: luma, feather: 4)` uses another layer as a track matte;
+    /// `mask(rect, x: 0, y: 0, width: 400, height: 120)`, `mask(circle, radius: 50)` and
+    /// `mask(ellipse, rx: 80, ry: 40)` clip the layer to a shape in its own pixels.
+    fn compile_mask(
+        layer: &mut Layer,
+        args: &[ValueNode],
+        named_args: &[NamedArg],
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<(), VidraError> {
+        let resolve = |v: &ValueNode| -> ValueNode {
+            match v {
+                ValueNode::Identifier(id) => env.get(id).cloned().unwrap_or_else(|| v.clone()),
+                _ => v.clone(),
+            }
+        };
+        let number = |name: &str| -> Result<Option<f64>, VidraError> {
+            named_args
+                .iter()
+                .find(|a| a.name == name)
+                .map(|a| Self::value_to_f64(&resolve(&a.value)))
+                .transpose()
         };
 
-        // Best-effort: if ffmpeg isn't available, fall back to 0.
-        if !is_ffmpeg_available() {
-            return Ok((rewritten, None));
+        let mode = match named_args.iter().find(|a| a.name == "mode") {
+            Some(arg) => match Self::value_to_string(&arg.value)?.as_str() {
+                "alpha" => MatteMode::Alpha,
+                "alphaInverted" => MatteMode::AlphaInverted,
+                "luma" => MatteMode::Luma,
+                "lumaInverted" => MatteMode::LumaInverted,
+                other => return Err(VidraError::Compile(format!("unknown mask mode: {}", other))),
+            },
+            None => MatteMode::Alpha,
+        };
+        if let Some(feather) = number("feather")? {
+            layer.mask_feather = feather.max(0.0);
         }
 
-        let samples = compute_audio_rms_envelope(&audio_path, duration).map_err(|e| {
-            VidraError::Compile(format!("failed to compute audio amplitude: {}", e))
-        })?;
-        Ok((rewritten, Some(samples)))
+        let shape = match resolve(&args[0]) {
+            ValueNode::Identifier(kind) if kind == "rect" => Some(ShapeType::Rect {
+                width: number("width")?.unwrap_or(0.0),
+                height: number("height")?.unwrap_or(0.0),
+                corner_radius: number("cornerRadius")?.unwrap_or(0.0),
+            }),
+            ValueNode::Identifier(kind) if kind == "circle" => Some(ShapeType::Circle {
+                radius: number("radius")?.unwrap_or(0.0),
+            }),
+            ValueNode::Identifier(kind) if kind == "ellipse" => Some(ShapeType::Ellipse {
+                rx: number("rx")?.unwrap_or(0.0),
+                ry: number("ry")?.unwrap_or(0.0),
+            }),
+            _ => None,
+        };
+        match shape {
+            Some(_) if matches!(mode, MatteMode::Luma | MatteMode::LumaInverted) => {
+                Err(VidraError::Compile(
+                    "shape masks only support alpha and alphaInverted modes".into(),
+                ))
+            }
+            Some(shape) => {
+                let mut mask = ShapeMask::new(
+                    shape,
+                    number("x")?.unwrap_or(0.0),
+                    number("y")?.unwrap_or(0.0),
+                );
+                mask.inverted = mode.is_inverted();
+                layer.shape_mask = Some(mask);
+                Ok(())
+            }
+            None => {
+                layer.mask = Some(LayerId::new(Self::value_to_string(&resolve(&args[0]))?));
+                layer.matte_mode = mode;
+                Ok(())
+            }
+        }
     }
 
     // --- Value converters ---
@@ -1684,6 +2488,19 @@ impl Compiler {
         }
     }
 
+    /// A point written as `[x, y]`.
+    fn value_to_point(value: &ValueNode) -> Result<Vec<f64>, VidraError> {
+        match value {
+            ValueNode::Array(items) if items.len() == 2 => {
+                items.iter().map(Self::value_to_f64).collect()
+            }
+            _ => Err(VidraError::Compile(format!(
+                "expected a point [x, y], got {:?}",
+                value
+            ))),
+        }
+    }
+
     fn value_to_string(value: &ValueNode) -> Result<String, VidraError> {
         match value {
             ValueNode::String(s) => Ok(s.clone()),
@@ -1729,6 +2546,76 @@ impl Compiler {
         }
     }
 
+    /// A color, or a gradient written as `linearGradient(...)`, `radialGradient(...)`
+    /// or `conicGradient(...)`: leading numbers set the geometry, then each stop is a
+    /// color or `[color, offset]`. Stops without an offset are spread out evenly.
+    fn value_to_paint(
+        value: &ValueNode,
+        env: &HashMap<String, ValueNode>,
+    ) -> Result<Paint, VidraError> {
+        let ValueNode::Call { name, args } = value else {
+            return Ok(Paint::Solid(Self::value_to_color(value)?));
+        };
+        let Some((_, max_params)) = GRADIENTS.iter().find(|(n, _)| n == name) else {
+            return Err(VidraError::Compile(format!("unknown paint: {}(...)", name)));
+        };
+        let resolve = |v: &ValueNode| match v {
+            ValueNode::Identifier(id) => env.get(id).cloned().unwrap_or_else(|| v.clone()),
+            _ => v.clone(),
+        };
+        let args: Vec<ValueNode> = args.iter().map(resolve).collect();
+
+        let params: Vec<f64> = args
+            .iter()
+            .take(*max_params)
+            .map_while(|arg| match arg {
+                ValueNode::Number(n) => Some(*n),
+                _ => None,
+            })
+            .collect();
+        let mut colors = Vec::new();
+        let mut offsets = Vec::new();
+        for arg in &args[params.len()..] {
+            match arg {
+                ValueNode::Array(items) if items.len() == 2 => {
+                    colors.push(Self::value_to_color(&resolve(&items[0]))?);
+                    offsets.push(Some(Self::value_to_f64(&items[1])?));
+                }
+                other => {
+                    colors.push(Self::value_to_color(other)?);
+                    offsets.push(None);
+                }
+            }
+        }
+        if colors.len() < 2 {
+            return Err(VidraError::Compile(format!(
+                "{} needs at least two color stops",
+                name
+            )));
+        }
+        let stops = distribute_offsets(&offsets)
+            .into_iter()
+            .zip(colors)
+            .map(|(offset, color)| ColorStop::new(offset, color))
+            .collect();
+
+        let param = |i: usize, default: f64| params.get(i).copied().unwrap_or(default);
+        let gradient = match name.as_str() {
+            "linearGradient" => Gradient::linear(param(0, 180.0), stops),
+            "radialGradient" => Gradient::radial(
+                Point2D::new(param(0, 0.5), param(1, 0.5)),
+                param(2, 1.0),
+                stops,
+            ),
+            _ => Gradient::conic(
+                param(0, 0.0),
+                Point2D::new(param(1, 0.5), param(2, 0.5)),
+                stops,
+            ),
+        };
+        Ok(Paint::Gradient(gradient))
+    }
+
     fn value_to_duration(value: &ValueNode) -> Result<f64, VidraError> {
         match value {
             ValueNode::Duration(d) => Ok(*d),
@@ -1741,25 +2628,67 @@ impl Compiler {
         }
     }
 
-    fn value_to_easing(value: &ValueNode) -> Result<vidra_core::types::Easing, VidraError> {
+    fn value_to_blend_mode(value: &ValueNode) -> Result<BlendMode, VidraError> {
         let name = Self::value_to_string(value)?;
-        match name.as_str() {
-            "linear" => Ok(vidra_core::types::Easing::Linear),
-            "easeIn" => Ok(vidra_core::types::Easing::EaseIn),
-            "easeOut" => Ok(vidra_core::types::Easing::EaseOut),
-            "easeInOut" => Ok(vidra_core::types::Easing::EaseInOut),
-            "cubicIn" => Ok(vidra_core::types::Easing::CubicIn),
-            "cubicOut" => Ok(vidra_core::types::Easing::CubicOut),
-            "cubicInOut" => Ok(vidra_core::types::Easing::CubicInOut),
-            "easeOutBack" => Ok(vidra_core::types::Easing::EaseOutBack),
+        BLEND_MODES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, mode)| *mode)
+            .ok_or_else(|| VidraError::Compile(format!("unknown blend mode: {}", name)))
+    }
+
+    /// Look up an identifier in one of the VidraScript name tables.
+    fn value_to_named<T: Copy>(
+        value: &ValueNode,
+        names: &[(&str, T)],
+        what: &str,
+    ) -> Result<T, VidraError> {
+        let name = Self::value_to_string(value)?;
+        names
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| VidraError::Compile(format!("unknown {}: {}", what, name)))
+    }
+
+    /// An easing preset name, `cubicBezier(x1, y1, x2, y2)` or `steps(n)` /
+    /// `steps(n, position)`.
+    fn value_to_easing(value: &ValueNode) -> Result<Easing, VidraError> {
+        let ValueNode::Call { name, args } = value else {
+            return Self::value_to_named(value, &EASINGS, "easing function");
+        };
+        match (name.as_str(), args.as_slice()) {
+            ("cubicBezier", [x1, y1, x2, y2]) => Ok(Easing::CubicBezier(
+                Self::value_to_f64(x1)?,
+                Self::value_to_f64(y1)?,
+                Self::value_to_f64(x2)?,
+                Self::value_to_f64(y2)?,
+            )),
+            ("steps", [n]) => Ok(Easing::Steps(
+                Self::value_to_f64(n)?.max(1.0) as u32,
+                StepPosition::default(),
+            )),
+            ("steps", [n, position]) => Ok(Easing::Steps(
+                Self::value_to_f64(n)?.max(1.0) as u32,
+                Self::value_to_named(position, &STEP_POSITIONS, "step position")?,
+            )),
+            ("cubicBezier" | "steps", _) => Err(VidraError::Compile(format!(
+                "wrong number of arguments to {}(...)",
+                name
+            ))),
             _ => Err(VidraError::Compile(format!(
-                "unknown easing function: {}",
+                "unknown easing function: {}(...)",
                 name
             ))),
         }
     }
 }
 
+/// Rewrite an animation expression into the form evaluated at render time.
+pub(crate) fn normalize_expression(expr: &str) -> String {
+    rewrite_audio_expr(&rewrite_interactive_state_expr(expr).0)
+}
+
 fn rewrite_interactive_state_expr(expr: &str) -> (String, bool) {
     // evalexpr variable names cannot contain '@' or '.', so we rewrite.
     let rewritten = expr
@@ -1775,126 +2704,120 @@ fn rewrite_interactive_state_expr(expr: &str) -> (String, bool) {
     (rewritten, uses_mouse)
 }
 
-fn rewrite_audio_amplitude_expr(expr: &str) -> String {
-    // Replace audio.amplitude("...") or audio.amplitude(...) with audio_amp.
-    // This is a simple rewrite so evalexpr can use a normal variable name.
+/// `audio.<feature>` names usable in expressions and the variables they evaluate as.
+const AUDIO_FEATURES: &[(&str, &str)] = &[
+    ("amplitude", "audio_amp"),
+    ("amp", "audio_amp"),
+    ("peak", "audio_peak"),
+    ("bass", "audio_bass"),
+    ("mid", "audio_mid"),
+    ("treble", "audio_treble"),
+    ("beat", "audio_beat"),
+];
+
+fn rewrite_audio_expr(expr: &str) -> String {
+    // Replace audio.bass, audio.amplitude("...") etc. with audio_bass, audio_amp, ...
+    // so evalexpr can use normal variable names. The optional argument names the audio
+    // file and is picked up separately by `extract_audio_path`.
     let mut out = String::new();
-    let mut chars = expr.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == 'a' {
-            // Quick check for substring.
-            let mut lookahead = String::new();
-            lookahead.push(c);
-            for _ in 0..("udio.amplitude".len()) {
-                if let Some(n) = chars.peek().copied() {
-                    lookahead.push(n);
-                    chars.next();
-                }
-            }
-            if lookahead == "audio.amplitude" {
-                // If next is '(', consume until matching ')'.
-                if chars.peek() == Some(&'(') {
+    let mut rest = expr;
+    while let Some(pos) = rest.find("audio.") {
+        let preceded_by_ident = rest[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + "audio.".len()..];
+        let name_len = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        let var = AUDIO_FEATURES
+            .iter()
+            .find(|(name, _)| *name == &after[..name_len])
+            .map(|(_, var)| *var);
+        match var {
+            Some(var) if !preceded_by_ident => {
+                out.push_str(var);
+                rest = &after[name_len..];
+                // If next is '(', consume until the matching ')'.
+                if rest.starts_with('(') {
                     let mut depth = 0i32;
-                    while let Some(n) = chars.next() {
-                        if n == '(' {
+                    let mut end = rest.len();
+                    for (i, c) in rest.char_indices() {
+                        if c == '(' {
                             depth += 1;
-                        } else if n == ')' {
+                        } else if c == ')' {
                             depth -= 1;
                             if depth <= 0 {
+                                end = i + 1;
                                 break;
                             }
                         }
                     }
+                    rest = &rest[end..];
                 }
-                out.push_str("audio_amp");
-                continue;
             }
-            out.push_str(&lookahead);
-            continue;
+            _ => {
+                out.push_str("audio.");
+                rest = after;
+            }
         }
-        out.push(c);
     }
+    out.push_str(rest);
     out
 }
 
-fn extract_audio_amplitude_path(expr: &str) -> Option<String> {
-    // Look for audio.amplitude("path") and return path.
-    let needle = "audio.amplitude(\"";
-    let start = expr.find(needle)? + needle.len();
-    let rest = &expr[start..];
-    let end = rest.find('"')?;
-    Some(rest[..end].to_string())
+/// The index in a `stopN` animation property name.
+pub(crate) fn gradient_stop_index(property: &str) -> Option<usize> {
+    property.strip_prefix("stop")?.parse().ok()
 }
 
-fn is_ffmpeg_available() -> bool {
-    Command::new("ffmpeg")
-        .arg("-version")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .map(|s| s.success())
-        .unwrap_or(false)
-}
-
-fn compute_audio_rms_envelope(
-    audio_path: &str,
-    duration: f64,
-) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
-    // Decode to mono s16le at low sample rate to keep memory reasonable.
-    let sample_rate: f64 = 8000.0;
-    let fps: f64 = 60.0;
-    let dt: f64 = 1.0 / fps;
-
-    let mut child = Command::new("ffmpeg")
-        .args(["-v", "error", "-i"])
-        .arg(audio_path)
-        .args(["-vn", "-ac", "1", "-ar", "8000", "-f", "s16le", "-"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    let mut stdout = child
-        .stdout
-        .take()
-        .ok_or_else(|| "failed to open ffmpeg stdout")?;
-    let mut pcm = Vec::new();
-    stdout.read_to_end(&mut pcm)?;
-
-    let status = child.wait()?;
-    if !status.success() {
-        return Err("ffmpeg decode failed".into());
-    }
-
-    let sample_count = pcm.len() / 2;
-    let mut samples = Vec::with_capacity(sample_count);
-    for i in 0..sample_count {
-        let lo = pcm[i * 2] as u16;
-        let hi = (pcm[i * 2 + 1] as u16) << 8;
-        let v = (lo | hi) as i16;
-        samples.push(v as f64 / 32768.0);
-    }
-
-    let window = (sample_rate / fps).round().max(1.0) as usize;
-    let frame_count = (duration / dt).ceil() as usize + 1;
-    let mut out = Vec::with_capacity(frame_count);
-
-    for frame_idx in 0..frame_count {
-        let start = frame_idx * window;
-        if start >= samples.len() {
-            out.push(0.0);
-            continue;
-        }
-        let end = (start + window).min(samples.len());
-        let mut sum_sq = 0.0;
-        for &s in &samples[start..end] {
-            sum_sq += s * s;
+/// Gradient stop offsets with the missing ones filled in as CSS does: the first and
+/// last default to 0 and 1, and a run of missing offsets is spread evenly between its
+/// neighbours.
+fn distribute_offsets(offsets: &[Option<f64>]) -> Vec<f64> {
+    let mut out = offsets.to_vec();
+    let n = out.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    out[0] = out[0].or(Some(0.0));
+    out[n - 1] = out[n - 1].or(Some(1.0));
+    let mut known = 0;
+    for i in 1..n {
+        if let Some(b) = out[i] {
+            let a = out[known].unwrap_or(0.0);
+            let gap = (i - known) as f64;
+            for (k, slot) in out.iter_mut().enumerate().take(i).skip(known + 1) {
+                *slot = Some(a + (b - a) * (k - known) as f64 / gap);
+            }
+            known = i;
         }
-        let mean = sum_sq / (end - start).max(1) as f64;
-        let rms = mean.sqrt().clamp(0.0, 1.0);
-        out.push(rms);
     }
+    out.into_iter().map(|o| o.unwrap_or(0.0)).collect()
+}
 
-    Ok(out)
+/// The file named by the first `audio.<feature>("path")` call in `expr`.
+fn extract_audio_path(expr: &str) -> Option<String> {
+    AUDIO_FEATURES
+        .iter()
+        .filter_map(|(name, _)| {
+            let needle = format!("audio.{}(\"", name);
+            let start = expr.find(&needle)? + needle.len();
+            let end = expr[start..].find('"')?;
+            Some((start, expr[start..start + end].to_string()))
+        })
+        .min_by_key(|(start, _)| *start)
+        .map(|(_, path)| path)
+}
+
+/// Whether `expr` uses `ident` as a whole identifier.
+fn contains_identifier(expr: &str, ident: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    expr.match_indices(ident).any(|(i, _)| {
+        !expr[..i].chars().next_back().is_some_and(is_ident)
+            && !expr[i + ident.len()..].chars().next().is_some_and(is_ident)
+    })
 }
 
 #[cfg(test)]
@@ -1904,18 +2827,25 @@ mod audio_expr_tests {
     #[test]
     fn rewrite_replaces_audio_amplitude_calls() {
         let src = "1 + audio.amplitude(\"assets/a.mp3\") * 2";
-        let out = rewrite_audio_amplitude_expr(src);
+        let out = rewrite_audio_expr(src);
         assert_eq!(out, "1 + audio_amp * 2");
     }
 
     #[test]
-    fn extract_path_from_audio_amplitude() {
-        let src = "audio.amplitude(\"assets/a.mp3\")";
+    fn rewrite_replaces_audio_feature_names() {
+        let src = "audio.bass(\"a.wav\") * 2 + audio.beat - my_audio.mid + audio.other";
+        let out = rewrite_audio_expr(src);
         assert_eq!(
-            extract_audio_amplitude_path(src).as_deref(),
-            Some("assets/a.mp3")
+            out,
+            "audio_bass * 2 + audio_beat - my_audio.mid + audio.other"
         );
     }
+
+    #[test]
+    fn extract_path_from_audio_amplitude() {
+        let src = "audio.amplitude(\"assets/a.mp3\")";
+        assert_eq!(extract_audio_path(src).as_deref(), Some("assets/a.mp3"));
+    }
 }
 
 #[cfg(test)]
@@ -1953,7 +2883,7 @@ mod tests {
 
         match &project.scenes[0].layers[0].content {
             LayerContent::Solid { color } => {
-                assert_eq!(color.to_rgba8(), [0, 0, 0, 255]);
+                assert_eq!(color.as_solid().unwrap().to_rgba8(), [0, 0, 0, 255]);
             }
             _ => panic!("expected solid layer"),
         }
@@ -1981,8 +2911,12 @@ mod tests {
                 font_family,
                 font_size,
                 color,
+                style,
+                animators,
             } => {
                 assert_eq!(text, "Hello");
+                assert!(style.is_plain());
+                assert!(animators.is_empty());
                 assert_eq!(font_family, "Inter Bold");
                 assert!((font_size - 72.0).abs() < 0.001);
                 assert_eq!(color.to_rgba8(), [255, 255, 255, 255]);
@@ -1993,6 +2927,322 @@ mod tests {
         assert!((layer.transform.position.y - 200.0).abs() < 0.001);
     }
 
+    #[test]
+    fn test_compile_rich_text_layer() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("s", 3s) {
+                    layer("caption") {
+                        text("Say <b>hi</b> <color=#FF0000>now</color>", maxWidth: 600, align: center, letterSpacing: 2, lineHeight: 1.5, stroke: #000000, strokeWidth: 3, shadowY: 6)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let LayerContent::Text { text, style, .. } = &project.scenes[0].layers[0].content else {
+            panic!("expected text layer");
+        };
+        assert_eq!(text, "Say hi now");
+        assert_eq!(style.max_width, Some(600.0));
+        assert_eq!(style.align, TextAlign::Center);
+        assert_eq!(style.letter_spacing, 2.0);
+        assert_eq!(style.line_height, Some(1.5));
+        assert_eq!(style.stroke.as_ref().map(|s| s.width), Some(3.0));
+        let shadow = style.shadow.as_ref().unwrap();
+        assert_eq!(
+            (shadow.offset_x, shadow.offset_y, shadow.blur),
+            (0.0, 6.0, 4.0)
+        );
+        assert_eq!(style.spans.len(), 2);
+        assert_eq!((style.spans[1].start, style.spans[1].end), (7, 10));
+        assert_eq!(style.spans[1].color, Some(Color::rgb(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_compile_transitions() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("a", 2s) {
+                    layer("bg") { solid(#000000) }
+                }
+                scene("b", 2s) {
+                    transition("push", 0.5s, ease: easeOutBack, direction: up)
+                }
+                scene("c", 2s) {
+                    transition("iris", 1s, center: [0.25, 0.75])
+                }
+                scene("d", 2s) {
+                    transition("shader", 1s, fx: "@transition dip(depth: 0.5) { blend(from(), to(), progress) * depth }", depth: 0.8)
+                }
+                scene("e", 2s) {
+                    transition("ripple", 1s, ease: "easeInOut", waves: 3)
+                }
+            }
+        "#,
+        );
+
+        let transition = |i: usize| project.scenes[i].transition.clone().unwrap();
+        assert_eq!(
+            transition(1).effect,
+            TransitionType::Push {
+                direction: Direction::Up
+            }
+        );
+        assert_eq!(transition(1).easing, Easing::EaseOutBack);
+        assert_eq!(
+            transition(2).effect,
+            TransitionType::Iris {
+                center: Point2D::new(0.25, 0.75)
+            }
+        );
+        let TransitionType::Shader {
+            wgsl_source,
+            params,
+        } = transition(3).effect
+        else {
+            panic!("expected a shader transition");
+        };
+        assert!(wgsl_source.contains("t_to"));
+        assert_eq!(params, [0.8]);
+        assert_eq!(
+            transition(4).effect,
+            TransitionType::Plugin {
+                name: "ripple".into(),
+                params: [("waves".to_string(), 3.0)].into(),
+            }
+        );
+        assert_eq!(transition(4).easing, Easing::EaseInOut);
+
+        let mut lexer = Lexer::new(
+            r#"project(1920, 1080, 30) { scene("a", 1s) { transition("wipe", 1s, direction: sideways) } }"#,
+        );
+        let ast = Parser::new(lexer.tokenize().unwrap(), "test.vidra")
+            .parse()
+            .unwrap();
+        let err = Compiler::compile(&ast).unwrap_err();
+        assert!(err.to_string().contains("sideways"));
+    }
+
+    #[test]
+    fn test_compile_and_check_layer_styles() {
+        let project = compile(
+            r#"
+            project(100, 100, 30) {
+                scene("a", 1s) {
+                    layer("title") {
+                        solid(#FFFFFF)
+                        effect(dropShadow, x: 2, y: 6, blur: 12, color: #00000080)
+                        effect(stroke, width: 3, position: center)
+                        effect(bevel)
+                        animation(shadowBlur, from: 0, to: 12, duration: 1s)
+                    }
+                }
+            }
+        "#,
+        );
+        let layer = &project.scenes[0].layers[0];
+        assert_eq!(
+            layer.effects[0],
+            LayerEffect::DropShadow {
+                offset: Point2D::new(2.0, 6.0),
+                blur: 12.0,
+                color: Color::from_hex("#00000080").unwrap(),
+                spread: 0.0,
+            }
+        );
+        assert_eq!(
+            layer.effects[1],
+            LayerEffect::Stroke {
+                width: 3.0,
+                color: Color::BLACK,
+                position: StrokePosition::Center,
+            }
+        );
+        assert!(matches!(layer.effects[2], LayerEffect::Bevel { size, .. } if size == 4.0));
+        assert_eq!(layer.animations[0].property, AnimatableProperty::ShadowBlur);
+
+        let src = r#"
+            project(100, 100, 30) {
+                scene("a", 1s) {
+                    layer("title") {
+                        solid(#FFFFFF)
+                        effect(outerGlow, radius: 4, color: 3)
+                        effect(stroke, position: around)
+                    }
+                }
+            }
+        "#;
+        let ast = Parser::new(Lexer::new(src).tokenize().unwrap(), "test.vidra")
+            .parse()
+            .unwrap();
+        let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .unwrap_err();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "effect(outerGlow) has no option 'radius'",
+                "expected Color, got Number",
+                "expected one of: outside, inside, center",
+            ]
+        );
+        let err = Compiler::compile(&ast).unwrap_err();
+        assert!(err.to_string().contains("radius"));
+    }
+
+    #[test]
+    fn test_compile_and_check_plugins() {
+        use vidra_core::plugin::{PluginKind, PluginSignature};
+        use vidra_core::types::LayerEffect;
+
+        let src = r#"
+            project(100, 100, 30) {
+                scene("a", 1s) {
+                    layer("stars") {
+                        plugin(particles, count: 200, color: #FFCC00)
+                        effect(glow, radius: 4)
+                    }
+                }
+                scene("b", 1s) {
+                    transition("ripple", 1s, waves: 3)
+                }
+            }
+        "#;
+        let project = compile(src);
+        let layer = &project.scenes[0].layers[0];
+        assert_eq!(
+            layer.content,
+            LayerContent::Plugin {
+                name: "particles".into(),
+                props: [
+                    ("count".to_string(), "200".to_string()),
+                    ("color".to_string(), "#FFCC00".to_string()),
+                ]
+                .into(),
+            }
+        );
+        assert_eq!(
+            layer.effects,
+            [LayerEffect::Plugin {
+                name: "glow".into(),
+                params: [("radius".to_string(), 4.0)].into(),
+            }]
+        );
+
+        let ast = Parser::new(Lexer::new(src).tokenize().unwrap(), "test.vidra")
+            .parse()
+            .unwrap();
+        let signature = |kind, name: &str, params: &[&str]| PluginSignature {
+            kind,
+            name: name.to_string(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+        };
+        let check = |plugins| {
+            crate::checker::TypeChecker::new("test.vidra")
+                .with_plugins(plugins)
+                .check(&ast)
+        };
+        // Plugins are only checked against the ones known to be registered.
+        assert!(crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .is_ok());
+        assert!(check(vec![
+            signature(PluginKind::Layer, "particles", &["count", "color"]),
+            signature(PluginKind::Effect, "glow", &[]),
+            signature(PluginKind::Transition, "ripple", &["waves"]),
+        ])
+        .is_ok());
+
+        let diagnostics = check(vec![
+            signature(PluginKind::Layer, "particles", &["count"]),
+            signature(PluginKind::Transition, "glow", &[]),
+        ])
+        .unwrap_err();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "layer plugin 'particles' has no parameter 'color'",
+                "no effect plugin named 'glow' is registered",
+                "no transition plugin named 'ripple' is registered",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_text_animators() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("s", 3s) {
+                    layer("title") {
+                        text("Hello kinetic world")
+                        animate.stagger(by: word, offset: 0.1s, rangeEnd: 0.5) {
+                            animation(opacity, from: 0, to: 1, duration: 0.4s, ease: easeOut)
+                            animation(scale, from: 2, to: 1, duration: 0.4s)
+                            animation(rangeOffset, from: 0, to: 0.5, duration: 2s)
+                        }
+                    }
+                    layer("caption") {
+                        text("Hi")
+                    }
+                    animate.stagger(layers: ["caption"], by: char, offset: 0.05) {
+                        animation(y, from: 20, to: 0, duration: 0.3s)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let layers = &project.scenes[0].layers;
+        let LayerContent::Text { animators, .. } = &layers[0].content else {
+            panic!("expected text layer");
+        };
+        assert_eq!(animators.len(), 1);
+        let animator = &animators[0];
+        assert_eq!(animator.unit, TextUnit::Word);
+        assert_eq!(animator.stagger, 0.1);
+        assert_eq!((animator.range.start, animator.range.end), (0.0, 0.5));
+        let properties: Vec<_> = animator.animations.iter().map(|a| &a.property).collect();
+        assert_eq!(
+            properties,
+            [
+                &AnimatableProperty::Opacity,
+                &AnimatableProperty::ScaleY,
+                &AnimatableProperty::ScaleX,
+                &AnimatableProperty::TextRangeOffset,
+            ]
+        );
+        assert!(layers[0].animations.is_empty());
+
+        let LayerContent::Text { animators, .. } = &layers[1].content else {
+            panic!("expected text layer");
+        };
+        assert_eq!(animators[0].unit, TextUnit::Char);
+        assert_eq!(animators[0].stagger, 0.05);
+
+        let src = r#"
+            project(100, 100, 30) {
+                scene("s", 1s) {
+                    layer("box") {
+                        solid(#ffffff)
+                        animate.stagger(by: char) {
+                            animation(opacity, from: 0, to: 1)
+                        }
+                    }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let err = Compiler::compile(&ast).unwrap_err().to_string();
+        assert!(err.contains("'box' is not one"), "{}", err);
+    }
+
     #[test]
     fn test_compile_web_layer() {
         let project = compile(
@@ -2110,7 +3360,7 @@ mod tests {
 
         let btn_bg = &btn1_wrapper.children[0];
         assert!(
-            matches!(btn_bg.content, LayerContent::Solid { color: c } if c.to_rgba8() == [0, 0, 255, 255])
+            matches!(&btn_bg.content, LayerContent::Solid { color: c } if c.as_solid().unwrap().to_rgba8() == [0, 0, 255, 255])
         );
         assert_eq!(btn_bg.animations[0].duration().as_seconds(), 1.0); // Default t_duration
 
@@ -2234,21 +3484,27 @@ mod tests {
         let btn_default = &main_scene.layers[0];
         assert_eq!(btn_default.children.len(), 1);
         match &btn_default.children[0].content {
-            LayerContent::Solid { color } => assert_eq!(color.to_rgba8(), [255, 255, 255, 255]),
+            LayerContent::Solid { color } => {
+                assert_eq!(color.as_solid().unwrap().to_rgba8(), [255, 255, 255, 255])
+            }
             _ => panic!("Expected solid content"),
         }
 
         let btn_primary = &main_scene.layers[1];
         assert_eq!(btn_primary.children.len(), 1);
         match &btn_primary.children[0].content {
-            LayerContent::Solid { color } => assert_eq!(color.to_rgba8(), [0, 0, 255, 255]),
+            LayerContent::Solid { color } => {
+                assert_eq!(color.as_solid().unwrap().to_rgba8(), [0, 0, 255, 255])
+            }
             _ => panic!("Expected solid content"),
         }
 
         let btn_danger = &main_scene.layers[2];
         assert_eq!(btn_danger.children.len(), 1);
         match &btn_danger.children[0].content {
-            LayerContent::Solid { color } => assert_eq!(color.to_rgba8(), [255, 0, 0, 255]),
+            LayerContent::Solid { color } => {
+                assert_eq!(color.as_solid().unwrap().to_rgba8(), [255, 0, 0, 255])
+            }
             _ => panic!("Expected solid content"),
         }
     }
@@ -2311,7 +3567,9 @@ mod tests {
         assert_eq!(box_layer.animations.len(), 2);
 
         match &box_layer.content {
-            LayerContent::Solid { color } => assert_eq!(color.to_rgba8(), [255, 204, 0, 255]),
+            LayerContent::Solid { color } => {
+                assert_eq!(color.as_solid().unwrap().to_rgba8(), [255, 204, 0, 255])
+            }
             _ => panic!("Expected solid content"),
         }
     }
@@ -2357,6 +3615,413 @@ mod tests {
         assert_eq!(seq_item.animations[1].delay.as_seconds(), 1.5);
     }
 
+    #[test]
+    fn test_invalid_expression_reports_layer_and_span() {
+        let src = r#"
+            project(1920, 1080, 30) {
+                scene("main", 5s) {
+                    layer("wobble") {
+                        animation(y, expr: "sin(t * ", duration: 2.0)
+                    }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+
+        let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .unwrap_err();
+        let diag = diagnostics
+            .iter()
+            .find(|d| d.message.contains("invalid expression"))
+            .expect("checker should flag the expression");
+        assert!(diag.message.contains("layer 'wobble'"));
+        assert_eq!(diag.span.line, 5);
+
+        let err = Compiler::compile(&ast).unwrap_err().to_string();
+        assert!(err.contains("layer 'wobble'"), "{}", err);
+        assert!(err.contains("at 5:"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_motion_blur_settings() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 24) {
+                motionBlur(180, samples: 12)
+
+                scene("main", 2s) {
+                    layer("fast") {
+                        solid(#FFFFFF)
+                        motionBlur(shutter: 270)
+                    }
+                    layer("crisp") {
+                        solid(#000000)
+                        motionBlur(off)
+                    }
+                    layer("plain") {
+                        solid(#000000)
+                    }
+                }
+            }
+        "#,
+        );
+
+        use vidra_core::MotionBlur;
+        assert_eq!(
+            project.settings.motion_blur,
+            Some(MotionBlur::new(180.0, 12))
+        );
+        let layers = &project.scenes[0].layers;
+        assert_eq!(
+            layers[0].motion_blur,
+            Some(MotionBlur::new(270.0, MotionBlur::DEFAULT_SAMPLES))
+        );
+        assert_eq!(layers[1].motion_blur, Some(MotionBlur::off()));
+        assert_eq!(layers[2].motion_blur, None);
+    }
+
+    #[test]
+    fn test_compile_masks() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("main", 2s) {
+                    layer("matte") {
+                        solid(#FFFFFF)
+                    }
+                    layer("title") {
+                        text("Hello", size: 48)
+                        mask("matte", mode: lumaInverted, feather: 4)
+                    }
+                    layer("bar") {
+                        solid(#FF0000)
+                        mask(rect, x: 10, y: 20, width: 0, height: 80, mode: alphaInverted)
+                        animation(maskWidth, from: 0, to: 400, duration: 1s)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let layers = &project.scenes[0].layers;
+        assert!(!layers[0].visible, "track matte layers are not drawn");
+        assert_eq!(layers[1].mask, Some(LayerId::new("matte")));
+        assert_eq!(layers[1].matte_mode, MatteMode::LumaInverted);
+        assert_eq!(layers[1].mask_feather, 4.0);
+
+        let bar = &layers[2];
+        assert_eq!(bar.mask, None);
+        let mask = bar.shape_mask.as_ref().unwrap();
+        assert!(mask.inverted);
+        assert_eq!((mask.position.x, mask.position.y), (10.0, 20.0));
+        assert_eq!(mask.size(), (0.0, 80.0));
+        assert_eq!(bar.animations[0].property, AnimatableProperty::MaskWidth);
+    }
+
+    #[test]
+    fn test_shape_masks_reject_luma() {
+        let src = r#"
+            project(1920, 1080, 30) {
+                scene("main", 2s) {
+                    layer("bar") {
+                        solid(#FF0000)
+                        mask(circle, radius: 50, mode: luma)
+                    }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+
+        let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .unwrap_err();
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.message == "mask mode must be one of: alpha, alphaInverted"));
+
+        let err = Compiler::compile(&ast).unwrap_err().to_string();
+        assert!(err.contains("shape masks only support alpha"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_blend_modes() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("main", 2s) {
+                    layer("glow") {
+                        solid(#FFFFFF)
+                        blend(colorDodge)
+                    }
+                    layer("tint") {
+                        solid(#FF0000)
+                        blend(luminosity)
+                    }
+                    layer("plain") {
+                        solid(#000000)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let layers = &project.scenes[0].layers;
+        assert_eq!(layers[0].blend_mode, BlendMode::ColorDodge);
+        assert_eq!(layers[1].blend_mode, BlendMode::Luminosity);
+        assert_eq!(layers[2].blend_mode, BlendMode::Normal);
+        assert_eq!(BLEND_MODES.len(), BlendMode::ALL.len());
+    }
+
+    #[test]
+    fn test_compile_path_shape() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("main", 2s) {
+                    layer("line") {
+                        shape(path, d: "M0 0 C50 0 50 100 100 100", stroke: #FFFFFF, strokeWidth: 4, cap: round, join: bevel, dash: [10, 5], trimEnd: 0.5)
+                        animation(trimOffset, from: 0, to: 1, duration: 2s)
+                    }
+                    layer("ring") {
+                        shape(path, d: "M0 0 H10 V10 H0 Z M2 2 H8 V8 H2 Z", fillRule: evenOdd)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let layers = &project.scenes[0].layers;
+        let LayerContent::Shape {
+            shape: ShapeType::Path(path),
+            fill,
+            stroke,
+            ..
+        } = &layers[0].content
+        else {
+            panic!("expected a path shape, got {:?}", layers[0].content);
+        };
+        assert_eq!(path.data.segments.len(), 2);
+        assert_eq!(path.stroke_style.cap, LineCap::Round);
+        assert_eq!(path.stroke_style.join, LineJoin::Bevel);
+        assert_eq!(path.stroke_style.dash, vec![10.0, 5.0]);
+        assert_eq!(path.trim.end, 0.5);
+        // Stroked paths without a fill are outlines.
+        assert!(fill.is_none() && stroke.is_some());
+        assert_eq!(
+            layers[0].animations[0].property,
+            AnimatableProperty::TrimOffset
+        );
+
+        let LayerContent::Shape {
+            shape: ShapeType::Path(ring),
+            fill,
+            ..
+        } = &layers[1].content
+        else {
+            panic!("expected a path shape");
+        };
+        assert_eq!(ring.fill_rule, FillRule::EvenOdd);
+        assert_eq!(*fill, Some(Color::WHITE.into()));
+
+        let bad = r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("x") { shape(path, d: "M0 0 Q10") }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(bad).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let err = Compiler::compile(&ast).unwrap_err().to_string();
+        assert!(err.contains("invalid path data"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_gradients() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                @var accent = #FF0000
+                scene("main", 2s) {
+                    layer("bg") {
+                        solid(linearGradient(90, #000000, [accent, 0.25], #FFFFFF, #0000FF))
+                        animation(gradientAngle, from: 90, to: 270, duration: 2s)
+                        animation(stop1, from: 0.25, to: 0.75, duration: 2s)
+                    }
+                    layer("badge") {
+                        shape(circle, radius: 40, fill: radialGradient(0.5, 0.3, #FFFFFF, #000000))
+                    }
+                }
+            }
+        "#,
+        );
+
+        let layers = &project.scenes[0].layers;
+        let LayerContent::Solid {
+            color: Paint::Gradient(gradient),
+        } = &layers[0].content
+        else {
+            panic!("expected a gradient, got {:?}", layers[0].content);
+        };
+        assert_eq!(
+            gradient.kind,
+            vidra_core::paint::GradientKind::Linear { angle: 90.0 }
+        );
+        // Unpositioned stops are spread between their neighbours.
+        let offsets: Vec<f64> = gradient.stops.iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, vec![0.0, 0.25, 0.625, 1.0]);
+        assert_eq!(gradient.stops[1].color, Color::RED);
+        let properties: Vec<_> = layers[0].animations.iter().map(|a| &a.property).collect();
+        assert_eq!(
+            properties,
+            vec![
+                &AnimatableProperty::GradientAngle,
+                &AnimatableProperty::GradientStop(1)
+            ]
+        );
+
+        let LayerContent::Shape {
+            fill: Some(Paint::Gradient(radial)),
+            ..
+        } = &layers[1].content
+        else {
+            panic!("expected a gradient fill");
+        };
+        assert_eq!(
+            radial.kind,
+            vidra_core::paint::GradientKind::Radial {
+                center: Point2D::new(0.5, 0.3),
+                radius: 1.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_checker_rejects_bad_gradients() {
+        let src = r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("a") { solid(linearGradient(90, #000000)) }
+                    layer("b") { solid(sparkle(#000000, #FFFFFF)) }
+                    layer("c") { text("hi", color: conicGradient(#000000, #FFFFFF)) }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .unwrap_err();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert!(messages.contains(&"linearGradient needs at least two color stops"));
+        assert!(messages
+            .iter()
+            .any(|m| m.starts_with("expected Color or one of: linearGradient")));
+        assert!(
+            messages.contains(&"expected Color, got Gradient"),
+            "{:?}",
+            messages
+        );
+    }
+
+    #[test]
+    fn test_compile_svg_images() {
+        let project = compile(
+            r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("logo") {
+                        image("brand/logo.svg", groups: true)
+                        layer("mark") { animation(rotation, from: 0, to: 90, duration: 1s) }
+                    }
+                    layer("word") { image("brand/logo.svg", element: "word") }
+                    layer("photo") { image("photo.png") }
+                }
+            }
+        "#,
+        );
+
+        let layers = &project.scenes[0].layers;
+        let id = AssetId::new("brand/logo.svg");
+        assert_eq!(
+            layers[0].content,
+            LayerContent::Svg {
+                asset_id: id.clone(),
+                element: None,
+                exclude: Vec::new(),
+                expand_groups: true,
+            }
+        );
+        assert_eq!(layers[0].children[0].content, LayerContent::Empty);
+        assert_eq!(
+            layers[1].content,
+            LayerContent::Svg {
+                asset_id: id.clone(),
+                element: Some("word".to_string()),
+                exclude: Vec::new(),
+                expand_groups: false,
+            }
+        );
+        assert!(matches!(layers[2].content, LayerContent::Image { .. }));
+        assert_eq!(project.assets.get(&id).unwrap().asset_type, AssetType::Svg);
+    }
+
+    #[test]
+    fn test_checker_rejects_bad_image_arguments() {
+        let src = r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("a") { image("photo.png", element: "mark") }
+                    layer("b") { image("logo.svg", groups: 1) }
+                    layer("c") { image("logo.svg", fit: cover) }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .unwrap_err();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "'element' only applies to SVG images",
+                "expected one of: true, false",
+                "unknown property 'fit' for image layer",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audio_expression_is_evaluated_at_render_time() {
+        let project = compile(
+            r#"
+            project(1920, 1080, 30) {
+                scene("main", 5s) {
+                    layer("pulse") {
+                        animation(scaleX, expr: "1 + audio.bass(\"music/kick.wav\") * 0.5", duration: 5.0)
+                        animation(opacity, expr: "0.5 + audio.beat * 0.5", audio: "music/kick.wav", duration: 5.0)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let anims = &project.scenes[0].layers[0].animations;
+        assert_eq!(anims[0].expr.as_deref(), Some("1 + audio_bass * 0.5"));
+        assert_eq!(anims[1].expr.as_deref(), Some("0.5 + audio_beat * 0.5"));
+        let id = AssetId::new("music/kick.wav");
+        assert!(anims.iter().all(|a| a.expr_audio.as_ref() == Some(&id)));
+        assert!(anims.iter().all(|a| a.keyframes.is_empty()));
+
+        let asset = project.assets.get(&id).expect("audio should be registered");
+        assert_eq!(asset.asset_type, AssetType::Audio);
+    }
+
     #[test]
     fn test_compile_advanced_animations() {
         let project = compile(
@@ -2383,7 +4048,11 @@ mod tests {
 
         let l1 = &s.layers[0];
         assert_eq!(l1.animations.len(), 1);
-        assert!(l1.animations[0].keyframes.len() > 2);
+        assert_eq!(l1.animations[0].keyframes.len(), 2);
+        assert_eq!(
+            l1.animations[0].spring,
+            Some(Spring::new(1.0, 50.0, 5.0, 0.0))
+        );
 
         let l2 = &s.layers[1];
         assert_eq!(l2.animations.len(), 1);
@@ -2396,7 +4065,9 @@ mod tests {
         assert!(l_mouse.animations[0].expr_duration.is_some());
 
         let l3 = &s.layers[3];
-        assert_eq!(l3.animations.len(), 2);
+        assert_eq!(l3.animations.len(), 1);
+        assert_eq!(l3.animations[0].property, AnimatableProperty::Position);
+        assert_eq!(l3.animations[0].keyframes[1].vector, vec![100.0, 100.0]);
     }
 
     #[test]
@@ -2593,4 +4264,233 @@ mod tests {
             other => panic!("expected Spritesheet content, got {:?}", other),
         }
     }
+
+    #[test]
+    fn test_compile_lottie_layer() {
+        let project = compile(
+            r#"
+            project(640, 360, 30) {
+                scene("main", 2s) {
+                    layer("intro") {
+                        lottie("anim/intro.json")
+                        position(100, 50)
+                        layer("caption") { text("Hello") }
+                    }
+                }
+            }
+        "#,
+        );
+
+        let layer = &project.scenes[0].layers[0];
+        let id = AssetId::new("anim/intro.json");
+        assert_eq!(
+            layer.content,
+            LayerContent::Lottie {
+                asset_id: id.clone()
+            }
+        );
+        assert_eq!(layer.children.len(), 1);
+        assert_eq!(
+            project.assets.get(&id).unwrap().asset_type,
+            AssetType::Lottie
+        );
+    }
+
+    #[test]
+    fn test_compile_easings_and_hold() {
+        let project = compile(
+            r#"
+            project(640, 360, 30) {
+                scene("main", 2s) {
+                    layer("a") {
+                        solid(#ff0000)
+                        animation(rotation, from: -90, to: 0, duration: 1s, easing: cubicBezier(0.68, -0.55, 0.27, 1.55))
+                        animation(opacity, from: 0, to: 1, duration: 1s, easing: steps(4, jumpStart))
+                        animation(scaleX, from: 1, to: 2, duration: 1s, easing: steps(3))
+                        animation(scaleY, from: 1, to: 2, duration: 1s, easing: easeOutBounce)
+                        animation(x, from: 0, to: 50, duration: 1s, easing: hold)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let anims = &project.scenes[0].layers[0].animations;
+        assert_eq!(anims[0].keyframes[0].value, -90.0);
+        assert_eq!(
+            anims[0].keyframes[1].easing,
+            Easing::CubicBezier(0.68, -0.55, 0.27, 1.55)
+        );
+        assert_eq!(
+            anims[1].keyframes[1].easing,
+            Easing::Steps(4, StepPosition::JumpStart)
+        );
+        assert_eq!(
+            anims[2].keyframes[1].easing,
+            Easing::Steps(3, StepPosition::JumpEnd)
+        );
+        assert_eq!(anims[3].keyframes[1].easing, Easing::EaseOutBounce);
+        assert!(anims[4].keyframes[0].hold);
+        assert!(!anims[3].keyframes[0].hold);
+        assert_eq!(
+            anims[4].evaluate(vidra_core::Duration::from_seconds(0.9)),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn test_checker_rejects_bad_easings() {
+        let src = r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("a") {
+                        animation(opacity, from: 0, to: 1, duration: 1s, easing: cubicBezier(0.4, 0))
+                        animation(opacity, from: 0, to: 1, duration: 1s, easing: steps(2, middle))
+                        animation(opacity, from: 0, to: 1, duration: 1s, easing: wobble(2))
+                    }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+            .check(&ast)
+            .unwrap_err();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "cubicBezier takes x1, y1, x2, y2",
+                "expected one of: jumpStart, jumpEnd, jumpNone, jumpBoth",
+                "expected an easing, got wobble(...)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compile_spring_animation() {
+        let project = compile(
+            r#"
+            project(640, 360, 30) {
+                scene("main", 3s) {
+                    layer("a") {
+                        solid(#ff0000)
+                        animate.sequence {
+                            animation(x, from: 0, to: 200, stiffness: 170, damping: 26, mass: 2, velocity: 40)
+                            animation(y, from: 0, to: 100, duration: 1s)
+                        }
+                    }
+                }
+            }
+        "#,
+        );
+
+        let anims = &project.scenes[0].layers[0].animations;
+        let spring = Spring::new(2.0, 170.0, 26.0, 40.0);
+        assert_eq!(anims[0].spring, Some(spring));
+        let settle = spring.settle_time(-200.0);
+        assert!((anims[0].duration().as_seconds() - settle).abs() < 1e-9);
+        assert!((anims[1].delay.as_seconds() - settle).abs() < 1e-9);
+
+        let src = r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("a") {
+                        animation(x, from: 0, to: 1, stiffness: 0)
+                    }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let err = Compiler::compile(&ast).unwrap_err().to_string();
+        assert!(err.contains("positive mass and stiffness"), "{}", err);
+    }
+
+    #[test]
+    fn test_compile_vector_color_and_path_animations() {
+        let project = compile(
+            r#"
+            project(640, 360, 30) {
+                scene("main", 3s) {
+                    layer("a") {
+                        solid(#ff0000)
+                        animation(position, from: [0, 0], to: [200, 100], duration: 1s, easing: easeOut)
+                        animation(scale, from: 1, to: [2, 0.5], duration: 1s)
+                        animation(color, from: #ff0000, to: #00ff00, duration: 1s, space: linear)
+                        animation(position, path: "M0 0 C50 -50 150 -50 200 0 L200 100", duration: 2s, autoOrient: true)
+                    }
+                }
+            }
+        "#,
+        );
+
+        let anims = &project.scenes[0].layers[0].animations;
+        assert_eq!(anims[0].property, AnimatableProperty::Position);
+        assert_eq!(anims[0].keyframes[1].vector, vec![200.0, 100.0]);
+        assert_eq!(anims[0].keyframes[1].easing, Easing::EaseOut);
+        assert_eq!(anims[1].property, AnimatableProperty::Scale);
+        assert_eq!(anims[1].keyframes[0].vector, vec![1.0, 1.0]);
+        assert_eq!(anims[2].property, AnimatableProperty::Color);
+        assert_eq!(anims[2].color_space, Some(ColorSpace::LinearRgb));
+        assert_eq!(anims[2].keyframes[0].vector, vec![1.0, 0.0, 0.0, 1.0]);
+
+        let path = &anims[3];
+        assert!(path.auto_orient);
+        assert_eq!(path.keyframes.len(), 3);
+        assert_eq!(path.keyframes[0].out_tangent, Some([50.0, -50.0]));
+        assert_eq!(path.keyframes[1].in_tangent, Some([-50.0, -50.0]));
+        assert_eq!(path.keyframes[1].out_tangent, Some([0.0, 0.0]));
+        // The curve is longer than the 100 px line, so it takes more than half the time.
+        let split = path.keyframes[1].time.as_seconds();
+        assert!(split > 1.0 && split < 2.0, "{}", split);
+        let end = path
+            .evaluate_vector(vidra_core::Duration::from_seconds(2.0))
+            .unwrap();
+        assert_eq!(end, vec![200.0, 100.0]);
+
+        let src = r#"
+            project(100, 100, 30) {
+                scene("main", 1s) {
+                    layer("a") {
+                        animation(position, path: "M0 0 Q", duration: 1s)
+                    }
+                }
+            }
+        "#;
+        let tokens = Lexer::new(src).tokenize().unwrap();
+        let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+        let err = Compiler::compile(&ast).unwrap_err().to_string();
+        assert!(err.contains("invalid motion path"), "{}", err);
+    }
+
+    #[test]
+    fn test_motion_paths_reject_easing() {
+        for easing in ["easeInOut", "hold"] {
+            let src = format!(
+                r#"
+                project(100, 100, 30) {{
+                    scene("main", 1s) {{
+                        layer("a") {{
+                            animation(position, path: "M0 0 L100 100", duration: 1s, easing: {})
+                        }}
+                    }}
+                }}
+            "#,
+                easing
+            );
+            let tokens = Lexer::new(&src).tokenize().unwrap();
+            let ast = Parser::new(tokens, "test.vidra").parse().unwrap();
+
+            let diagnostics = crate::checker::TypeChecker::new("test.vidra")
+                .check(&ast)
+                .unwrap_err();
+            assert!(diagnostics
+                .iter()
+                .any(|d| d.message.contains("takes no easing")));
+
+            let err = Compiler::compile(&ast).unwrap_err().to_string();
+            assert!(err.contains("takes no easing"), "{}", err);
+        }
+    }
 }
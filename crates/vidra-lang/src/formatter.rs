@@ -39,7 +39,10 @@ impl Formatter {
         ));
         self.indent_level += 1;
 
-        let mut first = true;
+        for setting in &proj.settings {
+            self.format_property(setting);
+        }
+        let mut first = proj.settings.is_empty();
 
         for comp in &proj.components {
             if !first {
@@ -128,19 +131,19 @@ impl Formatter {
             LayerBlockItem::Transition {
                 transition_type,
                 duration,
-                easing,
+                args,
                 span: _,
             } => {
-                let mut ease_str = "".to_string();
-                if let Some(e) = easing {
-                    ease_str = format!(", ease: \"{}\"", e);
-                }
+                let args_str: String = args
+                    .iter()
+                    .map(|arg| format!(", {}: {}", arg.name, self.format_value(&arg.value)))
+                    .collect();
                 self.indent();
                 self.push_line(&format!(
                     "transition(\"{}\", {}{})",
                     transition_type,
                     self.format_value(duration),
-                    ease_str
+                    args_str
                 ));
             }
             LayerBlockItem::AnimationStagger {
@@ -212,6 +215,9 @@ impl Formatter {
             LayerContentNode::Spritesheet { path, args } => {
                 self.format_content_func("spritesheet", path, args);
             }
+            LayerContentNode::Lottie { path, args } => {
+                self.format_content_func("lottie", path, args);
+            }
             LayerContentNode::Video { path, args, .. } => {
                 self.format_content_func("video", path, args);
             }
@@ -273,6 +279,9 @@ impl Formatter {
             LayerContentNode::Web { source, args } => {
                 self.format_content_func("web", source, args);
             }
+            LayerContentNode::Plugin { name, args } => {
+                self.format_content_func("plugin", name, args);
+            }
             LayerContentNode::Empty => {}
         }
     }
@@ -384,6 +393,14 @@ impl Formatter {
                     .join(", ");
                 format!("[{}]", items_str)
             }
+            ValueNode::Call { name, args } => {
+                let args_str = args
+                    .iter()
+                    .map(|arg| self.format_value(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}({})", name, args_str)
+            }
         }
     }
 }
@@ -30,6 +30,7 @@ pub enum TokenKind {
     Text,
     Image,
     Spritesheet,
+    Lottie,
     Video,
     Audio,
     Waveform,
@@ -95,6 +96,7 @@ impl fmt::Display for TokenKind {
             TokenKind::Text => write!(f, "text"),
             TokenKind::Image => write!(f, "image"),
             TokenKind::Spritesheet => write!(f, "spritesheet"),
+            TokenKind::Lottie => write!(f, "lottie"),
             TokenKind::Video => write!(f, "video"),
             TokenKind::Audio => write!(f, "audio"),
             TokenKind::Waveform => write!(f, "waveform"),
@@ -413,6 +415,7 @@ impl Lexer {
                     "text" => TokenKind::Text,
                     "image" => TokenKind::Image,
                     "spritesheet" => TokenKind::Spritesheet,
+                    "lottie" => TokenKind::Lottie,
                     "video" => TokenKind::Video,
                     "audio" => TokenKind::Audio,
                     "waveform" => TokenKind::Waveform,
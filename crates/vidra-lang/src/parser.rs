@@ -110,6 +110,7 @@ impl Parser {
         let mut assets = Vec::new();
         let mut layout_rules = Vec::new();
         let mut variables = Vec::new();
+        let mut settings = Vec::new();
         while self.peek() != &TokenKind::RightBrace && self.peek() != &TokenKind::Eof {
             self.skip_newlines();
             if self.peek() == &TokenKind::RightBrace {
@@ -123,6 +124,8 @@ impl Parser {
                 assets.push(self.parse_asset()?);
             } else if self.peek() == &TokenKind::Layout {
                 layout_rules.push(self.parse_layout_rules()?);
+            } else if self.peek() == &TokenKind::Identifier("motionBlur".into()) {
+                settings.push(self.parse_property()?);
             } else if self.peek() == &TokenKind::At {
                 // Peek ahead to see if it's `@var`
                 let next_token = &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)];
@@ -153,6 +156,7 @@ impl Parser {
             variables,
             scenes,
             components,
+            settings,
             span,
         })
     }
@@ -500,18 +504,11 @@ impl Parser {
         let duration = self.parse_value()?;
         self.skip_newlines();
 
-        let mut easing = None;
+        let mut args = Vec::new();
         if self.peek() == &TokenKind::Comma {
             self.advance();
             self.skip_newlines();
-            let args = self.parse_named_args_list()?;
-            for arg in args {
-                if arg.name == "ease" {
-                    if let ValueNode::String(s) = arg.value {
-                        easing = Some(s);
-                    }
-                }
-            }
+            args = self.parse_named_args_list()?;
         }
 
         self.expect(&TokenKind::RightParen)?;
@@ -519,11 +516,20 @@ impl Parser {
         Ok(LayerBlockItem::Transition {
             transition_type,
             duration,
-            easing,
+            args,
             span,
         })
     }
 
+    /// Whether the next tokens are `animate.stagger`, as opposed to `animate.group` or
+    /// `animate.sequence`.
+    fn at_animate_stagger(&self) -> bool {
+        let kind = |offset: usize| self.tokens.get(self.pos + offset).map(|t| &t.kind);
+        matches!(kind(0), Some(TokenKind::Identifier(name)) if name == "animate")
+            && kind(1) == Some(&TokenKind::Dot)
+            && matches!(kind(2), Some(TokenKind::Identifier(name)) if name == "stagger")
+    }
+
     fn parse_animate_stagger_item(&mut self) -> Result<LayerBlockItem, VidraError> {
         let span = self.current_span();
         self.advance(); // consume `animate`
@@ -640,6 +646,7 @@ impl Parser {
             TokenKind::Text
             | TokenKind::Image
             | TokenKind::Spritesheet
+            | TokenKind::Lottie
             | TokenKind::Video
             | TokenKind::Audio
             | TokenKind::Solid
@@ -673,8 +680,11 @@ impl Parser {
             if self.peek() == &TokenKind::RightBrace {
                 break;
             }
-            // Check if it's a nested layer
-            if self.peek() == &TokenKind::Layer || self.peek() == &TokenKind::If {
+            // Check if it's a nested layer or a stagger
+            if self.peek() == &TokenKind::Layer
+                || self.peek() == &TokenKind::If
+                || self.at_animate_stagger()
+            {
                 children.push(self.parse_layer_block_item()?);
             } else {
                 properties.push(self.parse_property()?);
@@ -720,6 +730,14 @@ impl Parser {
                 self.expect(&TokenKind::RightParen)?;
                 Ok(LayerContentNode::Spritesheet { path, args })
             }
+            TokenKind::Lottie => {
+                self.advance();
+                self.expect(&TokenKind::LeftParen)?;
+                let path = self.parse_value()?;
+                let args = self.parse_trailing_named_args()?;
+                self.expect(&TokenKind::RightParen)?;
+                Ok(LayerContentNode::Lottie { path, args })
+            }
             TokenKind::Video => {
                 self.advance();
                 self.expect(&TokenKind::LeftParen)?;
@@ -817,6 +835,14 @@ impl Parser {
                 self.expect(&TokenKind::RightParen)?;
                 Ok(LayerContentNode::Web { source, args })
             }
+            TokenKind::Identifier(name) if name == "plugin" => {
+                self.advance();
+                self.expect(&TokenKind::LeftParen)?;
+                let name = self.parse_value()?;
+                let args = self.parse_trailing_named_args()?;
+                self.expect(&TokenKind::RightParen)?;
+                Ok(LayerContentNode::Plugin { name, args })
+            }
             TokenKind::Identifier(name) => {
                 // E.g., CustomComponent(prop: "value")
                 self.advance();
@@ -1131,6 +1157,10 @@ impl Parser {
                 self.advance();
                 Ok("video".to_string())
             }
+            TokenKind::Audio => {
+                self.advance();
+                Ok("audio".to_string())
+            }
             TokenKind::Scene => {
                 self.advance();
                 Ok("scene".to_string())
@@ -1193,13 +1223,47 @@ impl Parser {
                 self.advance();
                 Ok(ValueNode::Duration(d))
             }
+            TokenKind::Minus => {
+                self.advance();
+                match self.parse_value()? {
+                    ValueNode::Number(n) => Ok(ValueNode::Number(-n)),
+                    ValueNode::Duration(d) => Ok(ValueNode::Duration(-d)),
+                    _ => {
+                        let span = self.current_span();
+                        Err(VidraError::parse(
+                            "expected a number after '-'",
+                            &self.file,
+                            span.line,
+                            span.column,
+                        ))
+                    }
+                }
+            }
             TokenKind::ColorLiteral(c) => {
                 self.advance();
                 Ok(ValueNode::Color(c))
             }
             TokenKind::Identifier(s) => {
                 self.advance();
-                Ok(ValueNode::Identifier(s))
+                if self.peek() != &TokenKind::LeftParen {
+                    return Ok(ValueNode::Identifier(s));
+                }
+                self.advance();
+                let mut args = Vec::new();
+                while self.peek() != &TokenKind::RightParen && self.peek() != &TokenKind::Eof {
+                    self.skip_newlines();
+                    if self.peek() == &TokenKind::RightParen {
+                        break;
+                    }
+                    args.push(self.parse_value()?);
+                    self.skip_newlines();
+                    if self.peek() == &TokenKind::Comma {
+                        self.advance();
+                    }
+                    self.skip_newlines();
+                }
+                self.expect(&TokenKind::RightParen)?;
+                Ok(ValueNode::Call { name: s, args })
             }
             TokenKind::At => {
                 self.advance(); // consume '@'
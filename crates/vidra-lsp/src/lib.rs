@@ -107,6 +107,12 @@ impl LanguageServer for Backend {
                 ),
                 ..Default::default()
             },
+            CompletionItem {
+                label: "lottie".to_string(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some("lottie(path)".to_string()),
+                ..Default::default()
+            },
             CompletionItem {
                 label: "component".to_string(),
                 kind: Some(CompletionItemKind::KEYWORD),
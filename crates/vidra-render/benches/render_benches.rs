@@ -13,12 +13,12 @@ fn create_native_project() -> Project {
     let mut scene = Scene::new(SceneId::new("bench_scene_native"), Duration::from_seconds(4.0)); // 120 frames
 
     // Native layer 1: Solid background
-    let bg = Layer::new(LayerId::new("bg"), LayerContent::Solid { color: Color::BLACK });
+    let bg = Layer::new(LayerId::new("bg"), LayerContent::Solid { color: Color::BLACK.into() });
     
     // Native layer 2: Shape
     let shape = Layer::new(LayerId::new("shape"), LayerContent::Shape {
         shape: ShapeType::Rect { corner_radius: 10.0, width: 400.0, height: 400.0 },
-        fill: Some(Color::RED),
+        fill: Some(Color::RED.into()),
         stroke: None,
         stroke_width: 0.0,
     }).with_position(960.0, 540.0);
@@ -29,6 +29,8 @@ fn create_native_project() -> Project {
         font_family: "Inter".to_string(), // Requires actual font rendering
         font_size: 100.0,
         color: Color::WHITE,
+        style: Default::default(),
+        animators: Vec::new(),
     }).with_position(960.0, 200.0);
 
     scene.add_layer(bg);
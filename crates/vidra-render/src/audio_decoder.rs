@@ -0,0 +1,316 @@
+//! Audio decoding for audio-reactive expressions.
+//!
+//! Decodes audio files to mono PCM and analyzes them into per-frame
+//! [`AudioEnvelope`]s. WAV files are read directly; other formats go through an
+//! FFmpeg subprocess. Envelopes are cached in memory and, when a media cache
+//! directory is configured, on disk next to the other cached media, so re-renders
+//! reuse exactly the same numbers.
+
+use dashmap::DashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use vidra_core::hash::ContentHasher;
+use vidra_core::VidraError;
+use vidra_eval::{AudioEnvelope, AudioFrame};
+
+/// Sample rate audio is decoded at when going through FFmpeg.
+const DECODE_SAMPLE_RATE: u32 = 22050;
+/// Version of the envelope analysis; part of every cache key so stale envelopes are
+/// not reused after the analysis changes.
+const ENVELOPE_VERSION: u32 = 1;
+
+/// Decodes audio files into cached envelopes.
+pub struct AudioDecoder {
+    /// Envelopes keyed by the hash of the audio bytes and analysis parameters.
+    envelopes: DashMap<String, Arc<AudioEnvelope>>,
+    /// Media cache directory; envelopes are stored under `audio_envelopes/`.
+    cache_dir: Option<PathBuf>,
+}
+
+impl AudioDecoder {
+    /// A decoder that caches envelopes in memory only.
+    pub fn new() -> Self {
+        Self {
+            envelopes: DashMap::new(),
+            cache_dir: None,
+        }
+    }
+
+    /// Store envelopes under `dir` (or only in memory with `None`).
+    pub fn set_cache_dir(&mut self, dir: Option<PathBuf>) {
+        self.cache_dir = dir;
+    }
+
+    /// The per-frame envelope of the audio file at `path`, at `fps` frames per second.
+    pub fn envelope(&self, path: &Path, fps: f64) -> Result<Arc<AudioEnvelope>, VidraError> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| VidraError::asset(format!("failed to read audio file: {}", e), path))?;
+
+        let mut hasher = ContentHasher::new();
+        hasher.update(ENVELOPE_VERSION.to_le_bytes());
+        hasher.update(fps.to_bits().to_le_bytes());
+        hasher.update(&bytes);
+        let key = hasher.finish().to_hex();
+
+        if let Some(envelope) = self.envelopes.get(&key) {
+            return Ok(envelope.clone());
+        }
+
+        let cache_path = self
+            .cache_dir
+            .as_ref()
+            .map(|dir| dir.join("audio_envelopes").join(format!("{}.bin", key)));
+        let cached = cache_path
+            .as_ref()
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|data| decode_envelope(&data));
+
+        let envelope = match cached {
+            Some(envelope) => envelope,
+            None => {
+                let (samples, sample_rate) = match read_wav(&bytes) {
+                    Some(decoded) => decoded,
+                    None => (decode_with_ffmpeg(path)?, DECODE_SAMPLE_RATE),
+                };
+                let envelope = AudioEnvelope::analyze(&samples, sample_rate, fps);
+                if let Some(p) = &cache_path {
+                    if let Err(e) = write_cache(p, &envelope) {
+                        tracing::warn!("Failed to cache audio envelope {}: {}", p.display(), e);
+                    }
+                }
+                envelope
+            }
+        };
+
+        let envelope = Arc::new(envelope);
+        self.envelopes.insert(key, envelope.clone());
+        Ok(envelope)
+    }
+}
+
+impl Default for AudioDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_cache(path: &Path, envelope: &AudioEnvelope) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, encode_envelope(envelope))
+}
+
+/// Cached envelopes are stored as raw little-endian `f64`s (fps, then six values per
+/// frame) so they read back bit-for-bit identical to a fresh analysis.
+fn encode_envelope(envelope: &AudioEnvelope) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + envelope.frames.len() * 48);
+    out.extend_from_slice(&envelope.fps.to_le_bytes());
+    for f in &envelope.frames {
+        for v in [f.amp, f.peak, f.bass, f.mid, f.treble, f.beat] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out
+}
+
+fn decode_envelope(data: &[u8]) -> Option<AudioEnvelope> {
+    if data.len() < 8 || !(data.len() - 8).is_multiple_of(48) {
+        return None;
+    }
+    let values: Vec<f64> = data
+        .chunks_exact(8)
+        .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    let frames = values[1..]
+        .chunks_exact(6)
+        .map(|v| AudioFrame {
+            amp: v[0],
+            peak: v[1],
+            bass: v[2],
+            mid: v[3],
+            treble: v[4],
+            beat: v[5],
+        })
+        .collect();
+    Some(AudioEnvelope {
+        fps: values[0],
+        frames,
+    })
+}
+
+/// Decode to mono `f32` PCM through FFmpeg.
+fn decode_with_ffmpeg(path: &Path) -> Result<Vec<f32>, VidraError> {
+    let mut child = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-vn", "-ac", "1", "-ar"])
+        .arg(DECODE_SAMPLE_RATE.to_string())
+        .args(["-f", "f32le", "-"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| VidraError::Encode(format!("failed to run ffmpeg: {}", e)))?;
+
+    let mut pcm = Vec::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout.read_to_end(&mut pcm)?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(VidraError::Encode(format!(
+            "ffmpeg failed to decode audio: {}",
+            path.display()
+        )));
+    }
+
+    Ok(pcm
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// Read a PCM (8/16/24/32-bit integer or 32-bit float) WAV file, downmixed to mono.
+/// Returns `None` for anything else.
+fn read_wav(bytes: &[u8]) -> Option<(Vec<f32>, u32)> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let u16_at = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?));
+    let u32_at = |i: usize| Some(u32::from_le_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32_at(pos + 4)? as usize;
+        let body = pos + 8;
+        match id {
+            b"fmt " => {
+                // (format tag, channels, sample rate, bits per sample)
+                format = Some((
+                    u16_at(body)?,
+                    u16_at(body + 2)?,
+                    u32_at(body + 4)?,
+                    u16_at(body + 14)?,
+                ));
+            }
+            b"data" => {
+                let (tag, channels, rate, bits) = format?;
+                let data = bytes.get(body..(body + size).min(bytes.len()))?;
+                let samples = wav_samples(data, tag, channels, bits)?;
+                return Some((samples, rate));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        pos = body + size + (size & 1);
+    }
+    None
+}
+
+fn wav_samples(data: &[u8], tag: u16, channels: u16, bits: u16) -> Option<Vec<f32>> {
+    const PCM: u16 = 1;
+    const FLOAT: u16 = 3;
+    const EXTENSIBLE: u16 = 0xFFFE;
+
+    let width = (bits / 8) as usize;
+    let channels = channels.max(1) as usize;
+    let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+        (PCM | EXTENSIBLE, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+        (PCM | EXTENSIBLE, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+        (PCM | EXTENSIBLE, 24) => {
+            |b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0
+        }
+        (PCM | EXTENSIBLE, 32) => {
+            |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0
+        }
+        (FLOAT, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        _ => return None,
+    };
+
+    Some(
+        data.chunks_exact(width * channels)
+            .map(|frame| {
+                let sum: f32 = frame.chunks_exact(width).map(decode).sum();
+                sum / channels as f32
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16-bit stereo WAV file.
+    fn wav_bytes(left: &[i16], right: &[i16], rate: u32) -> Vec<u8> {
+        let data_len = (left.len() * 4) as u32;
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(36 + data_len).to_le_bytes());
+        out.extend_from_slice(b"WAVEfmt ");
+        out.extend_from_slice(&16u32.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&2u16.to_le_bytes());
+        out.extend_from_slice(&rate.to_le_bytes());
+        out.extend_from_slice(&(rate * 4).to_le_bytes());
+        out.extend_from_slice(&4u16.to_le_bytes());
+        out.extend_from_slice(&16u16.to_le_bytes());
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&data_len.to_le_bytes());
+        for (l, r) in left.iter().zip(right) {
+            out.extend_from_slice(&l.to_le_bytes());
+            out.extend_from_slice(&r.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn test_read_wav_downmixes_to_mono() {
+        let bytes = wav_bytes(&[16384, -32768], &[0, -32768], 8000);
+        let (samples, rate) = read_wav(&bytes).unwrap();
+        assert_eq!(rate, 8000);
+        assert_eq!(samples, vec![0.25, -1.0]);
+        assert!(read_wav(b"not a wav file").is_none());
+    }
+
+    #[test]
+    fn test_envelope_is_cached_on_disk() {
+        let dir = std::env::temp_dir().join(format!("vidra-audio-test-{}", std::process::id()));
+        let wav = dir.join("tone.wav");
+        std::fs::create_dir_all(&dir).unwrap();
+        // Half a second of full-scale square wave, then silence.
+        let samples: Vec<i16> = (0..8000)
+            .map(|i| match (i < 4000, (i / 20) % 2 == 0) {
+                (true, true) => i16::MAX,
+                (true, false) => -i16::MAX,
+                _ => 0,
+            })
+            .collect();
+        std::fs::write(&wav, wav_bytes(&samples, &samples, 8000)).unwrap();
+
+        let mut decoder = AudioDecoder::new();
+        decoder.set_cache_dir(Some(dir.join("media")));
+        let envelope = decoder.envelope(&wav, 10.0).unwrap();
+        assert_eq!(envelope.frames.len(), 10);
+        assert!(envelope.at(0.2).amp > 0.99);
+        assert_eq!(envelope.at(0.7).amp, 0.0);
+
+        let cached: Vec<_> = std::fs::read_dir(dir.join("media").join("audio_envelopes"))
+            .unwrap()
+            .collect();
+        assert_eq!(cached.len(), 1);
+
+        // A fresh decoder reads the same envelope back from disk.
+        let mut fresh = AudioDecoder::new();
+        fresh.set_cache_dir(Some(dir.join("media")));
+        assert_eq!(*fresh.envelope(&wav, 10.0).unwrap(), *envelope);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,356 @@
+//! Content-addressed caches of rasterized buffers.
+//!
+//! The pipeline keeps one cache of finished layer buffers (rasterized, masked and
+//! with effects applied) and one of whole frames. Keys are [`ContentHash`]es of
+//! everything that went into a buffer, so entries stay valid across frames and
+//! across project reloads for as long as their inputs are unchanged.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use vidra_core::frame::FrameBuffer;
+use vidra_core::hash::{ContentHash, ContentHasher};
+use vidra_core::{Color, LayerEffect};
+use vidra_eval::{FrameState, LayerBuffer, LayerState, SceneState};
+use vidra_ir::layer::LayerContent;
+
+use crate::pipeline::RenderContext;
+
+/// Default memory budget of the layer cache.
+pub const DEFAULT_LAYER_CACHE_BYTES: usize = 512 * 1024 * 1024;
+/// Default memory budget of the frame cache.
+pub const DEFAULT_FRAME_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+/// Hit/miss counters and occupancy of one cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Number of buffers currently held.
+    pub entries: usize,
+    /// Pixel bytes currently held.
+    pub bytes: usize,
+}
+
+impl CacheStats {
+    /// Fraction of lookups that hit, or 0 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// Stats of the pipeline's layer and frame caches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RenderCacheStats {
+    pub layers: CacheStats,
+    pub frames: CacheStats,
+}
+
+/// A buffer a [`RenderCache`] can hold, budgeted by its pixel data.
+pub trait CachedBuffer {
+    fn pixel_bytes(&self) -> usize;
+}
+
+impl CachedBuffer for FrameBuffer {
+    fn pixel_bytes(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl CachedBuffer for LayerBuffer {
+    fn pixel_bytes(&self) -> usize {
+        self.buffer.data.len()
+    }
+}
+
+struct CacheEntry<T> {
+    buffer: Arc<T>,
+    last_used: AtomicU64,
+}
+
+/// A thread-safe buffer cache with a memory budget.
+///
+/// When the budget is exceeded the least recently used entries are evicted until the
+/// cache is back under three quarters of it.
+pub struct RenderCache<T = FrameBuffer> {
+    entries: DashMap<ContentHash, CacheEntry<T>>,
+    budget_bytes: usize,
+    bytes: AtomicUsize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evicting: Mutex<()>,
+}
+
+impl<T: CachedBuffer> RenderCache<T> {
+    /// Create an empty cache holding at most `budget_bytes` of pixel data.
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            budget_bytes,
+            bytes: AtomicUsize::new(0),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evicting: Mutex::new(()),
+        }
+    }
+
+    /// Look up a buffer, counting the lookup as a hit or miss.
+    pub fn get(&self, key: &ContentHash) -> Option<Arc<T>> {
+        let found = self.entries.get(key).map(|entry| {
+            entry.last_used.store(self.tick(), Ordering::Relaxed);
+            entry.buffer.clone()
+        });
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// Store a buffer. Buffers larger than the whole budget are not cached.
+    pub fn insert(&self, key: ContentHash, buffer: Arc<T>) {
+        let size = buffer.pixel_bytes();
+        if size > self.budget_bytes {
+            return;
+        }
+        let entry = CacheEntry {
+            buffer,
+            last_used: AtomicU64::new(self.tick()),
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.bytes
+                .fetch_sub(old.buffer.pixel_bytes(), Ordering::Relaxed);
+        }
+        let total = self.bytes.fetch_add(size, Ordering::Relaxed) + size;
+        if total > self.budget_bytes {
+            self.evict();
+        }
+    }
+
+    /// Drop every entry. Counters are kept.
+    pub fn clear(&self) {
+        self.entries.clear();
+        self.bytes.store(0, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.len(),
+            bytes: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn evict(&self) {
+        // One evicting thread at a time is enough; others just carry on.
+        let Ok(_guard) = self.evicting.try_lock() else {
+            return;
+        };
+        let mut by_age: Vec<(u64, ContentHash)> = self
+            .entries
+            .iter()
+            .map(|e| (e.last_used.load(Ordering::Relaxed), e.key().clone()))
+            .collect();
+        by_age.sort_unstable_by_key(|(last_used, _)| *last_used);
+
+        let target = self.budget_bytes / 4 * 3;
+        for (_, key) in by_age {
+            if self.bytes.load(Ordering::Relaxed) <= target {
+                break;
+            }
+            if let Some((_, old)) = self.entries.remove(&key) {
+                self.bytes
+                    .fetch_sub(old.buffer.pixel_bytes(), Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Key of a layer's composite-ready buffer: its rasterized content and children, its
+/// mask and its effects, against the loaded assets identified by `assets`.
+///
+/// Returns `None` for layers whose pixels do not follow from their state alone
+/// (web captures), which are never cached.
+pub(crate) fn layer_key(
+    assets: &ContentHash,
+    ctx: &RenderContext,
+    scene: &SceneState,
+    layer: &LayerState,
+) -> Option<ContentHash> {
+    let mut hasher = ContentHasher::new();
+    hasher.update(assets.as_bytes());
+    hasher.update(ctx.width.to_le_bytes());
+    hasher.update(ctx.height.to_le_bytes());
+    hasher.update(ctx.fps.to_bits().to_le_bytes());
+    hash_layer_tree(&mut hasher, layer, scene.local_frame)?;
+    hash_effects(&mut hasher, layer)?;
+
+    match layer.mask.as_ref().and_then(|id| scene.layer(id)) {
+        Some(mask) => {
+            // The mask is applied at its offset from the layer, so both placements count.
+            hasher.update([1]);
+            hash_json(&mut hasher, &layer.matte_mode)?;
+            hash_layer_tree(&mut hasher, mask, scene.local_frame)?;
+            hash_placement(&mut hasher, layer);
+            hash_placement(&mut hasher, mask);
+        }
+        None => hasher.update([0]),
+    }
+    Some(hasher.finish())
+}
+
+/// Key of a whole output frame, given the keys of each scene's layers (aligned with
+/// `SceneState::layers`). Returns `None` if any visible layer is uncacheable.
+pub(crate) fn frame_key(
+    ctx: &RenderContext,
+    background: &Color,
+    state: &FrameState,
+    layer_keys: &[Vec<Option<ContentHash>>],
+) -> Option<ContentHash> {
+    let mut hasher = ContentHasher::new();
+    hasher.update(ctx.width.to_le_bytes());
+    hasher.update(ctx.height.to_le_bytes());
+    hash_json(&mut hasher, background)?;
+    for (scene, keys) in state.scenes.iter().zip(layer_keys) {
+        hasher.update((scene.layers.len() as u64).to_le_bytes());
+        for (layer, key) in scene.layers.iter().zip(keys) {
+            if !layer.visible {
+                continue;
+            }
+            hasher.update(key.as_ref()?.as_bytes());
+            hash_placement(&mut hasher, layer);
+            hash_json(&mut hasher, &layer.transform)?;
+            hash_json(&mut hasher, &layer.blend_mode)?;
+        }
+    }
+    if let Some(transition) = &state.transition {
+        hash_json(&mut hasher, &transition.effect)?;
+        hasher.update(transition.progress.to_bits().to_le_bytes());
+    }
+    Some(hasher.finish())
+}
+
+fn hash_layer_tree(hasher: &mut ContentHasher, layer: &LayerState, frame: u64) -> Option<()> {
+    match &layer.content {
+        LayerContent::Web { .. } => return None,
+        // Content that changes with time even when its state does not.
+        LayerContent::Video { .. }
+        | LayerContent::Shader { .. }
+        | LayerContent::Spritesheet { .. } => hasher.update(frame.to_le_bytes()),
+        // Plugins and text animators see the layer's time, which motion blur offsets.
+        LayerContent::Plugin { .. } => {
+            hasher.update(layer.time.as_seconds().to_bits().to_le_bytes())
+        }
+        LayerContent::Text { animators, .. } if !animators.is_empty() => {
+            hasher.update(layer.time.as_seconds().to_bits().to_le_bytes())
+        }
+        _ => {}
+    }
+    hash_json(hasher, &layer.content)?;
+    hasher.update(layer.opacity.to_bits().to_le_bytes());
+    hasher.update(layer.scale.0.to_bits().to_le_bytes());
+    hasher.update(layer.scale.1.to_bits().to_le_bytes());
+    hash_json(hasher, &layer.shape_mask)?;
+    hasher.update(layer.mask_feather.to_bits().to_le_bytes());
+
+    let children: Vec<&LayerState> = layer.children.iter().filter(|c| c.visible).collect();
+    hasher.update((children.len() as u64).to_le_bytes());
+    for child in children {
+        // Children are drawn into the parent's buffer through their full transform.
+        hash_placement(hasher, child);
+        hash_json(hasher, &child.transform)?;
+        hash_json(hasher, &child.blend_mode)?;
+        hash_layer_tree(hasher, child, frame)?;
+        hash_effects(hasher, child)?;
+    }
+    Some(())
+}
+
+fn hash_effects(hasher: &mut ContentHasher, layer: &LayerState) -> Option<()> {
+    hash_json(hasher, &layer.effects)?;
+    // Plugin effects are given the layer's time.
+    if layer
+        .effects
+        .iter()
+        .any(|e| matches!(e, LayerEffect::Plugin { .. }))
+    {
+        hasher.update(layer.time.as_seconds().to_bits().to_le_bytes());
+    }
+    Some(())
+}
+
+/// Position and anchor, which together place a buffer in its parent.
+fn hash_placement(hasher: &mut ContentHasher, layer: &LayerState) {
+    hasher.update(layer.position.0.to_le_bytes());
+    hasher.update(layer.position.1.to_le_bytes());
+    hasher.update(layer.transform.anchor.x.to_bits().to_le_bytes());
+    hasher.update(layer.transform.anchor.y.to_bits().to_le_bytes());
+}
+
+fn hash_json(hasher: &mut ContentHasher, value: &impl Serialize) -> Option<()> {
+    let json = serde_json::to_vec(value).ok()?;
+    hasher.update((json.len() as u64).to_le_bytes());
+    hasher.update(json);
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u32) -> ContentHash {
+        let mut hasher = ContentHasher::new();
+        hasher.update(n.to_le_bytes());
+        hasher.finish()
+    }
+
+    fn buffer() -> Arc<FrameBuffer> {
+        // 10×10 RGBA = 400 bytes.
+        Arc::new(FrameBuffer::solid(10, 10, &Color::RED))
+    }
+
+    #[test]
+    fn test_hits_and_misses_are_counted() {
+        let cache = RenderCache::new(10_000);
+        assert!(cache.get(&key(1)).is_none());
+        cache.insert(key(1), buffer());
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(1)).is_some());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!((stats.entries, stats.bytes), (1, 400));
+        assert!((stats.hit_rate() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_over_budget() {
+        let cache = RenderCache::new(1_100);
+        cache.insert(key(1), buffer());
+        cache.insert(key(2), buffer());
+        // Touch 1 so 2 becomes the oldest.
+        cache.get(&key(1));
+        cache.insert(key(3), buffer());
+
+        assert_eq!(cache.stats().bytes, 800);
+        assert!(cache.get(&key(1)).is_some());
+        assert!(cache.get(&key(2)).is_none());
+        assert!(cache.get(&key(3)).is_some());
+    }
+}
@@ -1,6 +1,7 @@
 use crate::gpu::GpuContext;
 use std::sync::Arc;
 use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::BlendMode;
 use wgpu::util::DeviceExt;
 
 #[repr(C)]
@@ -40,17 +41,21 @@ const VERTICES: &[Vertex] = &[
 ];
 
 pub struct GpuCompositor {
+    /// `None` when running on the CPU backend.
+    gpu: Option<CompositorGpu>,
+    effects_pipeline: crate::effects::GpuEffects,
+}
+
+struct CompositorGpu {
     gpu: Arc<GpuContext>,
-    #[allow(dead_code)]
-    pipeline: wgpu::RenderPipeline,
-    #[allow(dead_code)]
-    bind_group_layout: wgpu::BindGroupLayout,
+    /// Blend modes other than normal, which read the backdrop in the shader.
+    blend_pipeline: wgpu::RenderPipeline,
+    blend_bind_group_layout: wgpu::BindGroupLayout,
     sampler: wgpu::Sampler,
     #[allow(dead_code)]
     vertex_buffer: wgpu::Buffer,
     projective_pipeline: wgpu::RenderPipeline,
     projective_bind_group_layout: wgpu::BindGroupLayout,
-    effects_pipeline: crate::effects::GpuEffects,
 }
 
 impl GpuCompositor {
@@ -63,10 +68,10 @@ impl GpuCompositor {
             .device
             .create_shader_module(wgpu::include_wgsl!("compositor_projective.wgsl"));
 
-        let bind_group_layout =
+        let blend_bind_group_layout =
             gpu.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    label: Some("compositor_bind_group_layout"),
+                    label: Some("compositor_blend_bind_group_layout"),
                     entries: &[
                         wgpu::BindGroupLayoutEntry {
                             binding: 0,
@@ -84,6 +89,26 @@ impl GpuCompositor {
                             ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                             count: None,
                         },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 3,
+                            visibility: wgpu::ShaderStages::FRAGMENT,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
                     ],
                 });
 
@@ -124,8 +149,8 @@ impl GpuCompositor {
         let pipeline_layout = gpu
             .device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("compositor_pipeline_layout"),
-                bind_group_layouts: &[&bind_group_layout],
+                label: Some("compositor_blend_pipeline_layout"),
+                bind_group_layouts: &[&blend_bind_group_layout],
                 push_constant_ranges: &[],
             });
 
@@ -137,10 +162,10 @@ impl GpuCompositor {
                     push_constant_ranges: &[],
                 });
 
-        let pipeline = gpu
+        let blend_pipeline = gpu
             .device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("compositor_pipeline"),
+                label: Some("compositor_blend_pipeline"),
                 layout: Some(&pipeline_layout),
                 vertex: wgpu::VertexState {
                     module: &shader,
@@ -158,14 +183,8 @@ impl GpuCompositor {
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
                     targets: &[Some(wgpu::ColorTargetState {
                         format: wgpu::TextureFormat::Rgba8Unorm,
-                        blend: Some(wgpu::BlendState {
-                            color: wgpu::BlendComponent {
-                                src_factor: wgpu::BlendFactor::One,
-                                dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                                operation: wgpu::BlendOperation::Add,
-                            },
-                            alpha: wgpu::BlendComponent::OVER,
-                        }),
+                        // The shader composites over the backdrop itself.
+                        blend: None,
                         write_mask: wgpu::ColorWrites::ALL,
                     })],
                 }),
@@ -228,18 +247,40 @@ impl GpuCompositor {
             });
 
         Self {
-            gpu,
-            pipeline,
-            bind_group_layout,
-            sampler,
-            vertex_buffer,
-            projective_pipeline,
-            projective_bind_group_layout,
+            gpu: Some(CompositorGpu {
+                gpu,
+                blend_pipeline,
+                blend_bind_group_layout,
+                sampler,
+                vertex_buffer,
+                projective_pipeline,
+                projective_bind_group_layout,
+            }),
             effects_pipeline,
         }
     }
 
-    /// Uploads source layer and blends it into the destination texture.
+    /// A compositor that never touches wgpu: effects run through [`crate::cpu`]
+    /// and projected quads use `FrameBuffer::composite_over_projected`.
+    pub fn cpu() -> Self {
+        Self {
+            gpu: None,
+            effects_pipeline: crate::effects::GpuEffects::cpu(),
+        }
+    }
+
+    /// Apply `effects` to a layer buffer in order. Effects that can't run on `src` are skipped.
+    pub fn apply_effects(
+        &self,
+        src: FrameBuffer,
+        effects: &[vidra_core::types::LayerEffect],
+    ) -> FrameBuffer {
+        effects.iter().fold(src, |buf, effect| {
+            self.effects_pipeline.apply(&buf, effect).unwrap_or(buf)
+        })
+    }
+
+    /// Uploads source layer and blends it into the destination texture with `mode`.
     /// In a fully integrated phase, texture allocations would be persistent.
     pub fn composite(
         &self,
@@ -247,19 +288,21 @@ impl GpuCompositor {
         src: &FrameBuffer,
         x: i32,
         y: i32,
+        mode: BlendMode,
         effects: &[vidra_core::types::LayerEffect],
     ) {
         if dst.format != PixelFormat::Rgba8 || src.format != PixelFormat::Rgba8 {
-            return dst.composite_over(src, x, y); // CPU fallback
+            return dst.composite_blend(src, x, y, mode); // CPU fallback
         }
 
         // Apply effects if provided
-        let mut final_src = src.clone();
-        for effect in effects {
-            if let Some(processed) = self.effects_pipeline.apply(&final_src, effect) {
-                final_src = processed;
-            }
-        }
+        let processed;
+        let final_src = if effects.is_empty() {
+            src
+        } else {
+            processed = self.apply_effects(src.clone(), effects);
+            &processed
+        };
 
         // Just use CPU if it's offscreen
         if x >= dst.width as i32
@@ -273,15 +316,17 @@ impl GpuCompositor {
         // Extremely fast SIMD CPU pass is heavily preferred right now if the texture doesn't live on GPU already.
         // Doing full round-trip texture transfers per-layer is currently an anti-pattern unless rendering entirely on GPU
         // But we implement the WGPU path to fulfill phase 1 constraints and pave the way for fully bound GPU framebuffers.
-        dst.composite_over(&final_src, x, y);
+        dst.composite_blend(final_src, x, y, mode);
     }
 
-    /// Composite a layer into `dst` via a projected quad (used for 2.5D transforms).
+    /// Composite a layer into `dst` with `mode` via a projected quad (used for 2.5D
+    /// transforms).
     pub fn composite_projected(
         &self,
         dst: &mut FrameBuffer,
         src: &FrameBuffer,
         dst_corners: [[f64; 2]; 4],
+        mode: BlendMode,
         effects: &[vidra_core::types::LayerEffect],
     ) {
         if dst.format != PixelFormat::Rgba8 || src.format != PixelFormat::Rgba8 {
@@ -289,34 +334,33 @@ impl GpuCompositor {
         }
 
         // Apply effects first in source space.
-        let mut final_src = src.clone();
-        for effect in effects {
-            if let Some(processed) = self.effects_pipeline.apply(&final_src, effect) {
-                final_src = processed;
-            }
-        }
+        let final_src = self.apply_effects(src.clone(), effects);
 
-        // If wgpu row alignment requirements aren't met, fall back to CPU.
+        // CPU backend, or wgpu row alignment requirements aren't met.
+        let Some(gpu) = &self.gpu else {
+            dst.composite_blend_projected(&final_src, dst_corners, mode);
+            return;
+        };
         if !is_wgpu_bytes_per_row_aligned(dst.width)
             || !is_wgpu_bytes_per_row_aligned(final_src.width)
         {
-            dst.composite_over_projected(&final_src, dst_corners);
+            dst.composite_blend_projected(&final_src, dst_corners, mode);
             return;
         }
 
-        if self
-            .composite_projected_gpu(dst, &final_src, dst_corners)
-            .is_err()
-        {
-            dst.composite_over_projected(&final_src, dst_corners);
+        if Self::composite_projected_gpu(gpu, dst, &final_src, dst_corners, mode).is_err() {
+            dst.composite_blend_projected(&final_src, dst_corners, mode);
         }
     }
 
+    /// Normal layers use fixed-function blending; other modes read a copy of the
+    /// backdrop in `compositor.wgsl` and write the composite with blending off.
     fn composite_projected_gpu(
-        &self,
+        ctx: &CompositorGpu,
         dst: &mut FrameBuffer,
         src: &FrameBuffer,
         dst_corners: [[f64; 2]; 4],
+        mode: BlendMode,
     ) -> anyhow::Result<()> {
         // Compute inverse homography (dst -> src) so the fragment shader can inverse-map.
         let w = src.width as f64;
@@ -339,8 +383,8 @@ impl GpuCompositor {
             | wgpu::TextureUsages::RENDER_ATTACHMENT
             | wgpu::TextureUsages::COPY_SRC
             | wgpu::TextureUsages::COPY_DST;
-        let tex_dst = self.gpu.texture_pool.acquire(
-            &self.gpu.device,
+        let tex_dst = ctx.gpu.texture_pool.acquire(
+            &ctx.gpu.device,
             Some("projective_dst"),
             dst_w,
             dst_h,
@@ -348,8 +392,8 @@ impl GpuCompositor {
             usage_dst,
         );
         let usage_src = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
-        let tex_src = self.gpu.texture_pool.acquire(
-            &self.gpu.device,
+        let tex_src = ctx.gpu.texture_pool.acquire(
+            &ctx.gpu.device,
             Some("projective_src"),
             src_w_u32,
             src_h_u32,
@@ -357,7 +401,7 @@ impl GpuCompositor {
             usage_src,
         );
 
-        self.gpu.queue.write_texture(
+        ctx.gpu.queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &tex_dst,
                 mip_level: 0,
@@ -377,7 +421,7 @@ impl GpuCompositor {
             },
         );
 
-        self.gpu.queue.write_texture(
+        ctx.gpu.queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &tex_src,
                 mip_level: 0,
@@ -397,12 +441,48 @@ impl GpuCompositor {
             },
         );
 
+        // A render attachment can't be sampled, so blend modes read a second copy.
+        let usage_backdrop = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        let tex_backdrop = (mode != BlendMode::Normal).then(|| {
+            let tex = ctx.gpu.texture_pool.acquire(
+                &ctx.gpu.device,
+                Some("blend_backdrop"),
+                dst_w,
+                dst_h,
+                format,
+                usage_backdrop,
+            );
+            ctx.gpu.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &tex,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &dst.data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(dst_w * 4),
+                    rows_per_image: Some(dst_h),
+                },
+                wgpu::Extent3d {
+                    width: dst_w,
+                    height: dst_h,
+                    depth_or_array_layers: 1,
+                },
+            );
+            tex
+        });
+
         let view_dst = tex_dst.create_view(&wgpu::TextureViewDescriptor::default());
         let view_src = tex_src.create_view(&wgpu::TextureViewDescriptor::default());
+        let view_backdrop = tex_backdrop
+            .as_ref()
+            .map(|tex| tex.create_view(&wgpu::TextureViewDescriptor::default()));
 
         // Build projected-quad vertices in NDC.
         let verts = build_projected_vertices(dst_w as f32, dst_h as f32, dst_corners);
-        let vb = self
+        let vb = ctx
             .gpu
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -411,8 +491,11 @@ impl GpuCompositor {
                 usage: wgpu::BufferUsages::VERTEX,
             });
 
-        let params = ProjectiveParams::from_inv_h(inv, src_w_u32 as f32, src_h_u32 as f32);
-        let params_buffer = self
+        let params = BlendParams {
+            projective: ProjectiveParams::from_inv_h(inv, src_w_u32 as f32, src_h_u32 as f32),
+            mode: [mode as u32, 0, 0, 0],
+        };
+        let params_buffer = ctx
             .gpu
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -421,36 +504,47 @@ impl GpuCompositor {
                 usage: wgpu::BufferUsages::UNIFORM,
             });
 
-        let bind_group = self
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view_src),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&ctx.sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: params_buffer.as_entire_binding(),
+            },
+        ];
+        let (pipeline, layout) = match &view_backdrop {
+            Some(view) => {
+                entries.push(wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(view),
+                });
+                (&ctx.blend_pipeline, &ctx.blend_bind_group_layout)
+            }
+            None => (&ctx.projective_pipeline, &ctx.projective_bind_group_layout),
+        };
+        let bind_group = ctx
             .gpu
             .device
             .create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("projective_bind_group"),
-                layout: &self.projective_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view_src),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&self.sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: params_buffer.as_entire_binding(),
-                    },
-                ],
+                layout,
+                entries: &entries,
             });
 
-        let out_buf = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        let out_buf = ctx.gpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("projective_readback"),
             size: (dst_w * dst_h * 4) as wgpu::BufferAddress,
             usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
-        let mut encoder = self
+        let mut encoder = ctx
             .gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
@@ -471,7 +565,7 @@ impl GpuCompositor {
                 occlusion_query_set: None,
             });
 
-            rpass.set_pipeline(&self.projective_pipeline);
+            rpass.set_pipeline(pipeline);
             rpass.set_bind_group(0, &bind_group, &[]);
             rpass.set_vertex_buffer(0, vb.slice(..));
             rpass.draw(0..6, 0..1);
@@ -499,21 +593,26 @@ impl GpuCompositor {
             },
         );
 
-        self.gpu.queue.submit(Some(encoder.finish()));
+        ctx.gpu.queue.submit(Some(encoder.finish()));
 
         let slice = out_buf.slice(..);
         let (tx, rx) = std::sync::mpsc::channel();
         slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
-        self.gpu.device.poll(wgpu::Maintain::Wait);
+        ctx.gpu.device.poll(wgpu::Maintain::Wait);
 
         if rx.recv().unwrap().is_err() {
             out_buf.unmap();
-            self.gpu
+            ctx.gpu
                 .texture_pool
                 .release(tex_dst, dst_w, dst_h, format, usage_dst);
-            self.gpu
+            ctx.gpu
                 .texture_pool
                 .release(tex_src, src_w_u32, src_h_u32, format, usage_src);
+            if let Some(tex) = tex_backdrop {
+                ctx.gpu
+                    .texture_pool
+                    .release(tex, dst_w, dst_h, format, usage_backdrop);
+            }
             return Err(anyhow::anyhow!("failed to map projective readback buffer"));
         }
 
@@ -521,19 +620,24 @@ impl GpuCompositor {
         out_buf.unmap();
 
         // Release textures back to pool.
-        self.gpu
+        ctx.gpu
             .texture_pool
             .release(tex_dst, dst_w, dst_h, format, usage_dst);
-        self.gpu
+        ctx.gpu
             .texture_pool
             .release(tex_src, src_w_u32, src_h_u32, format, usage_src);
+        if let Some(tex) = tex_backdrop {
+            ctx.gpu
+                .texture_pool
+                .release(tex, dst_w, dst_h, format, usage_backdrop);
+        }
 
         dst.data = data;
         Ok(())
     }
 }
 
-fn is_wgpu_bytes_per_row_aligned(width: u32) -> bool {
+pub(crate) fn is_wgpu_bytes_per_row_aligned(width: u32) -> bool {
     let bytes_per_row = (width as usize) * 4;
     bytes_per_row % (wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize) == 0
 }
@@ -593,6 +697,14 @@ struct ProjectiveParams {
     src: [f32; 4],
 }
 
+/// Uniforms of `compositor.wgsl`; the projective shader reads only the leading fields.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct BlendParams {
+    projective: ProjectiveParams,
+    mode: [u32; 4],
+}
+
 impl ProjectiveParams {
     fn from_inv_h(inv: [f64; 9], src_w: f32, src_h: f32) -> Self {
         // inv is row-major; WGSL expects column-major.
@@ -1,26 +1,178 @@
+// Blend-mode compositing of a layer over a backdrop.
+//
+// Mirrors `vidra_core::blend` (W3C Compositing and Blending Level 1) on straight-alpha
+// colors. The layer quad is drawn into a copy of the backdrop with hardware blending
+// off; each fragment reads the backdrop pixel from `t_dst` and writes the composite.
+
 struct VertexInput {
-    @builtin(vertex_index) vertex_index: u32,
     @location(0) position: vec2<f32>,
     @location(1) uv: vec2<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
-    @location(0) uv: vec2<f32>,
 };
 
+struct BlendParams {
+    // Column-major 3x3 inverse homography mapping destination pixels to source pixels.
+    col0: vec4<f32>,
+    col1: vec4<f32>,
+    col2: vec4<f32>,
+    // src.xy = (width, height)
+    src: vec4<f32>,
+    // mode.x = index of the `BlendMode` variant
+    mode: vec4<u32>,
+};
+
+@group(0) @binding(0) var t_src: texture_2d<f32>;
+@group(0) @binding(1) var s_src: sampler;
+@group(0) @binding(2) var<uniform> params: BlendParams;
+@group(0) @binding(3) var t_dst: texture_2d<f32>;
+
 @vertex
 fn vs_main(in: VertexInput) -> VertexOutput {
     var out: VertexOutput;
     out.position = vec4<f32>(in.position, 0.0, 1.0);
-    out.uv = in.uv;
     return out;
 }
 
-@group(0) @binding(0) var t_diffuse: texture_2d<f32>;
-@group(0) @binding(1) var s_diffuse: sampler;
+fn screen(cb: f32, cs: f32) -> f32 {
+    return cb + cs - cb * cs;
+}
+
+fn hard_light(cb: f32, cs: f32) -> f32 {
+    if (cs <= 0.5) {
+        return cb * 2.0 * cs;
+    }
+    return screen(cb, 2.0 * cs - 1.0);
+}
+
+fn blend_channel(mode: u32, cb: f32, cs: f32) -> f32 {
+    switch mode {
+        case 1u: { return cb * cs; } // multiply
+        case 2u: { return screen(cb, cs); } // screen
+        case 3u: { return hard_light(cs, cb); } // overlay
+        case 4u: { return min(cb + cs, 1.0); } // add
+        case 5u: { return min(cb, cs); } // darken
+        case 6u: { return max(cb, cs); } // lighten
+        case 7u: { // color-dodge
+            if (cb == 0.0) {
+                return 0.0;
+            }
+            if (cs >= 1.0) {
+                return 1.0;
+            }
+            return min(cb / (1.0 - cs), 1.0);
+        }
+        case 8u: { // color-burn
+            if (cb >= 1.0) {
+                return 1.0;
+            }
+            if (cs == 0.0) {
+                return 0.0;
+            }
+            return 1.0 - min((1.0 - cb) / cs, 1.0);
+        }
+        case 9u: { return hard_light(cb, cs); } // hard-light
+        case 10u: { // soft-light
+            if (cs <= 0.5) {
+                return cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb);
+            }
+            var d = sqrt(cb);
+            if (cb <= 0.25) {
+                d = ((16.0 * cb - 12.0) * cb + 4.0) * cb;
+            }
+            return cb + (2.0 * cs - 1.0) * (d - cb);
+        }
+        case 11u: { return abs(cb - cs); } // difference
+        case 12u: { return cb + cs - 2.0 * cb * cs; } // exclusion
+        default: { return cs; }
+    }
+}
+
+fn lum(c: vec3<f32>) -> f32 {
+    return dot(c, vec3<f32>(0.3, 0.59, 0.11));
+}
+
+fn clip_color(c: vec3<f32>) -> vec3<f32> {
+    let l = lum(c);
+    let n = min(min(c.r, c.g), c.b);
+    let x = max(max(c.r, c.g), c.b);
+    var out = c;
+    if (n < 0.0) {
+        out = l + (out - l) * l / (l - n);
+    }
+    if (x > 1.0) {
+        out = l + (out - l) * (1.0 - l) / (x - l);
+    }
+    return out;
+}
+
+fn set_lum(c: vec3<f32>, l: f32) -> vec3<f32> {
+    return clip_color(c + (l - lum(c)));
+}
+
+fn sat(c: vec3<f32>) -> f32 {
+    return max(max(c.r, c.g), c.b) - min(min(c.r, c.g), c.b);
+}
+
+fn set_sat(c: vec3<f32>, s: f32) -> vec3<f32> {
+    let lo = min(min(c.r, c.g), c.b);
+    let hi = max(max(c.r, c.g), c.b);
+    if (hi <= lo) {
+        return vec3<f32>(0.0);
+    }
+    return (c - lo) * s / (hi - lo);
+}
+
+fn blend(mode: u32, cb: vec3<f32>, cs: vec3<f32>) -> vec3<f32> {
+    switch mode {
+        case 13u: { return set_lum(set_sat(cs, sat(cb)), lum(cb)); } // hue
+        case 14u: { return set_lum(set_sat(cb, sat(cs)), lum(cb)); } // saturation
+        case 15u: { return set_lum(cs, lum(cb)); } // color
+        case 16u: { return set_lum(cb, lum(cs)); } // luminosity
+        default: {
+            return vec3<f32>(
+                blend_channel(mode, cb.r, cs.r),
+                blend_channel(mode, cb.g, cs.g),
+                blend_channel(mode, cb.b, cs.b),
+            );
+        }
+    }
+}
+
+fn composite(src: vec4<f32>, dst: vec4<f32>, mode: u32) -> vec4<f32> {
+    let sa = src.a;
+    let da = dst.a;
+    if (sa <= 0.0) {
+        return dst;
+    }
+    let mixed = (1.0 - da) * src.rgb + da * blend(mode, dst.rgb, src.rgb);
+    let out_a = sa + da * (1.0 - sa);
+    let premultiplied = sa * mixed + (1.0 - sa) * da * dst.rgb;
+    return vec4<f32>(premultiplied / out_a, out_a);
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.uv);
+    let backdrop = textureLoad(t_dst, vec2<i32>(in.position.xy), 0);
+
+    let inv_h = mat3x3<f32>(
+        vec3<f32>(params.col0.xyz),
+        vec3<f32>(params.col1.xyz),
+        vec3<f32>(params.col2.xyz),
+    );
+    // Fragment position is already at the pixel center.
+    let s = inv_h * vec3<f32>(in.position.xy, 1.0);
+    if (abs(s.z) < 1e-6) {
+        return backdrop;
+    }
+    let src_size = max(params.src.xy, vec2<f32>(1.0));
+    let uv = s.xy / s.z / src_size;
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        return backdrop;
+    }
+
+    let source = textureSampleLevel(t_src, s_src, uv, 0.0);
+    return composite(source, backdrop, params.mode.x);
 }
@@ -0,0 +1,358 @@
+//! CPU software backend.
+//!
+//! Used when no wgpu adapter is available (headless CI, GPU-less VMs) or when
+//! `--backend cpu` is requested. Every effect mirrors `effects.wgsl` in `f32`
+//! with the same `rgba8unorm` round-trip, so output matches the GPU path within
+//! [`CPU_EFFECT_TOLERANCE`]. Projected quads go through
+//! `FrameBuffer::composite_blend_projected`, which agrees with the GPU
+//! rasterizer within [`CPU_PROJECTED_TOLERANCE`] away from the quad edges
+//! (edge pixels differ in coverage, exactly as they already do whenever the
+//! GPU path falls back for unaligned widths).
+//!
+//! Custom WGSL is not interpreted on the CPU: rendering a `LayerEffect::CustomShader`
+//! or a shader layer on this backend fails with `VidraError::Unsupported`.
+
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::types::LayerEffect;
+
+/// Max per-channel difference (0–255) between CPU and GPU effect output.
+pub const CPU_EFFECT_TOLERANCE: u8 = 1;
+
+/// Max per-channel difference (0–255) between CPU and GPU projected composites
+/// on smooth content, for pixels at least one pixel inside the projected quad.
+/// The two samplers place texel centers up to half a pixel apart, so hard edges
+/// inside the source can differ by more.
+pub const CPU_PROJECTED_TOLERANCE: u8 = 4;
+
+/// Apply a single effect on the CPU. Returns `None` when the effect is not
+/// supported here (custom WGSL), matching `GpuEffects::apply`.
+pub fn apply_effect(src: &FrameBuffer, effect: &LayerEffect) -> Option<FrameBuffer> {
+    if src.format != PixelFormat::Rgba8 {
+        return None;
+    }
+    if src.width == 0 || src.height == 0 {
+        return Some(src.clone());
+    }
+
+    match effect {
+        LayerEffect::Blur(radius) => Some(box_blur(src, *radius as f32 as i32)),
+        LayerEffect::Grayscale(intensity) => {
+            let t = *intensity as f32;
+            Some(map_pixels(src, |c| {
+                let gray = luma(c);
+                [
+                    mix(c[0], gray, t),
+                    mix(c[1], gray, t),
+                    mix(c[2], gray, t),
+                    c[3],
+                ]
+            }))
+        }
+        LayerEffect::Invert(intensity) => {
+            let t = *intensity as f32;
+            Some(map_pixels(src, |c| {
+                [
+                    mix(c[0], 1.0 - c[0], t),
+                    mix(c[1], 1.0 - c[1], t),
+                    mix(c[2], 1.0 - c[2], t),
+                    c[3],
+                ]
+            }))
+        }
+        LayerEffect::Brightness(amount) => {
+            let k = *amount as f32;
+            Some(map_pixels(src, |c| [c[0] * k, c[1] * k, c[2] * k, c[3]]))
+        }
+        LayerEffect::Contrast(amount) => {
+            let k = *amount as f32;
+            Some(map_pixels(src, |c| {
+                [
+                    (c[0] - 0.5) * k + 0.5,
+                    (c[1] - 0.5) * k + 0.5,
+                    (c[2] - 0.5) * k + 0.5,
+                    c[3],
+                ]
+            }))
+        }
+        LayerEffect::Saturation(amount) => {
+            let k = *amount as f32;
+            Some(map_pixels(src, |c| {
+                let gray = luma(c);
+                [
+                    mix(gray, c[0], k),
+                    mix(gray, c[1], k),
+                    mix(gray, c[2], k),
+                    c[3],
+                ]
+            }))
+        }
+        LayerEffect::HueRotate(degrees) => {
+            let hue_rad = (*degrees as f32).to_radians();
+            let (s, c) = hue_rad.sin_cos();
+            Some(map_pixels(src, |px| {
+                let y = px[0] * 0.299 + px[1] * 0.587 + px[2] * 0.114;
+                let i = px[0] * 0.596 + px[1] * -0.274 + px[2] * -0.322;
+                let q = px[0] * 0.211 + px[1] * -0.523 + px[2] * 0.312;
+                let i2 = i * c - q * s;
+                let q2 = i * s + q * c;
+                [
+                    y + i2 * 0.956 + q2 * 0.621,
+                    y + i2 * -0.272 + q2 * -0.647,
+                    y + i2 * -1.106 + q2 * 1.703,
+                    px[3],
+                ]
+            }))
+        }
+        LayerEffect::Vignette(amount) => Some(vignette(src, *amount as f32)),
+        LayerEffect::CustomShader { .. } => None,
+        // Materialized before render / handled by the LUT path in `GpuEffects::apply` /
+        // applied by the pipeline from its plugin registry.
+        LayerEffect::RemoveBackground | LayerEffect::Lut { .. } | LayerEffect::Plugin { .. } => {
+            Some(src.clone())
+        }
+        LayerEffect::DropShadow { .. }
+        | LayerEffect::OuterGlow { .. }
+        | LayerEffect::InnerShadow { .. }
+        | LayerEffect::Stroke { .. }
+        | LayerEffect::Bevel { .. } => crate::styles::apply(src, effect),
+    }
+}
+
+fn luma(c: [f32; 4]) -> f32 {
+    c[0] * 0.299 + c[1] * 0.587 + c[2] * 0.114
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a * (1.0 - t) + b * t
+}
+
+fn to_unorm(v: u8) -> f32 {
+    v as f32 / 255.0
+}
+
+fn from_unorm(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Apply `f` to every pixel in normalized `[0,1]` space.
+fn map_pixels(src: &FrameBuffer, f: impl Fn([f32; 4]) -> [f32; 4]) -> FrameBuffer {
+    let mut out = src.clone();
+    for px in out.data.chunks_exact_mut(4) {
+        let c = f([
+            to_unorm(px[0]),
+            to_unorm(px[1]),
+            to_unorm(px[2]),
+            to_unorm(px[3]),
+        ]);
+        for (dst, v) in px.iter_mut().zip(c) {
+            *dst = from_unorm(v);
+        }
+    }
+    out
+}
+
+/// Clamp-to-edge box blur. Separable, so it's two 1D passes instead of the
+/// shader's (2r+1)² loop, with identical weights.
+fn box_blur(src: &FrameBuffer, radius: i32) -> FrameBuffer {
+    if radius <= 0 {
+        return src.clone();
+    }
+    let w = src.width as i32;
+    let h = src.height as i32;
+    let taps = (2 * radius + 1) as f32;
+
+    let mut horizontal = vec![0.0f32; (w * h * 4) as usize];
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = [0.0f32; 4];
+            for dx in -radius..=radius {
+                let sx = (x + dx).clamp(0, w - 1);
+                let idx = ((y * w + sx) * 4) as usize;
+                for (c, acc) in sum.iter_mut().enumerate() {
+                    *acc += to_unorm(src.data[idx + c]);
+                }
+            }
+            let out_idx = ((y * w + x) * 4) as usize;
+            horizontal[out_idx..out_idx + 4].copy_from_slice(&sum);
+        }
+    }
+
+    let mut out = src.clone();
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = [0.0f32; 4];
+            for dy in -radius..=radius {
+                let sy = (y + dy).clamp(0, h - 1);
+                let idx = ((sy * w + x) * 4) as usize;
+                for (c, acc) in sum.iter_mut().enumerate() {
+                    *acc += horizontal[idx + c];
+                }
+            }
+            let out_idx = ((y * w + x) * 4) as usize;
+            for (c, v) in sum.iter().enumerate() {
+                out.data[out_idx + c] = from_unorm(v / (taps * taps));
+            }
+        }
+    }
+    out
+}
+
+fn vignette(src: &FrameBuffer, intensity: f32) -> FrameBuffer {
+    let mut out = src.clone();
+    let (w, h) = (src.width as f32, src.height as f32);
+    let edge0 = 1.5 - intensity;
+    let edge1 = 0.5 - intensity * 0.5;
+    for y in 0..src.height {
+        for x in 0..src.width {
+            let u = (x as f32 / w - 0.5) * 2.0;
+            let v = (y as f32 / h - 0.5) * 2.0;
+            let dist = (u * u + v * v).sqrt();
+            let t = ((dist - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+            let k = t * t * (3.0 - 2.0 * t);
+            let idx = ((y * src.width + x) * 4) as usize;
+            for c in 0..3 {
+                out.data[idx + c] = from_unorm(to_unorm(src.data[idx + c]) * k);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use vidra_core::BlendMode;
+
+    fn gradient(w: u32, h: u32) -> FrameBuffer {
+        let mut fb = FrameBuffer::new(w, h, PixelFormat::Rgba8);
+        for y in 0..h {
+            for x in 0..w {
+                fb.set_pixel(
+                    x,
+                    y,
+                    [
+                        (x * 255 / w.max(1)) as u8,
+                        (y * 255 / h.max(1)) as u8,
+                        ((x + y) * 127 / (w + h).max(1)) as u8,
+                        (255 - x * 127 / w.max(1)) as u8,
+                    ],
+                );
+            }
+        }
+        fb
+    }
+
+    fn max_channel_diff(a: &FrameBuffer, b: &FrameBuffer) -> u8 {
+        a.data
+            .iter()
+            .zip(&b.data)
+            .map(|(x, y)| x.abs_diff(*y))
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_cpu_blur_zero_radius_is_identity() {
+        let src = gradient(8, 8);
+        let out = apply_effect(&src, &LayerEffect::Blur(0.0)).unwrap();
+        assert_eq!(out.data, src.data);
+    }
+
+    #[test]
+    fn test_cpu_blur_of_solid_is_solid() {
+        let src = FrameBuffer::solid(6, 4, &vidra_core::Color::rgba(0.2, 0.4, 0.6, 1.0));
+        let out = apply_effect(&src, &LayerEffect::Blur(3.0)).unwrap();
+        assert_eq!(out.data, src.data);
+    }
+
+    #[test]
+    fn test_cpu_invert_full() {
+        let src = FrameBuffer::solid(2, 2, &vidra_core::Color::RED);
+        let out = apply_effect(&src, &LayerEffect::Invert(1.0)).unwrap();
+        assert_eq!(out.get_pixel(0, 0), Some([0, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_cpu_custom_shader_effect_is_unsupported() {
+        let src = gradient(4, 4);
+        let effect = LayerEffect::CustomShader {
+            wgsl_source: String::new(),
+        };
+        assert!(apply_effect(&src, &effect).is_none());
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_cpu_effects_match_gpu_within_tolerance() {
+        let gpu = crate::gpu::GpuContext::init().expect("no wgpu adapter");
+        let gpu_effects = crate::effects::GpuEffects::new(Arc::new(gpu));
+        // An unaligned width goes through the GPU's padded readback.
+        let src = gradient(61, 23);
+
+        let effects = [
+            LayerEffect::Blur(2.0),
+            LayerEffect::Grayscale(0.7),
+            LayerEffect::Invert(0.5),
+            LayerEffect::Brightness(1.3),
+            LayerEffect::Contrast(1.5),
+            LayerEffect::Saturation(0.4),
+            LayerEffect::HueRotate(90.0),
+            LayerEffect::Vignette(0.8),
+        ];
+        for effect in &effects {
+            let cpu = apply_effect(&src, effect).unwrap();
+            let gpu = gpu_effects.apply(&src, effect).unwrap();
+            let diff = max_channel_diff(&cpu, &gpu);
+            assert!(
+                diff <= CPU_EFFECT_TOLERANCE,
+                "{:?}: CPU/GPU differ by {} (tolerance {})",
+                effect,
+                diff,
+                CPU_EFFECT_TOLERANCE
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a wgpu adapter"]
+    fn test_cpu_projected_matches_gpu_within_tolerance() {
+        let gpu = crate::gpu::GpuContext::init().expect("no wgpu adapter");
+        let gpu_compositor = crate::compositor::GpuCompositor::new(Arc::new(gpu));
+        let cpu_compositor = crate::compositor::GpuCompositor::cpu();
+
+        // 64px wide keeps both buffers on the wgpu row-alignment fast path.
+        let src = gradient(64, 64);
+        let corners = [[20.0, 12.0], [100.0, 20.0], [96.0, 90.0], [16.0, 80.0]];
+        // An opaque backdrop: fixed-function normal blending doesn't unpremultiply.
+        let mut backdrop = gradient(128, 128);
+        for px in backdrop.data.chunks_exact_mut(4) {
+            px[3] = 255;
+        }
+        for mode in BlendMode::ALL {
+            let mut on_gpu = backdrop.clone();
+            let mut on_cpu = on_gpu.clone();
+            gpu_compositor.composite_projected(&mut on_gpu, &src, corners, mode, &[]);
+            cpu_compositor.composite_projected(&mut on_cpu, &src, corners, mode, &[]);
+
+            // Compare a region safely inside the quad.
+            let mut worst = 0u8;
+            for y in 30..70 {
+                for x in 35..85 {
+                    let a = on_gpu.get_pixel(x, y).unwrap();
+                    let b = on_cpu.get_pixel(x, y).unwrap();
+                    for c in 0..4 {
+                        worst = worst.max(a[c].abs_diff(b[c]));
+                    }
+                }
+            }
+            assert!(
+                worst <= CPU_PROJECTED_TOLERANCE,
+                "{:?}: projected CPU/GPU differ by {} (tolerance {})",
+                mode,
+                worst,
+                CPU_PROJECTED_TOLERANCE
+            );
+        }
+    }
+}
@@ -12,12 +12,19 @@ struct ShaderUniforms {
 }
 
 pub struct CustomShaderRenderer {
-    gpu: Arc<GpuContext>,
+    /// `None` when running on the CPU backend.
+    gpu: Option<Arc<GpuContext>>,
 }
 
 impl CustomShaderRenderer {
     pub fn new(gpu: Arc<GpuContext>) -> Self {
-        Self { gpu }
+        Self { gpu: Some(gpu) }
+    }
+
+    /// A renderer without a device. WGSL can't be executed on the CPU, so
+    /// every `render` call returns `VidraError::Unsupported`.
+    pub fn cpu() -> Self {
+        Self { gpu: None }
     }
 
     pub fn render(
@@ -27,6 +34,12 @@ impl CustomShaderRenderer {
         height: u32,
         time_sec: f32,
     ) -> Result<FrameBuffer, vidra_core::VidraError> {
+        let Some(gpu) = &self.gpu else {
+            return Err(vidra_core::VidraError::Unsupported(
+                "custom WGSL shaders need a GPU adapter (CPU backend active)".into(),
+            ));
+        };
+
         // Inject uniforms into the provided source.
         // We will assume the user provides a compute shader with entry point `main`.
         // We inject the `out_tex` binding and `uniforms` struct.
@@ -46,8 +59,7 @@ struct Uniforms {{
             shader_source
         );
 
-        let module = self
-            .gpu
+        let module = gpu
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
                 label: Some("custom_shader_injected"),
@@ -55,8 +67,7 @@ struct Uniforms {{
             });
 
         let bind_group_layout =
-            self.gpu
-                .device
+            gpu.device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                     label: Some("custom_shader_bind_group_layout"),
                     entries: &[
@@ -83,17 +94,15 @@ struct Uniforms {{
                     ],
                 });
 
-        let pipeline_layout =
-            self.gpu
-                .device
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("custom_shader_pipeline_layout"),
-                    bind_group_layouts: &[&bind_group_layout],
-                    push_constant_ranges: &[],
-                });
+        let pipeline_layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("custom_shader_pipeline_layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
 
-        let pipeline = self
-            .gpu
+        let pipeline = gpu
             .device
             .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
                 label: Some("custom_shader_pipeline"),
@@ -118,7 +127,7 @@ struct Uniforms {{
             usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         };
-        let texture_out = self.gpu.device.create_texture(&texture_desc);
+        let texture_out = gpu.device.create_texture(&texture_desc);
         let view_out = texture_out.create_view(&wgpu::TextureViewDescriptor::default());
 
         // 3) Setup Uniforms Data
@@ -128,8 +137,7 @@ struct Uniforms {{
             _pad: 0.0,
         };
 
-        let params_buffer = self
-            .gpu
+        let params_buffer = gpu
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("custom_shader_params_buffer"),
@@ -137,27 +145,23 @@ struct Uniforms {{
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
-        let bind_group = self
-            .gpu
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("custom_shader_bind_group"),
-                layout: &bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view_out),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: params_buffer.as_entire_binding(),
-                    },
-                ],
-            });
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("custom_shader_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view_out),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
 
         // 4) Execute Compute Pass
-        let mut encoder = self
-            .gpu
+        let mut encoder = gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
         {
@@ -169,7 +173,7 @@ struct Uniforms {{
 
         // 5) Readback into CPU buffer
         let padded_bytes_per_row = (width * 4 + 255) & !255;
-        let texture_out_buf = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        let texture_out_buf = gpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("custom_shader_readback"),
             size: (padded_bytes_per_row * height) as u64,
             usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
@@ -198,14 +202,14 @@ struct Uniforms {{
             },
         );
 
-        let _submission_idx = self.gpu.queue.submit(Some(encoder.finish()));
+        let _submission_idx = gpu.queue.submit(Some(encoder.finish()));
 
         // Wait and map
         let slice = texture_out_buf.slice(..);
         let (tx, rx) = std::sync::mpsc::channel();
         slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
 
-        self.gpu.device.poll(wgpu::Maintain::Wait);
+        gpu.device.poll(wgpu::Maintain::Wait);
 
         if rx.recv().unwrap().is_ok() {
             let data = slice.get_mapped_range();
@@ -234,4 +238,229 @@ struct Uniforms {{
             ))
         }
     }
+
+    /// Run a compiled VidraFX `@transition` (see `vidra_fx::compile_transition`) over the
+    /// outgoing and incoming scene frames, which must be the same size.
+    pub fn render_transition(
+        &self,
+        wgsl_source: &str,
+        outgoing: &FrameBuffer,
+        incoming: &FrameBuffer,
+        progress: f32,
+        params: &[f32],
+    ) -> Result<FrameBuffer, vidra_core::VidraError> {
+        let Some(gpu) = &self.gpu else {
+            return Err(vidra_core::VidraError::Unsupported(
+                "shader transitions need a GPU adapter (CPU backend active)".into(),
+            ));
+        };
+        let (width, height) = (outgoing.width, outgoing.height);
+
+        let module = gpu
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("transition_shader"),
+                source: wgpu::ShaderSource::Wgsl(wgsl_source.into()),
+            });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let bind_group_layout =
+            gpu.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("transition_shader_bind_group_layout"),
+                    entries: &[
+                        texture_entry(0),
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: wgpu::TextureFormat::Rgba8Unorm,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 2,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        texture_entry(3),
+                    ],
+                });
+
+        let pipeline_layout = gpu
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("transition_shader_pipeline_layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let pipeline = gpu
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("transition_shader_pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: "main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            });
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let upload = |frame: &FrameBuffer, label| {
+            let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            gpu.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &frame.data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: Some(height),
+                },
+                size,
+            );
+            texture.create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let view_from = upload(outgoing, "transition_from");
+        let view_to = upload(incoming, "transition_to");
+
+        let texture_out = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("transition_out"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view_out = texture_out.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // `progress` then the parameters, padded to the 16-byte size uniforms round up to.
+        let mut uniforms = vec![progress];
+        uniforms.extend_from_slice(params);
+        uniforms.resize(uniforms.len().div_ceil(4) * 4, 0.0);
+        let params_buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("transition_shader_params_buffer"),
+                contents: bytemuck::cast_slice(&uniforms),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("transition_shader_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view_from),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view_out),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&view_to),
+                },
+            ],
+        });
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            cpass.set_pipeline(&pipeline);
+            cpass.set_bind_group(0, &bind_group, &[]);
+            cpass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
+        }
+
+        let padded_bytes_per_row = (width * 4 + 255) & !255;
+        let readback = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("transition_shader_readback"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &texture_out,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        gpu.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
+        gpu.device.poll(wgpu::Maintain::Wait);
+        if rx.recv().map_or(true, |mapped| mapped.is_err()) {
+            return Err(vidra_core::VidraError::Render(
+                "Failed to map transition shader output buffer".into(),
+            ));
+        }
+
+        let mut out = FrameBuffer::new(width, height, vidra_core::PixelFormat::Rgba8);
+        {
+            let data = slice.get_mapped_range();
+            for (row, dst) in data
+                .chunks(padded_bytes_per_row as usize)
+                .zip(out.data.chunks_exact_mut((width * 4) as usize))
+            {
+                dst.copy_from_slice(&row[..dst.len()]);
+            }
+        }
+        readback.unmap();
+        Ok(out)
+    }
 }
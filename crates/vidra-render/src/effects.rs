@@ -7,10 +7,15 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 
 pub struct GpuEffects {
+    /// `None` when running on the CPU backend.
+    gpu: Option<EffectsGpu>,
+    lut_cache: Mutex<HashMap<String, Lut3D>>,
+}
+
+struct EffectsGpu {
     gpu: Arc<GpuContext>,
     pipeline: wgpu::ComputePipeline,
     bind_group_layout: wgpu::BindGroupLayout,
-    lut_cache: Mutex<HashMap<String, Lut3D>>,
 }
 
 #[derive(Clone)]
@@ -143,9 +148,19 @@ impl GpuEffects {
             });
 
         Self {
-            gpu,
-            pipeline,
-            bind_group_layout,
+            gpu: Some(EffectsGpu {
+                gpu,
+                pipeline,
+                bind_group_layout,
+            }),
+            lut_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Effects evaluated entirely on the CPU (see [`crate::cpu`]).
+    pub fn cpu() -> Self {
+        Self {
+            gpu: None,
             lut_cache: Mutex::new(HashMap::new()),
         }
     }
@@ -188,6 +203,19 @@ impl GpuEffects {
             return Some(out);
         }
 
+        if crate::styles::is_style(effect) {
+            return crate::styles::apply(src, effect);
+        }
+
+        let Some(EffectsGpu {
+            gpu,
+            pipeline,
+            bind_group_layout,
+        }) = &self.gpu
+        else {
+            return crate::cpu::apply_effect(src, effect);
+        };
+
         let width = src.width;
         let height = src.height;
 
@@ -198,8 +226,8 @@ impl GpuEffects {
 
         let format = wgpu::TextureFormat::Rgba8Unorm;
         let usage_in = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
-        let texture_in = self.gpu.texture_pool.acquire(
-            &self.gpu.device,
+        let texture_in = gpu.texture_pool.acquire(
+            &gpu.device,
             Some("effect_texture_in"),
             width,
             height,
@@ -207,7 +235,7 @@ impl GpuEffects {
             usage_in,
         );
 
-        self.gpu.queue.write_texture(
+        gpu.queue.write_texture(
             wgpu::ImageCopyTexture {
                 texture: &texture_in,
                 mip_level: 0,
@@ -227,16 +255,18 @@ impl GpuEffects {
             },
         );
 
-        let texture_out_buf = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        // The readback copy needs 256-byte aligned rows.
+        let padded_bytes_per_row = (width * 4 + 255) & !255;
+        let texture_out_buf = gpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("effect_texture_out_buf"),
-            size: (width * height * 4) as wgpu::BufferAddress,
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
             usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
         let usage_out = wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC;
-        let texture_out = self.gpu.texture_pool.acquire(
-            &self.gpu.device,
+        let texture_out = gpu.texture_pool.acquire(
+            &gpu.device,
             Some("effect_texture_out"),
             width,
             height,
@@ -271,8 +301,7 @@ impl GpuEffects {
             }
             LayerEffect::CustomShader { wgsl_source } => {
                 params.effect_type = 4; // Or ignored because we use custom pipeline
-                let module = self
-                    .gpu
+                let module = gpu
                     .device
                     .create_shader_module(wgpu::ShaderModuleDescriptor {
                         label: Some("custom_effect_shader"),
@@ -280,15 +309,14 @@ impl GpuEffects {
                     });
 
                 let pipeline_layout =
-                    self.gpu
-                        .device
+                    gpu.device
                         .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                             label: Some("custom_effects_pipeline_layout"),
-                            bind_group_layouts: &[&self.bind_group_layout],
+                            bind_group_layouts: &[bind_group_layout],
                             push_constant_ranges: &[],
                         });
 
-                custom_pipeline = Some(self.gpu.device.create_compute_pipeline(
+                custom_pipeline = Some(gpu.device.create_compute_pipeline(
                     &wgpu::ComputePipelineDescriptor {
                         label: Some("custom_effect_compute_pipeline"),
                         layout: Some(&pipeline_layout),
@@ -327,10 +355,21 @@ impl GpuEffects {
                 // Handled in the CPU early-return path above.
                 params.effect_type = 0;
             }
+            LayerEffect::Plugin { .. } => {
+                // Applied by the render pipeline, which owns the plugin registry.
+                params.effect_type = 0;
+            }
+            LayerEffect::DropShadow { .. }
+            | LayerEffect::OuterGlow { .. }
+            | LayerEffect::InnerShadow { .. }
+            | LayerEffect::Stroke { .. }
+            | LayerEffect::Bevel { .. } => {
+                // Handled by the layer styles path above.
+                params.effect_type = 0;
+            }
         }
 
-        let params_buffer = self
-            .gpu
+        let params_buffer = gpu
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("effect_params_buffer"),
@@ -338,36 +377,32 @@ impl GpuEffects {
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
 
-        let bind_group = self
-            .gpu
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("effect_bind_group"),
-                layout: &self.bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view_in),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&view_out),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: params_buffer.as_entire_binding(),
-                    },
-                ],
-            });
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("effect_bind_group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view_in),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view_out),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
 
-        let mut encoder = self
-            .gpu
+        let mut encoder = gpu
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
 
         {
             let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
-            cpass.set_pipeline(custom_pipeline.as_ref().unwrap_or(&self.pipeline));
+            cpass.set_pipeline(custom_pipeline.as_ref().unwrap_or(pipeline));
             cpass.set_bind_group(0, &bind_group, &[]);
             cpass.dispatch_workgroups((width + 15) / 16, (height + 15) / 16, 1);
         }
@@ -383,7 +418,7 @@ impl GpuEffects {
                 buffer: &texture_out_buf,
                 layout: wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(width * 4),
+                    bytes_per_row: Some(padded_bytes_per_row),
                     rows_per_image: Some(height),
                 },
             },
@@ -394,15 +429,20 @@ impl GpuEffects {
             },
         );
 
-        self.gpu.queue.submit(Some(encoder.finish()));
+        gpu.queue.submit(Some(encoder.finish()));
 
         let slice = texture_out_buf.slice(..);
         let (tx, rx) = std::sync::mpsc::channel();
         slice.map_async(wgpu::MapMode::Read, move |v| tx.send(v).unwrap());
-        self.gpu.device.poll(wgpu::Maintain::Wait);
+        gpu.device.poll(wgpu::Maintain::Wait);
 
         let result = if rx.recv().unwrap().is_ok() {
-            let data = slice.get_mapped_range().to_vec();
+            let data = slice
+                .get_mapped_range()
+                .chunks_exact(padded_bytes_per_row as usize)
+                .flat_map(|row| &row[..(width * 4) as usize])
+                .copied()
+                .collect();
             Some(FrameBuffer {
                 width,
                 height,
@@ -416,11 +456,9 @@ impl GpuEffects {
         texture_out_buf.unmap();
 
         // Release textures back to pool!
-        self.gpu
-            .texture_pool
+        gpu.texture_pool
             .release(texture_in, width, height, format, usage_in);
-        self.gpu
-            .texture_pool
+        gpu.texture_pool
             .release(texture_out, width, height, format, usage_out);
 
         result
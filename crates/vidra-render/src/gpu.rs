@@ -85,6 +85,57 @@ impl TexturePool {
     }
 }
 
+/// Which compositing backend a render pipeline runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderBackend {
+    /// Use wgpu when an adapter is available, otherwise fall back to the CPU.
+    #[default]
+    Auto,
+    /// Require a wgpu adapter; fail if none is found.
+    Gpu,
+    /// Pure software compositing — never touches wgpu.
+    Cpu,
+}
+
+impl RenderBackend {
+    /// Environment variable consulted by [`RenderBackend::from_env`] (set by `vidra render --backend`).
+    pub const ENV_VAR: &'static str = "VIDRA_RENDER_BACKEND";
+
+    /// Read the backend from `VIDRA_RENDER_BACKEND`, defaulting to `Auto`.
+    pub fn from_env() -> Self {
+        std::env::var(Self::ENV_VAR)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl std::str::FromStr for RenderBackend {
+    type Err = vidra_core::VidraError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "gpu" => Ok(Self::Gpu),
+            "cpu" => Ok(Self::Cpu),
+            other => Err(vidra_core::VidraError::InvalidArgument(format!(
+                "unknown render backend '{}' (expected auto, gpu or cpu)",
+                other
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for RenderBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Gpu => write!(f, "gpu"),
+            Self::Cpu => write!(f, "cpu"),
+        }
+    }
+}
+
 /// A shared context for all GPU-accelerated operations.
 pub struct GpuContext {
     pub instance: Instance,
@@ -129,3 +180,23 @@ impl GpuContext {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_backend_parse() {
+        assert_eq!("cpu".parse::<RenderBackend>().unwrap(), RenderBackend::Cpu);
+        assert_eq!(
+            " GPU ".parse::<RenderBackend>().unwrap(),
+            RenderBackend::Gpu
+        );
+        assert_eq!(
+            "auto".parse::<RenderBackend>().unwrap(),
+            RenderBackend::Auto
+        );
+        assert!("metal".parse::<RenderBackend>().is_err());
+        assert_eq!(RenderBackend::Cpu.to_string(), "cpu");
+    }
+}
@@ -1,13 +1,39 @@
 //! Image loading module.
-//! Decodes PNG, JPEG, WebP, and other formats into FrameBuffers.
+//! Decodes PNG, JPEG, WebP, and other formats into FrameBuffers, and parses SVG files
+//! into vector documents.
 
 use std::path::Path;
 
 use vidra_core::frame::FrameBuffer;
 use vidra_core::{PixelFormat, VidraError};
+use vidra_eval::svg::SvgDocument;
 
-/// Load an image file and convert it to a FrameBuffer.
+/// Whether `path` names an SVG file, which is loaded with [`load_svg`] rather than
+/// decoded into pixels.
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+}
+
+/// Load and parse an SVG file.
+pub fn load_svg(path: &Path) -> Result<SvgDocument, VidraError> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        VidraError::asset(
+            format!("failed to read SVG file '{}': {}", path.display(), e),
+            path,
+        )
+    })?;
+    SvgDocument::parse(&text).map_err(|e| {
+        VidraError::asset(format!("failed to parse '{}': {}", path.display(), e), path)
+    })
+}
+
+/// Load an image file and convert it to a FrameBuffer. A `data:` URI path (an image
+/// embedded in a Lottie animation) is decoded in memory.
 pub fn load_image(path: &Path) -> Result<FrameBuffer, VidraError> {
+    if let Some(bytes) = path.to_str().and_then(vidra_eval::media::data_uri_bytes) {
+        return load_image_from_bytes(&bytes?);
+    }
     let img_bytes = std::fs::read(path).map_err(|e| {
         VidraError::asset(
             format!("failed to read image file '{}': {}", path.display(), e),
@@ -55,6 +81,27 @@ pub fn load_image_from_bytes(data: &[u8]) -> Result<FrameBuffer, VidraError> {
     Ok(fb)
 }
 
+/// Resize a frame buffer to exactly `width`×`height` (nearest-neighbor).
+pub fn resize(fb: &FrameBuffer, width: u32, height: u32) -> FrameBuffer {
+    if width == fb.width && height == fb.height {
+        return fb.clone();
+    }
+    let mut resized = FrameBuffer::new(width, height, fb.format);
+    if fb.width == 0 || fb.height == 0 {
+        return resized;
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let src_x = (x as u64 * fb.width as u64 / width as u64) as u32;
+            let src_y = (y as u64 * fb.height as u64 / height as u64) as u32;
+            if let Some(pixel) = fb.get_pixel(src_x, src_y) {
+                resized.set_pixel(x, y, pixel);
+            }
+        }
+    }
+    resized
+}
+
 /// Resize an image frame buffer to fit within the given max dimensions,
 /// preserving aspect ratio.
 pub fn resize_to_fit(fb: &FrameBuffer, max_width: u32, max_height: u32) -> FrameBuffer {
@@ -95,6 +142,14 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_svg_reports_the_file() {
+        assert!(is_svg(Path::new("brand/Logo.SVG")));
+        assert!(!is_svg(Path::new("logo.png")));
+        let err = load_svg(Path::new("/nonexistent/logo.svg")).unwrap_err();
+        assert!(err.to_string().contains("logo.svg"), "{}", err);
+    }
+
     #[test]
     fn test_resize_to_fit_no_upscale() {
         let fb = FrameBuffer::solid(100, 100, &vidra_core::Color::RED);
@@ -112,4 +167,14 @@ mod tests {
         assert_eq!(resized.width, 100);
         assert_eq!(resized.height, 50);
     }
+
+    #[test]
+    fn test_resize_scales_each_axis() {
+        let mut fb = FrameBuffer::solid(2, 1, &vidra_core::Color::RED);
+        fb.set_pixel(1, 0, [0, 0, 255, 255]);
+        let resized = resize(&fb, 4, 3);
+        assert_eq!((resized.width, resized.height), (4, 3));
+        assert_eq!(resized.get_pixel(1, 2), Some([255, 0, 0, 255]));
+        assert_eq!(resized.get_pixel(2, 0), Some([0, 0, 255, 255]));
+    }
 }
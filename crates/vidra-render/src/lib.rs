@@ -4,15 +4,22 @@
 //! This is the single-threaded, CPU-only prototype renderer (Phase 0).
 //! GPU acceleration comes in Phase 1.
 
+pub mod audio_decoder;
+pub mod cache;
 pub mod compositor;
+pub mod cpu;
 pub mod custom_shader;
 pub mod effects;
 pub mod gpu;
 pub mod image_loader;
+mod motion_blur;
 pub mod pipeline;
+pub mod styles;
 pub mod text;
 pub mod video_decoder;
 
-pub use gpu::GpuContext;
+pub use audio_decoder::AudioDecoder;
+pub use cache::{CacheStats, RenderCacheStats};
+pub use gpu::{GpuContext, RenderBackend};
 pub use pipeline::{RenderContext, RenderPipeline, RenderResult};
 pub use video_decoder::VideoDecoder;
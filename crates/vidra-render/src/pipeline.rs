@@ -1,22 +1,26 @@
 use dashmap::DashMap;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
-use vidra_core::frame::FrameBuffer;
+use vidra_core::frame::{FrameBuffer, FrameSink, PixelFormat};
 use vidra_core::hash::{self, ContentHash};
-use vidra_core::Color;
-use vidra_ir::animation::AnimatableProperty;
+use vidra_core::plugin::{
+    EffectContext, LayerContext, PluginKind, PluginRegistry, TransitionContext,
+};
+use vidra_core::{Color, LayerEffect, Mat4};
+use vidra_eval::svg::SvgDocument;
+use vidra_eval::{FrameState, LayerBuffer, LayerState, Placement, SceneState, TransitionState};
 use vidra_ir::asset::AssetId;
-use vidra_ir::layer::{Layer, LayerContent};
+use vidra_ir::layer::LayerContent;
 use vidra_ir::project::Project;
-use vidra_ir::scene::Scene;
-
-use evalexpr::{
-    build_operator_tree, ContextWithMutableVariables, DefaultNumericTypes, HashMapContext, Value,
-};
+use vidra_ir::transition::TransitionType;
 
-use crate::text::TextRenderer;
+use crate::audio_decoder::AudioDecoder;
+use crate::cache::{RenderCache, RenderCacheStats};
+use crate::motion_blur::{self, SubframeStates};
+use crate::text::{TextBlock, TextRenderer};
 use crate::video_decoder::VideoDecoder;
 
 use std::sync::Arc;
@@ -24,21 +28,7 @@ use tokio::sync::Mutex;
 use vidra_web::{WebCaptureSession, WebCaptureSessionConfig};
 
 /// Context for rendering a single frame.
-pub struct RenderContext {
-    /// Output width.
-    pub width: u32,
-    /// Output height.
-    pub height: u32,
-    /// Current frame rate.
-    pub fps: f64,
-
-    /// Mouse position in pixel coordinates for interactive previews.
-    pub mouse_x: f64,
-    pub mouse_y: f64,
-
-    /// Runtime numeric state vars (used by interactive previews).
-    pub state_vars: HashMap<String, f64>,
-}
+pub type RenderContext = vidra_eval::EvalContext;
 
 /// Result of a complete render.
 pub struct RenderResult {
@@ -84,41 +74,156 @@ pub struct LayerBounds {
 pub struct RenderPipeline {
     text_renderer: TextRenderer,
     video_decoder: VideoDecoder,
+    audio_decoder: AudioDecoder,
     image_cache: DashMap<String, FrameBuffer>,
+    svg_cache: DashMap<String, Arc<SvgDocument>>,
     shader_cache: DashMap<String, String>,
-    #[allow(dead_code)]
-    gpu_ctx: std::sync::Arc<crate::gpu::GpuContext>,
+    /// `None` on the CPU backend.
+    gpu_ctx: Option<std::sync::Arc<crate::gpu::GpuContext>>,
     compositor: crate::compositor::GpuCompositor,
     shader_renderer: crate::custom_shader::CustomShaderRenderer,
     tokio_rt: Arc<tokio::runtime::Runtime>,
     web_sessions: DashMap<String, Arc<Mutex<WebCaptureSession>>>,
+    /// Composite-ready layer buffers, keyed by [`crate::cache::layer_key`].
+    layer_cache: RenderCache<LayerBuffer>,
+    /// Finished frames, keyed by [`crate::cache::frame_key`].
+    frame_cache: RenderCache,
+    /// Identifies the asset files loaded by [`RenderPipeline::load_assets`]; part of every
+    /// cache key so edited assets are never served stale.
+    assets_fingerprint: ContentHash,
+    /// Parsed animation and action expressions.
+    exprs: Arc<vidra_eval::ExprCache>,
+    /// Envelopes of the audio driving audio-reactive expressions.
+    audio: Arc<vidra_eval::AudioEnvelopes>,
+    /// Plugins that plugin effects, layers and transitions are looked up in.
+    plugins: Arc<PluginRegistry>,
 }
 
 impl RenderPipeline {
-    /// Create a new render pipeline.
+    /// Create a new render pipeline on the backend named by `VIDRA_RENDER_BACKEND`
+    /// (auto-detected when unset).
     pub fn new() -> Result<Self, vidra_core::VidraError> {
-        let gpu_ctx = std::sync::Arc::new(crate::gpu::GpuContext::init().map_err(|e| {
-            vidra_core::VidraError::Render(format!("Failed to initialize WGPU context: {}", e))
-        })?);
-        let compositor = crate::compositor::GpuCompositor::new(gpu_ctx.clone());
-        let shader_renderer = crate::custom_shader::CustomShaderRenderer::new(gpu_ctx.clone());
+        Self::with_backend(crate::gpu::RenderBackend::from_env())
+    }
+
+    /// Create a new render pipeline, as [`RenderPipeline::new`], that draws plugin
+    /// effects, layers and transitions with the plugins in `registry`.
+    pub fn with_plugins(registry: PluginRegistry) -> Result<Self, vidra_core::VidraError> {
+        let mut pipeline = Self::new()?;
+        pipeline.set_plugins(Arc::new(registry));
+        Ok(pipeline)
+    }
+
+    /// Create a new render pipeline on an explicit backend. `Auto` falls back to
+    /// the CPU when no wgpu adapter can be initialized.
+    pub fn with_backend(
+        backend: crate::gpu::RenderBackend,
+    ) -> Result<Self, vidra_core::VidraError> {
+        use crate::gpu::{GpuContext, RenderBackend};
+
+        let gpu_ctx = match backend {
+            RenderBackend::Cpu => None,
+            RenderBackend::Gpu => Some(std::sync::Arc::new(GpuContext::init().map_err(|e| {
+                vidra_core::VidraError::Render(format!("Failed to initialize WGPU context: {}", e))
+            })?)),
+            RenderBackend::Auto => match GpuContext::init() {
+                Ok(ctx) => Some(std::sync::Arc::new(ctx)),
+                Err(e) => {
+                    tracing::warn!("No usable GPU ({}); using the CPU render backend", e);
+                    None
+                }
+            },
+        };
+        let (compositor, shader_renderer) = match &gpu_ctx {
+            Some(gpu) => (
+                crate::compositor::GpuCompositor::new(gpu.clone()),
+                crate::custom_shader::CustomShaderRenderer::new(gpu.clone()),
+            ),
+            None => (
+                crate::compositor::GpuCompositor::cpu(),
+                crate::custom_shader::CustomShaderRenderer::cpu(),
+            ),
+        };
         let tokio_rt = Arc::new(tokio::runtime::Runtime::new().unwrap());
 
         Ok(Self {
             text_renderer: TextRenderer::new(),
             video_decoder: VideoDecoder::new(),
+            audio_decoder: AudioDecoder::new(),
             image_cache: DashMap::new(),
+            svg_cache: DashMap::new(),
             shader_cache: DashMap::new(),
             gpu_ctx,
             compositor,
             shader_renderer,
             tokio_rt,
             web_sessions: DashMap::new(),
+            layer_cache: RenderCache::new(crate::cache::DEFAULT_LAYER_CACHE_BYTES),
+            frame_cache: RenderCache::new(crate::cache::DEFAULT_FRAME_CACHE_BYTES),
+            assets_fingerprint: ContentHash::from_bytes([0; 32]),
+            exprs: Arc::default(),
+            audio: Arc::default(),
+            plugins: Arc::default(),
         })
     }
 
-    /// Load fonts (and other assets later) from the Project into the pipeline
+    /// The backend this pipeline actually runs on (never `Auto`).
+    pub fn backend(&self) -> crate::gpu::RenderBackend {
+        if self.gpu_ctx.is_some() {
+            crate::gpu::RenderBackend::Gpu
+        } else {
+            crate::gpu::RenderBackend::Cpu
+        }
+    }
+
+    /// Hit/miss counters of the layer and frame caches.
+    pub fn cache_stats(&self) -> RenderCacheStats {
+        RenderCacheStats {
+            layers: self.layer_cache.stats(),
+            frames: self.frame_cache.stats(),
+        }
+    }
+
+    /// Drop every cached layer and frame buffer.
+    pub fn clear_caches(&self) {
+        self.layer_cache.clear();
+        self.frame_cache.clear();
+    }
+
+    /// Use the plugins in `plugins` for plugin effects, layers and transitions.
+    /// Cached buffers drawn with the previous plugins are dropped.
+    pub fn set_plugins(&mut self, plugins: Arc<PluginRegistry>) {
+        self.plugins = plugins;
+        self.clear_caches();
+    }
+
+    /// Store audio envelopes under `dir` in addition to memory (only in memory by
+    /// default).
+    pub fn set_media_cache_dir(&mut self, dir: Option<std::path::PathBuf>) {
+        self.audio_decoder.set_cache_dir(dir);
+    }
+
+    /// Expand the SVG group and Lottie layers of `project` into ordinary layers, reading
+    /// their documents from disk (see [`vidra_eval::media`]). Call it once the project is
+    /// compiled or loaded, before [`Self::load_assets`]; expanding twice changes nothing.
+    pub fn expand_media(
+        &self,
+        project: &mut Project,
+    ) -> Result<vidra_eval::MediaReport, vidra_core::VidraError> {
+        let report = vidra_eval::expand_media(project, self)?;
+        for issue in &report.lottie_issues {
+            tracing::warn!("Lottie {}", issue);
+        }
+        Ok(report)
+    }
+
+    /// Load fonts (and other assets later) from the Project into the pipeline, parse
+    /// its animation expressions and analyze the audio they react to. Fails on the
+    /// first invalid expression.
     pub fn load_assets(&mut self, project: &Project) -> Result<(), vidra_core::VidraError> {
+        self.exprs.compile_project(project)?;
+        self.audio = Arc::new(self.load_audio_envelopes(project)?);
+        self.assets_fingerprint = assets_fingerprint(project);
         for asset in project.assets.all() {
             if asset.asset_type == vidra_ir::asset::AssetType::Font {
                 tracing::info!("Loading font {} from {}", asset.id.0, asset.path.display());
@@ -139,6 +244,15 @@ impl RenderPipeline {
                     ))
                 })?;
                 self.image_cache.insert(asset.id.to_string(), fb);
+            } else if asset.asset_type == vidra_ir::asset::AssetType::Svg {
+                tracing::info!("Loading SVG {} from {}", asset.id.0, asset.path.display());
+                let doc = crate::image_loader::load_svg(&asset.path).map_err(|e| {
+                    vidra_core::VidraError::Render(format!(
+                        "Asset load error {}: {}",
+                        asset.id.0, e
+                    ))
+                })?;
+                self.svg_cache.insert(asset.id.to_string(), Arc::new(doc));
             } else if asset.path.extension().map(|e| e == "wgsl").unwrap_or(false) {
                 tracing::info!(
                     "Loading shader {} from {}",
@@ -157,24 +271,91 @@ impl RenderPipeline {
         Ok(())
     }
 
-    /// Render the entire project to a sequence of FrameBuffers.
+    /// A non-interactive render context for `project`, sharing this pipeline's parsed
+    /// expressions.
+    fn context(&self, project: &Project) -> RenderContext {
+        RenderContext::new(project)
+            .with_exprs(self.exprs.clone())
+            .with_audio(self.audio.clone())
+    }
+
+    /// Decode every audio file referenced by an audio-reactive expression.
+    ///
+    /// A missing file, or audio that can't be decoded (e.g. without FFmpeg), is an error.
+    fn load_audio_envelopes(
+        &self,
+        project: &Project,
+    ) -> Result<vidra_eval::AudioEnvelopes, vidra_core::VidraError> {
+        fn collect<'a>(layer: &'a vidra_ir::layer::Layer, ids: &mut Vec<&'a AssetId>) {
+            ids.extend(
+                layer
+                    .animations
+                    .iter()
+                    .filter_map(|a| a.expr_audio.as_ref()),
+            );
+            for child in &layer.children {
+                collect(child, ids);
+            }
+        }
+        let mut ids = Vec::new();
+        for scene in &project.scenes {
+            for layer in &scene.layers {
+                collect(layer, &mut ids);
+            }
+        }
+
+        let mut envelopes = vidra_eval::AudioEnvelopes::new();
+        for id in ids {
+            if envelopes.contains_key(id) {
+                continue;
+            }
+            let path = project
+                .assets
+                .get(id)
+                .map(|asset| asset.path.clone())
+                .unwrap_or_else(|| id.0.clone().into());
+            if !path.exists() {
+                return Err(vidra_core::VidraError::asset(
+                    format!("audio for audio-reactive expression '{}' not found", id),
+                    path,
+                ));
+            }
+            tracing::info!("Analyzing audio {} from {}", id, path.display());
+            let envelope = self
+                .audio_decoder
+                .envelope(&path, project.settings.fps)
+                .map_err(|e| {
+                    vidra_core::VidraError::asset(
+                        format!("failed to decode audio '{}': {}", id, e),
+                        &path,
+                    )
+                })?;
+            envelopes.insert(id.clone(), envelope.as_ref().clone());
+        }
+        Ok(envelopes)
+    }
+
+    /// Render the entire project to a sequence of FrameBuffers. SVG group and Lottie
+    /// layers must already be expanded with [`RenderPipeline::expand_media`].
     pub fn render(project: &Project) -> Result<RenderResult, vidra_core::VidraError> {
         let mut pipeline = Self::new()?;
         pipeline.load_assets(project)?;
+        pipeline.render_project(project)
+    }
 
-        let ctx = RenderContext {
-            width: project.settings.width,
-            height: project.settings.height,
-            fps: project.settings.fps,
-            mouse_x: 0.0,
-            mouse_y: 0.0,
-            state_vars: HashMap::new(),
-        };
+    /// Render the entire project on this pipeline, reusing its caches.
+    ///
+    /// Assets must already be loaded with [`RenderPipeline::load_assets`].
+    pub fn render_project(
+        &self,
+        project: &Project,
+    ) -> Result<RenderResult, vidra_core::VidraError> {
+        let ctx = self.context(project);
 
         let total_frames = project.total_frames();
         let frames: Result<Vec<FrameBuffer>, _> = (0..total_frames)
             .into_par_iter()
-            .map(|global_frame| pipeline.render_frame_index(project, global_frame))
+            .map(|global_frame| self.render_frame_index(project, global_frame))
             .collect();
 
         let frames = frames?;
@@ -188,398 +369,367 @@ impl RenderPipeline {
         })
     }
 
-    /// Render exactly one frame by global frame index. Used for live preview.
-    pub fn render_frame_index(
+    /// Render the entire project and stream each frame, in order, into `sink`.
+    ///
+    /// Unlike [`RenderPipeline::render`], memory use is bounded by the reorder
+    /// window rather than the video length. The sink is finished on success.
+    /// Returns the number of frames written.
+    pub fn render_to_sink(
+        project: &Project,
+        sink: &mut dyn FrameSink,
+    ) -> Result<u64, vidra_core::VidraError> {
+        let mut pipeline = Self::new()?;
+        pipeline.load_assets(project)?;
+        let written = pipeline.stream_frames(project, sink)?;
+        sink.finish()?;
+        Ok(written)
+    }
+
+    /// Render every frame of `project` in parallel and hand them to `sink` in order.
+    ///
+    /// Workers render ahead of the sink by at most a fixed window of frames;
+    /// finished frames wait in a small reorder buffer until their turn. A worker
+    /// that gets too far ahead blocks until the sink catches up, so at most
+    /// `window + workers` frames are alive at any time.
+    pub fn stream_frames(
         &self,
         project: &Project,
-        global_frame: u64,
-    ) -> Result<FrameBuffer, vidra_core::VidraError> {
-        let ctx = RenderContext {
-            width: project.settings.width,
-            height: project.settings.height,
-            fps: project.settings.fps,
-            mouse_x: 0.0,
-            mouse_y: 0.0,
-            state_vars: HashMap::new(),
-        };
+        sink: &mut dyn FrameSink,
+    ) -> Result<u64, vidra_core::VidraError> {
+        let total_frames = project.total_frames();
+        if total_frames == 0 {
+            return Ok(0);
+        }
 
-        let mut current_global = 0u64;
-
-        let mut target_scenes = Vec::new();
-
-        for (i, scene) in project.scenes.iter().enumerate() {
-            let sf = scene.frame_count(project.settings.fps);
-            let trans_f = if i > 0 {
-                if let Some(trans) = &scene.transition {
-                    let max_overlap = project.scenes[i - 1]
-                        .frame_count(project.settings.fps)
-                        .min(sf);
-                    trans
-                        .duration
-                        .frame_count(project.settings.fps)
-                        .min(max_overlap)
-                } else {
-                    0
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(total_frames as usize);
+        let window = (workers * 2) as u64;
+
+        let next_index = AtomicU64::new(0);
+        let aborted = AtomicBool::new(false);
+        // Number of frames handed to the sink so far; workers wait on this.
+        let written_gate = (std::sync::Mutex::new(0u64), std::sync::Condvar::new());
+        let (tx, rx) = std::sync::mpsc::sync_channel::<(
+            u64,
+            Result<FrameBuffer, vidra_core::VidraError>,
+        )>(window as usize);
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                let tx = tx.clone();
+                let (next_index, aborted, written_gate) = (&next_index, &aborted, &written_gate);
+                scope.spawn(move || loop {
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    if index >= total_frames {
+                        break;
+                    }
+                    {
+                        let (lock, cvar) = written_gate;
+                        let mut written = lock.lock().unwrap();
+                        while index >= *written + window && !aborted.load(Ordering::SeqCst) {
+                            written = cvar.wait(written).unwrap();
+                        }
+                    }
+                    if aborted.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let frame = self.render_frame_index(project, index);
+                    if tx.send((index, frame)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            let mut pending: BTreeMap<u64, FrameBuffer> = BTreeMap::new();
+            let mut written = 0u64;
+            let result = (|| {
+                for (index, frame) in rx.iter() {
+                    pending.insert(index, frame?);
+                    while let Some(frame) = pending.remove(&written) {
+                        sink.write_frame(frame)?;
+                        written += 1;
+                        let (lock, cvar) = &written_gate;
+                        *lock.lock().unwrap() = written;
+                        cvar.notify_all();
+                    }
                 }
-            } else {
-                0
-            };
+                Ok(written)
+            })();
 
-            let start_f = current_global.saturating_sub(trans_f);
-            let end_f = start_f + sf;
-
-            if global_frame >= start_f && global_frame < end_f {
-                let local_f = global_frame - start_f;
-                target_scenes.push((scene, local_f));
+            if result.is_err() {
+                // Release any worker parked on the window, and make pending sends fail.
+                aborted.store(true, Ordering::SeqCst);
+                written_gate.1.notify_all();
             }
+            drop(rx);
+            result
+        })
+    }
 
-            current_global = end_f;
-        }
+    /// Render exactly one frame by global frame index. Used for live preview.
+    pub fn render_frame_index(
+        &self,
+        project: &Project,
+        global_frame: u64,
+    ) -> Result<FrameBuffer, vidra_core::VidraError> {
+        let ctx = self.context(project);
+        let state = self.evaluate_frame(project, global_frame);
 
-        if target_scenes.is_empty() {
+        let [outgoing, rest @ ..] = state.scenes.as_slice() else {
             return Err(vidra_core::VidraError::Render(format!(
                 "frame out of bounds: {}",
                 global_frame
             )));
-        }
+        };
+
+        let samples: Vec<SubframeStates> = motion_blur::settings_in_frame(project, &state)
+            .into_iter()
+            .map(|settings| SubframeStates {
+                settings,
+                states: settings
+                    .sample_offsets()
+                    .into_iter()
+                    .map(|offset| {
+                        self.evaluate_frame_with(
+                            &ctx.clone().with_subframe(offset),
+                            project,
+                            global_frame,
+                        )
+                    })
+                    .collect(),
+            })
+            .collect();
+        let blur = motion_blur::plan(project, &state, &samples);
 
-        if target_scenes.len() == 1 {
-            let (scene, local_f) = target_scenes[0];
-            self.render_frame(&ctx, project, scene, local_f, global_frame)
+        let layer_keys: Vec<Vec<Option<ContentHash>>> = state
+            .scenes
+            .iter()
+            .map(|scene| self.layer_keys(&ctx, scene))
+            .collect();
+        // A blurred frame also depends on its sub-frame states, which the key doesn't
+        // cover; such frames skip the frame cache (their layer samples are still cached).
+        let frame_key = if blur.iter().flatten().any(Option::is_some) {
+            None
         } else {
-            // Composite the two scenes for transition
-            // They are added in order, so index 0 is the older scene, index 1 is the entering scene
-            let (scene1, local_f1) = target_scenes[0];
-            let (scene2, local_f2) = target_scenes[1];
-
-            let frame1 = self.render_frame(&ctx, project, scene1, local_f1, global_frame)?;
-            let frame2 = self.render_frame(&ctx, project, scene2, local_f2, global_frame)?;
-
-            let trans = scene2.transition.as_ref().unwrap();
-            let trans_frames = trans.duration.frame_count(project.settings.fps) as f64;
-            let progress = local_f2 as f64 / trans_frames;
-            let eased_progress = trans.easing.apply(progress);
-
-            self.apply_transition(
-                frame1,
-                frame2,
-                &trans.effect,
-                eased_progress,
-                ctx.width,
-                ctx.height,
-            )
+            crate::cache::frame_key(&ctx, &project.settings.background, &state, &layer_keys)
+        };
+        if let Some(cached) = frame_key.as_ref().and_then(|key| self.frame_cache.get(key)) {
+            return Ok(cached.as_ref().clone());
+        }
+
+        let mut frame = self.render_frame(&ctx, project, outgoing, &layer_keys[0], &blur[0])?;
+        if let (Some(incoming), Some(transition)) = (rest.first(), &state.transition) {
+            let incoming = self.render_frame(&ctx, project, incoming, &layer_keys[1], &blur[1])?;
+            frame = self.apply_transition(&frame, &incoming, transition, &ctx)?;
+        }
+
+        if let Some(key) = frame_key {
+            self.frame_cache.insert(key, Arc::new(frame.clone()));
         }
+        Ok(frame)
     }
 
+    /// Blend the outgoing and incoming scene frames of a transition. Shader transitions
+    /// run on the GPU (crossfading on the CPU backend) and plugin transitions are handed
+    /// to the registered `TransitionPlugin`; the rest are shared with every renderer.
     fn apply_transition(
         &self,
-        frame1: FrameBuffer,
-        frame2: FrameBuffer,
-        effect: &vidra_ir::transition::TransitionType,
-        progress: f64,
-        width: u32,
-        height: u32,
+        outgoing: &FrameBuffer,
+        incoming: &FrameBuffer,
+        transition: &TransitionState,
+        ctx: &RenderContext,
     ) -> Result<FrameBuffer, vidra_core::VidraError> {
-        let mut out = frame1.clone();
-
-        match effect {
-            vidra_ir::transition::TransitionType::Crossfade => {
-                for y in 0..height {
-                    for x in 0..width {
-                        let c1 = frame1.get_pixel(x, y).unwrap_or([0, 0, 0, 0]);
-                        let c2 = frame2.get_pixel(x, y).unwrap_or([0, 0, 0, 0]);
-
-                        let r = (c1[0] as f64 * (1.0 - progress) + c2[0] as f64 * progress) as u8;
-                        let g = (c1[1] as f64 * (1.0 - progress) + c2[1] as f64 * progress) as u8;
-                        let b = (c1[2] as f64 * (1.0 - progress) + c2[2] as f64 * progress) as u8;
-                        let a = (c1[3] as f64 * (1.0 - progress) + c2[3] as f64 * progress) as u8;
-
-                        out.set_pixel(x, y, [r, g, b, a]);
-                    }
-                }
-            }
-            vidra_ir::transition::TransitionType::Wipe { direction } => {
-                let offset_x = (width as f64 * progress) as u32;
-                let offset_y = (height as f64 * progress) as u32;
-                for y in 0..height {
-                    for x in 0..width {
-                        let show_frame2 = match direction.as_str() {
-                            "left" => x >= width - offset_x,
-                            "up" => y >= height - offset_y,
-                            "down" => y < offset_y,
-                            _ => x < offset_x, // right
-                        };
-                        if show_frame2 {
-                            out.set_pixel(x, y, frame2.get_pixel(x, y).unwrap_or([0, 0, 0, 0]));
-                        }
-                    }
-                }
+        match &transition.effect {
+            TransitionType::Plugin { name, params } => {
+                let plugin = self.plugins.get_transition(name).ok_or_else(|| {
+                    vidra_core::VidraError::Plugin(format!(
+                        "no transition plugin named '{}' is registered",
+                        name
+                    ))
+                })?;
+                let plugin_ctx = TransitionContext {
+                    width: ctx.width,
+                    height: ctx.height,
+                    progress: transition.progress,
+                    params: params.clone(),
+                };
+                plugin
+                    .apply(outgoing, incoming, &plugin_ctx)
+                    .map_err(|e| plugin_error(PluginKind::Transition, name, e))
             }
-            vidra_ir::transition::TransitionType::Push { direction } => {
-                let offset_x = (width as f64 * progress) as u32;
-                let offset_y = (height as f64 * progress) as u32;
-                for y in 0..height {
-                    for x in 0..width {
-                        match direction.as_str() {
-                            "left" => {
-                                if x >= width - offset_x {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(x - (width - offset_x), y)
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                } else {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame1.get_pixel(x + offset_x, y).unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                            "up" => {
-                                if y >= height - offset_y {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(x, y - (height - offset_y))
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                } else {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame1.get_pixel(x, y + offset_y).unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                            "down" => {
-                                if y < offset_y {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(x, height - offset_y + y)
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                } else {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame1.get_pixel(x, y - offset_y).unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                            _ => {
-                                // right
-                                if x < offset_x {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(width - offset_x + x, y)
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                } else {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame1.get_pixel(x - offset_x, y).unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
+            TransitionType::Shader {
+                wgsl_source,
+                params,
+            } if self.gpu_ctx.is_some() => {
+                let params: Vec<f32> = params.iter().map(|&p| p as f32).collect();
+                self.shader_renderer.render_transition(
+                    wgsl_source,
+                    outgoing,
+                    incoming,
+                    transition.progress as f32,
+                    &params,
+                )
             }
-            vidra_ir::transition::TransitionType::Slide { direction } => {
-                let offset_x = (width as f64 * progress) as u32;
-                let offset_y = (height as f64 * progress) as u32;
-                for y in 0..height {
-                    for x in 0..width {
-                        match direction.as_str() {
-                            "left" => {
-                                if x >= width - offset_x {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(x - (width - offset_x), y)
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                            "up" => {
-                                if y >= height - offset_y {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(x, y - (height - offset_y))
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                            "down" => {
-                                if y < offset_y {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(x, height - offset_y + y)
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                            _ => {
-                                // right
-                                if x < offset_x {
-                                    out.set_pixel(
-                                        x,
-                                        y,
-                                        frame2
-                                            .get_pixel(width - offset_x + x, y)
-                                            .unwrap_or([0, 0, 0, 0]),
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
+            TransitionType::Shader { .. } => {
+                tracing::warn!("Shader transitions need a GPU; crossfading on the CPU backend");
+                Ok(vidra_eval::raster::blend_transition(
+                    outgoing, incoming, transition, ctx.width, ctx.height,
+                ))
             }
+            _ => Ok(vidra_eval::raster::blend_transition(
+                outgoing, incoming, transition, ctx.width, ctx.height,
+            )),
         }
+    }
 
-        Ok(out)
+    /// Evaluate the scenes and animated layer state of one frame without rasterizing it.
+    pub fn evaluate_frame(&self, project: &Project, global_frame: u64) -> FrameState {
+        self.evaluate_frame_with(&self.context(project), project, global_frame)
     }
 
-    /// Retrieve the bounding boxes of all visible layers at this exact frame
-    pub fn inspect_frame_bounds(
+    fn evaluate_frame_with(
         &self,
+        ctx: &RenderContext,
         project: &Project,
         global_frame: u64,
-    ) -> Result<Vec<LayerBounds>, vidra_core::VidraError> {
-        let ctx = RenderContext {
-            width: project.settings.width,
-            height: project.settings.height,
-            fps: project.settings.fps,
-            mouse_x: 0.0,
-            mouse_y: 0.0,
-            state_vars: HashMap::new(),
+    ) -> FrameState {
+        let measure = PipelineMeasure {
+            pipeline: self,
+            project,
         };
+        vidra_eval::evaluate_frame(ctx, project, global_frame, &measure)
+    }
 
-        let mut current_global = 0u64;
-        let mut target_scenes = Vec::new();
-
-        for (i, scene) in project.scenes.iter().enumerate() {
-            let sf = scene.frame_count(project.settings.fps);
-            let trans_f = if i > 0 {
-                if let Some(trans) = &scene.transition {
-                    let max_overlap = project.scenes[i - 1]
-                        .frame_count(project.settings.fps)
-                        .min(sf);
-                    trans
-                        .duration
-                        .frame_count(project.settings.fps)
-                        .min(max_overlap)
-                } else {
-                    0
-                }
-            } else {
-                0
-            };
-            let start_f = current_global.saturating_sub(trans_f);
-            let end_f = start_f + sf;
-
-            if global_frame >= start_f && global_frame < end_f {
-                let local_f = global_frame - start_f;
-                target_scenes.push((scene, local_f));
-            }
-            current_global = end_f;
-        }
-
-        let scene = target_scenes.last().map(|(s, _)| *s).ok_or_else(|| {
+    /// Retrieve the canvas-space bounding boxes of all visible layers at this exact
+    /// frame, nested layers included (listed after their parent).
+    pub fn inspect_frame_bounds(
+        &self,
+        project: &Project,
+        global_frame: u64,
+    ) -> Result<Vec<LayerBounds>, vidra_core::VidraError> {
+        let ctx = self.context(project);
+        let state = self.evaluate_frame(project, global_frame);
+        let scene = state.top_scene().ok_or_else(|| {
             vidra_core::VidraError::Render(format!("frame out of bounds: {}", global_frame))
         })?;
-        let local_f = target_scenes.last().map(|(_, f)| *f).unwrap();
 
         let mut bounds = Vec::new();
-
-        for layer in &scene.layers {
-            if !layer.visible {
-                continue;
-            }
-            let (content, _) = Self::compute_layer_animated_state(&ctx, layer, local_f);
-            if let Ok(layer_buf) = self.render_layer(&ctx, project, layer, &content, local_f) {
-                let (dx, dy) = Self::compute_layer_position(&ctx, layer, local_f);
-                let (cx, cy) = Self::apply_anchor(dx, dy, &layer_buf, layer, &content);
-                bounds.push(LayerBounds {
-                    id: layer.id.to_string(),
-                    x: cx,
-                    y: cy,
-                    width: layer_buf.width,
-                    height: layer_buf.height,
-                });
+        let keys = self.layer_keys(&ctx, scene);
+
+        for (layer, key) in scene.layers.iter().zip(&keys).filter(|(l, _)| l.visible) {
+            let output = self.layer_output(&ctx, project, scene, layer, key.as_ref());
+            if let Some(output) = or_skip(output, &layer.id)? {
+                let frame = scene.local_frame;
+                let content = output.content_size;
+                self.collect_bounds(
+                    &ctx,
+                    project,
+                    layer,
+                    content,
+                    style_outset(&ctx, layer),
+                    &Mat4::identity(),
+                    frame,
+                    &mut bounds,
+                );
             }
         }
 
         Ok(bounds)
     }
 
-    /// Render a single frame.
+    /// Push the bounds of `layer`, whose content is `content` pixels grown by what its
+    /// styles draw around it, and of its visible descendants. `parent` maps the layer's
+    /// parent space onto the canvas.
+    #[allow(clippy::too_many_arguments)]
+    fn collect_bounds(
+        &self,
+        ctx: &RenderContext,
+        project: &Project,
+        layer: &LayerState,
+        content: (u32, u32),
+        outset: crate::styles::Outset,
+        parent: &Mat4,
+        frame: u64,
+        out: &mut Vec<LayerBounds>,
+    ) {
+        let origin = (-(outset.left as i32), -(outset.top as i32));
+        let size = (
+            content
+                .0
+                .saturating_add(outset.left)
+                .saturating_add(outset.right),
+            content
+                .1
+                .saturating_add(outset.top)
+                .saturating_add(outset.bottom),
+        );
+        let placement = layer.placement(parent, content, origin, size);
+        let (x, y, width, height) = placement.bounds(size.0, size.1);
+        out.push(LayerBounds {
+            id: layer.id.to_string(),
+            x,
+            y,
+            width,
+            height,
+        });
+
+        let space = *parent * layer.local_matrix(content.0, content.1) * layer.child_matrix();
+        for child in layer.children.iter().filter(|c| c.visible) {
+            if let Ok(buf) = self.render_content(ctx, project, child, frame, (1.0, 1.0)) {
+                let content = (buf.width, buf.height);
+                let outset = style_outset(ctx, child);
+                self.collect_bounds(ctx, project, child, content, outset, &space, frame, out);
+            }
+        }
+    }
+
+    /// Cache keys of a scene's layers, aligned with `scene.layers`; `None` for hidden
+    /// and uncacheable layers.
+    fn layer_keys(&self, ctx: &RenderContext, scene: &SceneState) -> Vec<Option<ContentHash>> {
+        scene
+            .layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .visible
+                    .then(|| crate::cache::layer_key(&self.assets_fingerprint, ctx, scene, layer))
+                    .flatten()
+            })
+            .collect()
+    }
+
+    /// Render a single scene at its evaluated state. `blur` holds the sub-frame samples
+    /// of its motion-blurred layers, aligned with `scene.layers`.
     fn render_frame(
         &self,
         ctx: &RenderContext,
         project: &Project,
-        scene: &Scene,
-        local_frame: u64,
-        _global_frame: u64,
+        scene: &SceneState,
+        layer_keys: &[Option<ContentHash>],
+        blur: &[Option<Vec<&SceneState>>],
     ) -> Result<FrameBuffer, vidra_core::VidraError> {
         // Start with the background color
         let mut canvas = FrameBuffer::solid(ctx.width, ctx.height, &project.settings.background);
 
         // Composite layers bottom-to-top
-        for layer in &scene.layers {
-            if !layer.visible {
-                continue;
-            }
-            let (content, effects) = Self::compute_layer_animated_state(ctx, layer, local_frame);
-            if let Ok(mut layer_buf) = self.render_layer(ctx, project, layer, &content, local_frame)
-            {
-                let (dx, dy) = Self::compute_layer_position(ctx, layer, local_frame);
-
-                if let Some(mask_id) = &layer.mask {
-                    if let Some(mask_layer) = scene.layers.iter().find(|l| &l.id == mask_id) {
-                        let (m_content, _) =
-                            Self::compute_layer_animated_state(ctx, mask_layer, local_frame);
-                        if let Ok(mask_buf) =
-                            self.render_layer(ctx, project, mask_layer, &m_content, local_frame)
-                        {
-                            let (mdx, mdy) =
-                                Self::compute_layer_position(ctx, mask_layer, local_frame);
-                            let (mcx, mcy) =
-                                Self::apply_anchor(mdx, mdy, &mask_buf, mask_layer, &m_content);
-                            // For masks, keep the existing 2D anchor-based alignment (masking is applied
-                            // in the layer's local buffer space).
-                            let (cx, cy) = Self::apply_anchor(dx, dy, &layer_buf, layer, &content);
-                            let rel_x = mcx - cx;
-                            let rel_y = mcy - cy;
-                            layer_buf.apply_mask(&mask_buf, rel_x, rel_y);
-                        }
-                    }
-                }
-
-                let transform = Self::compute_layer_transform(ctx, layer, local_frame, dx, dy);
-                if Self::needs_projective_composite(&transform) {
-                    let corners =
-                        transform.project_corners(layer_buf.width as f64, layer_buf.height as f64);
-                    self.compositor
-                        .composite_projected(&mut canvas, &layer_buf, corners, &effects);
-                } else {
-                    let (cx, cy) = Self::apply_anchor(dx, dy, &layer_buf, layer, &content);
-                    self.compositor
-                        .composite(&mut canvas, &layer_buf, cx, cy, &effects);
+        for (i, (layer, key)) in scene
+            .layers
+            .iter()
+            .zip(layer_keys)
+            .enumerate()
+            .filter(|(_, (l, _))| l.visible)
+        {
+            if let Some(samples) = &blur[i] {
+                let blurred = self.motion_blurred_layer(ctx, project, samples, i)?;
+                self.compositor
+                    .composite(&mut canvas, &blurred, 0, 0, layer.blend_mode, &[]);
+            } else {
+                let output = self.layer_output(ctx, project, scene, layer, key.as_ref());
+                if let Some(output) = or_skip(output, &layer.id)? {
+                    self.place_layer(&mut canvas, layer, &output);
                 }
             }
         }
@@ -587,306 +737,180 @@ impl RenderPipeline {
         Ok(canvas)
     }
 
-    fn compute_layer_transform(
-        ctx: &RenderContext,
-        layer: &Layer,
-        frame: u64,
-        dx: i32,
-        dy: i32,
-    ) -> vidra_core::Transform2D {
-        let mut t = layer.transform;
-        t.position.x = dx as f64;
-        t.position.y = dy as f64;
-
-        let time = vidra_core::Duration::from_seconds(frame as f64 / ctx.fps);
-        for anim in &layer.animations {
-            if let Some(value) = Self::evaluate_animation(ctx, anim, time) {
-                match anim.property {
-                    AnimatableProperty::Rotation => t.rotation = value,
-                    AnimatableProperty::TranslateZ => t.translate_z = value,
-                    AnimatableProperty::RotateX => t.rotate_x = value,
-                    AnimatableProperty::RotateY => t.rotate_y = value,
-                    AnimatableProperty::Perspective => t.perspective = value,
-                    _ => {}
-                }
-            }
-        }
-        t
-    }
-
-    fn needs_projective_composite(t: &vidra_core::Transform2D) -> bool {
-        // Use projective path for any non-trivial rotation or 2.5D parameters.
-        t.rotation.abs() > f64::EPSILON
-            || t.translate_z.abs() > f64::EPSILON
-            || t.rotate_x.abs() > f64::EPSILON
-            || t.rotate_y.abs() > f64::EPSILON
-            || t.perspective > 0.0
-    }
-
-    /// Compute the animated position of a layer at a given frame.
-    fn compute_layer_position(ctx: &RenderContext, layer: &Layer, frame: u64) -> (i32, i32) {
-        let time = vidra_core::Duration::from_seconds(frame as f64 / ctx.fps);
-        let mut x = layer.transform.position.x;
-        let mut y = layer.transform.position.y;
-
-        for anim in &layer.animations {
-            if let Some(value) = Self::evaluate_animation(ctx, anim, time) {
-                match anim.property {
-                    AnimatableProperty::PositionX => x = value,
-                    AnimatableProperty::PositionY => y = value,
-                    _ => {}
-                }
+    /// Composite a top-level layer's output into `canvas` at its evaluated transform.
+    fn place_layer(&self, canvas: &mut FrameBuffer, layer: &LayerState, output: &LayerBuffer) {
+        match output.placement(layer, &Mat4::identity()) {
+            Placement::Blit(x, y) => {
+                self.compositor
+                    .composite(canvas, &output.buffer, x, y, layer.blend_mode, &[])
             }
+            Placement::Warp(corners) => self.compositor.composite_projected(
+                canvas,
+                &output.buffer,
+                corners,
+                layer.blend_mode,
+                &[],
+            ),
         }
-
-        (x as i32, y as i32)
     }
 
-    /// Compute the animated scale of a layer at a given frame.
-    fn compute_layer_scale(ctx: &RenderContext, layer: &Layer, frame: u64) -> (f64, f64) {
-        let time = vidra_core::Duration::from_seconds(frame as f64 / ctx.fps);
-        let mut sx = layer.transform.scale.x;
-        let mut sy = layer.transform.scale.y;
-
-        for anim in &layer.animations {
-            if let Some(value) = Self::evaluate_animation(ctx, anim, time) {
-                match anim.property {
-                    AnimatableProperty::ScaleX => sx = value,
-                    AnimatableProperty::ScaleY => sy = value,
-                    _ => {}
-                }
+    /// The canvas-sized average of layer `index` placed at each of its sub-frame states.
+    /// Samples that fail are left empty, like a layer that fails in an unblurred frame.
+    fn motion_blurred_layer(
+        &self,
+        ctx: &RenderContext,
+        project: &Project,
+        samples: &[&SceneState],
+        index: usize,
+    ) -> Result<FrameBuffer, vidra_core::VidraError> {
+        let mut acc = motion_blur::Accumulator::new(ctx.width, ctx.height);
+        for scene in samples {
+            let layer = &scene.layers[index];
+            let key = crate::cache::layer_key(&self.assets_fingerprint, ctx, scene, layer);
+            let mut sample = FrameBuffer::new(ctx.width, ctx.height, PixelFormat::Rgba8);
+            let output = self.layer_output(ctx, project, scene, layer, key.as_ref());
+            if let Some(output) = or_skip(output, &layer.id)? {
+                self.place_layer(&mut sample, layer, &output);
             }
+            acc.add(&sample);
         }
-
-        (sx, sy)
+        Ok(acc.finish())
     }
 
-    fn compute_layer_opacity(ctx: &RenderContext, layer: &Layer, frame: u64) -> f64 {
-        let time = vidra_core::Duration::from_seconds(frame as f64 / ctx.fps);
-        let mut opacity = layer.transform.opacity;
-
-        for anim in &layer.animations {
-            if let Some(value) = Self::evaluate_animation(ctx, anim, time) {
-                if matches!(anim.property, AnimatableProperty::Opacity) {
-                    opacity = value;
-                }
-            }
+    /// A layer's composite-ready buffer: grouped with its children, masked, and with
+    /// its effects applied. Served from the layer cache when `key` is known.
+    fn layer_output(
+        &self,
+        ctx: &RenderContext,
+        project: &Project,
+        scene: &SceneState,
+        layer: &LayerState,
+        key: Option<&ContentHash>,
+    ) -> Result<Arc<LayerBuffer>, vidra_core::VidraError> {
+        if let Some(cached) = key.and_then(|key| self.layer_cache.get(key)) {
+            return Ok(cached);
         }
 
-        opacity.clamp(0.0, 1.0)
-    }
+        let frame = scene.local_frame;
+        let mut output = self.render_layer(ctx, project, layer, frame, (1.0, 1.0))?;
 
-    /// Compute the layer content and effects updated with animation values at the given frame.
-    fn compute_layer_animated_state(
-        ctx: &RenderContext,
-        layer: &Layer,
-        frame: u64,
-    ) -> (LayerContent, Vec<vidra_core::types::LayerEffect>) {
-        let time = vidra_core::Duration::from_seconds(frame as f64 / ctx.fps);
-        let mut content = layer.content.clone();
-        let mut effects = layer.effects.clone();
-
-        for anim in &layer.animations {
-            if let Some(value) = Self::evaluate_animation(ctx, anim, time) {
-                match anim.property {
-                    AnimatableProperty::ColorR => match &mut content {
-                        LayerContent::Text { color, .. } => color.r = value as f32,
-                        LayerContent::Solid { color } => color.r = value as f32,
-                        LayerContent::Shape { fill, .. } => {
-                            if let Some(c) = fill {
-                                c.r = value as f32;
-                            }
-                        }
-                        _ => {}
-                    },
-                    AnimatableProperty::ColorG => match &mut content {
-                        LayerContent::Text { color, .. } => color.g = value as f32,
-                        LayerContent::Solid { color } => color.g = value as f32,
-                        LayerContent::Shape { fill, .. } => {
-                            if let Some(c) = fill {
-                                c.g = value as f32;
-                            }
-                        }
-                        _ => {}
-                    },
-                    AnimatableProperty::ColorB => match &mut content {
-                        LayerContent::Text { color, .. } => color.b = value as f32,
-                        LayerContent::Solid { color } => color.b = value as f32,
-                        LayerContent::Shape { fill, .. } => {
-                            if let Some(c) = fill {
-                                c.b = value as f32;
-                            }
-                        }
-                        _ => {}
-                    },
-                    AnimatableProperty::ColorA => match &mut content {
-                        LayerContent::Text { color, .. } => color.a = value as f32,
-                        LayerContent::Solid { color } => color.a = value as f32,
-                        LayerContent::Shape { fill, .. } => {
-                            if let Some(c) = fill {
-                                c.a = value as f32;
-                            }
-                        }
-                        _ => {}
-                    },
-                    AnimatableProperty::FontSize => {
-                        if let LayerContent::Text { font_size, .. } = &mut content {
-                            *font_size = value;
-                        }
-                    }
-                    AnimatableProperty::CornerRadius => {
-                        if let LayerContent::Shape {
-                            shape: vidra_core::types::ShapeType::Rect { corner_radius, .. },
-                            ..
-                        } = &mut content
-                        {
-                            *corner_radius = value;
-                        }
-                    }
-                    AnimatableProperty::StrokeWidth => {
-                        if let LayerContent::Shape { stroke_width, .. } = &mut content {
-                            *stroke_width = value;
-                        }
-                    }
-                    AnimatableProperty::Volume => {
-                        if let LayerContent::Audio { volume, .. }
-                        | LayerContent::TTS { volume, .. } = &mut content
-                        {
-                            *volume = value;
-                        }
-                    }
-                    AnimatableProperty::BlurRadius => {
-                        for effect in &mut effects {
-                            if let vidra_core::types::LayerEffect::Blur(radius) = effect {
-                                *radius = value;
-                            }
-                        }
-                    }
-                    AnimatableProperty::BrightnessLevel => {
-                        for effect in &mut effects {
-                            if let vidra_core::types::LayerEffect::Brightness(level) = effect {
-                                *level = value;
-                            }
-                        }
-                    }
-                    _ => {}
-                }
+        if let Some(matte_layer) = layer.mask.as_ref().and_then(|id| scene.layer(id)) {
+            let matte = self.render_layer(ctx, project, matte_layer, frame, (1.0, 1.0));
+            if let Some(matte) = or_skip(matte, &matte_layer.id)? {
+                vidra_eval::mask::apply_track_matte(&mut output, layer, &matte, matte_layer);
             }
         }
 
-        (content, effects)
+        let output = Arc::new(self.with_effects(ctx, layer, output)?);
+        if let Some(key) = key {
+            self.layer_cache.insert(key.clone(), output.clone());
+        }
+        Ok(output)
     }
 
-    fn evaluate_animation(
+    /// `output` of `layer` padded by what its styles draw around it, with its effects
+    /// applied.
+    fn with_effects(
+        &self,
         ctx: &RenderContext,
-        anim: &vidra_ir::animation::Animation,
-        time: vidra_core::Duration,
-    ) -> Option<f64> {
-        if let Some(expr) = anim.expr.as_deref() {
-            let effective_secs = time.as_seconds() - anim.delay.as_seconds();
-            if effective_secs < 0.0 {
-                return None;
-            }
+        layer: &LayerState,
+        mut output: LayerBuffer,
+    ) -> Result<LayerBuffer, vidra_core::VidraError> {
+        let outset = style_outset(ctx, layer);
+        output.pad(outset.left, outset.top, outset.right, outset.bottom);
+        output.buffer = self.apply_effects(ctx, layer, output.buffer)?;
+        Ok(output)
+    }
 
-            let duration_secs = anim
-                .expr_duration
-                .as_ref()
-                .map(|d| d.as_seconds())
-                .unwrap_or(0.0);
-            let t = if duration_secs > 0.0 {
-                effective_secs.min(duration_secs)
-            } else {
-                effective_secs
-            };
-            let p = if duration_secs > 0.0 {
-                (t / duration_secs).clamp(0.0, 1.0)
-            } else {
-                1.0
-            };
-
-            let compiled = build_operator_tree::<DefaultNumericTypes>(expr).ok()?;
-            let mut context = HashMapContext::new();
-            let _ = context.set_value("t".to_string(), Value::Float(t));
-            let _ = context.set_value("p".to_string(), Value::Float(p));
-            let _ = context.set_value("T".to_string(), Value::Float(duration_secs));
-            let _ = context.set_value("mouse_x".to_string(), Value::Float(ctx.mouse_x));
-            let _ = context.set_value("mouse_y".to_string(), Value::Float(ctx.mouse_y));
-            let _ = context.set_value("audio_amp".to_string(), Value::Float(0.0));
-            for (k, v) in &ctx.state_vars {
-                if k != "t"
-                    && k != "p"
-                    && k != "T"
-                    && k != "mouse_x"
-                    && k != "mouse_y"
-                    && k != "audio_amp"
-                {
-                    let _ = context.set_value(k.clone(), Value::Float(*v));
+    /// Apply `layer`'s effects in order: plugin effects through the plugin registry, the
+    /// rest through the compositor.
+    fn apply_effects(
+        &self,
+        ctx: &RenderContext,
+        layer: &LayerState,
+        buffer: FrameBuffer,
+    ) -> Result<FrameBuffer, vidra_core::VidraError> {
+        layer
+            .effects
+            .iter()
+            .try_fold(buffer, |mut buffer, effect| match effect {
+                LayerEffect::Plugin { name, params } => {
+                    let plugin = self.plugins.get_effect(name).ok_or_else(|| {
+                        vidra_core::VidraError::Plugin(format!(
+                            "no effect plugin named '{}' is registered",
+                            name
+                        ))
+                    })?;
+                    let effect_ctx = EffectContext {
+                        width: buffer.width,
+                        height: buffer.height,
+                        time: layer.time.as_seconds(),
+                        fps: ctx.fps,
+                        params: params.clone(),
+                    };
+                    plugin
+                        .apply(&mut buffer, &effect_ctx)
+                        .map_err(|e| plugin_error(PluginKind::Effect, name, e))?;
+                    Ok(buffer)
                 }
-            }
-
-            return compiled
-                .eval_with_context(&context)
-                .ok()
-                .and_then(|v| v.as_number().ok());
-        }
-
-        anim.evaluate(time)
-    }
-
-    /// Determine whether a layer's content has an intrinsic bounding box
-    /// (i.e., it's not a full-canvas fill). Only layers with intrinsic sizes
-    /// should have anchor-point offsets applied.
-    fn has_intrinsic_size(content: &LayerContent) -> bool {
-        matches!(
-            content,
-            LayerContent::Text { .. }
-                | LayerContent::Image { .. }
-                | LayerContent::Video { .. }
-                | LayerContent::Shape { .. }
-                | LayerContent::TTS { .. }
-                | LayerContent::AutoCaption { .. }
-                | LayerContent::Waveform { .. }
-                | LayerContent::Spritesheet { .. }
-                | LayerContent::Web { .. }
-        )
+                LayerEffect::CustomShader { .. } if self.gpu_ctx.is_none() => {
+                    Err(vidra_core::VidraError::Unsupported(
+                        "custom WGSL effects need a GPU adapter (CPU backend active)".into(),
+                    ))
+                }
+                other => Ok(self
+                    .compositor
+                    .apply_effects(buffer, std::slice::from_ref(other))),
+            })
     }
 
-    /// Apply anchor-point offset to the raw position.
-    /// For full-canvas layers (Solid, Empty, Audio), the position is returned unchanged.
-    fn apply_anchor(
-        dx: i32,
-        dy: i32,
-        buf: &FrameBuffer,
-        layer: &Layer,
-        content: &LayerContent,
-    ) -> (i32, i32) {
-        if Self::has_intrinsic_size(content) {
-            let cx = dx - (buf.width as f64 * layer.transform.anchor.x).round() as i32;
-            let cy = dy - (buf.height as f64 * layer.transform.anchor.y).round() as i32;
-            (cx, cy)
-        } else {
-            (dx, dy)
-        }
+    /// Render a layer grouped with its visible children, in the layer's own space.
+    /// `inherited` is the combined scale of the layers it is nested in.
+    fn render_layer(
+        &self,
+        ctx: &RenderContext,
+        project: &Project,
+        layer: &LayerState,
+        frame: u64,
+        inherited: (f64, f64),
+    ) -> Result<LayerBuffer, vidra_core::VidraError> {
+        // Vector content is drawn at the size its ancestors will show it at.
+        let density = match layer.content {
+            LayerContent::Svg { .. } => inherited,
+            _ => (1.0, 1.0),
+        };
+        let content = self.render_content(ctx, project, layer, frame, density)?;
+        let inherited = (inherited.0 * layer.scale.0, inherited.1 * layer.scale.1);
+        let children = layer
+            .children
+            .iter()
+            .filter(|c| c.visible)
+            .map(|child| {
+                let output = self.render_layer(ctx, project, child, frame, inherited)?;
+                Ok((child, self.with_effects(ctx, child, output)?))
+            })
+            .collect::<Result<Vec<_>, vidra_core::VidraError>>()?;
+        let margin = (ctx.width, ctx.height);
+        let mut output = LayerBuffer::group(content, density, layer, &children, margin);
+        vidra_eval::mask::apply_shape_mask(&mut output, layer);
+        Ok(output)
     }
 
-    /// Render a single layer to its own FrameBuffer.
-    fn render_layer(
+    /// Render a layer's own content (without its children), scaled. Vector content is
+    /// drawn `density` times larger still; everything else ignores it.
+    fn render_content(
         &self,
         ctx: &RenderContext,
         project: &Project,
-        layer: &Layer,
-        content: &LayerContent,
+        layer: &LayerState,
         frame: u64,
+        density: (f64, f64),
     ) -> Result<FrameBuffer, vidra_core::VidraError> {
-        let opacity = Self::compute_layer_opacity(ctx, layer, frame);
+        let opacity = layer.opacity;
 
-        let mut buf = match content {
-            LayerContent::Solid { color } => {
-                let mut c = *color;
-                c.a *= opacity as f32;
-                FrameBuffer::solid(ctx.width, ctx.height, &c)
-            }
-            LayerContent::Empty | LayerContent::Audio { .. } => {
+        let mut buf = match &layer.content {
+            LayerContent::Solid { color } => color.render(ctx.width, ctx.height, opacity),
+            LayerContent::Empty | LayerContent::Lottie { .. } | LayerContent::Audio { .. } => {
                 FrameBuffer::new(ctx.width, ctx.height, vidra_core::frame::PixelFormat::Rgba8)
             }
             LayerContent::Text {
@@ -894,64 +918,50 @@ impl RenderPipeline {
                 font_family,
                 font_size,
                 color,
+                style,
+                animators,
             } => {
-                // Real text rendering using fontdue
-                let mut c = *color;
-                c.a *= opacity as f32;
+                let block = TextBlock {
+                    text,
+                    font_family,
+                    font_size: *font_size as f32,
+                    color,
+                    style,
+                    animators,
+                };
                 self.text_renderer
-                    .render_text(text, font_family, *font_size as f32, &c)
-            }
-            LayerContent::Shape { shape, fill, .. } => {
-                let fill_color = fill.unwrap_or(Color::WHITE);
-                match shape {
-                    vidra_core::types::ShapeType::Rect { width, height, .. } => {
-                        let mut c = fill_color;
-                        c.a *= opacity as f32;
-                        FrameBuffer::solid(*width as u32, *height as u32, &c)
-                    }
-                    vidra_core::types::ShapeType::Circle { radius } => {
-                        let size = (*radius * 2.0) as u32;
-                        let mut fb = FrameBuffer::new(size, size, vidra_core::PixelFormat::Rgba8);
-                        let rgba = {
-                            let mut c = fill_color;
-                            c.a *= opacity as f32;
-                            c.to_rgba8()
-                        };
-                        let center = *radius;
-                        for y in 0..size {
-                            for x in 0..size {
-                                let dx = x as f64 - center;
-                                let dy = y as f64 - center;
-                                if dx * dx + dy * dy <= center * center {
-                                    fb.set_pixel(x, y, rgba);
-                                }
-                            }
-                        }
-                        fb
-                    }
-                    vidra_core::types::ShapeType::Ellipse { rx, ry } => {
-                        let w = (*rx * 2.0) as u32;
-                        let h = (*ry * 2.0) as u32;
-                        let mut fb = FrameBuffer::new(w, h, vidra_core::PixelFormat::Rgba8);
-                        let rgba = {
-                            let mut c = fill_color;
-                            c.a *= opacity as f32;
-                            c.to_rgba8()
-                        };
-                        for y in 0..h {
-                            for x in 0..w {
-                                let dx = (x as f64 - *rx) / rx;
-                                let dy = (y as f64 - *ry) / ry;
-                                if dx * dx + dy * dy <= 1.0 {
-                                    fb.set_pixel(x, y, rgba);
-                                }
-                            }
-                        }
-                        fb
-                    }
-                }
+                    .render_styled(&block, layer.time, opacity as f32)
             }
+            LayerContent::Shape {
+                shape,
+                fill,
+                stroke,
+                stroke_width,
+            } => vidra_eval::shape::render_shape(
+                shape,
+                fill.as_ref(),
+                stroke.as_ref(),
+                *stroke_width,
+                opacity,
+            ),
             LayerContent::Image { asset_id } => self.load_image_asset(project, asset_id, opacity),
+            LayerContent::Svg {
+                asset_id,
+                element,
+                exclude,
+                ..
+            } => {
+                // Drawn at its final size, so it isn't resized below.
+                let scale = (layer.scale.0 * density.0, layer.scale.1 * density.1);
+                return Ok(match self.load_svg_asset(project, asset_id) {
+                    Some(doc) => doc.render(element.as_deref(), exclude, scale, opacity),
+                    None => FrameBuffer::solid(
+                        (128.0 * scale.0).round() as u32,
+                        (128.0 * scale.1).round() as u32,
+                        &Color::rgba(1.0, 0.0, 1.0, opacity as f32),
+                    ),
+                });
+            }
             LayerContent::Spritesheet {
                 asset_id,
                 frame_width,
@@ -961,7 +971,7 @@ impl RenderPipeline {
                 frame_count,
             } => {
                 let sheet = self.load_image_asset(project, asset_id, opacity);
-                self.render_spritesheet_frame(
+                vidra_eval::raster::spritesheet_frame(
                     &sheet,
                     *frame_width,
                     *frame_height,
@@ -1001,22 +1011,8 @@ impl RenderPipeline {
                 // Or maybe the user set a custom scale/size on the layer. We'll default to project for this prototype phase.
                 if let Some(source) = self.shader_cache.get(&asset_id.to_string()) {
                     let time_sec = frame as f32 / ctx.fps as f32;
-                    match self.shader_renderer.render(
-                        source.value(),
-                        ctx.width,
-                        ctx.height,
-                        time_sec,
-                    ) {
-                        Ok(fb) => fb,
-                        Err(e) => {
-                            tracing::error!(
-                                "Custom shader render failed for {}: {}",
-                                asset_id.to_string(),
-                                e
-                            );
-                            FrameBuffer::new(ctx.width, ctx.height, vidra_core::PixelFormat::Rgba8)
-                        }
-                    }
+                    self.shader_renderer
+                        .render(source.value(), ctx.width, ctx.height, time_sec)?
                 } else {
                     tracing::warn!("Custom shader {} not found in cache", asset_id.to_string());
                     FrameBuffer::new(ctx.width, ctx.height, vidra_core::PixelFormat::Rgba8)
@@ -1071,82 +1067,52 @@ impl RenderPipeline {
                     }
                 }
             }
+            LayerContent::Plugin { name, props } => {
+                let plugin = self.plugins.get_layer(name).ok_or_else(|| {
+                    vidra_core::VidraError::Plugin(format!(
+                        "no layer plugin named '{}' is registered",
+                        name
+                    ))
+                })?;
+                let mut fb = plugin
+                    .render(&LayerContext {
+                        width: ctx.width,
+                        height: ctx.height,
+                        frame: frame as u32,
+                        time: layer.time.as_seconds(),
+                        fps: ctx.fps,
+                        params: props.clone(),
+                    })
+                    .map_err(|e| plugin_error(PluginKind::Layer, name, e))?;
+                if (opacity - 1.0).abs() > f64::EPSILON {
+                    for y in 0..fb.height {
+                        for x in 0..fb.width {
+                            if let Some([r, g, b, a]) = fb.get_pixel(x, y) {
+                                fb.set_pixel(x, y, [r, g, b, (a as f64 * opacity) as u8]);
+                            }
+                        }
+                    }
+                }
+                fb
+            }
         };
 
-        // Apply scale generically via resize_to_fit if needed
-        let (sx, sy) = Self::compute_layer_scale(ctx, layer, frame);
+        // Apply scale; children are placed in this scaled space.
+        let (sx, sy) = layer.scale;
         if (sx - 1.0).abs() > f64::EPSILON || (sy - 1.0).abs() > f64::EPSILON {
-            let max_w = (buf.width as f64 * sx).round() as u32;
-            let max_h = (buf.height as f64 * sy).round() as u32;
-            if max_w > 0 && max_h > 0 {
-                // Easiest scaling fallback for prototype implementation
-                buf = crate::image_loader::resize_to_fit(&buf, max_w.max(1), max_h.max(1));
+            let w = (buf.width as f64 * sx).round() as u32;
+            let h = (buf.height as f64 * sy).round() as u32;
+            if w > 0 && h > 0 {
+                buf = crate::image_loader::resize(&buf, w, h);
             } else {
                 // If scaled down to 0, just return empty frame buffer
                 buf = FrameBuffer::new(1, 1, vidra_core::frame::PixelFormat::Rgba8);
             }
         }
 
-        // Render child layers
-        for child in &layer.children {
-            if !child.visible {
-                continue;
-            }
-            let (c_content, _) = Self::compute_layer_animated_state(ctx, child, frame);
-            let child_buf = self.render_layer(ctx, project, child, &c_content, frame)?;
-            let (dx, dy) = Self::compute_layer_position(ctx, child, frame);
-            let (cx, cy) = Self::apply_anchor(dx, dy, &child_buf, child, &c_content);
-            buf.composite_over(&child_buf, cx, cy);
-        }
-
         Ok(buf)
     }
 
-    fn render_spritesheet_frame(
-        &self,
-        sheet: &FrameBuffer,
-        frame_w: u32,
-        frame_h: u32,
-        sheet_fps: f64,
-        start_frame: u32,
-        frame_count: Option<u32>,
-        local_frame: u64,
-        timeline_fps: f64,
-    ) -> FrameBuffer {
-        if sheet.format != vidra_core::frame::PixelFormat::Rgba8 || frame_w == 0 || frame_h == 0 {
-            return sheet.clone();
-        }
-
-        let cols = (sheet.width / frame_w).max(1);
-        let rows = (sheet.height / frame_h).max(1);
-        let derived_total = cols.saturating_mul(rows) as u32;
-        let total = frame_count
-            .unwrap_or(derived_total)
-            .max(1)
-            .min(derived_total.max(1));
-
-        let t = local_frame as f64 / timeline_fps;
-        let idx = if sheet_fps <= 0.0 {
-            0
-        } else {
-            ((t * sheet_fps).floor() as u32) % total
-        };
-
-        let frame_idx = start_frame.saturating_add(idx) % total;
-        let x = (frame_idx % cols as u32) * frame_w;
-        let y = (frame_idx / cols as u32) * frame_h;
-
-        let mut out = FrameBuffer::new(frame_w, frame_h, vidra_core::frame::PixelFormat::Rgba8);
-        for yy in 0..frame_h {
-            for xx in 0..frame_w {
-                if let Some(px) = sheet.get_pixel(x + xx, y + yy) {
-                    out.set_pixel(xx, yy, px);
-                }
-            }
-        }
-        out
-    }
-
     /// Load an image asset, with caching.
     fn load_image_asset(&self, project: &Project, asset_id: &AssetId, opacity: f64) -> FrameBuffer {
         let cache_key = asset_id.to_string();
@@ -1193,6 +1159,26 @@ impl RenderPipeline {
         }
     }
 
+    /// Load an SVG asset, with caching. `None` if it can't be read or parsed.
+    fn load_svg_asset(&self, project: &Project, asset_id: &AssetId) -> Option<Arc<SvgDocument>> {
+        let cache_key = asset_id.to_string();
+        if let Some(cached) = self.svg_cache.get(&cache_key) {
+            return Some(cached.value().clone());
+        }
+        let asset = project.assets.get(asset_id)?;
+        match crate::image_loader::load_svg(&asset.path) {
+            Ok(doc) => {
+                let doc = Arc::new(doc);
+                self.svg_cache.insert(cache_key, doc.clone());
+                Some(doc)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load SVG asset '{}': {}", asset_id, e);
+                None
+            }
+        }
+    }
+
     /// Render a video frame by extracting the appropriate frame from the source video.
     fn render_video_frame(
         &self,
@@ -1256,6 +1242,156 @@ impl RenderPipeline {
     }
 }
 
+/// Documents for SVG group and Lottie layers, read from disk (SVGs through the cache).
+impl vidra_eval::MediaSource for RenderPipeline {
+    fn svg(
+        &self,
+        assets: &vidra_ir::asset::AssetRegistry,
+        id: &AssetId,
+    ) -> Result<Option<Arc<SvgDocument>>, vidra_core::VidraError> {
+        if let Some(cached) = self.svg_cache.get(&id.0) {
+            return Ok(Some(cached.value().clone()));
+        }
+        let doc = Arc::new(crate::image_loader::load_svg(&asset_path(assets, id))?);
+        self.svg_cache.insert(id.0.clone(), doc.clone());
+        Ok(Some(doc))
+    }
+
+    fn lottie(
+        &self,
+        assets: &vidra_ir::asset::AssetRegistry,
+        id: &AssetId,
+    ) -> Result<Option<vidra_ir::lottie::LottieImport>, vidra_core::VidraError> {
+        let path = asset_path(assets, id);
+        let json = std::fs::read_to_string(&path).map_err(|e| {
+            vidra_core::VidraError::asset(format!("failed to read Lottie file: {}", e), &path)
+        })?;
+        let mut import = vidra_ir::lottie::import(&json).map_err(|e| {
+            vidra_core::VidraError::asset(format!("failed to import Lottie file: {}", e), &path)
+        })?;
+        import.resolve_image_paths(path.parent().unwrap_or(Path::new("")));
+        Ok(Some(import))
+    }
+}
+
+/// Where the asset `id` is read from: its registered path, or the id itself.
+fn asset_path(assets: &vidra_ir::asset::AssetRegistry, id: &AssetId) -> std::path::PathBuf {
+    assets
+        .get(id)
+        .map(|asset| asset.path.clone())
+        .unwrap_or_else(|| id.0.clone().into())
+}
+
+/// `result`, or `None` if it failed in a way that only skips layer `id`. Plugin failures
+/// and content the backend can't draw fail the render instead: without them, the output
+/// would silently differ.
+fn or_skip<T>(
+    result: Result<T, vidra_core::VidraError>,
+    id: &impl std::fmt::Display,
+) -> Result<Option<T>, vidra_core::VidraError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e @ (vidra_core::VidraError::Plugin(_) | vidra_core::VidraError::Unsupported(_))) => {
+            Err(e)
+        }
+        Err(e) => {
+            tracing::warn!("Skipping layer '{}': {}", id, e);
+            Ok(None)
+        }
+    }
+}
+
+/// `err` raised by the `kind` plugin `name`, as a plugin error so that it fails the render.
+fn plugin_error(
+    kind: PluginKind,
+    name: &str,
+    err: vidra_core::VidraError,
+) -> vidra_core::VidraError {
+    match err {
+        vidra_core::VidraError::Plugin(_) => err,
+        other => {
+            vidra_core::VidraError::Plugin(format!("{} plugin '{}' failed: {}", kind, name, other))
+        }
+    }
+}
+
+/// The room `layer`'s styles draw in around it, at most a canvas on each side so that
+/// extreme values cannot blow up its buffer.
+fn style_outset(ctx: &RenderContext, layer: &LayerState) -> crate::styles::Outset {
+    crate::styles::outset(&layer.effects).clamped(ctx.width, ctx.height)
+}
+
+/// Fingerprint of the project's asset files: ids, paths, sizes and modification times.
+fn assets_fingerprint(project: &Project) -> ContentHash {
+    let mut assets: Vec<_> = project.assets.all().collect();
+    assets.sort_by(|a, b| a.id.0.cmp(&b.id.0));
+
+    let mut hasher = hash::ContentHasher::new();
+    for asset in assets {
+        hasher.update(asset.id.0.as_bytes());
+        hasher.update([0]);
+        hasher.update(asset.path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        if let Ok(meta) = std::fs::metadata(&asset.path) {
+            hasher.update(meta.len().to_le_bytes());
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            hasher.update(modified.as_nanos().to_le_bytes());
+        }
+    }
+    hasher.finish()
+}
+
+/// Sizes text and image content for layout constraints.
+struct PipelineMeasure<'a> {
+    pipeline: &'a RenderPipeline,
+    project: &'a Project,
+}
+
+impl vidra_eval::ContentMeasure for PipelineMeasure<'_> {
+    fn measure(&self, content: &LayerContent) -> Option<(u32, u32)> {
+        let fb = match content {
+            LayerContent::Text {
+                text,
+                font_family,
+                font_size,
+                color,
+                style,
+                animators,
+            } => {
+                // Animators pad the text by the same amount at every frame, so any
+                // time measures the same.
+                let block = TextBlock {
+                    text,
+                    font_family,
+                    font_size: *font_size as f32,
+                    color,
+                    style,
+                    animators,
+                };
+                self.pipeline
+                    .text_renderer
+                    .render_styled(&block, vidra_core::Duration::zero(), 1.0)
+            }
+            LayerContent::Image { asset_id } => {
+                self.pipeline.load_image_asset(self.project, asset_id, 1.0)
+            }
+            LayerContent::Svg {
+                asset_id, element, ..
+            } => {
+                let doc = self.pipeline.load_svg_asset(self.project, asset_id)?;
+                let (_, size) = doc.content_rect(element.as_deref())?;
+                return Some((size.width.round() as u32, size.height.round() as u32));
+            }
+            _ => return None,
+        };
+        Some((fb.width, fb.height))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1271,7 +1407,9 @@ mod tests {
         );
         scene.add_layer(Layer::new(
             LayerId::new("bg"),
-            LayerContent::Solid { color: Color::BLUE },
+            LayerContent::Solid {
+                color: Color::BLUE.into(),
+            },
         ));
         scene.add_layer(
             Layer::new(
@@ -1281,6 +1419,8 @@ mod tests {
                     font_family: "Inter".into(),
                     font_size: 48.0,
                     color: Color::WHITE,
+                    style: Default::default(),
+                    animators: Vec::new(),
                 },
             )
             .with_position(100.0, 100.0),
@@ -1319,7 +1459,7 @@ mod tests {
         scene.add_layer(Layer::new(
             LayerId::new("fill"),
             LayerContent::Solid {
-                color: Color::GREEN,
+                color: Color::GREEN.into(),
             },
         ));
         project.add_scene(scene);
@@ -1393,6 +1533,816 @@ mod tests {
         assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
     }
 
+    #[test]
+    fn test_render_to_sink_matches_render_in_order() {
+        let mut project = test_project();
+        project.scenes[0].layers[0]
+            .animations
+            .push(vidra_ir::animation::Animation::from_to(
+                vidra_ir::animation::AnimatableProperty::Opacity,
+                0.0,
+                1.0,
+                vidra_core::Duration::from_seconds(1.0),
+                vidra_core::types::Easing::Linear,
+            ));
+
+        let collected = RenderPipeline::render(&project).unwrap();
+        let mut streamed: Vec<FrameBuffer> = Vec::new();
+        let written = RenderPipeline::render_to_sink(&project, &mut streamed).unwrap();
+
+        assert_eq!(written, collected.frame_count);
+        assert_eq!(hash::hash_frames(&streamed), collected.content_hash());
+    }
+
+    #[test]
+    fn test_render_to_sink_stops_on_sink_error() {
+        struct FailingSink {
+            accepted: u64,
+        }
+        impl FrameSink for FailingSink {
+            fn write_frame(&mut self, _frame: FrameBuffer) -> vidra_core::VidraResult<()> {
+                if self.accepted == 3 {
+                    return Err(vidra_core::VidraError::Encode("disk full".into()));
+                }
+                self.accepted += 1;
+                Ok(())
+            }
+            fn finish(&mut self) -> vidra_core::VidraResult<()> {
+                panic!("finish must not be called after a write error");
+            }
+        }
+
+        let project = test_project();
+        let mut sink = FailingSink { accepted: 0 };
+        let err = RenderPipeline::render_to_sink(&project, &mut sink).unwrap_err();
+        assert!(err.to_string().contains("disk full"));
+        assert_eq!(sink.accepted, 3);
+    }
+
+    #[test]
+    fn test_cpu_backend_renders_without_gpu() {
+        let mut project = test_project();
+        project.scenes[0].layers[1]
+            .effects
+            .push(vidra_core::types::LayerEffect::Grayscale(1.0));
+
+        let cpu = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        assert_eq!(cpu.backend(), crate::gpu::RenderBackend::Cpu);
+        let cpu_frame = cpu.render_frame_index(&project, 0).unwrap();
+        assert_eq!(cpu_frame.width, 320);
+
+        let Ok(gpu) = RenderPipeline::with_backend(crate::gpu::RenderBackend::Gpu) else {
+            return;
+        };
+        let gpu_frame = gpu.render_frame_index(&project, 0).unwrap();
+        let worst = cpu_frame
+            .data
+            .iter()
+            .zip(&gpu_frame.data)
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap();
+        assert!(worst <= crate::cpu::CPU_EFFECT_TOLERANCE);
+    }
+
+    #[test]
+    fn test_plugin_and_shader_transitions() {
+        use vidra_core::plugin::{PluginManifest, TransitionPlugin, VidraPlugin};
+        use vidra_ir::transition::Transition;
+
+        /// Fills the frame with the gray level named by its `level` parameter.
+        struct Flash;
+        impl VidraPlugin for Flash {
+            fn manifest(&self) -> PluginManifest {
+                PluginManifest {
+                    id: "test-flash".into(),
+                    name: "Flash".into(),
+                    version: "0.1.0".into(),
+                    author: "Vidra Tests".into(),
+                    description: "Flashes a gray frame".into(),
+                }
+            }
+        }
+        impl TransitionPlugin for Flash {
+            fn transition_name(&self) -> &str {
+                "flash"
+            }
+            fn apply(
+                &self,
+                _outgoing: &FrameBuffer,
+                _incoming: &FrameBuffer,
+                ctx: &TransitionContext,
+            ) -> Result<FrameBuffer, vidra_core::VidraError> {
+                let level = ctx.params["level"] as f32;
+                let gray = Color::rgba(level, level, level, 1.0);
+                Ok(FrameBuffer::solid(ctx.width, ctx.height, &gray))
+            }
+        }
+
+        let mut project = Project::new(ProjectSettings::custom(16, 16, 10.0));
+        for (id, color) in [("a", Color::BLACK), ("b", Color::WHITE)] {
+            let mut scene = Scene::new(SceneId::new(id), vidra_core::Duration::from_seconds(1.0));
+            scene.add_layer(Layer::new(
+                LayerId::new("bg"),
+                LayerContent::Solid {
+                    color: color.into(),
+                },
+            ));
+            project.add_scene(scene);
+        }
+        let transition = |effect| {
+            Some(Transition {
+                effect,
+                duration: vidra_core::Duration::from_seconds(0.5),
+                easing: vidra_core::types::Easing::Linear,
+            })
+        };
+        project.scenes[1].transition = transition(TransitionType::Plugin {
+            name: "flash".into(),
+            params: [("level".to_string(), 0.2)].into(),
+        });
+
+        // The second scene starts at frame 5; frame 7 is mid-transition.
+        let mut pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let missing = pipeline.render_frame_index(&project, 7).unwrap_err();
+        assert!(missing.to_string().contains("flash"));
+
+        let mut plugins = PluginRegistry::new();
+        plugins.register_transition(Box::new(Flash)).unwrap();
+        pipeline.set_plugins(Arc::new(plugins));
+        let frame = pipeline.render_frame_index(&project, 7).unwrap();
+        assert_eq!(frame.get_pixel(3, 3), Some([51, 51, 51, 255]));
+
+        // Without a GPU a shader transition crossfades.
+        project.scenes[1].transition = transition(TransitionType::Shader {
+            wgsl_source: String::new(),
+            params: Vec::new(),
+        });
+        let frame = pipeline.render_frame_index(&project, 7).unwrap();
+        assert_eq!(frame.get_pixel(3, 3), Some([102, 102, 102, 255]));
+    }
+
+    #[test]
+    fn test_plugin_effects_and_layers() {
+        use vidra_core::plugin::{EffectPlugin, LayerPlugin, PluginManifest, VidraPlugin};
+
+        fn manifest(id: &str) -> PluginManifest {
+            PluginManifest {
+                id: id.into(),
+                name: id.into(),
+                version: "0.1.0".into(),
+                author: "Vidra Tests".into(),
+                description: String::new(),
+            }
+        }
+
+        /// Fills the canvas with the gray level named by its `level` prop.
+        struct Gray;
+        impl VidraPlugin for Gray {
+            fn manifest(&self) -> PluginManifest {
+                manifest("test-gray")
+            }
+        }
+        impl LayerPlugin for Gray {
+            fn layer_type(&self) -> &str {
+                "gray"
+            }
+            fn render(&self, ctx: &LayerContext) -> Result<FrameBuffer, vidra_core::VidraError> {
+                let level: f32 = ctx.params["level"]
+                    .parse()
+                    .map_err(|_| vidra_core::VidraError::Render("level is not a number".into()))?;
+                let gray = Color::rgba(level, level, level, 1.0);
+                Ok(FrameBuffer::solid(ctx.width, ctx.height, &gray))
+            }
+        }
+
+        /// Adds its `amount` parameter to the red channel.
+        struct Redden;
+        impl VidraPlugin for Redden {
+            fn manifest(&self) -> PluginManifest {
+                manifest("test-redden")
+            }
+        }
+        impl EffectPlugin for Redden {
+            fn effect_name(&self) -> &str {
+                "redden"
+            }
+            fn apply(
+                &self,
+                frame: &mut FrameBuffer,
+                ctx: &EffectContext,
+            ) -> Result<(), vidra_core::VidraError> {
+                let amount = ctx.params["amount"] as u8;
+                for y in 0..frame.height {
+                    for x in 0..frame.width {
+                        let [r, g, b, a] = frame.get_pixel(x, y).unwrap();
+                        frame.set_pixel(x, y, [r.saturating_add(amount), g, b, a]);
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        let mut project = Project::new(ProjectSettings::custom(16, 16, 10.0));
+        let mut scene = Scene::new(SceneId::new("a"), vidra_core::Duration::from_seconds(1.0));
+        scene.add_layer(
+            Layer::new(
+                LayerId::new("plugin"),
+                LayerContent::Plugin {
+                    name: "gray".into(),
+                    props: [("level".to_string(), "0.2".to_string())].into(),
+                },
+            )
+            .with_effect(LayerEffect::Plugin {
+                name: "redden".into(),
+                params: [("amount".to_string(), 100.0)].into(),
+            }),
+        );
+        project.add_scene(scene);
+
+        // Missing plugins fail the render rather than leave something out.
+        let mut pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let err = pipeline.render_frame_index(&project, 0).unwrap_err();
+        assert!(
+            err.to_string().contains("no layer plugin named 'gray'"),
+            "{}",
+            err
+        );
+
+        let mut plugins = PluginRegistry::new();
+        plugins.register_layer(Box::new(Gray)).unwrap();
+        pipeline.set_plugins(Arc::new(plugins));
+        let err = pipeline.render_frame_index(&project, 0).unwrap_err();
+        assert!(
+            err.to_string().contains("no effect plugin named 'redden'"),
+            "{}",
+            err
+        );
+
+        let mut plugins = PluginRegistry::new();
+        plugins.register_layer(Box::new(Gray)).unwrap();
+        plugins.register_effect(Box::new(Redden)).unwrap();
+        pipeline.set_plugins(Arc::new(plugins));
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        assert_eq!(frame.get_pixel(3, 3), Some([151, 51, 51, 255]));
+
+        // So do failing ones.
+        let LayerContent::Plugin { props, .. } = &mut project.scenes[0].layers[0].content else {
+            unreachable!();
+        };
+        props.insert("level".to_string(), "bright".to_string());
+        let err = pipeline.render_frame_index(&project, 0).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("layer plugin 'gray' failed: render error: level is not a number"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_render_cache_reuses_static_layers() {
+        let mut project = test_project();
+        // Moving a layer changes the frame, but not the layer's own pixels.
+        project.scenes[0].layers[1]
+            .animations
+            .push(vidra_ir::animation::Animation::from_to(
+                vidra_ir::animation::AnimatableProperty::PositionX,
+                0.0,
+                200.0,
+                vidra_core::Duration::from_seconds(1.0),
+                vidra_core::types::Easing::Linear,
+            ));
+
+        let pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let render_all = || -> Vec<FrameBuffer> {
+            (0..project.total_frames())
+                .map(|i| pipeline.render_frame_index(&project, i).unwrap())
+                .collect()
+        };
+
+        let first = render_all();
+        let stats = pipeline.cache_stats();
+        assert_eq!((stats.layers.hits, stats.layers.misses), (58, 2));
+        assert_eq!((stats.frames.hits, stats.frames.misses), (0, 30));
+
+        let second = render_all();
+        let stats = pipeline.cache_stats();
+        assert_eq!((stats.frames.hits, stats.frames.misses), (30, 30));
+        assert_eq!(hash::hash_frames(&first), hash::hash_frames(&second));
+
+        pipeline.clear_caches();
+        let uncached = render_all();
+        assert_eq!(hash::hash_frames(&first), hash::hash_frames(&uncached));
+        assert_ne!(hash::hash_frame(&first[0]), hash::hash_frame(&first[29]));
+    }
+
+    #[test]
+    fn test_render_cache_tracks_child_blend_modes() {
+        let rect = |w: f64, h: f64, color: Color| LayerContent::Shape {
+            shape: vidra_core::types::ShapeType::Rect {
+                width: w,
+                height: h,
+                corner_radius: 0.0,
+            },
+            fill: Some(color.into()),
+            stroke: None,
+            stroke_width: 0.0,
+        };
+        let mut card = Layer::new(LayerId::new("card"), rect(40.0, 20.0, Color::RED))
+            .with_position(50.0, 50.0);
+        card.add_child(
+            Layer::new(LayerId::new("tint"), rect(20.0, 20.0, Color::GREEN))
+                .with_position(20.0, 10.0),
+        );
+        let mut project = Project::new(ProjectSettings::custom(100, 100, 1.0));
+        project.settings.background = Color::BLACK;
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(1.0));
+        scene.add_layer(card);
+        project.add_scene(scene);
+
+        let pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        assert_eq!(frame.get_pixel(50, 50), Some([0, 255, 0, 255]));
+
+        // Green multiplied into red is black; the cached group must not be reused.
+        project.scenes[0].layers[0].children[0].blend_mode = vidra_core::BlendMode::Multiply;
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        assert_eq!(frame.get_pixel(50, 50), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_children_inherit_parent_transform() {
+        let rect = |w: f64, h: f64, color: Color| LayerContent::Shape {
+            shape: vidra_core::types::ShapeType::Rect {
+                width: w,
+                height: h,
+                corner_radius: 0.0,
+            },
+            fill: Some(color.into()),
+            stroke: None,
+            stroke_width: 0.0,
+        };
+        let mut card = Layer::new(LayerId::new("card"), rect(40.0, 20.0, Color::RED))
+            .with_position(100.0, 100.0)
+            .with_scale(2.0, 2.0);
+        // Centered at (50, 10) in the card's unscaled pixels, just right of the card.
+        card.add_child(
+            Layer::new(LayerId::new("dot"), rect(10.0, 10.0, Color::BLUE))
+                .with_position(50.0, 10.0),
+        );
+        let mut project = Project::new(ProjectSettings::custom(200, 200, 1.0));
+        project.settings.background = Color::BLACK;
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(2.0));
+        scene.add_layer(card);
+        project.add_scene(scene);
+
+        let pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        // The card covers 60..140 × 80..120; the dot is scaled with it and not clipped.
+        assert_eq!(frame.get_pixel(100, 100), Some([255, 0, 0, 255]));
+        assert_eq!(frame.get_pixel(160, 100), Some([0, 0, 255, 255]));
+        let bounds = pipeline.inspect_frame_bounds(&project, 0).unwrap();
+        let rects: Vec<_> = bounds
+            .iter()
+            .map(|b| (b.id.as_str(), b.x, b.y, b.width, b.height))
+            .collect();
+        assert_eq!(
+            rects,
+            vec![("card", 60, 80, 80, 40), ("dot", 150, 90, 20, 20)]
+        );
+
+        // Rotating the card a quarter turn swings the dot below it.
+        project.scenes[0].layers[0].transform.rotation = 90.0;
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        assert_eq!(frame.get_pixel(100, 160), Some([0, 0, 255, 255]));
+        assert_eq!(frame.get_pixel(160, 100), Some([0, 0, 0, 255]));
+        let bounds = pipeline.inspect_frame_bounds(&project, 0).unwrap();
+        assert_eq!((bounds[1].x, bounds[1].y), (90, 150));
+        assert_eq!((bounds[1].width, bounds[1].height), (20, 20));
+    }
+
+    #[test]
+    fn test_layer_styles_draw_outside_the_layer_and_grow_its_bounds() {
+        use vidra_core::types::{LayerEffect, StrokePosition};
+
+        let mut card = Layer::new(
+            LayerId::new("card"),
+            LayerContent::Shape {
+                shape: vidra_core::types::ShapeType::Rect {
+                    width: 40.0,
+                    height: 20.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Color::RED.into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(100.0, 100.0);
+        card.effects = vec![
+            LayerEffect::Stroke {
+                width: 2.0,
+                color: Color::WHITE,
+                position: StrokePosition::Outside,
+            },
+            LayerEffect::DropShadow {
+                offset: vidra_core::Point2D::new(10.0, 0.0),
+                blur: 0.0,
+                color: Color::BLUE,
+                spread: 0.0,
+            },
+        ];
+        let mut project = Project::new(ProjectSettings::custom(200, 200, 1.0));
+        project.settings.background = Color::BLACK;
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(2.0));
+        scene.add_layer(card);
+        project.add_scene(scene);
+
+        let pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        // The card covers 80..120 × 90..110, its stroke 78..122 × 88..112 and the
+        // stroked card's shadow 88..132 × 88..112.
+        assert_eq!(frame.get_pixel(100, 100), Some([255, 0, 0, 255]));
+        assert_eq!(frame.get_pixel(79, 100), Some([255, 255, 255, 255]));
+        assert_eq!(frame.get_pixel(128, 100), Some([0, 0, 255, 255]));
+        assert_eq!(frame.get_pixel(133, 100), Some([0, 0, 0, 255]));
+        let bounds = pipeline.inspect_frame_bounds(&project, 0).unwrap();
+        assert_eq!(
+            (bounds[0].x, bounds[0].y, bounds[0].width, bounds[0].height),
+            (78, 88, 54, 24)
+        );
+
+        // A nested layer's styles are drawn and counted in its bounds too. The dot's
+        // center is at (50, 10) in the card's content, so it covers 125..135 × 95..105
+        // and its glow 122..138 × 92..108.
+        let mut dot = Layer::new(
+            LayerId::new("dot"),
+            LayerContent::Shape {
+                shape: vidra_core::types::ShapeType::Rect {
+                    width: 10.0,
+                    height: 10.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Color::GREEN.into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(50.0, 10.0);
+        dot.effects = vec![LayerEffect::OuterGlow {
+            blur: 0.0,
+            color: Color::WHITE,
+            spread: 3.0,
+        }];
+        project.scenes[0].layers[0].effects.clear();
+        project.scenes[0].layers[0].add_child(dot);
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        assert_eq!(frame.get_pixel(130, 100), Some([0, 255, 0, 255]));
+        assert_eq!(frame.get_pixel(123, 100), Some([255, 255, 255, 255]));
+        assert_eq!(frame.get_pixel(121, 100), Some([0, 0, 0, 255]));
+        let bounds = pipeline.inspect_frame_bounds(&project, 0).unwrap();
+        assert_eq!(bounds[1].id, "dot");
+        assert_eq!(
+            (bounds[1].x, bounds[1].y, bounds[1].width, bounds[1].height),
+            (122, 92, 16, 16)
+        );
+
+        // Styles reach at most a canvas beyond the layer.
+        project.scenes[0].layers[0].effects = vec![LayerEffect::OuterGlow {
+            blur: 1e12,
+            color: Color::WHITE,
+            spread: 1e300,
+        }];
+        pipeline.render_frame_index(&project, 0).unwrap();
+        let bounds = pipeline.inspect_frame_bounds(&project, 0).unwrap();
+        assert_eq!(
+            (bounds[0].x, bounds[0].y, bounds[0].width, bounds[0].height),
+            (-120, -110, 440, 420)
+        );
+    }
+
+    #[test]
+    fn test_track_matte_and_animated_shape_mask() {
+        use vidra_ir::animation::{AnimatableProperty, Animation};
+        use vidra_ir::mask::{MatteMode, ShapeMask};
+
+        let rect = |w: f64, h: f64| vidra_core::types::ShapeType::Rect {
+            width: w,
+            height: h,
+            corner_radius: 0.0,
+        };
+        let mut matte = Layer::new(
+            LayerId::new("matte"),
+            LayerContent::Shape {
+                shape: rect(50.0, 20.0),
+                fill: Some(Color::WHITE.into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(25.0, 10.0);
+        matte.visible = false;
+        let title = Layer::new(
+            LayerId::new("title"),
+            LayerContent::Solid {
+                color: Color::RED.into(),
+            },
+        )
+        .with_matte(LayerId::new("matte"), MatteMode::AlphaInverted);
+        let wipe = Layer::new(
+            LayerId::new("wipe"),
+            LayerContent::Solid {
+                color: Color::BLUE.into(),
+            },
+        )
+        .with_shape_mask(ShapeMask::new(rect(0.0, 20.0), 90.0, 0.0))
+        .with_animation(Animation::from_to(
+            AnimatableProperty::MaskWidth,
+            0.0,
+            20.0,
+            vidra_core::Duration::from_seconds(1.0),
+            vidra_core::types::Easing::Linear,
+        ));
+
+        let mut project = Project::new(ProjectSettings::custom(100, 20, 10.0));
+        project.settings.background = Color::BLACK;
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(2.0));
+        scene.add_layer(matte);
+        scene.add_layer(title);
+        scene.add_layer(wipe);
+        project.add_scene(scene);
+
+        let pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        // The inverted matte hides the title's left half; the wipe has not started.
+        assert_eq!(frame.get_pixel(10, 10), Some([0, 0, 0, 255]));
+        assert_eq!(frame.get_pixel(60, 10), Some([255, 0, 0, 255]));
+        assert_eq!(frame.get_pixel(95, 10), Some([255, 0, 0, 255]));
+
+        let frame = pipeline.render_frame_index(&project, 10).unwrap();
+        assert_eq!(frame.get_pixel(85, 10), Some([255, 0, 0, 255]));
+        assert_eq!(frame.get_pixel(95, 10), Some([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_expand_media_imports_lottie_layers() {
+        let dir = std::env::temp_dir().join(format!("vidra-lottie-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dot.json");
+        std::fs::write(
+            &path,
+            r##"{"fr": 10, "ip": 0, "op": 10, "w": 10, "h": 10, "layers": [
+                {"ty": 1, "nm": "Dot", "ind": 1, "ip": 0, "op": 10, "st": 0,
+                 "ks": {"p": {"a": 0, "k": [2, 2]}, "a": {"a": 0, "k": [0, 0]}},
+                 "sc": "#ff0000", "sw": 4, "sh": 4}
+            ]}"##,
+        )
+        .unwrap();
+
+        let mut project = Project::new(ProjectSettings::custom(10, 10, 10.0));
+        project.settings.background = Color::BLACK;
+        let anim = project.assets.register(vidra_ir::asset::Asset::new(
+            AssetId::new("dot"),
+            vidra_ir::asset::AssetType::Lottie,
+            &path,
+        ));
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(1.0));
+        scene.add_layer(Layer::new(
+            LayerId::new("dot"),
+            LayerContent::Lottie { asset_id: anim },
+        ));
+        project.add_scene(scene);
+
+        let mut pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let report = pipeline.expand_media(&mut project).unwrap();
+        assert_eq!(report.lottie_layers_imported, 1);
+        pipeline.load_assets(&project).unwrap();
+        let layer = &project.scenes[0].layers[0];
+        assert!(matches!(layer.content, LayerContent::Empty));
+        assert_eq!(layer.children.len(), 1);
+        let frame = pipeline.render_frame_index(&project, 0).unwrap();
+        assert_eq!(frame.get_pixel(3, 3), Some([255, 0, 0, 255]));
+        assert_eq!(frame.get_pixel(8, 8), Some([0, 0, 0, 255]));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_custom_shaders_fail_on_the_cpu_backend() {
+        let dir = std::env::temp_dir().join(format!("vidra-cpu-shader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("plasma.wgsl");
+        std::fs::write(&path, "@compute @workgroup_size(16, 16) fn main() {}").unwrap();
+
+        let mut project = Project::new(ProjectSettings::custom(10, 10, 10.0));
+        let shader = project.assets.register(vidra_ir::asset::Asset::new(
+            AssetId::new("plasma"),
+            vidra_ir::asset::AssetType::Shader,
+            &path,
+        ));
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(1.0));
+        scene.add_layer(Layer::new(
+            LayerId::new("plasma"),
+            LayerContent::Shader { asset_id: shader },
+        ));
+        project.add_scene(scene);
+
+        let mut pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        pipeline.load_assets(&project).unwrap();
+        let err = pipeline.render_frame_index(&project, 0).unwrap_err();
+        assert!(err.to_string().contains("need a GPU adapter"), "{}", err);
+
+        project.scenes[0].layers[0] = Layer::new(
+            LayerId::new("bg"),
+            LayerContent::Solid {
+                color: Color::BLUE.into(),
+            },
+        )
+        .with_effect(LayerEffect::CustomShader {
+            wgsl_source: String::new(),
+        });
+        let err = pipeline.render_frame_index(&project, 0).unwrap_err();
+        assert!(err.to_string().contains("need a GPU adapter"), "{}", err);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_undecodable_audio_fails_to_load() {
+        let dir = std::env::temp_dir().join(format!("vidra-audio-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("noise.wav");
+        std::fs::write(&path, b"not audio").unwrap();
+
+        let mut project = Project::new(ProjectSettings::custom(10, 10, 10.0));
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(1.0));
+        let mut anim =
+            vidra_ir::animation::Animation::new(vidra_ir::animation::AnimatableProperty::Opacity);
+        anim.expr = Some("audio_amp".into());
+        anim.expr_duration = Some(vidra_core::Duration::from_seconds(1.0));
+        anim.expr_audio = Some(AssetId::new(path.to_string_lossy()));
+        scene.add_layer(
+            Layer::new(
+                LayerId::new("bg"),
+                LayerContent::Solid {
+                    color: Color::BLUE.into(),
+                },
+            )
+            .with_animation(anim),
+        );
+        project.add_scene(scene);
+
+        let mut pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let err = pipeline.load_assets(&project).unwrap_err();
+        assert!(
+            err.to_string().contains("failed to decode audio"),
+            "{}",
+            err
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_motion_blur_smears_moving_layers_only() {
+        let mut project = Project::new(ProjectSettings::custom(100, 20, 10.0));
+        project.settings.motion_blur = Some(vidra_core::MotionBlur::new(360.0, 4));
+        let mut scene = Scene::new(
+            SceneId::new("test"),
+            vidra_core::Duration::from_seconds(1.0),
+        );
+        scene.add_layer(Layer::new(
+            LayerId::new("bg"),
+            LayerContent::Solid {
+                color: Color::BLUE.into(),
+            },
+        ));
+        // 10 px per frame to the right.
+        let rect = Layer::new(
+            LayerId::new("box"),
+            LayerContent::Shape {
+                shape: vidra_core::types::ShapeType::Rect {
+                    width: 10.0,
+                    height: 10.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Color::WHITE.into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(0.0, 10.0)
+        .with_animation(vidra_ir::animation::Animation::from_to(
+            vidra_ir::animation::AnimatableProperty::PositionX,
+            0.0,
+            100.0,
+            vidra_core::Duration::from_seconds(1.0),
+            vidra_core::types::Easing::Linear,
+        ));
+        scene.add_layer(rect);
+        project.add_scene(scene);
+
+        let pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let blurred = pipeline.render_frame_index(&project, 5).unwrap();
+        // The box sits at x 45–55; samples reach from ~41 to ~58.
+        assert_eq!(blurred.get_pixel(50, 10), Some([255, 255, 255, 255]));
+        let [r, _, b, _] = blurred.get_pixel(42, 10).unwrap();
+        assert!(
+            r > 0 && r < 255 && b == 255,
+            "{:?}",
+            blurred.get_pixel(42, 10)
+        );
+        assert_eq!(blurred.get_pixel(30, 10), Some([0, 0, 255, 255]));
+        // The static background is rendered once; the box samples share one buffer.
+        let stats = pipeline.cache_stats();
+        assert_eq!((stats.layers.hits, stats.layers.misses), (3, 2));
+
+        project.scenes[0].layers[1].motion_blur = Some(vidra_core::MotionBlur::off());
+        let sharp = pipeline.render_frame_index(&project, 5).unwrap();
+        assert_eq!(sharp.get_pixel(42, 10), Some([0, 0, 255, 255]));
+
+        // Sub-frame samples fail the render like the frame itself would.
+        project.scenes[0].layers[1].motion_blur = None;
+        project.scenes[0].layers[1]
+            .effects
+            .push(vidra_core::types::LayerEffect::Plugin {
+                name: "missing".into(),
+                params: Default::default(),
+            });
+        let err = pipeline.render_frame_index(&project, 5).unwrap_err();
+        assert!(
+            err.to_string().contains("no effect plugin named 'missing'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_motion_blur_samples_plugin_layers_at_sub_frame_times() {
+        use vidra_core::plugin::{LayerPlugin, PluginManifest, VidraPlugin};
+
+        /// Fills the canvas with a red level that cycles ten times a second.
+        struct Clock;
+        impl VidraPlugin for Clock {
+            fn manifest(&self) -> PluginManifest {
+                PluginManifest {
+                    id: "test-clock".into(),
+                    name: "test-clock".into(),
+                    version: "0.1.0".into(),
+                    author: "Vidra Tests".into(),
+                    description: String::new(),
+                }
+            }
+        }
+        impl LayerPlugin for Clock {
+            fn layer_type(&self) -> &str {
+                "clock"
+            }
+            fn render(&self, ctx: &LayerContext) -> Result<FrameBuffer, vidra_core::VidraError> {
+                let red = (ctx.time * 10.0).fract() as f32;
+                Ok(FrameBuffer::solid(
+                    ctx.width,
+                    ctx.height,
+                    &Color::rgba(red, 0.0, 0.0, 1.0),
+                ))
+            }
+        }
+
+        let mut project = Project::new(ProjectSettings::custom(8, 8, 10.0));
+        project.settings.motion_blur = Some(vidra_core::MotionBlur::new(360.0, 4));
+        let mut scene = Scene::new(SceneId::new("s"), vidra_core::Duration::from_seconds(1.0));
+        // Only animated layers are blurred; this one stays put and fully opaque.
+        scene.add_layer(
+            Layer::new(
+                LayerId::new("clock"),
+                LayerContent::Plugin {
+                    name: "clock".into(),
+                    props: Default::default(),
+                },
+            )
+            .with_animation(vidra_ir::animation::Animation::from_to(
+                vidra_ir::animation::AnimatableProperty::Opacity,
+                1.0,
+                1.0,
+                vidra_core::Duration::from_seconds(1.0),
+                vidra_core::types::Easing::Linear,
+            )),
+        );
+        project.add_scene(scene);
+
+        let mut pipeline = RenderPipeline::with_backend(crate::gpu::RenderBackend::Cpu).unwrap();
+        let mut plugins = PluginRegistry::new();
+        plugins.register_layer(Box::new(Clock)).unwrap();
+        pipeline.set_plugins(Arc::new(plugins));
+
+        // Every sample is drawn at its own time, not served from the first one's entry.
+        let blurred = pipeline.render_frame_index(&project, 5).unwrap();
+        let stats = pipeline.cache_stats();
+        assert_eq!((stats.layers.hits, stats.layers.misses), (0, 4));
+
+        project.scenes[0].layers[0].motion_blur = Some(vidra_core::MotionBlur::off());
+        let sharp = pipeline.render_frame_index(&project, 5).unwrap();
+        assert_ne!(blurred.get_pixel(4, 4), sharp.get_pixel(4, 4));
+    }
+
     #[test]
     fn test_render_video_layer_fallback() {
         // Video layer with a missing asset should fall back to a cyan frame
@@ -0,0 +1,656 @@
+//! Layer styles: drop shadow, outer glow, inner shadow, stroke and bevel.
+//!
+//! Every style is computed from the layer's alpha, on the CPU for both backends, so GPU
+//! and CPU renders match exactly. Styles that draw outside the layer need room to do
+//! so: the pipeline pads the layer's buffer by [`outset`] before applying its effects.
+
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::types::{LayerEffect, StrokePosition};
+use vidra_core::Color;
+
+/// Distance to a pixel that does not exist; squared distances stay finite below it.
+const FAR: f64 = 1e20;
+
+/// Elevation of the light that shades a bevel.
+const LIGHT_ELEVATION: f32 = 30.0;
+
+/// Pixels a layer's effects draw beyond each side of its buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Outset {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl Outset {
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// At most `x` pixels on the left and right and `y` on the top and bottom.
+    pub fn clamped(self, x: u32, y: u32) -> Self {
+        Self {
+            left: self.left.min(x),
+            top: self.top.min(y),
+            right: self.right.min(x),
+            bottom: self.bottom.min(y),
+        }
+    }
+}
+
+/// The room `effects`, applied in order, need around the layer.
+pub fn outset(effects: &[LayerEffect]) -> Outset {
+    let mut total = Outset::default();
+    let side = |extent: f64| extent.ceil().max(0.0) as u32;
+    for effect in effects {
+        let (left, top, right, bottom) = match effect {
+            LayerEffect::DropShadow {
+                offset,
+                blur,
+                spread,
+                ..
+            } => {
+                let e = spread.max(0.0) + blur_extent(*blur) as f64;
+                (
+                    side(e - offset.x),
+                    side(e - offset.y),
+                    side(e + offset.x),
+                    side(e + offset.y),
+                )
+            }
+            LayerEffect::OuterGlow { blur, spread, .. } => {
+                let e = side(spread.max(0.0) + blur_extent(*blur) as f64);
+                (e, e, e, e)
+            }
+            LayerEffect::Stroke {
+                width, position, ..
+            } => {
+                let e = match position {
+                    StrokePosition::Outside => side(*width),
+                    StrokePosition::Center => side(width / 2.0),
+                    StrokePosition::Inside => 0,
+                };
+                (e, e, e, e)
+            }
+            _ => continue,
+        };
+        total.left = total.left.saturating_add(left);
+        total.top = total.top.saturating_add(top);
+        total.right = total.right.saturating_add(right);
+        total.bottom = total.bottom.saturating_add(bottom);
+    }
+    total
+}
+
+/// Whether `effect` is a layer style, applied by [`apply`].
+pub fn is_style(effect: &LayerEffect) -> bool {
+    matches!(
+        effect,
+        LayerEffect::DropShadow { .. }
+            | LayerEffect::OuterGlow { .. }
+            | LayerEffect::InnerShadow { .. }
+            | LayerEffect::Stroke { .. }
+            | LayerEffect::Bevel { .. }
+    )
+}
+
+/// Apply a layer style. Returns `None` for effects that are not styles. Nothing is drawn
+/// outside `src`, so it should already be padded by [`outset`].
+pub fn apply(src: &FrameBuffer, effect: &LayerEffect) -> Option<FrameBuffer> {
+    if src.format != PixelFormat::Rgba8 || !is_style(effect) {
+        return None;
+    }
+    if src.width == 0 || src.height == 0 {
+        return Some(src.clone());
+    }
+    let (w, h) = (src.width as usize, src.height as usize);
+    let alpha: Vec<f32> = src.data.chunks_exact(4).map(|px| unorm(px[3])).collect();
+
+    let out = match effect {
+        LayerEffect::DropShadow {
+            offset,
+            blur,
+            color,
+            spread,
+        } => {
+            let mut shadow = grow(&alpha, w, h, *spread, false);
+            box_blur(&mut shadow, w, h, *blur, 0.0);
+            let shadow = shift(&shadow, w, h, offset.x, offset.y, 0.0);
+            under(src, color, &shadow)
+        }
+        LayerEffect::OuterGlow {
+            blur,
+            color,
+            spread,
+        } => {
+            let mut glow = grow(&alpha, w, h, *spread, false);
+            box_blur(&mut glow, w, h, *blur, 0.0);
+            under(src, color, &glow)
+        }
+        LayerEffect::InnerShadow {
+            offset,
+            blur,
+            color,
+            spread,
+        } => {
+            // The shadow is cast by everything outside the layer, including what lies
+            // beyond the buffer.
+            let outside: Vec<f32> = alpha.iter().map(|a| 1.0 - a).collect();
+            let mut shadow = grow(&outside, w, h, *spread, true);
+            box_blur(&mut shadow, w, h, *blur, 1.0);
+            let shadow = shift(&shadow, w, h, offset.x, offset.y, 1.0);
+            tint(src, color, &shadow)
+        }
+        LayerEffect::Stroke {
+            width,
+            color,
+            position,
+        } => match position {
+            StrokePosition::Outside => under(src, color, &grow(&alpha, w, h, *width, false)),
+            StrokePosition::Inside => tint(src, color, &edge_band(&alpha, w, h, *width)),
+            StrokePosition::Center => {
+                let half = width / 2.0;
+                let inner = tint(src, color, &edge_band(&alpha, w, h, half));
+                under(&inner, color, &grow(&alpha, w, h, half, false))
+            }
+        },
+        LayerEffect::Bevel {
+            size,
+            depth,
+            angle,
+            highlight,
+            shadow,
+        } => bevel(src, &alpha, *size, *depth, *angle, highlight, shadow),
+        _ => unreachable!("not a layer style"),
+    };
+    Some(out)
+}
+
+fn unorm(v: u8) -> f32 {
+    v as f32 / 255.0
+}
+
+fn from_unorm(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// How far [`box_blur`] with `radius` spreads a pixel.
+fn blur_extent(radius: f64) -> u32 {
+    if radius <= 0.0 {
+        0
+    } else {
+        ((radius / 3.0).ceil() as u32).saturating_mul(3)
+    }
+}
+
+/// Approximate a Gaussian blur reaching `radius` pixels with three box blurs. Pixels
+/// beyond the map read as `outside`.
+fn box_blur(map: &mut [f32], w: usize, h: usize, radius: f64, outside: f32) {
+    let r = blur_extent(radius) as usize / 3;
+    if r == 0 {
+        return;
+    }
+    let mut line = vec![0.0; w.max(h)];
+    let mut blurred = vec![0.0; w.max(h)];
+    for _ in 0..3 {
+        for y in 0..h {
+            let row = &mut map[y * w..(y + 1) * w];
+            box_1d(row, &mut blurred[..w], r, outside);
+            row.copy_from_slice(&blurred[..w]);
+        }
+        for x in 0..w {
+            for y in 0..h {
+                line[y] = map[y * w + x];
+            }
+            box_1d(&line[..h], &mut blurred[..h], r, outside);
+            for y in 0..h {
+                map[y * w + x] = blurred[y];
+            }
+        }
+    }
+}
+
+fn box_1d(src: &[f32], dst: &mut [f32], r: usize, outside: f32) {
+    let n = src.len() as isize;
+    let r = r as isize;
+    let at = |i: isize| {
+        if (0..n).contains(&i) {
+            src[i as usize] as f64
+        } else {
+            outside as f64
+        }
+    };
+    let taps = (2 * r + 1) as f64;
+    // The first window holds `src[..=r]` and pads the rest with `outside`.
+    let inside = (r + 1).min(n);
+    let mut sum = src[..inside as usize]
+        .iter()
+        .map(|&v| v as f64)
+        .sum::<f64>()
+        + (2 * r + 1 - inside) as f64 * outside as f64;
+    for (i, out) in dst.iter_mut().enumerate() {
+        *out = (sum / taps) as f32;
+        let i = i as isize;
+        sum += at(i + r + 1) - at(i - r);
+    }
+}
+
+/// `map` moved by `(dx, dy)` pixels, sampled bilinearly. Pixels beyond the map read as
+/// `outside`.
+fn shift(map: &[f32], w: usize, h: usize, dx: f64, dy: f64, outside: f32) -> Vec<f32> {
+    if dx == 0.0 && dy == 0.0 {
+        return map.to_vec();
+    }
+    let at = |x: i64, y: i64| {
+        if (0..w as i64).contains(&x) && (0..h as i64).contains(&y) {
+            map[y as usize * w + x as usize]
+        } else {
+            outside
+        }
+    };
+    let mut out = vec![0.0; w * h];
+    for y in 0..h {
+        // Anything over a pixel beyond the edge reads as `outside` alike.
+        let sy = (y as f64 - dy).clamp(-1.0, h as f64);
+        let (y0, ty) = (sy.floor(), (sy - sy.floor()) as f32);
+        for x in 0..w {
+            let sx = (x as f64 - dx).clamp(-1.0, w as f64);
+            let (x0, tx) = (sx.floor(), (sx - sx.floor()) as f32);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+            let top = at(x0, y0) * (1.0 - tx) + at(x0 + 1, y0) * tx;
+            let bottom = at(x0, y0 + 1) * (1.0 - tx) + at(x0 + 1, y0 + 1) * tx;
+            out[y * w + x] = top * (1.0 - ty) + bottom * ty;
+        }
+    }
+    out
+}
+
+/// `map` with its covered area grown by `r` pixels. Pixels beyond the map count as
+/// covered when `border` is set.
+fn grow(map: &[f32], w: usize, h: usize, r: f64, border: bool) -> Vec<f32> {
+    if r <= 0.0 {
+        return map.to_vec();
+    }
+    let dist = distance(|i| map[i] >= 0.5, w, h, border);
+    map.iter()
+        .zip(dist)
+        .map(|(&m, d)| m.max((r as f32 + 1.0 - d).clamp(0.0, 1.0)))
+        .collect()
+}
+
+/// The part of `alpha` within `r` pixels of the layer's edge, as a fraction of each
+/// pixel's alpha.
+fn edge_band(alpha: &[f32], w: usize, h: usize, r: f64) -> Vec<f32> {
+    if r <= 0.0 {
+        return vec![0.0; alpha.len()];
+    }
+    let dist = distance(|i| alpha[i] < 0.5, w, h, true);
+    alpha
+        .iter()
+        .zip(dist)
+        .map(|(&a, d)| {
+            if a <= 0.0 {
+                0.0
+            } else {
+                let shrunk = a.min((d - r as f32).clamp(0.0, 1.0));
+                (a - shrunk) / a
+            }
+        })
+        .collect()
+}
+
+/// Euclidean distance from every pixel to the nearest pixel where `set` holds, with
+/// pixels beyond the map counting as set when `border` is.
+fn distance(set: impl Fn(usize) -> bool, w: usize, h: usize, border: bool) -> Vec<f32> {
+    // Work on a grid one pixel larger on each side so the border takes part.
+    let (gw, gh) = (w + 2, h + 2);
+    let mut grid = vec![FAR; gw * gh];
+    for gy in 0..gh {
+        for gx in 0..gw {
+            let inside = (1..=w).contains(&gx) && (1..=h).contains(&gy);
+            let is_set = if inside {
+                set((gy - 1) * w + gx - 1)
+            } else {
+                border
+            };
+            if is_set {
+                grid[gy * gw + gx] = 0.0;
+            }
+        }
+    }
+
+    let n = gw.max(gh);
+    let mut f = vec![0.0; n];
+    let mut d = vec![0.0; n];
+    let mut v = vec![0; n];
+    let mut z = vec![0.0; n + 1];
+    for gx in 0..gw {
+        for gy in 0..gh {
+            f[gy] = grid[gy * gw + gx];
+        }
+        squared_distance_1d(&f[..gh], &mut d[..gh], &mut v, &mut z);
+        for gy in 0..gh {
+            grid[gy * gw + gx] = d[gy];
+        }
+    }
+    for gy in 0..gh {
+        let row = &mut grid[gy * gw..(gy + 1) * gw];
+        squared_distance_1d(row, &mut d[..gw], &mut v, &mut z);
+        row.copy_from_slice(&d[..gw]);
+    }
+
+    let mut out = Vec::with_capacity(w * h);
+    for gy in 1..=h {
+        out.extend((1..=w).map(|gx| grid[gy * gw + gx].sqrt() as f32));
+    }
+    out
+}
+
+/// One pass of the Felzenszwalb–Huttenlocher distance transform: the lower envelope of
+/// the parabolas rooted at `f`.
+fn squared_distance_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let n = f.len();
+    let intersect = |q: usize, p: usize| {
+        ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2.0 * (q - p) as f64)
+    };
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    for q in 1..n {
+        let mut s = intersect(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersect(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+    k = 0;
+    for (q, out) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let p = v[k];
+        *out = (q as f64 - p as f64).powi(2) + f[p];
+    }
+}
+
+/// `src` composited over `color` drawn with `coverage`.
+fn under(src: &FrameBuffer, color: &Color, coverage: &[f32]) -> FrameBuffer {
+    let mut out = src.clone();
+    let rgb = [color.r, color.g, color.b];
+    for (px, &c) in out.data.chunks_exact_mut(4).zip(coverage) {
+        let sa = unorm(px[3]);
+        let da = color.a * c.clamp(0.0, 1.0) * (1.0 - sa);
+        let a = sa + da;
+        if a <= 0.0 {
+            continue;
+        }
+        for (channel, d) in px.iter_mut().zip(rgb) {
+            *channel = from_unorm((unorm(*channel) * sa + d * da) / a);
+        }
+        px[3] = from_unorm(a);
+    }
+    out
+}
+
+/// `src` with its color moved toward `color` by `amount`, keeping its alpha.
+fn tint(src: &FrameBuffer, color: &Color, amount: &[f32]) -> FrameBuffer {
+    let mut out = src.clone();
+    for (px, &t) in out.data.chunks_exact_mut(4).zip(amount) {
+        tint_pixel(px, color, t);
+    }
+    out
+}
+
+fn tint_pixel(px: &mut [u8], color: &Color, amount: f32) {
+    let t = amount.clamp(0.0, 1.0) * color.a;
+    if t <= 0.0 {
+        return;
+    }
+    for (channel, c) in px.iter_mut().zip([color.r, color.g, color.b]) {
+        let v = unorm(*channel);
+        *channel = from_unorm(v + (c - v) * t);
+    }
+}
+
+/// Shade the layer as if its edges sloped up over `size` pixels, lit from `angle`.
+fn bevel(
+    src: &FrameBuffer,
+    alpha: &[f32],
+    size: f64,
+    depth: f64,
+    angle: f64,
+    highlight: &Color,
+    shadow: &Color,
+) -> FrameBuffer {
+    if size <= 0.0 || depth == 0.0 {
+        return src.clone();
+    }
+    let (w, h) = (src.width as usize, src.height as usize);
+    let dist = distance(|i| alpha[i] < 0.5, w, h, true);
+    let (size, depth) = (size as f32, depth as f32);
+    let height: Vec<f32> = dist
+        .iter()
+        .map(|d| ((d - 0.5) / size).clamp(0.0, 1.0) * size * depth)
+        .collect();
+
+    // Image rows grow downward, so the light's y is flipped.
+    let (sin, cos) = (angle as f32).to_radians().sin_cos();
+    let (el_sin, el_cos) = LIGHT_ELEVATION.to_radians().sin_cos();
+    let light = [el_cos * cos, -el_cos * sin, el_sin];
+    let at = |x: usize, y: usize| height[y * w + x];
+
+    let mut out = src.clone();
+    for y in 0..h {
+        for x in 0..w {
+            let i = y * w + x;
+            if alpha[i] <= 0.0 {
+                continue;
+            }
+            let gx = (at((x + 1).min(w - 1), y) - at(x.saturating_sub(1), y)) / 2.0;
+            let gy = (at(x, (y + 1).min(h - 1)) - at(x, y.saturating_sub(1))) / 2.0;
+            let len = (gx * gx + gy * gy + 1.0).sqrt();
+            let lit = (-gx * light[0] - gy * light[1] + light[2]) / len;
+            // A flat surface is left as it is.
+            let shade = ((lit - light[2]) * 2.0).clamp(-1.0, 1.0);
+            let px = &mut out.data[i * 4..i * 4 + 4];
+            if shade > 0.0 {
+                tint_pixel(px, highlight, shade);
+            } else {
+                tint_pixel(px, shadow, -shade);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vidra_core::Point2D;
+
+    /// A `size` square frame with an opaque white square covering `[from, to)`.
+    fn square(size: u32, from: u32, to: u32) -> FrameBuffer {
+        let mut fb = FrameBuffer::new(size, size, PixelFormat::Rgba8);
+        for y in from..to {
+            for x in from..to {
+                fb.set_pixel(x, y, [255, 255, 255, 255]);
+            }
+        }
+        fb
+    }
+
+    fn pixel(fb: &FrameBuffer, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * fb.width + x) * 4) as usize;
+        fb.data[i..i + 4].try_into().unwrap()
+    }
+
+    fn black() -> Color {
+        Color::rgb(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn test_drop_shadow_is_offset_beneath_the_layer() {
+        let src = square(20, 5, 10);
+        let shadow = LayerEffect::DropShadow {
+            offset: Point2D::new(4.0, 4.0),
+            blur: 0.0,
+            color: black(),
+            spread: 0.0,
+        };
+        let out = apply(&src, &shadow).unwrap();
+        assert_eq!(pixel(&out, 6, 6), [255, 255, 255, 255]);
+        assert_eq!(pixel(&out, 12, 12), [0, 0, 0, 255]);
+        assert_eq!(pixel(&out, 15, 15), [0, 0, 0, 0]);
+        assert_eq!(pixel(&out, 2, 2), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_blurred_glow_fades_with_distance() {
+        let src = square(40, 15, 25);
+        let glow = LayerEffect::OuterGlow {
+            blur: 6.0,
+            color: Color::rgb(1.0, 0.0, 0.0),
+            spread: 0.0,
+        };
+        let out = apply(&src, &glow).unwrap();
+        let near = pixel(&out, 26, 20)[3];
+        let far = pixel(&out, 29, 20)[3];
+        assert!(near > far && far > 0, "near {near}, far {far}");
+        assert_eq!(pixel(&out, 20, 20), [255, 255, 255, 255]);
+        assert_eq!(pixel(&out, 0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_stroke_positions() {
+        let src = square(20, 6, 14);
+        let stroke = |position| LayerEffect::Stroke {
+            width: 2.0,
+            color: black(),
+            position,
+        };
+
+        let outside = apply(&src, &stroke(StrokePosition::Outside)).unwrap();
+        assert_eq!(pixel(&outside, 4, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&outside, 3, 10)[3], 0);
+        assert_eq!(pixel(&outside, 6, 10), [255, 255, 255, 255]);
+
+        let inside = apply(&src, &stroke(StrokePosition::Inside)).unwrap();
+        assert_eq!(pixel(&inside, 5, 10)[3], 0);
+        assert_eq!(pixel(&inside, 7, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&inside, 8, 10), [255, 255, 255, 255]);
+
+        let center = apply(&src, &stroke(StrokePosition::Center)).unwrap();
+        assert_eq!(pixel(&center, 5, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&center, 6, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&center, 7, 10), [255, 255, 255, 255]);
+        assert_eq!(pixel(&center, 4, 10)[3], 0);
+    }
+
+    #[test]
+    fn test_inner_shadow_keeps_the_layer_alpha() {
+        let src = square(20, 5, 15);
+        let shadow = LayerEffect::InnerShadow {
+            offset: Point2D::new(2.0, 2.0),
+            blur: 0.0,
+            color: black(),
+            spread: 0.0,
+        };
+        let out = apply(&src, &shadow).unwrap();
+        for (a, b) in src.data.chunks_exact(4).zip(out.data.chunks_exact(4)) {
+            assert_eq!(a[3], b[3]);
+        }
+        // Cast from the top-left edges only.
+        assert_eq!(pixel(&out, 5, 10), [0, 0, 0, 255]);
+        assert_eq!(pixel(&out, 14, 10), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_bevel_lights_the_edges_facing_the_light() {
+        let src = square(30, 5, 25);
+        let bevel = LayerEffect::Bevel {
+            size: 4.0,
+            depth: 1.0,
+            angle: 90.0,
+            highlight: Color::rgb(1.0, 1.0, 0.0),
+            shadow: black(),
+        };
+        let out = apply(&src, &bevel).unwrap();
+        // Lit from above: the top slope turns yellow, the bottom one darkens and the
+        // flat middle is untouched.
+        let top = pixel(&out, 15, 6);
+        let bottom = pixel(&out, 15, 23);
+        assert!(top[2] < 255 && top[0] == 255, "top {top:?}");
+        assert!(bottom[0] < 255, "bottom {bottom:?}");
+        assert_eq!(pixel(&out, 15, 15), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_outset_adds_up_the_styles_reach() {
+        let effects = [
+            LayerEffect::Blur(4.0),
+            LayerEffect::Stroke {
+                width: 2.5,
+                color: black(),
+                position: StrokePosition::Outside,
+            },
+            LayerEffect::DropShadow {
+                offset: Point2D::new(4.0, -2.0),
+                blur: 6.0,
+                color: black(),
+                spread: 1.0,
+            },
+        ];
+        assert_eq!(
+            outset(&effects),
+            Outset {
+                left: 3 + 3,
+                top: 3 + 9,
+                right: 3 + 11,
+                bottom: 3 + 5,
+            }
+        );
+        assert!(outset(&[LayerEffect::Grayscale(1.0)]).is_zero());
+    }
+
+    #[test]
+    fn test_huge_styles_stay_bounded() {
+        let huge = LayerEffect::DropShadow {
+            offset: Point2D::new(1e30, -1e30),
+            blur: 1e30,
+            color: black(),
+            spread: f64::MAX,
+        };
+        let effects = [huge.clone(), huge.clone()];
+        assert_eq!(
+            outset(&effects).clamped(100, 50),
+            Outset {
+                left: 100,
+                top: 50,
+                right: 100,
+                bottom: 50,
+            }
+        );
+
+        let src = square(8, 2, 6);
+        let out = apply(&src, &huge).unwrap();
+        assert_eq!(pixel(&out, 4, 4), [255, 255, 255, 255]);
+        let glow = LayerEffect::OuterGlow {
+            blur: 1e12,
+            color: black(),
+            spread: 0.0,
+        };
+        assert_eq!(apply(&src, &glow).unwrap().data.len(), src.data.len());
+    }
+
+    #[test]
+    fn test_non_styles_are_left_to_other_paths() {
+        let src = square(4, 0, 2);
+        assert!(apply(&src, &LayerEffect::Blur(2.0)).is_none());
+    }
+}
@@ -40,7 +40,9 @@ fn test_conformance_01_solid_color() {
 
     scene.add_layer(Layer::new(
         LayerId::new("bg"),
-        LayerContent::Solid { color: Color::RED },
+        LayerContent::Solid {
+            color: Color::RED.into(),
+        },
     ));
 
     proj.add_scene(scene);
@@ -66,6 +68,8 @@ fn test_conformance_02_text_basic() {
                 font_family: "Inter".into(),
                 font_size: 48.0,
                 color: Color::WHITE,
+                style: Default::default(),
+                animators: Vec::new(),
             },
         )
         .with_position(50.0, 100.0),
@@ -75,7 +79,7 @@ fn test_conformance_02_text_basic() {
     let hash = run_conformance_test(&proj);
     assert_eq!(
         hash,
-        "0ee1a20bb1da2e181859372edd8566cf339f8144269ab6e328d6862cdf30f59f"
+        "f2ac3dfebc1b52ff95533a04e83b62fd6b9130ad4f56037cb2273acb417f6337"
     );
 }
 
@@ -92,6 +96,8 @@ fn test_conformance_03_text_multiline() {
                 font_family: "Inter".into(),
                 font_size: 24.0,
                 color: Color::BLUE,
+                style: Default::default(),
+                animators: Vec::new(),
             },
         )
         .with_position(10.0, 50.0),
@@ -101,7 +107,7 @@ fn test_conformance_03_text_multiline() {
     let hash = run_conformance_test(&proj);
     assert_eq!(
         hash,
-        "aa72688ff62828f6653b35215f5b0e38012102b8cfe5bd31db331211219be86e"
+        "d212efebdcba936a0c7c0b75d80e95c51f621724ef273d99372ea133c5cf51ef"
     );
 }
 
@@ -119,7 +125,7 @@ fn test_conformance_04_shape_rect() {
                     height: 80.0,
                     corner_radius: 0.0,
                 },
-                fill: Some(Color::GREEN),
+                fill: Some(Color::GREEN.into()),
                 stroke: None,
                 stroke_width: 0.0,
             },
@@ -145,7 +151,7 @@ fn test_conformance_05_shape_circle() {
             LayerId::new("circle"),
             LayerContent::Shape {
                 shape: ShapeType::Circle { radius: 50.0 },
-                fill: Some(Color::rgb(1.0, 0.0, 1.0)),
+                fill: Some(Color::rgb(1.0, 0.0, 1.0).into()),
                 stroke: None,
                 stroke_width: 0.0,
             },
@@ -217,7 +223,7 @@ fn test_conformance_08_opacity() {
     scene.add_layer(Layer::new(
         LayerId::new("bg"),
         LayerContent::Solid {
-            color: Color::WHITE,
+            color: Color::WHITE.into(),
         },
     ));
 
@@ -230,7 +236,7 @@ fn test_conformance_08_opacity() {
                     height: 100.0,
                     corner_radius: 0.0,
                 },
-                fill: Some(Color::RED),
+                fill: Some(Color::RED.into()),
                 stroke: None,
                 stroke_width: 0.0,
             },
@@ -255,7 +261,7 @@ fn test_conformance_09_hierarchy() {
     let mut parent = Layer::new(
         LayerId::new("parent"),
         LayerContent::Solid {
-            color: Color::TRANSPARENT,
+            color: Color::TRANSPARENT.into(),
         },
     )
     .with_position(100.0, 100.0);
@@ -264,7 +270,7 @@ fn test_conformance_09_hierarchy() {
         LayerId::new("child"),
         LayerContent::Shape {
             shape: ShapeType::Circle { radius: 20.0 },
-            fill: Some(Color::BLUE),
+            fill: Some(Color::BLUE.into()),
             stroke: None,
             stroke_width: 0.0,
         },
@@ -276,9 +282,11 @@ fn test_conformance_09_hierarchy() {
 
     proj.add_scene(scene);
     let hash = run_conformance_test(&proj);
+    // The child reaches past the parent's top-left corner and is drawn whole, not
+    // clipped to the parent's buffer.
     assert_eq!(
         hash,
-        "e5683a38ab4ee14563c97bd8df7da49d3ba9dd6d47d44fc969a32d2dd8260cd1"
+        "548bed97b6bc4a6a7f706dafb93a725e1af251df41267a3f5c3a828a26a9e3b0"
     );
 }
 
@@ -299,7 +307,7 @@ fn test_conformance_10_animation_position() {
                     height: 20.0,
                     corner_radius: 0.0,
                 },
-                fill: Some(Color::WHITE),
+                fill: Some(Color::WHITE.into()),
                 stroke: None,
                 stroke_width: 0.0,
             },
@@ -328,18 +336,22 @@ fn test_conformance_11_transitions() {
     let mut scene1 = Scene::new(SceneId::new("s1"), Duration::from_seconds(0.5));
     scene1.add_layer(Layer::new(
         LayerId::new("bg1"),
-        LayerContent::Solid { color: Color::RED },
+        LayerContent::Solid {
+            color: Color::RED.into(),
+        },
     ));
     proj.add_scene(scene1);
 
     let mut scene2 = Scene::new(SceneId::new("s2"), Duration::from_seconds(0.5));
     scene2.add_layer(Layer::new(
         LayerId::new("bg2"),
-        LayerContent::Solid { color: Color::BLUE },
+        LayerContent::Solid {
+            color: Color::BLUE.into(),
+        },
     ));
     scene2.transition = Some(vidra_ir::transition::Transition {
         effect: vidra_ir::transition::TransitionType::Wipe {
-            direction: "right".to_string(),
+            direction: vidra_ir::transition::Direction::Right,
         },
         duration: Duration::from_seconds(0.5),
         easing: vidra_core::types::Easing::Linear,
@@ -352,3 +364,242 @@ fn test_conformance_11_transitions() {
         "b1ddd99a4b6c0b195de2de751d04c9e8d02e364b292976726286a7900d884e1a"
     );
 }
+
+#[test]
+fn test_conformance_12_blend_modes() {
+    use vidra_core::BlendMode;
+
+    let mut proj = create_base_project();
+    let mut scene = create_scene();
+
+    scene.add_layer(Layer::new(
+        LayerId::new("bg"),
+        LayerContent::Solid {
+            color: Color::from_hex("#4080C0").unwrap().into(),
+        },
+    ));
+    // One 16px swatch per mode, left to right in declaration order.
+    for (i, mode) in BlendMode::ALL.into_iter().enumerate() {
+        let mut swatch = Layer::new(
+            LayerId::new(format!("swatch_{}", i)),
+            LayerContent::Shape {
+                shape: ShapeType::Rect {
+                    width: 16.0,
+                    height: 16.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Color::from_hex("#FF8000").unwrap().into()),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(i as f64 * 16.0 + 8.0, 8.0);
+        swatch.blend_mode = mode;
+        scene.add_layer(swatch);
+    }
+
+    proj.add_scene(scene);
+    let result = RenderPipeline::render(&proj).expect("render should succeed");
+
+    // #FF8000 over #4080C0.
+    let expected: [[u8; 4]; 17] = [
+        [255, 128, 0, 255],   // normal
+        [64, 64, 0, 255],     // multiply
+        [255, 192, 192, 255], // screen
+        [128, 128, 129, 255], // overlay
+        [255, 255, 192, 255], // add
+        [64, 128, 0, 255],    // darken
+        [255, 128, 192, 255], // lighten
+        [255, 255, 192, 255], // color-dodge
+        [64, 2, 0, 255],      // color-burn
+        [255, 128, 0, 255],   // hard-light
+        [128, 128, 145, 255], // soft-light
+        [191, 0, 192, 255],   // difference
+        [191, 127, 192, 255], // exclusion
+        [168, 104, 40, 255],  // hue
+        [21, 138, 255, 255],  // saturation
+        [194, 98, 0, 255],    // color
+        [100, 164, 228, 255], // luminosity
+    ];
+    let frame = &result.frames[0];
+    for (i, (mode, want)) in BlendMode::ALL.iter().zip(expected).enumerate() {
+        let px = frame.get_pixel(i as u32 * 16 + 8, 8).unwrap();
+        assert_eq!(px, want, "{:?}", mode);
+    }
+    assert_eq!(
+        result.content_hash().to_hex(),
+        "7195a465f19972964dbba098a4153388a7340e7edaa708393399bee1b2e29f62"
+    );
+}
+
+#[test]
+fn test_conformance_13_path_shapes() {
+    use vidra_core::path::{PathData, PathShape};
+
+    let mut proj = create_base_project();
+    let mut scene = create_scene();
+    let path = |d: &str| PathShape::new(PathData::parse_svg(d).unwrap());
+
+    scene.add_layer(Layer::new(
+        LayerId::new("bg"),
+        LayerContent::Solid {
+            color: Color::BLACK.into(),
+        },
+    ));
+    // A stroked outline: the 4px stroke straddles the path, so the box is 44px.
+    scene.add_layer(
+        Layer::new(
+            LayerId::new("outline"),
+            LayerContent::Shape {
+                shape: ShapeType::Path(path("M0 0 H40 V40 H0 Z")),
+                fill: None,
+                stroke: Some(Color::RED.into()),
+                stroke_width: 4.0,
+            },
+        )
+        .with_position(40.0, 40.0),
+    );
+    // A line trimmed to its first half.
+    let mut line = path("M0 0 L100 0");
+    line.trim.end = 0.5;
+    scene.add_layer(
+        Layer::new(
+            LayerId::new("line"),
+            LayerContent::Shape {
+                shape: ShapeType::Path(line),
+                fill: None,
+                stroke: Some(Color::WHITE.into()),
+                stroke_width: 4.0,
+            },
+        )
+        .with_position(200.0, 120.0),
+    );
+
+    proj.add_scene(scene);
+    let result = RenderPipeline::render(&proj).expect("render should succeed");
+    let frame = &result.frames[0];
+    assert_eq!(frame.get_pixel(20, 40), Some([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(40, 40), Some([0, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(170, 120), Some([255, 255, 255, 255]));
+    assert_eq!(frame.get_pixel(230, 120), Some([0, 0, 0, 255]));
+    assert_eq!(
+        result.content_hash().to_hex(),
+        "00e2cd2005b7fd3936f6c167215247cfe0ae1b1559bf3efd328a9af1d191a430"
+    );
+}
+
+#[test]
+fn test_conformance_14_gradients() {
+    use vidra_core::paint::{ColorStop, Gradient};
+    use vidra_core::{Paint, Point2D};
+
+    let mut proj = create_base_project();
+    let mut scene = create_scene();
+    let stops = |a: Color, b: Color| vec![ColorStop::new(0.0, a), ColorStop::new(1.0, b)];
+
+    // Left to right, black to blue, across the whole frame.
+    scene.add_layer(Layer::new(
+        LayerId::new("bg"),
+        LayerContent::Solid {
+            color: Paint::Gradient(Gradient::linear(90.0, stops(Color::BLACK, Color::BLUE))),
+        },
+    ));
+    // White at the center of the circle fading to red at its box corners.
+    scene.add_layer(
+        Layer::new(
+            LayerId::new("badge"),
+            LayerContent::Shape {
+                shape: ShapeType::Circle { radius: 40.0 },
+                fill: Some(Paint::Gradient(Gradient::radial(
+                    Point2D::new(0.5, 0.5),
+                    1.0,
+                    stops(Color::WHITE, Color::RED),
+                ))),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(160.0, 120.0),
+    );
+    // A conic sweep, red at 12 o'clock through green.
+    scene.add_layer(
+        Layer::new(
+            LayerId::new("dial"),
+            LayerContent::Shape {
+                shape: ShapeType::Rect {
+                    width: 40.0,
+                    height: 40.0,
+                    corner_radius: 0.0,
+                },
+                fill: Some(Paint::Gradient(Gradient::conic(
+                    0.0,
+                    Point2D::new(0.5, 0.5),
+                    stops(Color::RED, Color::GREEN),
+                ))),
+                stroke: None,
+                stroke_width: 0.0,
+            },
+        )
+        .with_position(40.0, 40.0),
+    );
+
+    proj.add_scene(scene);
+    let result = RenderPipeline::render(&proj).expect("render should succeed");
+    let frame = &result.frames[0];
+    assert_eq!(frame.get_pixel(0, 0), Some([0, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(319, 239), Some([0, 0, 254, 255]));
+    // Pixel centers sit half a pixel off the circle's center.
+    assert_eq!(frame.get_pixel(160, 120), Some([255, 251, 251, 255]));
+    // Just right of 12 o'clock the sweep has barely started.
+    assert_eq!(frame.get_pixel(40, 21), Some([253, 1, 0, 255]));
+    assert_eq!(
+        result.content_hash().to_hex(),
+        "f906f12eeca1c6c8f2f38adb294fac26af018cead29630620b3e8fb14ba6bb5c"
+    );
+}
+
+#[test]
+fn test_conformance_15_svg() {
+    let mut proj = create_base_project();
+    proj.assets.register(Asset::new(
+        AssetId::new("badge"),
+        AssetType::Svg,
+        "tests/assets/badge.svg",
+    ));
+    let mut scene = create_scene();
+    let mut badge = Layer::new(
+        LayerId::new("badge"),
+        LayerContent::Svg {
+            asset_id: AssetId::new("badge"),
+            element: None,
+            exclude: Vec::new(),
+            expand_groups: true,
+        },
+    )
+    .with_position(160.0, 120.0)
+    .with_scale(4.0, 4.0);
+    let doc = vidra_render::image_loader::load_svg(std::path::Path::new("tests/assets/badge.svg"))
+        .unwrap();
+    // The badge and both of its groups.
+    assert_eq!(doc.expand_groups(&mut badge), 3);
+    scene.add_layer(badge);
+    proj.add_scene(scene);
+
+    let result = RenderPipeline::render(&proj).expect("render should succeed");
+    let frame = &result.frames[0];
+    // The 160x80 badge spans x 80..240 and y 80..160.
+    assert_eq!(frame.get_pixel(100, 100), Some([255, 0, 0, 255]));
+    assert_eq!(frame.get_pixel(200, 120), Some([0, 0, 255, 255]));
+    assert_eq!(frame.get_pixel(160, 120), Some([0, 0, 0, 255]));
+    // The expanded groups are drawn at the parent's scale: the circle's edge is at
+    // most one pixel wide rather than four.
+    let soft = (220..240)
+        .filter_map(|x| frame.get_pixel(x, 120))
+        .filter(|p| p[2] > 0 && p[2] < 255)
+        .count();
+    assert!(soft <= 1, "{} partially covered pixels", soft);
+    assert_eq!(
+        result.content_hash().to_hex(),
+        "87b5d6b38c5218d17162c55a2868b4de282225fe04879e3af9f8ee73dacb269b"
+    );
+}
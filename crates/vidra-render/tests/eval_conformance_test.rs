@@ -0,0 +1,70 @@
+//! The native pipeline and the WASM renderer must agree on the evaluated state of every
+//! frame of every example project; only their rasterizers are allowed to differ.
+
+use std::path::Path;
+
+use vidra_ir::project::Project;
+use vidra_render::{RenderBackend, RenderPipeline};
+use vidra_wasm::WasmRenderer;
+
+/// Examples written against syntax the parser does not accept yet (unquoted
+/// `viewport: 1920x1080` sizes). Any other example must compile.
+const UNPARSEABLE_EXAMPLES: &[&str] = &["web_interactive.vidra", "web_react.vidra"];
+
+fn compile_example(path: &Path) -> Result<Project, String> {
+    let source = std::fs::read_to_string(path).expect("example should be readable");
+    let name = path.display().to_string();
+    let tokens = vidra_lang::Lexer::new(&source)
+        .tokenize()
+        .map_err(|e| format!("lex error: {}", e))?;
+    let ast = vidra_lang::Parser::new(tokens, &name)
+        .parse()
+        .map_err(|e| format!("parse error: {}", e))?;
+    vidra_lang::Compiler::compile(&ast).map_err(|e| format!("compile error: {}", e))
+}
+
+#[test]
+fn test_examples_layer_state_matches_wasm() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut paths: Vec<_> = std::fs::read_dir(&examples)
+        .expect("examples directory should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "vidra"))
+        .collect();
+    paths.sort();
+    assert!(
+        !paths.is_empty(),
+        "no examples found in {}",
+        examples.display()
+    );
+
+    let pipeline = RenderPipeline::with_backend(RenderBackend::Cpu).unwrap();
+    let wasm = WasmRenderer::new();
+
+    let mut checked = 0;
+    for path in &paths {
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        let project = match compile_example(path) {
+            Ok(project) => project,
+            Err(_) if UNPARSEABLE_EXAMPLES.contains(&file_name.as_ref()) => continue,
+            Err(e) => panic!("{}: {}", path.display(), e),
+        };
+        let total_frames = project.total_frames();
+        assert!(total_frames > 0, "{} has no frames", path.display());
+
+        // One frame past the end checks that both agree the project is over.
+        for frame in 0..=total_frames {
+            let native = pipeline.evaluate_frame(&project, frame);
+            let web = wasm.evaluate_frame(&project, frame);
+            assert_eq!(
+                native,
+                web,
+                "{}: layer state differs at frame {}",
+                path.display(),
+                frame
+            );
+        }
+        checked += 1;
+    }
+    assert!(checked > 0, "no example could be compiled");
+}
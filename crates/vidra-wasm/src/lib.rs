@@ -5,7 +5,7 @@
 
 mod renderer;
 
-use renderer::WasmRenderer;
+pub use renderer::WasmRenderer;
 use wasm_bindgen::prelude::*;
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -82,6 +82,16 @@ pub fn get_project_info(ir_json: &str) -> Result<String, JsValue> {
     Ok(serde_json::to_string(&info).unwrap_or_default())
 }
 
+/// Parse IR JSON for rendering, expanding its SVG group and Lottie layers with the
+/// loaded documents.
+fn load_project(ir_json: &str) -> Result<vidra_ir::project::Project, JsValue> {
+    let mut project: vidra_ir::project::Project = serde_json::from_str(ir_json)
+        .map_err(|e| JsValue::from_str(&format!("JSON parse error: {}", e)))?;
+    with_renderer(|r| r.expand_media(&mut project))
+        .map_err(|e| JsValue::from_str(&format!("Media error: {}", e)))?;
+    Ok(project)
+}
+
 /// Load an image asset (as raw bytes) into the renderer cache.
 ///
 /// Call this before rendering frames that reference the asset.
@@ -92,6 +102,37 @@ pub fn load_image_asset(asset_id: &str, data: &[u8]) {
     });
 }
 
+/// Load a Lottie animation (its JSON text) for `lottie(...)` layers using `asset_id`.
+///
+/// Embedded images come with it; images the animation references by path are loaded
+/// with `load_image_asset` under `<asset_id>/<image id>`. Throws if the JSON is not a
+/// Lottie animation.
+#[wasm_bindgen]
+pub fn load_lottie_asset(asset_id: &str, json: &str) -> Result<(), JsValue> {
+    with_renderer(|r| r.load_lottie_json(asset_id, json))
+        .map_err(|e| JsValue::from_str(&format!("Lottie error: {}", e)))
+}
+
+/// Load a font file (as raw bytes) for text layers whose font family is `name`.
+///
+/// Returns false if the bytes are not a font. Text in an unknown family falls back to
+/// the embedded Inter.
+#[wasm_bindgen]
+pub fn load_font_asset(name: &str, data: &[u8]) -> bool {
+    with_renderer(|r| r.load_font_bytes(name, data))
+}
+
+/// Load decoded audio for the audio-reactive expressions that read `asset_id` (the
+/// asset id, or the path named in the script).
+///
+/// `samples` are mono PCM in -1–1 at `sample_rate`, e.g. a downmix of what Web Audio's
+/// `decodeAudioData` returns, and `fps` is the project's frame rate. Until its audio is
+/// loaded, an expression's audio variables read 0.
+#[wasm_bindgen]
+pub fn load_audio_samples(asset_id: &str, samples: &[f32], sample_rate: u32, fps: f64) {
+    with_renderer(|r| r.load_audio_samples(asset_id, samples, sample_rate, fps));
+}
+
 /// Update the current mouse position (in pixel coordinates) for interactive previews.
 ///
 /// Note: this currently does not affect rendering output yet; it is exposed as
@@ -137,8 +178,7 @@ pub fn get_state_var(name: &str) -> JsValue {
 /// Returns a JSON string: { handled: bool, layerId?: string }
 #[wasm_bindgen]
 pub fn dispatch_click(ir_json: &str, frame_index: u32, x: f64, y: f64) -> Result<String, JsValue> {
-    let project: vidra_ir::project::Project = serde_json::from_str(ir_json)
-        .map_err(|e| JsValue::from_str(&format!("JSON parse error: {}", e)))?;
+    let project = load_project(ir_json)?;
 
     let hit = with_renderer(|r| r.dispatch_click(&project, frame_index as u64, x, y));
     let out = serde_json::json!({
@@ -153,8 +193,7 @@ pub fn dispatch_click(ir_json: &str, frame_index: u32, x: f64, y: f64) -> Result
 /// Returns a `Vec<u8>` of length `width * height * 4`.
 #[wasm_bindgen]
 pub fn render_frame(ir_json: &str, frame_index: u32) -> Result<Vec<u8>, JsValue> {
-    let project: vidra_ir::project::Project = serde_json::from_str(ir_json)
-        .map_err(|e| JsValue::from_str(&format!("JSON parse error: {}", e)))?;
+    let project = load_project(ir_json)?;
 
     let fb = with_renderer(|r| r.render_frame(&project, frame_index as u64));
 
@@ -165,8 +204,7 @@ pub fn render_frame(ir_json: &str, frame_index: u32) -> Result<Vec<u8>, JsValue>
 /// Returns a JSON string representing an array of { id, source, x, y, width, height, opacity, scaleX, scaleY }.
 #[wasm_bindgen]
 pub fn get_web_layers_state(ir_json: &str, frame_index: u32) -> Result<String, JsValue> {
-    let project: vidra_ir::project::Project = serde_json::from_str(ir_json)
-        .map_err(|e| JsValue::from_str(&format!("JSON parse error: {}", e)))?;
+    let project = load_project(ir_json)?;
 
     let json = with_renderer(|r| r.get_web_layers_state(&project, frame_index as u64));
 
@@ -372,6 +410,8 @@ fn apply_caption_segments(
                 font_family: font_family.to_string(),
                 font_size,
                 color,
+                style: Default::default(),
+                animators: Vec::new(),
             },
         );
 
@@ -0,0 +1,60 @@
+//! A sample layer plugin: a checkerboard of `color` and transparent squares.
+//!
+//! Build it with `cargo build -p vidra-plugin-checkerboard`, install the library with
+//! `vidra plugins install checkerboard --path target/debug/libvidra_plugin_checkerboard.so`
+//! and draw it with `plugin(checkerboard, size: 40, color: #FF0000)`.
+
+use vidra_core::frame::{FrameBuffer, PixelFormat};
+use vidra_core::plugin::{LayerContext, LayerPlugin, PluginManifest, VidraPlugin};
+use vidra_core::{Color, VidraError};
+
+struct Checkerboard;
+
+impl VidraPlugin for Checkerboard {
+    fn manifest(&self) -> PluginManifest {
+        PluginManifest {
+            id: "vidra-plugin-checkerboard".to_string(),
+            name: "Checkerboard".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            author: "Vidra Team".to_string(),
+            description: "A checkerboard of colored and transparent squares".to_string(),
+        }
+    }
+
+    fn params(&self) -> &[&str] {
+        &["size", "color"]
+    }
+}
+
+impl LayerPlugin for Checkerboard {
+    fn layer_type(&self) -> &str {
+        "checkerboard"
+    }
+
+    fn render(&self, ctx: &LayerContext) -> Result<FrameBuffer, VidraError> {
+        let size = match ctx.params.get("size") {
+            Some(size) => size
+                .parse::<f64>()
+                .map_err(|_| VidraError::InvalidArgument(format!("size '{}'", size)))?
+                .max(1.0) as u32,
+            None => 32,
+        };
+        let color = match ctx.params.get("color") {
+            Some(hex) => Color::from_hex(hex)
+                .map_err(|e| VidraError::InvalidArgument(format!("color: {}", e)))?,
+            None => Color::WHITE,
+        };
+        let pixel = color.to_rgba8();
+        let mut frame = FrameBuffer::new(ctx.width, ctx.height, PixelFormat::Rgba8);
+        for y in 0..ctx.height {
+            for x in 0..ctx.width {
+                if (x / size + y / size) % 2 == 0 {
+                    frame.set_pixel(x, y, pixel);
+                }
+            }
+        }
+        Ok(frame)
+    }
+}
+
+vidra_core::export_plugin!(layer, Checkerboard);
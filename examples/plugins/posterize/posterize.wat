@@ -0,0 +1,60 @@
+;; A sample WebAssembly effect plugin: posterizes a layer to `levels` steps per channel.
+;;
+;; Compile it with `wat2wasm posterize.wat` (from WABT), install it with
+;; `vidra plugins install posterize --path posterize.wasm` and apply it with
+;; `effect(posterize, levels: 4)`.
+(module
+  (import "vidra" "param" (func $param (param i32 i32) (result f64)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "{\"abi_version\":1,\"kind\":\"effect\",\"name\":\"posterize\",\"params\":[\"levels\"],\"id\":\"vidra-plugin-posterize\",\"display_name\":\"Posterize\",\"version\":\"0.1.0\",\"author\":\"Vidra Team\",\"description\":\"Reduces each channel to a few levels\"}\00")
+  (data (i32.const 512) "levels")
+
+  (func (export "vidra_plugin_info") (result i32)
+    (i32.const 0))
+
+  ;; The frame goes at 1024, after the data above.
+  (func (export "vidra_alloc") (param $len i32) (result i32)
+    (local $pages i32)
+    (local.set $pages
+      (i32.sub
+        (i32.div_u (i32.add (local.get $len) (i32.const 66559)) (i32.const 65536))
+        (memory.size)))
+    (if (i32.gt_s (local.get $pages) (i32.const 0))
+      (then
+        (if (i32.eq (memory.grow (local.get $pages)) (i32.const -1))
+          (then (unreachable)))))
+    (i32.const 1024))
+
+  (func (export "vidra_apply_effect")
+    (param $ptr i32) (param $width i32) (param $height i32) (param $time f64) (param $fps f64)
+    (result i32)
+    (local $levels i32) (local $i i32) (local $end i32) (local $step i32)
+    ;; NaN (not given) truncates to 0.
+    (local.set $levels (i32.trunc_sat_f64_u (call $param (i32.const 512) (i32.const 6))))
+    (if (i32.eqz (local.get $levels))
+      (then (local.set $levels (i32.const 4))))
+    (if (i32.lt_u (local.get $levels) (i32.const 2))
+      (then (local.set $levels (i32.const 2))))
+    (if (i32.gt_u (local.get $levels) (i32.const 256))
+      (then (local.set $levels (i32.const 256))))
+    (local.set $end
+      (i32.add (local.get $ptr)
+        (i32.mul (i32.mul (local.get $width) (local.get $height)) (i32.const 4))))
+    (local.set $i (local.get $ptr))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $end)))
+        ;; Every byte but the fourth, alpha.
+        (if (i32.ne (i32.and (i32.sub (local.get $i) (local.get $ptr)) (i32.const 3)) (i32.const 3))
+          (then
+            (local.set $step
+              (i32.div_u
+                (i32.mul (i32.load8_u (local.get $i)) (local.get $levels))
+                (i32.const 256)))
+            (i32.store8 (local.get $i)
+              (i32.div_u
+                (i32.mul (local.get $step) (i32.const 255))
+                (i32.sub (local.get $levels) (i32.const 1))))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.const 0)))
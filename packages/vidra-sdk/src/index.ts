@@ -24,9 +24,13 @@ import type {
     Animation,
     AnimatableProperty,
     Easing as EasingType,
+    StepPosition,
     Color,
+    Paint,
     AssetType,
     LayerEffect,
+    TextSpan,
+    TextStyle,
 } from "./types.js";
 
 export type * from "./types.js";
@@ -64,6 +68,58 @@ function colorToHex(c: Color): string {
     return `#${r}${g}${b}`;
 }
 
+function paintToScript(p: Paint): string {
+    if (!("kind" in p)) return colorToHex(p);
+    const stops = p.stops.map((s) => `[${colorToHex(s.color)}, ${s.offset}]`).join(", ");
+    const k = p.kind;
+    if ("Linear" in k) return `linearGradient(${k.Linear.angle}, ${stops})`;
+    if ("Radial" in k) return `radialGradient(${k.Radial.center.x}, ${k.Radial.center.y}, ${k.Radial.radius}, ${stops})`;
+    return `conicGradient(${k.Conic.angle}, ${k.Conic.center.x}, ${k.Conic.center.y}, ${stops})`;
+}
+
+function colorToHexAlpha(c: Color): string {
+    return c.a === 255 ? colorToHex(c) : colorToHex(c) + c.a.toString(16).padStart(2, "0");
+}
+
+/** Re-insert a text layer's spans as VidraScript inline tags. */
+function textToMarkup(text: string, spans: TextSpan[]): string {
+    const chars = Array.from(text);
+    const opens: string[][] = Array.from({ length: chars.length + 1 }, () => []);
+    const closes: string[][] = Array.from({ length: chars.length + 1 }, () => []);
+    for (const span of spans) {
+        const tags: [string, string][] = [];
+        if (span.bold) tags.push(["<b>", "</b>"]);
+        if (span.color) tags.push([`<color=${colorToHexAlpha(span.color)}>`, "</color>"]);
+        if (span.size !== undefined) tags.push([`<size=${span.size}>`, "</size>"]);
+        if (span.font_family) tags.push([`<font=${span.font_family}>`, "</font>"]);
+        for (const [open, close] of tags) {
+            opens[span.start].push(open);
+            closes[span.end].unshift(close);
+        }
+    }
+    let out = "";
+    for (let i = 0; i <= chars.length; i++) {
+        out += closes[i].join("") + opens[i].join("") + (chars[i] ?? "");
+    }
+    return out;
+}
+
+function textStyleArgs(style: TextStyle | undefined): string {
+    if (!style) return "";
+    const args: string[] = [];
+    if (style.max_width !== undefined) args.push(`maxWidth: ${style.max_width}`);
+    if (style.align && style.align !== "left") args.push(`align: ${style.align}`);
+    if (style.letter_spacing) args.push(`letterSpacing: ${style.letter_spacing}`);
+    if (style.line_height !== undefined) args.push(`lineHeight: ${style.line_height}`);
+    if (style.bold) args.push("bold: true");
+    if (style.stroke) args.push(`stroke: ${colorToHexAlpha(style.stroke.color)}`, `strokeWidth: ${style.stroke.width}`);
+    if (style.shadow) {
+        const s = style.shadow;
+        args.push(`shadow: ${colorToHexAlpha(s.color)}`, `shadowX: ${s.offset_x ?? 0}`, `shadowY: ${s.offset_y ?? 0}`, `shadowBlur: ${s.blur ?? 0}`);
+    }
+    return args.map((a) => `, ${a}`).join("");
+}
+
 // ─── UUID ───────────────────────────────────────────────────────────
 
 function uuidv4(): string {
@@ -85,9 +141,41 @@ export const Easing = {
     CubicIn: "CubicIn" as EasingType,
     CubicOut: "CubicOut" as EasingType,
     CubicInOut: "CubicInOut" as EasingType,
-    Step: "Step" as EasingType,
+    EaseInQuart: "EaseInQuart" as EasingType,
+    EaseOutQuart: "EaseOutQuart" as EasingType,
+    EaseInOutQuart: "EaseInOutQuart" as EasingType,
+    EaseInQuint: "EaseInQuint" as EasingType,
+    EaseOutQuint: "EaseOutQuint" as EasingType,
+    EaseInOutQuint: "EaseInOutQuint" as EasingType,
+    EaseInSine: "EaseInSine" as EasingType,
+    EaseOutSine: "EaseOutSine" as EasingType,
+    EaseInOutSine: "EaseInOutSine" as EasingType,
+    EaseInExpo: "EaseInExpo" as EasingType,
+    EaseOutExpo: "EaseOutExpo" as EasingType,
+    EaseInOutExpo: "EaseInOutExpo" as EasingType,
+    EaseInCirc: "EaseInCirc" as EasingType,
+    EaseOutCirc: "EaseOutCirc" as EasingType,
+    EaseInOutCirc: "EaseInOutCirc" as EasingType,
+    EaseInBack: "EaseInBack" as EasingType,
+    EaseOutBack: "EaseOutBack" as EasingType,
+    EaseInOutBack: "EaseInOutBack" as EasingType,
+    EaseInElastic: "EaseInElastic" as EasingType,
+    EaseOutElastic: "EaseOutElastic" as EasingType,
+    EaseInOutElastic: "EaseInOutElastic" as EasingType,
+    EaseInBounce: "EaseInBounce" as EasingType,
+    EaseOutBounce: "EaseOutBounce" as EasingType,
+    EaseInOutBounce: "EaseInOutBounce" as EasingType,
+    cubicBezier: (x1: number, y1: number, x2: number, y2: number): EasingType => ({ CubicBezier: [x1, y1, x2, y2] }),
+    steps: (n: number, position: StepPosition = "JumpEnd"): EasingType => ({ Steps: [n, position] }),
 } as const;
 
+function easingToVidraScript(e: EasingType): string {
+    if (typeof e === "string") return e.charAt(0).toLowerCase() + e.slice(1);
+    if ("CubicBezier" in e) return `cubicBezier(${e.CubicBezier.join(", ")})`;
+    const [n, position] = e.Steps;
+    return `steps(${n}, ${position.charAt(0).toLowerCase() + position.slice(1)})`;
+}
+
 // ─── Layer Builder ──────────────────────────────────────────────────
 
 export class Layer {
@@ -114,9 +202,9 @@ export class Layer {
 
     // ── Content setters ───────────────────────────────────────────
 
-    text(content: string, fontFamily: string = "Inter", fontSize: number = 48, color: string | Color = "#ffffff"): this {
+    text(content: string, fontFamily: string = "Inter", fontSize: number = 48, color: string | Color = "#ffffff", style?: TextStyle): this {
         const c = typeof color === "string" ? hex(color) : color;
-        this._layer.content = { Text: { text: content, font_family: fontFamily, font_size: fontSize, color: c } };
+        this._layer.content = { Text: { text: content, font_family: fontFamily, font_size: fontSize, color: c, ...(style ? { style } : {}) } };
         return this;
     }
 
@@ -131,6 +219,11 @@ export class Layer {
         return this;
     }
 
+    lottie(assetId: string): this {
+        this._layer.content = { Lottie: { asset_id: assetId } };
+        return this;
+    }
+
     video(assetId: string, trimStart: number = 0, trimEnd?: number): this {
         this._layer.content = {
             Video: {
@@ -241,7 +334,7 @@ export class Layer {
             ScaleX: "ScaleX", ScaleY: "ScaleY",
             Rotation: "Rotation", Opacity: "Opacity",
         };
-        const mapped = propMap[property] ?? property as AnimatableProperty;
+        const mapped = typeof property === "string" ? propMap[property] ?? property as AnimatableProperty : property;
 
         this._layer.animations.push({
             property: mapped,
@@ -388,12 +481,19 @@ export class Project {
         if (content === "Empty") {
             // no content line
         } else if ("Solid" in content) {
-            lines.push(`${pad}    solid(${colorToHex(content.Solid.color)})`);
+            lines.push(`${pad}    solid(${paintToScript(content.Solid.color)})`);
         } else if ("Text" in content) {
             const t = content.Text;
-            lines.push(`${pad}    text("${t.text}", font: "${t.font_family}", size: ${t.font_size}, color: ${colorToHex(t.color)})`);
+            lines.push(`${pad}    text("${textToMarkup(t.text, t.style?.spans ?? [])}", font: "${t.font_family}", size: ${t.font_size}, color: ${colorToHex(t.color)}${textStyleArgs(t.style)})`);
         } else if ("Image" in content) {
             lines.push(`${pad}    image("${content.Image.asset_id}")`);
+        } else if ("Svg" in content) {
+            const svg = content.Svg;
+            const element = svg.element ? `, element: "${svg.element}"` : "";
+            const groups = svg.expand_groups ? ", groups: true" : "";
+            lines.push(`${pad}    image("${svg.asset_id}"${element}${groups})`);
+        } else if ("Lottie" in content) {
+            lines.push(`${pad}    lottie("${content.Lottie.asset_id}")`);
         } else if ("Video" in content) {
             lines.push(`${pad}    video("${content.Video.asset_id}")`);
         } else if ("Audio" in content) {
@@ -436,14 +536,27 @@ export class Project {
             if (anim.keyframes.length >= 2) {
                 const from = anim.keyframes[0]!;
                 const to = anim.keyframes[anim.keyframes.length - 1]!;
-                const easingStr = from.easing.charAt(0).toLowerCase() + from.easing.slice(1);
+                const easingStr = from.hold ? "hold" : easingToVidraScript(from.easing);
                 const propMap: Record<string, string> = {
                     PositionX: "positionX", PositionY: "positionY",
                     ScaleX: "scaleX", ScaleY: "scaleY",
                     Rotation: "rotation", Opacity: "opacity",
+                    GradientAngle: "gradientAngle",
+                    Position: "position", Scale: "scale", Color: "color",
+                };
+                const prop = typeof anim.property === "string"
+                    ? propMap[anim.property] ?? anim.property
+                    : `stop${anim.property.GradientStop}`;
+                const value = (k: typeof from): string => {
+                    if (!k.vector) return `${k.value}`;
+                    if (anim.property !== "Color") return `[${k.vector.join(", ")}]`;
+                    const [r = 0, g = 0, b = 0] = k.vector.map((c) => Math.round(c * 255));
+                    return colorToHex({ r, g, b, a: 255 });
                 };
-                const prop = propMap[anim.property] ?? anim.property;
-                lines.push(`${pad}    animation(${prop}, from: ${from.value}, to: ${to.value}, duration: ${to.time.seconds}s, easing: ${easingStr})`);
+                const spaces = { Srgb: "srgb", LinearRgb: "linear", Oklab: "oklab" };
+                // VidraScript colors without `space:` blend in sRGB; the IR default is OKLab.
+                const space = anim.property === "Color" ? `, space: ${spaces[anim.color_space ?? "Oklab"]}` : "";
+                lines.push(`${pad}    animation(${prop}, from: ${value(from)}, to: ${value(to)}, duration: ${to.time.seconds}s, easing: ${easingStr}${space})`);
             }
         }
 
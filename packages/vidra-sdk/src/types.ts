@@ -28,9 +28,19 @@ export type BlendMode =
     | "Overlay"
     | "Darken"
     | "Lighten"
-    | "Add";
+    | "Add"
+    | "ColorDodge"
+    | "ColorBurn"
+    | "HardLight"
+    | "SoftLight"
+    | "Difference"
+    | "Exclusion"
+    | "Hue"
+    | "Saturation"
+    | "Color"
+    | "Luminosity";
 
-export type AssetType = "Image" | "Video" | "Audio" | "Font";
+export type AssetType = "Image" | "Svg" | "Lottie" | "Video" | "Audio" | "Font";
 
 export type AssetId = string;
 
@@ -50,7 +60,42 @@ export type LayerId = string;
 export type ShapeType =
     | { Rect: { width: number; height: number; radius: number } }
     | { Circle: { radius: number } }
-    | { Ellipse: { rx: number; ry: number } };
+    | { Ellipse: { rx: number; ry: number } }
+    | { Path: PathShape };
+
+export type PathSegment =
+    | { MoveTo: { x: number; y: number } }
+    | { LineTo: { x: number; y: number } }
+    | { QuadTo: { x1: number; y1: number; x: number; y: number } }
+    | { CubicTo: { x1: number; y1: number; x2: number; y2: number; x: number; y: number } }
+    | "Close";
+
+export interface PathShape {
+    /** Segments, or SVG path data when written by hand. */
+    data: PathSegment[] | string;
+    fill_rule: "non-zero" | "even-odd";
+    stroke_style: {
+        cap: "butt" | "round" | "square";
+        join: "miter" | "round" | "bevel";
+        miter_limit: number;
+        dash?: number[];
+        dash_offset: number;
+    };
+    trim: { start: number; end: number; offset: number };
+}
+
+export interface ColorStop {
+    offset: number;
+    color: Color;
+}
+
+export type GradientKind =
+    | { Linear: { angle: number } }
+    | { Radial: { center: Point2D; radius: number } }
+    | { Conic: { angle: number; center: Point2D } };
+
+/** A flat color, or a gradient laid out over the painted box. */
+export type Paint = Color | { kind: GradientKind; stops: ColorStop[] };
 
 export interface Duration {
     seconds: number;
@@ -58,16 +103,62 @@ export interface Duration {
 
 export type WebCaptureMode = "FrameAccurate" | "Realtime";
 
+export type TextAlign = "left" | "center" | "right";
+
+/** Style overrides for the characters `start..end` (character indices, `end` exclusive). */
+export interface TextSpan {
+    start: number;
+    end: number;
+    bold?: boolean;
+    color?: Color;
+    size?: number;
+    font_family?: string;
+}
+
+/** Layout and decoration of a text layer. Every field is optional. */
+export interface TextStyle {
+    /** Wrap words so no line is wider than this; the layer is then exactly this wide. */
+    max_width?: number;
+    align?: TextAlign;
+    letter_spacing?: number;
+    /** Distance between baselines as a multiple of the font size (default 1.3). */
+    line_height?: number;
+    bold?: boolean;
+    stroke?: { color: Color; width: number };
+    shadow?: { color: Color; offset_x?: number; offset_y?: number; blur?: number };
+    spans?: TextSpan[];
+}
+
+export type TextUnit = "char" | "word" | "line";
+
+/** Which units an animator applies to, as fractions of the text (default 0..1). */
+export interface TextRange {
+    start?: number;
+    end?: number;
+    offset?: number;
+}
+
+/** Animations run on each character, word or line, one unit `stagger` seconds after the last. */
+export interface TextAnimator {
+    unit?: TextUnit;
+    animations: Animation[];
+    stagger?: number;
+    range?: TextRange;
+}
+
 export type LayerContent =
-    | { Text: { text: string; font_family: string; font_size: number; color: Color } }
+    | { Text: { text: string; font_family: string; font_size: number; color: Color; style?: TextStyle; animators?: TextAnimator[] } }
     | { Image: { asset_id: AssetId } }
+    | { Svg: { asset_id: AssetId; element?: string; exclude?: string[]; expand_groups?: boolean } }
+    | { Lottie: { asset_id: AssetId } }
     | { Video: { asset_id: AssetId; trim_start: Duration; trim_end: Duration | null } }
     | { Audio: { asset_id: AssetId; trim_start: Duration; trim_end: Duration | null; volume: number } }
-    | { Shape: { shape: ShapeType; fill: Color | null; stroke: Color | null; stroke_width: number } }
-    | { Solid: { color: Color } }
+    | { Shape: { shape: ShapeType; fill: Paint | null; stroke: Paint | null; stroke_width: number } }
+    | { Solid: { color: Paint } }
     | { TTS: { text: string; voice: string; volume: number } }
     | { AutoCaption: { asset_id: AssetId; font_family: string; font_size: number; color: Color } }
     | { Web: { source: string; viewport_width: number; viewport_height: number; mode: WebCaptureMode; wait_for: string | null; variables: Record<string, number> } }
+    | { Plugin: { name: string; props?: Record<string, string> } }
     | "Empty";
 
 export type AnimatableProperty =
@@ -90,7 +181,37 @@ export type AnimatableProperty =
     | "CropLeft"
     | "Volume"
     | "BlurRadius"
-    | "BrightnessLevel";
+    | "BrightnessLevel"
+    | "MaskX"
+    | "MaskY"
+    | "MaskWidth"
+    | "MaskHeight"
+    | "MaskFeather"
+    | "ShadowX"
+    | "ShadowY"
+    | "ShadowBlur"
+    | "ShadowSpread"
+    | "InnerShadowX"
+    | "InnerShadowY"
+    | "InnerShadowBlur"
+    | "InnerShadowSpread"
+    | "GlowBlur"
+    | "GlowSpread"
+    | "OutlineWidth"
+    | "BevelSize"
+    | "BevelDepth"
+    | "BevelAngle"
+    | "TrimStart"
+    | "TrimEnd"
+    | "TrimOffset"
+    | "TextRangeStart"
+    | "TextRangeEnd"
+    | "TextRangeOffset"
+    | "GradientAngle"
+    | { GradientStop: number }
+    | "Position"
+    | "Scale"
+    | "Color";
 
 export type Easing =
     | "Linear"
@@ -100,23 +221,74 @@ export type Easing =
     | "CubicIn"
     | "CubicOut"
     | "CubicInOut"
-    | "Step";
+    | "EaseInQuart"
+    | "EaseOutQuart"
+    | "EaseInOutQuart"
+    | "EaseInQuint"
+    | "EaseOutQuint"
+    | "EaseInOutQuint"
+    | "EaseInSine"
+    | "EaseOutSine"
+    | "EaseInOutSine"
+    | "EaseInExpo"
+    | "EaseOutExpo"
+    | "EaseInOutExpo"
+    | "EaseInCirc"
+    | "EaseOutCirc"
+    | "EaseInOutCirc"
+    | "EaseInBack"
+    | "EaseOutBack"
+    | "EaseInOutBack"
+    | "EaseInElastic"
+    | "EaseOutElastic"
+    | "EaseInOutElastic"
+    | "EaseInBounce"
+    | "EaseOutBounce"
+    | "EaseInOutBounce"
+    | { CubicBezier: [number, number, number, number] }
+    | { Steps: [number, StepPosition] };
+
+export type StepPosition = "JumpStart" | "JumpEnd" | "JumpNone" | "JumpBoth";
 
 export interface Keyframe {
     time: Duration;
     value: number;
     easing: Easing;
+    hold?: boolean;
+    /** Value of `Position`/`Scale` (`[x, y]`) and `Color` (`[r, g, b, a]`, 0–1) keyframes. */
+    vector?: number[];
+    in_tangent?: [number, number];
+    out_tangent?: [number, number];
+}
+
+export interface Spring {
+    mass: number;
+    stiffness: number;
+    damping: number;
+    initial_velocity: number;
 }
 
 export interface Animation {
     property: AnimatableProperty;
     keyframes: Keyframe[];
+    spring?: Spring;
+    color_space?: ColorSpace;
+    auto_orient?: boolean;
     delay: Duration;
 }
 
+export type ColorSpace = "Oklab" | "LinearRgb" | "Srgb";
+
+export type StrokePosition = "Outside" | "Inside" | "Center";
+
 export type LayerEffect =
     | { Blur: { radius: number } }
-    | { DropShadow: { offset_x: number; offset_y: number; blur: number; color: Color } };
+    | { DropShadow: { offset: Point2D; blur: number; color: Color; spread: number } }
+    | { OuterGlow: { blur: number; color: Color; spread: number } }
+    | { InnerShadow: { offset: Point2D; blur: number; color: Color; spread: number } }
+    | { Stroke: { width: number; color: Color; position: StrokePosition } }
+    | { Bevel: { size: number; depth: number; angle: number; highlight: Color; shadow: Color } }
+    | { Plugin: { name: string; params: Record<string, number> } };
 
 export interface LayerIR {
     id: LayerId;
@@ -129,10 +301,36 @@ export interface LayerIR {
     children: LayerIR[];
 }
 
+export type Direction = "left" | "right" | "up" | "down";
+
+export type TransitionType =
+    | "Crossfade"
+    | { Slide: { direction: Direction } }
+    | { Push: { direction: Direction } }
+    | { Wipe: { direction: Direction } }
+    | { Zoom: { scale: number } }
+    /** `center` is a fraction of the canvas size. */
+    | { Iris: { center: Point2D } }
+    /** `angle` is in degrees clockwise from twelve o'clock. */
+    | { ClockWipe: { angle: number } }
+    | { BlurDissolve: { radius: number } }
+    | { Glitch: { intensity: number } }
+    /** WGSL compiled from a VidraFX `@transition`; `params` in declaration order. */
+    | { Shader: { wgsl_source: string; params?: number[] } }
+    /** A registered transition plugin. */
+    | { Plugin: { name: string; params?: Record<string, number> } };
+
+export interface Transition {
+    effect: TransitionType;
+    duration: Duration;
+    easing: Easing;
+}
+
 export interface SceneIR {
     id: string;
     duration: Duration;
     layers: LayerIR[];
+    transition?: Transition;
 }
 
 export interface ProjectSettings {